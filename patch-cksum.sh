@@ -0,0 +1,21 @@
+#! /bin/sh
+#
+# Embeds the checksum of main1 into the image trailer.
+#
+# The image trailer (16 bytes) is located at the tail of the image
+# (see config/x86_64-unknown-none.ld).  Its checksum field is patched
+# with the POSIX cksum (CRC-32) of main1 excluding the trailer itself.
+# The checksum is verified by man_image::verify() at startup.
+#
+
+BINARY=$1
+
+SIZE=`wc -c < $BINARY`
+LENGTH=`expr $SIZE - 512 - 16`
+CKSUM=`tail -c +513 $BINARY | head -c $LENGTH | cksum | cut -d' ' -f1`
+
+# Write the checksum in little-endian at offset SIZE - 8.
+printf "`printf '\\\\%03o\\\\%03o\\\\%03o\\\\%03o' \
+	$(($CKSUM & 255)) $(($CKSUM >> 8 & 255)) \
+	$(($CKSUM >> 16 & 255)) $(($CKSUM >> 24 & 255))`" |
+	dd of=$BINARY bs=1 seek=`expr $SIZE - 8` conv=notrunc 2>/dev/null
@@ -0,0 +1,47 @@
+#! /bin/sh
+#
+# Embeds the function symbols of the ELF file into the boot image.
+#
+# The symbol table buffer (16KB after the magic "LMBSYMS:") is defined
+# in src/debug/symbols.rs, and it exists only if the feature `symbols`
+# is enabled.  Otherwise, this script does nothing.  Because the
+# buffer is a part of main1, patch-cksum.sh must be called after this
+# script.
+#
+# Usage: patch-symbols.sh BINARY ELF
+#
+
+BINARY=$1
+ELF=$2
+SYMBOLS_SIZE=16384
+NM=${NM:-rust-nm}
+
+MAGIC_OFFSET=`grep -obUa "LMBSYMS:" $BINARY | head -1 | cut -d: -f1`
+if [ -z "$MAGIC_OFFSET" ]; then
+	exit 0		# Not built with the feature `symbols`
+fi
+
+# "<address> <name>" of functions sorted by address (without hashes,
+# leading zeros, and names longer than 60 characters are truncated)
+TABLE=`$NM -n -C --defined-only $ELF |
+	awk '$2 ~ /^[tT]$/ {
+		addr = $1; sub(/^0+/, "", addr);
+		$1 = ""; $2 = ""; name = substr($0, 3);
+		sub(/::h[0-9a-f]+$/, "", name);
+		print addr " " substr(name, 1, 60);
+	}'`
+
+# Truncate the table at a line boundary if it is too large.
+TABLE=`printf '%s\n' "$TABLE" |
+	awk -v max=$SYMBOLS_SIZE '{ n += length($0) + 1; if (n < max) print }'`
+if [ `printf '%s\n' "$TABLE" | wc -c` -ge $SYMBOLS_SIZE ]; then
+	echo "$0: symbol table is too large" >&2
+	exit 1
+fi
+
+# Clear the buffer, then write the table.
+OFFSET=`expr $MAGIC_OFFSET + 8`
+dd if=/dev/zero of=$BINARY bs=1 seek=$OFFSET count=$SYMBOLS_SIZE \
+	conv=notrunc 2>/dev/null
+printf '%s\n' "$TABLE" |
+	dd of=$BINARY bs=1 seek=$OFFSET conv=notrunc 2>/dev/null
@@ -0,0 +1,35 @@
+#! /bin/sh
+
+NAME=`grep name Cargo.toml | head -1 | cut -d= -f2 | sed -e 's/[ "]*//g'`
+
+TARGET="x86_64-unknown-none"
+ELF="target/$TARGET/debug/$NAME"
+BINARY="target/$TARGET/debug/$NAME.bin"
+HOST=`rustc -vV | sed -n 's/^host: //p'`
+ISODIR="target/$TARGET/debug/iso"
+ISOIMAGE="target/$TARGET/debug/$NAME.iso"
+
+# Make the boot image with the command line and the checksum.
+# (The checksum is updated again after the symbol table is embedded.)
+cargo build --bin $NAME $CARGO_FLAGS || exit 1
+cargo run -q --target $HOST --features mkimage --bin mkimage -- \
+	$ELF $BINARY "$CMDLINE" || exit 1
+./patch-symbols.sh $BINARY $ELF && ./patch-cksum.sh $BINARY || exit 1
+
+# The whole image is loaded by BIOS in El Torito no-emulation mode.
+# Hence, the load sector count (in 512-byte sectors) covers the whole image.
+SIZE=`wc -c < $BINARY`
+NSECTORS=`expr \( $SIZE + 511 \) / 512`
+
+mkdir -p $ISODIR
+cp $BINARY $ISODIR/boot.bin
+
+xorriso -as mkisofs \
+	-b boot.bin -no-emul-boot -boot-load-size $NSECTORS \
+	-o $ISOIMAGE $ISODIR
+
+qemu-system-x86_64 \
+	-cdrom $ISOIMAGE \
+	-m 4G \
+	-netdev user,id=net0,tftp=${TFTP_DIR:-tftp} -device virtio-net-pci,netdev=net0 \
+	-monitor stdio
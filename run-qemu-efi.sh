@@ -0,0 +1,22 @@
+#! /bin/sh
+
+NAME=`grep name Cargo.toml | head -1 | cut -d= -f2 | sed -e 's/[ "]*//g'`
+
+TARGET="x86_64-unknown-uefi"
+BINARY="target/$TARGET/debug/${NAME}_efi.efi"
+ESPDIR="target/$TARGET/debug/esp"
+
+# OVMF (UEFI firmware for QEMU) is required.
+OVMF=${OVMF:-/usr/share/OVMF/OVMF_CODE.fd}
+
+cargo build --target $TARGET --features efi --bin ${NAME}_efi
+
+mkdir -p $ESPDIR/EFI/BOOT
+cp $BINARY $ESPDIR/EFI/BOOT/BOOTX64.EFI
+
+qemu-system-x86_64 \
+	-drive if=pflash,format=raw,readonly=on,file=$OVMF \
+	-drive format=raw,file=fat:rw:$ESPDIR \
+	-m 4G \
+	-netdev user,id=net0,tftp=${TFTP_DIR:-tftp} -device virtio-net-pci,netdev=net0 \
+	-monitor stdio
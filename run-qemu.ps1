@@ -1,11 +1,19 @@
 $NAME = "nostd_env"
 $TARGET = "x86_64-unknown-none"
+$ELF = "target/$TARGET/debug/$NAME"
 $BINARY = "target/$TARGET/debug/$NAME.bin"
+$HOST = (rustc -vV | Select-String "^host: ").Line.Substring(6)
 
-cargo objcopy -- -O binary $BINARY
+# Make the boot image with the command line and the checksum.
+cargo build --bin $NAME
+if ($LASTEXITCODE -ne 0) { exit 1 }
+cargo run -q --target $HOST --features mkimage --bin mkimage -- `
+	$ELF $BINARY "$env:CMDLINE"
+if ($LASTEXITCODE -ne 0) { exit 1 }
 
 qemu-system-x86_64 `
 	-drive format=raw,file=$BINARY `
 	-m 4G `
+	-netdev user,id=net0 -device virtio-net-pci,netdev=net0 `
 	-monitor stdio
 #	-d int -no-reboot
@@ -1,14 +1,22 @@
 #! /bin/sh
 
-NAME=`grep name Cargo.toml | cut -d= -f2 | sed -e 's/[ "]*//g'`
+NAME=`grep name Cargo.toml | head -1 | cut -d= -f2 | sed -e 's/[ "]*//g'`
 
 TARGET="x86_64-unknown-none"
+ELF="target/$TARGET/debug/$NAME"
 BINARY="target/$TARGET/debug/$NAME.bin"
+HOST=`rustc -vV | sed -n 's/^host: //p'`
 
-cargo objcopy -- -O binary $BINARY
+# Make the boot image with the command line and the checksum.
+# (The checksum is updated again after the symbol table is embedded.)
+cargo build --bin $NAME $CARGO_FLAGS || exit 1
+cargo run -q --target $HOST --features mkimage --bin mkimage -- \
+	$ELF $BINARY "$CMDLINE" || exit 1
+./patch-symbols.sh $BINARY $ELF && ./patch-cksum.sh $BINARY || exit 1
 
 qemu-system-x86_64 \
 	-drive format=raw,file=$BINARY \
 	-m 4G \
+	-netdev user,id=net0,tftp=${TFTP_DIR:-tftp} -device virtio-net-pci,netdev=net0 \
 	-monitor stdio
 #	-d int -no-reboot
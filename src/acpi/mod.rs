@@ -0,0 +1,80 @@
+/*!
+
+Provides access to ACPI tables.
+
+* `find_rsdp` - scans the EBDA and the BIOS area for the RSDP (Root
+  System Description Pointer), and validates its checksum.
+
+* `Acpi` - iterates the entries of the RSDT (or the XSDT if available)
+  as typed `SdtHeader` references.
+
+```ignore
+let acpi = acpi::Acpi::new()?;
+for table in acpi.tables() {
+    println!("{}", table);
+}
+let madt = acpi.find_table(b"APIC");
+```
+
+Note: ACPI tables must be placed in the memory space mapped by the
+page tables (i.e., the first 4GiB identity-mapped by lmboot0).
+
+# Supplementary Resources
+
+* [ACPI Specification](https://uefi.org/specifications) (UEFI Forum)
+* [RSDP](https://wiki.osdev.org/RSDP) (OSDev Wiki)
+* [RSDT](https://wiki.osdev.org/RSDT) (OSDev Wiki)
+
+ */
+
+
+#[doc(hidden)] pub mod rsdp;
+#[doc(hidden)] pub mod sdt;
+
+#[doc(inline)] pub use self::rsdp::{Rsdp, find_rsdp};
+#[doc(inline)] pub use self::sdt::{Acpi, SdtHeader};
+
+use core::fmt;
+
+use crate::util;
+
+
+/// Errors detected while accessing ACPI tables.
+#[derive(Debug)]
+pub enum AcpiError {
+    /// The RSDP is not found.
+    RsdpNotFound,
+    /// The checksum of a table is invalid.
+    InvalidChecksum { signature: [u8; 4] },
+    /// The signature of a table is unexpected.
+    InvalidSignature { signature: [u8; 4] },
+}
+
+impl fmt::Display for AcpiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::RsdpNotFound =>
+		write!(f, "ACPI RSDP is not found"),
+	    Self::InvalidChecksum { signature } =>
+		write!(f, "ACPI table {} has an invalid checksum",
+		       signature_str(signature)),
+	    Self::InvalidSignature { signature } =>
+		write!(f, "ACPI table {} is unexpected",
+		       signature_str(signature)),
+	}
+    }
+}
+
+
+// Returns true if the sum of all bytes is zero.
+fn is_checksum_valid(bytes: &[u8]) -> bool {
+    util::sum8(bytes) == 0
+}
+
+// Returns a signature as a string (or "????" if not ASCII).
+fn signature_str(signature: &[u8]) -> &str {
+    match core::str::from_utf8(signature) {
+	Ok(s) if signature.is_ascii() => s,
+	_ => "????",
+    }
+}
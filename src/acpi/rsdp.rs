@@ -0,0 +1,100 @@
+//
+// Root System Description Pointer (RSDP)
+//
+// Supplementary Resource:
+//	https://wiki.osdev.org/RSDP
+//
+
+use core::mem::size_of;
+use core::slice;
+
+use super::{AcpiError, is_checksum_valid};
+
+
+/// Root System Description Pointer (ACPI 1.0 and 2.0+)
+#[repr(C, packed)]
+pub struct Rsdp {
+    pub signature: [u8; 8],	//00-07: Signature "RSD PTR "
+    pub checksum: u8,		//08   : Checksum of bytes 00-13
+    pub oem_id: [u8; 6],	//09-0E: OEM ID
+    pub revision: u8,		//0F   : 0 (ACPI 1.0) or 2 (ACPI 2.0+)
+    pub rsdt_address: u32,	//10-13: Physical Address of RSDT
+    // The following fields are available if revision >= 2.
+    pub length: u32,		//14-17: Length of RSDP
+    pub xsdt_address: u64,	//18-1F: Physical Address of XSDT
+    pub extended_checksum: u8,	//20   : Checksum of the entire RSDP
+    pub reserved: [u8; 3],	//21-23: (reserved)
+}
+
+const _: () = assert!(size_of::<Rsdp>() == 0x24);
+
+impl Rsdp {
+    /// Signature "RSD PTR "
+    pub const SIGNATURE: [u8; 8] = *b"RSD PTR ";
+
+    /// The size in bytes of RSDP in ACPI 1.0.
+    pub const V1_SIZE: usize = 0x14;
+
+    ///
+    /// Returns a reference to the RSDP at the address if its
+    /// signature and checksums are valid.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be readable for 0x24 bytes.
+    ///
+    pub unsafe fn at(addr: usize) -> Option<&'static Self> {
+	let bytes = slice::from_raw_parts(addr as *const u8, Self::V1_SIZE);
+	if bytes[.. 8] != Self::SIGNATURE || !is_checksum_valid(bytes) {
+	    return None;
+	}
+
+	let rsdp = &*(addr as *const Self);
+	if rsdp.revision >= 2 {
+	    let length = rsdp.length as usize;
+	    if length < size_of::<Self>() {
+		return None;
+	    }
+	    let bytes = slice::from_raw_parts(addr as *const u8, length);
+	    if !is_checksum_valid(bytes) {
+		return None;
+	    }
+	}
+
+	Some(rsdp)
+    }
+
+    /// Returns the physical address of the XSDT (if available).
+    pub fn xsdt_address(&self) -> Option<u64> {
+	if self.revision >= 2 && self.xsdt_address != 0 {
+	    Some(self.xsdt_address)
+	} else {
+	    None
+	}
+    }
+}
+
+
+///
+/// Finds the RSDP.
+///
+/// It searches for the signature on 16-byte boundaries in the first
+/// 1KiB of the EBDA (Extended BIOS Data Area), then in the BIOS area
+/// (0xE0000 - 0xFFFFF).
+///
+pub fn find_rsdp() -> Result<&'static Rsdp, AcpiError> {
+    // The segment of EBDA is stored at 0x040E in BDA.
+    let ebda = unsafe { (0x040e as *const u16).read_volatile() } as usize;
+    let ebda_start = ebda << 4;
+
+    let mut areas = [(0xe0000, 0x100000), (0, 0)];
+    if (0x80000 .. 0xa0000).contains(&ebda_start) {
+	areas[1] = areas[0];
+	areas[0] = (ebda_start, ebda_start + 1024);
+    }
+
+    areas.iter()
+	.flat_map(|&(start, end)| (start .. end).step_by(16))
+	.find_map(|addr| unsafe { Rsdp::at(addr) })
+	.ok_or(AcpiError::RsdpNotFound)
+}
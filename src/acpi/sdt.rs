@@ -0,0 +1,145 @@
+//
+// System Description Tables (RSDT, XSDT and others)
+//
+// Supplementary Resource:
+//	https://wiki.osdev.org/RSDT
+//	https://wiki.osdev.org/XSDT
+//
+
+use core::fmt;
+use core::mem::size_of;
+use core::ptr::read_unaligned;
+use core::slice;
+
+use super::{AcpiError, Rsdp, find_rsdp, is_checksum_valid, signature_str};
+
+
+/// System Description Table Header (common to all tables but FACS)
+#[repr(C, packed)]
+pub struct SdtHeader {
+    pub signature: [u8; 4],	//00-03: Signature (e.g., "APIC")
+    pub length: u32,		//04-07: Length of the table (with header)
+    pub revision: u8,		//08   : Revision
+    pub checksum: u8,		//09   : Checksum of the entire table
+    pub oem_id: [u8; 6],	//0A-0F: OEM ID
+    pub oem_table_id: [u8; 8],	//10-17: OEM Table ID
+    pub oem_revision: u32,	//18-1B: OEM Revision
+    pub creator_id: u32,	//1C-1F: Creator ID
+    pub creator_revision: u32,	//20-23: Creator Revision
+}
+
+const _: () = assert!(size_of::<SdtHeader>() == 0x24);
+
+impl SdtHeader {
+    ///
+    /// Returns a reference to the table at the address if its
+    /// checksum is valid.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must point to a table mapped in memory.
+    ///
+    pub unsafe fn at(addr: usize) -> Result<&'static Self, AcpiError> {
+	let header = &*(addr as *const Self);
+	if header.length as usize >= size_of::<Self>() &&
+	    is_checksum_valid(header.bytes()) {
+	    Ok(header)
+	} else {
+	    Err(AcpiError::InvalidChecksum { signature: header.signature })
+	}
+    }
+
+    /// Returns the signature as a string (e.g., "APIC").
+    pub fn signature_str(&self) -> &str {
+	signature_str(&self.signature)
+    }
+
+    /// Returns the entire table (including the header) as bytes.
+    pub fn bytes(&self) -> &[u8] {
+	unsafe {
+	    slice::from_raw_parts(self as *const Self as *const u8,
+				  self.length as usize)
+	}
+    }
+
+    /// Returns the table following the header as bytes.
+    pub fn data(&self) -> &[u8] {
+	&self.bytes()[size_of::<Self>() ..]
+    }
+}
+
+impl fmt::Display for SdtHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	let length = self.length;
+	write!(f, "{} at {:#x}: length={:#x}, revision={}, oem={}",
+	       self.signature_str(), self as *const Self as usize,
+	       length, self.revision, signature_str(&self.oem_id))
+    }
+}
+
+
+///
+/// Provides access to ACPI tables via the RSDT or the XSDT.
+///
+/// The XSDT is used if available (ACPI 2.0+).
+///
+#[derive(Clone, Copy)]
+pub struct Acpi {
+    pub rsdp: &'static Rsdp,
+    pub root: &'static SdtHeader,	// RSDT or XSDT
+    entry_size: usize,			// 4 (RSDT) or 8 (XSDT)
+}
+
+impl Acpi {
+    /// Finds the RSDP, then validates the RSDT (or the XSDT).
+    pub fn new() -> Result<Self, AcpiError> {
+	Self::from_rsdp(find_rsdp()?)
+    }
+
+    ///
+    /// Validates the RSDT (or the XSDT) pointed by the RSDP.
+    ///
+    /// It is useful if the RSDP is found in another way (e.g., in the
+    /// UEFI configuration table).
+    ///
+    pub fn from_rsdp(rsdp: &'static Rsdp) -> Result<Self, AcpiError> {
+	let (addr, signature, entry_size) = match rsdp.xsdt_address() {
+	    Some(addr) => (addr as usize, *b"XSDT", 8),
+	    None => (rsdp.rsdt_address as usize, *b"RSDT", 4),
+	};
+
+	let root = unsafe { SdtHeader::at(addr)? };
+	if root.signature != signature {
+	    return Err(AcpiError::InvalidSignature {
+		signature: root.signature,
+	    });
+	}
+
+	Ok(Self { rsdp, root, entry_size })
+    }
+
+    ///
+    /// Returns an iterator over the tables listed in the RSDT (or the
+    /// XSDT).  Tables with invalid checksums are skipped.
+    ///
+    pub fn tables(&self) -> impl Iterator<Item = &'static SdtHeader> {
+	let entries = self.root.data();
+	let entry_size = self.entry_size;
+
+	entries.chunks_exact(entry_size)
+	    .map(move |entry| unsafe {
+		if entry_size == 8 {
+		    read_unaligned(entry.as_ptr() as *const u64) as usize
+		} else {
+		    read_unaligned(entry.as_ptr() as *const u32) as usize
+		}
+	    })
+	    .filter_map(|addr| unsafe { SdtHeader::at(addr) }.ok())
+    }
+
+    /// Returns the first table with the signature (e.g., b"APIC").
+    pub fn find_table(&self, signature: &[u8; 4])
+		      -> Option<&'static SdtHeader> {
+	self.tables().find(|table| &table.signature == signature)
+    }
+}
@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+//
+// UEFI entry point (Build with feature `efi`).
+//
+// % cargo build --target x86_64-unknown-uefi --features efi --bin nostd_env_efi
+//
+
+use core::panic::PanicInfo;
+
+// See src/lib.rs
+use nostd_env::{
+    console,
+    efi,
+    man_heap,
+    power,
+    println,
+    testing::ExitCode,
+    x86,
+};
+#[cfg(feature = "tests")]
+use nostd_env::{man_heap::GLOBAL_ALLOC, test_alloc};
+
+
+// Panic handler (cf. https://doc.rust-lang.org/nomicon/panic-handler.html )
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    println!("{}", info);
+    println!("{}", x86::Registers::capture());
+
+    power::PanicPolicy::from_cmdline().apply();
+}
+
+
+// Entry point of the UEFI application.
+#[no_mangle]
+pub extern "efiapi" fn efi_main(image_handle: efi::Handle,
+				system_table: *mut efi::SystemTable)
+				-> efi::Status {
+    // Bring up the early console (no allocation is required).
+    // Note: The text output of BIOS is not available.
+    console::early_init();
+
+    // Obtain the memory map and the frame buffer, then exit boot services.
+    let boot_info =
+	match unsafe { efi::boot(image_handle, system_table) } {
+	    Ok(boot_info) => boot_info,
+	    Err(err) => {
+		println!("Failed to exit boot services: {:?}", err);
+		power::halt();
+	    },
+	};
+
+    // Initialize the global allocator (size = 1MB)
+    if !man_heap::init_global_alloc_in(1024 * 1024,
+				       boot_info.memory_map()) {
+	panic!("Failed to initialize the global allocator");
+    }
+
+    // Draw the screen on the frame buffer obtained from GOP.
+    match boot_info.frame_buffer {
+	Some(fb) => {
+	    #[cfg(feature = "video")]
+	    if !fb.to_frame_buffer().is_some_and(console::init_fb_screen) {
+		println!("Failed to draw on the frame buffer");
+	    }
+	    println!("Frame buffer: {}", fb);
+	},
+	None => println!("Frame buffer: not available"),
+    }
+
+    // Test: allocator and heap manager
+    #[cfg(feature = "tests")]
+    test_alloc::try_sieve(30, 100, 10000, &GLOBAL_ALLOC);
+
+    // Exit QEMU (if isa-debug-exit is available), or take the action
+    // given by `on_exit=` (halt by default).
+    power::finish(ExitCode::Success);
+}
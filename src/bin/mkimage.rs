@@ -0,0 +1,297 @@
+//
+// mkimage - Makes the boot image from the ELF file (Build with feature
+// `mkimage` for the host).
+//
+// % cargo run --target x86_64-unknown-linux-gnu --features mkimage \
+//	--bin mkimage -- ELF IMAGE [CMDLINE]
+//
+// The boot image consists of lmboot0 (`.boot0`, the MBR at LBA 0) and
+// main1 (`.main1`, from LBA 1) as laid out by the linker script
+// ( config/x86_64-unknown-none.ld ).  mkimage copies them from the ELF
+// file, then
+//	1. checks that main1 follows lmboot0 and fits the load area
+//	   (below the 32-bit heap, `__lmb_heap32_start`),
+//	2. embeds the command line (cf. src/cmdline.rs),
+//	3. embeds the checksum into the image trailer (cf. src/man_image.rs),
+//	4. prints the sizes of the sections and of code and data.
+// The number of sectors loaded by lmboot0 is computed from the linker
+// symbols at link time.  Hence, it is only checked here.
+//
+// Because the symbol table (feature `symbols`) is embedded by
+// patch-symbols.sh afterwards, patch-cksum.sh must be called again
+// in that case.
+//
+
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+// The checksum is shared with the runtime (without linking it).
+#[allow(dead_code)]
+#[path = "../util/checksum.rs"]
+mod checksum;
+
+// So are the readers of little-endian integers.
+#[path = "../util/bytes.rs"]
+mod bytes;
+use bytes::{le16, le32, le64};
+
+
+// Sector Size
+const SECTOR_SIZE: usize = 512;
+
+// The command line buffer (cf. src/cmdline.rs)
+const CMDLINE_MAGIC: &[u8] = b"LMBCMDL:";
+const CMDLINE_SIZE: usize = 248;
+
+// The image trailer (cf. src/man_image.rs)
+const TRAILER_SIZE: usize = 16;
+const TRAILER_MAGIC: u32 = 0x54424d4c;	// "LMBT"
+
+// ELF
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_NOBITS: u32 = 8;
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 || args.len() > 4 {
+	eprintln!("Usage: mkimage ELF IMAGE [CMDLINE]");
+	return ExitCode::FAILURE;
+    }
+    let cmdline = args.get(3).map_or("", String::as_str);
+
+    match make_image(&args[1], &args[2], cmdline) {
+	Ok(()) => ExitCode::SUCCESS,
+	Err(msg) => {
+	    eprintln!("mkimage: {}", msg);
+	    ExitCode::FAILURE
+	},
+    }
+}
+
+fn make_image(elf_path: &str, image_path: &str, cmdline: &str)
+	      -> Result<(), String> {
+    let bytes = fs::read(elf_path)
+	.map_err(|err| format!("{}: {}", elf_path, err))?;
+    let elf = Elf::parse(&bytes)?;
+
+    let boot0 = elf.section(".boot0")?;
+    let main1 = elf.section(".main1")?;
+    let heap32_start = elf.symbol("__lmb_heap32_start")?;
+
+    // Check the layout.
+    if boot0.size != SECTOR_SIZE as u64 {
+	return Err(format!(".boot0 is not a sector ({} bytes)", boot0.size));
+    }
+    if main1.addr != boot0.addr + SECTOR_SIZE as u64 {
+	return Err(format!(".main1 ({:#x}) does not follow .boot0 ({:#x})",
+			   main1.addr, boot0.addr));
+    }
+    if main1.size == 0 || main1.size % SECTOR_SIZE as u64 != 0 {
+	return Err(format!(".main1 is not a multiple of sectors \
+			    ({} bytes)", main1.size));
+    }
+    if main1.addr + main1.size > heap32_start {
+	return Err(format!(".main1 ({:#x} - {:#x}) does not fit the load \
+			    area (below {:#x})", main1.addr,
+			   main1.addr + main1.size, heap32_start));
+    }
+
+    // Assemble lmboot0 and main1.
+    let mut image = elf.contents(&boot0)?.to_vec();
+    if image[SECTOR_SIZE - 2 ..] != [0x55, 0xaa] {
+	return Err("lmboot0 has no boot signature".to_string());
+    }
+    image.extend_from_slice(elf.contents(&main1)?);
+
+    embed_cmdline(&mut image, cmdline)?;
+    embed_checksum(&mut image)?;
+
+    fs::write(image_path, &image)
+	.map_err(|err| format!("{}: {}", image_path, err))?;
+
+    print_report(&elf, &main1, heap32_start);
+    println!("{}: {} bytes ({} sectors)", image_path, image.len(),
+	     image.len() / SECTOR_SIZE);
+    Ok(())
+}
+
+// Embeds the command line (cf. src/cmdline.rs).
+fn embed_cmdline(image: &mut [u8], cmdline: &str) -> Result<(), String> {
+    if cmdline.len() >= CMDLINE_SIZE {
+	return Err(format!("Command line is too long (>= {} bytes)",
+			   CMDLINE_SIZE));
+    }
+    let offset = image.windows(CMDLINE_MAGIC.len())
+	.position(|window| window == CMDLINE_MAGIC)
+	.ok_or("Command line buffer is not found")? + CMDLINE_MAGIC.len();
+
+    let buf = &mut image[offset .. offset + CMDLINE_SIZE];
+    buf.fill(0);
+    buf[.. cmdline.len()].copy_from_slice(cmdline.as_bytes());
+    Ok(())
+}
+
+// Embeds the checksum of main1 into the image trailer (cf. patch-cksum.sh).
+fn embed_checksum(image: &mut [u8]) -> Result<(), String> {
+    let len = image.len();
+    let trailer = &image[len - TRAILER_SIZE ..];
+    let magic = u32::from_le_bytes(trailer[0 .. 4].try_into().unwrap());
+    let size = u32::from_le_bytes(trailer[4 .. 8].try_into().unwrap());
+    if magic != TRAILER_MAGIC || size as usize != len - SECTOR_SIZE {
+	return Err("Image trailer is not found".to_string());
+    }
+
+    let cksum = checksum::cksum(&image[SECTOR_SIZE .. len - TRAILER_SIZE]);
+    image[len - 8 .. len - 4].copy_from_slice(&cksum.to_le_bytes());
+    Ok(())
+}
+
+// Prints the sizes of the sections, and those of code and data in main1.
+fn print_report(elf: &Elf, main1: &Section, heap32_start: u64) {
+    println!("{:<12} {:>10} {:>10}", "Section", "Address", "Size");
+    for section in elf.sections.iter().filter(|s| s.addr != 0) {
+	println!("{:<12} {:>#10x} {:>10}",
+		 elf.name(section.name), section.addr, section.size);
+    }
+
+    let (mut code, mut data) = (0, 0);
+    for (info, value, size) in elf.symbols() {
+	if value >= main1.addr && value < main1.addr + main1.size {
+	    match info & 0x0f {
+		STT_FUNC => code += size,
+		STT_OBJECT => data += size,
+		_ => (),
+	    }
+	}
+    }
+    let area = heap32_start - main1.addr;
+    println!("main1: code {} bytes, data {} bytes, \
+	      {} of {} bytes of the load area ({}%)",
+	     code, data, main1.size, area, main1.size * 100 / area);
+}
+
+
+// A section header (of the fields used)
+#[derive(Clone, Copy)]
+struct Section {
+    name: u32,		// Offset in .shstrtab
+    stype: u32,
+    addr: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+}
+
+// An ELF64 file (little-endian)
+struct Elf<'a> {
+    bytes: &'a [u8],
+    sections: Vec<Section>,
+    shstrtab: Section,
+}
+
+impl<'a> Elf<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, String> {
+	if bytes.len() < 64 || &bytes[0 .. 4] != ELF_MAGIC
+	    || bytes[4] != ELFCLASS64 || bytes[5] != ELFDATA2LSB
+	{
+	    return Err("Not an ELF64 file (little-endian)".to_string());
+	}
+
+	let shoff = le64(bytes, 0x28) as usize;
+	let shentsize = le16(bytes, 0x3a) as usize;
+	let shnum = le16(bytes, 0x3c) as usize;
+	let shstrndx = le16(bytes, 0x3e) as usize;
+	if shentsize < 64 || shstrndx >= shnum
+	    || shoff + shnum * shentsize > bytes.len()
+	{
+	    return Err("Invalid section headers".to_string());
+	}
+
+	let sections: Vec<Section> = (0 .. shnum).map(|i| {
+	    let sh = &bytes[shoff + i * shentsize ..];
+	    Section {
+		name: le32(sh, 0x00),
+		stype: le32(sh, 0x04),
+		addr: le64(sh, 0x10),
+		offset: le64(sh, 0x18),
+		size: le64(sh, 0x20),
+		link: le32(sh, 0x28),
+	    }
+	}).collect();
+	let shstrtab = sections[shstrndx];
+
+	let elf = Self { bytes, sections, shstrtab };
+	elf.contents(&shstrtab)?;
+	Ok(elf)
+    }
+
+    // Returns the section of the name.
+    fn section(&self, name: &str) -> Result<Section, String> {
+	self.sections.iter().copied()
+	    .find(|s| self.name(s.name) == name)
+	    .ok_or(format!("Section {} is not found", name))
+    }
+
+    // Returns the contents of the section.
+    fn contents(&self, section: &Section) -> Result<&'a [u8], String> {
+	if section.stype == SHT_NOBITS {
+	    return Err("Section has no contents".to_string());
+	}
+	let start = section.offset as usize;
+	let end = start + section.size as usize;
+	self.bytes.get(start .. end).ok_or("Section is truncated".to_string())
+    }
+
+    // Returns the name at the offset in .shstrtab.
+    fn name(&self, offset: u32) -> &'a str {
+	let strtab = self.contents(&self.shstrtab).unwrap_or(&[]);
+	string_at(strtab, offset as usize)
+    }
+
+    // Returns the value of the symbol.
+    fn symbol(&self, name: &str) -> Result<u64, String> {
+	self.named_symbols().find(|(sym_name, _)| *sym_name == name)
+	    .map(|(_, value)| value)
+	    .ok_or(format!("Symbol {} is not found", name))
+    }
+
+    // Returns an iterator over (name, value) of the symbols.
+    fn named_symbols(&self) -> impl Iterator<Item = (&'a str, u64)> + '_ {
+	self.symbol_entries().map(|(strtab, sym)| {
+	    (string_at(strtab, le32(sym, 0x00) as usize), le64(sym, 0x08))
+	})
+    }
+
+    // Returns an iterator over (info, value, size) of the symbols.
+    fn symbols(&self) -> impl Iterator<Item = (u8, u64, u64)> + '_ {
+	self.symbol_entries().map(|(_, sym)| {
+	    (sym[0x04], le64(sym, 0x08), le64(sym, 0x10))
+	})
+    }
+
+    // Returns an iterator over (strtab, entry) of the symbol tables.
+    fn symbol_entries(&self)
+		      -> impl Iterator<Item = (&'a [u8], &'a [u8])> + '_ {
+	self.sections.iter().filter(|s| s.stype == SHT_SYMTAB)
+	    .flat_map(move |symtab| {
+		let strtab = self.sections.get(symtab.link as usize)
+		    .and_then(|s| self.contents(s).ok()).unwrap_or(&[]);
+		let entries = self.contents(symtab).unwrap_or(&[]);
+		entries.chunks_exact(24).map(move |sym| (strtab, sym))
+	    })
+    }
+}
+
+// Returns the NUL-terminated string at the offset.
+fn string_at(strtab: &[u8], offset: usize) -> &str {
+    let bytes = strtab.get(offset ..).unwrap_or(&[]);
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[.. len]).unwrap_or("")
+}
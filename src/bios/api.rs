@@ -1,4 +1,19 @@
+#[cfg(feature = "disk")]
+use core::alloc::Allocator;
+
 use super::ffi;
+#[cfg(feature = "disk")]
+use super::int13h48h::{self, DevicePath};
+
+
+/// The lowest drive ID assigned to CD-ROM drives (El Torito).
+pub const CDROM_DRIVE_ID_MIN: u8 = 0xE0;
+
+/// Sector Size of Hard Disk Drives and Floppy Disk Drives = 512
+pub const SECTOR_SIZE: usize = 512;
+
+/// Sector Size of CD-ROM Drives = 2048
+pub const CDROM_SECTOR_SIZE: usize = 2048;
 
 
 /// Gets the boot drive id.
@@ -7,3 +22,31 @@ pub fn get_boot_drive_id() -> u8 {
 	ffi::lmbios_get_boot_drive_id()
     }
 }
+
+/// Returns true if the drive is a CD-ROM drive.
+///
+/// Note: BIOSes assign drive IDs 0xE0 - 0xFF to CD-ROM drives
+/// booted in El Torito no-emulation mode.  To make sure, call
+/// [`int13h4b01h::call`](super::int13h4b01h::call).
+pub fn is_cdrom_drive(drive_id: u8) -> bool {
+    drive_id >= CDROM_DRIVE_ID_MIN
+}
+
+/// Gets the sector size in bytes of the drive.
+pub fn get_sector_size(drive_id: u8) -> usize {
+    if is_cdrom_drive(drive_id) {
+	CDROM_SECTOR_SIZE
+    } else {
+	SECTOR_SIZE
+    }
+}
+
+/// Returns the device path (EDD 3.0) of the boot drive, which tells
+/// the PCI function (and the interface) owning the boot drive.
+#[cfg(feature = "disk")]
+pub fn boot_device_path<A20>(alloc20: A20) -> Option<DevicePath>
+where
+    A20: Allocator,
+{
+    int13h48h::call(get_boot_drive_id(), alloc20)?.device_path()
+}
@@ -5,7 +5,10 @@
 	.section .text.debug_helper, "xa" # xa = executable, allocatable
 	.globl debug_clear_stack_area
 
-	.set	STACK_START, __lmb_stack_start
+	# The lowest 64 bytes of the stack area hold a canary pattern
+	# (cf. STACK_CANARY_SIZE in src/bios/stack_usage.rs).
+	.set	STACK_CANARY_SIZE, 64
+	.set	STACK_START, __lmb_stack_start + STACK_CANARY_SIZE
 
 
 #########################################################################
@@ -11,6 +11,12 @@
 #   (3) The A20 line can be enabled via I/O Port 92h Bit 1, and
 #   (4) BIOS supports INT 13h AH=42h (Extended Read Sectors From Drive).
 #
+# When booted from a CD in El Torito no-emulation mode, lmboot0 also
+# assumes that:
+#   (4') The whole image (lmboot0 + main1) has already been loaded at
+#        0x7C00 by BIOS.  That is, the "load sector count" in the boot
+#        catalog covers the whole image (see run-qemu-cd.sh).
+#
 # lmboot0 also assumes that:
 #   (5) All configuration will be re-done by loaded program, and
 #   (6) All messages should be printed by loaded program.
@@ -77,6 +83,7 @@
 	.set	MAIN1_START, __lmb_main1_start
 	.set	MAIN1_END, __lmb_main1_end
 	.set	LBA_START, 1
+	.set	CDROM_DRIVE_MIN, 0xe0	# The lowest drive ID of CD-ROM drives
 
 
 #########################################################################
@@ -94,7 +101,7 @@ __lmboot0_entry:
 	# Room for disk parameters.
 	#   e.g. BIOS Parameter Block of FAT12 or FAT16 (0x3E bytes)
 
-.org 0x047
+.org 0x03e
 
 lmboot0_start:
 
@@ -122,6 +129,16 @@ lmboot0_rm16:
 	#
 	movb	%dl, __lmboot0_boot_drive_id
 
+	########################################################
+	#
+	# Skip loading if booted from a CD (El Torito no-emulation mode).
+	#
+	# Note: In no-emulation mode, BIOS has already loaded the whole
+	#       image, and the sector size of the boot drive is 2048.
+	#
+	cmpb	$CDROM_DRIVE_MIN, %dl
+	jae	lmboot0_loaded
+
 	########################################################
 	#
 	# Load main program from the boot drive.
@@ -157,6 +174,7 @@ lmboot0_rm16:
 	#
 	call	lmboot0_load_blocks
 	jc	lmboot0_io_error
+lmboot0_loaded:
 
 	########################################################
 	#
@@ -676,6 +694,12 @@ lmboot0_gdt_end:
 #	https://en.wikipedia.org/wiki/INT_13H#INT_13h_AH=42h:_Extended_Read_Sectors_From_Drive
 #
 
+#
+# Supplementary Resources for El Torito (Bootable CD-ROM Format)
+#	https://wiki.osdev.org/El-Torito
+#	https://en.wikipedia.org/wiki/El_Torito_(CD-ROM_standard)
+#
+
 #
 # Supplementary Resources for Master Boot Record (MBR)
 #	https://en.wikipedia.org/wiki/Master_boot_record
@@ -0,0 +1,191 @@
+//
+// BiosCall - Typed inputs and outputs of BIOS functions over LmbiosRegs.
+//
+// Each supported BIOS function defines a request type implementing
+// `BiosCall` (e.g., `int10h0eh::TeletypeOut`).  Its inputs are loaded
+// into the registers by `CallRegs`, whose setters are named after the
+// registers (e.g., `bh` and `bl`).  Hence, a register can be checked
+// against the specification at a glance.
+//
+// `LmbiosRegs` remains available as an escape hatch.
+//
+
+use super::LmbiosRegs;
+use crate::x86::{FLAGS_CF, FLAGS_ZF};
+
+
+// Returned by lmbios_call if BIOS functions are not available (UEFI).
+// It is also the status of calls locked out (cf. `lockout`).
+const LMBIOS_UNSUPPORTED: u16 = 0xffff;
+
+
+///
+/// A request to call a BIOS function with typed inputs and outputs.
+///
+/// # Safety
+///
+/// The registers built by `regs` must be safe to pass to the BIOS
+/// function (e.g., a buffer address must be valid and below 1MB).
+///
+pub unsafe trait BiosCall {
+    /// The output of the BIOS function.
+    type Output;
+
+    /// Builds the input registers.
+    fn regs(&self) -> CallRegs;
+
+    /// Extracts the output from the result.
+    fn output(&self, result: &CallResult) -> Self::Output;
+}
+
+/// Calls the BIOS function of the request.
+pub fn call<C>(request: &C) -> C::Output
+where
+    C: BiosCall
+{
+    let result = unsafe { request.regs().call() };
+    request.output(&result)
+}
+
+
+/// A builder of the input registers of a BIOS function.
+pub struct CallRegs {
+    regs: LmbiosRegs,
+}
+
+// Defines setters of a 16-bit register and its 8-bit halves.
+macro_rules! reg_setters {
+    ($e:ident, $x:ident, $h:ident, $l:ident) => {
+	#[doc = concat!("Sets ", stringify!($x), ".")]
+	pub fn $x(mut self, value: u16) -> Self {
+	    self.regs.$e = (self.regs.$e & !0xffff) | value as u32;
+	    self
+	}
+
+	#[doc = concat!("Sets ", stringify!($h), ".")]
+	pub fn $h(mut self, value: u8) -> Self {
+	    self.regs.$e = (self.regs.$e & !0xff00) | (value as u32) << 8;
+	    self
+	}
+
+	#[doc = concat!("Sets ", stringify!($l), ".")]
+	pub fn $l(mut self, value: u8) -> Self {
+	    self.regs.$e = (self.regs.$e & !0x00ff) | value as u32;
+	    self
+	}
+    };
+}
+
+impl CallRegs {
+    /// Starts building the registers of `INT vector`.
+    pub fn int(vector: u8) -> Self {
+	Self {
+	    regs: LmbiosRegs {
+		fun: vector as u16,
+		..Default::default()
+	    },
+	}
+    }
+
+    reg_setters!(eax, ax, ah, al);
+    reg_setters!(ebx, bx, bh, bl);
+    reg_setters!(ecx, cx, ch, cl);
+    reg_setters!(edx, dx, dh, dl);
+
+    /// Sets SI.
+    pub fn si(mut self, value: u16) -> Self {
+	self.regs.esi = value as u32;
+	self
+    }
+
+    /// Sets DI.
+    pub fn di(mut self, value: u16) -> Self {
+	self.regs.edi = value as u32;
+	self
+    }
+
+    /// Sets BP.
+    pub fn bp(mut self, value: u16) -> Self {
+	self.regs.ebp = value as u32;
+	self
+    }
+
+    /// Sets DS.
+    pub fn ds(mut self, value: u16) -> Self {
+	self.regs.ds = value;
+	self
+    }
+
+    /// Sets ES.
+    pub fn es(mut self, value: u16) -> Self {
+	self.regs.es = value;
+	self
+    }
+
+    /// Returns the raw registers.
+    pub fn into_raw(self) -> LmbiosRegs {
+	self.regs
+    }
+
+    ///
+    /// Calls the BIOS function with the registers.
+    ///
+    /// # Safety
+    ///
+    /// The registers must be safe to pass to the BIOS function.
+    ///
+    pub unsafe fn call(self) -> CallResult {
+	let mut regs = self.regs;
+	let status = unsafe { regs.call() }.unwrap_or(LMBIOS_UNSUPPORTED);
+	CallResult { regs, status }
+    }
+}
+
+
+/// The output registers of a BIOS function.
+pub struct CallResult {
+    regs: LmbiosRegs,
+    status: u16,
+}
+
+impl CallResult {
+    /// Returns false if BIOS functions are not available (UEFI or
+    /// locked out).
+    pub fn is_supported(&self) -> bool {
+	self.status != LMBIOS_UNSUPPORTED
+    }
+
+    /// Returns true if the carry flag (CF) is set (usually on error).
+    /// It is also true if BIOS functions are not available.
+    pub fn carry(&self) -> bool {
+	!self.is_supported() || (self.regs.flags & FLAGS_CF) != 0
+    }
+
+    /// Returns true if the zero flag (ZF) is set.
+    pub fn zero(&self) -> bool {
+	(self.regs.flags & FLAGS_ZF) != 0
+    }
+
+    // Getters of the output registers
+    pub fn ax(&self) -> u16 { self.regs.eax as u16 }
+    pub fn ah(&self) -> u8 { (self.regs.eax >> 8) as u8 }
+    pub fn al(&self) -> u8 { self.regs.eax as u8 }
+    pub fn bx(&self) -> u16 { self.regs.ebx as u16 }
+    pub fn bh(&self) -> u8 { (self.regs.ebx >> 8) as u8 }
+    pub fn bl(&self) -> u8 { self.regs.ebx as u8 }
+    pub fn cx(&self) -> u16 { self.regs.ecx as u16 }
+    pub fn ch(&self) -> u8 { (self.regs.ecx >> 8) as u8 }
+    pub fn cl(&self) -> u8 { self.regs.ecx as u8 }
+    pub fn dx(&self) -> u16 { self.regs.edx as u16 }
+    pub fn dh(&self) -> u8 { (self.regs.edx >> 8) as u8 }
+    pub fn dl(&self) -> u8 { self.regs.edx as u8 }
+    pub fn si(&self) -> u16 { self.regs.esi as u16 }
+    pub fn di(&self) -> u16 { self.regs.edi as u16 }
+    pub fn bp(&self) -> u16 { self.regs.ebp as u16 }
+    pub fn es(&self) -> u16 { self.regs.es }
+
+    /// Returns the raw registers.
+    pub fn raw(&self) -> &LmbiosRegs {
+	&self.regs
+    }
+}
@@ -0,0 +1,373 @@
+/*!
+
+Enumerates drives known to BIOS.
+
+`enumerate` probes floppy drives 00h-03h (INT 13h AH=08h) and hard
+drives 80h-8Fh (INT 13h AH=41h and AH=48h), then returns the drives
+present with their sizes.  Hence, drives other than the boot drive
+can be used in multi-disk QEMU setups.
+
+```ignore
+let disks = bios::disk::enumerate(&ALLOC_UNDER20);
+let data = bios::int13h42h::call(disks[1].drive_id, 0, 1, &ALLOC_UNDER20);
+```
+
+Transfers by INT 13h (`int13h02h`, `int13h03h`, `int13h42h` and
+`int13h43h`) are retried up to `MAX_ATTEMPTS` times, and counted per
+drive: sectors transferred, retries and failures by the status (AH).
+[`stats`] returns the counters, e.g. to debug flaky transfers split
+into chunks of 127 sectors.
+
+```ignore
+for stats in bios::disk::stats() {
+    println!("{}", stats);
+}
+```
+
+[`read_async`] reads sectors in an `async` task (cf. [`task`](crate::task)).
+BIOS cannot complete a transfer in the background.  Hence, the future
+yields once to let other tasks run, then reads the sectors blocking
+(INT 13h AH=42h).  Native backends (e.g. AHCI or virtio-blk) completing
+by interrupts are not implemented yet.
+
+```ignore
+executor.spawn(async {
+    match bios::disk::read_async(drive_id, lba, 8, &ALLOC_UNDER20).await {
+        Ok(data) => println!("{} bytes", data.len()),
+        Err(err) => println!("{}", err),
+    }
+});
+```
+
+ */
+
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use super::{SECTOR_SIZE, int13h08h, int13h41h, int13h42h, int13h48h};
+use crate::mu::MuMutex;
+
+
+/// The range of floppy drive IDs probed by `enumerate`.
+pub const FLOPPY_DRIVE_IDS: core::ops::Range<u8> = 0x00 .. 0x04;
+
+/// The range of hard drive IDs probed by `enumerate`.
+pub const HARD_DRIVE_IDS: core::ops::Range<u8> = 0x80 .. 0x90;
+
+/// The maximum number of attempts of a transfer (1 + retries).
+pub const MAX_ATTEMPTS: usize = 3;
+
+/// The maximum number of drives whose statistics are kept.
+pub const MAX_STATS_DRIVES: usize = 8;
+
+/// The maximum number of distinct statuses counted per drive.
+pub const MAX_STATUSES: usize = 4;
+
+
+/// Kinds of drives.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiskKind {
+    Floppy,
+    HardDisk,
+}
+
+/// A drive present.
+#[derive(Clone, Copy, Debug)]
+pub struct DiskInfo {
+    pub drive_id: u8,
+    pub kind: DiskKind,
+    pub sectors: u64,		// Total Number of Sectors
+    pub sector_size: usize,	// Bytes per Sector
+    pub edd_version: Option<u8>,	// Version of INT 13h Extensions
+}
+
+impl DiskInfo {
+    /// Returns the size in bytes.
+    pub fn size(&self) -> u64 {
+	self.sectors * self.sector_size as u64
+    }
+}
+
+impl fmt::Display for DiskInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	let kind = match self.kind {
+	    DiskKind::Floppy => "floppy",
+	    DiskKind::HardDisk => "hard disk",
+	};
+	write!(f, "drive {:#04x}: {}, {} sectors x {} bytes ({} KiB)",
+	       self.drive_id, kind, self.sectors, self.sector_size,
+	       self.size() / 1024)?;
+	if let Some(version) = self.edd_version {
+	    write!(f, ", EDD {}.{}", version >> 4, version & 0x0f)?;
+	}
+	Ok(())
+    }
+}
+
+
+/// Probes floppy drives 00h-03h and hard drives 80h-8Fh, then returns
+/// the drives present.  Buffers are allocated by `alloc20`.
+pub fn enumerate<A20>(alloc20: A20) -> Vec<DiskInfo>
+where
+    A20: Copy + Allocator,
+{
+    let mut disks = Vec::new();
+
+    for drive_id in FLOPPY_DRIVE_IDS {
+	if let Some(disk) = probe_chs(drive_id, DiskKind::Floppy) {
+	    disks.push(disk);
+	}
+    }
+
+    for drive_id in HARD_DRIVE_IDS {
+	if let Some(disk) = probe(drive_id, alloc20) {
+	    disks.push(disk);
+	}
+    }
+
+    disks
+}
+
+/// Probes a drive, then returns it if present.
+pub fn probe<A20>(drive_id: u8, alloc20: A20) -> Option<DiskInfo>
+where
+    A20: Allocator,
+{
+    let kind = if drive_id < HARD_DRIVE_IDS.start {
+	DiskKind::Floppy
+    } else {
+	DiskKind::HardDisk
+    };
+
+    // Prefer the size reported by the extensions (AH=41h and AH=48h).
+    if let Some(ext) = int13h41h::call(drive_id) {
+	if (ext.support & int13h41h::Extensions::DISK_ACCESS) != 0 {
+	    if let Some(params) = int13h48h::query(drive_id, alloc20) {
+		return Some(DiskInfo {
+		    drive_id,
+		    kind,
+		    sectors: params.total_sectors,
+		    sector_size: params.bytes_per_sector as usize,
+		    edd_version: Some(ext.version),
+		});
+	    }
+	}
+    }
+
+    probe_chs(drive_id, kind)
+}
+
+// Probes a drive by its CHS geometry (AH=08h).
+fn probe_chs(drive_id: u8, kind: DiskKind) -> Option<DiskInfo> {
+    let params = int13h08h::call(drive_id)?;
+
+    // Note: AH=08h reports the number of drives of the kind.  Hence,
+    //       a drive ID beyond it does not exist (although some BIOSes
+    //       report the parameters of the last drive).
+    let index = drive_id & 0x7f;
+    if index >= params.ndrives {
+	return None;
+    }
+
+    let cylinders = params.max_cylinder as u64 + 1;
+    Some(DiskInfo {
+	drive_id,
+	kind,
+	sectors: cylinders * params.nheads() as u64
+	    * params.sectors_per_track as u64,
+	sector_size: SECTOR_SIZE,
+	edd_version: None,
+    })
+}
+
+
+/// Errors returned by [`read_async`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiskError {
+    /// Reading the sectors failed (after retries).
+    ReadFailed { drive_id: u8, lba: u64, nsectors: u16 },
+}
+
+impl fmt::Display for DiskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::ReadFailed { drive_id, lba, nsectors } =>
+		write!(f, "disk: Failed to read {} sectors at LBA {} \
+			   of drive {:#04x}", nsectors, lba, drive_id),
+	}
+    }
+}
+
+///
+/// Returns a future reading `nsectors` sectors from `lba` of the drive
+/// into a buffer allocated by `alloc20` (cf. `int13h42h::call`).
+///
+/// Note: By BIOS, the sectors are read blocking when the future is
+///       polled the second time (it yields once first).
+///
+pub fn read_async<A20>(drive_id: u8, lba: u64, nsectors: u16, alloc20: A20)
+		       -> ReadAsync<A20>
+where
+    A20: Allocator + Unpin,
+{
+    ReadAsync { drive_id, lba, nsectors, alloc20: Some(alloc20),
+		yielded: false }
+}
+
+/// A future returned by [`read_async`].
+pub struct ReadAsync<A20>
+where
+    A20: Allocator + Unpin,
+{
+    drive_id: u8,
+    lba: u64,
+    nsectors: u16,
+    alloc20: Option<A20>,	// Taken when the sectors are read
+    yielded: bool,
+}
+
+impl<A20> Future for ReadAsync<A20>
+where
+    A20: Allocator + Unpin,
+{
+    type Output = Result<Vec<u8, A20>, DiskError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>)
+	    -> Poll<Self::Output> {
+	// Yield once so that the other ready tasks run first.
+	if !self.yielded {
+	    self.yielded = true;
+	    cx.waker().wake_by_ref();
+	    return Poll::Pending;
+	}
+
+	let alloc20 = self.alloc20.take()
+	    .expect("ReadAsync polled after completion");
+	let Self { drive_id, lba, nsectors, .. } = *self;
+	let result = int13h42h::call(drive_id, lba, nsectors, alloc20)
+	    .ok_or(DiskError::ReadFailed { drive_id, lba, nsectors });
+	Poll::Ready(result)
+    }
+}
+
+
+/// I/O statistics of a drive.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DiskStats {
+    pub drive_id: u8,
+    pub sectors_read: u64,
+    pub sectors_written: u64,
+    pub retries: u64,		// Number of Calls Retried
+    pub failures: u64,		// Number of Calls Failed (incl. Retried)
+    pub statuses: [(u8, u64); MAX_STATUSES],	// Failures by Status (AH)
+}
+
+impl DiskStats {
+    // Counts a failure by the status (Only the first `MAX_STATUSES`
+    // distinct statuses are counted separately).
+    fn count_failure(&mut self, status: u8) {
+	self.failures += 1;
+	let slot = self.statuses.iter_mut()
+	    .find(| (s, count) | *s == status || *count == 0);
+	if let Some((s, count)) = slot {
+	    *s = status;
+	    *count += 1;
+	}
+    }
+}
+
+impl fmt::Display for DiskStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "drive {:#04x}: {} sectors read, {} written, \
+		   {} retries, {} failures",
+	       self.drive_id, self.sectors_read, self.sectors_written,
+	       self.retries, self.failures)?;
+	for (status, count) in self.statuses.iter()
+	    .filter(| (_, count) | *count != 0) {
+	    write!(f, ", AH={:#04x} x{}", status, count)?;
+	}
+	Ok(())
+    }
+}
+
+// The statistics of the drives (in order of the first transfer)
+static STATS: MuMutex<[Option<DiskStats>; MAX_STATS_DRIVES]> =
+    MuMutex::new([None; MAX_STATS_DRIVES]);
+
+/// Returns the I/O statistics of the drives transferred so far.
+pub fn stats() -> Vec<DiskStats> {
+    STATS.lock().iter().flatten().copied().collect()
+}
+
+/// Resets the I/O statistics.
+pub fn reset_stats() {
+    *STATS.lock() = [None; MAX_STATS_DRIVES];
+}
+
+// Updates the statistics of the drive (Ignored if there are too many
+// drives).
+fn update_stats<F>(drive_id: u8, f: F)
+where
+    F: FnOnce(&mut DiskStats),
+{
+    let mut stats = STATS.lock();
+    let index = stats.iter()
+	.position(| s | s.is_none_or(| s | s.drive_id == drive_id));
+    if let Some(index) = index {
+	f(stats[index].get_or_insert(DiskStats {
+	    drive_id,
+	    ..Default::default()
+	}));
+    }
+}
+
+
+// The status (AH) of a transfer locked out (cf. `bios::lockout`),
+// which is the same as that of an invalid function.
+pub(super) const STATUS_LOCKED_OUT: u8 = 0x01;
+
+// Directions of transfers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Transfer {
+    Read,
+    Write,
+}
+
+//
+// Calls `call` (a BIOS call returning the status AH on failure) until
+// it succeeds, at most `MAX_ATTEMPTS` times, then returns true if it
+// succeeds.  The sectors transferred, the retries and the failures are
+// counted in the statistics of the drive.
+//
+pub(super) fn transfer_with_retries<F>(drive_id: u8, nsectors: u64,
+				       transfer: Transfer, mut call: F)
+				       -> bool
+where
+    F: FnMut() -> Result<(), u8>,
+{
+    for attempt in 1 ..= MAX_ATTEMPTS {
+	match call() {
+	    Ok(()) => {
+		update_stats(drive_id, | stats | match transfer {
+		    Transfer::Read => stats.sectors_read += nsectors,
+		    Transfer::Write => stats.sectors_written += nsectors,
+		});
+		return true;
+	    },
+	    Err(status) => {
+		update_stats(drive_id, | stats | {
+		    stats.count_failure(status);
+		    if attempt < MAX_ATTEMPTS {
+			stats.retries += 1;
+		    }
+		});
+	    },
+	}
+    }
+    false
+}
@@ -18,10 +18,17 @@ extern "C" {
 
 extern {
     // defined in the linker script ( config/x86_64-unknown-none.ld )
+    pub static __lmb_crashlog_start: u8;
+    pub static __lmb_crashlog_end: u8;
     pub static __lmb_heap16_start: u8;
     pub static __lmb_heap16_end: u8;
     pub static __lmb_heap32_start: u8;
     pub static __lmb_heap32_end: u8;
+    pub static __lmb_main1_start: u8;
+    pub static __lmb_main1_end: u8;
+    pub static __lmb_page_tables_start: u8;
+    pub static __lmb_page_tables_end: u8;
     pub static __lmb_stack_start: u8;
     pub static __lmb_stack_end: u8;
+    pub static __lmb_trailer_start: u8;
 }
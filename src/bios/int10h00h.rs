@@ -0,0 +1,45 @@
+/*!
+
+BIOS INT 10h AH=00h : Set Video Mode
+
+# Supplementary Resource
+
+* <https://en.wikipedia.org/wiki/INT_10H>
+
+ */
+
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/INT_10H
+//
+
+use super::{BiosCall, CallRegs, CallResult};
+
+
+/// Video Mode 03h: 80x25 Text, 16 Colors
+pub const MODE_TEXT_80X25: u8 = 0x03;
+
+/// A request of BIOS INT 10h AH=00h (Set Video Mode).
+pub struct SetVideoMode {
+    pub mode: u8,	// Video Mode
+}
+
+unsafe impl BiosCall for SetVideoMode {
+    type Output = ();
+
+    fn regs(&self) -> CallRegs {
+	// INT 10h AH=00h (Set Video Mode)
+	// IN
+	//   AL = Video Mode
+	CallRegs::int(0x10)
+	    .ah(0x00)
+	    .al(self.mode)
+    }
+
+    fn output(&self, _result: &CallResult) {}
+}
+
+/// Calls BIOS INT 10h AH=00h (Set Video Mode).
+pub fn call(mode: u8) {
+    super::call(&SetVideoMode { mode });
+}
@@ -0,0 +1,61 @@
+/*!
+
+BIOS INT 10h AH=03h : Get Cursor Position and Shape
+
+# Supplementary Resource
+
+* <https://en.wikipedia.org/wiki/INT_10H>
+
+ */
+
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/INT_10H
+//
+
+use super::{BiosCall, CallRegs, CallResult};
+
+
+/// Cursor Position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CursorPos {
+    pub row: u8,	// Row (0 = Top)
+    pub col: u8,	// Column (0 = Left)
+}
+
+/// A request of BIOS INT 10h AH=03h (Get Cursor Position and Shape).
+pub struct GetCursorPos {
+    pub page: u8,	// Page Number
+}
+
+unsafe impl BiosCall for GetCursorPos {
+    type Output = Option<CursorPos>;
+
+    fn regs(&self) -> CallRegs {
+	// INT 10h AH=03h (Get Cursor Position and Shape)
+	// IN
+	//   BH = Page Number
+	CallRegs::int(0x10)
+	    .ah(0x03)
+	    .bh(self.page)
+    }
+
+    fn output(&self, result: &CallResult) -> Option<CursorPos> {
+	// OUT
+	//   CH = Start Scan Line
+	//   CL = End Scan Line
+	//   DH = Row
+	//   DL = Column
+	if !result.is_supported() {
+	    return None;
+	}
+
+	Some(CursorPos { row: result.dh(), col: result.dl() })
+    }
+}
+
+
+/// Calls BIOS INT 10h AH=03h (Get Cursor Position and Shape).
+pub fn call(page_number: u8) -> Option<CursorPos> {
+    super::call(&GetCursorPos { page: page_number })
+}
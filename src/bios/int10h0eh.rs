@@ -13,22 +13,37 @@ BIOS INT 10h AH=0Eh : Teletype Output
 //	https://en.wikipedia.org/wiki/INT_10H
 //
 
-use super::LmbiosRegs;
+use super::{BiosCall, CallRegs, CallResult};
 
 
-/// Calls BIOS INT 10h AH=0Eh (Teletype Output).
-pub fn call(byte: u8, page_number: u8, color: u8) {
-    unsafe {
+/// A request of BIOS INT 10h AH=0Eh (Teletype Output).
+pub struct TeletypeOut {
+    pub ch: u8,		// Character
+    pub page: u8,	// Page Number
+    pub color: u8,	// Color (in graphics modes)
+}
+
+unsafe impl BiosCall for TeletypeOut {
+    type Output = ();
+
+    fn regs(&self) -> CallRegs {
 	// INT 10h AH=0Eh (Teletype Output)
 	// IN
 	//   AL = Character
 	//   BH = Page Number
 	//   BL = Color
-	LmbiosRegs {
-	    fun: 0x10,
-	    eax: 0x0E00 | byte as u32,
-	    ebx: (page_number as u32) << 8 | (color as u32),
-	    ..Default::default()
-	}.call();
+	CallRegs::int(0x10)
+	    .ah(0x0E)
+	    .al(self.ch)
+	    .bh(self.page)
+	    .bl(self.color)
     }
+
+    fn output(&self, _result: &CallResult) {}
+}
+
+
+/// Calls BIOS INT 10h AH=0Eh (Teletype Output).
+pub fn call(byte: u8, page_number: u8, color: u8) {
+    super::call(&TeletypeOut { ch: byte, page: page_number, color });
 }
@@ -0,0 +1,60 @@
+/*!
+
+BIOS INT 10h AH=0Fh : Get Current Video Mode
+
+# Supplementary Resource
+
+* <https://en.wikipedia.org/wiki/INT_10H>
+
+ */
+
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/INT_10H
+//
+
+use super::{BiosCall, CallRegs, CallResult};
+
+
+/// Current Video Mode
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VideoMode {
+    pub mode: u8,	// Video Mode (Bit 7 is cleared)
+    pub columns: u8,	// Number of Character Columns
+    pub page: u8,	// Active Page Number
+}
+
+/// A request of BIOS INT 10h AH=0Fh (Get Current Video Mode).
+pub struct GetVideoMode;
+
+unsafe impl BiosCall for GetVideoMode {
+    type Output = Option<VideoMode>;
+
+    fn regs(&self) -> CallRegs {
+	// INT 10h AH=0Fh (Get Current Video Mode)
+	CallRegs::int(0x10)
+	    .ah(0x0F)
+    }
+
+    fn output(&self, result: &CallResult) -> Option<VideoMode> {
+	// OUT
+	//   AL = Video Mode (Bit 7 is set if the screen was not cleared)
+	//   AH = Number of Character Columns
+	//   BH = Active Page Number
+	if !result.is_supported() {
+	    return None;
+	}
+
+	Some(VideoMode {
+	    mode: result.al() & 0x7f,
+	    columns: result.ah(),
+	    page: result.bh(),
+	})
+    }
+}
+
+
+/// Calls BIOS INT 10h AH=0Fh (Get Current Video Mode).
+pub fn call() -> Option<VideoMode> {
+    super::call(&GetVideoMode)
+}
@@ -0,0 +1,91 @@
+/*!
+
+BIOS INT 10h AX=1110h : Load User Font
+
+# Supplementary Resources
+
+* [INT 10H](https://en.wikipedia.org/wiki/INT_10H) (Wikipedia)
+* [VGA Fonts](https://wiki.osdev.org/VGA_Fonts) (OS Dev)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://en.wikipedia.org/wiki/INT_10H
+//	https://wiki.osdev.org/VGA_Fonts
+//
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+
+use super::{BiosCall, CallRegs, CallResult};
+use crate::x86::{X86FarPtr, X86GetAddr};
+
+
+/// A request of BIOS INT 10h AX=1110h (Load User Font).
+pub struct LoadUserFont {
+    pub table: X86FarPtr,	// Address of the Glyphs (in 20-bit space)
+    pub count: u16,		// Number of Glyphs
+    pub first: u16,		// Character Code of the First Glyph
+    pub block: u8,		// Font Block to Load (0 for the active one)
+    pub bytes_per_char: u8,	// Bytes per Character (i.e., Height)
+}
+
+unsafe impl BiosCall for LoadUserFont {
+    type Output = ();
+
+    fn regs(&self) -> CallRegs {
+	// INT 10h AX=1110h (Load User Font)
+	// IN
+	//   ES:BP = Address of the Glyphs
+	//   CX    = Number of Glyphs
+	//   DX    = Character Code of the First Glyph
+	//   BL    = Font Block to Load
+	//   BH    = Bytes per Character
+	CallRegs::int(0x10)
+	    .ax(0x1110)
+	    .es(self.table.segment)
+	    .bp(self.table.offset)
+	    .cx(self.count)
+	    .dx(self.first)
+	    .bl(self.block)
+	    .bh(self.bytes_per_char)
+    }
+
+    fn output(&self, _result: &CallResult) {}
+}
+
+///
+/// Calls BIOS INT 10h AX=1110h (Load User Font) to replace the glyphs
+/// of the active font block from the character code `first`.
+///
+/// `glyphs` yields `bytes_per_char` bytes per glyph, which are copied
+/// into a buffer allocated by `alloc20` (in 20-bit address space).
+/// Returns false if the buffer is not in 20-bit address space.
+///
+pub fn call<I, A20>(first: u8, bytes_per_char: u8, glyphs: I, alloc20: A20)
+		    -> bool
+where
+    I: IntoIterator<Item = u8>,
+    A20: Allocator,
+{
+    if bytes_per_char == 0 {
+	return false;
+    }
+
+    // Copy the glyphs into a buffer in 20-bit address space.
+    let mut buf = Vec::new_in(alloc20);
+    buf.extend(glyphs);
+    let Some(table) = buf.get_far_ptr() else {
+	return false;
+    };
+
+    super::call(&LoadUserFont {
+	table,
+	count: (buf.len() / bytes_per_char as usize) as u16,
+	first: first as u16,
+	block: 0,
+	bytes_per_char,
+    });
+    true
+}
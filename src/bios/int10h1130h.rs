@@ -0,0 +1,81 @@
+/*!
+
+BIOS INT 10h AX=1130h : Get Font Information
+
+# Supplementary Resources
+
+* [INT 10H](https://en.wikipedia.org/wiki/INT_10H) (Wikipedia)
+* [VGA Fonts](https://wiki.osdev.org/VGA_Fonts) (OS Dev)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://en.wikipedia.org/wiki/INT_10H
+//	https://wiki.osdev.org/VGA_Fonts
+//
+
+use super::{BiosCall, CallRegs, CallResult};
+use crate::x86::X86FarPtr;
+
+
+/// Font Selector (BH): ROM 8x8 Font (Characters 00h-7Fh)
+pub const FONT_8X8: u8 = 0x03;
+/// Font Selector (BH): ROM 8x14 Font
+pub const FONT_8X14: u8 = 0x02;
+/// Font Selector (BH): ROM 8x16 Font
+pub const FONT_8X16: u8 = 0x06;
+
+
+/// Font Information (The font is 8 pixels wide)
+#[derive(Clone, Copy, Debug)]
+pub struct FontInfo {
+    pub addr: usize,		// Linear Address of the Font (256 glyphs)
+    pub bytes_per_char: u16,	// Bytes per Character (i.e., Height)
+    pub rows: u8,		// Number of Character Rows on Screen
+}
+
+/// A request of BIOS INT 10h AX=1130h (Get Font Information).
+pub struct GetFontInfo {
+    pub font: u8,	// Font Selector
+}
+
+unsafe impl BiosCall for GetFontInfo {
+    type Output = Option<FontInfo>;
+
+    fn regs(&self) -> CallRegs {
+	// INT 10h AX=1130h (Get Font Information)
+	// IN
+	//   BH    = Font Selector
+	CallRegs::int(0x10)
+	    .ax(0x1130)
+	    .bh(self.font)
+    }
+
+    fn output(&self, result: &CallResult) -> Option<FontInfo> {
+	// OUT
+	//   ES:BP = Address of the Font
+	//   CX    = Bytes per Character
+	//   DL    = Number of Character Rows on Screen - 1
+	if !result.is_supported() || result.cx() == 0 {
+	    return None;
+	}
+
+	let font_fp = X86FarPtr {
+	    offset: result.bp(),
+	    segment: result.es(),
+	};
+
+	Some(FontInfo {
+	    addr: font_fp.to_linear_addr(),
+	    bytes_per_char: result.cx(),
+	    rows: result.dl().wrapping_add(1),
+	})
+    }
+}
+
+
+/// Calls BIOS INT 10h AX=1130h (Get Font Information).
+pub fn call(font: u8) -> Option<FontInfo> {
+    super::call(&GetFontInfo { font })
+}
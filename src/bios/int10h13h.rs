@@ -0,0 +1,104 @@
+/*!
+
+BIOS INT 10h AH=13h : Write String
+
+It writes a string at the cursor position in teletype fashion (CR,
+LF, BS and BEL are interpreted), then moves the cursor after it.
+Hence, one call replaces a call of INT 10h AH=0Eh per character.
+
+It is supported by the video BIOS of EGA and later adapters (and the
+system BIOS of the PC/AT), not by that of the original PC.  Hence,
+[`is_available`] checks that INT 10h is handled by an option ROM.
+
+# Supplementary Resource
+
+* <https://en.wikipedia.org/wiki/INT_10H>
+
+ */
+
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/INT_10H
+//
+
+use super::{BiosCall, CallRegs, CallResult};
+use super::int10h03h::CursorPos;
+use super::ivt::{self, Owner};
+use crate::x86::{X86FarPtr, X86GetAddr};
+
+
+/// Write Mode 01h: Attribute in BL, the cursor is moved.
+const MODE_UPDATE_CURSOR: u8 = 0x01;
+
+
+/// A request of BIOS INT 10h AH=13h (Write String).
+pub struct WriteString {
+    pub string: X86FarPtr,	// String (in 20-bit address space)
+    pub len: u16,		// Number of Characters
+    pub page: u8,		// Page Number
+    pub attr: u8,		// Attribute (Color)
+    pub pos: CursorPos,		// Position to Start Writing
+}
+
+unsafe impl BiosCall for WriteString {
+    type Output = bool;
+
+    fn regs(&self) -> CallRegs {
+	// INT 10h AH=13h (Write String)
+	// IN
+	//   AL    = Write Mode
+	//   BH    = Page Number
+	//   BL    = Attribute
+	//   CX    = Number of Characters
+	//   DH    = Row
+	//   DL    = Column
+	//   ES:BP = String
+	CallRegs::int(0x10)
+	    .ah(0x13)
+	    .al(MODE_UPDATE_CURSOR)
+	    .bh(self.page)
+	    .bl(self.attr)
+	    .cx(self.len)
+	    .dh(self.pos.row)
+	    .dl(self.pos.col)
+	    .es(self.string.segment)
+	    .bp(self.string.offset)
+    }
+
+    fn output(&self, result: &CallResult) -> bool {
+	result.is_supported()
+    }
+}
+
+
+///
+/// Returns true if INT 10h AH=13h is expected to be supported, i.e.
+/// INT 10h is handled by a video BIOS in an option ROM.
+///
+pub fn is_available() -> bool {
+    // The IVT is read only if BIOS functions are available (not UEFI).
+    super::int10h0fh::call().is_some()
+	&& ivt::entry(0x10).owner == Owner::OptionRom
+}
+
+///
+/// Calls BIOS INT 10h AH=13h (Write String) to write the bytes at the
+/// cursor position of the page.
+///
+/// Returns false if the bytes are not in 20-bit address space (or
+/// too long), or if BIOS functions are not available.
+///
+pub fn call(bytes: &[u8], page_number: u8, color: u8) -> bool {
+    let Ok(len) = u16::try_from(bytes.len()) else {
+	return false;
+    };
+    let Some(string) = bytes.get_far_ptr() else {
+	return false;
+    };
+    let Some(pos) = super::int10h03h::call(page_number) else {
+	return false;
+    };
+
+    super::call(&WriteString { string, len, page: page_number, attr: color,
+				pos })
+}
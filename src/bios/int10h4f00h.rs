@@ -25,13 +25,14 @@ BIOS INT 10h AX=4F00h : Return VBE Controller Information
 //	https://glenwing.github.io/docs/
 //
 
+use alloc::alloc::Global;
 use alloc::boxed::Box;
 use core::alloc::Allocator;
-use core::mem::{MaybeUninit, size_of};
+use core::mem::MaybeUninit;
 
 use super::LmbiosRegs;
-use crate::{print, println};
-use crate::x86::{X86GetAddr, X86FarPtr};
+use crate::{assert_layout, print, println};
+use crate::x86::{PhysMem, RealModeStr, X86GetAddr, X86FarPtr};
 
 
 #[doc(hidden)]
@@ -68,7 +69,7 @@ where
 		     regs.eax, regs.es, regs.edi);
 	}
 
-	regs.call();
+	regs.call().ok()?;
 
 	if DEBUG {
 	    println!("OUT: EAX={:#x}",
@@ -108,11 +109,27 @@ pub struct VbeInfoBlock {
     pub oem_data: [u8; 256],		//100-1FF: Data Area for OEM Strings
 }
 
-const _: () = assert!(size_of::<VbeInfoBlock>() == 0x200);
+assert_layout!(VbeInfoBlock, 0x200, {
+    signature: 0x00,
+    version: 0x04,
+    oem_string_ptr: 0x06,
+    capabilities: 0x0a,
+    video_mode_ptr: 0x0e,
+    total_memory: 0x12,
+    oem_software_rev: 0x14,
+    oem_vendor_name_ptr: 0x16,
+    oem_product_name_ptr: 0x1a,
+    oem_product_rev_ptr: 0x1e,
+    reserved: 0x22,
+    oem_data: 0x100,
+});
 
 impl X86GetAddr for VbeInfoBlock {}
 
 impl VbeInfoBlock {
+    /// The maximum number of modes read from a mode list.
+    pub const MAX_MODES: usize = 512;
+
     fn uninit() -> Self {
 	unsafe {
 	    let myself = MaybeUninit::<Self>::uninit();
@@ -195,42 +212,36 @@ impl VbeInfoBlock {
 
     fn print_mode_list(title: &str, far_ptr: [u16; 2]) {
 	let mode_fp = X86FarPtr::from_array(far_ptr);
-	let mode_ptr = mode_fp.to_linear_ptr::<u16>();
 
 	print!("  {}:", title);
 
-	let mut i: isize = 0;
-	loop {
-	    let mode = unsafe { *mode_ptr.offset(i) };
-	    match mode {
-		0xffff	=> break,
-		_	=> print!(" {:04x}", mode),
-	    }
-	    i += 1;
+	for mode in Self::mode_list(&mode_fp) {
+	    print!(" {:04x}", mode);
 	}
 
 	println!();
     }
 
+    ///
+    /// Returns an iterator over the mode numbers in the list at the far
+    /// pointer (terminated by 0xFFFF).  It stops at an inaccessible
+    /// address or after `MAX_MODES` modes in case of a garbage pointer.
+    ///
+    pub fn mode_list(far_ptr: &X86FarPtr) -> impl Iterator<Item = u16> {
+	let start = far_ptr.to_linear_addr();
+	(0 .. Self::MAX_MODES)
+	    .map_while(move | i | PhysMem::read::<u16>(start + i * 2).ok())
+	    .take_while(| &mode | mode != 0xffff)
+    }
+
     fn print_cstr(title: &str, far_ptr: [u16; 2]) {
+	// The longest OEM string fits in the OEM data area (256 bytes).
+	const MAX_LEN: usize = 256;
+
 	let str_fp = X86FarPtr::from_array(far_ptr);
-	let str_ptr = str_fp.to_linear_ptr::<u8>();
-
-	print!("  {}: {} \"", title, str_fp);
-
-	let mut i: isize = 0;
-	loop {
-	    let byte = unsafe { *str_ptr.offset(i) };
-	    match byte {
-		0x00 => break,
-		0x20 ..= 0x7E | b'\n' | b'\r' => {
-		    print!("{}", byte as char);
-		},
-		_ => print!("."),
-	    }
-	    i += 1;
+	match RealModeStr::read(&str_fp, MAX_LEN, Global) {
+	    Some(s) => println!("  {}: {} \"{}\"", title, str_fp, s),
+	    None => println!("  {}: {} (invalid)", title, str_fp),
 	}
-
-	println!("\"");
     }
 }
@@ -27,10 +27,10 @@ BIOS INT 10h AX=4F01h : Return VBE Mode Information
 
 use alloc::boxed::Box;
 use core::alloc::Allocator;
-use core::mem::{MaybeUninit, size_of};
+use core::mem::MaybeUninit;
 
 use super::LmbiosRegs;
-use crate::{print, println};
+use crate::{assert_layout, print, println};
 use crate::x86::X86GetAddr;
 
 
@@ -70,7 +70,7 @@ where
 		     regs.eax, regs.es, regs.edi);
 	}
 
-	regs.call();
+	regs.call().ok()?;
 
 	if DEBUG {
 	    println!("OUT: EAX={:#x}",
@@ -145,7 +145,53 @@ pub struct ModeInfoBlock {
     pub reserved4: [u8; 190],		//42-FF: (reserved)
 }
 
-const _: () = assert!(size_of::<ModeInfoBlock>() == 0x100);
+assert_layout!(ModeInfoBlock, 0x100, {
+    mode_attributes: 0x00,
+    win_a_attributes: 0x02,
+    win_b_attributes: 0x03,
+    win_granularity: 0x04,
+    win_size: 0x06,
+    win_a_segment: 0x08,
+    win_b_segment: 0x0a,
+    win_func_ptr: 0x0c,
+    bytes_per_scan_line: 0x10,
+    x_resolution: 0x12,
+    y_resolution: 0x14,
+    x_char_size: 0x16,
+    y_char_size: 0x17,
+    number_of_planes: 0x18,
+    bits_per_pixel: 0x19,
+    number_of_banks: 0x1a,
+    memory_model: 0x1b,
+    bank_size: 0x1c,
+    number_of_image_pages: 0x1d,
+    reserved0: 0x1e,
+    red_mask_size: 0x1f,
+    red_field_position: 0x20,
+    green_mask_size: 0x21,
+    green_field_position: 0x22,
+    blue_mask_size: 0x23,
+    blue_field_position: 0x24,
+    rsvd_mask_size: 0x25,
+    rsvd_field_position: 0x26,
+    direct_color_mode_info: 0x27,
+    phys_base_ptr: 0x28,
+    reserved1: 0x2c,
+    reserved2: 0x30,
+    lin_bytes_per_scan_line: 0x32,
+    bnk_number_of_image_pages: 0x34,
+    lin_number_of_image_pages: 0x35,
+    lin_red_mask_size: 0x36,
+    lin_red_field_position: 0x37,
+    lin_green_mask_size: 0x38,
+    lin_green_field_position: 0x39,
+    lin_blue_mask_size: 0x3a,
+    lin_blue_field_position: 0x3b,
+    lin_rsvd_mask_size: 0x3c,
+    lin_rsvd_field_position: 0x3d,
+    max_pixel_clock: 0x3e,
+    reserved4: 0x42,
+});
 
 impl X86GetAddr for ModeInfoBlock {}
 
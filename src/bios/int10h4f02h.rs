@@ -25,15 +25,15 @@ BIOS INT 10h AX=4F02h : Set VBE Mode
 //	https://glenwing.github.io/docs/
 //
 
-use core::mem::size_of;
 
 use super::LmbiosRegs;
+use crate::assert_layout;
 use crate::println;
 use crate::x86::{X86GetAddr, X86FarPtr};
 
 
 #[doc(hidden)]
-const DEBUG: bool = true;
+const DEBUG: bool = false;
 
 
 /// Calls BIOS INT 10h AX=4F02h (Set VBE Mode).
@@ -72,7 +72,9 @@ pub fn call(mode: u16, crtc_info_block: Option<CRTCInfoBlock>) -> bool
 		     regs.eax, regs.ebx, regs.es, regs.edi);
 	}
 
-	regs.call();
+	if regs.call().is_err() {
+	    return false;
+	}
 
 	if DEBUG {
 	    println!("OUT: EAX={:#x}",
@@ -107,6 +109,17 @@ pub struct CRTCInfoBlock {
     pub reserved: [u8; 41],		//13-3A: (reserved)
 }
 
-const _: () = assert!(size_of::<CRTCInfoBlock>() == 0x3c);
+assert_layout!(CRTCInfoBlock, 0x3c, {
+    horizontal_total: 0x00,
+    horizontal_sync_start: 0x02,
+    horizontal_sync_end: 0x04,
+    vertical_total: 0x06,
+    vertical_sync_start: 0x08,
+    vertical_sync_end: 0x0a,
+    flags: 0x0c,
+    pixel_clock: 0x0d,
+    refresh_rate: 0x11,
+    reserved: 0x13,
+});
 
 impl X86GetAddr for CRTCInfoBlock {}
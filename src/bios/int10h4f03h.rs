@@ -54,7 +54,8 @@ pub fn call() -> u16
 		     regs.eax);
 	}
 
-	regs.call();
+	// Note: If BIOS is locked out, BX is left zero.
+	let _ = regs.call();
 
 	if DEBUG {
 	    println!("OUT: EAX={:#x}, EBX={:#x}",
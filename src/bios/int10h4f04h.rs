@@ -0,0 +1,135 @@
+/*!
+
+BIOS INT 10h AX=4F04h : Save/Restore State
+
+# Resource
+
+* [VESA BIOS Extension Core Function Standard Version 3.0](http://www.petesqbsite.com/sections/tutorials/tuts/vbe3.pdf) (VESA, 1998-09-16)
+
+# Supplementary Resources
+
+* [VESA Video Modes](https://wiki.osdev.org/VESA_Video_Modes) (OS Dev)
+
+ */
+
+//
+// BIOS INT 10h AX=4F04h (Save/Restore State)
+//
+// Resource:
+//	"VESA BIOS Extension Core Function Standard Version 3.0" (1998-09-16)
+//	http://www.petesqbsite.com/sections/tutorials/tuts/vbe3.pdf
+//
+// Supplementary Resources:
+//	https://wiki.osdev.org/VESA_Video_Modes
+//
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+
+use super::{BiosCall, CallRegs, CallResult};
+use crate::x86::{X86FarPtr, X86GetAddr};
+
+
+/// Requested States (CX): Controller Hardware State
+pub const STATE_HARDWARE: u16 = 0x0001;
+/// Requested States (CX): BIOS Data State
+pub const STATE_BIOS_DATA: u16 = 0x0002;
+/// Requested States (CX): DAC State
+pub const STATE_DAC: u16 = 0x0004;
+/// Requested States (CX): Register State
+pub const STATE_REGISTERS: u16 = 0x0008;
+/// Requested States (CX): All of the above
+pub const STATE_ALL: u16 = 0x000F;
+
+// Subfunctions (DL)
+const GET_BUFFER_SIZE: u8 = 0x00;
+const SAVE_STATE: u8 = 0x01;
+const RESTORE_STATE: u8 = 0x02;
+
+// The buffer size is returned in 64-byte blocks.
+const BLOCK_SIZE: usize = 64;
+
+
+/// A request of BIOS INT 10h AX=4F04h (Save/Restore State).
+pub struct SaveRestoreState {
+    pub subfunction: u8,	// DL: 00h (Size), 01h (Save), 02h (Restore)
+    pub states: u16,		// CX: Requested States
+    pub buffer: X86FarPtr,	// ES:BX: State Buffer (for Save/Restore)
+}
+
+unsafe impl BiosCall for SaveRestoreState {
+    type Output = Option<u16>;
+
+    fn regs(&self) -> CallRegs {
+	// INT 10h AH=4Fh AL=04h
+	// IN
+	//   DL    = Subfunction
+	//   CX    = Requested States
+	//   ES:BX = Address of State Buffer
+	CallRegs::int(0x10)
+	    .ax(0x4f04)
+	    .dl(self.subfunction)
+	    .cx(self.states)
+	    .es(self.buffer.segment)
+	    .bx(self.buffer.offset)
+    }
+
+    fn output(&self, result: &CallResult) -> Option<u16> {
+	// OUT
+	//   AX    = Status (004Fh if successful)
+	//   BX    = Number of 64-byte blocks (for Size)
+	if result.is_supported() && result.ax() == 0x004f {
+	    Some(result.bx())
+	} else {
+	    None
+	}
+    }
+}
+
+
+/// Returns the size in bytes of the buffer to save the states.
+pub fn get_buffer_size(states: u16) -> Option<usize> {
+    let nblocks = super::call(&SaveRestoreState {
+	subfunction: GET_BUFFER_SIZE,
+	states,
+	buffer: X86FarPtr::null(),
+    })?;
+
+    Some(nblocks as usize * BLOCK_SIZE)
+}
+
+/// Saves the states into a buffer in 20-bit address space.
+pub fn save<A20>(states: u16, alloc20: A20) -> Option<Vec<u8, A20>>
+where
+    A20: Allocator,
+{
+    let size = get_buffer_size(states)?;
+
+    // Allocate a buffer in 20-bit address space.
+    let mut buf = Vec::with_capacity_in(size, alloc20);
+    buf.resize(size, 0);
+
+    // Get the far pointer of the buffer.
+    let buf_fp = buf.get_far_ptr()?;
+
+    super::call(&SaveRestoreState {
+	subfunction: SAVE_STATE,
+	states,
+	buffer: buf_fp,
+    })?;
+
+    Some(buf)
+}
+
+/// Restores the states saved by [`save`].
+pub fn restore(states: u16, buf: &[u8]) -> bool {
+    let Some(buf_fp) = buf.get_far_ptr() else {
+	return false;
+    };
+
+    super::call(&SaveRestoreState {
+	subfunction: RESTORE_STATE,
+	states,
+	buffer: buf_fp,
+    }).is_some()
+}
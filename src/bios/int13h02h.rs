@@ -19,6 +19,8 @@ use alloc::vec::Vec;
 use core::alloc::Allocator;
 
 use super::LmbiosRegs;
+use super::disk::{self, Transfer};
+use super::int13h08h::chs_to_cx;
 use crate::mu::PushBulk;
 use crate::x86::{FLAGS_CF, X86GetAddr};
 
@@ -39,47 +41,45 @@ where
     let mut vec = Vec::new_in(alloc20);
 
     unsafe {
-	vec.push_bulk(nbytes, | buf | {
+	vec.push_bulk_init(nbytes, 0, | buf | {
 	    // Get the far pointer of the buffer.
 	    let buf_fp = buf.get_far_ptr().ok_or(())?;
 
-	    // INT 13h AH=02h (Read Sectors From Drive)
-	    // IN
-	    //   AL    = Number of Sectors
-	    //   CX    = Cylinder and Sector
-	    //   DH    = Head
-	    //   DL    = Drive ID
-	    //   ES:BX = Buffer Address
-	    // OUT
-	    //   CF    = 0 if Ok, 1 if Err
-	    let mut regs = LmbiosRegs {
-		fun: 0x13,
-		eax: 0x0200 | (nsectors as u32),
-		ecx: cylsec_to_cx(cylinder, sector) as u32,
-		edx: (head as u32) << 8 | drive_id as u32,
-		ebx: buf_fp.offset as u32,
-		es: buf_fp.segment,
-		..Default::default()
-	    };
-
-	    regs.call();
-
-	    // Check the results.
-	    // Note: On error, the carry flag (CF) is set.
-	    if (regs.flags & FLAGS_CF) == 0 {
-		Ok(())
-	    } else {
-		Err(())
-	    }
+	    let ok = disk::transfer_with_retries(
+		drive_id, nsectors as u64, Transfer::Read, || {
+		    // INT 13h AH=02h (Read Sectors From Drive)
+		    // IN
+		    //   AL    = Number of Sectors
+		    //   CX    = Cylinder and Sector
+		    //   DH    = Head
+		    //   DL    = Drive ID
+		    //   ES:BX = Buffer Address
+		    // OUT
+		    //   CF    = 0 if Ok, 1 if Err
+		    //   AH    = Status
+		    let mut regs = LmbiosRegs {
+			fun: 0x13,
+			eax: 0x0200 | (nsectors as u32),
+			ecx: chs_to_cx(cylinder, sector) as u32,
+			edx: (head as u32) << 8 | drive_id as u32,
+			ebx: buf_fp.offset as u32,
+			es: buf_fp.segment,
+			..Default::default()
+		    };
+
+		    regs.call().map_err(| _ | disk::STATUS_LOCKED_OUT)?;
+
+		    // Check the results.
+		    // Note: On error, the carry flag (CF) is set.
+		    if (regs.flags & FLAGS_CF) == 0 {
+			Ok(())
+		    } else {
+			Err((regs.eax >> 8) as u8)
+		    }
+		});
+	    ok.then_some(()).ok_or(())
 	}).ok()?;
     }
 
     Some(vec)
 }
-
-/// Calculate the CX register value from the cylinder number
-/// (0 to 1023) and the sector number (1 to 63).
-#[inline]
-fn cylsec_to_cx(cylinder: u16, sector: u8) -> u16 {
-    (cylinder & 0x00ff) << 8 | (cylinder & 0x0300) >> 2 | (sector as u16)
-}
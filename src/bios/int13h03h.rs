@@ -0,0 +1,105 @@
+/*!
+
+BIOS INT 13h AH=03h : Write Sectors To Drive
+
+The sectors are addressed by CHS (cf. `int13h08h::DriveParams` to
+convert LBA into CHS).  The data must be within a track.
+
+```ignore
+let params = bios::int13h08h::call(drive_id)?;
+let (cylinder, head, sector) = params.lba_to_chs(lba);
+bios::int13h03h::call(drive_id, cylinder, head, sector, &data,
+                      &ALLOC_UNDER20);
+```
+
+# Supplementary Resources
+
+* [INT 13H](https://en.wikipedia.org/wiki/INT_13H) (Wikipedia)
+* [Cylinder-head-sector](https://en.wikipedia.org/wiki/Cylinder-head-sector) (Wikipedia)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://en.wikipedia.org/wiki/INT_13H
+//	https://en.wikipedia.org/wiki/Cylinder-head-sector
+//
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+
+use super::LmbiosRegs;
+use super::disk::{self, Transfer};
+use super::int13h08h::chs_to_cx;
+use crate::x86::{FLAGS_CF, X86GetAddr};
+
+
+/// Sector Size = 512
+const SECTOR_SIZE: usize = 512;
+
+
+///
+/// Calls BIOS INT 13h AH=03h (Write Sectors To Drive).
+///
+/// `data` is copied into a buffer in 20-bit address space allocated by
+/// `alloc20`.  Its size must be a multiple of 512 bytes (1 to 255
+/// sectors).  Returns false if it is not, or if writing fails.
+///
+/// Note: It overwrites the sectors of the drive (e.g. the boot image
+///       on the boot drive) without confirmation.
+///
+pub fn call<A20>(drive_id: u8, cylinder: u16, head: u8, sector: u8,
+		 data: &[u8], alloc20: A20) -> bool
+where
+    A20: Allocator
+{
+    let nsectors = data.len() / SECTOR_SIZE;
+    if !data.len().is_multiple_of(SECTOR_SIZE) ||
+	!(1 ..= 255).contains(&nsectors) {
+	return false;
+    }
+
+    // Copy the data into a buffer in 20-bit address space.
+    let mut buf = Vec::with_capacity_in(data.len(), alloc20);
+    buf.extend_from_slice(data);
+
+    // Get the far pointer of the buffer.
+    let Some(buf_fp) = buf.get_far_ptr() else {
+	return false;
+    };
+
+    disk::transfer_with_retries(drive_id, nsectors as u64, Transfer::Write,
+				|| {
+	// INT 13h AH=03h (Write Sectors To Drive)
+	// IN
+	//   AL    = Number of Sectors
+	//   CX    = Cylinder and Sector
+	//   DH    = Head
+	//   DL    = Drive ID
+	//   ES:BX = Buffer Address
+	// OUT
+	//   CF    = 0 if Ok, 1 if Err
+	//   AH    = Status
+	let mut regs = LmbiosRegs {
+	    fun: 0x13,
+	    eax: 0x0300 | (nsectors as u32),
+	    ecx: chs_to_cx(cylinder, sector) as u32,
+	    edx: (head as u32) << 8 | drive_id as u32,
+	    ebx: buf_fp.offset as u32,
+	    es: buf_fp.segment,
+	    ..Default::default()
+	};
+
+	unsafe {
+	    regs.call().map_err(| _ | disk::STATUS_LOCKED_OUT)?;
+	}
+
+	// Check the results.
+	// Note: On error, the carry flag (CF) is set.
+	if (regs.flags & FLAGS_CF) == 0 {
+	    Ok(())
+	} else {
+	    Err((regs.eax >> 8) as u8)
+	}
+    })
+}
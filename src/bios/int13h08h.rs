@@ -0,0 +1,110 @@
+/*!
+
+BIOS INT 13h AH=08h : Read Drive Parameters
+
+# Supplementary Resources
+
+* [INT 13H](https://en.wikipedia.org/wiki/INT_13H) (Wikipedia)
+
+ */
+
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/INT_13H
+//
+
+use super::{BiosCall, CallRegs, CallResult};
+
+
+/// Drive Parameters (CHS geometry)
+#[derive(Clone, Copy, Debug)]
+pub struct DriveParams {
+    pub max_cylinder: u16,	// Maximum Cylinder Number (0-based)
+    pub max_head: u8,		// Maximum Head Number (0-based)
+    pub sectors_per_track: u8,	// Number of Sectors per Track (1-based)
+    pub ndrives: u8,		// Number of Drives
+}
+
+impl DriveParams {
+    /// Returns the number of heads.
+    pub fn nheads(&self) -> u32 {
+	self.max_head as u32 + 1
+    }
+
+    /// Converts LBA into CHS (cylinder, head, sector).
+    pub fn lba_to_chs(&self, lba: u32) -> (u16, u8, u8) {
+	let spt = self.sectors_per_track as u32;
+	let cylinder = lba / (spt * self.nheads());
+	let head = (lba / spt) % self.nheads();
+	let sector = (lba % spt) + 1;
+	(cylinder as u16, head as u8, sector as u8)
+    }
+
+    /// Returns the number of sectors from the sector (1-based) to the
+    /// end of the track, i.e. the most sectors transferred at once.
+    pub fn sectors_to_track_end(&self, sector: u8) -> u8 {
+	self.sectors_per_track.saturating_sub(sector) + 1
+    }
+}
+
+/// Encodes the cylinder number (0 to 1023) and the sector number
+/// (1 to 63) into CX of INT 13h AH=02h and AH=03h.
+#[inline]
+pub fn chs_to_cx(cylinder: u16, sector: u8) -> u16 {
+    (cylinder & 0x00ff) << 8 | (cylinder & 0x0300) >> 2 | (sector as u16)
+}
+
+
+/// A request of BIOS INT 13h AH=08h (Read Drive Parameters).
+pub struct ReadDriveParams {
+    pub drive_id: u8,
+}
+
+unsafe impl BiosCall for ReadDriveParams {
+    type Output = Option<DriveParams>;
+
+    fn regs(&self) -> CallRegs {
+	// INT 13h AH=08h (Read Drive Parameters)
+	// IN
+	//   DL    = Drive ID
+	//   ES:DI = 0000h:0000h (to guard against BIOS bugs)
+	CallRegs::int(0x13)
+	    .ah(0x08)
+	    .dl(self.drive_id)
+	    .es(0)
+	    .di(0)
+    }
+
+    fn output(&self, result: &CallResult) -> Option<DriveParams> {
+	// OUT
+	//   CF    = 0 if Ok, 1 if Err
+	//   CH    = Low 8 bits of Maximum Cylinder Number
+	//   CL    = Bits 0-5: Maximum Sector Number,
+	//           Bits 6-7: High 2 bits of Maximum Cylinder Number
+	//   DH    = Maximum Head Number
+	//   DL    = Number of Drives
+	if result.carry() {
+	    return None;
+	}
+
+	let (ch, cl) = (result.ch(), result.cl());
+	let params = DriveParams {
+	    max_cylinder: (ch as u16) | ((cl as u16) & 0xc0) << 2,
+	    max_head: result.dh(),
+	    sectors_per_track: cl & 0x3f,
+	    ndrives: result.dl(),
+	};
+
+	if params.sectors_per_track == 0 {
+	    None
+	} else {
+	    Some(params)
+	}
+    }
+}
+
+
+/// Calls BIOS INT 13h AH=08h (Read Drive Parameters).
+pub fn call(drive_id: u8) -> Option<DriveParams> {
+    super::call(&ReadDriveParams { drive_id })
+}
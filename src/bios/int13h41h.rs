@@ -0,0 +1,75 @@
+/*!
+
+BIOS INT 13h AH=41h : Check Extensions Present
+
+# Supplementary Resources
+
+* [INT 13H](https://en.wikipedia.org/wiki/INT_13H) (Wikipedia)
+
+ */
+
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/INT_13H
+//
+
+use super::{BiosCall, CallRegs, CallResult};
+
+
+/// INT 13h Extensions
+#[derive(Clone, Copy, Debug)]
+pub struct Extensions {
+    pub version: u8,	// Major Version (e.g., 30h for EDD 3.0)
+    pub support: u16,	// Interface Support Bitmap
+}
+
+impl Extensions {
+    /// Interface Support Bitmap: Extended Disk Access (AH=42h-44h,47h,48h)
+    pub const DISK_ACCESS: u16 = 1 << 0;
+    /// Interface Support Bitmap: Removable Drive Controlling (AH=45h,46h)
+    pub const REMOVABLE: u16 = 1 << 1;
+    /// Interface Support Bitmap: Enhanced Disk Drive (AH=48h, EDD)
+    pub const EDD: u16 = 1 << 2;
+}
+
+/// A request of BIOS INT 13h AH=41h (Check Extensions Present).
+pub struct CheckExtensions {
+    pub drive_id: u8,
+}
+
+unsafe impl BiosCall for CheckExtensions {
+    type Output = Option<Extensions>;
+
+    fn regs(&self) -> CallRegs {
+	// INT 13h AH=41h (Check Extensions Present)
+	// IN
+	//   BX = 55AAh
+	//   DL = Drive ID
+	CallRegs::int(0x13)
+	    .ah(0x41)
+	    .bx(0x55aa)
+	    .dl(self.drive_id)
+    }
+
+    fn output(&self, result: &CallResult) -> Option<Extensions> {
+	// OUT
+	//   CF = 0 if Ok, 1 if Err
+	//   AH = Major Version of Extensions
+	//   BX = AA55h
+	//   CX = Interface Support Bitmap
+	if result.carry() || result.bx() != 0xaa55 {
+	    return None;
+	}
+
+	Some(Extensions {
+	    version: result.ah(),
+	    support: result.cx(),
+	})
+    }
+}
+
+
+/// Calls BIOS INT 13h AH=41h (Check Extensions Present).
+pub fn call(drive_id: u8) -> Option<Extensions> {
+    super::call(&CheckExtensions { drive_id })
+}
@@ -0,0 +1,166 @@
+/*!
+
+BIOS INT 13h AH=43h : Extended Write Sectors To Drive
+
+Like `int13h42h`, the sectors are addressed by LBA with a Disk
+Address Packet, and a write over 127 sectors is split into chunks.
+[`call_verified`] reads each chunk back (INT 13h AH=42h) and compares
+it with the data written.
+
+```ignore
+if !bios::int13h43h::call_verified(drive_id, lba, &data, &ALLOC_UNDER20) {
+    println!("Failed to write LBA {}", lba);
+}
+```
+
+# Supplementary Resources
+
+* [INT 13H](https://en.wikipedia.org/wiki/INT_13H) (Wikipedia)
+
+ */
+
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/INT_13H
+//
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+
+use super::{LmbiosRegs, get_sector_size, int13h42h};
+use super::disk::{self, Transfer};
+use super::int13h42h::DiskAddressPacket;
+use crate::x86::{FLAGS_CF, X86GetAddr};
+
+
+/// The maximum number of bytes that can be written by one BIOS call.
+/// (= 127 sectors of 512 bytes)
+const MAX_NBYTES: usize = 127 * 512;
+
+
+///
+/// Calls BIOS INT 13h AH=43h (Extended Write Sectors To Drive).
+///
+/// `data` is copied chunk by chunk into a buffer in 20-bit address
+/// space allocated by `alloc20`.  Its size must be a multiple of the
+/// sector size of the drive (cf. `int13h42h::call`).  Returns false if
+/// it is not, or if writing fails.
+///
+/// Note: It overwrites the sectors of the drive (e.g. the boot image
+///       on the boot drive) without confirmation.
+///
+pub fn call<A20>(drive_id: u8, lba: u64, data: &[u8], alloc20: A20) -> bool
+where
+    A20: Copy + Allocator
+{
+    write(drive_id, lba, data, false, alloc20)
+}
+
+///
+/// Same as [`call`], but reads each chunk back after writing it, and
+/// returns false if it differs from the data.
+///
+pub fn call_verified<A20>(drive_id: u8, lba: u64, data: &[u8], alloc20: A20)
+			  -> bool
+where
+    A20: Copy + Allocator
+{
+    write(drive_id, lba, data, true, alloc20)
+}
+
+fn write<A20>(drive_id: u8, lba: u64, data: &[u8], verify: bool,
+	      alloc20: A20) -> bool
+where
+    A20: Copy + Allocator
+{
+    // Get the sector size of the drive.
+    let sector_size = get_sector_size(drive_id);
+    if data.is_empty() || !data.len().is_multiple_of(sector_size) {
+	return false;
+    }
+    let max_nbytes = MAX_NBYTES / sector_size * sector_size;
+
+    // Prepare a buffer in 20-bit address space.
+    let mut buf = Vec::with_capacity_in(data.len().min(max_nbytes), alloc20);
+
+    let mut cur_lba = lba;
+    for chunk in data.chunks(max_nbytes) {
+	let cur_nsectors = (chunk.len() / sector_size) as u16;
+
+	// Copy the chunk into the buffer, then get its far pointer.
+	buf.clear();
+	buf.extend_from_slice(chunk);
+	let Some(buf_fp) = buf.get_far_ptr() else {
+	    return false;
+	};
+
+	// Allocate a buffer for DAP on the stack.
+	let mut dap = DiskAddressPacket::default();
+
+	// Get the far pointer of the Disk Address Packet.
+	let Some(dap_fp) = dap.get_far_ptr() else {
+	    return false;
+	};
+
+	let ok = disk::transfer_with_retries(
+	    drive_id, cur_nsectors as u64, Transfer::Write, || {
+		// Note: On error, BIOS may update the number of blocks
+		//       to that transferred.
+		dap = DiskAddressPacket {
+		    size: 0x10,
+		    reserved: 0,
+		    nsectors: cur_nsectors,
+		    buf_offset: buf_fp.offset,
+		    buf_segment: buf_fp.segment,
+		    lba: cur_lba,
+		};
+
+		// INT 13h AH=43h (Extended Write Sectors To Drive)
+		// IN
+		//   AL    = 00h or 01h (Write without Verify)
+		//   DL    = Drive ID
+		//   DS:SI = DAP Address
+		// OUT
+		//   CF    = 0 if Ok, 1 if Err
+		//   AH    = Status
+		let mut regs = LmbiosRegs {
+		    fun: 0x13,
+		    eax: 0x4300,
+		    edx: drive_id as u32,
+		    esi: dap_fp.offset as u32,
+		    ds: dap_fp.segment,
+		    ..Default::default()
+		};
+
+		unsafe {
+		    regs.call().map_err(| _ | disk::STATUS_LOCKED_OUT)?;
+		}
+
+		// Check the results.
+		// Note: On error, the carry flag (CF) is set.
+		if (regs.flags & FLAGS_CF) == 0 {
+		    Ok(())
+		} else {
+		    Err((regs.eax >> 8) as u8)
+		}
+	    });
+	if !ok {
+	    return false;
+	}
+
+	// Read the chunk back, then compare it with the data.
+	// Note: AL=02h (Write with Verify) is not supported by every
+	//       BIOS, and it does not compare the data.
+	if verify {
+	    let read = int13h42h::call(drive_id, cur_lba, cur_nsectors,
+				       alloc20);
+	    if read.as_deref() != Some(chunk) {
+		return false;
+	    }
+	}
+
+	cur_lba += cur_nsectors as u64;
+    }
+
+    true
+}
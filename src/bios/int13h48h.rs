@@ -0,0 +1,399 @@
+/*!
+
+BIOS INT 13h AH=48h : Extended Read Drive Parameters (EDD 3.0)
+
+[`query`] returns the geometry and the size of a drive as
+[`ExtendedDriveParameters`], including the sector size (e.g. 2048
+bytes of CD-ROM drives), so that it need not be assumed.
+
+```ignore
+let params = bios::int13h48h::query(drive_id, &ALLOC_UNDER20)?;
+let nbytes = params.total_sectors * params.bytes_per_sector as u64;
+```
+
+The EDD 3.0 device path extension tells which host bus (e.g., a PCI
+function) and interface (e.g., an ATA device or a SATA port) own the
+drive.  It is parsed by [`DriveParamsExt::device_path`].
+
+# Resource
+
+* BIOS Enhanced Disk Drive Specification Version 3.0 (T13/1386D)
+
+# Supplementary Resources
+
+* [INT 13H](https://en.wikipedia.org/wiki/INT_13H) (Wikipedia)
+
+ */
+
+//
+// Resource:
+//	"BIOS Enhanced Disk Drive Specification Version 3.0" (T13/1386D)
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/INT_13H
+//
+
+use alloc::boxed::Box;
+use core::alloc::Allocator;
+use core::fmt;
+
+use super::{BiosCall, CallRegs, CallResult, get_sector_size};
+use crate::assert_layout;
+use crate::drivers::pci::PciAddress;
+use crate::util;
+use crate::x86::{X86FarPtr, X86GetAddr};
+
+
+/// A request of BIOS INT 13h AH=48h (Extended Read Drive Parameters).
+pub struct ExtReadDriveParams {
+    pub drive_id: u8,		// DL: Drive ID
+    pub buffer: X86FarPtr,	// DS:SI: Result Buffer
+}
+
+unsafe impl BiosCall for ExtReadDriveParams {
+    type Output = bool;
+
+    fn regs(&self) -> CallRegs {
+	// INT 13h AH=48h (Extended Read Drive Parameters)
+	// IN
+	//   DL    = Drive ID
+	//   DS:SI = Address of Result Buffer
+	CallRegs::int(0x13)
+	    .ah(0x48)
+	    .dl(self.drive_id)
+	    .ds(self.buffer.segment)
+	    .si(self.buffer.offset)
+    }
+
+    fn output(&self, result: &CallResult) -> bool {
+	// OUT
+	//   CF    = 0 if Ok, 1 if Err
+	!result.carry()
+    }
+}
+
+
+/// Calls BIOS INT 13h AH=48h (Extended Read Drive Parameters).
+pub fn call<A20>(drive_id: u8, alloc20: A20)
+		 -> Option<Box<DriveParamsExt, A20>>
+where
+    A20: Allocator,
+{
+    // Allocate a buffer in 20-bit address space.
+    let buf = Box::new_in(DriveParamsExt::initial_value(), alloc20);
+
+    // Get the far pointer of the buffer.
+    let buf_fp = buf.get_far_ptr()?;
+
+    if super::call(&ExtReadDriveParams { drive_id, buffer: buf_fp }) {
+	Some(buf)
+    } else {
+	None
+    }
+}
+
+///
+/// Calls BIOS INT 13h AH=48h (Extended Read Drive Parameters), then
+/// returns the geometry and the size of the drive.
+///
+pub fn query<A20>(drive_id: u8, alloc20: A20)
+		  -> Option<ExtendedDriveParameters>
+where
+    A20: Allocator,
+{
+    call(drive_id, alloc20)?.params(drive_id)
+}
+
+
+/// The geometry and the size of a drive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExtendedDriveParameters {
+    pub cylinders: u32,			// Number of Cylinders
+    pub heads: u32,			// Number of Heads
+    pub sectors_per_track: u32,		// Sectors per Track
+    pub total_sectors: u64,		// Total Number of Sectors
+    pub bytes_per_sector: u16,		// Bytes per Sector
+}
+
+impl ExtendedDriveParameters {
+    /// Returns the size in bytes.
+    pub fn size(&self) -> u64 {
+	self.total_sectors * self.bytes_per_sector as u64
+    }
+
+    /// Returns true if the CHS geometry is known.
+    pub fn has_geometry(&self) -> bool {
+	self.cylinders != 0 && self.heads != 0 && self.sectors_per_track != 0
+    }
+}
+
+impl fmt::Display for ExtendedDriveParameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{} sectors x {} bytes", self.total_sectors,
+	       self.bytes_per_sector)?;
+	if self.has_geometry() {
+	    write!(f, " (C/H/S = {}/{}/{})", self.cylinders, self.heads,
+		   self.sectors_per_track)?;
+	}
+	Ok(())
+    }
+}
+
+
+/// Result Buffer of Extended Read Drive Parameters (EDD 3.0)
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct DriveParamsExt {
+    pub size: u16,			//00-01: Buffer Size
+    pub info_flags: u16,		//02-03: Information Flags
+    pub cylinders: u32,			//04-07: Number of Cylinders
+    pub heads: u32,			//08-0B: Number of Heads
+    pub sectors_per_track: u32,		//0C-0F: Sectors per Track
+    pub total_sectors: u64,		//10-17: Total Number of Sectors
+    pub bytes_per_sector: u16,		//18-19: Bytes per Sector
+    pub dpte_ptr: [u16; 2],		//1A-1D: EDD Config Params (far ptr)
+    // Added for EDD 3.0 (Device Path Information)
+    pub key: u16,			//1E-1F: Key = 0xBEDD
+    pub path_length: u8,		//20   : Length of Path Info = 44
+    pub reserved0: [u8; 3],		//21-23: (reserved)
+    pub host_bus: [u8; 4],		//24-27: Host Bus Type (ASCII)
+    pub interface: [u8; 8],		//28-2F: Interface Type (ASCII)
+    pub interface_path: [u8; 8],	//30-37: Interface Path
+    pub device_path: [u8; 16],		//38-47: Device Path
+    pub reserved1: u8,			//48   : (reserved)
+    pub checksum: u8,			//49   : Checksum of 1E-49
+    pub padding: [u8; 6],		//4A-4F: (padding)
+}
+
+assert_layout!(DriveParamsExt, 0x50, {
+    size: 0x00,
+    info_flags: 0x02,
+    cylinders: 0x04,
+    heads: 0x08,
+    sectors_per_track: 0x0c,
+    total_sectors: 0x10,
+    bytes_per_sector: 0x18,
+    dpte_ptr: 0x1a,
+    key: 0x1e,
+    path_length: 0x20,
+    reserved0: 0x21,
+    host_bus: 0x24,
+    interface: 0x28,
+    interface_path: 0x30,
+    device_path: 0x38,
+    reserved1: 0x48,
+    checksum: 0x49,
+    padding: 0x4a,
+});
+
+impl X86GetAddr for DriveParamsExt {}
+
+impl DriveParamsExt {
+    /// Information Flags: The CHS geometry is valid.
+    pub const INFO_CHS_VALID: u16 = 1 << 1;
+
+    // The size of the result buffer of EDD 3.0 (excluding the padding)
+    const EDD30_SIZE: u16 = 0x4a;
+
+    // The key and the length of the device path information
+    const PATH_KEY: u16 = 0xbedd;
+    const PATH_LENGTH: u8 = 44;
+
+    fn initial_value() -> Self {
+	Self {
+	    size: Self::EDD30_SIZE,
+	    ..Default::default()
+	}
+    }
+
+    ///
+    /// Returns the geometry and the size of the drive, or None if the
+    /// size is unknown.  The geometry is zero unless it is valid, and
+    /// the sector size defaults to that of the kind of the drive.
+    ///
+    pub fn params(&self, drive_id: u8) -> Option<ExtendedDriveParameters> {
+	let (cylinders, heads, sectors_per_track) =
+	    if (self.info_flags & Self::INFO_CHS_VALID) != 0 {
+		(self.cylinders, self.heads, self.sectors_per_track)
+	    } else {
+		(0, 0, 0)
+	    };
+
+	// Note: Some BIOSes report only the geometry.
+	let total_sectors = match self.total_sectors {
+	    0 => cylinders as u64 * heads as u64 * sectors_per_track as u64,
+	    n => n,
+	};
+	if total_sectors == 0 {
+	    return None;
+	}
+
+	let bytes_per_sector = match self.bytes_per_sector {
+	    0 => get_sector_size(drive_id) as u16,
+	    size => size,
+	};
+
+	Some(ExtendedDriveParameters {
+	    cylinders,
+	    heads,
+	    sectors_per_track,
+	    total_sectors,
+	    bytes_per_sector,
+	})
+    }
+
+    /// Returns the device path (EDD 3.0) if it is present and valid.
+    pub fn device_path(&self) -> Option<DevicePath> {
+	#[allow(unused_parens)]
+	if (self.size < Self::EDD30_SIZE || self.key != Self::PATH_KEY ||
+	    self.path_length != Self::PATH_LENGTH) {
+	    return None;
+	}
+
+	// The sum of the bytes from the key to the checksum must be 0.
+	let bytes = unsafe {
+	    core::slice::from_raw_parts(
+		(self as *const Self as *const u8).add(0x1e),
+		Self::PATH_LENGTH as usize)
+	};
+	if util::sum8(bytes) != 0 {
+	    return None;
+	}
+
+	Some(DevicePath {
+	    host_bus: HostBus::parse(&self.host_bus, &self.interface_path),
+	    interface: Interface::parse(&self.interface, &self.device_path),
+	})
+    }
+}
+
+
+/// The device path of a drive (EDD 3.0).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DevicePath {
+    pub host_bus: HostBus,
+    pub interface: Interface,
+}
+
+impl fmt::Display for DevicePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{} {}", self.host_bus, self.interface)
+    }
+}
+
+
+/// The host bus of a drive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HostBus {
+    /// A PCI function (and its channel, e.g., primary or secondary)
+    Pci { bus: u8, device: u8, function: u8, channel: u8 },
+    /// An ISA controller at the base I/O address
+    Isa { base_address: u16 },
+    /// Other host bus types
+    Other([u8; 4]),
+}
+
+impl HostBus {
+    fn parse(bus_type: &[u8; 4], path: &[u8; 8]) -> Self {
+	match bus_type {
+	    b"PCI " | b"PCIX" => Self::Pci {
+		bus: path[0],
+		device: path[1],
+		function: path[2],
+		channel: path[3],
+	    },
+	    b"ISA " => Self::Isa {
+		base_address: u16::from_le_bytes([path[0], path[1]]),
+	    },
+	    _ => Self::Other(*bus_type),
+	}
+    }
+
+    /// Returns the address of the PCI function (if on a PCI bus).
+    pub fn pci_address(&self) -> Option<PciAddress> {
+	match *self {
+	    Self::Pci { bus, device, function, .. } =>
+		Some(PciAddress::new(bus, device, function)),
+	    _ => None,
+	}
+    }
+}
+
+impl fmt::Display for HostBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Pci { bus, device, function, channel } =>
+		write!(f, "PCI {:02x}:{:02x}.{:x} channel {}",
+		       bus, device, function, channel),
+	    Self::Isa { base_address } =>
+		write!(f, "ISA {:#x}", base_address),
+	    Self::Other(bus_type) =>
+		write!(f, "{}", ascii(bus_type)),
+	}
+    }
+}
+
+
+/// The interface of a drive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interface {
+    /// An ATA device (0 = master, 1 = slave)
+    Ata { device: u8 },
+    /// An ATAPI device (0 = master, 1 = slave)
+    Atapi { device: u8, lun: u8 },
+    /// A SATA port
+    Sata { port: u8 },
+    /// A SCSI target
+    Scsi { id: u16, lun: u64 },
+    /// A USB device
+    Usb { serial: u64 },
+    /// Other interface types
+    Other([u8; 8]),
+}
+
+impl Interface {
+    fn parse(interface_type: &[u8; 8], path: &[u8; 16]) -> Self {
+	let u64_at = |offset: usize| {
+	    let mut bytes = [0; 8];
+	    bytes.copy_from_slice(&path[offset .. offset + 8]);
+	    u64::from_le_bytes(bytes)
+	};
+
+	match interface_type {
+	    b"ATA     " => Self::Ata { device: path[0] },
+	    b"ATAPI   " => Self::Atapi { device: path[0], lun: path[1] },
+	    b"SATA    " => Self::Sata { port: path[0] },
+	    b"SCSI    " => Self::Scsi {
+		id: u16::from_le_bytes([path[0], path[1]]),
+		lun: u64_at(2),
+	    },
+	    b"USB     " => Self::Usb { serial: u64_at(0) },
+	    _ => Self::Other(*interface_type),
+	}
+    }
+}
+
+impl fmt::Display for Interface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Ata { device } =>
+		write!(f, "ATA device {}", device),
+	    Self::Atapi { device, lun } =>
+		write!(f, "ATAPI device {} LUN {}", device, lun),
+	    Self::Sata { port } =>
+		write!(f, "SATA port {}", port),
+	    Self::Scsi { id, lun } =>
+		write!(f, "SCSI ID {} LUN {}", id, lun),
+	    Self::Usb { serial } =>
+		write!(f, "USB serial {:#x}", serial),
+	    Self::Other(interface_type) =>
+		write!(f, "{}", ascii(interface_type)),
+	}
+    }
+}
+
+
+// Returns an ASCII type name without trailing spaces.
+fn ascii(bytes: &[u8]) -> &str {
+    core::str::from_utf8(bytes).unwrap_or("?").trim_end()
+}
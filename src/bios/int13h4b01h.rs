@@ -0,0 +1,178 @@
+/*!
+
+BIOS INT 13h AX=4B01h : Get Disk Emulation Status (El Torito)
+
+# Resource
+
+* ["El Torito" Bootable CD-ROM Format Specification Version 1.0](https://pdos.csail.mit.edu/6.828/2014/readings/boot-cdrom.pdf) (Phoenix Technologies and IBM, 1995-01-25)
+
+# Supplementary Resources
+
+* [El-Torito](https://wiki.osdev.org/El-Torito) (OS Dev)
+* [INT 13H](https://en.wikipedia.org/wiki/INT_13H) (Wikipedia)
+
+ */
+
+//
+// Resource:
+//	"El Torito" Bootable CD-ROM Format Specification Version 1.0
+//	(1995-01-25)
+//	https://pdos.csail.mit.edu/6.828/2014/readings/boot-cdrom.pdf
+//
+// Supplementary Resources:
+//	https://wiki.osdev.org/El-Torito
+//	https://en.wikipedia.org/wiki/INT_13H
+//
+
+use alloc::boxed::Box;
+use core::alloc::Allocator;
+
+use super::LmbiosRegs;
+use crate::assert_layout;
+use crate::println;
+use crate::x86::{FLAGS_CF, X86GetAddr};
+
+
+#[doc(hidden)]
+const DEBUG: bool = false;
+
+
+/// Calls BIOS INT 13h AX=4B01h (Get Disk Emulation Status).
+///
+/// It returns `None` if the drive was not booted from a CD-ROM.
+pub fn call<A20>(drive_id: u8, alloc20: A20)
+		 -> Option<Box<SpecificationPacket, A20>>
+where
+    A20: Allocator,
+{
+    // Allocate a buffer in 20-bit address space.
+    let buf = Box::new_in(SpecificationPacket::initial_value(), alloc20);
+
+    // Get the far pointer of the buffer.
+    let buf_fp = buf.get_far_ptr()?;
+
+    unsafe {
+	// INT 13h AH=4Bh AL=01h (Get Disk Emulation Status)
+	// IN
+	//   DL    = Drive ID
+	//   DS:SI = Address of Specification Packet
+	// OUT
+	//   CF    = 0 if Ok, 1 if Err
+	let mut regs = LmbiosRegs {
+	    fun: 0x13,			// INT 13h
+	    eax: 0x4b01,		// AH=4Bh AL=01h
+	    edx: drive_id as u32,	// Drive ID
+	    esi: buf_fp.offset as u32,	// Offset of Specification Packet
+	    ds: buf_fp.segment,		// Segment of Specification Packet
+	    ..Default::default()
+	};
+
+	if DEBUG {
+	    println!("IN:  EAX={:#x}, EDX={:#x}, DS:ESI={:#x}:{:#x}",
+		     regs.eax, regs.edx, regs.ds, regs.esi);
+	}
+
+	regs.call().ok()?;
+
+	if DEBUG {
+	    println!("OUT: EAX={:#x}, FLAGS={:#x}",
+		     regs.eax, regs.flags);
+	}
+
+	// Check the results.
+	// Note: On error, the carry flag (CF) is set.
+	if (regs.flags & FLAGS_CF) != 0 {
+	    return None;
+	}
+    }
+
+    // Return the result.
+    Some(buf)
+}
+
+
+/// El Torito Specification Packet
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct SpecificationPacket {
+    pub size: u8,			//00   : Packet Size = 0x13
+    pub media_type: u8,			//01   : Boot Media Type
+    pub drive_id: u8,			//02   : Drive Number
+    pub controller_index: u8,		//03   : CD-ROM Controller Index
+    pub lba: [u16; 2],			//04-07: LBA of Disk Image (u32)
+    pub device_spec: u16,		//08-09: Device Specification
+    pub buffer_segment: u16,		//0A-0B: Segment of User Buffer
+    pub load_segment: u16,		//0C-0D: Load Segment
+    pub sector_count: u16,		//0E-0F: Number of 512-byte Sectors
+    pub cylinder_count: u8,		//10   : Low Bits of Cylinder Count
+    pub sector_cylinder: u8,		//11   : Sector and High Bits of Cyl
+    pub head_count: u8,			//12   : Head Count
+    pub reserved: u8,			//13   : (padding)
+}
+
+assert_layout!(SpecificationPacket, 0x14, {
+    size: 0x00,
+    media_type: 0x01,
+    drive_id: 0x02,
+    controller_index: 0x03,
+    lba: 0x04,
+    device_spec: 0x08,
+    buffer_segment: 0x0a,
+    load_segment: 0x0c,
+    sector_count: 0x0e,
+    cylinder_count: 0x10,
+    sector_cylinder: 0x11,
+    head_count: 0x12,
+    reserved: 0x13,
+});
+
+impl X86GetAddr for SpecificationPacket {}
+
+impl SpecificationPacket {
+    // Boot Media Types (Bit 0-3)
+    pub const MEDIA_NO_EMULATION        : u8 = 0;
+    pub const MEDIA_FLOPPY_1200K        : u8 = 1;
+    pub const MEDIA_FLOPPY_1440K        : u8 = 2;
+    pub const MEDIA_FLOPPY_2880K        : u8 = 3;
+    pub const MEDIA_HARD_DISK           : u8 = 4;
+    pub const MEDIA_TYPE_MASK           : u8 = 0x0f;
+
+    fn initial_value() -> Self {
+	Self {
+	    size: 0x13,
+	    ..Default::default()
+	}
+    }
+
+    /// Returns true if the boot media is in no-emulation mode.
+    #[inline]
+    pub fn is_no_emulation(&self) -> bool {
+	(self.media_type & Self::MEDIA_TYPE_MASK) == Self::MEDIA_NO_EMULATION
+    }
+
+    #[inline]
+    pub fn lba(&self) -> u32 {
+	#[allow(unused_parens)]
+	((self.lba[0] as u32) |
+	 (self.lba[1] as u32) << 16)
+    }
+
+    pub fn print(&self) {
+	let type_name =
+	    match self.media_type & Self::MEDIA_TYPE_MASK {
+		Self::MEDIA_NO_EMULATION        => "No Emulation",
+		Self::MEDIA_FLOPPY_1200K        => "1.2MB Floppy",
+		Self::MEDIA_FLOPPY_1440K        => "1.44MB Floppy",
+		Self::MEDIA_FLOPPY_2880K        => "2.88MB Floppy",
+		Self::MEDIA_HARD_DISK           => "Hard Disk",
+		_ => "unknown",
+	    };
+
+	println!("SpecificationPacket:");
+	println!("  Media Type: {:#x} ({})", self.media_type, type_name);
+	println!("  Drive: {:#x}, Controller: {}",
+		 self.drive_id, self.controller_index);
+	println!("  LBA: {:#x}, Load Segment: {:#x}, Sector Count: {}",
+		 self.lba(), self.load_segment, self.sector_count);
+    }
+}
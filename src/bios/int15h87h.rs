@@ -0,0 +1,186 @@
+/*!
+
+BIOS INT 15h AH=87h : Move Extended Memory Block
+
+BIOS copies the block in its own Protected Mode without paging.
+Hence, it can copy data above 1MiB even where the page tables of the
+runtime do not map the addresses yet (e.g., before they are extended
+to cover the whole memory map).
+
+The addresses are 32-bit physical addresses.  Old BIOSes (for 80286)
+ignore the top 8 bits, i.e. they are limited to 16MiB.
+
+# Supplementary Resources
+
+* [BIOS interrupt call](https://en.wikipedia.org/wiki/BIOS_interrupt_call) (Wikipedia)
+
+ */
+
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/BIOS_interrupt_call
+//
+
+use core::cmp::min;
+
+use super::LmbiosRegs;
+use crate::assert_layout;
+use crate::x86::{FLAGS_CF, X86GetAddr};
+
+
+/// The maximum number of bytes copied by one BIOS call.
+/// (= 8000h words)
+pub const MAX_NBYTES: usize = 0x10000;
+
+// The access byte of the descriptors (present, DPL=0, read/write data)
+const ACCESS_DATA: u8 = 0x93;
+
+
+///
+/// Calls BIOS INT 15h AH=87h (Move Extended Memory Block) to copy
+/// `nbytes` bytes from `src_addr` to `dst_addr`.  Blocks larger than
+/// `MAX_NBYTES` are copied by multiple calls.
+///
+/// Returns false if `nbytes` is odd (BIOS copies words), or if BIOS
+/// fails (e.g., the A20 gate cannot be enabled).
+///
+/// # Safety
+///
+/// The destination must not be used by anyone else (e.g., the
+/// runtime itself or BIOS).
+///
+pub unsafe fn call(dst_addr: u32, src_addr: u32, nbytes: usize) -> bool {
+    if !nbytes.is_multiple_of(2)
+	|| (src_addr as u64) + (nbytes as u64) > (1 << 32)
+	|| (dst_addr as u64) + (nbytes as u64) > (1 << 32)
+    {
+	return false;
+    }
+
+    let mut offset = 0;
+    while offset < nbytes {
+	let cur_nbytes = min(nbytes - offset, MAX_NBYTES);
+	if !move_block(dst_addr + offset as u32, src_addr + offset as u32,
+		       cur_nbytes) {
+	    return false;
+	}
+	offset += cur_nbytes;
+    }
+
+    true
+}
+
+///
+/// Copies the data to `dst_addr` by BIOS INT 15h AH=87h (cf. [`call`]).
+/// The data must be below 4GiB.
+///
+/// # Safety
+///
+/// The destination must not be used by anyone else.
+///
+pub unsafe fn copy_from_slice(dst_addr: u32, data: &[u8]) -> bool {
+    match u32::try_from(data.as_ptr() as usize) {
+	Ok(src_addr) => call(dst_addr, src_addr, data.len()),
+	Err(_) => false,
+    }
+}
+
+// Copies a block of up to `MAX_NBYTES` bytes.
+unsafe fn move_block(dst_addr: u32, src_addr: u32, nbytes: usize) -> bool {
+    // Allocate the GDT on the stack (in 20-bit address space).
+    let gdt = BlockMoveGdt {
+	src: SegmentDescriptor::data(src_addr, nbytes),
+	dst: SegmentDescriptor::data(dst_addr, nbytes),
+	..Default::default()
+    };
+
+    // Get the far pointer of the GDT.
+    let Some(gdt_fp) = gdt.get_far_ptr() else {
+	return false;
+    };
+
+    // INT 15h AH=87h (Move Extended Memory Block)
+    // IN
+    //   CX    = Number of Words to Copy (8000h max)
+    //   ES:SI = GDT Address
+    // OUT
+    //   CF    = 0 if Ok, 1 if Err
+    //   AH    = Status
+    //           (00h Ok, 01h Parity Error, 02h Exception,
+    //            03h A20 Gate Failed, 86h Unsupported)
+    let mut regs = LmbiosRegs {
+	fun: 0x15,
+	eax: 0x8700,
+	ecx: (nbytes / 2) as u32,
+	esi: gdt_fp.offset as u32,
+	es: gdt_fp.segment,
+	..Default::default()
+    };
+
+    if regs.call().is_err() {
+	return false;
+    }
+
+    // Check the result.
+    (regs.flags & FLAGS_CF) == 0 && (regs.eax & 0xff00) == 0
+}
+
+
+/// Global Descriptor Table passed to INT 15h AH=87h
+#[repr(C)]
+#[derive(Default)]
+struct BlockMoveGdt {
+    dummy: SegmentDescriptor,		//00-07: (zero)
+    gdt: SegmentDescriptor,		//08-0F: (zero, used by BIOS)
+    src: SegmentDescriptor,		//10-17: Source Segment
+    dst: SegmentDescriptor,		//18-1F: Destination Segment
+    cs: SegmentDescriptor,		//20-27: (zero, used by BIOS)
+    ss: SegmentDescriptor,		//28-2F: (zero, used by BIOS)
+}
+
+assert_layout!(BlockMoveGdt, 0x30, {
+    dummy: 0x00,
+    gdt: 0x08,
+    src: 0x10,
+    dst: 0x18,
+    cs: 0x20,
+    ss: 0x28,
+});
+
+impl X86GetAddr for BlockMoveGdt {}
+
+
+/// Segment Descriptor
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct SegmentDescriptor {
+    limit: u16,		//00-01: Segment Limit (bits 0-15)
+    base_low: u16,	//02-03: Base Address (bits 0-15)
+    base_mid: u8,	//04   : Base Address (bits 16-23)
+    access: u8,		//05   : Access Rights
+    limit_high: u8,	//06   : Flags and Limit (bits 16-19) = 0
+    base_high: u8,	//07   : Base Address (bits 24-31)
+}
+
+assert_layout!(SegmentDescriptor, 0x08, {
+    limit: 0x00,
+    base_low: 0x02,
+    base_mid: 0x04,
+    access: 0x05,
+    limit_high: 0x06,
+    base_high: 0x07,
+});
+
+impl SegmentDescriptor {
+    // Returns a descriptor of a data segment of `nbytes` bytes.
+    fn data(base: u32, nbytes: usize) -> Self {
+	Self {
+	    limit: (nbytes - 1) as u16,
+	    base_low: base as u16,
+	    base_mid: (base >> 16) as u8,
+	    access: ACCESS_DATA,
+	    limit_high: 0,
+	    base_high: (base >> 24) as u8,
+	}
+    }
+}
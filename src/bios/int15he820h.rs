@@ -19,9 +19,11 @@ BIOS INT 15h AX=E820h : Query System Address Map
 
 use alloc::vec::Vec;
 use core::alloc::Allocator;
+use core::fmt;
 use core::mem::{MaybeUninit, size_of};
 
 use super::LmbiosRegs;
+use crate::assert_layout;
 use crate::println;
 use crate::mu::PushBulk;
 use crate::x86::{FLAGS_CF, X86GetAddr};
@@ -48,10 +50,8 @@ where
 
     loop {
 	unsafe {
-	    vec.push_bulk(1, | buf | {
-		// Fill the AddrRange buffer with the initial value.
-		buf[0] = AddrRange::initial_value();
-
+	    // Note: The AddrRange buffer is filled with the initial value.
+	    vec.push_bulk_init(1, AddrRange::initial_value(), | buf | {
 		// Get the far pointer of the buffer.
 		let buf_fp = buf.get_far_ptr().ok_or(())?;
 
@@ -85,7 +85,7 @@ where
 			     regs.edx, regs.es, regs.edi);
 		}
 
-		regs.call();
+		regs.call().map_err(drop)?;
 
 		if DEBUG {
 		    println!("OUT: EAX={:#x}, EBX={:#x}, ECX={:#x}, \
@@ -135,7 +135,12 @@ pub struct AddrRange {
     pub attr: u32,	//14-17: Extended Attributes (ACPI 3.0)
 }
 
-const _: () = assert!(size_of::<AddrRange>() == 0x18);
+assert_layout!(AddrRange, 0x18, {
+    addr: 0x00,
+    length: 0x08,
+    atype: 0x10,
+    attr: 0x14,
+});
 
 impl AddrRange {
     // Address Range Types
@@ -164,22 +169,66 @@ impl AddrRange {
 	}
     }
 
-    pub fn print(&self) {
-	let type_name =
-	    match self.atype {
-		Self::TYPE_USABLE	=> "Usable",
-		Self::TYPE_RESERVED	=> "Reserved",
-		Self::TYPE_ACPI		=> "ACPI Reclaimable",
-		Self::TYPE_NVS		=> "ACPI Non-Volatile Storage",
-		Self::TYPE_UNUSABLE	=> "Containing Bad Memory",
-		Self::TYPE_DISABLED	=> "Disabled",
-		Self::TYPE_PERSISTENT	=> "Persistent Memory",
-		_ => "unknown",
-	    };
+    /// Returns the name of the address range type.
+    pub fn type_name(&self) -> &'static str {
+	match self.atype {
+	    Self::TYPE_USABLE		=> "Usable",
+	    Self::TYPE_RESERVED		=> "Reserved",
+	    Self::TYPE_ACPI		=> "ACPI Reclaimable",
+	    Self::TYPE_NVS		=> "ACPI Non-Volatile Storage",
+	    Self::TYPE_UNUSABLE		=> "Containing Bad Memory",
+	    Self::TYPE_DISABLED		=> "Disabled",
+	    Self::TYPE_PERSISTENT	=> "Persistent Memory",
+	    _ => "unknown",
+	}
+    }
 
+    pub fn print(&self) {
 	println!("addr={:#x}, length={:#x}, type={} ({}), attr={:#x}",
-		 self.addr, self.length, self.atype, type_name, self.attr);
+		 self.addr, self.length, self.atype, self.type_name(),
+		 self.attr);
     }
 }
 
 impl X86GetAddr for AddrRange {}
+
+
+///
+/// A memory map printed as a table with aligned columns.
+///
+/// Each row shows the start address, the end address (inclusive),
+/// the size in human units and the type of an address range.
+///
+pub struct MemoryMap<'a>(pub &'a [AddrRange]);
+
+impl fmt::Display for MemoryMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{:<18} {:<18} {:>12}  Type\r\n", "Start", "End", "Size")?;
+	for entry in self.0 {
+	    let end = entry.addr.saturating_add(entry.length).saturating_sub(1);
+	    let (whole, tenth, unit) = human_size(entry.length);
+	    write!(f, "{:#018x} {:#018x} {:>6}.{} {:<3}  {}\r\n",
+		   entry.addr, end, whole, tenth, unit, entry.type_name())?;
+	}
+	Ok(())
+    }
+}
+
+/// Prints the memory map as a table.
+pub fn print_table(addr_ranges: &[AddrRange]) {
+    crate::print!("{}", MemoryMap(addr_ranges));
+}
+
+// Returns a size in human units: (whole part, first decimal, unit).
+fn human_size(bytes: u64) -> (u64, u64, &'static str) {
+    const UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    // Find the largest unit in which the whole part is not zero.
+    let mut unit = 0;
+    while unit + 1 < UNITS.len() && (bytes >> (10 * (unit + 1))) != 0 {
+	unit += 1;
+    }
+
+    let tenths = (((bytes as u128) * 10) >> (10 * unit)) as u64;
+    (tenths / 10, tenths % 10, UNITS[unit])
+}
@@ -0,0 +1,121 @@
+/*!
+
+BIOS INT 16h AH=00h : Read Key Press
+
+Waits for a keystroke, then removes it from the keyboard buffer of
+BIOS.  Use [`int16h01h`](super::int16h01h) to poll without waiting.
+
+Note: BIOS waits with interrupts enabled (so that the keyboard IRQ is
+handled).  The PS/2 keyboard driver (`input::ps2`) reads the keyboard
+controller directly, so both should not be used at the same time.
+
+```ignore
+if let Some(keystroke) = bios::int16h00h::call() {
+    println!("{}", keystroke);
+}
+```
+
+# Supplementary Resources
+
+* [INT 16H](https://en.wikipedia.org/wiki/INT_16H) (Wikipedia)
+* [Keyboard scan codes](https://www.stanislavs.org/helppc/scan_codes.html) (HelpPC)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://en.wikipedia.org/wiki/INT_16H
+//	https://www.stanislavs.org/helppc/scan_codes.html
+//
+
+use core::fmt;
+
+use super::{BiosCall, CallRegs, CallResult};
+use crate::input::Key;
+
+
+/// A keystroke returned by BIOS.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyStroke {
+    pub scan_code: u8,	// BIOS Scan Code
+    pub ascii: u8,	// ASCII Character (0 or 0xE0 if an extended key)
+}
+
+impl KeyStroke {
+    // Extended keys have no ASCII character.
+    const EXTENDED: [u8; 2] = [0x00, 0xe0];
+
+    // Converts the output registers (AH = Scan Code, AL = ASCII).
+    pub(super) fn from_ax(ax: u16) -> Self {
+	Self { scan_code: (ax >> 8) as u8, ascii: ax as u8 }
+    }
+
+    /// Translates the keystroke into a key (None if not supported).
+    pub fn key(&self) -> Option<Key> {
+	if Self::EXTENDED.contains(&self.ascii) {
+	    return match self.scan_code {
+		0x47 => Some(Key::Home),
+		0x48 => Some(Key::Up),
+		0x4b => Some(Key::Left),
+		0x4d => Some(Key::Right),
+		0x4f => Some(Key::End),
+		0x50 => Some(Key::Down),
+		0x53 => Some(Key::Delete),
+		_ => None,
+	    };
+	}
+
+	match self.ascii {
+	    b'\r' => Some(Key::Enter),
+	    b'\x08' => Some(Key::Backspace),
+	    b'\t' => Some(Key::Tab),
+	    b'\x1b' => Some(Key::Escape),
+	    0x20 ..= 0x7e => Some(Key::Char(self.ascii as char)),
+	    _ => None,
+	}
+    }
+}
+
+impl fmt::Display for KeyStroke {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "scan code {:#04x}, ", self.scan_code)?;
+	match self.ascii {
+	    0x20 ..= 0x7e => write!(f, "'{}'", self.ascii as char),
+	    _ => write!(f, "{:#04x}", self.ascii),
+	}
+    }
+}
+
+
+/// A request of BIOS INT 16h AH=00h (Read Key Press).
+pub struct ReadKeyPress;
+
+unsafe impl BiosCall for ReadKeyPress {
+    type Output = Option<KeyStroke>;
+
+    fn regs(&self) -> CallRegs {
+	// INT 16h AH=00h (Read Key Press)
+	CallRegs::int(0x16)
+	    .ah(0x00)
+    }
+
+    fn output(&self, result: &CallResult) -> Option<KeyStroke> {
+	// OUT
+	//   AH = Scan Code
+	//   AL = ASCII Character
+	if !result.is_supported() {
+	    return None;
+	}
+
+	Some(KeyStroke::from_ax(result.ax()))
+    }
+}
+
+
+///
+/// Calls BIOS INT 16h AH=00h (Read Key Press), which blocks until a
+/// key is pressed.  Returns None if BIOS is not available.
+///
+pub fn call() -> Option<KeyStroke> {
+    super::call(&ReadKeyPress)
+}
@@ -0,0 +1,64 @@
+/*!
+
+BIOS INT 16h AH=01h : Get the State of the Keyboard Buffer
+
+Returns the next keystroke without removing it from the keyboard
+buffer of BIOS (non-blocking).  Then [`int16h00h`](super::int16h00h)
+removes it.
+
+```ignore
+while bios::int16h01h::call().is_none() {
+    // Do something else.
+}
+let keystroke = bios::int16h00h::call();
+```
+
+# Supplementary Resource
+
+* [INT 16H](https://en.wikipedia.org/wiki/INT_16H) (Wikipedia)
+
+ */
+
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/INT_16H
+//
+
+use super::{BiosCall, CallRegs, CallResult};
+use super::int16h00h::KeyStroke;
+
+
+/// A request of BIOS INT 16h AH=01h (Get the State of the Keyboard
+/// Buffer).
+pub struct PeekKeyPress;
+
+unsafe impl BiosCall for PeekKeyPress {
+    type Output = Option<KeyStroke>;
+
+    fn regs(&self) -> CallRegs {
+	// INT 16h AH=01h (Get the State of the Keyboard Buffer)
+	CallRegs::int(0x16)
+	    .ah(0x01)
+    }
+
+    fn output(&self, result: &CallResult) -> Option<KeyStroke> {
+	// OUT
+	//   ZF = Set if no keystroke is available
+	//   AH = Scan Code (if available)
+	//   AL = ASCII Character (if available)
+	if !result.is_supported() || result.zero() {
+	    return None;
+	}
+
+	Some(KeyStroke::from_ax(result.ax()))
+    }
+}
+
+
+///
+/// Calls BIOS INT 16h AH=01h (Get the State of the Keyboard Buffer).
+/// Returns the next keystroke (if any) without removing it.
+///
+pub fn call() -> Option<KeyStroke> {
+    super::call(&PeekKeyPress)
+}
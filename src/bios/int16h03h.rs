@@ -0,0 +1,64 @@
+/*!
+
+BIOS INT 16h AH=03h : Set Typematic Rate and Delay
+
+# Supplementary Resources
+
+* [INT 16H](https://en.wikipedia.org/wiki/INT_16H) (Wikipedia)
+* [PS/2 Keyboard](https://wiki.osdev.org/PS/2_Keyboard) (OS Dev)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://en.wikipedia.org/wiki/INT_16H
+//	https://wiki.osdev.org/PS/2_Keyboard
+//
+
+use super::{BiosCall, CallRegs, CallResult};
+
+
+/// Delay (BH): 250 milliseconds before repeating
+pub const DELAY_250MS: u8 = 0;
+/// Delay (BH): 500 milliseconds before repeating
+pub const DELAY_500MS: u8 = 1;
+/// Delay (BH): 750 milliseconds before repeating
+pub const DELAY_750MS: u8 = 2;
+/// Delay (BH): 1000 milliseconds before repeating
+pub const DELAY_1000MS: u8 = 3;
+
+/// Rate (BL): The fastest (30 characters per second)
+pub const RATE_FASTEST: u8 = 0x00;
+/// Rate (BL): 10 characters per second (the default of BIOS)
+pub const RATE_10CPS: u8 = 0x0c;
+/// Rate (BL): The slowest (2 characters per second)
+pub const RATE_SLOWEST: u8 = 0x1f;
+
+
+/// A request of BIOS INT 16h AX=0305h (Set Typematic Rate and Delay).
+pub struct SetTypematic {
+    pub delay: u8,	// Delay before repeating (0-3)
+    pub rate: u8,	// Repeat Rate (0x00-0x1F, lower is faster)
+}
+
+unsafe impl BiosCall for SetTypematic {
+    type Output = ();
+
+    fn regs(&self) -> CallRegs {
+	// INT 16h AX=0305h (Set Typematic Rate and Delay)
+	// IN
+	//   BH = Delay
+	//   BL = Repeat Rate
+	CallRegs::int(0x16)
+	    .ax(0x0305)
+	    .bh(self.delay & 0x03)
+	    .bl(self.rate & 0x1f)
+    }
+
+    fn output(&self, _result: &CallResult) {}
+}
+
+/// Calls BIOS INT 16h AX=0305h (Set Typematic Rate and Delay).
+pub fn call(delay: u8, rate: u8) {
+    super::call(&SetTypematic { delay, rate });
+}
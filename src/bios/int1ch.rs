@@ -0,0 +1,169 @@
+/*!
+
+BIOS INT 1Ch : System Timer Tick Hook
+
+`install` places a tiny Real Mode stub in 16-bit address space (and
+registers it in the region manager), then hooks the INT 1Ch vector
+with it.  The stub increments a counter shared with Long Mode, then
+chains to the previous handler.  `ticks` reads the counter.
+
+The system timer ticks about 18.2 times a second (`TICK_PERIOD`).
+
+Note: Because no interrupt is delivered in Long Mode (there is no
+IDT), the counter advances only while a Real Mode function is called
+with interrupts enabled (e.g., while BIOS waits for a key or a disk).
+Hence, it measures the time spent in BIOS rather than the wall clock.
+
+```ignore
+bios::int1ch::install(&ALLOC_UNDER16)?;
+bios::int16h00h::call();
+println!("{} ticks", bios::int1ch::ticks().unwrap());
+```
+
+# Supplementary Resources
+
+* [Interrupt Vector Table](https://wiki.osdev.org/Interrupt_Vector_Table) (OS Dev)
+* [BIOS interrupt call](https://en.wikipedia.org/wiki/BIOS_interrupt_call) (Wikipedia)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://wiki.osdev.org/Interrupt_Vector_Table
+//	https://en.wikipedia.org/wiki/BIOS_interrupt_call
+//
+
+use core::alloc::{Allocator, Layout};
+use core::fmt;
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+
+use crate::man_region::{self, RegionError};
+
+
+/// The period of the system timer tick (65536 / 1193182 Hz).
+pub const TICK_PERIOD: Duration = Duration::from_micros(54_925);
+
+// The address of the INT 1Ch vector in the IVT.
+const IVT_INT1CH: usize = 0x1c * 4;
+
+//
+// The Real Mode stub (CS = its address / 16, IP = 0)
+//
+//	00: 2E 66 FF 06 0C 00	inc	dword ptr cs:[000Ch]
+//	06: 2E FF 2E 10 00	jmp	far ptr cs:[0010h]
+//	0B: 90			nop
+//	0C: (4 bytes)		Counter
+//	10: (4 bytes)		Previous Vector (Offset, Segment)
+//
+const STUB_CODE: [u8; 12] = [
+    0x2e, 0x66, 0xff, 0x06, 0x0c, 0x00,
+    0x2e, 0xff, 0x2e, 0x10, 0x00,
+    0x90,
+];
+const STUB_COUNTER: usize = 0x0c;
+const STUB_PREV_VECTOR: usize = 0x10;
+const STUB_SIZE: usize = 0x14;
+
+// The address of the installed stub (0 if not installed).
+static STUB_ADDR: AtomicUsize = AtomicUsize::new(0);
+
+
+/// Errors returned by [`install`].
+#[derive(Clone, Copy, Debug)]
+pub enum TickHookError {
+    /// The stub could not be allocated in 16-bit address space.
+    OutOfMemory,
+    /// The stub could not be registered in the region manager.
+    Region(RegionError),
+}
+
+impl fmt::Display for TickHookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::OutOfMemory =>
+		write!(f, "INT 1Ch: Failed to allocate the stub"),
+	    Self::Region(err) =>
+		write!(f, "INT 1Ch: Failed to register the stub ({:?})", err),
+	}
+    }
+}
+
+
+///
+/// Installs the stub on the INT 1Ch vector (if not installed yet).
+///
+/// The stub is allocated by `alloc16`, which must allocate memory in
+/// 16-bit address space (e.g., `ALLOC_UNDER16`).  It is never freed.
+///
+pub fn install<A16>(alloc16: A16) -> Result<(), TickHookError>
+where
+    A16: Allocator,
+{
+    if is_installed() {
+	return Ok(());
+    }
+
+    // The stub must be 16-byte aligned so that its offset is 0.
+    let layout = Layout::from_size_align(STUB_SIZE, 16)
+	.map_err(|_| TickHookError::OutOfMemory)?;
+    let stub = alloc16.allocate(layout)
+	.map_err(|_| TickHookError::OutOfMemory)?
+	.cast::<u8>().as_ptr();
+    let addr = stub as usize;
+    if addr + STUB_SIZE > 0x10000 {
+	return Err(TickHookError::OutOfMemory);
+    }
+
+    // Note: If the stub is in a heap area registered as a whole
+    //       (cf. man_memory::reserve_runtime_regions), it is already
+    //       owned by the runtime.
+    match man_region::register("INT 1Ch Stub", addr, STUB_SIZE) {
+	Ok(()) => (),
+	Err(RegionError::Overlap(region))
+	    if region.start <= addr && addr + STUB_SIZE <= region.end => (),
+	Err(err) => return Err(TickHookError::Region(err)),
+    }
+
+    unsafe {
+	let ivt_entry = IVT_INT1CH as *mut u32;
+	let prev_vector = read_volatile(ivt_entry);
+
+	for (i, byte) in STUB_CODE.iter().enumerate() {
+	    write_volatile(stub.add(i), *byte);
+	}
+	write_volatile(stub.add(STUB_COUNTER) as *mut u32, 0);
+	write_volatile(stub.add(STUB_PREV_VECTOR) as *mut u32, prev_vector);
+
+	// Hook the vector (Offset = 0, Segment = addr / 16).
+	// Note: Interrupts are never delivered in Long Mode.  Hence,
+	//       the vector is not used while it is being written.
+	write_volatile(ivt_entry, ((addr >> 4) as u32) << 16);
+    }
+
+    STUB_ADDR.store(addr, Ordering::Release);
+    Ok(())
+}
+
+/// Returns true if the stub is installed.
+pub fn is_installed() -> bool {
+    STUB_ADDR.load(Ordering::Acquire) != 0
+}
+
+/// Returns the number of ticks counted by the stub (if installed).
+pub fn ticks() -> Option<u32> {
+    let addr = STUB_ADDR.load(Ordering::Acquire);
+    if addr == 0 {
+	return None;
+    }
+
+    unsafe {
+	Some(read_volatile((addr + STUB_COUNTER) as *const u32))
+    }
+}
+
+/// Converts a number of ticks into a duration.
+pub fn ticks_to_duration(ticks: u32) -> Duration {
+    TICK_PERIOD * ticks
+}
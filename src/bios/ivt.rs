@@ -0,0 +1,251 @@
+/*!
+
+Inspects the Real Mode Interrupt Vector Table (IVT).
+
+The IVT at address 0 holds 256 far pointers (offset, then segment)
+to the handlers of `INT 00h` to `INT FFh`.  [`entry`] reads a vector
+and classifies where its handler lives (cf. [`Owner`]):
+
+* the system BIOS (F000:xxxx),
+* an option ROM (C000:0000 to EFFF:FFFF), e.g. a disk controller or
+  a PXE ROM,
+* the top of base memory hidden from the BIOS Data Area (BDA), where
+  the EBDA and PXE stacks (UNDI) live,
+* a region owned by the runtime (e.g. the INT 1Ch stub),
+* or elsewhere in base memory (e.g. a resident program).
+
+[`hooks`] reports the disk and time services (`INT 13h` and `INT 1Ah`)
+whose handlers are not in the system BIOS.  Such a hook may emulate a
+drive (e.g. a CD-ROM in floppy emulation, or iSCSI/HTTP boot), so that
+bypassing BIOS and accessing the hardware directly may see different
+contents.
+
+```ignore
+for entry in bios::ivt::hooks() {
+    println!("Hooked: {}", entry);
+}
+```
+
+# Supplementary Resources
+
+* [Interrupt Vector Table](https://wiki.osdev.org/Interrupt_Vector_Table) (OS Dev)
+* [Memory Map (x86)](https://wiki.osdev.org/Memory_Map_(x86)) (OS Dev)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://wiki.osdev.org/Interrupt_Vector_Table
+//	https://wiki.osdev.org/Memory_Map_(x86)
+//
+
+use core::fmt;
+use core::ptr::read_volatile;
+
+use crate::man_region;
+use crate::println;
+
+
+/// The number of vectors in the IVT.
+pub const VECTOR_COUNT: usize = 256;
+
+/// The vectors of the services whose hooks are reported by [`hooks`].
+pub const WATCHED_VECTORS: [u8; 2] = [
+    0x13,	// Disk Services
+    0x1a,	// Time Services (and PCI BIOS, TCG BIOS)
+];
+
+// The IVT and the BIOS Data Area end at 0x500.
+const IVT_BDA_END: usize = 0x500;
+
+// The size of base memory in KiB in the BDA.
+const BDA_BASE_MEMORY_KIB: usize = 0x413;
+
+// Address ranges of the upper memory area
+const VIDEO_MEMORY_START: usize = 0xa0000;
+const OPTION_ROM_START: usize = 0xc0000;
+const SYSTEM_BIOS_START: usize = 0xf0000;
+const REAL_MODE_END: usize = 0x100000;
+
+
+/// A Real Mode far pointer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FarPtr {
+    pub segment: u16,
+    pub offset: u16,
+}
+
+impl FarPtr {
+    /// Returns the linear address (segment * 16 + offset).
+    pub fn linear(&self) -> usize {
+	((self.segment as usize) << 4) + self.offset as usize
+    }
+
+    /// Returns true if it is 0000:0000.
+    pub fn is_null(&self) -> bool {
+	self.segment == 0 && self.offset == 0
+    }
+}
+
+impl fmt::Display for FarPtr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{:04x}:{:04x}", self.segment, self.offset)
+    }
+}
+
+
+/// Owners of interrupt handlers (by the address of the handler).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Owner {
+    /// The system BIOS (F0000h - FFFFFh)
+    SystemBios,
+    /// An option ROM (C0000h - EFFFFh)
+    OptionRom,
+    /// The top of base memory hidden from the BDA (EBDA, PXE stacks)
+    HiddenBaseMemory,
+    /// A region owned by the runtime (the name of the region)
+    Runtime(&'static str),
+    /// Elsewhere in base memory (e.g. a resident program)
+    BaseMemory,
+    /// No handler (0000:0000)
+    Null,
+    /// An invalid address (the IVT, the BDA, video memory or the HMA)
+    Invalid,
+}
+
+impl Owner {
+    // Classifies the linear address of a handler.
+    fn of(addr: usize) -> Self {
+	let base_memory_end = unsafe {
+	    read_volatile(BDA_BASE_MEMORY_KIB as *const u16) as usize * 1024
+	};
+
+	match addr {
+	    0 => Self::Null,
+	    _ if addr < IVT_BDA_END => Self::Invalid,
+	    SYSTEM_BIOS_START .. REAL_MODE_END => Self::SystemBios,
+	    OPTION_ROM_START .. SYSTEM_BIOS_START => Self::OptionRom,
+	    VIDEO_MEMORY_START .. => Self::Invalid,
+	    _ if addr >= base_memory_end => Self::HiddenBaseMemory,
+	    _ => match man_region::find(addr) {
+		Some(region) => Self::Runtime(region.name),
+		None => Self::BaseMemory,
+	    },
+	}
+    }
+}
+
+impl fmt::Display for Owner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::SystemBios => write!(f, "System BIOS"),
+	    Self::OptionRom => write!(f, "Option ROM"),
+	    Self::HiddenBaseMemory => write!(f, "Hidden Base Memory"),
+	    Self::Runtime(name) => write!(f, "Runtime ({})", name),
+	    Self::BaseMemory => write!(f, "Base Memory"),
+	    Self::Null => write!(f, "Null"),
+	    Self::Invalid => write!(f, "Invalid"),
+	}
+    }
+}
+
+
+/// An entry of the IVT.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IvtEntry {
+    pub vector: u8,
+    pub handler: FarPtr,
+    pub owner: Owner,
+}
+
+impl IvtEntry {
+    /// Returns true if the handler is at a valid address.
+    pub fn is_valid(&self) -> bool {
+	!matches!(self.owner, Owner::Null | Owner::Invalid)
+    }
+
+    /// Returns true if the handler is not in the system BIOS.
+    pub fn is_hooked(&self) -> bool {
+	self.owner != Owner::SystemBios
+    }
+}
+
+impl fmt::Display for IvtEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "INT {:02X}h: {} ({:#07x}) {}", self.vector,
+	       self.handler, self.handler.linear(), self.owner)
+    }
+}
+
+
+/// Errors returned by [`validate`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IvtError {
+    /// The vector has no handler.
+    Null { vector: u8 },
+    /// The handler is at an invalid address.
+    Invalid { vector: u8, handler: FarPtr },
+}
+
+impl fmt::Display for IvtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Null { vector } =>
+		write!(f, "IVT: No handler of INT {:02X}h", vector),
+	    Self::Invalid { vector, handler } =>
+		write!(f, "IVT: Invalid handler of INT {:02X}h at {}",
+		       vector, handler),
+	}
+    }
+}
+
+
+/// Reads the entry of the vector.
+pub fn entry(vector: u8) -> IvtEntry {
+    let ptr = (vector as usize * 4) as *const u16;
+    let handler = unsafe {
+	FarPtr {
+	    offset: read_volatile(ptr),
+	    segment: read_volatile(ptr.add(1)),
+	}
+    };
+
+    IvtEntry { vector, handler, owner: Owner::of(handler.linear()) }
+}
+
+/// Returns an iterator over all entries of the IVT.
+pub fn entries() -> impl Iterator<Item = IvtEntry> {
+    (0 ..= u8::MAX).map(entry)
+}
+
+/// Reads the entry of the vector, and checks that it has a handler.
+pub fn validate(vector: u8) -> Result<IvtEntry, IvtError> {
+    let entry = entry(vector);
+    match entry.owner {
+	Owner::Null => Err(IvtError::Null { vector }),
+	Owner::Invalid => Err(IvtError::Invalid {
+	    vector,
+	    handler: entry.handler,
+	}),
+	_ => Ok(entry),
+    }
+}
+
+///
+/// Returns an iterator over the entries of the watched vectors
+/// (`WATCHED_VECTORS`) whose handlers are not in the system BIOS.
+///
+pub fn hooks() -> impl Iterator<Item = IvtEntry> {
+    WATCHED_VECTORS.into_iter().map(entry).filter(IvtEntry::is_hooked)
+}
+
+///
+/// Prints the entries of the CPU exceptions, the hardware interrupts
+/// (IRQ 0-15) and the BIOS services (INT 00h-1Fh and 70h-77h).
+///
+pub fn print() {
+    println!("Interrupt Vector Table:");
+    for entry in (0x00 ..= 0x1f).chain(0x70 ..= 0x77).map(entry) {
+	println!("  {}", entry);
+    }
+}
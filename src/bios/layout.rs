@@ -0,0 +1,37 @@
+//
+// Layout Assertions - Compile-time checks of the layouts of structures
+// exchanged with BIOS.
+//
+// The BIOS reads and writes such a structure at fixed offsets.  Hence,
+// not only its size but also the offset of every field is asserted, so
+// that a field reordered or resized by mistake breaks the build instead
+// of BIOS calls.
+//
+
+///
+/// Asserts the size of a `#[repr(C)]` structure and the offsets of its
+/// fields at compile time.
+///
+/// ```ignore
+/// assert_layout!(AddrRange, 0x18, {
+///     addr: 0x00,
+///     length: 0x08,
+///     atype: 0x10,
+///     attr: 0x14,
+/// });
+/// ```
+///
+#[macro_export]
+macro_rules! assert_layout {
+    ($type:ty, $size:expr, { $($field:ident : $offset:expr),* $(,)? }) => {
+	const _: () = {
+	    assert!(::core::mem::size_of::<$type>() == $size,
+		    concat!("size of ", stringify!($type)));
+	    $(
+		assert!(::core::mem::offset_of!($type, $field) == $offset,
+			concat!("offset of ", stringify!($type), "::",
+				stringify!($field)));
+	    )*
+	};
+    };
+}
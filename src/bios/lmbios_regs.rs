@@ -1,8 +1,10 @@
 use core::ops::Deref;
-use core::mem::size_of;
 
 use super::ffi;
+use super::lockout::{self, BiosUnavailable};
+use crate::assert_layout;
 use crate::mu::MuMutex;
+use crate::x86::FLAGS_CF;
 
 
 //
@@ -30,13 +32,49 @@ pub struct LmbiosRegs {	// Offset:
     pub es: u16,	// 22-23 : ES			(IN/OUT)
 }
 
-const _: () = assert!(size_of::<LmbiosRegs>() == 0x24);
+assert_layout!(LmbiosRegs, 0x24, {
+    fun: 0x00,
+    flags: 0x02,
+    eax: 0x04,
+    ebx: 0x08,
+    ecx: 0x0c,
+    edx: 0x10,
+    esi: 0x14,
+    edi: 0x18,
+    ebp: 0x1c,
+    ds: 0x20,
+    es: 0x22,
+});
 
 
 impl LmbiosRegs {
-    pub unsafe fn call(&mut self) -> u16 {
-	let _guard = BIOS_TICKET.lock();
-	ffi::lmbios_call(self)
+    ///
+    /// Calls the BIOS function, then returns the status of lmbios1.
+    ///
+    /// If BIOS calls are locked out (cf. `bios::lockout`), it returns
+    /// `Err(BiosUnavailable)` without calling it (CF is set, too).
+    ///
+    pub unsafe fn call(&mut self) -> Result<u16, BiosUnavailable> {
+	if let Err(err) = lockout::check(self.fun, (self.eax >> 8) as u8) {
+	    self.flags |= FLAGS_CF;
+	    return Err(err);
+	}
+
+	// Check the stack canary before and after every BIOS call.
+	// Note: It is checked without the ticket because the panic
+	//       handler may call BIOS.
+	#[cfg(feature = "debug-stack")]
+	super::check_stack_canary();
+
+	let result = {
+	    let _guard = BIOS_TICKET.lock();
+	    ffi::lmbios_call(self)
+	};
+
+	#[cfg(feature = "debug-stack")]
+	super::check_stack_canary();
+
+	Ok(result)
     }
 }
 
@@ -0,0 +1,99 @@
+//
+// Lockout - Stops calling BIOS once native drivers take over.
+//
+// BIOS functions run in Real Mode with the IVT, the PIC and the
+// hardware in the state that BIOS set up.  Once native drivers change
+// it (e.g. page tables, the APIC or an own IDT), BIOS may hang or
+// corrupt memory.  After `lockout` is called, `LmbiosRegs::call`
+// refuses to switch modes and returns `Err(BiosUnavailable)`, except
+// for the calls registered by `allow_after_lockout` (which must have
+// been reviewed to be safe in that state).
+//
+
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+
+/// The maximum number of calls allowed after the lockout.
+pub const MAX_ALLOWED_CALLS: usize = 8;
+
+// An entry of the allowlist: VALID | ANY_AH (if AH is not given)
+// | function number << 8 | AH
+const ENTRY_VALID: u32 = 1 << 31;
+const ENTRY_ANY_AH: u32 = 1 << 30;
+
+static LOCKED_OUT: AtomicBool = AtomicBool::new(false);
+
+static ALLOWLIST: [AtomicU32; MAX_ALLOWED_CALLS] =
+    [const { AtomicU32::new(0) }; MAX_ALLOWED_CALLS];
+
+
+/// Returned by `LmbiosRegs::call` if the call is locked out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BiosUnavailable {
+    pub fun: u16,		// Function Number (e.g. 0x13 for INT 13h)
+    pub ah: u8,			// AH
+}
+
+impl fmt::Display for BiosUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	if self.fun <= 0xff {
+	    write!(f, "BIOS: INT {:02X}h AH={:02X}h is locked out",
+		   self.fun, self.ah)
+	} else {
+	    write!(f, "BIOS: Subroutine {:#06x} is locked out", self.fun)
+	}
+    }
+}
+
+
+///
+/// Locks out BIOS calls.  It cannot be undone.  Call it once native
+/// drivers (e.g. paging changes, the APIC or an own IDT) are active.
+///
+pub fn lockout() {
+    LOCKED_OUT.store(true, Ordering::Release);
+}
+
+/// Returns true if BIOS calls are locked out.
+pub fn is_locked_out() -> bool {
+    LOCKED_OUT.load(Ordering::Acquire)
+}
+
+///
+/// Allows the function (and AH if given) to be called even after the
+/// lockout.  Returns false if the allowlist is full.
+///
+/// Note: Only calls reviewed to be safe with native drivers active
+///       should be allowed (e.g. a call that touches no hardware).
+///
+pub fn allow_after_lockout(fun: u16, ah: Option<u8>) -> bool {
+    let entry = match ah {
+	Some(ah) => ENTRY_VALID | (fun as u32) << 8 | ah as u32,
+	None => ENTRY_VALID | ENTRY_ANY_AH | (fun as u32) << 8,
+    };
+    ALLOWLIST.iter().any(| slot | {
+	slot.load(Ordering::Acquire) == entry ||
+	    slot.compare_exchange(0, entry, Ordering::AcqRel,
+				  Ordering::Acquire).is_ok()
+    })
+}
+
+// Checks that the function can be called.
+pub(super) fn check(fun: u16, ah: u8) -> Result<(), BiosUnavailable> {
+    if !is_locked_out() {
+	return Ok(());
+    }
+
+    let exact = ENTRY_VALID | (fun as u32) << 8 | ah as u32;
+    let any_ah = ENTRY_VALID | ENTRY_ANY_AH | (fun as u32) << 8;
+    let allowed = ALLOWLIST.iter().any(| slot | {
+	let entry = slot.load(Ordering::Acquire);
+	entry == exact || entry == any_ah
+    });
+    if allowed {
+	Ok(())
+    } else {
+	Err(BiosUnavailable { fun, ah })
+    }
+}
@@ -2,22 +2,70 @@
 
 Calls BIOS functions.
 
+Each supported BIOS function has a typed request implementing
+[`BiosCall`] (e.g., [`int10h0eh::TeletypeOut`]), which is called by
+[`call`].  [`LmbiosRegs`] remains available as an escape hatch.
+
+Once native drivers take over the hardware (e.g. the APIC or an own
+IDT), [`lockout`] stops BIOS calls: they return [`BiosUnavailable`]
+(or fail as unsupported) instead of switching to Real Mode, except for
+the calls allowed by [`allow_after_lockout`].
+
+```ignore
+bios::call(&bios::int10h0eh::TeletypeOut { ch: b'A', page: 0, color: 0 });
+```
+
  */
 
 #[doc(hidden)] pub mod api;
-pub mod asm;
+#[doc(hidden)] pub mod bios_call;
+#[cfg(not(any(feature = "efi", test)))] pub mod asm;
+#[cfg(feature = "disk")] pub mod disk;
 pub mod ffi;
+pub mod int10h00h;
+pub mod int10h03h;
 pub mod int10h0eh;
-pub mod int10h4f00h;
-pub mod int10h4f01h;
-pub mod int10h4f02h;
-pub mod int10h4f03h;
-pub mod int13h02h;
-pub mod int13h42h;
+pub mod int10h0fh;
+#[cfg(feature = "video")] pub mod int10h1110h;
+#[cfg(feature = "video")] pub mod int10h1130h;
+pub mod int10h13h;
+#[cfg(feature = "video")] pub mod int10h4f00h;
+#[cfg(feature = "video")] pub mod int10h4f01h;
+#[cfg(feature = "video")] pub mod int10h4f02h;
+#[cfg(feature = "video")] pub mod int10h4f03h;
+#[cfg(feature = "video")] pub mod int10h4f04h;
+#[cfg(feature = "disk")] pub mod int13h02h;
+#[cfg(feature = "disk")] pub mod int13h03h;
+#[cfg(feature = "disk")] pub mod int13h08h;
+#[cfg(feature = "disk")] pub mod int13h41h;
+#[cfg(feature = "disk")] pub mod int13h42h;
+#[cfg(feature = "disk")] pub mod int13h43h;
+#[cfg(feature = "disk")] pub mod int13h48h;
+#[cfg(feature = "disk")] pub mod int13h4b01h;
+pub mod int15h87h;
 pub mod int15he820h;
+pub mod int16h00h;
+pub mod int16h01h;
+pub mod int16h03h;
+pub mod int1ch;
+pub mod ivt;
+#[doc(hidden)] pub mod layout;
+#[doc(hidden)] pub mod lockout;
 #[doc(hidden)] pub mod lmbios_regs;
 #[doc(hidden)] pub mod stack_usage;
 
-#[doc(inline)] pub use self::api::get_boot_drive_id;
+#[doc(inline)] pub use self::api::{
+    SECTOR_SIZE, get_boot_drive_id, get_sector_size, is_cdrom_drive,
+};
+#[cfg(feature = "disk")]
+#[doc(inline)] pub use self::api::boot_device_path;
+#[doc(inline)] pub use self::bios_call::{
+    BiosCall, CallRegs, CallResult, call,
+};
 #[doc(inline)] pub use self::lmbios_regs::LmbiosRegs;
-#[doc(inline)] pub use self::stack_usage::StackUsage;
+#[doc(inline)] pub use self::lockout::{
+    BiosUnavailable, allow_after_lockout, is_locked_out, lockout,
+};
+#[doc(inline)] pub use self::stack_usage::{
+    StackUsage, check_stack_canary, init_stack_canary,
+};
@@ -1,5 +1,10 @@
 use core::fmt;
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{AtomicBool, Ordering};
+
 use super::ffi;
+use crate::man_region;
+use crate::stack::Watermark;
 
 
 /// Reports stack usage.
@@ -11,6 +16,9 @@ pub struct StackUsage {
 
 impl StackUsage {
     pub fn new() -> Self {
+	// Check the canary as well because this is called periodically.
+	check_stack_canary();
+
 	unsafe {
 	    Self {
 		start: &ffi::__lmb_stack_start as *const u8 as usize,
@@ -21,8 +29,118 @@ impl StackUsage {
     }
 }
 
+impl StackUsage {
+    ///
+    /// Returns the watermark of the lmbios stack (above the canary).
+    ///
+    /// Because `debug_clear_stack_area` fills the unused part with
+    /// zero, the pattern is zero.  Unlike `new`, it does not clear
+    /// the stack area.
+    ///
+    pub fn watermark() -> Watermark {
+	unsafe {
+	    let start = &ffi::__lmb_stack_start as *const u8 as usize;
+	    let end = &ffi::__lmb_stack_end as *const u8 as usize;
+	    let start = core::cmp::min(start + STACK_CANARY_SIZE, end);
+	    Watermark::with_pattern(start, end, 0)
+	}
+    }
+}
+
 impl fmt::Display for StackUsage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 	write!(f, "{:#x} in {:#x} - {:#x}", self.max, self.start, self.end)
     }
 }
+
+
+//
+// Stack Canary
+//
+// Because the stack grows downward, an overflow first overwrites the
+// lowest bytes of the stack area (i.e., from __lmb_stack_start).
+// Hence, the canary pattern is written there.
+//
+// Note: debug_clear_stack_area (debug_helper.s) skips the canary.
+//
+
+/// The size in bytes of the canary (cf. STACK_CANARY_SIZE in
+/// debug_helper.s).
+pub const STACK_CANARY_SIZE: usize = 64;
+
+// The canary pattern.
+const STACK_CANARY: u64 = 0x5354_4143_4b43_4e59;	// "YNCKCATS"
+
+
+///
+/// Writes the canary pattern at the bottom of the stack area, and
+/// registers the stack area and the page tables below it in the
+/// region manager.
+///
+pub fn init_stack_canary() {
+    let Some(start) = canary_start() else {
+	return;
+    };
+
+    unsafe {
+	for i in 0 .. STACK_CANARY_SIZE / 8 {
+	    write_volatile((start as *mut u64).add(i), STACK_CANARY);
+	}
+
+	let stack_end = &ffi::__lmb_stack_end as *const u8 as usize;
+	let tables_start = &ffi::__lmb_page_tables_start as *const u8 as usize;
+	let tables_end = &ffi::__lmb_page_tables_end as *const u8 as usize;
+	let _ = man_region::register("Page Tables", tables_start,
+				     tables_end - tables_start);
+	let _ = man_region::register("Stack", start, stack_end - start);
+    }
+
+    CANARY_ENABLED.store(true, Ordering::Release);
+}
+
+///
+/// Checks the canary pattern at the bottom of the stack area.
+///
+/// Panics with "stack overflow into <region>" if it is violated.
+/// If `init_stack_canary` has not been called, it does nothing.
+///
+pub fn check_stack_canary() {
+    if !CANARY_ENABLED.load(Ordering::Acquire) {
+	return;
+    }
+    let Some(start) = canary_start() else {
+	return;
+    };
+
+    let intact = (0 .. STACK_CANARY_SIZE / 8).all(|i| unsafe {
+	read_volatile((start as *const u64).add(i)) == STACK_CANARY
+    });
+
+    if !intact {
+	// Do not check again while panicking.
+	CANARY_ENABLED.store(false, Ordering::Release);
+
+	let below =
+	    match man_region::find(start - 1) {
+		Some(region) => region.name,
+		None => "the area below the stack",
+	    };
+	panic!("stack overflow into {}", below);
+    }
+}
+
+// True if the canary has been written.
+static CANARY_ENABLED: AtomicBool = AtomicBool::new(false);
+
+// Returns the address of the canary (None if the stack area is too small).
+fn canary_start() -> Option<usize> {
+    let (start, end) = unsafe {
+	(&ffi::__lmb_stack_start as *const u8 as usize,
+	 &ffi::__lmb_stack_end as *const u8 as usize)
+    };
+    if end - start > STACK_CANARY_SIZE {
+	Some(start)
+    } else {
+	None
+    }
+}
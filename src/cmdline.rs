@@ -0,0 +1,56 @@
+/*!
+
+Provides the command line embedded in the boot image.
+
+The command line is a statically allocated buffer starting with the
+magic "LMBCMDL:".  It is patched in the boot image by `mkimage`
+(called by `run-qemu.sh` and `run-qemu.ps1` with `$CMDLINE`).  The
+command line consists of space-separated arguments such as
+`heap_scenario=mixed` and `verbose`.
+
+ */
+
+
+use core::ptr::addr_of;
+use core::str;
+
+
+/// The size in bytes of the command line (excluding the magic).
+pub const CMDLINE_SIZE: usize = 248;
+
+#[repr(C)]
+struct CmdLine {
+    magic: [u8; 8],			// Magic "LMBCMDL:"
+    text: [u8; CMDLINE_SIZE],		// NUL-terminated (unless full)
+}
+
+// Note: It is mutable so that the compiler does not assume its contents
+//       because it is patched after the build.
+#[used]
+static mut LMB_CMDLINE: CmdLine = CmdLine {
+    magic: *b"LMBCMDL:",
+    text: [0; CMDLINE_SIZE],
+};
+
+
+/// Returns the command line.
+pub fn get() -> &'static str {
+    let text = unsafe { &*addr_of!(LMB_CMDLINE.text) };
+    let len = text.iter().position(|&byte| byte == 0).unwrap_or(text.len());
+    str::from_utf8(&text[.. len]).unwrap_or("")
+}
+
+/// Returns an iterator over the arguments.
+pub fn args() -> impl Iterator<Item = &'static str> {
+    get().split_ascii_whitespace()
+}
+
+/// Returns the value of the argument `key=value` (if any).
+pub fn value(key: &str) -> Option<&'static str> {
+    args().find_map(|arg| arg.strip_prefix(key)?.strip_prefix('='))
+}
+
+/// Returns true if the argument `key` is given.
+pub fn flag(key: &str) -> bool {
+    args().any(|arg| arg == key)
+}
@@ -0,0 +1,144 @@
+//
+// Console Configuration - Routes printed text to multiple sinks.
+//
+
+use core::fmt;
+
+use crate::mu::MuMutex;
+
+use super::early::{EarlyPort, PortWriter};
+use super::history;
+use super::screen::ScreenWriter;
+
+
+/// Levels of printed text (Lower is more important).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// Sinks of printed text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sink {
+    /// Screen (a frame buffer, the VGA text buffer or BIOS teletype)
+    Screen,
+    /// 16550 UART at COM1 (if found by `early_init`)
+    Serial,
+    /// QEMU / Bochs debug console (if found by `early_init`)
+    Debugcon,
+}
+
+impl Sink {
+    /// All sinks.
+    pub const ALL: [Sink; 3] = [Sink::Screen, Sink::Serial, Sink::Debugcon];
+}
+
+
+///
+/// Console configuration: the most verbose level printed to each sink.
+///
+/// `None` disables the sink.  By default, `println!` (`Level::Info`)
+/// is printed to all sinks, whereas debug output (`Level::Debug` and
+/// `Level::Trace`) is printed only to the serial port and debugcon,
+/// because the screen (BIOS teletype output) is slow.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct ConsoleConfig {
+    pub screen: Option<Level>,
+    pub serial: Option<Level>,
+    pub debugcon: Option<Level>,
+}
+
+impl ConsoleConfig {
+    /// Returns the default configuration.
+    pub const fn new() -> Self {
+	Self {
+	    screen: Some(Level::Info),
+	    serial: Some(Level::Trace),
+	    debugcon: Some(Level::Trace),
+	}
+    }
+
+    /// Returns the most verbose level printed to the sink.
+    pub fn level(&self, sink: Sink) -> Option<Level> {
+	match sink {
+	    Sink::Screen => self.screen,
+	    Sink::Serial => self.serial,
+	    Sink::Debugcon => self.debugcon,
+	}
+    }
+
+    /// Sets the most verbose level printed to the sink.
+    pub fn set_level(&mut self, sink: Sink, level: Option<Level>) {
+	match sink {
+	    Sink::Screen => self.screen = level,
+	    Sink::Serial => self.serial = level,
+	    Sink::Debugcon => self.debugcon = level,
+	}
+    }
+
+    /// Returns true if text of the level is printed to the sink.
+    pub fn is_enabled(&self, sink: Sink, level: Level) -> bool {
+	self.level(sink).is_some_and(|max| level <= max)
+    }
+}
+
+impl Default for ConsoleConfig {
+    fn default() -> Self {
+	Self::new()
+    }
+}
+
+
+// The current console configuration.
+static CONFIG: MuMutex<ConsoleConfig> = MuMutex::new(ConsoleConfig::new());
+
+/// Returns the current console configuration.
+pub fn config() -> ConsoleConfig {
+    *CONFIG.lock()
+}
+
+/// Replaces the console configuration.
+pub fn set_config(config: ConsoleConfig) {
+    *CONFIG.lock() = config;
+}
+
+/// Sets the most verbose level printed to the sink.
+pub fn set_sink_level(sink: Sink, level: Option<Level>) {
+    CONFIG.lock().set_level(sink, level);
+}
+
+
+/// Prints text of the level to the sinks enabled in the configuration.
+pub fn print_at(level: Level, args: fmt::Arguments) {
+    // Record everything printed (even if no sink is enabled).
+    history::record(args);
+
+    // Copy the configuration not to hold the lock while printing.
+    let config = config();
+
+    for sink in Sink::ALL {
+	if config.is_enabled(sink, level) {
+	    write_to(sink, args);
+	}
+    }
+
+    // Write also to the sinks registered at runtime (if any).
+    crate::text_writer::write_consoles(level, args);
+}
+
+// Writes text to the sink.
+pub(super) fn write_to(sink: Sink, args: fmt::Arguments) {
+    use fmt::Write;
+
+    let _ =
+	match sink {
+	    Sink::Screen => ScreenWriter.write_fmt(args),
+	    Sink::Serial => PortWriter(EarlyPort::Com1).write_fmt(args),
+	    Sink::Debugcon => PortWriter(EarlyPort::Debugcon).write_fmt(args),
+	};
+}
@@ -0,0 +1,99 @@
+/*!
+
+Transliterates Unicode characters into Code Page 437 (CP437).
+
+CP437 is the character set of the VGA text mode and BIOS teletype
+output.  Box drawing characters, arrows, the degree sign, accented
+Latin letters, etc. are mapped to their CP437 equivalents.
+
+# Supplementary Resource
+
+* [Code page 437](https://en.wikipedia.org/wiki/Code_page_437) (Wikipedia)
+
+ */
+
+//
+// Supplementary Resource:
+//	https://en.wikipedia.org/wiki/Code_page_437
+//
+
+
+// Characters of CP437 0x80 - 0xFF
+const HIGH_HALF: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç',	// 80-87
+    'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',	// 88-8F
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù',	// 90-97
+    'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',	// 98-9F
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º',	// A0-A7
+    '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',	// A8-AF
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖',	// B0-B7
+    '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',	// B8-BF
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟',	// C0-C7
+    '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',	// C8-CF
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫',	// D0-D7
+    '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',	// D8-DF
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ',	// E0-E7
+    'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',	// E8-EF
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈',	// F0-F7
+    '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',	// F8-FF
+];
+
+// Characters of CP437 0x01 - 0x1F (Glyphs in place of control codes)
+// Note: 0x07 (BEL), 0x08 (BS), 0x0A (LF) and 0x0D (CR) are excluded
+//       because BIOS teletype output interprets them.
+const LOW_GLYPHS: [(char, u8); 27] = [
+    ('☺', 0x01), ('☻', 0x02), ('♥', 0x03), ('♦', 0x04),
+    ('♣', 0x05), ('♠', 0x06), ('○', 0x09), ('♂', 0x0b),
+    ('♀', 0x0c), ('♫', 0x0e), ('☼', 0x0f), ('►', 0x10),
+    ('◄', 0x11), ('↕', 0x12), ('‼', 0x13), ('¶', 0x14),
+    ('§', 0x15), ('▬', 0x16), ('↨', 0x17), ('↑', 0x18),
+    ('↓', 0x19), ('→', 0x1a), ('←', 0x1b), ('∟', 0x1c),
+    ('↔', 0x1d), ('▲', 0x1e), ('▼', 0x1f),
+];
+
+// Characters not in CP437 transliterated into similar characters.
+const SIMILAR: [(char, u8); 36] = [
+    ('À', b'A'), ('Á', b'A'), ('Â', b'A'), ('Ã', b'A'),
+    ('È', b'E'), ('Ê', b'E'), ('Ë', b'E'), ('Ì', b'I'),
+    ('Í', b'I'), ('Î', b'I'), ('Ï', b'I'), ('Ò', b'O'),
+    ('Ó', b'O'), ('Ô', b'O'), ('Õ', b'O'), ('Ø', b'O'),
+    ('Ù', b'U'), ('Ú', b'U'), ('Û', b'U'), ('Ý', b'Y'),
+    ('ã', b'a'), ('õ', b'o'), ('ø', b'o'), ('ý', b'y'),
+    ('‘', b'\''), ('’', b'\''), ('“', b'"'), ('”', b'"'),
+    ('–', b'-'), ('—', b'-'), ('…', b'.'), ('•', 0xf9),
+    ('μ', 0xe6), ('β', 0xe1), ('×', b'x'), ('∑', 0xe4),
+];
+
+
+///
+/// Transliterates a character into CP437.
+///
+/// ASCII printable characters are returned as they are.  Returns
+/// `None` if there is no equivalent character.
+///
+pub fn encode(ch: char) -> Option<u8> {
+    if (' ' ..= '~').contains(&ch) {
+	return Some(ch as u8);
+    }
+
+    if let Some(index) = HIGH_HALF.iter().position(|&c| c == ch) {
+	return Some(0x80 + index as u8);
+    }
+
+    LOW_GLYPHS.iter().chain(SIMILAR.iter())
+	.find(|&&(c, _)| c == ch)
+	.map(|&(_, byte)| byte)
+}
+
+///
+/// Transliterates a character into CP437 for a text console.
+///
+/// CR, LF and BS are kept as they are (to be interpreted by the
+/// console), and a character with no equivalent is replaced by '.'.
+///
+pub fn encode_or_control(ch: char) -> u8 {
+    match ch {
+	'\n' | '\r' | '\x08' => ch as u8,
+	_ => encode(ch).unwrap_or(b'.'),
+    }
+}
@@ -0,0 +1,137 @@
+//
+// Early Console - A console available before the allocator is initialized.
+//
+
+use core::fmt;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::cmdline;
+use crate::x86::{SerialPort, inb, outb};
+
+use super::config::{Sink, set_sink_level};
+
+
+/// Ports that can be used as an early console.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EarlyPort {
+    /// 16550 UART at COM1 (I/O Port 0x3F8)
+    Com1,
+    /// QEMU / Bochs debug console (I/O Port 0xE9)
+    Debugcon,
+}
+
+impl EarlyPort {
+    // Bits of AVAILABLE_PORTS.
+    fn bit(self) -> u8 {
+	match self {
+	    Self::Com1 => 1 << 0,
+	    Self::Debugcon => 1 << 1,
+	}
+    }
+}
+
+
+// The set of available early console ports (bits of EarlyPort::bit).
+static AVAILABLE_PORTS: AtomicU8 = AtomicU8::new(0);
+
+// I/O Port Address of QEMU / Bochs debug console
+const DEBUGCON_PORT: u16 = 0xe9;
+
+
+///
+/// Brings up an early console with zero allocations.
+///
+/// It probes both COM1 (16550 UART) and QEMU debugcon.  Once it is
+/// initialized, output of `print!` and `println!` is also routed to
+/// the available ports (cf. `ConsoleConfig`).  Hence, failures
+/// before the global allocator is initialized (e.g. in
+/// `init_global_alloc`) become diagnosable.
+///
+/// If `console=serial` is given and COM1 is found, the output is
+/// routed only to the serial port (and debugcon), not to the screen.
+/// Hence, QEMU `-serial stdio` captures all output even in a graphics
+/// mode, without the cost of drawing it.
+///
+/// It returns the primary port (COM1 is preferred), or `None` if
+/// neither is available.
+///
+pub fn early_init() -> Option<EarlyPort> {
+    let mut ports = 0;
+
+    if SerialPort::COM1.init() {
+	ports |= EarlyPort::Com1.bit();
+	if cmdline::value("console") == Some("serial") {
+	    set_sink_level(Sink::Screen, None);
+	}
+    }
+
+    // Reading the debugcon port returns 0xE9 if it exists.
+    if unsafe { inb(DEBUGCON_PORT) } == DEBUGCON_PORT as u8 {
+	ports |= EarlyPort::Debugcon.bit();
+    }
+
+    AVAILABLE_PORTS.store(ports, Ordering::Release);
+
+    early_port()
+}
+
+/// Returns the primary early console port (COM1 is preferred).
+pub fn early_port() -> Option<EarlyPort> {
+    if is_available(EarlyPort::Com1) {
+	Some(EarlyPort::Com1)
+    } else if is_available(EarlyPort::Debugcon) {
+	Some(EarlyPort::Debugcon)
+    } else {
+	None
+    }
+}
+
+/// Returns true if the port has been found by `early_init`.
+pub fn is_available(port: EarlyPort) -> bool {
+    (AVAILABLE_PORTS.load(Ordering::Acquire) & port.bit()) != 0
+}
+
+/// Writes a string to the primary early console (if initialized).
+pub fn early_write_str(s: &str) {
+    if let Some(port) = early_port() {
+	port_write_str(port, s);
+    }
+}
+
+/// Writes a string to the port (if available).
+pub fn port_write_str(port: EarlyPort, s: &str) {
+    if !is_available(port) {
+	return;
+    }
+
+    match port {
+	EarlyPort::Com1 => SerialPort::COM1.write_bytes(s.as_bytes()),
+	EarlyPort::Debugcon => {
+	    for byte in s.bytes() {
+		unsafe { outb(DEBUGCON_PORT, byte) };
+	    }
+	},
+    }
+}
+
+
+/// A writer to the primary early console.
+pub struct EarlyWriter;
+
+impl fmt::Write for EarlyWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+	early_write_str(s);
+	Ok(())
+    }
+}
+
+/// A writer to an early console port.
+pub struct PortWriter(pub EarlyPort);
+
+impl fmt::Write for PortWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+	port_write_str(self.0, s);
+	Ok(())
+    }
+}
+
@@ -0,0 +1,209 @@
+//
+// Frame Buffer Console - Draws text on a VBE linear frame buffer.
+//
+// Glyphs are taken from the 8x16 ROM font of the VGA BIOS
+// (INT 10h AX=1130h), or from the embedded font (cf. `font8x16`) if
+// the ROM font is not available.
+//
+// Supplementary Resource:
+//	https://wiki.osdev.org/VGA_Fonts
+//
+
+use core::ptr::copy;
+use core::slice;
+
+use crate::bios::int10h1130h::FontInfo;
+use crate::man_video::FrameBuffer;
+
+use super::font8x16;
+
+
+// Glyphs are 8 pixels wide.
+const GLYPH_WIDTH: usize = 8;
+
+// Colors (0x00RRGGBB)
+const COLOR_TEXT: u32 = 0x00c0c0c0;	// Light Gray
+const COLOR_BACKGROUND: u32 = 0x00000000;	// Black
+const COLOR_STATUS_TEXT: u32 = 0x00000000;	// Black
+const COLOR_STATUS_BACKGROUND: u32 = 0x00c0c0c0;	// Light Gray
+
+
+/// The source of glyphs drawn by [`FbConsole`].
+#[derive(Clone, Copy, Debug)]
+pub enum FbFont {
+    /// The ROM font of the VGA BIOS (cf. `bios::int10h1130h`)
+    Rom(FontInfo),
+    /// The embedded 8x16 font (cf. `console::font8x16`)
+    Embedded,
+}
+
+impl FbFont {
+    // Returns the height of glyphs in pixels.
+    fn height(&self) -> usize {
+	match self {
+	    Self::Rom(font) => font.bytes_per_char as usize,
+	    Self::Embedded => font8x16::GLYPH_HEIGHT,
+	}
+    }
+
+    // Returns the glyph of the CP437 character (a row per byte).
+    fn glyph(&self, byte: u8) -> &'static [u8] {
+	match self {
+	    Self::Rom(font) => {
+		let height = font.bytes_per_char as usize;
+		let addr = font.addr + byte as usize * height;
+		unsafe { slice::from_raw_parts(addr as *const u8, height) }
+	    },
+	    Self::Embedded => font8x16::glyph(byte),
+	}
+    }
+}
+
+
+/// A console drawing text on a linear frame buffer.
+pub struct FbConsole {
+    fb: FrameBuffer,
+    font: FbFont,
+    fg: u32,			// Foreground Color (0x00RRGGBB)
+    bg: u32,			// Background Color (0x00RRGGBB)
+    columns: usize,
+    rows: usize,
+    scroll_rows: usize,		// Rows above the status row (if any)
+    row: usize,
+    col: usize,
+}
+
+impl FbConsole {
+    /// Creates a console on the frame buffer with the font, then
+    /// clears the screen.  Returns None if the screen is smaller than
+    /// a character.
+    pub fn new(fb: FrameBuffer, font: FbFont) -> Option<Self> {
+	let glyph_height = font.height();
+	if glyph_height == 0 {
+	    return None;
+	}
+
+	let mut console = Self {
+	    fb,
+	    font,
+	    fg: COLOR_TEXT,
+	    bg: COLOR_BACKGROUND,
+	    columns: fb.width / GLYPH_WIDTH,
+	    rows: fb.height / glyph_height,
+	    scroll_rows: fb.height / glyph_height,
+	    row: 0,
+	    col: 0,
+	};
+	if console.columns == 0 || console.rows == 0 {
+	    return None;
+	}
+
+	console.clear();
+	Some(console)
+    }
+
+    /// Returns the number of columns and rows.
+    pub fn size(&self) -> (usize, usize) {
+	(self.columns, self.rows)
+    }
+
+    /// Sets the colors (0x00RRGGBB) of the characters written after.
+    pub fn set_colors(&mut self, fg: u32, bg: u32) {
+	self.fg = fg;
+	self.bg = bg;
+    }
+
+    /// Fills the screen with the background color, then moves the
+    /// cursor to the top left.
+    pub fn clear(&mut self) {
+	self.fb.fill_rect(0, 0, self.fb.width, self.fb.height, self.bg);
+	self.row = 0;
+	self.col = 0;
+    }
+
+    /// Writes a CP437 character (CR, LF and BS are interpreted).
+    pub fn write_byte(&mut self, byte: u8) {
+	match byte {
+	    b'\r' => self.col = 0,
+	    b'\n' => self.new_line(),
+	    b'\x08' => self.col = self.col.saturating_sub(1),
+	    _ => {
+		if self.col >= self.columns {
+		    self.col = 0;
+		    self.new_line();
+		}
+		self.draw_glyph(self.row, self.col, byte, self.fg, self.bg);
+		self.col += 1;
+	    },
+	}
+    }
+
+    ///
+    /// Reserves the bottom row for the status (or releases it).  The
+    /// reserved row is excluded from scrolling.
+    ///
+    pub fn reserve_status_row(&mut self, reserve: bool) {
+	if self.rows < 2 {
+	    return;
+	}
+	if reserve && self.scroll_rows == self.rows {
+	    // Move the output above the status row.
+	    if self.row == self.rows - 1 {
+		self.scroll_up();
+		self.row -= 1;
+	    }
+	    self.scroll_rows = self.rows - 1;
+	} else if !reserve {
+	    self.scroll_rows = self.rows;
+	}
+	self.draw_status(b"");
+    }
+
+    /// Draws CP437 characters in the status row (padded with spaces).
+    pub fn draw_status(&mut self, text: &[u8]) {
+	let (fg, bg) =
+	    if self.scroll_rows < self.rows {
+		(COLOR_STATUS_TEXT, COLOR_STATUS_BACKGROUND)
+	    } else {
+		(self.fg, self.bg)
+	    };
+	for col in 0 .. self.columns {
+	    let byte = text.get(col).copied().unwrap_or(b' ');
+	    self.draw_glyph(self.rows - 1, col, byte, fg, bg);
+	}
+    }
+
+    fn draw_glyph(&mut self, row: usize, col: usize, byte: u8,
+		  fg: u32, bg: u32) {
+	let height = self.font.height();
+	for (dy, &bits) in self.font.glyph(byte).iter().enumerate() {
+	    let y = row * height + dy;
+	    for dx in 0 .. GLYPH_WIDTH {
+		let color = if (bits & (0x80 >> dx)) != 0 { fg } else { bg };
+		self.fb.put_pixel(col * GLYPH_WIDTH + dx, y, color);
+	    }
+	}
+    }
+
+    fn new_line(&mut self) {
+	if self.row + 1 < self.scroll_rows {
+	    self.row += 1;
+	} else {
+	    self.scroll_up();
+	}
+    }
+
+    // Scrolls up by a text line, then clears the bottom text line
+    // (above the status row if reserved).
+    fn scroll_up(&mut self) {
+	let height = self.font.height();
+	let line_bytes = height * self.fb.pitch;
+	let base = self.fb.base as *mut u8;
+	let rows = self.scroll_rows;
+	unsafe {
+	    copy(base.add(line_bytes), base, (rows - 1) * line_bytes);
+	}
+	self.fb.fill_rect(0, (rows - 1) * height,
+			  self.fb.width, height, self.bg);
+    }
+}
@@ -0,0 +1,322 @@
+//
+// Font 8x16 - An embedded 8x16 bitmap font of printable ASCII.
+//
+// It is used to draw text on a frame buffer when the ROM font of the
+// VGA BIOS is not available (cf. `fb_console::FbFont`).  Each
+// glyph is 16 rows of 8 pixels (MSB is the leftmost pixel).  The other
+// characters are drawn as a box.
+//
+
+/// The width of glyphs in pixels.
+pub const GLYPH_WIDTH: usize = 8;
+
+/// The height of glyphs in pixels.
+pub const GLYPH_HEIGHT: usize = 16;
+
+// The first and the last characters of the font
+const FIRST_CHAR: u8 = 0x20;
+const LAST_CHAR: u8 = 0x7e;
+
+
+/// Returns the glyph of the character (a box if it is not in the font).
+pub fn glyph(byte: u8) -> &'static [u8; GLYPH_HEIGHT] {
+    match byte {
+	FIRST_CHAR ..= LAST_CHAR => &GLYPHS[(byte - FIRST_CHAR) as usize],
+	_ => &BOX,
+    }
+}
+
+
+// The glyph of characters not in the font
+static BOX: [u8; GLYPH_HEIGHT] =
+    [0x00, 0x00, 0xfe, 0x82, 0x82, 0x82, 0x82, 0x82,
+     0x82, 0x82, 0x82, 0xfe, 0x00, 0x00, 0x00, 0x00];
+
+// The glyphs of 0x20 to 0x7E
+static GLYPHS: [[u8; GLYPH_HEIGHT]; (LAST_CHAR - FIRST_CHAR + 1) as usize] = [
+    // 0x20 ' '
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x21 '!'
+    [0x00, 0x00, 0x18, 0x3c, 0x3c, 0x3c, 0x18, 0x18,
+     0x18, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x00],
+    // 0x22 '"'
+    [0x00, 0x00, 0x66, 0x66, 0x66, 0x24, 0x00, 0x00,
+     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x23 '#'
+    [0x00, 0x00, 0x00, 0x00, 0x6c, 0x6c, 0xfe, 0x6c,
+     0x6c, 0x6c, 0xfe, 0x6c, 0x00, 0x00, 0x00, 0x00],
+    // 0x24 '$'
+    [0x00, 0x00, 0x18, 0x7c, 0xc6, 0xc2, 0xc0, 0x7c,
+     0x06, 0x86, 0xc6, 0x7c, 0x18, 0x18, 0x00, 0x00],
+    // 0x25 '%'
+    [0x00, 0x00, 0x00, 0x00, 0xc2, 0xc6, 0x0c, 0x18,
+     0x30, 0x60, 0xc6, 0x86, 0x00, 0x00, 0x00, 0x00],
+    // 0x26 '&'
+    [0x00, 0x00, 0x38, 0x6c, 0x6c, 0x38, 0x76, 0xdc,
+     0xcc, 0xcc, 0xcc, 0x76, 0x00, 0x00, 0x00, 0x00],
+    // 0x27 '\''
+    [0x00, 0x00, 0x30, 0x30, 0x30, 0x60, 0x00, 0x00,
+     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x28 '('
+    [0x00, 0x00, 0x0c, 0x18, 0x30, 0x30, 0x30, 0x30,
+     0x30, 0x30, 0x18, 0x0c, 0x00, 0x00, 0x00, 0x00],
+    // 0x29 ')'
+    [0x00, 0x00, 0x30, 0x18, 0x0c, 0x0c, 0x0c, 0x0c,
+     0x0c, 0x0c, 0x18, 0x30, 0x00, 0x00, 0x00, 0x00],
+    // 0x2A '*'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x66, 0x3c, 0xff,
+     0x3c, 0x66, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x2B '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x7e,
+     0x18, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x2C ','
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+     0x00, 0x00, 0x18, 0x18, 0x30, 0x00, 0x00, 0x00],
+    // 0x2D '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfe,
+     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x2E '.'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+     0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x00],
+    // 0x2F '/'
+    [0x00, 0x00, 0x00, 0x00, 0x02, 0x06, 0x0c, 0x18,
+     0x30, 0x60, 0xc0, 0x80, 0x00, 0x00, 0x00, 0x00],
+    // 0x30 '0'
+    [0x00, 0x00, 0x38, 0x6c, 0xc6, 0xc6, 0xd6, 0xd6,
+     0xc6, 0xc6, 0x6c, 0x38, 0x00, 0x00, 0x00, 0x00],
+    // 0x31 '1'
+    [0x00, 0x00, 0x18, 0x38, 0x78, 0x18, 0x18, 0x18,
+     0x18, 0x18, 0x18, 0x7e, 0x00, 0x00, 0x00, 0x00],
+    // 0x32 '2'
+    [0x00, 0x00, 0x7c, 0xc6, 0x06, 0x0c, 0x18, 0x30,
+     0x60, 0xc0, 0xc6, 0xfe, 0x00, 0x00, 0x00, 0x00],
+    // 0x33 '3'
+    [0x00, 0x00, 0x7c, 0xc6, 0x06, 0x06, 0x3c, 0x06,
+     0x06, 0x06, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x34 '4'
+    [0x00, 0x00, 0x0c, 0x1c, 0x3c, 0x6c, 0xcc, 0xfe,
+     0x0c, 0x0c, 0x0c, 0x1e, 0x00, 0x00, 0x00, 0x00],
+    // 0x35 '5'
+    [0x00, 0x00, 0xfe, 0xc0, 0xc0, 0xc0, 0xfc, 0x06,
+     0x06, 0x06, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x36 '6'
+    [0x00, 0x00, 0x38, 0x60, 0xc0, 0xc0, 0xfc, 0xc6,
+     0xc6, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x37 '7'
+    [0x00, 0x00, 0xfe, 0xc6, 0x06, 0x0c, 0x18, 0x30,
+     0x30, 0x30, 0x30, 0x30, 0x00, 0x00, 0x00, 0x00],
+    // 0x38 '8'
+    [0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xc6, 0x7c, 0xc6,
+     0xc6, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x39 '9'
+    [0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xc6, 0x7e, 0x06,
+     0x06, 0x06, 0x0c, 0x78, 0x00, 0x00, 0x00, 0x00],
+    // 0x3A ':'
+    [0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00,
+     0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x3B ';'
+    [0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00,
+     0x00, 0x18, 0x18, 0x30, 0x00, 0x00, 0x00, 0x00],
+    // 0x3C '<'
+    [0x00, 0x00, 0x00, 0x06, 0x0c, 0x18, 0x30, 0x60,
+     0x30, 0x18, 0x0c, 0x06, 0x00, 0x00, 0x00, 0x00],
+    // 0x3D '='
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7e, 0x00,
+     0x00, 0x7e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x3E '>'
+    [0x00, 0x00, 0x00, 0x60, 0x30, 0x18, 0x0c, 0x06,
+     0x0c, 0x18, 0x30, 0x60, 0x00, 0x00, 0x00, 0x00],
+    // 0x3F '?'
+    [0x00, 0x00, 0x7c, 0xc6, 0xc6, 0x0c, 0x18, 0x18,
+     0x18, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x00],
+    // 0x40 '@'
+    [0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xde, 0xde,
+     0xde, 0xdc, 0xc0, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x41 'A'
+    [0x00, 0x00, 0x10, 0x38, 0x6c, 0xc6, 0xc6, 0xfe,
+     0xc6, 0xc6, 0xc6, 0xc6, 0x00, 0x00, 0x00, 0x00],
+    // 0x42 'B'
+    [0x00, 0x00, 0xfc, 0x66, 0x66, 0x66, 0x7c, 0x66,
+     0x66, 0x66, 0x66, 0xfc, 0x00, 0x00, 0x00, 0x00],
+    // 0x43 'C'
+    [0x00, 0x00, 0x3c, 0x66, 0xc2, 0xc0, 0xc0, 0xc0,
+     0xc0, 0xc2, 0x66, 0x3c, 0x00, 0x00, 0x00, 0x00],
+    // 0x44 'D'
+    [0x00, 0x00, 0xf8, 0x6c, 0x66, 0x66, 0x66, 0x66,
+     0x66, 0x66, 0x6c, 0xf8, 0x00, 0x00, 0x00, 0x00],
+    // 0x45 'E'
+    [0x00, 0x00, 0xfe, 0x66, 0x62, 0x68, 0x78, 0x68,
+     0x60, 0x62, 0x66, 0xfe, 0x00, 0x00, 0x00, 0x00],
+    // 0x46 'F'
+    [0x00, 0x00, 0xfe, 0x66, 0x62, 0x68, 0x78, 0x68,
+     0x60, 0x60, 0x60, 0xf0, 0x00, 0x00, 0x00, 0x00],
+    // 0x47 'G'
+    [0x00, 0x00, 0x3c, 0x66, 0xc2, 0xc0, 0xc0, 0xde,
+     0xc6, 0xc6, 0x66, 0x3a, 0x00, 0x00, 0x00, 0x00],
+    // 0x48 'H'
+    [0x00, 0x00, 0xc6, 0xc6, 0xc6, 0xc6, 0xfe, 0xc6,
+     0xc6, 0xc6, 0xc6, 0xc6, 0x00, 0x00, 0x00, 0x00],
+    // 0x49 'I'
+    [0x00, 0x00, 0x3c, 0x18, 0x18, 0x18, 0x18, 0x18,
+     0x18, 0x18, 0x18, 0x3c, 0x00, 0x00, 0x00, 0x00],
+    // 0x4A 'J'
+    [0x00, 0x00, 0x1e, 0x0c, 0x0c, 0x0c, 0x0c, 0x0c,
+     0x0c, 0xcc, 0xcc, 0x78, 0x00, 0x00, 0x00, 0x00],
+    // 0x4B 'K'
+    [0x00, 0x00, 0xe6, 0x66, 0x6c, 0x6c, 0x78, 0x78,
+     0x6c, 0x66, 0x66, 0xe6, 0x00, 0x00, 0x00, 0x00],
+    // 0x4C 'L'
+    [0x00, 0x00, 0xf0, 0x60, 0x60, 0x60, 0x60, 0x60,
+     0x60, 0x62, 0x66, 0xfe, 0x00, 0x00, 0x00, 0x00],
+    // 0x4D 'M'
+    [0x00, 0x00, 0xc6, 0xee, 0xfe, 0xfe, 0xd6, 0xc6,
+     0xc6, 0xc6, 0xc6, 0xc6, 0x00, 0x00, 0x00, 0x00],
+    // 0x4E 'N'
+    [0x00, 0x00, 0xc6, 0xe6, 0xf6, 0xfe, 0xde, 0xce,
+     0xc6, 0xc6, 0xc6, 0xc6, 0x00, 0x00, 0x00, 0x00],
+    // 0x4F 'O'
+    [0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6,
+     0xc6, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x50 'P'
+    [0x00, 0x00, 0xfc, 0x66, 0x66, 0x66, 0x7c, 0x60,
+     0x60, 0x60, 0x60, 0xf0, 0x00, 0x00, 0x00, 0x00],
+    // 0x51 'Q'
+    [0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6,
+     0xc6, 0xd6, 0xde, 0x7c, 0x0c, 0x0e, 0x00, 0x00],
+    // 0x52 'R'
+    [0x00, 0x00, 0xfc, 0x66, 0x66, 0x66, 0x7c, 0x6c,
+     0x66, 0x66, 0x66, 0xe6, 0x00, 0x00, 0x00, 0x00],
+    // 0x53 'S'
+    [0x00, 0x00, 0x7c, 0xc6, 0xc6, 0x60, 0x38, 0x0c,
+     0x06, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x54 'T'
+    [0x00, 0x00, 0xff, 0xdb, 0x18, 0x18, 0x18, 0x18,
+     0x18, 0x18, 0x18, 0x3c, 0x00, 0x00, 0x00, 0x00],
+    // 0x55 'U'
+    [0x00, 0x00, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6,
+     0xc6, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x56 'V'
+    [0x00, 0x00, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6,
+     0xc6, 0x6c, 0x38, 0x10, 0x00, 0x00, 0x00, 0x00],
+    // 0x57 'W'
+    [0x00, 0x00, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xd6,
+     0xd6, 0xfe, 0xee, 0x6c, 0x00, 0x00, 0x00, 0x00],
+    // 0x58 'X'
+    [0x00, 0x00, 0xc6, 0xc6, 0x6c, 0x7c, 0x38, 0x38,
+     0x7c, 0x6c, 0xc6, 0xc6, 0x00, 0x00, 0x00, 0x00],
+    // 0x59 'Y'
+    [0x00, 0x00, 0x66, 0x66, 0x66, 0x66, 0x3c, 0x18,
+     0x18, 0x18, 0x18, 0x3c, 0x00, 0x00, 0x00, 0x00],
+    // 0x5A 'Z'
+    [0x00, 0x00, 0xfe, 0xc6, 0x86, 0x0c, 0x18, 0x30,
+     0x60, 0xc2, 0xc6, 0xfe, 0x00, 0x00, 0x00, 0x00],
+    // 0x5B '['
+    [0x00, 0x00, 0x3c, 0x30, 0x30, 0x30, 0x30, 0x30,
+     0x30, 0x30, 0x30, 0x3c, 0x00, 0x00, 0x00, 0x00],
+    // 0x5C '\\'
+    [0x00, 0x00, 0x00, 0x80, 0xc0, 0x60, 0x30, 0x18,
+     0x0c, 0x06, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x5D ']'
+    [0x00, 0x00, 0x3c, 0x0c, 0x0c, 0x0c, 0x0c, 0x0c,
+     0x0c, 0x0c, 0x0c, 0x3c, 0x00, 0x00, 0x00, 0x00],
+    // 0x5E '^'
+    [0x00, 0x00, 0x10, 0x38, 0x6c, 0xc6, 0x00, 0x00,
+     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x5F '_'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+     0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00],
+    // 0x60 '`'
+    [0x00, 0x00, 0x30, 0x18, 0x0c, 0x00, 0x00, 0x00,
+     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 0x61 'a'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x78, 0x0c, 0x7c,
+     0xcc, 0xcc, 0xcc, 0x76, 0x00, 0x00, 0x00, 0x00],
+    // 0x62 'b'
+    [0x00, 0x00, 0xe0, 0x60, 0x60, 0x78, 0x6c, 0x66,
+     0x66, 0x66, 0x66, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x63 'c'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc0,
+     0xc0, 0xc0, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x64 'd'
+    [0x00, 0x00, 0x1c, 0x0c, 0x0c, 0x3c, 0x6c, 0xcc,
+     0xcc, 0xcc, 0xcc, 0x76, 0x00, 0x00, 0x00, 0x00],
+    // 0x65 'e'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0xc6, 0xfe,
+     0xc0, 0xc0, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x66 'f'
+    [0x00, 0x00, 0x38, 0x6c, 0x64, 0x60, 0xf0, 0x60,
+     0x60, 0x60, 0x60, 0xf0, 0x00, 0x00, 0x00, 0x00],
+    // 0x67 'g'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x76, 0xcc, 0xcc,
+     0xcc, 0xcc, 0xcc, 0x7c, 0x0c, 0xcc, 0x78, 0x00],
+    // 0x68 'h'
+    [0x00, 0x00, 0xe0, 0x60, 0x60, 0x6c, 0x76, 0x66,
+     0x66, 0x66, 0x66, 0xe6, 0x00, 0x00, 0x00, 0x00],
+    // 0x69 'i'
+    [0x00, 0x00, 0x18, 0x18, 0x00, 0x38, 0x18, 0x18,
+     0x18, 0x18, 0x18, 0x3c, 0x00, 0x00, 0x00, 0x00],
+    // 0x6A 'j'
+    [0x00, 0x00, 0x06, 0x06, 0x00, 0x0e, 0x06, 0x06,
+     0x06, 0x06, 0x06, 0x06, 0x66, 0x66, 0x3c, 0x00],
+    // 0x6B 'k'
+    [0x00, 0x00, 0xe0, 0x60, 0x60, 0x66, 0x6c, 0x78,
+     0x78, 0x6c, 0x66, 0xe6, 0x00, 0x00, 0x00, 0x00],
+    // 0x6C 'l'
+    [0x00, 0x00, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18,
+     0x18, 0x18, 0x18, 0x3c, 0x00, 0x00, 0x00, 0x00],
+    // 0x6D 'm'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xec, 0xfe, 0xd6,
+     0xd6, 0xd6, 0xd6, 0xc6, 0x00, 0x00, 0x00, 0x00],
+    // 0x6E 'n'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xdc, 0x66, 0x66,
+     0x66, 0x66, 0x66, 0x66, 0x00, 0x00, 0x00, 0x00],
+    // 0x6F 'o'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc6,
+     0xc6, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x70 'p'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xdc, 0x66, 0x66,
+     0x66, 0x66, 0x66, 0x7c, 0x60, 0x60, 0xf0, 0x00],
+    // 0x71 'q'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x76, 0xcc, 0xcc,
+     0xcc, 0xcc, 0xcc, 0x7c, 0x0c, 0x0c, 0x1e, 0x00],
+    // 0x72 'r'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xdc, 0x76, 0x66,
+     0x60, 0x60, 0x60, 0xf0, 0x00, 0x00, 0x00, 0x00],
+    // 0x73 's'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0xc6, 0x60,
+     0x38, 0x0c, 0xc6, 0x7c, 0x00, 0x00, 0x00, 0x00],
+    // 0x74 't'
+    [0x00, 0x00, 0x10, 0x30, 0x30, 0xfc, 0x30, 0x30,
+     0x30, 0x30, 0x36, 0x1c, 0x00, 0x00, 0x00, 0x00],
+    // 0x75 'u'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xcc, 0xcc, 0xcc,
+     0xcc, 0xcc, 0xcc, 0x76, 0x00, 0x00, 0x00, 0x00],
+    // 0x76 'v'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xc6, 0xc6, 0xc6,
+     0xc6, 0x6c, 0x38, 0x10, 0x00, 0x00, 0x00, 0x00],
+    // 0x77 'w'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xc6, 0xc6, 0xd6,
+     0xd6, 0xd6, 0xfe, 0x6c, 0x00, 0x00, 0x00, 0x00],
+    // 0x78 'x'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xc6, 0x6c, 0x38,
+     0x38, 0x38, 0x6c, 0xc6, 0x00, 0x00, 0x00, 0x00],
+    // 0x79 'y'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xc6, 0xc6, 0xc6,
+     0xc6, 0xc6, 0xc6, 0x7e, 0x06, 0x0c, 0xf8, 0x00],
+    // 0x7A 'z'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0xcc, 0x18,
+     0x30, 0x60, 0xc6, 0xfe, 0x00, 0x00, 0x00, 0x00],
+    // 0x7B '{'
+    [0x00, 0x00, 0x0e, 0x18, 0x18, 0x18, 0x70, 0x18,
+     0x18, 0x18, 0x18, 0x0e, 0x00, 0x00, 0x00, 0x00],
+    // 0x7C '|'
+    [0x00, 0x00, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+     0x18, 0x18, 0x18, 0x18, 0x18, 0x00, 0x00, 0x00],
+    // 0x7D '}'
+    [0x00, 0x00, 0x70, 0x18, 0x18, 0x18, 0x0e, 0x18,
+     0x18, 0x18, 0x18, 0x70, 0x00, 0x00, 0x00, 0x00],
+    // 0x7E '~'
+    [0x00, 0x00, 0x76, 0xdc, 0x00, 0x00, 0x00, 0x00,
+     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+];
@@ -0,0 +1,60 @@
+//
+// Console History - Records everything printed from the first instruction.
+//
+
+use core::fmt;
+use core::str;
+
+use crate::mu::{MuMutex, MuRingBuf};
+
+use super::config::{self, Sink};
+
+
+/// The size in bytes of the console history.
+pub const HISTORY_SIZE: usize = 8 * 1024;
+
+// The console history (Older text is overwritten when it is full).
+static HISTORY: MuMutex<MuRingBuf<u8, HISTORY_SIZE>> =
+    MuMutex::new(MuRingBuf::new(0));
+
+
+// Records printed text in the console history.
+pub(super) fn record(args: fmt::Arguments) {
+    use fmt::Write;
+    let _ = HistoryWriter.write_fmt(args);
+}
+
+struct HistoryWriter;
+
+impl fmt::Write for HistoryWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+	HISTORY.lock().push_slice_overwrite(s.as_bytes());
+	Ok(())
+    }
+}
+
+
+///
+/// Dumps the console history to the sink.
+///
+/// Text printed before the sink becomes available (e.g., before
+/// `early_init` finds a serial port) is otherwise lost.  The history
+/// keeps the last `HISTORY_SIZE` bytes.
+///
+pub fn replay(sink: Sink) {
+    let mut history = HISTORY.lock();
+    let bytes = history.make_contiguous();
+
+    // Skip a partially overwritten UTF-8 character (if any).
+    let start = bytes.iter()
+	.position(|&byte| (byte & 0xc0) != 0x80)
+	.unwrap_or(bytes.len());
+    let text =
+	match str::from_utf8(&bytes[start ..]) {
+	    Ok(text) => text,
+	    Err(e) => str::from_utf8(&bytes[start .. start + e.valid_up_to()])
+		.unwrap_or(""),
+	};
+
+    config::write_to(sink, format_args!("{}", text));
+}
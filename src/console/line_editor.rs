@@ -0,0 +1,99 @@
+//
+// Line Editor - Reads a line from the keyboard with echo and a cursor.
+//
+
+use core::str;
+
+use crate::input::{KEY_QUEUE, Key};
+use crate::print;
+
+
+// Moves the cursor to the left (Both BIOS teletype output and
+// terminals connected to a serial port interpret it).
+const CURSOR_LEFT: &str = "\x08";
+
+
+///
+/// Reads a line from the keyboard into `buf`, and returns the line.
+///
+/// The entered characters are echoed to the console.  The line can
+/// be edited with Backspace, Delete, Left, Right, Home and End.
+/// Enter finishes the line (The newline is not included).  Only
+/// ASCII characters are accepted, and the excess is ignored.
+///
+pub fn read_line(buf: &mut [u8]) -> &str {
+    let mut len = 0;	// Length of the line
+    let mut pos = 0;	// Position of the cursor
+
+    loop {
+	match KEY_QUEUE.wait().key {
+	    Key::Enter => break,
+	    Key::Char(ch) if ch.is_ascii() && !ch.is_ascii_control() => {
+		if len < buf.len() {
+		    buf.copy_within(pos .. len, pos + 1);
+		    buf[pos] = ch as u8;
+		    len += 1;
+		    pos += 1;
+		    print!("{}", ch);
+		    redraw_tail(&buf[pos .. len], 0);
+		}
+	    },
+	    Key::Backspace if pos > 0 => {
+		buf.copy_within(pos .. len, pos - 1);
+		len -= 1;
+		pos -= 1;
+		print!("{}", CURSOR_LEFT);
+		redraw_tail(&buf[pos .. len], 1);
+	    },
+	    Key::Delete if pos < len => {
+		buf.copy_within(pos + 1 .. len, pos);
+		len -= 1;
+		redraw_tail(&buf[pos .. len], 1);
+	    },
+	    Key::Left if pos > 0 => {
+		pos -= 1;
+		print!("{}", CURSOR_LEFT);
+	    },
+	    Key::Right if pos < len => {
+		print!("{}", buf[pos] as char);
+		pos += 1;
+	    },
+	    Key::Home => {
+		move_left(pos);
+		pos = 0;
+	    },
+	    Key::End => {
+		print_ascii(&buf[pos .. len]);
+		pos = len;
+	    },
+	    _ => {},
+	}
+    }
+
+    print!("\r\n");
+
+    // Only ASCII characters are stored in the buffer.
+    str::from_utf8(&buf[.. len]).unwrap_or("")
+}
+
+// Prints the text after the cursor followed by `nerased` spaces, then
+// moves the cursor back.
+fn redraw_tail(tail: &[u8], nerased: usize) {
+    print_ascii(tail);
+    for _ in 0 .. nerased {
+	print!(" ");
+    }
+    move_left(tail.len() + nerased);
+}
+
+// Prints ASCII characters.
+fn print_ascii(text: &[u8]) {
+    print!("{}", str::from_utf8(text).unwrap_or(""));
+}
+
+// Moves the cursor to the left.
+fn move_left(count: usize) {
+    for _ in 0 .. count {
+	print!("{}", CURSOR_LEFT);
+    }
+}
@@ -0,0 +1,167 @@
+//
+// Logger - An implementation of the `log` crate facade (feature `log`).
+//
+
+use core::cmp;
+
+use log::{Level, Log, Metadata, Record, SetLoggerError};
+
+use crate::console;
+use crate::mu::MuMutex;
+
+pub use log::LevelFilter;
+
+
+/// The maximum number of per-module level filters.
+pub const MAX_MODULE_FILTERS: usize = 16;
+
+/// Errors on setting a per-module level filter.
+#[derive(Debug)]
+pub enum FilterError {
+    /// The filter table is full.
+    Full,
+}
+
+
+// A level filter applied to a module and its submodules.
+#[derive(Clone, Copy)]
+struct ModuleFilter {
+    module: &'static str,
+    level: LevelFilter,
+}
+
+impl ModuleFilter {
+    // Returns true if `target` is the module or one of its submodules.
+    fn matches(&self, target: &str) -> bool {
+	match target.strip_prefix(self.module) {
+	    Some(rest) => rest.is_empty() || rest.starts_with("::"),
+	    None => false,
+	}
+    }
+}
+
+// Level filters (Fixed-size because it is used before the global
+// allocator is initialized).
+struct Filters {
+    default: LevelFilter,
+    modules: [Option<ModuleFilter>; MAX_MODULE_FILTERS],
+}
+
+impl Filters {
+    // Returns the level filter of the longest matching module.
+    fn level_of(&self, target: &str) -> LevelFilter {
+	let mut best: Option<ModuleFilter> = None;
+	for filter in self.modules.iter().flatten() {
+	    #[allow(unused_parens)]
+	    if (filter.matches(target) &&
+		best.is_none_or(|b| b.module.len() < filter.module.len())) {
+		best = Some(*filter);
+	    }
+	}
+	best.map_or(self.default, |b| b.level)
+    }
+
+    // Returns the most verbose level filter in the table.
+    fn max_level(&self) -> LevelFilter {
+	self.modules.iter().flatten()
+	    .fold(self.default, |max, filter| cmp::max(max, filter.level))
+    }
+}
+
+
+struct Logger {
+    filters: MuMutex<Filters>,
+}
+
+static LOGGER: Logger = Logger {
+    filters: MuMutex::new(Filters {
+	default: LevelFilter::Info,
+	modules: [None; MAX_MODULE_FILTERS],
+    }),
+};
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+	metadata.level() <= self.filters.lock().level_of(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+	if self.enabled(record.metadata()) {
+	    console::print_at(console_level(record.level()),
+			      format_args!("[{:<5} {}] {}\r\n",
+					   record.level(),
+					   record.target(),
+					   record.args()));
+	}
+    }
+
+    fn flush(&self) {}
+}
+
+
+// Converts a log level into a console level (cf. ConsoleConfig).
+fn console_level(level: Level) -> console::Level {
+    match level {
+	Level::Error => console::Level::Error,
+	Level::Warn => console::Level::Warn,
+	Level::Info => console::Level::Info,
+	Level::Debug => console::Level::Debug,
+	Level::Trace => console::Level::Trace,
+    }
+}
+
+
+///
+/// Installs the logger with the default level filter.
+///
+/// Once it is installed, `log::info!` etc. are printed to the console.
+/// The sinks of each level are configured by `ConsoleConfig`.
+/// It fails if another logger has already been installed.
+///
+pub fn init(level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    set_level(level);
+    Ok(())
+}
+
+/// Sets the default level filter (applied to modules without filters).
+pub fn set_level(level: LevelFilter) {
+    let mut filters = LOGGER.filters.lock();
+    filters.default = level;
+    log::set_max_level(filters.max_level());
+}
+
+///
+/// Sets the level filter of a module and its submodules.
+///
+/// `module` is a module path such as `"nostd_env::bios"`.  When
+/// multiple filters match, the filter of the longest module path wins.
+///
+pub fn set_module_level(module: &'static str, level: LevelFilter)
+			-> Result<(), FilterError> {
+    let mut filters = LOGGER.filters.lock();
+
+    let slot =
+	match filters.modules.iter().position(
+	    |entry| entry.is_some_and(|filter| filter.module == module)) {
+	    Some(index) => index,
+	    None => filters.modules.iter().position(|entry| entry.is_none())
+		.ok_or(FilterError::Full)?,
+	};
+
+    filters.modules[slot] = Some(ModuleFilter { module, level });
+    log::set_max_level(filters.max_level());
+    Ok(())
+}
+
+/// Removes the level filter of a module (if any).
+pub fn clear_module_level(module: &str) {
+    let mut filters = LOGGER.filters.lock();
+
+    for entry in filters.modules.iter_mut() {
+	if entry.is_some_and(|filter| filter.module == module) {
+	    *entry = None;
+	}
+    }
+    log::set_max_level(filters.max_level());
+}
@@ -0,0 +1,68 @@
+/*!
+
+Provides console facilities.
+
+* `early_init` - brings up an early console (16550 UART and/or QEMU
+  debugcon) with zero allocations.  Once it is initialized, output of
+  `print!` and `println!` is also routed to it (or only to it if
+  `console=serial` is given).  The UART is driven by `x86::SerialPort`.
+
+* `ConsoleConfig` - configures the most verbose level printed to each
+  sink (screen, serial and debugcon) at runtime.  For example, debug
+  output (`debug_println!`) can be printed only to serial.  More
+  sinks can be registered by `text_writer::add_console`.
+
+* `replay` - dumps the console history (everything printed from the
+  first instruction) to a sink once it becomes available.
+
+* `init_screen` - selects the backend of the screen: a console on a
+  VBE linear frame buffer (feature `video`), the VGA text buffer, or
+  BIOS teletype output (in order of preference).  The console draws
+  the ROM font of the VGA BIOS, or an embedded 8x16 font if it is not
+  available (cf. `font8x16`).  `init_fb_screen` selects a console on a
+  given frame buffer instead (e.g. GOP in the UEFI boot path).
+
+* `cp437` - transliterates Unicode characters into CP437 for the
+  screen (BIOS teletype output and the VGA text buffer).
+
+* `read_line` - reads a line from the keyboard with echo, backspace
+  handling and a cursor.
+
+* `StatusLine` - owns the bottom row of the screen, and draws progress
+  bars and spinners there without disturbing the output scrolling
+  above it.
+
+* `vga_font` - replaces glyphs of the VGA text mode font with custom
+  symbols (e.g. progress blocks) (feature `video`).
+
+* `logger` - implements the `log` crate facade on top of the console
+  (feature `log`).  The level can be filtered per module at runtime.
+
+ */
+
+
+#[doc(hidden)] pub mod config;
+pub mod cp437;
+#[doc(hidden)] pub mod early;
+#[cfg(feature = "video")] #[doc(hidden)] pub mod fb_console;
+#[cfg(feature = "video")] #[doc(hidden)] pub mod font8x16;
+#[doc(hidden)] pub mod history;
+#[doc(hidden)] pub mod line_editor;
+#[cfg(feature = "log")] pub mod logger;
+#[doc(hidden)] pub mod screen;
+#[doc(hidden)] pub mod status_line;
+#[cfg(feature = "video")] pub mod vga_font;
+#[doc(hidden)] pub mod vga_text;
+
+#[doc(inline)] pub use self::config::{
+    ConsoleConfig, Level, Sink, config, print_at, set_config, set_sink_level,
+};
+#[doc(inline)] pub use self::early::{EarlyPort, early_init, early_port};
+#[doc(inline)] pub use self::history::replay;
+#[doc(inline)] pub use self::line_editor::read_line;
+#[doc(inline)] pub use self::screen::{
+    ScreenKind, init_screen, reset_screen, screen_kind,
+};
+#[cfg(feature = "video")]
+#[doc(inline)] pub use self::screen::init_fb_screen;
+#[doc(inline)] pub use self::status_line::StatusLine;
@@ -0,0 +1,251 @@
+//
+// Screen - Selects the backend of the screen sink.
+//
+// The backend is negotiated by `init_screen`:
+//	1. A VBE linear frame buffer console (feature `video`)
+//	2. The VGA text buffer at 0xB8000 (in text mode 03h)
+//	3. BIOS teletype output (INT 10h AH=0Eh)
+// The command line `screen=<fb|vga|bios>` limits the negotiation to
+// the given backend or below.
+//
+// The frame buffer console draws the ROM font of the VGA BIOS.  If it
+// is not available (or `font=embedded` is given), the embedded font is
+// drawn instead.
+//
+
+use core::alloc::Allocator;
+use core::fmt;
+
+use crate::bios::{self, int10h00h::MODE_TEXT_80X25};
+use crate::cmdline;
+#[cfg(feature = "video")]
+use crate::man_video::FrameBuffer;
+use crate::mu::MuMutex;
+use crate::text_writer::TextWriter;
+
+use super::cp437;
+#[cfg(feature = "video")]
+use super::fb_console::{FbConsole, FbFont};
+use super::vga_text::VgaText;
+
+
+/// Backends of the screen sink.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScreenKind {
+    /// A console on a VBE linear frame buffer
+    FrameBuffer,
+    /// The VGA text buffer at 0xB8000
+    VgaText,
+    /// BIOS Teletype Output
+    Teletype,
+}
+
+impl fmt::Display for ScreenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::FrameBuffer => write!(f, "VBE frame buffer"),
+	    Self::VgaText => write!(f, "VGA text"),
+	    Self::Teletype => write!(f, "BIOS teletype"),
+	}
+    }
+}
+
+
+// The backend of the screen sink with its state.
+enum Screen {
+    #[cfg(feature = "video")]
+    FrameBuffer(FbConsole),
+    VgaText(VgaText),
+    Teletype,
+}
+
+impl Screen {
+    fn kind(&self) -> ScreenKind {
+	match self {
+	    #[cfg(feature = "video")]
+	    Self::FrameBuffer(_) => ScreenKind::FrameBuffer,
+	    Self::VgaText(_) => ScreenKind::VgaText,
+	    Self::Teletype => ScreenKind::Teletype,
+	}
+    }
+}
+
+// BIOS teletype output is used until `init_screen` is called.
+static SCREEN: MuMutex<Screen> = MuMutex::new(Screen::Teletype);
+
+// The size of the frame buffer console (640x480 = 80x30 characters)
+#[cfg(feature = "video")]
+const FB_WIDTH: u16 = 640;
+#[cfg(feature = "video")]
+const FB_HEIGHT: u16 = 480;
+
+
+///
+/// Negotiates the backend of the screen sink, then returns it.
+///
+/// It prefers a VBE linear frame buffer console, falls back to the
+/// VGA text buffer, and finally to BIOS teletype output.
+///
+pub fn init_screen<A20>(alloc20: A20) -> ScreenKind
+where
+    A20: Copy + Allocator,
+{
+    let limit = match cmdline::value("screen") {
+	Some("bios") => ScreenKind::Teletype,
+	Some("vga") => ScreenKind::VgaText,
+	_ => ScreenKind::FrameBuffer,
+    };
+
+    #[cfg(feature = "video")]
+    if limit == ScreenKind::FrameBuffer {
+	if let Some(screen) = find_fb_screen(alloc20) {
+	    *SCREEN.lock() = screen;
+	    return ScreenKind::FrameBuffer;
+	}
+    }
+    #[cfg(not(feature = "video"))]
+    let _ = alloc20;
+
+    if limit != ScreenKind::Teletype && is_text_mode() {
+	*SCREEN.lock() = Screen::VgaText(VgaText::new());
+	return ScreenKind::VgaText;
+    }
+
+    *SCREEN.lock() = Screen::Teletype;
+    ScreenKind::Teletype
+}
+
+/// Returns the current backend of the screen sink.
+pub fn screen_kind() -> ScreenKind {
+    SCREEN.lock().kind()
+}
+
+///
+/// Stops drawing to the frame buffer (if any), and selects the VGA
+/// text buffer (in text mode 03h) or BIOS teletype output.
+///
+/// It is called after the video mode is changed (e.g., on panic).
+/// It does not block even if the screen is locked by the panicking
+/// code.
+///
+pub fn reset_screen() {
+    if let Some(mut screen) = SCREEN.try_lock() {
+	*screen = if is_text_mode() {
+	    Screen::VgaText(VgaText::new())
+	} else {
+	    Screen::Teletype
+	};
+    }
+}
+
+
+//
+// Reserves the bottom row of the screen for the status line (or
+// releases it).  Returns false if the backend has no rows to reserve
+// (BIOS teletype output).
+//
+pub(super) fn reserve_status_row(reserve: bool) -> bool {
+    let mut screen = SCREEN.lock();
+    match &mut *screen {
+	#[cfg(feature = "video")]
+	Screen::FrameBuffer(console) => console.reserve_status_row(reserve),
+	Screen::VgaText(vga) => vga.reserve_status_row(reserve),
+	Screen::Teletype => return false,
+    }
+    true
+}
+
+// Draws CP437 characters in the reserved status row.
+pub(super) fn draw_status_row(text: &[u8]) {
+    let mut screen = SCREEN.lock();
+    match &mut *screen {
+	#[cfg(feature = "video")]
+	Screen::FrameBuffer(console) => console.draw_status(text),
+	Screen::VgaText(vga) => vga.draw_status(text),
+	Screen::Teletype => (),
+    }
+}
+
+// Returns the number of columns of the screen (0 if unknown).
+pub(super) fn status_columns() -> usize {
+    match &*SCREEN.lock() {
+	#[cfg(feature = "video")]
+	Screen::FrameBuffer(console) => console.size().0,
+	Screen::VgaText(vga) => vga.columns(),
+	Screen::Teletype => 0,
+    }
+}
+
+
+/// A writer to the screen sink.
+pub struct ScreenWriter;
+
+impl fmt::Write for ScreenWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+	let mut screen = SCREEN.lock();
+	match &mut *screen {
+	    #[cfg(feature = "video")]
+	    Screen::FrameBuffer(console) => {
+		for ch in s.chars() {
+		    console.write_byte(cp437::encode_or_control(ch));
+		}
+	    },
+	    Screen::VgaText(vga) => {
+		for ch in s.chars() {
+		    vga.write_byte(cp437::encode_or_control(ch));
+		}
+		vga.sync_cursor();
+	    },
+	    Screen::Teletype => {
+		TextWriter.write_ascii_printables(s);
+	    },
+	}
+	Ok(())
+    }
+}
+
+///
+/// Selects a console on the linear frame buffer with the embedded
+/// font (e.g. on the GOP frame buffer in the UEFI boot path, where the
+/// ROM font of the VGA BIOS is not available).  Returns false if the
+/// screen is smaller than a character.
+///
+#[cfg(feature = "video")]
+pub fn init_fb_screen(fb: FrameBuffer) -> bool {
+    match FbConsole::new(fb, FbFont::Embedded) {
+	Some(console) => {
+	    *SCREEN.lock() = Screen::FrameBuffer(console);
+	    true
+	},
+	None => false,
+    }
+}
+
+// Returns true if the current video mode is text mode 03h.
+pub(super) fn is_text_mode() -> bool {
+    bios::int10h0fh::call().is_some_and(|cur| cur.mode == MODE_TEXT_80X25)
+}
+
+// Sets a VBE graphics mode, then creates a console on it (with the ROM
+// font if available, or with the embedded font).
+#[cfg(feature = "video")]
+fn find_fb_screen<A20>(alloc20: A20) -> Option<Screen>
+where
+    A20: Copy + Allocator,
+{
+    use crate::man_video;
+
+    // Get the ROM font before switching to a graphics mode.
+    let font = match cmdline::value("font") {
+	Some("embedded") => None,
+	_ => bios::int10h1130h::call(bios::int10h1130h::FONT_8X16),
+    };
+    let font = font.map_or(FbFont::Embedded, FbFont::Rom);
+
+    let fb = man_video::set_frame_buffer_mode(FB_WIDTH, FB_HEIGHT, alloc20)?;
+    let screen = FbConsole::new(fb, font).map(Screen::FrameBuffer);
+    if screen.is_none() {
+	man_video::restore_text_mode();
+    }
+    screen
+}
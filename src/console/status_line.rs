@@ -0,0 +1,171 @@
+//
+// Status Line - Shows progress on the bottom row of the screen.
+//
+// While a `StatusLine` is alive, the bottom row is excluded from
+// scrolling, so that printed output scrolls above it undisturbed.
+// The row is released (and cleared) when it is dropped.
+//
+//	let mut status = StatusLine::new();
+//	for i in 0 .. n {
+//	    status.progress("Reading", i, n);
+//	    ...
+//	}
+//
+// Only one status line can be alive at a time.  On BIOS teletype
+// output (or while another is alive), it is inactive and draws
+// nothing, so that callers can fall back to printing counters.
+//
+
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::cp437;
+use super::screen;
+
+
+// The maximum number of columns drawn.
+const MAX_COLUMNS: usize = 160;
+
+// CP437 characters of the progress bar
+const BAR_FULL: u8 = 0xdb;		// Full Block
+const BAR_EMPTY: u8 = 0xb0;		// Light Shade
+
+// The width of the progress bar in columns.
+const BAR_WIDTH: usize = 40;
+
+// Frames of the spinner
+const SPINNER: &[u8] = b"|/-\\";
+
+// True while a status line owns the bottom row.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+
+/// A status line on the bottom row of the screen.
+pub struct StatusLine {
+    active: bool,
+    last_progress: Option<(usize, usize)>,	// (Bar, Percent)
+    frame: usize,				// Frame of the spinner
+}
+
+impl StatusLine {
+    /// Reserves the bottom row (inactive if it cannot be reserved).
+    pub fn new() -> Self {
+	let active = ACTIVE.compare_exchange(false, true, Ordering::Acquire,
+					     Ordering::Relaxed).is_ok();
+	let active = active && {
+	    let reserved = screen::reserve_status_row(true);
+	    if !reserved {
+		ACTIVE.store(false, Ordering::Release);
+	    }
+	    reserved
+	};
+
+	Self { active, last_progress: None, frame: 0 }
+    }
+
+    /// Returns true if the status line is drawn.
+    pub fn is_active(&self) -> bool {
+	self.active
+    }
+
+    /// Draws the formatted text (e.g. `format_args!(...)`).
+    pub fn set(&mut self, args: fmt::Arguments<'_>) {
+	if !self.active {
+	    return;
+	}
+	let mut buf = StatusBuf::new();
+	let _ = buf.write_fmt(args);
+	buf.draw();
+	self.last_progress = None;
+    }
+
+    ///
+    /// Draws a progress bar `label [###---] 42%` of `done` out of
+    /// `total`.  It is redrawn only when the bar or the percentage
+    /// changes, so that it can be called on every step.
+    ///
+    pub fn progress(&mut self, label: &str, done: u64, total: u64) {
+	if !self.active {
+	    return;
+	}
+	let total = total.max(1);
+	let done = done.min(total);
+	let bar = (done * BAR_WIDTH as u64 / total) as usize;
+	let percent = (done * 100 / total) as usize;
+	if self.last_progress == Some((bar, percent)) {
+	    return;
+	}
+	self.last_progress = Some((bar, percent));
+
+	let mut buf = StatusBuf::new();
+	let _ = write!(buf, "{} [", label);
+	for i in 0 .. BAR_WIDTH {
+	    buf.push(if i < bar { BAR_FULL } else { BAR_EMPTY });
+	}
+	let _ = write!(buf, "] {:3}%", percent);
+	buf.draw();
+    }
+
+    /// Draws the label with the next frame of a spinner.
+    pub fn spin(&mut self, label: &str) {
+	if !self.active {
+	    return;
+	}
+	let frame = SPINNER[self.frame % SPINNER.len()] as char;
+	self.frame = self.frame.wrapping_add(1);
+	self.set(format_args!("{} {}", label, frame));
+    }
+}
+
+impl Default for StatusLine {
+    fn default() -> Self {
+	Self::new()
+    }
+}
+
+impl Drop for StatusLine {
+    fn drop(&mut self) {
+	if self.active {
+	    screen::reserve_status_row(false);
+	    ACTIVE.store(false, Ordering::Release);
+	}
+    }
+}
+
+
+// A row of CP437 characters to be drawn in the status row.
+struct StatusBuf {
+    bytes: [u8; MAX_COLUMNS],
+    len: usize,
+}
+
+impl StatusBuf {
+    fn new() -> Self {
+	Self { bytes: [b' '; MAX_COLUMNS], len: 0 }
+    }
+
+    fn push(&mut self, byte: u8) {
+	if let Some(slot) = self.bytes.get_mut(self.len) {
+	    *slot = byte;
+	    self.len += 1;
+	}
+    }
+
+    fn draw(&self) {
+	let columns = screen::status_columns().min(MAX_COLUMNS);
+	screen::draw_status_row(&self.bytes[.. self.len.min(columns)]);
+    }
+}
+
+impl fmt::Write for StatusBuf {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+	for ch in s.chars() {
+	    self.push(if ch.is_control() {
+		b' '
+	    } else {
+		cp437::encode(ch).unwrap_or(b'.')
+	    });
+	}
+	Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+/*!
+
+Replaces glyphs of the VGA text mode font.
+
+In text mode 03h, each character is drawn by the glyph of its code in
+the font loaded in the VGA.  [`upload`] replaces some of the glyphs
+(by BIOS INT 10h AX=1110h) so that text output can include custom
+symbols, e.g. finer progress blocks or logo glyphs.
+
+The replaced glyphs are lost when the video mode is set again.
+
+```ignore
+// Replace the glyphs of 0xB0-0xB6 (CP437 shades and box drawings).
+let blocks = vga_font::progress_blocks(0xb0);
+vga_font::upload(&blocks, &ALLOC_UNDER20)?;
+```
+
+ */
+
+use core::alloc::Allocator;
+use core::fmt;
+
+use super::screen::is_text_mode;
+use crate::bios::int10h1110h;
+
+
+/// The height of glyphs in text mode 03h (8x16 pixels).
+pub const GLYPH_HEIGHT: usize = 16;
+
+/// The number of glyphs returned by [`progress_blocks`].
+pub const PROGRESS_STEPS: usize = 7;
+
+
+/// A glyph replacing that of the character code.
+#[derive(Clone, Copy, Debug)]
+pub struct Glyph {
+    pub code: u8,			// Character Code (CP437)
+    pub bitmap: [u8; GLYPH_HEIGHT],	// Rows (MSB is the leftmost pixel)
+}
+
+
+/// Errors returned by [`upload`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VgaFontError {
+    /// The video mode is not text mode 03h.
+    NotTextMode,
+    /// The glyphs could not be placed in 20-bit address space.
+    OutOfMemory,
+}
+
+impl fmt::Display for VgaFontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::NotTextMode =>
+		write!(f, "VGA font: Not in text mode"),
+	    Self::OutOfMemory =>
+		write!(f, "VGA font: Failed to allocate the glyphs"),
+	}
+    }
+}
+
+
+///
+/// Replaces the glyphs of the active font in text mode 03h.
+///
+/// Glyphs of consecutive character codes are loaded by one BIOS call.
+/// The buffers are allocated by `alloc20`, which must allocate memory
+/// in 20-bit address space (e.g., `ALLOC_UNDER20`).
+///
+pub fn upload<A20>(glyphs: &[Glyph], alloc20: A20) -> Result<(), VgaFontError>
+where
+    A20: Copy + Allocator,
+{
+    if !is_text_mode() {
+	return Err(VgaFontError::NotTextMode);
+    }
+
+    let mut rest = glyphs;
+    while let Some(first) = rest.first() {
+	// Count the glyphs of consecutive character codes.
+	let count = 1 + rest.windows(2)
+	    .take_while(| pair | {
+		pair[0].code.checked_add(1) == Some(pair[1].code)
+	    })
+	    .count();
+	let (run, next) = rest.split_at(count);
+
+	let bitmaps = run.iter().flat_map(| glyph | glyph.bitmap);
+	if !int10h1110h::call(first.code, GLYPH_HEIGHT as u8, bitmaps,
+			      alloc20) {
+	    return Err(VgaFontError::OutOfMemory);
+	}
+	rest = next;
+    }
+
+    Ok(())
+}
+
+///
+/// Returns the glyphs of left-aligned blocks 1/8 to 7/8 wide (from
+/// the character code `first`) for progress bars finer than a cell.
+///
+/// The full block is 0xDB in CP437.
+///
+pub fn progress_blocks(first: u8) -> [Glyph; PROGRESS_STEPS] {
+    core::array::from_fn(| i | Glyph {
+	code: first.wrapping_add(i as u8),
+	bitmap: [!(0xff_u8 >> (i + 1)); GLYPH_HEIGHT],
+    })
+}
@@ -0,0 +1,179 @@
+//
+// VGA Text Console - Writes directly to the VGA text buffer at 0xB8000.
+//
+// It is much faster than BIOS teletype output.  The cursor position is
+// shared with BIOS through the BIOS Data Area (BDA) so that BIOS
+// teletype output can continue where it leaves off (and vice versa).
+//
+// Supplementary Resources:
+//	https://wiki.osdev.org/Text_UI
+//	https://wiki.osdev.org/Text_Mode_Cursor
+//
+
+use core::ptr::{read_volatile, write_volatile};
+
+use crate::x86::outb;
+
+
+// VGA Text Buffer (80x25, a character and an attribute per cell)
+const VGA_TEXT_BUFFER: usize = 0xb8000;
+const VGA_COLUMNS: usize = 80;
+const VGA_ROWS: usize = 25;
+
+// Attribute of printed text by default (White on Black)
+const ATTR_TEXT: u8 = 0x0f;
+
+// Attribute of the status row (Black on Light Gray)
+const ATTR_STATUS: u8 = 0x70;
+
+// The cursor position (column, row) of page 0 in the BIOS Data Area.
+const BDA_CURSOR: usize = 0x450;
+
+// CRT Controller Registers
+const CRTC_INDEX: u16 = 0x3d4;
+const CRTC_DATA: u16 = 0x3d5;
+const CRTC_CURSOR_HIGH: u8 = 0x0e;
+const CRTC_CURSOR_LOW: u8 = 0x0f;
+
+
+/// A console writing directly to the VGA text buffer (mode 03h).
+pub struct VgaText {
+    row: usize,
+    col: usize,
+    scroll_rows: usize,		// Rows above the status row (if any)
+    attr: u8,			// Attribute of printed text
+}
+
+impl VgaText {
+    /// Starts at the cursor position of BIOS.
+    pub fn new() -> Self {
+	let (col, row) = unsafe {
+	    (read_volatile(BDA_CURSOR as *const u8) as usize,
+	     read_volatile((BDA_CURSOR + 1) as *const u8) as usize)
+	};
+
+	Self {
+	    row: row.min(VGA_ROWS - 1),
+	    col: col.min(VGA_COLUMNS - 1),
+	    scroll_rows: VGA_ROWS,
+	    attr: ATTR_TEXT,
+	}
+    }
+
+    /// Sets the attribute of the characters written after (the
+    /// background in bits 4-7 and the foreground in bits 0-3).
+    pub fn set_attr(&mut self, attr: u8) {
+	self.attr = attr;
+    }
+
+    /// Returns the number of columns.
+    pub fn columns(&self) -> usize {
+	VGA_COLUMNS
+    }
+
+    ///
+    /// Reserves the bottom row for the status (or releases it).  The
+    /// reserved row is excluded from scrolling.
+    ///
+    pub fn reserve_status_row(&mut self, reserve: bool) {
+	if reserve && self.scroll_rows == VGA_ROWS {
+	    // Move the output above the status row.
+	    if self.row == VGA_ROWS - 1 {
+		self.scroll_up();
+		self.row -= 1;
+	    }
+	    self.scroll_rows = VGA_ROWS - 1;
+	} else if !reserve {
+	    self.scroll_rows = VGA_ROWS;
+	}
+	self.draw_status(b"");
+	self.sync_cursor();
+    }
+
+    /// Draws CP437 characters in the status row (padded with spaces).
+    pub fn draw_status(&mut self, text: &[u8]) {
+	let attr =
+	    if self.scroll_rows < VGA_ROWS {
+		ATTR_STATUS
+	    } else {
+		self.attr
+	    };
+	for col in 0 .. VGA_COLUMNS {
+	    let byte = text.get(col).copied().unwrap_or(b' ');
+	    self.put_attr(VGA_ROWS - 1, col, byte, attr);
+	}
+    }
+
+    /// Writes a CP437 character (CR, LF and BS are interpreted).
+    pub fn write_byte(&mut self, byte: u8) {
+	match byte {
+	    b'\r' => self.col = 0,
+	    b'\n' => self.new_line(),
+	    b'\x08' => self.col = self.col.saturating_sub(1),
+	    _ => {
+		if self.col >= VGA_COLUMNS {
+		    self.col = 0;
+		    self.new_line();
+		}
+		self.put(self.row, self.col, byte);
+		self.col += 1;
+	    },
+	}
+    }
+
+    /// Moves the hardware cursor (and that of BIOS) to the position.
+    pub fn sync_cursor(&self) {
+	let col = self.col.min(VGA_COLUMNS - 1);
+	let pos = (self.row * VGA_COLUMNS + col) as u16;
+	unsafe {
+	    outb(CRTC_INDEX, CRTC_CURSOR_HIGH);
+	    outb(CRTC_DATA, (pos >> 8) as u8);
+	    outb(CRTC_INDEX, CRTC_CURSOR_LOW);
+	    outb(CRTC_DATA, pos as u8);
+
+	    write_volatile(BDA_CURSOR as *mut u8, col as u8);
+	    write_volatile((BDA_CURSOR + 1) as *mut u8, self.row as u8);
+	}
+    }
+
+    fn put(&mut self, row: usize, col: usize, byte: u8) {
+	self.put_attr(row, col, byte, self.attr);
+    }
+
+    fn put_attr(&mut self, row: usize, col: usize, byte: u8, attr: u8) {
+	let offset = (row * VGA_COLUMNS + col) * 2;
+	let cell = (VGA_TEXT_BUFFER + offset) as *mut u16;
+	unsafe {
+	    write_volatile(cell, (attr as u16) << 8 | byte as u16);
+	}
+    }
+
+    fn new_line(&mut self) {
+	if self.row + 1 < self.scroll_rows {
+	    self.row += 1;
+	} else {
+	    self.scroll_up();
+	}
+    }
+
+    // Scrolls up by a line, then clears the bottom line (above the
+    // status row if reserved).
+    fn scroll_up(&mut self) {
+	let buf = VGA_TEXT_BUFFER as *mut u16;
+	for i in 0 .. (self.scroll_rows - 1) * VGA_COLUMNS {
+	    unsafe {
+		let cell = read_volatile(buf.add(i + VGA_COLUMNS));
+		write_volatile(buf.add(i), cell);
+	    }
+	}
+	for col in 0 .. VGA_COLUMNS {
+	    self.put(self.scroll_rows - 1, col, b' ');
+	}
+    }
+}
+
+impl Default for VgaText {
+    fn default() -> Self {
+	Self::new()
+    }
+}
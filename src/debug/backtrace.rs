@@ -0,0 +1,103 @@
+//
+// Backtrace - Walks the chain of frame pointers (RBP).
+//
+// Note: Frame pointers are always preserved because "frame-pointer"
+//       is set to "always" in config/x86_64-unknown-none.json.
+//
+// Note: Function names are shown if the symbol table is embedded
+//       (cf. src/debug/symbols.rs).
+//
+
+use core::arch::asm;
+use core::fmt;
+
+use crate::bios::ffi;
+use super::symbols;
+
+
+/// The maximum number of frames recorded in a backtrace.
+pub const MAX_FRAMES: usize = 16;
+
+
+/// A backtrace (a list of return addresses).
+pub struct Backtrace {
+    frames: [usize; MAX_FRAMES],
+    len: usize,
+}
+
+impl Backtrace {
+    /// Captures the backtrace of the caller.
+    #[inline(never)]
+    pub fn capture() -> Self {
+	let rbp: usize;
+	unsafe {
+	    asm!("mov {}, rbp", out(reg) rbp,
+		 options(nomem, nostack, preserves_flags));
+	}
+
+	let (stack_start, stack_end) = unsafe {
+	    (&ffi::__lmb_stack_start as *const u8 as usize,
+	     &ffi::__lmb_stack_end as *const u8 as usize)
+	};
+
+	Self::walk(rbp, stack_start, stack_end)
+    }
+
+    /// Walks the chain of frame pointers starting at `rbp`
+    /// within the stack area from `stack_start` to `stack_end - 1`.
+    pub fn walk(rbp: usize, stack_start: usize, stack_end: usize) -> Self {
+	let mut backtrace = Self {
+	    frames: [0; MAX_FRAMES],
+	    len: 0,
+	};
+
+	// Each frame holds the saved RBP at [RBP] and
+	// the return address at [RBP + 8].
+	let mut cur_rbp = rbp;
+	while backtrace.len < MAX_FRAMES {
+	    #[allow(unused_parens)]
+	    if (cur_rbp < stack_start || cur_rbp + 16 > stack_end ||
+		(cur_rbp & 0x7) != 0) {
+		break;
+	    }
+
+	    let (next_rbp, ret_addr) = unsafe {
+		let frame = cur_rbp as *const usize;
+		(*frame, *frame.add(1))
+	    };
+	    if ret_addr == 0 {
+		break;
+	    }
+
+	    backtrace.frames[backtrace.len] = ret_addr;
+	    backtrace.len += 1;
+
+	    // The stack grows downward.  Hence, the previous frame must
+	    // be at a higher address.
+	    if next_rbp <= cur_rbp {
+		break;
+	    }
+	    cur_rbp = next_rbp;
+	}
+
+	backtrace
+    }
+
+    /// Returns the return addresses.
+    pub fn frames(&self) -> &[usize] {
+	&self.frames[.. self.len]
+    }
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "Backtrace:")?;
+	for addr in self.frames() {
+	    write!(f, " {:#x}", addr)?;
+	    if let Some(symbol) = symbols::lookup(*addr) {
+		write!(f, " <{}>", symbol)?;
+	    }
+	}
+	Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+/*!
+
+Crash Log - A log preserved across warm reboot.
+
+The panic handler writes the last panic message, registers and
+backtrace into the crash log area ( `__lmb_crashlog_start` -
+`__lmb_crashlog_end` - 1 defined in the linker script ) with a magic
+header.  Because the contents of memory survive a warm reboot (e.g.
+a reset of QEMU or a triple fault), the crash log can be printed on
+the next boot.
+
+ */
+
+
+use core::fmt;
+use core::mem::size_of;
+use core::slice;
+use core::str;
+
+use crate::bios::ffi;
+use crate::man_region;
+use crate::util::sum32;
+
+
+/// Header of the crash log area.
+#[repr(C)]
+struct CrashLogHeader {
+    magic: u64,		//00-07: Magic "LMBCRASH"
+    length: u32,	//08-0B: Length in bytes of the log text
+    checksum: u32,	//0C-0F: Sum of the bytes of the log text
+}
+
+const _: () = assert!(size_of::<CrashLogHeader>() == 0x10);
+
+/// Magic "LMBCRASH" in little-endian.
+const MAGIC: u64 = 0x4853_4152_4342_4d4c;
+
+
+///
+/// Registers the crash log area in the region manager, and takes
+/// the crash log written in the previous boot (if any).
+///
+/// The returned crash log is taken.  That is, it will not be
+/// returned again on the next boot.
+///
+pub fn init() -> Option<&'static str> {
+    let (start, size) = area();
+    let _ = man_region::register("Crash Log", start, size);
+
+    take()
+}
+
+/// Takes the crash log written in the previous boot (if any).
+pub fn take() -> Option<&'static str> {
+    let (header, text) = unsafe { header_and_text()? };
+
+    if header.magic != MAGIC || header.length as usize > text.len() {
+	return None;
+    }
+
+    let text = &text[.. header.length as usize];
+    if sum32(text) != header.checksum {
+	return None;
+    }
+
+    // Invalidate the crash log so that it is not taken again.
+    header.magic = 0;
+
+    // The log text may be truncated in the middle of a character.
+    match str::from_utf8(text) {
+	Ok(s) => Some(s),
+	Err(e) => str::from_utf8(&text[.. e.valid_up_to()]).ok(),
+    }
+}
+
+///
+/// Saves the crash log.
+///
+/// It is intended to be called from the panic handler.  Hence, it
+/// does not allocate memory.  The text is truncated if it is longer
+/// than the crash log area.
+///
+pub fn save(args: fmt::Arguments) {
+    let Some((header, text)) = (unsafe { header_and_text() }) else {
+	return;		// The crash log area is not available.
+    };
+
+    // Invalidate the crash log while writing.
+    header.magic = 0;
+
+    let mut writer = CrashLogWriter { buf: text, len: 0 };
+    let _ = fmt::write(&mut writer, args);
+    let length = writer.len;
+
+    header.length = length as u32;
+    header.checksum = sum32(&writer.buf[.. length]);
+    header.magic = MAGIC;
+}
+
+
+// Returns the address and the size of the crash log area.
+fn area() -> (usize, usize) {
+    unsafe {
+	let start = &ffi::__lmb_crashlog_start as *const u8 as usize;
+	let end = &ffi::__lmb_crashlog_end as *const u8 as usize;
+	(start, end - start)
+    }
+}
+
+// Returns the header and the text area of the crash log area.
+// Returns None if the area is too small (e.g., in the UEFI boot path).
+unsafe fn header_and_text() -> Option<(&'static mut CrashLogHeader,
+				       &'static mut [u8])> {
+    let (start, size) = area();
+    let header_size = size_of::<CrashLogHeader>();
+    if size < header_size {
+	return None;
+    }
+    Some((&mut *(start as *mut CrashLogHeader),
+	  slice::from_raw_parts_mut((start + header_size) as *mut u8,
+				    size - header_size)))
+}
+
+// A writer to the crash log area (Excess text is discarded).
+struct CrashLogWriter {
+    buf: &'static mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for CrashLogWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+	let bytes = s.as_bytes();
+	let n = core::cmp::min(bytes.len(), self.buf.len() - self.len);
+	self.buf[self.len .. self.len + n].copy_from_slice(&bytes[.. n]);
+	self.len += n;
+	Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+//
+// Heap Assertions - Assertions that dump the state of a heap on failure.
+//
+
+use core::fmt;
+use core::panic::Location;
+
+use crate::mu::{MuAlloc, MuHeapIndex};
+use crate::println;
+
+
+/// The maximum number of blocks printed on failure.
+const MAX_DUMP_BLOCKS: usize = 32;
+
+///
+/// Asserts that a boolean expression is true.  On failure, it dumps
+/// the state of the heap managed by the allocator before panicking.
+///
+/// ```ignore
+/// heap_assert!(&GLOBAL_ALLOC, ptr.is_aligned_to(align),
+///              "layout={:?}, ptr={:p}", layout, ptr);
+/// ```
+///
+#[macro_export]
+macro_rules! heap_assert {
+    ($alloc:expr, $cond:expr $(,)?) => {
+	if !$cond {
+	    $crate::debug::heap_assert::failed(
+		$alloc,
+		format_args!(concat!("assertion failed: ", stringify!($cond))))
+	}
+    };
+    ($alloc:expr, $cond:expr, $($arg:tt)+) => {
+	if !$cond {
+	    $crate::debug::heap_assert::failed(
+		$alloc,
+		format_args!("assertion failed: {}: {}",
+			     stringify!($cond), format_args!($($arg)+)))
+	}
+    };
+}
+
+///
+/// Asserts that two expressions are equal.  On failure, it dumps
+/// the state of the heap managed by the allocator before panicking.
+///
+#[macro_export]
+macro_rules! heap_assert_eq {
+    ($alloc:expr, $left:expr, $right:expr $(,)?) => {
+	match (&$left, &$right) {
+	    (left, right) => if !(*left == *right) {
+		$crate::debug::heap_assert::failed(
+		    $alloc,
+		    format_args!("assertion `left == right` failed\r\n  \
+				  left: {:?}\r\n right: {:?}", left, right))
+	    }
+	}
+    };
+    ($alloc:expr, $left:expr, $right:expr, $($arg:tt)+) => {
+	match (&$left, &$right) {
+	    (left, right) => if !(*left == *right) {
+		$crate::debug::heap_assert::failed(
+		    $alloc,
+		    format_args!("assertion `left == right` failed: {}\r\n  \
+				  left: {:?}\r\n right: {:?}",
+				 format_args!($($arg)+), left, right))
+	    }
+	}
+    };
+}
+
+///
+/// Prints the failing context and the state of the heap, validates
+/// the heap, then panics.  (Called by `heap_assert!` and
+/// `heap_assert_eq!`)
+///
+#[track_caller]
+pub fn failed<I>(alloc: &MuAlloc<I>, context: fmt::Arguments) -> !
+where
+    I: MuHeapIndex
+{
+    let location = Location::caller();
+    println!("Heap {} at {}", context, location);
+
+    // The heap may be locked by the caller (e.g., inside the allocator).
+    match alloc.try_lock() {
+	Some(heap) => {
+	    println!("  {}", heap.usage());
+	    println!("  {}", heap.fragmentation());
+	    println!("  {:?}", heap.stat());
+
+	    let mut count = 0;
+	    heap.walk(|block| {
+		if count < MAX_DUMP_BLOCKS {
+		    println!("  {:#x}+{:#x} {}", block.addr, block.size,
+			     if block.in_use { "in use" } else { "free" });
+		}
+		count += 1;
+	    });
+	    if count > MAX_DUMP_BLOCKS {
+		println!("  ... ({} blocks)", count);
+	    }
+
+	    // Prints the first inconsistency (if any).
+	    if let Err(err) = heap.validate() {
+		println!("  {}", err);
+	    }
+	},
+	None => {
+	    println!("  (the heap is locked)");
+	},
+    }
+
+    panic!("heap {} at {}", context, location);
+}
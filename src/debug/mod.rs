@@ -0,0 +1,33 @@
+/*!
+
+Provides debugging facilities.
+
+* `Backtrace` - A backtrace captured by walking frame pointers.
+* `crash_log` - A crash log preserved across warm reboot.
+* `heap_assert!` / `heap_assert_eq!` - Assertions that dump the state
+  of a heap (usage, statistics and blocks) on failure.
+* `panic_screen` - A red-background VGA text screen showing a panic.
+* `post_code` - Writes progress codes of the initialization to I/O
+  port 0x80 (for a POST code card or QEMU tracing).
+* `restore_text_mode` - Returns the display to the text mode by the
+  hook registered by `set_text_mode_hook` (e.g., before a panic is
+  printed).
+* `symbols` - A symbol table embedded in the boot image to show
+  function names in backtraces (feature `symbols`).
+
+ */
+
+
+#[doc(hidden)] pub mod backtrace;
+pub mod crash_log;
+#[doc(hidden)] pub mod heap_assert;
+pub mod panic_screen;
+#[doc(hidden)] pub mod post_code;
+pub mod symbols;
+#[doc(hidden)] pub mod text_mode;
+
+#[doc(inline)] pub use self::backtrace::Backtrace;
+#[doc(inline)] pub use self::post_code::{last_post_code, post_code};
+#[doc(inline)] pub use self::text_mode::{
+    restore_text_mode, set_text_mode_hook,
+};
@@ -0,0 +1,96 @@
+//
+// Panic Screen - Shows a panic on a red-background VGA text screen.
+//
+
+use core::fmt;
+use core::ptr::write_volatile;
+
+use crate::console::cp437;
+
+
+// VGA Text Buffer (80x25, a character and an attribute per cell)
+const VGA_TEXT_BUFFER: usize = 0xb8000;
+const VGA_COLUMNS: usize = 80;
+const VGA_ROWS: usize = 25;
+
+// Attributes
+const ATTR_BODY: u8 = 0x4f;	// White on Red
+const ATTR_TITLE: u8 = 0x74;	// Red on Light Gray
+
+
+///
+/// Shows a panic on a red-background VGA text screen.
+///
+/// It stops the frame buffer console (if any), and returns the display
+/// to the standard 80x25 text mode first (by the hook registered by
+/// `set_text_mode_hook`).  Otherwise, the panic would
+/// be invisible.  It does not allocate memory.
+///
+pub fn show(args: fmt::Arguments) {
+    crate::console::reset_screen();
+    super::restore_text_mode();
+
+    let mut screen = PanicScreen { row: 0, col: 0, attr: ATTR_BODY };
+    screen.clear();
+
+    use fmt::Write;
+    screen.attr = ATTR_TITLE;
+    let _ = screen.write_str(" PANIC \r\n\r\n");
+    screen.attr = ATTR_BODY;
+    let _ = screen.write_fmt(args);
+}
+
+
+// A writer to the VGA text buffer (Excess text is discarded).
+struct PanicScreen {
+    row: usize,
+    col: usize,
+    attr: u8,
+}
+
+impl PanicScreen {
+    // Fills the screen with spaces.
+    fn clear(&mut self) {
+	for row in 0 .. VGA_ROWS {
+	    for col in 0 .. VGA_COLUMNS {
+		self.put(row, col, b' ');
+	    }
+	}
+    }
+
+    // Puts a character at (row, col).
+    fn put(&mut self, row: usize, col: usize, ch: u8) {
+	let offset = (row * VGA_COLUMNS + col) * 2;
+	let cell = (VGA_TEXT_BUFFER + offset) as *mut u16;
+	unsafe {
+	    write_volatile(cell, (self.attr as u16) << 8 | ch as u16);
+	}
+    }
+
+    fn new_line(&mut self) {
+	self.row += 1;
+	self.col = 0;
+    }
+}
+
+impl fmt::Write for PanicScreen {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+	for ch in s.chars() {
+	    match ch {
+		'\r' => self.col = 0,
+		'\n' => self.new_line(),
+		_ => {
+		    if self.col >= VGA_COLUMNS {
+			self.new_line();
+		    }
+		    if self.row < VGA_ROWS {
+			let byte = cp437::encode(ch).unwrap_or(b'.');
+			self.put(self.row, self.col, byte);
+			self.col += 1;
+		    }
+		},
+	    }
+	}
+	Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+//
+// POST Code - Writes progress codes to I/O port 0x80.
+//
+// The runtime writes a code at each milestone of initialization, so
+// that a hang can be localized with no console at all: on real
+// hardware by a POST code card (or the two-digit display of the
+// mainboard), and on QEMU by tracing I/O port writes (e.g.,
+// `-trace cpu_out` or `-d trace:...`).
+//
+// The codes of the runtime are in 0xB0-0xBF and 0xEE, away from the
+// usual codes of the firmware.
+//
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::x86;
+
+
+// The POST code port (also used as a short I/O delay by firmware)
+const POST_PORT: u16 = 0x80;
+
+/// The Rust world is entered (and the image is verified).
+pub const POST_ENTRY: u8 = 0xb0;
+/// The early console is up.
+pub const POST_CONSOLE: u8 = 0xb1;
+/// The clock is anchored and the TSC is calibrated.
+pub const POST_TIME: u8 = 0xb2;
+/// The global allocator is up.
+pub const POST_HEAP: u8 = 0xb3;
+/// VBE is queried and the screen is selected.
+pub const POST_SCREEN: u8 = 0xb4;
+/// The firmware tables and the PCI devices are enumerated.
+pub const POST_PLATFORM: u8 = 0xb5;
+/// The drives are enumerated.
+pub const POST_DISK: u8 = 0xb6;
+/// The user main (the tests) is entered.
+pub const POST_MAIN: u8 = 0xb7;
+/// The runtime is finishing (exit or halt).
+pub const POST_FINISH: u8 = 0xbf;
+/// A panic occurred.
+pub const POST_PANIC: u8 = 0xee;
+
+
+// The last code written (0 if none)
+static LAST_POST_CODE: AtomicU8 = AtomicU8::new(0);
+
+
+/// Writes the progress code to I/O port 0x80.
+pub fn post_code(code: u8) {
+    LAST_POST_CODE.store(code, Ordering::Relaxed);
+    unsafe {
+	x86::outb(POST_PORT, code);
+    }
+}
+
+/// Returns the last code written by [`post_code`] (0 if none).
+pub fn last_post_code() -> u8 {
+    LAST_POST_CODE.load(Ordering::Relaxed)
+}
@@ -0,0 +1,90 @@
+/*!
+
+Provides a symbol table embedded in the boot image (feature `symbols`).
+
+The symbol table is a statically allocated buffer starting with the
+magic "LMBSYMS:".  It is patched in the boot image by
+`patch-symbols.sh` (called by `run-qemu.sh`) with the function
+symbols of the linked ELF file.  Then backtraces and panic output
+show function names instead of raw addresses.
+
+The table is a text of lines `<address in hex> <name>\n` sorted by
+address.  Names are demangled, and their hashes are removed.
+
+Without the feature `symbols`, no buffer is embedded and [`lookup`]
+always returns `None`.
+
+ */
+
+
+/// The size in bytes of the symbol table (excluding the magic).
+pub const SYMBOLS_SIZE: usize = 16 * 1024;
+
+#[cfg(feature = "symbols")]
+#[repr(C)]
+struct Symbols {
+    magic: [u8; 8],			// Magic "LMBSYMS:"
+    text: [u8; SYMBOLS_SIZE],		// NUL-terminated (unless full)
+}
+
+// Note: It is mutable so that the compiler does not assume its contents
+//       because it is patched after the build.
+#[cfg(feature = "symbols")]
+#[used]
+static mut LMB_SYMBOLS: Symbols = Symbols {
+    magic: *b"LMBSYMS:",
+    text: [0; SYMBOLS_SIZE],
+};
+
+
+/// A function symbol containing an address.
+#[derive(Clone, Copy, Debug)]
+pub struct Symbol {
+    pub name: &'static str,
+    pub addr: usize,	// Start address of the function
+    pub offset: usize,	// Offset of the address looked up
+}
+
+impl core::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+	write!(f, "{}+{:#x}", self.name, self.offset)
+    }
+}
+
+
+/// Returns true if the symbol table is embedded and patched.
+pub fn is_available() -> bool {
+    !table().is_empty()
+}
+
+/// Returns the function symbol containing the address (if any).
+pub fn lookup(addr: usize) -> Option<Symbol> {
+    let mut found = None;
+    for line in table().lines() {
+	let Some((start, name)) = line.split_once(' ') else { continue };
+	let Ok(start) = usize::from_str_radix(start, 16) else { continue };
+	if start > addr {
+	    break;
+	}
+	found = Some((start, name));
+    }
+
+    found.map(|(start, name)| Symbol {
+	name,
+	addr: start,
+	offset: addr - start,
+    })
+}
+
+
+#[cfg(feature = "symbols")]
+fn table() -> &'static str {
+    let text = unsafe { &*core::ptr::addr_of!(LMB_SYMBOLS.text) };
+    let len = text.iter().position(|&byte| byte == 0).unwrap_or(text.len());
+    core::str::from_utf8(&text[.. len]).unwrap_or("")
+}
+
+#[cfg(not(feature = "symbols"))]
+fn table() -> &'static str {
+    ""
+}
@@ -0,0 +1,30 @@
+//
+// Text Mode Hook - Returns the display to the text mode before printing
+// a panic or a test result.
+//
+// Note: The hook is registered by the video manager (if any) so that
+//       the panic handler and the test runner do not depend on it.
+//
+
+use core::mem::transmute;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+
+// The hook (a `fn()`), or null if it is not registered.
+static TEXT_MODE_HOOK: AtomicPtr<()> = AtomicPtr::new(null_mut());
+
+
+/// Registers the hook to return the display to the text mode.
+pub fn set_text_mode_hook(hook: fn()) {
+    TEXT_MODE_HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Returns the display to the text mode by calling the hook (if any).
+pub fn restore_text_mode() {
+    let hook = TEXT_MODE_HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+	let hook: fn() = unsafe { transmute(hook) };
+	hook();
+    }
+}
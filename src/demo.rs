@@ -0,0 +1,296 @@
+/*!
+
+Runs demos selected at boot.
+
+Each demo exercises a subsystem, and is registered by name in
+[`DEMOS`]:
+
+* `diskio` - Disk I/O via INT 13h, and the integrity of the boot
+  image read back from the boot drive (feature `disk`).
+* `sieve` - The heap stress scenario (`Sieve of Eratosthenes` by
+  default, cf. `heap_scenario=`), then the allocator fuzzer.
+* `vbe` - The VBE controller and the best graphics mode (feature
+  `video`).
+* `gfx-pattern` - Draws a test pattern in a VBE graphics mode, then
+  returns to the previous mode (feature `video`).
+* `memtest` - Tests the free memory (cf. [`memtest`](crate::memtest)).
+  It destroys the contents.
+
+The demos are selected by the command line argument
+`demo=<name>[,<name>...]` (or `demo=all`).  `demo=menu` lists the
+demos, then reads the selection from the keyboard.  Without `demo=`,
+[`DEFAULT_DEMOS`] run (and `memtest` if `memtest` is given).
+
+```ignore
+let ctx = demo::DemoContext { memory_map: boot_info.memory_map(), seed };
+demo::run_selected(&ctx);
+```
+
+ */
+
+
+use alloc::vec::Vec;
+
+use crate::bios::int15he820h::AddrRange;
+use crate::console;
+use crate::man_heap::GLOBAL_ALLOC;
+#[cfg(any(feature = "disk", feature = "video"))]
+use crate::man_heap::ALLOC_UNDER20;
+#[cfg(feature = "disk")]
+use crate::man_heap::ALLOC_UNDER16_OR_20;
+use crate::memtest;
+use crate::test_alloc;
+#[cfg(feature = "disk")]
+use crate::test_diskio;
+use crate::testing;
+use crate::util::Config;
+use crate::{print, println};
+
+
+/// The demos run if no demo is selected by the command line.
+pub const DEFAULT_DEMOS: [&str; 2] = ["diskio", "sieve"];
+
+// The size of the line read by the menu.
+const MENU_LINE_SIZE: usize = 80;
+
+// The size of the graphics mode of `vbe` and `gfx-pattern`
+#[cfg(feature = "video")]
+const GFX_WIDTH: u16 = 640;
+#[cfg(feature = "video")]
+const GFX_HEIGHT: u16 = 480;
+#[cfg(feature = "video")]
+const GFX_BPP: u8 = 32;
+
+// How long the test pattern is shown.
+#[cfg(feature = "video")]
+const GFX_PATTERN_DURATION: crate::time::Duration =
+    crate::time::Duration::from_secs(2);
+
+
+/// The context given to demos.
+pub struct DemoContext<'a> {
+    pub memory_map: &'a [AddrRange],	// The Memory Map
+    pub seed: u64,			// The Seed of Randomized Tests
+}
+
+/// A named demo.
+#[derive(Clone, Copy)]
+pub struct Demo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub run: fn(&DemoContext),
+}
+
+/// The registered demos (in the order of running).
+pub const DEMOS: &[Demo] = &[
+    #[cfg(feature = "disk")]
+    Demo {
+	name: "diskio",
+	description: "Disk I/O via INT 13h",
+	run: run_diskio,
+    },
+    Demo {
+	name: "sieve",
+	description: "Heap stress scenario and fuzzer",
+	run: run_sieve,
+    },
+    #[cfg(feature = "video")]
+    Demo {
+	name: "vbe",
+	description: "VBE controller and modes",
+	run: run_vbe,
+    },
+    #[cfg(feature = "video")]
+    Demo {
+	name: "gfx-pattern",
+	description: "Test pattern in a graphics mode",
+	run: run_gfx_pattern,
+    },
+    Demo {
+	name: "memtest",
+	description: "Free memory test (destructive)",
+	run: run_memtest,
+    },
+];
+
+impl Demo {
+    /// Returns the registered demo of the name.
+    pub fn find(name: &str) -> Option<&'static Self> {
+	DEMOS.iter().find(|demo| demo.name == name)
+    }
+}
+
+
+///
+/// Returns the demos selected by the command line (in the order of
+/// `DEMOS`).  Unknown names are reported and ignored.
+///
+pub fn selected() -> Vec<&'static Demo> {
+    match crate::cmdline::value("demo") {
+	Some("menu") => select_by_menu(),
+	Some(names) => select(names),
+	None => {
+	    let mut demos = defaults();
+	    if Config::cmdline().contains("memtest") {
+		demos.extend(Demo::find("memtest"));
+	    }
+	    demos
+	},
+    }
+}
+
+/// Runs the demos selected by the command line.
+pub fn run_selected(ctx: &DemoContext) {
+    for demo in selected() {
+	println!("Demo: {} - {}", demo.name, demo.description);
+	(demo.run)(ctx);
+    }
+}
+
+// Selects the demos of the names (separated by commas or spaces).
+// Demos can also be selected by their numbers in the menu (from 1).
+fn select(names: &str) -> Vec<&'static Demo> {
+    let names = names.split([',', ' ']).filter(|name| !name.is_empty());
+    let mut chosen = [false; DEMOS.len()];
+    for name in names {
+	if name == "all" {
+	    chosen = [true; DEMOS.len()];
+	    continue;
+	}
+	let index = DEMOS.iter().position(|demo| demo.name == name)
+	    .or_else(|| name.parse::<usize>().ok()?.checked_sub(1));
+	match index.and_then(|i| chosen.get_mut(i)) {
+	    Some(flag) => *flag = true,
+	    None => println!("Demo: Unknown demo {}", name),
+	}
+    }
+
+    DEMOS.iter().zip(chosen)
+	.filter_map(|(demo, chosen)| chosen.then_some(demo))
+	.collect()
+}
+
+// Returns the default demos (excluding those left out by features).
+fn defaults() -> Vec<&'static Demo> {
+    DEMOS.iter().filter(|demo| DEFAULT_DEMOS.contains(&demo.name)).collect()
+}
+
+// Lists the demos, then reads the selection from the keyboard.
+fn select_by_menu() -> Vec<&'static Demo> {
+    println!("Demos:");
+    for (i, demo) in DEMOS.iter().enumerate() {
+	println!("  {}. {:12} {}", i + 1, demo.name, demo.description);
+    }
+    print!("Select demos (names or numbers, Enter for the defaults): ");
+
+    let mut buf = [0; MENU_LINE_SIZE];
+    let line = console::read_line(&mut buf);
+    println!();
+    if line.trim().is_empty() {
+	defaults()
+    } else {
+	select(line)
+    }
+}
+
+
+// Demo: disk I/O (the stack usages of BIOS are also checked)
+#[cfg(feature = "disk")]
+fn run_diskio(_ctx: &DemoContext) {
+    test_diskio::try_get_emulation_status(ALLOC_UNDER16_OR_20);
+    test_diskio::try_read_sectors1(ALLOC_UNDER16_OR_20);
+    test_diskio::try_read_sectors2(ALLOC_UNDER16_OR_20);
+    test_diskio::verify_image(&ALLOC_UNDER20);
+}
+
+// Demo: allocator and heap manager
+// (The scenario can be selected by the command line, and a failure
+// can be reproduced by passing the printed seed as `seed=<number>`.)
+fn run_sieve(ctx: &DemoContext) {
+    test_alloc::Scenario::from_cmdline().run(ctx.seed);
+    test_alloc::fuzz(ctx.seed, 10000, &GLOBAL_ALLOC);
+}
+
+// Demo: the VBE controller and the best mode for the frame buffer
+// console
+#[cfg(feature = "video")]
+fn run_vbe(_ctx: &DemoContext) {
+    use crate::bios;
+    use crate::man_video::{self, VbeMode};
+
+    match bios::int10h4f00h::call(&ALLOC_UNDER20) {
+	Some(info) => info.print(),
+	None => {
+	    testing::skip("vbe", "VBE is not available");
+	    return;
+	},
+    }
+
+    let found = man_video::find_graphics_mode(GFX_WIDTH, GFX_HEIGHT,
+					       GFX_BPP, &ALLOC_UNDER20);
+    if let Some(mode) = found {
+	print!("Best ");
+	VbeMode { mode }.print(&ALLOC_UNDER20);
+    }
+    testing::report("vbe", found.map(drop).ok_or("no graphics mode"));
+}
+
+// Demo: draws color bars over a gray ramp in a graphics mode, then
+// returns to the previous mode.
+#[cfg(feature = "video")]
+fn run_gfx_pattern(_ctx: &DemoContext) {
+    use crate::man_video;
+    use crate::time;
+
+    // Colors (0x00RRGGBB)
+    const BARS: [u32; 8] = [
+	0x00ffffff,	// White
+	0x00ffff00,	// Yellow
+	0x0000ffff,	// Cyan
+	0x0000ff00,	// Green
+	0x00ff00ff,	// Magenta
+	0x00ff0000,	// Red
+	0x000000ff,	// Blue
+	0x00000000,	// Black
+    ];
+
+    let Some(mode) = man_video::find_graphics_mode(GFX_WIDTH, GFX_HEIGHT,
+						   GFX_BPP, &ALLOC_UNDER20)
+    else {
+	testing::skip("gfx-pattern", "no graphics mode");
+	return;
+    };
+
+    let drawn = man_video::with_mode(mode, 0, &ALLOC_UNDER20, | fb | {
+	let bar_width = fb.width.div_ceil(BARS.len());
+	let bar_height = fb.height * 3 / 4;
+	for (i, &color) in BARS.iter().enumerate() {
+	    fb.fill_rect(i * bar_width, 0, bar_width, bar_height, color);
+	}
+	for x in 0 .. fb.width {
+	    let level = (x * 0xff / fb.width.max(1)) as u32;
+	    let gray = level << 16 | level << 8 | level;
+	    fb.fill_rect(x, bar_height, 1, fb.height - bar_height, gray);
+	}
+	time::sleep(GFX_PATTERN_DURATION);
+	(fb.width, fb.height, fb.bpp)
+    });
+
+    if let Some((width, height, bpp)) = drawn {
+	println!("gfx-pattern: {}x{}x{} (mode 0x{:04x})",
+		 width, height, bpp, mode);
+    }
+    testing::report("gfx-pattern", drawn.map(drop).ok_or("mode not set"));
+}
+
+// Demo: usable memory (`memtest=<MiB>` limits the size tested)
+// (It takes long, and destroys the contents of the free memory.)
+fn run_memtest(ctx: &DemoContext) {
+    let max_bytes = Config::cmdline().get("memtest")
+	.and_then(|mib| mib.parse::<u64>().ok())
+	.map(|mib| mib << 20);
+    let report = memtest::run(ctx.memory_map, max_bytes, ctx.seed);
+    println!("{}", report);
+    testing::report("memtest", report.passed().then_some(())
+		    .ok_or("memory errors"));
+}
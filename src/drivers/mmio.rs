@@ -0,0 +1,116 @@
+//
+// MMIO - Accesses memory-mapped I/O registers.
+//
+
+use core::mem::{align_of, size_of};
+use core::ptr::{read_volatile, write_volatile};
+
+use crate::x86::mmio::RegisterBlock;
+
+
+///
+/// An accessor to a memory-mapped I/O range.
+///
+/// All accesses are volatile, and offsets are checked against the
+/// size of the range (panics if out of range or misaligned).
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Mmio {
+    base: usize,
+    size: usize,
+}
+
+impl Mmio {
+    ///
+    /// Returns an accessor to the range.
+    ///
+    /// # Safety
+    ///
+    /// The range must be mapped (preferably uncached) and must not be
+    /// used as RAM.
+    ///
+    pub const unsafe fn new(base: usize, size: usize) -> Self {
+	Self { base, size }
+    }
+
+    /// Returns the base address.
+    pub fn base(&self) -> usize {
+	self.base
+    }
+
+    /// Returns the size in bytes.
+    pub fn size(&self) -> usize {
+	self.size
+    }
+
+    /// Returns an accessor to a part of the range.
+    pub fn subrange(&self, off: usize, size: usize) -> Self {
+	assert!(off.checked_add(size).is_some_and(|end| end <= self.size),
+		"MMIO subrange {:#x}+{:#x} is out of range (size={:#x})",
+		off, size, self.size);
+	Self { base: self.base + off, size }
+    }
+
+    ///
+    /// Returns the block of registers at the start of the range (cf.
+    /// `x86::mmio`).  Panics if the range is too small or misaligned.
+    ///
+    pub fn block<B>(&self) -> &B
+    where
+	B: RegisterBlock,
+    {
+	assert!(size_of::<B>() <= self.size
+		&& self.base.is_multiple_of(align_of::<B>()),
+		"MMIO block of {:#x} bytes is invalid at {:#x} (size={:#x})",
+		size_of::<B>(), self.base, self.size);
+	unsafe { &*(self.base as *const B) }
+    }
+
+    // Returns the address of a register of type T at the offset.
+    fn addr<T>(&self, off: usize) -> usize {
+	let size = size_of::<T>();
+	assert!(off + size <= self.size && off.is_multiple_of(size),
+		"MMIO offset {:#x} is invalid (size={:#x})", off, self.size);
+	self.base + off
+    }
+
+    /// Reads a byte at the offset.
+    pub fn read_u8(&self, off: usize) -> u8 {
+	unsafe { read_volatile(self.addr::<u8>(off) as *const u8) }
+    }
+
+    /// Reads a word at the offset.
+    pub fn read_u16(&self, off: usize) -> u16 {
+	unsafe { read_volatile(self.addr::<u16>(off) as *const u16) }
+    }
+
+    /// Reads a dword at the offset.
+    pub fn read_u32(&self, off: usize) -> u32 {
+	unsafe { read_volatile(self.addr::<u32>(off) as *const u32) }
+    }
+
+    /// Reads a qword at the offset.
+    pub fn read_u64(&self, off: usize) -> u64 {
+	unsafe { read_volatile(self.addr::<u64>(off) as *const u64) }
+    }
+
+    /// Writes a byte at the offset.
+    pub fn write_u8(&self, off: usize, value: u8) {
+	unsafe { write_volatile(self.addr::<u8>(off) as *mut u8, value) }
+    }
+
+    /// Writes a word at the offset.
+    pub fn write_u16(&self, off: usize, value: u16) {
+	unsafe { write_volatile(self.addr::<u16>(off) as *mut u16, value) }
+    }
+
+    /// Writes a dword at the offset.
+    pub fn write_u32(&self, off: usize, value: u32) {
+	unsafe { write_volatile(self.addr::<u32>(off) as *mut u32, value) }
+    }
+
+    /// Writes a qword at the offset.
+    pub fn write_u64(&self, off: usize, value: u64) {
+	unsafe { write_volatile(self.addr::<u64>(off) as *mut u64, value) }
+    }
+}
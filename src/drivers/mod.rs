@@ -0,0 +1,36 @@
+/*!
+
+Provides device drivers.
+
+* `pci` - enumerates PCI devices via the configuration space access
+  mechanism #1 (I/O ports 0xCF8 and 0xCFC).
+
+* `Mmio` - accesses memory-mapped I/O registers (e.g., those mapped
+  by `PciDevice::map_bar`), also as blocks of typed registers declared
+  by `register_block!` (cf. `x86::mmio`).
+
+* `NetDevice` - a common interface of network drivers, sending and
+  receiving raw Ethernet frames.
+
+* `virtio` - the virtio PCI transport and split virtqueues.
+
+* `virtio_net` - a virtio-net driver implementing `NetDevice`.
+
+* `usb_uhci` - a USB UHCI host controller driver with a HID boot
+  protocol keyboard.
+
+ */
+
+
+#[doc(hidden)] pub mod mmio;
+#[cfg(feature = "net")] #[doc(hidden)] pub mod net_device;
+pub mod pci;
+pub mod usb_uhci;
+#[cfg(feature = "net")] pub mod virtio;
+#[cfg(feature = "net")] pub mod virtio_net;
+
+#[doc(inline)] pub use self::mmio::Mmio;
+#[cfg(feature = "net")]
+#[doc(inline)] pub use self::net_device::{
+    MAX_FRAME_SIZE, MacAddress, NetDevice, NetError,
+};
@@ -0,0 +1,62 @@
+//
+// Network Device - A common interface of network drivers.
+//
+
+use core::fmt;
+
+
+/// The maximum size in bytes of an Ethernet frame (without FCS).
+pub const MAX_FRAME_SIZE: usize = 1514;
+
+/// Errors detected by network devices.
+#[derive(Debug, PartialEq)]
+pub enum NetError {
+    /// The frame is larger than `MAX_FRAME_SIZE`.
+    FrameTooLarge { len: usize },
+    /// No transmit buffer is available now.
+    QueueFull,
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::FrameTooLarge { len } =>
+		write!(f, "Frame is too large ({} bytes)", len),
+	    Self::QueueFull =>
+		write!(f, "No transmit buffer is available"),
+	}
+    }
+}
+
+
+///
+/// A network device sending and receiving raw Ethernet frames.
+///
+/// Frames start with the destination MAC address, and do not include
+/// the FCS.  Receiving is done by polling.
+///
+pub trait NetDevice {
+    /// Returns the MAC address of the device.
+    fn mac_address(&self) -> [u8; 6];
+
+    /// Sends a frame.
+    fn send(&mut self, frame: &[u8]) -> Result<(), NetError>;
+
+    ///
+    /// Receives a frame into `buf` if available, then returns its
+    /// length.  The frame is truncated if `buf` is too small.
+    ///
+    fn receive(&mut self, buf: &mut [u8]) -> Option<usize>;
+}
+
+
+/// Formats a MAC address as "xx:xx:xx:xx:xx:xx".
+pub struct MacAddress(pub [u8; 6]);
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	let m = &self.0;
+	write!(f, "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+	       m[0], m[1], m[2], m[3], m[4], m[5])
+    }
+}
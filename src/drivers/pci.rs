@@ -0,0 +1,418 @@
+/*!
+
+Enumerates PCI devices via I/O ports 0xCF8 and 0xCFC.
+
+The configuration space of each function is accessed using the
+Configuration Space Access Mechanism #1.  That is, the address is
+written to CONFIG_ADDRESS (0xCF8), then the data is read from or
+written to CONFIG_DATA (0xCFC).
+
+```ignore
+for device in pci::devices() {
+    println!("{}", device);
+}
+let ahci = pci::find(pci::CLASS_STORAGE, pci::SUBCLASS_SATA);
+```
+
+# Supplementary Resources
+
+* [PCI](https://wiki.osdev.org/PCI) (OSDev Wiki)
+* [PCI configuration space](https://en.wikipedia.org/wiki/PCI_configuration_space) (Wikipedia)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://wiki.osdev.org/PCI
+//	https://en.wikipedia.org/wiki/PCI_configuration_space
+//
+
+use core::fmt;
+
+use super::Mmio;
+use crate::mu::MuMutex;
+use crate::x86::{PagingError, inl, map_uncached, outl};
+
+
+// I/O Ports of the Configuration Space Access Mechanism #1
+const CONFIG_ADDRESS: u16 = 0xcf8;
+const CONFIG_DATA: u16 = 0xcfc;
+
+// Serializes the accesses to the pair of I/O ports.
+static CONFIG_LOCK: MuMutex<()> = MuMutex::new(());
+
+// Offsets in the Configuration Space Header
+const REG_VENDOR_ID: u8 = 0x00;
+const REG_COMMAND: u8 = 0x04;
+const REG_STATUS: u8 = 0x06;
+const REG_CLASS: u8 = 0x08;	// Revision, Prog IF, Subclass, Class
+const REG_HEADER_TYPE: u8 = 0x0e;
+const REG_BAR0: u8 = 0x10;
+const REG_CAPABILITIES: u8 = 0x34;
+const REG_INTERRUPT_LINE: u8 = 0x3c;
+const REG_INTERRUPT_PIN: u8 = 0x3d;
+
+// Bits in the Command Register
+pub const COMMAND_IO_SPACE: u16 = 1 << 0;
+pub const COMMAND_MEMORY_SPACE: u16 = 1 << 1;
+pub const COMMAND_BUS_MASTER: u16 = 1 << 2;
+pub const COMMAND_INTX_DISABLE: u16 = 1 << 10;
+
+// Bits in the Status Register
+const STATUS_CAPABILITIES: u16 = 1 << 4;
+
+// Class Codes (commonly used ones)
+pub const CLASS_STORAGE: u8 = 0x01;
+pub const CLASS_NETWORK: u8 = 0x02;
+pub const CLASS_DISPLAY: u8 = 0x03;
+pub const CLASS_BRIDGE: u8 = 0x06;
+pub const CLASS_SERIAL_BUS: u8 = 0x0c;
+
+// Subclass Codes (commonly used ones)
+pub const SUBCLASS_IDE: u8 = 0x01;		// of CLASS_STORAGE
+pub const SUBCLASS_SATA: u8 = 0x06;		// of CLASS_STORAGE
+pub const SUBCLASS_NVM: u8 = 0x08;		// of CLASS_STORAGE
+pub const SUBCLASS_ETHERNET: u8 = 0x00;		// of CLASS_NETWORK
+pub const SUBCLASS_USB: u8 = 0x03;		// of CLASS_SERIAL_BUS
+
+// Capability IDs (commonly used ones)
+pub const CAP_MSI: u8 = 0x05;
+pub const CAP_VENDOR: u8 = 0x09;
+pub const CAP_PCIE: u8 = 0x10;
+pub const CAP_MSIX: u8 = 0x11;
+
+
+/// The address of a PCI function (bus:device.function).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PciAddress {
+    pub bus: u8,	// 0 - 255
+    pub device: u8,	// 0 - 31
+    pub function: u8,	// 0 - 7
+}
+
+impl PciAddress {
+    /// Returns a new address.
+    pub const fn new(bus: u8, device: u8, function: u8) -> Self {
+	Self { bus, device, function }
+    }
+
+    // Returns the value written to CONFIG_ADDRESS.
+    fn config_address(&self, off: u8) -> u32 {
+	1 << 31				// Enable Bit
+	    | (self.bus as u32) << 16
+	    | ((self.device & 0x1f) as u32) << 11
+	    | ((self.function & 0x07) as u32) << 8
+	    | (off & 0xfc) as u32
+    }
+
+    /// Reads a dword from the configuration space.
+    pub fn read_u32(&self, off: u8) -> u32 {
+	let _guard = CONFIG_LOCK.lock();
+	unsafe {
+	    outl(CONFIG_ADDRESS, self.config_address(off));
+	    inl(CONFIG_DATA)
+	}
+    }
+
+    /// Reads a word from the configuration space.
+    pub fn read_u16(&self, off: u8) -> u16 {
+	(self.read_u32(off) >> ((off & 2) * 8)) as u16
+    }
+
+    /// Reads a byte from the configuration space.
+    pub fn read_u8(&self, off: u8) -> u8 {
+	(self.read_u32(off) >> ((off & 3) * 8)) as u8
+    }
+
+    /// Writes a dword to the configuration space.
+    pub fn write_u32(&self, off: u8, value: u32) {
+	let _guard = CONFIG_LOCK.lock();
+	unsafe {
+	    outl(CONFIG_ADDRESS, self.config_address(off));
+	    outl(CONFIG_DATA, value);
+	}
+    }
+
+    /// Writes a word to the configuration space.
+    pub fn write_u16(&self, off: u8, value: u16) {
+	let shift = (off & 2) * 8;
+	let old = self.read_u32(off) & !(0xffff << shift);
+	self.write_u32(off, old | (value as u32) << shift);
+    }
+}
+
+impl fmt::Display for PciAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{:02x}:{:02x}.{}", self.bus, self.device, self.function)
+    }
+}
+
+
+/// A Base Address Register (BAR).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bar {
+    /// Memory Space BAR
+    Memory { addr: u64, prefetchable: bool, is_64bit: bool },
+    /// I/O Space BAR
+    Io { port: u16 },
+}
+
+
+/// Errors detected by [`PciDevice::map_bar`].
+#[derive(Debug)]
+pub enum PciError {
+    /// The BAR is not implemented or not assigned.
+    NoBar { n: usize },
+    /// The BAR is an I/O space BAR.
+    NotMemoryBar { n: usize },
+    /// The MMIO range cannot be mapped.
+    Paging(PagingError),
+}
+
+impl fmt::Display for PciError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::NoBar { n } =>
+		write!(f, "PCI BAR{} is not available", n),
+	    Self::NotMemoryBar { n } =>
+		write!(f, "PCI BAR{} is not a memory space BAR", n),
+	    Self::Paging(err) =>
+		write!(f, "{}", err),
+	}
+    }
+}
+
+
+/// A PCI function found by enumeration.
+#[derive(Clone, Copy, Debug)]
+pub struct PciDevice {
+    pub addr: PciAddress,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub class: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
+    pub revision: u8,
+    pub header_type: u8,	// Bit 7 (multi-function) is cleared.
+    pub irq_line: u8,		// 0xFF if not connected
+    pub irq_pin: u8,		// 0 if not used, 1 - 4 for INTA# - INTD#
+}
+
+impl PciDevice {
+    /// Reads the configuration space header of the function.
+    pub fn probe(addr: PciAddress) -> Option<Self> {
+	let id = addr.read_u32(REG_VENDOR_ID);
+	if (id & 0xffff) == 0xffff {
+	    return None;
+	}
+
+	let class = addr.read_u32(REG_CLASS);
+	Some(Self {
+	    addr,
+	    vendor_id: id as u16,
+	    device_id: (id >> 16) as u16,
+	    class: (class >> 24) as u8,
+	    subclass: (class >> 16) as u8,
+	    prog_if: (class >> 8) as u8,
+	    revision: class as u8,
+	    header_type: addr.read_u8(REG_HEADER_TYPE) & 0x7f,
+	    irq_line: addr.read_u8(REG_INTERRUPT_LINE),
+	    irq_pin: addr.read_u8(REG_INTERRUPT_PIN),
+	})
+    }
+
+    /// Returns the number of BARs (6 for devices, 2 for bridges).
+    pub fn nbars(&self) -> usize {
+	match self.header_type {
+	    0x00 => 6,
+	    0x01 => 2,
+	    _ => 0,
+	}
+    }
+
+    /// Returns the n-th BAR (None if unused or out of range).
+    pub fn bar(&self, n: usize) -> Option<Bar> {
+	if n >= self.nbars() {
+	    return None;
+	}
+
+	let off = REG_BAR0 + (n as u8) * 4;
+	let value = self.addr.read_u32(off);
+	if (value & 1) != 0 {
+	    let port = (value & !0x3) as u16;
+	    return (port != 0).then_some(Bar::Io { port });
+	}
+
+	let is_64bit = (value & 0x6) == 0x4;
+	let high = if is_64bit && n + 1 < self.nbars() {
+	    self.addr.read_u32(off + 4) as u64
+	} else {
+	    0
+	};
+	let addr = high << 32 | (value & !0xf) as u64;
+	(addr != 0).then_some(Bar::Memory {
+	    addr,
+	    prefetchable: (value & 0x8) != 0,
+	    is_64bit,
+	})
+    }
+
+    ///
+    /// Returns the size in bytes of the n-th BAR (0 if unused).
+    ///
+    /// It writes all 1's to the BAR, and reads back the mask of the
+    /// address bits.  Memory and I/O decoding are disabled meanwhile.
+    ///
+    pub fn bar_size(&self, n: usize) -> u64 {
+	let Some(bar) = self.bar(n) else {
+	    return 0;
+	};
+
+	let off = REG_BAR0 + (n as u8) * 4;
+	let command = self.command();
+	self.addr.write_u16(REG_COMMAND,
+			    command & !(COMMAND_IO_SPACE |
+					COMMAND_MEMORY_SPACE));
+
+	let size_mask = |off: u8| {
+	    let saved = self.addr.read_u32(off);
+	    self.addr.write_u32(off, 0xffff_ffff);
+	    let mask = self.addr.read_u32(off);
+	    self.addr.write_u32(off, saved);
+	    mask
+	};
+
+	let mask = match bar {
+	    Bar::Io { .. } => (size_mask(off) & !0x3) as u64 | !0xffff,
+	    Bar::Memory { is_64bit: true, .. } => {
+		let low = (size_mask(off) & !0xf) as u64;
+		(size_mask(off + 4) as u64) << 32 | low
+	    },
+	    Bar::Memory { .. } =>
+		(size_mask(off) & !0xf) as u64 | !0xffff_ffff,
+	};
+
+	self.addr.write_u16(REG_COMMAND, command);
+	(!mask).wrapping_add(1)
+    }
+
+    ///
+    /// Maps the n-th memory space BAR, then returns an MMIO accessor.
+    ///
+    /// It sizes the BAR, identity-maps the range uncached, and enables
+    /// memory space decoding.  The BAR must be assigned by BIOS or
+    /// UEFI firmware in advance.
+    ///
+    pub fn map_bar(&self, n: usize) -> Result<Mmio, PciError> {
+	let addr = match self.bar(n) {
+	    Some(Bar::Memory { addr, .. }) => addr,
+	    Some(Bar::Io { .. }) => return Err(PciError::NotMemoryBar { n }),
+	    None => return Err(PciError::NoBar { n }),
+	};
+
+	let size = self.bar_size(n);
+	if size == 0 {
+	    return Err(PciError::NoBar { n });
+	}
+
+	unsafe {
+	    map_uncached(addr, size).map_err(PciError::Paging)?;
+	}
+	self.enable(COMMAND_MEMORY_SPACE);
+
+	Ok(unsafe { Mmio::new(addr as usize, size as usize) })
+    }
+
+    /// Returns the command register.
+    pub fn command(&self) -> u16 {
+	self.addr.read_u16(REG_COMMAND)
+    }
+
+    /// Sets bits (e.g., `COMMAND_BUS_MASTER`) in the command register.
+    pub fn enable(&self, bits: u16) {
+	self.addr.write_u16(REG_COMMAND, self.command() | bits);
+    }
+
+    /// Returns an iterator over the capabilities: (ID, offset).
+    pub fn capabilities(&self) -> Capabilities {
+	let has_list =
+	    (self.addr.read_u16(REG_STATUS) & STATUS_CAPABILITIES) != 0;
+	let next = if has_list {
+	    self.addr.read_u8(REG_CAPABILITIES) & 0xfc
+	} else {
+	    0
+	};
+	Capabilities { addr: self.addr, next, count: 0 }
+    }
+
+    /// Returns the offset of the first capability with the ID.
+    pub fn find_capability(&self, id: u8) -> Option<u8> {
+	self.capabilities().find(|&(cap_id, _)| cap_id == id)
+	    .map(|(_, off)| off)
+    }
+}
+
+impl fmt::Display for PciDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{} {:04x}:{:04x} class={:02x}.{:02x}.{:02x}",
+	       self.addr, self.vendor_id, self.device_id,
+	       self.class, self.subclass, self.prog_if)?;
+	if self.irq_pin != 0 {
+	    write!(f, " irq={}", self.irq_line)?;
+	}
+	Ok(())
+    }
+}
+
+
+/// An iterator over the capabilities of a PCI function.
+pub struct Capabilities {
+    addr: PciAddress,
+    next: u8,
+    count: usize,
+}
+
+impl Iterator for Capabilities {
+    type Item = (u8, u8);	// (Capability ID, Offset)
+
+    fn next(&mut self) -> Option<(u8, u8)> {
+	// At most 48 capabilities fit in 192 bytes (guards a loop).
+	if self.next < 0x40 || self.count >= 48 {
+	    return None;
+	}
+
+	let off = self.next;
+	let header = self.addr.read_u16(off);
+	self.next = (header >> 8) as u8 & 0xfc;
+	self.count += 1;
+	Some((header as u8, off))
+    }
+}
+
+
+///
+/// Returns an iterator over all PCI functions.
+///
+/// It scans all buses by brute force.  Functions 1 - 7 are scanned
+/// only if function 0 is a multi-function device.
+///
+pub fn devices() -> impl Iterator<Item = PciDevice> {
+    (0 ..= 255_u8)
+	.flat_map(|bus| (0 .. 32_u8).map(move |device| (bus, device)))
+	.flat_map(|(bus, device)| {
+	    let addr0 = PciAddress::new(bus, device, 0);
+	    let nfunctions = match PciDevice::probe(addr0) {
+		None => 0,
+		Some(_) if (addr0.read_u8(REG_HEADER_TYPE) & 0x80) != 0 => 8,
+		Some(_) => 1,
+	    };
+	    (0 .. nfunctions).filter_map(move |function| {
+		PciDevice::probe(PciAddress::new(bus, device, function))
+	    })
+	})
+}
+
+/// Returns the first PCI function with the class and the subclass.
+pub fn find(class: u8, subclass: u8) -> Option<PciDevice> {
+    devices().find(|dev| dev.class == class && dev.subclass == subclass)
+}
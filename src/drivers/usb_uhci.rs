@@ -0,0 +1,678 @@
+/*!
+
+Provides a minimal USB UHCI (Universal Host Controller Interface)
+driver with a HID boot protocol keyboard.
+
+Only control transfers and a single interrupt IN endpoint are
+supported, which are sufficient to enumerate a keyboard attached to
+a root hub port, and to poll its input reports.  USB hubs are not
+supported.  Everything is done by polling (no interrupts).
+
+Since the BIOS keyboard emulation (legacy support) is disabled when
+the host controller is initialized, keys must be read by
+`UsbKeyboard` afterward.
+
+To add a UHCI host controller with a USB keyboard to QEMU, add the
+following options to `qemu-system-x86_64`.
+
+```sh
+-device piix3-usb-uhci,id=uhci -device usb-kbd,bus=uhci.0
+```
+
+```ignore
+let mut keyboard = UsbKeyboard::find()?;
+let key = keyboard.read_key();
+```
+
+# Supplementary Resources
+
+* [Universal Host Controller Interface (UHCI) Design Guide](https://ftp.netbsd.org/pub/NetBSD/misc/blymn/uhci11d.pdf) (Intel)
+* [UHCI](https://wiki.osdev.org/Universal_Host_Controller_Interface) (OSDev Wiki)
+* [USB Human Interface Devices](https://wiki.osdev.org/USB_Human_Interface_Devices) (OSDev Wiki)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://ftp.netbsd.org/pub/NetBSD/misc/blymn/uhci11d.pdf
+//	https://wiki.osdev.org/Universal_Host_Controller_Interface
+//	https://wiki.osdev.org/USB_Human_Interface_Devices
+//
+
+use alloc::alloc::{Layout, alloc_zeroed};
+use core::fmt;
+use core::hint::spin_loop;
+use core::ops::Range;
+use core::ptr;
+use core::sync::atomic::{Ordering, fence};
+
+use super::pci::{self, PciDevice};
+use crate::input::{Key, KeyEvent, KeyQueue, KeySource};
+use crate::input::hid::{BootKeyboard, REPORT_SIZE};
+use crate::time::{self, Duration, Instant};
+use crate::x86::{inw, outl, outw};
+
+
+/// The programming interface of UHCI host controllers.
+pub const PROG_IF_UHCI: u8 = 0x00;
+
+// The I/O space BAR of the registers (USBBASE)
+const USBBASE_BAR: usize = 4;
+
+// Offsets in the PCI Configuration Space
+const REG_LEGSUP: u8 = 0xc0;		// Legacy Support Register
+
+// Writing this to LEGSUP clears the status bits and disables the
+// legacy keyboard emulation and SMIs.
+const LEGSUP_DISABLE: u16 = 0x8f00;
+
+// I/O Registers
+const USBCMD: u16 = 0x00;
+const USBSTS: u16 = 0x02;
+const USBINTR: u16 = 0x04;
+const FRNUM: u16 = 0x06;
+const FRBASEADD: u16 = 0x08;
+const PORTSC1: u16 = 0x10;
+
+/// The number of root hub ports.
+pub const NPORTS: usize = 2;
+
+// USB Command Register
+const CMD_RS: u16 = 1 << 0;		// Run/Stop
+const CMD_HCRESET: u16 = 1 << 1;	// Host Controller Reset
+const CMD_CF: u16 = 1 << 6;		// Configure Flag
+const CMD_MAXP: u16 = 1 << 7;		// Max Packet (64 bytes)
+
+// Port Status and Control Registers
+const PORT_CCS: u16 = 1 << 0;		// Current Connect Status
+const PORT_CSC: u16 = 1 << 1;		// Connect Status Change (R/WC)
+const PORT_PE: u16 = 1 << 2;		// Port Enabled
+const PORT_PEC: u16 = 1 << 3;		// Port Enable Change (R/WC)
+const PORT_LSDA: u16 = 1 << 8;		// Low Speed Device Attached
+const PORT_PR: u16 = 1 << 9;		// Port Reset
+
+// Link Pointers (of frame list entries, QHs and TDs)
+const LINK_T: u32 = 1 << 0;		// Terminate
+const LINK_QH: u32 = 1 << 1;		// Points to a QH.
+const LINK_VF: u32 = 1 << 2;		// Depth first
+
+// TD Control and Status
+const TD_ACTIVE: u32 = 1 << 23;
+const TD_STALLED: u32 = 1 << 22;
+const TD_ERRORS: u32 = 0x7e << 16;	// Stalled, Buffer, Babble, NAK, ...
+const TD_NAK: u32 = 1 << 19;
+const TD_LS: u32 = 1 << 26;		// Low Speed Device
+const TD_CERR_3: u32 = 3 << 27;		// Error Counter
+
+// TD Token
+const PID_SETUP: u32 = 0x2d;
+const PID_IN: u32 = 0x69;
+const PID_OUT: u32 = 0xe1;
+const TOKEN_TOGGLE: u32 = 1 << 19;
+
+// Layout of the memory shared with the host controller
+const FRAME_LIST_SIZE: usize = 1024;	// Entries (4 bytes each)
+const QH_INTR_OFF: usize = 4096;	// QH of the interrupt endpoint
+const QH_CTRL_OFF: usize = 4112;	// QH of control transfers
+const TD_OFF: usize = 4128;		// TDs (32 bytes each)
+const NTDS: usize = 32;
+const TD_INTR: usize = NTDS - 1;	// TD of the interrupt endpoint
+const SETUP_OFF: usize = TD_OFF + NTDS * 32;
+const DATA_OFF: usize = SETUP_OFF + 8;
+const DATA_SIZE: usize = 512;
+const REPORT_OFF: usize = DATA_OFF + DATA_SIZE;
+const DMA_SIZE: usize = REPORT_OFF + REPORT_SIZE;
+
+// Timeouts of the host controller reset and control transfers
+const RESET_TIMEOUT: Duration = Duration::from_millis(50);
+const TRANSFER_TIMEOUT: Duration = Duration::from_millis(500);
+
+// Standard Requests
+const REQ_SET_ADDRESS: u8 = 0x05;
+const REQ_GET_DESCRIPTOR: u8 = 0x06;
+const REQ_SET_CONFIGURATION: u8 = 0x09;
+
+// HID Class Requests
+const REQ_SET_IDLE: u8 = 0x0a;
+const REQ_SET_PROTOCOL: u8 = 0x0b;
+
+// Descriptor Types
+const DESC_DEVICE: u8 = 1;
+const DESC_CONFIGURATION: u8 = 2;
+const DESC_INTERFACE: u8 = 4;
+const DESC_ENDPOINT: u8 = 5;
+
+// Interface Class, Subclass and Protocol of Boot Keyboards
+const HID_BOOT_KEYBOARD: (u8, u8, u8) = (0x03, 0x01, 0x01);
+
+
+/// Errors detected by the UHCI driver.
+#[derive(Debug)]
+pub enum UsbError {
+    /// No UHCI host controller is found.
+    NoController,
+    /// The I/O space BAR is not available.
+    NoIoBar,
+    /// No memory (below 4GiB) for the schedule.
+    OutOfMemory,
+    /// No device is connected to the port.
+    NoDevice { port: usize },
+    /// No keyboard is found.
+    NoKeyboard,
+    /// The transfer did not complete in time.
+    Timeout,
+    /// The transfer failed with the TD status.
+    Transfer { status: u8 },
+}
+
+impl fmt::Display for UsbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::NoController =>
+		write!(f, "No UHCI host controller is found"),
+	    Self::NoIoBar =>
+		write!(f, "UHCI I/O space BAR is not available"),
+	    Self::OutOfMemory =>
+		write!(f, "No memory for the UHCI schedule"),
+	    Self::NoDevice { port } =>
+		write!(f, "No USB device is connected to port {}", port),
+	    Self::NoKeyboard =>
+		write!(f, "No USB keyboard is found"),
+	    Self::Timeout =>
+		write!(f, "USB transfer timed out"),
+	    Self::Transfer { status } =>
+		write!(f, "USB transfer failed (status {:#04x})", status),
+	}
+    }
+}
+
+
+/// A device attached to a root hub port.
+#[derive(Clone, Copy, Debug)]
+pub struct UsbDevice {
+    pub port: usize,
+    pub address: u8,
+    pub low_speed: bool,
+    pub max_packet: u16,	// of the default control endpoint
+}
+
+/// A setup packet of control transfers.
+#[derive(Clone, Copy, Debug)]
+pub struct SetupPacket {
+    pub request_type: u8,	// Bit 7 is set if device-to-host.
+    pub request: u8,
+    pub value: u16,
+    pub index: u16,
+    pub length: u16,
+}
+
+impl SetupPacket {
+    fn to_bytes(self) -> [u8; 8] {
+	let [v0, v1] = self.value.to_le_bytes();
+	let [i0, i1] = self.index.to_le_bytes();
+	let [l0, l1] = self.length.to_le_bytes();
+	[self.request_type, self.request, v0, v1, i0, i1, l0, l1]
+    }
+
+    fn is_in(&self) -> bool {
+	(self.request_type & 0x80) != 0
+    }
+}
+
+
+/// A UHCI host controller.
+pub struct Uhci {
+    io: u16,		// I/O Base Address
+    dma: *mut u8,	// Frame List, QHs, TDs and Buffers
+}
+
+impl Uhci {
+    /// Returns the first UHCI host controller.
+    pub fn find() -> Option<PciDevice> {
+	pci::devices().find(|dev| {
+	    dev.class == pci::CLASS_SERIAL_BUS &&
+		dev.subclass == pci::SUBCLASS_USB &&
+		dev.prog_if == PROG_IF_UHCI
+	})
+    }
+
+    /// Resets and starts the host controller.
+    pub fn new(dev: &PciDevice) -> Result<Self, UsbError> {
+	let Some(pci::Bar::Io { port: io }) = dev.bar(USBBASE_BAR) else {
+	    return Err(UsbError::NoIoBar);
+	};
+	dev.enable(pci::COMMAND_IO_SPACE | pci::COMMAND_BUS_MASTER);
+	dev.addr.write_u16(REG_LEGSUP, LEGSUP_DISABLE);
+
+	let dma = unsafe {
+	    alloc_zeroed(Layout::from_size_align(DMA_SIZE, 4096).unwrap())
+	};
+	if dma.is_null() || (dma as u64 + DMA_SIZE as u64) > (1 << 32) {
+	    return Err(UsbError::OutOfMemory);
+	}
+	let hc = Self { io, dma };
+
+	unsafe { outw(io + USBCMD, CMD_HCRESET) };
+	let reset_done = time::wait_until(RESET_TIMEOUT, || {
+	    unsafe { (inw(io + USBCMD) & CMD_HCRESET) == 0 }
+	});
+	if !reset_done {
+	    return Err(UsbError::Timeout);
+	}
+	unsafe { outw(io + USBINTR, 0) };
+
+	// Every frame executes the interrupt QH, then the control QH.
+	hc.write_u32(QH_INTR_OFF, hc.phys(QH_CTRL_OFF) | LINK_QH);
+	hc.write_u32(QH_INTR_OFF + 4, LINK_T);
+	hc.write_u32(QH_CTRL_OFF, LINK_T);
+	hc.write_u32(QH_CTRL_OFF + 4, LINK_T);
+	for i in 0 .. FRAME_LIST_SIZE {
+	    hc.write_u32(i * 4, hc.phys(QH_INTR_OFF) | LINK_QH);
+	}
+	fence(Ordering::SeqCst);
+
+	unsafe {
+	    outl(io + FRBASEADD, hc.phys(0));
+	    outw(io + FRNUM, 0);
+	    outw(io + USBSTS, 0xffff);
+	    outw(io + USBCMD, CMD_RS | CMD_CF | CMD_MAXP);
+	}
+
+	Ok(hc)
+    }
+
+    ///
+    /// Resets the port, then returns whether a low speed device is
+    /// attached if a device is connected.
+    ///
+    pub fn reset_port(&self, port: usize) -> Option<bool> {
+	assert!(port < NPORTS);
+	let reg = self.io + PORTSC1 + (port as u16) * 2;
+	let read = || unsafe { inw(reg) };
+	let write = |value| unsafe { outw(reg, value) };
+
+	if (read() & PORT_CCS) == 0 {
+	    return None;
+	}
+
+	write(PORT_PR);
+	time::sleep(Duration::from_millis(50));
+	write(0);
+	time::sleep(Duration::from_millis(1));
+
+	for _ in 0 .. 10 {
+	    let status = read();
+	    if (status & PORT_CCS) == 0 {
+		return None;
+	    }
+	    if (status & PORT_PE) != 0 {
+		write(PORT_PE | PORT_CSC | PORT_PEC);
+		time::sleep(Duration::from_millis(10));	// Reset recovery
+		return Some((status & PORT_LSDA) != 0);
+	    }
+	    write(PORT_PE | PORT_CSC | PORT_PEC);
+	    time::sleep(Duration::from_millis(10));
+	}
+	None
+    }
+
+    ///
+    /// Enumerates the device connected to the port, and assigns the
+    /// address to it.
+    ///
+    pub fn attach(&mut self, port: usize, address: u8)
+		  -> Result<UsbDevice, UsbError> {
+	let low_speed = self.reset_port(port)
+	    .ok_or(UsbError::NoDevice { port })?;
+	let mut device = UsbDevice { port, address: 0, low_speed,
+				     max_packet: 8 };
+
+	// The first 8 bytes of the device descriptor tell the maximum
+	// packet size of the default control endpoint.
+	let mut desc = [0; 8];
+	self.get_descriptor(&device, DESC_DEVICE, &mut desc)?;
+	device.max_packet = (desc[7] as u16).max(8);
+
+	self.control_transfer(&device, SetupPacket {
+	    request_type: 0x00,
+	    request: REQ_SET_ADDRESS,
+	    value: address as u16,
+	    index: 0,
+	    length: 0,
+	}, &mut [])?;
+	time::sleep(Duration::from_millis(2));	// Set address recovery
+	device.address = address;
+
+	Ok(device)
+    }
+
+    /// Reads the descriptor (index 0) of the type into `buf`.
+    pub fn get_descriptor(&mut self, device: &UsbDevice, desc_type: u8,
+			  buf: &mut [u8]) -> Result<usize, UsbError> {
+	self.control_transfer(device, SetupPacket {
+	    request_type: 0x80,
+	    request: REQ_GET_DESCRIPTOR,
+	    value: (desc_type as u16) << 8,
+	    index: 0,
+	    length: buf.len() as u16,
+	}, buf)
+    }
+
+    ///
+    /// Performs a control transfer to the default control endpoint,
+    /// then returns the number of bytes transferred in the data stage.
+    ///
+    pub fn control_transfer(&mut self, device: &UsbDevice,
+			    setup: SetupPacket, data: &mut [u8])
+			    -> Result<usize, UsbError> {
+	let len = (setup.length as usize).min(data.len());
+	let max_packet = device.max_packet as usize;
+	assert!(len <= DATA_SIZE && len.div_ceil(max_packet) + 2 <= TD_INTR);
+
+	self.write_buffer(SETUP_OFF, &setup.to_bytes());
+	if !setup.is_in() {
+	    self.write_buffer(DATA_OFF, &data[.. len]);
+	}
+
+	// Setup stage, data stage (toggling from DATA1), status stage
+	let data_pid = if setup.is_in() { PID_IN } else { PID_OUT };
+	let status_pid =
+	    if setup.is_in() && len > 0 { PID_OUT } else { PID_IN };
+	let mut ntds = 0;
+	let mut push = |pid, toggle, buf_off, size| {
+	    let buf = buf_off .. buf_off + size;
+	    self.write_td(ntds, device, (pid, 0, toggle), buf);
+	    ntds += 1;
+	};
+	push(PID_SETUP, false, SETUP_OFF, 8);
+	for (i, off) in (0 .. len).step_by(max_packet).enumerate() {
+	    let size = (len - off).min(max_packet);
+	    push(data_pid, i % 2 == 0, DATA_OFF + off, size);
+	}
+	push(status_pid, true, DATA_OFF, 0);
+	for i in 0 .. ntds - 1 {
+	    self.write_u32(self.td_off(i), self.phys(self.td_off(i + 1)) |
+			   LINK_VF);
+	}
+
+	fence(Ordering::SeqCst);
+	self.write_u32(QH_CTRL_OFF + 4, self.phys(self.td_off(0)));
+	let result = self.wait_tds(ntds);
+	self.write_u32(QH_CTRL_OFF + 4, LINK_T);
+	result?;
+
+	// Count the bytes transferred in the data stage.
+	let mut actual = 0;
+	for i in 1 .. ntds - 1 {
+	    actual += (self.read_u32(self.td_off(i) + 4) + 1) as usize & 0x7ff;
+	}
+	let actual = actual.min(len);
+	if setup.is_in() {
+	    self.read_buffer(DATA_OFF, &mut data[.. actual]);
+	}
+	Ok(actual)
+    }
+
+    ///
+    /// Starts polling the interrupt IN endpoint of the device with
+    /// the data toggle, to receive a report of the size.
+    ///
+    pub fn start_interrupt(&mut self, device: &UsbDevice, endpoint: u8,
+			   toggle: bool, size: usize) {
+	assert!(size <= REPORT_SIZE);
+	self.write_td(TD_INTR, device, (PID_IN, endpoint, toggle),
+		      REPORT_OFF .. REPORT_OFF + size);
+	fence(Ordering::SeqCst);
+	self.write_u32(QH_INTR_OFF + 4, self.phys(self.td_off(TD_INTR)));
+    }
+
+    ///
+    /// Copies the report received by the interrupt endpoint into
+    /// `buf` if any, then returns its length.
+    ///
+    pub fn poll_interrupt(&mut self, buf: &mut [u8])
+			  -> Option<Result<usize, UsbError>> {
+	let status = self.read_u32(self.td_off(TD_INTR) + 4);
+	if (status & TD_ACTIVE) != 0 {
+	    return None;
+	}
+	fence(Ordering::SeqCst);
+	if (status & TD_ERRORS & !TD_NAK) != 0 {
+	    let status = (status >> 16) as u8;
+	    return Some(Err(UsbError::Transfer { status }));
+	}
+
+	let len = ((status + 1) as usize & 0x7ff).min(buf.len());
+	self.read_buffer(REPORT_OFF, &mut buf[.. len]);
+	Some(Ok(len))
+    }
+
+
+    // Waits until the TDs complete.
+    fn wait_tds(&self, ntds: usize) -> Result<(), UsbError> {
+	let deadline = Instant::now() + TRANSFER_TIMEOUT;
+	loop {
+	    let mut done = true;
+	    for i in 0 .. ntds {
+		let status = self.read_u32(self.td_off(i) + 4);
+		if (status & TD_STALLED) != 0 ||
+		    (status & TD_ACTIVE) == 0 && (status & TD_ERRORS) != 0 {
+		    let status = (status >> 16) as u8;
+		    return Err(UsbError::Transfer { status });
+		}
+		done &= (status & TD_ACTIVE) == 0;
+	    }
+	    if done {
+		fence(Ordering::SeqCst);
+		return Ok(());
+	    }
+	    if Instant::now() > deadline {
+		return Err(UsbError::Timeout);
+	    }
+	    spin_loop();
+	}
+    }
+
+    // Writes an active TD transferring the buffer range with the
+    // token (PID, endpoint, data toggle).
+    fn write_td(&self, n: usize, device: &UsbDevice,
+		(pid, endpoint, toggle): (u32, u8, bool), buf: Range<usize>) {
+	let mut control = TD_ACTIVE | TD_CERR_3;
+	if device.low_speed {
+	    control |= TD_LS;
+	}
+	let max_len = (buf.len() as u32).wrapping_sub(1) & 0x7ff;
+	let mut token = max_len << 21 | (endpoint as u32 & 0xf) << 15 |
+	    (device.address as u32) << 8 | pid;
+	if toggle {
+	    token |= TOKEN_TOGGLE;
+	}
+
+	let off = self.td_off(n);
+	self.write_u32(off, LINK_T);
+	self.write_u32(off + 8, token);
+	self.write_u32(off + 12, self.phys(buf.start));
+	self.write_u32(off + 4, control);
+    }
+
+    fn td_off(&self, n: usize) -> usize {
+	TD_OFF + n * 32
+    }
+
+    fn phys(&self, off: usize) -> u32 {
+	self.dma as u32 + off as u32
+    }
+
+    fn read_u32(&self, off: usize) -> u32 {
+	unsafe { (self.dma.add(off) as *const u32).read_volatile() }
+    }
+
+    fn write_u32(&self, off: usize, value: u32) {
+	unsafe { (self.dma.add(off) as *mut u32).write_volatile(value) }
+    }
+
+    fn read_buffer(&self, off: usize, buf: &mut [u8]) {
+	unsafe {
+	    ptr::copy_nonoverlapping(self.dma.add(off), buf.as_mut_ptr(),
+				     buf.len());
+	}
+    }
+
+    fn write_buffer(&self, off: usize, data: &[u8]) {
+	unsafe {
+	    ptr::copy_nonoverlapping(data.as_ptr(), self.dma.add(off),
+				     data.len());
+	}
+    }
+}
+
+
+/// A USB keyboard driven in the HID boot protocol.
+pub struct UsbKeyboard {
+    hc: Uhci,
+    device: UsbDevice,
+    endpoint: u8,
+    toggle: bool,
+    state: BootKeyboard,
+}
+
+impl UsbKeyboard {
+    /// Initializes the first UHCI host controller and its keyboard.
+    pub fn find() -> Result<Self, UsbError> {
+	let dev = Uhci::find().ok_or(UsbError::NoController)?;
+	Self::new(Uhci::new(&dev)?)
+    }
+
+    /// Finds and configures a keyboard connected to the controller.
+    pub fn new(mut hc: Uhci) -> Result<Self, UsbError> {
+	for port in 0 .. NPORTS {
+	    let device = match hc.attach(port, port as u8 + 1) {
+		Ok(device) => device,
+		Err(UsbError::NoDevice { .. }) => continue,
+		Err(err) => return Err(err),
+	    };
+	    if let Some((interface, endpoint)) =
+		Self::configure(&mut hc, &device)? {
+		Self::set_boot_protocol(&mut hc, &device, interface)?;
+		hc.start_interrupt(&device, endpoint, false, REPORT_SIZE);
+		return Ok(Self {
+		    hc,
+		    device,
+		    endpoint,
+		    toggle: false,
+		    state: BootKeyboard::new(),
+		});
+	    }
+	}
+	Err(UsbError::NoKeyboard)
+    }
+
+    /// Returns the device of the keyboard.
+    pub fn device(&self) -> &UsbDevice {
+	&self.device
+    }
+
+    /// Reads a key (blocking).
+    pub fn read_key(&mut self) -> Key {
+	loop {
+	    if let Some(key) = self.poll_key() {
+		return key;
+	    }
+	    spin_loop();
+	}
+    }
+
+    /// Returns a key if available (non-blocking).
+    pub fn poll_key(&mut self) -> Option<Key> {
+	if let Some(key) = self.state.pop() {
+	    return Some(key);
+	}
+
+	let mut report = [0; REPORT_SIZE];
+	let result = self.hc.poll_interrupt(&mut report)?;
+	if let Ok(REPORT_SIZE) = result {
+	    self.toggle = !self.toggle;
+	    self.state.update(&report);
+	}
+	self.hc.start_interrupt(&self.device, self.endpoint, self.toggle,
+				REPORT_SIZE);
+	self.state.pop()
+    }
+
+    /// Pushes the available keys to the key queue.
+    pub fn feed(&mut self, queue: &KeyQueue) {
+	while let Some(key) = self.poll_key() {
+	    queue.push(KeyEvent { key, source: KeySource::Usb });
+	}
+    }
+
+
+    //
+    // Selects the first configuration if it has a boot keyboard
+    // interface, then returns the interface number and the endpoint
+    // number of its interrupt IN endpoint.
+    //
+    fn configure(hc: &mut Uhci, device: &UsbDevice)
+		 -> Result<Option<(u8, u8)>, UsbError> {
+	let mut config = [0; DATA_SIZE];
+	let len = hc.get_descriptor(device, DESC_CONFIGURATION,
+				    &mut config[.. 9])?;
+	if len < 9 {
+	    return Ok(None);
+	}
+	let total = u16::from_le_bytes([config[2], config[3]]) as usize;
+	let len = hc.get_descriptor(device, DESC_CONFIGURATION,
+				    &mut config[.. total.min(DATA_SIZE)])?;
+
+	// Walk the interface and endpoint descriptors.
+	let mut found = None;
+	let mut interface = None;
+	let mut off = 0;
+	while off + 2 <= len && config[off] >= 2 {
+	    let desc = &config[off .. (off + config[off] as usize).min(len)];
+	    match desc[1] {
+		DESC_INTERFACE if desc.len() >= 9 => {
+		    interface = ((desc[5], desc[6], desc[7]) ==
+				 HID_BOOT_KEYBOARD).then_some(desc[2]);
+		},
+		DESC_ENDPOINT if desc.len() >= 7 => {
+		    let is_intr_in = (desc[2] & 0x80) != 0 &&
+			(desc[3] & 0x3) == 0x3;
+		    if let (Some(interface), true) = (interface, is_intr_in) {
+			found = Some((interface, desc[2] & 0xf));
+			break;
+		    }
+		},
+		_ => (),
+	    }
+	    off += desc.len();
+	}
+	if found.is_none() {
+	    return Ok(None);
+	}
+
+	hc.control_transfer(device, SetupPacket {
+	    request_type: 0x00,
+	    request: REQ_SET_CONFIGURATION,
+	    value: config[5] as u16,
+	    index: 0,
+	    length: 0,
+	}, &mut [])?;
+	Ok(found)
+    }
+
+    // Selects the boot protocol, and reports only on changes.
+    fn set_boot_protocol(hc: &mut Uhci, device: &UsbDevice, interface: u8)
+			 -> Result<(), UsbError> {
+	for (request, value) in [(REQ_SET_PROTOCOL, 0), (REQ_SET_IDLE, 0)] {
+	    hc.control_transfer(device, SetupPacket {
+		request_type: 0x21,
+		request,
+		value,
+		index: interface as u16,
+		length: 0,
+	    }, &mut [])?;
+	}
+	Ok(())
+    }
+}
@@ -0,0 +1,378 @@
+/*!
+
+Provides the virtio PCI transport (virtio 1.0 "modern" interface) and
+split virtqueues.
+
+The configuration structures are located by the vendor-specific PCI
+capabilities, and mapped by `PciDevice::map_bar`.  Each virtqueue has
+a fixed buffer for each descriptor, and is used by polling (i.e., no
+interrupts).
+
+# Supplementary Resources
+
+* [Virtual I/O Device (VIRTIO) Version 1.1](https://docs.oasis-open.org/virtio/virtio/v1.1/virtio-v1.1.html) (OASIS)
+* [Virtio](https://wiki.osdev.org/Virtio) (OSDev Wiki)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://docs.oasis-open.org/virtio/virtio/v1.1/virtio-v1.1.html
+//	https://wiki.osdev.org/Virtio
+//
+
+use alloc::alloc::{Layout, alloc_zeroed};
+use core::fmt;
+use core::slice;
+use core::sync::atomic::{Ordering, fence};
+
+use super::Mmio;
+use super::pci::{self, COMMAND_BUS_MASTER, PciDevice, PciError};
+use crate::register_block;
+use crate::time::{self, Duration};
+use crate::x86::mmio::{ReadOnly, ReadWrite};
+
+
+/// The PCI vendor ID of virtio devices.
+pub const VENDOR_ID: u16 = 0x1af4;
+
+// Device Status
+pub const STATUS_ACKNOWLEDGE: u8 = 1;
+pub const STATUS_DRIVER: u8 = 2;
+pub const STATUS_DRIVER_OK: u8 = 4;
+pub const STATUS_FEATURES_OK: u8 = 8;
+pub const STATUS_FAILED: u8 = 128;
+
+/// Feature bit: Compliance with virtio 1.0 (required by this driver).
+pub const FEATURE_VERSION_1: u64 = 1 << 32;
+
+// Types of virtio PCI capabilities (cfg_type)
+const CAP_COMMON_CFG: u8 = 1;
+const CAP_NOTIFY_CFG: u8 = 2;
+const CAP_ISR_CFG: u8 = 3;
+const CAP_DEVICE_CFG: u8 = 4;
+
+register_block! {
+    // The Common Configuration Structure (virtio_pci_common_cfg)
+    struct CommonCfg(0x38) {
+	0x00 => device_feature_select: ReadWrite<u32>,
+	0x04 => device_feature: ReadOnly<u32>,
+	0x08 => driver_feature_select: ReadWrite<u32>,
+	0x0c => driver_feature: ReadWrite<u32>,
+	0x10 => msix_config: ReadWrite<u16>,
+	0x12 => num_queues: ReadOnly<u16>,
+	0x14 => device_status: ReadWrite<u8>,
+	0x15 => config_generation: ReadOnly<u8>,
+	0x16 => queue_select: ReadWrite<u16>,
+	0x18 => queue_size: ReadWrite<u16>,
+	0x1a => queue_msix_vector: ReadWrite<u16>,
+	0x1c => queue_enable: ReadWrite<u16>,
+	0x1e => queue_notify_off: ReadOnly<u16>,
+	0x20 => queue_desc: ReadWrite<u64>,
+	0x28 => queue_driver: ReadWrite<u64>,
+	0x30 => queue_device: ReadWrite<u64>,
+    }
+}
+
+/// The maximum number of descriptors in a virtqueue of this driver.
+pub const MAX_QUEUE_SIZE: u16 = 64;
+
+// The time waiting for the device to complete a reset
+const RESET_TIMEOUT: Duration = Duration::from_millis(100);
+
+
+/// Errors detected while initializing virtio devices.
+#[derive(Debug)]
+pub enum VirtioError {
+    /// A required PCI capability is not found.
+    NoCapability { cfg_type: u8 },
+    /// The BAR of a capability cannot be mapped.
+    Pci(PciError),
+    /// The device did not accept the features.
+    FeaturesRejected,
+    /// The virtqueue is not available.
+    NoQueue { index: u16 },
+    /// No memory for a virtqueue.
+    OutOfMemory,
+    /// The device did not complete the reset.
+    ResetTimeout,
+}
+
+impl fmt::Display for VirtioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::NoCapability { cfg_type } =>
+		write!(f, "virtio capability {} is not found", cfg_type),
+	    Self::Pci(err) =>
+		write!(f, "{}", err),
+	    Self::FeaturesRejected =>
+		write!(f, "virtio features are rejected"),
+	    Self::NoQueue { index } =>
+		write!(f, "virtqueue {} is not available", index),
+	    Self::OutOfMemory =>
+		write!(f, "No memory for a virtqueue"),
+	    Self::ResetTimeout =>
+		write!(f, "virtio device reset timed out"),
+	}
+    }
+}
+
+
+/// The virtio PCI transport of a device.
+pub struct VirtioPci {
+    common: Mmio,		// Common Configuration
+    notify: Mmio,		// Notifications
+    notify_multiplier: u32,	// Notify Offset Multiplier
+    isr: Mmio,			// ISR Status
+    device: Mmio,		// Device-specific Configuration
+}
+
+impl VirtioPci {
+    /// Maps the configuration structures of the device.
+    pub fn new(dev: &PciDevice) -> Result<Self, VirtioError> {
+	let find = |cfg_type| {
+	    dev.capabilities()
+		.filter(|&(id, _)| id == pci::CAP_VENDOR)
+		.map(|(_, off)| off)
+		.find(|&off| dev.addr.read_u8(off + 3) == cfg_type)
+		.ok_or(VirtioError::NoCapability { cfg_type })
+	};
+
+	// virtio_pci_cap:
+	//	03   : cfg_type	04   : bar
+	//	08-0B: offset	0C-0F: length
+	let map = |cap: u8| {
+	    let bar = dev.addr.read_u8(cap + 4) as usize;
+	    let off = dev.addr.read_u32(cap + 8) as usize;
+	    let len = dev.addr.read_u32(cap + 12) as usize;
+	    let mmio = dev.map_bar(bar).map_err(VirtioError::Pci)?;
+	    Ok(mmio.subrange(off, len))
+	};
+
+	let notify_cap = find(CAP_NOTIFY_CFG)?;
+	let transport = Self {
+	    common: map(find(CAP_COMMON_CFG)?)?,
+	    notify: map(notify_cap)?,
+	    notify_multiplier: dev.addr.read_u32(notify_cap + 16),
+	    isr: map(find(CAP_ISR_CFG)?)?,
+	    device: map(find(CAP_DEVICE_CFG)?)?,
+	};
+
+	dev.enable(COMMAND_BUS_MASTER);
+	Ok(transport)
+    }
+
+    // Returns the common configuration.
+    fn common(&self) -> &CommonCfg {
+	self.common.block()
+    }
+
+    /// Returns the device-specific configuration.
+    pub fn device_config(&self) -> Mmio {
+	self.device
+    }
+
+    /// Returns the ISR status (cleared by reading).
+    pub fn isr_status(&self) -> u8 {
+	self.isr.read_u8(0)
+    }
+
+    /// Returns the device status.
+    pub fn status(&self) -> u8 {
+	self.common().device_status.read()
+    }
+
+    /// Adds bits to the device status.
+    pub fn add_status(&self, bits: u8) {
+	self.common().device_status.modify(| status | status | bits);
+    }
+
+    /// Resets the device, then acknowledges it.
+    pub fn reset(&self) -> Result<(), VirtioError> {
+	self.common().device_status.write(0);
+	if !time::wait_until(RESET_TIMEOUT, || self.status() == 0) {
+	    return Err(VirtioError::ResetTimeout);
+	}
+	self.add_status(STATUS_ACKNOWLEDGE | STATUS_DRIVER);
+	Ok(())
+    }
+
+    ///
+    /// Negotiates features, then returns the accepted features.
+    ///
+    /// `FEATURE_VERSION_1` is always requested.
+    ///
+    pub fn negotiate(&self, wanted: u64) -> Result<u64, VirtioError> {
+	let mut offered = 0;
+	for select in 0 .. 2 {
+	    self.common().device_feature_select.write(select);
+	    let bits = self.common().device_feature.read() as u64;
+	    offered |= bits << (32 * select);
+	}
+
+	let accepted = offered & (wanted | FEATURE_VERSION_1);
+	if (accepted & FEATURE_VERSION_1) == 0 {
+	    self.add_status(STATUS_FAILED);
+	    return Err(VirtioError::FeaturesRejected);
+	}
+
+	for select in 0 .. 2 {
+	    self.common().driver_feature_select.write(select);
+	    self.common().driver_feature
+		.write((accepted >> (32 * select)) as u32);
+	}
+
+	self.add_status(STATUS_FEATURES_OK);
+	if (self.status() & STATUS_FEATURES_OK) == 0 {
+	    self.add_status(STATUS_FAILED);
+	    return Err(VirtioError::FeaturesRejected);
+	}
+
+	Ok(accepted)
+    }
+
+    /// Sets up the virtqueue with buffers of `buf_size` bytes each.
+    pub fn setup_queue(&self, index: u16, buf_size: usize)
+		       -> Result<Virtqueue, VirtioError> {
+	let common = self.common();
+	common.queue_select.write(index);
+	let max_size = common.queue_size.read();
+	if max_size == 0 {
+	    return Err(VirtioError::NoQueue { index });
+	}
+
+	// The queue size must be a power of 2.
+	let size = max_size.min(MAX_QUEUE_SIZE);
+	let size = 1 << (15 - size.leading_zeros());
+	let queue = Virtqueue::new(index, size, buf_size)?;
+
+	common.queue_size.write(size);
+	common.queue_desc.write(queue.desc as u64);
+	common.queue_driver.write(queue.avail as u64);
+	common.queue_device.write(queue.used as u64);
+
+	let notify_off = common.queue_notify_off.read();
+	let off = notify_off as usize * self.notify_multiplier as usize;
+	let queue = Virtqueue {
+	    notify: Some(self.notify.subrange(off, 2)),
+	    ..queue
+	};
+
+	common.queue_enable.write(1);
+	Ok(queue)
+    }
+
+    /// Tells the device that the driver is ready.
+    pub fn driver_ok(&self) {
+	self.add_status(STATUS_DRIVER_OK);
+    }
+}
+
+
+// Descriptor flags
+const DESC_F_WRITE: u16 = 2;	// Device writes (otherwise reads)
+
+/// A split virtqueue whose descriptor `i` always owns buffer `i`.
+pub struct Virtqueue {
+    index: u16,
+    size: u16,
+    desc: *mut u8,		// Descriptor Table (16 bytes each)
+    avail: *mut u16,		// Available Ring (flags, idx, ring...)
+    used: *mut u32,		// Used Ring (flags|idx, (id, len)...)
+    buffers: *mut u8,
+    buf_size: usize,
+    avail_idx: u16,
+    last_used_idx: u16,
+    notify: Option<Mmio>,
+}
+
+impl Virtqueue {
+    fn new(index: u16, size: u16, buf_size: usize)
+	   -> Result<Self, VirtioError> {
+	let n = size as usize;
+	let avail_off = 16 * n;
+	let used_off = (avail_off + 6 + 2 * n + 3) & !3;
+	let rings_size = used_off + 6 + 8 * n;
+
+	let alloc = |size, align| unsafe {
+	    let ptr = alloc_zeroed(Layout::from_size_align(size, align)
+				   .unwrap());
+	    if ptr.is_null() { Err(VirtioError::OutOfMemory) } else { Ok(ptr) }
+	};
+	let rings = alloc(rings_size, 4096)?;
+	let buffers = alloc(n * buf_size, 16)?;
+
+	Ok(Self {
+	    index,
+	    size,
+	    desc: rings,
+	    avail: unsafe { rings.add(avail_off) } as *mut u16,
+	    used: unsafe { rings.add(used_off) } as *mut u32,
+	    buffers,
+	    buf_size,
+	    avail_idx: 0,
+	    last_used_idx: 0,
+	    notify: None,
+	})
+    }
+
+    /// Returns the number of descriptors.
+    pub fn size(&self) -> u16 {
+	self.size
+    }
+
+    /// Returns the buffer of the descriptor.
+    pub fn buffer(&mut self, id: u16) -> &mut [u8] {
+	assert!(id < self.size);
+	unsafe {
+	    slice::from_raw_parts_mut(self.buffers
+				      .add(id as usize * self.buf_size),
+				      self.buf_size)
+	}
+    }
+
+    ///
+    /// Makes the descriptor available to the device with the first
+    /// `len` bytes of its buffer, then notifies the device.
+    ///
+    pub fn submit(&mut self, id: u16, len: usize, device_writes: bool) {
+	assert!(id < self.size && len <= self.buf_size);
+	unsafe {
+	    let desc = self.desc.add(id as usize * 16);
+	    let addr = self.buffers.add(id as usize * self.buf_size);
+	    (desc as *mut u64).write_volatile(addr as u64);
+	    (desc.add(8) as *mut u32).write_volatile(len as u32);
+	    (desc.add(12) as *mut u16).write_volatile(
+		if device_writes { DESC_F_WRITE } else { 0 });
+	    (desc.add(14) as *mut u16).write_volatile(0);
+
+	    let slot = (self.avail_idx % self.size) as usize;
+	    self.avail.add(2 + slot).write_volatile(id);
+	    fence(Ordering::SeqCst);
+	    self.avail_idx = self.avail_idx.wrapping_add(1);
+	    self.avail.add(1).write_volatile(self.avail_idx);
+	    fence(Ordering::SeqCst);
+	}
+
+	if let Some(notify) = self.notify {
+	    notify.write_u16(0, self.index);
+	}
+    }
+
+    /// Returns a descriptor used by the device: (id, written length).
+    pub fn pop_used(&mut self) -> Option<(u16, usize)> {
+	unsafe {
+	    let used_idx = (self.used.read_volatile() >> 16) as u16;
+	    if used_idx == self.last_used_idx {
+		return None;
+	    }
+	    fence(Ordering::SeqCst);
+
+	    let slot = (self.last_used_idx % self.size) as usize;
+	    let id = self.used.add(1 + 2 * slot).read_volatile();
+	    let len = self.used.add(2 + 2 * slot).read_volatile();
+	    self.last_used_idx = self.last_used_idx.wrapping_add(1);
+	    Some((id as u16, len as usize))
+	}
+    }
+}
@@ -0,0 +1,139 @@
+/*!
+
+Provides a virtio-net driver sending and receiving raw Ethernet
+frames via [`NetDevice`].
+
+To add a virtio-net device with user-mode networking to QEMU, add
+the following options to `qemu-system-x86_64`.
+
+```sh
+-netdev user,id=net0 -device virtio-net-pci,netdev=net0
+```
+
+ */
+
+
+use super::net_device::{MAX_FRAME_SIZE, NetDevice, NetError};
+use super::pci::{self, PciDevice};
+use super::virtio::{VENDOR_ID, VirtioError, VirtioPci, Virtqueue};
+
+
+/// PCI device IDs of virtio-net (transitional and modern).
+pub const DEVICE_IDS: [u16; 2] = [0x1000, 0x1041];
+
+// Feature bits
+const FEATURE_MAC: u64 = 1 << 5;	// The device has a MAC address.
+
+// Virtqueue Indexes
+const RECEIVEQ: u16 = 0;
+const TRANSMITQ: u16 = 1;
+
+// struct virtio_net_hdr (with FEATURE_VERSION_1)
+const NET_HDR_SIZE: usize = 12;
+
+// The size of each buffer (a header and a frame).
+const BUF_SIZE: usize = 2048;
+const _: () = assert!(NET_HDR_SIZE + MAX_FRAME_SIZE <= BUF_SIZE);
+
+/// The MAC address used if the device has none.
+pub const DEFAULT_MAC: [u8; 6] = [0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+
+
+/// A virtio-net device.
+pub struct VirtioNet {
+    transport: VirtioPci,
+    rx: Virtqueue,
+    tx: Virtqueue,
+    tx_next: u16,	// The next descriptor to transmit
+    tx_busy: u64,	// Bitmap of descriptors in transmission
+    mac: [u8; 6],
+}
+
+impl VirtioNet {
+    /// Returns the first virtio-net device.
+    pub fn find() -> Option<PciDevice> {
+	pci::devices().find(|dev| {
+	    dev.vendor_id == VENDOR_ID && DEVICE_IDS.contains(&dev.device_id)
+	})
+    }
+
+    /// Initializes the device.
+    pub fn new(dev: &PciDevice) -> Result<Self, VirtioError> {
+	let transport = VirtioPci::new(dev)?;
+
+	transport.reset()?;
+	let features = transport.negotiate(FEATURE_MAC)?;
+
+	let mut rx = transport.setup_queue(RECEIVEQ, BUF_SIZE)?;
+	let tx = transport.setup_queue(TRANSMITQ, BUF_SIZE)?;
+
+	// Post all receive buffers.
+	for id in 0 .. rx.size() {
+	    rx.submit(id, BUF_SIZE, true);
+	}
+
+	let mac = if (features & FEATURE_MAC) != 0 {
+	    let config = transport.device_config();
+	    core::array::from_fn(|i| config.read_u8(i))
+	} else {
+	    DEFAULT_MAC
+	};
+
+	transport.driver_ok();
+
+	Ok(Self { transport, rx, tx, tx_next: 0, tx_busy: 0, mac })
+    }
+
+    /// Returns the virtio PCI transport.
+    pub fn transport(&self) -> &VirtioPci {
+	&self.transport
+    }
+
+    // Reclaims transmitted descriptors.
+    fn reclaim_tx(&mut self) {
+	while let Some((id, _)) = self.tx.pop_used() {
+	    self.tx_busy &= !(1 << id);
+	}
+    }
+}
+
+impl NetDevice for VirtioNet {
+    fn mac_address(&self) -> [u8; 6] {
+	self.mac
+    }
+
+    fn send(&mut self, frame: &[u8]) -> Result<(), NetError> {
+	if frame.len() > MAX_FRAME_SIZE {
+	    return Err(NetError::FrameTooLarge { len: frame.len() });
+	}
+
+	self.reclaim_tx();
+	let id = self.tx_next;
+	if (self.tx_busy & (1 << id)) != 0 {
+	    return Err(NetError::QueueFull);
+	}
+
+	// A zero-filled header means no offloading.
+	let buf = self.tx.buffer(id);
+	buf[.. NET_HDR_SIZE].fill(0);
+	buf[NET_HDR_SIZE .. NET_HDR_SIZE + frame.len()].copy_from_slice(frame);
+
+	self.tx_busy |= 1 << id;
+	self.tx_next = (id + 1) % self.tx.size();
+	self.tx.submit(id, NET_HDR_SIZE + frame.len(), false);
+	Ok(())
+    }
+
+    fn receive(&mut self, buf: &mut [u8]) -> Option<usize> {
+	let (id, len) = self.rx.pop_used()?;
+
+	let rx_buf = self.rx.buffer(id);
+	let frame_len = len.saturating_sub(NET_HDR_SIZE).min(buf.len());
+	buf[.. frame_len]
+	    .copy_from_slice(&rx_buf[NET_HDR_SIZE .. NET_HDR_SIZE + frame_len]);
+
+	// Post the buffer again.
+	self.rx.submit(id, BUF_SIZE, true);
+	Some(frame_len)
+    }
+}
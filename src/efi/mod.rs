@@ -0,0 +1,432 @@
+/*!
+
+Boots the runtime from UEFI (enabled by feature `efi`).
+
+It obtains the memory map and the GOP frame buffer from UEFI boot
+services, then exits boot services.  After that, the same runtime
+layers (`man_heap`, `console`, etc.) are initialized as the BIOS boot
+path does.  See `src/bin/efi.rs` for the entry point `efi_main`.
+
+Note: BIOS functions (i.e., module `bios` except its data types) must
+not be called in the UEFI boot path.
+
+# Resource
+
+* [UEFI Specification Version 2.9](https://uefi.org/specifications) (UEFI Forum, 2021-03)
+
+# Supplementary Resource
+
+* [UEFI](https://wiki.osdev.org/UEFI) (OS Dev)
+
+ */
+
+//
+// Resource:
+//	"Unified Extensible Firmware Interface (UEFI) Specification"
+//	Version 2.9 (March 2021)
+//	https://uefi.org/specifications
+//
+// Supplementary Resource:
+//	https://wiki.osdev.org/UEFI
+//
+
+#[doc(hidden)] pub mod stubs;
+
+use core::ffi::c_void;
+use core::fmt;
+use core::mem::size_of;
+use core::ptr::{null_mut, addr_of_mut};
+
+use crate::bios::int15he820h::AddrRange;
+#[cfg(feature = "video")]
+use crate::man_video::{ColorField, FrameBuffer, PixelFormat};
+
+
+/// EFI_HANDLE
+pub type Handle = *mut c_void;
+
+/// EFI_STATUS
+pub type Status = usize;
+
+/// EFI_SUCCESS
+pub const SUCCESS: Status = 0;
+
+/// EFI_BUFFER_TOO_SMALL
+pub const BUFFER_TOO_SMALL: Status = (1 << 63) | 5;
+
+
+/// EFI_GUID
+#[repr(C)]
+pub struct Guid {
+    pub data1: u32,
+    pub data2: u16,
+    pub data3: u16,
+    pub data4: [u8; 8],
+}
+
+/// EFI_TABLE_HEADER
+#[repr(C)]
+pub struct TableHeader {
+    pub signature: u64,
+    pub revision: u32,
+    pub header_size: u32,
+    pub crc32: u32,
+    pub reserved: u32,
+}
+
+/// EFI_SYSTEM_TABLE
+#[repr(C)]
+pub struct SystemTable {
+    pub hdr: TableHeader,
+    pub firmware_vendor: *const u16,
+    pub firmware_revision: u32,
+    pub console_in_handle: Handle,
+    pub con_in: *mut c_void,
+    pub console_out_handle: Handle,
+    pub con_out: *mut c_void,
+    pub standard_error_handle: Handle,
+    pub std_err: *mut c_void,
+    pub runtime_services: *mut c_void,
+    pub boot_services: *mut BootServices,
+    pub number_of_table_entries: usize,
+    pub configuration_table: *mut c_void,
+}
+
+/// EFI_BOOT_SERVICES (only the functions used here are typed)
+#[repr(C)]
+pub struct BootServices {
+    pub hdr: TableHeader,
+    pub raise_tpl: usize,
+    pub restore_tpl: usize,
+    pub allocate_pages: usize,
+    pub free_pages: usize,
+    pub get_memory_map: extern "efiapi" fn(
+	memory_map_size: *mut usize,
+	memory_map: *mut MemoryDescriptor,
+	map_key: *mut usize,
+	descriptor_size: *mut usize,
+	descriptor_version: *mut u32) -> Status,
+    pub allocate_pool: usize,
+    pub free_pool: usize,
+    pub create_event: usize,
+    pub set_timer: usize,
+    pub wait_for_event: usize,
+    pub signal_event: usize,
+    pub close_event: usize,
+    pub check_event: usize,
+    pub install_protocol_interface: usize,
+    pub reinstall_protocol_interface: usize,
+    pub uninstall_protocol_interface: usize,
+    pub handle_protocol: usize,
+    pub reserved: usize,
+    pub register_protocol_notify: usize,
+    pub locate_handle: usize,
+    pub locate_device_path: usize,
+    pub install_configuration_table: usize,
+    pub load_image: usize,
+    pub start_image: usize,
+    pub exit: usize,
+    pub unload_image: usize,
+    pub exit_boot_services: extern "efiapi" fn(
+	image_handle: Handle,
+	map_key: usize) -> Status,
+    pub get_next_monotonic_count: usize,
+    pub stall: usize,
+    pub set_watchdog_timer: extern "efiapi" fn(
+	timeout: usize,
+	watchdog_code: u64,
+	data_size: usize,
+	watchdog_data: *const u16) -> Status,
+    pub connect_controller: usize,
+    pub disconnect_controller: usize,
+    pub open_protocol: usize,
+    pub close_protocol: usize,
+    pub open_protocol_information: usize,
+    pub protocols_per_handle: usize,
+    pub locate_handle_buffer: usize,
+    pub locate_protocol: extern "efiapi" fn(
+	protocol: *const Guid,
+	registration: *mut c_void,
+	interface: *mut *mut c_void) -> Status,
+}
+
+/// EFI_MEMORY_DESCRIPTOR
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct MemoryDescriptor {
+    pub mtype: u32,
+    pub physical_start: u64,
+    pub virtual_start: u64,
+    pub number_of_pages: u64,
+    pub attribute: u64,
+}
+
+const _: () = assert!(size_of::<MemoryDescriptor>() == 0x28);
+
+impl MemoryDescriptor {
+    // Memory Types
+    pub const LOADER_CODE		: u32 = 1;
+    pub const LOADER_DATA		: u32 = 2;
+    pub const BOOT_SERVICES_CODE	: u32 = 3;
+    pub const BOOT_SERVICES_DATA	: u32 = 4;
+    pub const CONVENTIONAL_MEMORY	: u32 = 7;
+    pub const UNUSABLE_MEMORY		: u32 = 8;
+    pub const ACPI_RECLAIM_MEMORY	: u32 = 9;
+    pub const ACPI_MEMORY_NVS		: u32 = 10;
+    pub const PERSISTENT_MEMORY		: u32 = 14;
+
+    /// The size of a page in bytes.
+    pub const PAGE_SIZE: u64 = 4096;
+
+    /// Converts the descriptor into an E820-style address range.
+    ///
+    /// Note: Boot services code and data are not reported as usable
+    /// because the current stack may reside in them.
+    pub fn to_addr_range(&self) -> AddrRange {
+	let atype =
+	    match self.mtype {
+		Self::CONVENTIONAL_MEMORY	=> AddrRange::TYPE_USABLE,
+		Self::UNUSABLE_MEMORY		=> AddrRange::TYPE_UNUSABLE,
+		Self::ACPI_RECLAIM_MEMORY	=> AddrRange::TYPE_ACPI,
+		Self::ACPI_MEMORY_NVS		=> AddrRange::TYPE_NVS,
+		Self::PERSISTENT_MEMORY		=> AddrRange::TYPE_PERSISTENT,
+		_ => AddrRange::TYPE_RESERVED,
+	    };
+
+	AddrRange {
+	    addr: self.physical_start,
+	    length: self.number_of_pages * Self::PAGE_SIZE,
+	    atype,
+	    attr: AddrRange::ATTR_DEFAULT,
+	}
+    }
+}
+
+/// EFI_GRAPHICS_OUTPUT_PROTOCOL
+#[repr(C)]
+pub struct GraphicsOutput {
+    pub query_mode: usize,
+    pub set_mode: usize,
+    pub blt: usize,
+    pub mode: *const GraphicsOutputMode,
+}
+
+/// EFI_GRAPHICS_OUTPUT_PROTOCOL_MODE
+#[repr(C)]
+pub struct GraphicsOutputMode {
+    pub max_mode: u32,
+    pub mode: u32,
+    pub info: *const GraphicsOutputModeInfo,
+    pub size_of_info: usize,
+    pub frame_buffer_base: u64,
+    pub frame_buffer_size: usize,
+}
+
+/// EFI_GRAPHICS_OUTPUT_MODE_INFORMATION
+#[repr(C)]
+pub struct GraphicsOutputModeInfo {
+    pub version: u32,
+    pub horizontal_resolution: u32,
+    pub vertical_resolution: u32,
+    pub pixel_format: u32,
+    pub red_mask: u32,
+    pub green_mask: u32,
+    pub blue_mask: u32,
+    pub reserved_mask: u32,
+    pub pixels_per_scan_line: u32,
+}
+
+/// PixelRedGreenBlueReserved8BitPerColor (EFI_GRAPHICS_PIXEL_FORMAT)
+pub const PIXEL_RGB_RESERVED_8BPC: u32 = 0;
+
+/// PixelBlueGreenRedReserved8BitPerColor (EFI_GRAPHICS_PIXEL_FORMAT)
+pub const PIXEL_BGR_RESERVED_8BPC: u32 = 1;
+
+/// EFI_GRAPHICS_OUTPUT_PROTOCOL_GUID
+pub const GRAPHICS_OUTPUT_PROTOCOL_GUID: Guid = Guid {
+    data1: 0x9042a9de,
+    data2: 0x23dc,
+    data3: 0x4a38,
+    data4: [0x96, 0xfb, 0x7a, 0xde, 0xd0, 0x80, 0x51, 0x6a],
+};
+
+
+/// Frame buffer information obtained from GOP.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameBufferInfo {
+    pub base: usize,		// Physical Address of Frame Buffer
+    pub size: usize,		// Size in Bytes of Frame Buffer
+    pub width: u32,		// Horizontal Resolution
+    pub height: u32,		// Vertical Resolution
+    pub pixels_per_line: u32,	// Pixels per Scan Line
+    pub pixel_format: u32,	// Pixel Format (0 = RGBX, 1 = BGRX, ..)
+}
+
+impl fmt::Display for FrameBufferInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "base={:#x}, size={:#x}, (x, y) = ({}, {}), \
+		   pixels_per_line={}, format={}",
+	       self.base, self.size, self.width, self.height,
+	       self.pixels_per_line, self.pixel_format)
+    }
+}
+
+#[cfg(feature = "video")]
+impl FrameBufferInfo {
+    ///
+    /// Describes the frame buffer for drawing (e.g. by
+    /// `console::init_fb_screen`).  Returns None if the pixel format
+    /// is neither RGBX nor BGRX (8 bits per color), or the frame
+    /// buffer is not accessible (BltOnly).
+    ///
+    pub fn to_frame_buffer(&self) -> Option<FrameBuffer> {
+	let field = | position | ColorField { size: 8, position };
+	let format = match self.pixel_format {
+	    PIXEL_RGB_RESERVED_8BPC => PixelFormat::Direct {
+		red: field(0),
+		green: field(8),
+		blue: field(16),
+	    },
+	    PIXEL_BGR_RESERVED_8BPC => PixelFormat::RGB888,
+	    _ => return None,
+	};
+
+	let fb = FrameBuffer {
+	    base: self.base,
+	    pitch: self.pixels_per_line as usize * 4,
+	    width: self.width as usize,
+	    height: self.height as usize,
+	    bpp: 32,
+	    format,
+	};
+	(fb.base != 0).then_some(fb)
+    }
+}
+
+
+/// The maximum number of address ranges converted from the memory map.
+pub const MAX_ADDR_RANGES: usize = 256;
+
+/// Resources obtained from UEFI boot services.
+pub struct EfiBootInfo {
+    addr_ranges: [AddrRange; MAX_ADDR_RANGES],
+    naddr_ranges: usize,
+    pub frame_buffer: Option<FrameBufferInfo>,
+}
+
+impl EfiBootInfo {
+    /// Returns the memory map in the E820 style.
+    pub fn memory_map(&self) -> &[AddrRange] {
+	&self.addr_ranges[.. self.naddr_ranges]
+    }
+}
+
+
+/// Errors in the UEFI boot path.
+#[derive(Debug)]
+pub enum EfiError {
+    /// GetMemoryMap() failed.
+    GetMemoryMap(Status),
+    /// ExitBootServices() failed.
+    ExitBootServices(Status),
+}
+
+
+// The buffer of the memory map.  Because the global allocator is not
+// initialized yet, a statically allocated buffer is used.
+const MEMORY_MAP_SIZE: usize = 16 * 1024;
+static mut MEMORY_MAP: [u64; MEMORY_MAP_SIZE / 8] = [0; MEMORY_MAP_SIZE / 8];
+
+
+///
+/// Obtains the memory map and the GOP frame buffer, then exits boot
+/// services.
+///
+/// # Safety
+///
+/// `system_table` must be the pointer passed to `efi_main`.  Boot
+/// services must not be used after this function returns.
+///
+pub unsafe fn boot(image_handle: Handle, system_table: *mut SystemTable)
+		   -> Result<EfiBootInfo, EfiError> {
+    let bs = &*(*system_table).boot_services;
+
+    // Disable the watchdog timer (5 minutes by default).
+    (bs.set_watchdog_timer)(0, 0, 0, null_mut());
+
+    // Query the frame buffer of GOP (if available).
+    let frame_buffer = query_frame_buffer(bs);
+
+    // Get the memory map, then exit boot services.
+    // Note: ExitBootServices() fails if the memory map has been changed
+    //       since GetMemoryMap().  In that case, retry once.
+    let mut retry = 1;
+    let (map_size, desc_size) = loop {
+	let mut map_size = MEMORY_MAP_SIZE;
+	let mut map_key = 0;
+	let mut desc_size = 0;
+	let mut desc_version = 0;
+
+	let status = (bs.get_memory_map)(
+	    &mut map_size,
+	    addr_of_mut!(MEMORY_MAP) as *mut MemoryDescriptor,
+	    &mut map_key, &mut desc_size, &mut desc_version);
+	if status != SUCCESS {
+	    return Err(EfiError::GetMemoryMap(status));
+	}
+
+	let status = (bs.exit_boot_services)(image_handle, map_key);
+	if status == SUCCESS {
+	    break (map_size, desc_size);
+	} else if retry == 0 {
+	    return Err(EfiError::ExitBootServices(status));
+	}
+	retry -= 1;
+    };
+
+    // Boot services are no longer available!
+
+    // Convert the memory map into E820-style address ranges.
+    let mut info = EfiBootInfo {
+	addr_ranges: [AddrRange {
+	    addr: 0, length: 0, atype: 0, attr: 0,
+	}; MAX_ADDR_RANGES],
+	naddr_ranges: 0,
+	frame_buffer,
+    };
+
+    let base = addr_of_mut!(MEMORY_MAP) as usize;
+    let mut offset = 0;
+    while offset + desc_size <= map_size &&
+	info.naddr_ranges < MAX_ADDR_RANGES {
+	let desc = &*((base + offset) as *const MemoryDescriptor);
+	info.addr_ranges[info.naddr_ranges] = desc.to_addr_range();
+	info.naddr_ranges += 1;
+	offset += desc_size;
+    }
+
+    Ok(info)
+}
+
+// Queries the current mode of GOP.
+unsafe fn query_frame_buffer(bs: &BootServices) -> Option<FrameBufferInfo> {
+    let mut interface: *mut c_void = null_mut();
+    let status = (bs.locate_protocol)(&GRAPHICS_OUTPUT_PROTOCOL_GUID,
+				      null_mut(), &mut interface);
+    if status != SUCCESS || interface.is_null() {
+	return None;
+    }
+
+    let gop = &*(interface as *const GraphicsOutput);
+    let mode = gop.mode.as_ref()?;
+    let info = mode.info.as_ref()?;
+
+    Some(FrameBufferInfo {
+	base: mode.frame_buffer_base as usize,
+	size: mode.frame_buffer_size,
+	width: info.horizontal_resolution,
+	height: info.vertical_resolution,
+	pixels_per_line: info.pixels_per_scan_line,
+	pixel_format: info.pixel_format,
+    })
+}
@@ -0,0 +1,60 @@
+/*!
+
+Stubs of symbols which are not linked in the UEFI boot path.
+
+The symbols defined in the assembly language source files and the
+linker script (cf. `bios::ffi`) are replaced with the stubs below.
+BIOS functions always fail, and all areas are empty.
+
+ */
+
+use core::arch::global_asm;
+
+use crate::bios::LmbiosRegs;
+
+
+// Returned by lmbios_call instead of a BIOS function number.
+const LMBIOS_UNSUPPORTED: u16 = 0xffff;
+
+#[no_mangle]
+extern "C" fn lmbios_call(_regs: &mut LmbiosRegs) -> u16 {
+    LMBIOS_UNSUPPORTED
+}
+
+#[no_mangle]
+extern "C" fn lmbios_get_boot_drive_id() -> u8 {
+    0
+}
+
+#[no_mangle]
+extern "C" fn debug_clear_stack_area() -> u64 {
+    0
+}
+
+// All areas defined in the linker script are empty (start = end).
+global_asm!(r#"
+	.section .bss.lmb_stubs, "aw"
+	.balign	16
+	.globl	__lmb_crashlog_start, __lmb_crashlog_end
+	.globl	__lmb_heap16_start, __lmb_heap16_end
+	.globl	__lmb_heap32_start, __lmb_heap32_end
+	.globl	__lmb_main1_start, __lmb_main1_end
+	.globl	__lmb_page_tables_start, __lmb_page_tables_end
+	.globl	__lmb_stack_start, __lmb_stack_end
+	.globl	__lmb_trailer_start
+__lmb_crashlog_start:
+__lmb_crashlog_end:
+__lmb_heap16_start:
+__lmb_heap16_end:
+__lmb_heap32_start:
+__lmb_heap32_end:
+__lmb_main1_start:
+__lmb_main1_end:
+__lmb_page_tables_start:
+__lmb_page_tables_end:
+__lmb_stack_start:
+__lmb_stack_end:
+__lmb_trailer_start:
+	.quad	0
+	.text
+"#, options(att_syntax));
@@ -0,0 +1,274 @@
+//
+// Disk Slice - A range of blocks of a block device as a file.
+//
+// A few consecutive blocks (`CACHE_BLOCKS`) are loaded into a cache
+// at a time, when a byte out of the cache is read.  Hence, reading
+// sequentially calls the device once per `CACHE_BLOCKS` blocks, and
+// the heap holds no more than them.  Writing goes through the cache
+// to the device, i.e. the blocks modified are written immediately.
+//
+
+use alloc::vec::Vec;
+use core::cmp::min;
+
+use super::{IoError, Read, Seek, SeekFrom, Write};
+use crate::storage::{BlockDevice, BlockError};
+
+
+/// The number of blocks loaded into the cache at a time.
+pub const CACHE_BLOCKS: u64 = 8;
+
+
+/// A range of blocks of a block device, read as a file.
+pub struct DiskSlice<D>
+where
+    D: BlockDevice,
+{
+    device: D,
+    start_lba: u64,		// The First Block
+    len: u64,			// Length in Bytes
+    pos: u64,			// Current Position
+    cache: Vec<u8>,		// Cached Blocks
+    cache_lba: Option<u64>,	// The First Block Cached (from start_lba)
+}
+
+impl<D> DiskSlice<D>
+where
+    D: BlockDevice,
+{
+    ///
+    /// Returns `len` bytes from the block `start_lba` of the device as
+    /// a file.  The blocks must be within the device.
+    ///
+    pub fn new(device: D, start_lba: u64, len: u64)
+	       -> Result<Self, BlockError> {
+	let count = len.div_ceil(device.block_size() as u64);
+	match start_lba.checked_add(count) {
+	    Some(end) if end <= device.num_blocks() => (),
+	    _ => return Err(BlockError::OutOfRange { lba: start_lba, count }),
+	}
+
+	Ok(Self {
+	    device,
+	    start_lba,
+	    len,
+	    pos: 0,
+	    cache: Vec::new(),
+	    cache_lba: None,
+	})
+    }
+
+    /// Returns the length in bytes.
+    pub fn len(&self) -> u64 {
+	self.len
+    }
+
+    /// Returns true if the length is zero.
+    pub fn is_empty(&self) -> bool {
+	self.len == 0
+    }
+
+    /// Returns the block device.
+    pub fn into_inner(self) -> D {
+	self.device
+    }
+
+    // Loads the blocks from the block (relative to `start_lba`) into the
+    // cache unless cached, then returns the cached bytes from `pos`.
+    fn cached(&mut self) -> Result<&[u8], BlockError> {
+	let (offset, end) = self.load_cache()?;
+	Ok(&self.cache[offset .. end])
+    }
+
+    // Loads the blocks into the cache unless cached, then returns the
+    // range in the cache from `pos` to the end of the cache (or file).
+    fn load_cache(&mut self) -> Result<(usize, usize), BlockError> {
+	let block_size = self.device.block_size() as u64;
+	let block = self.pos / block_size;
+
+	let hit = self.cache_lba.is_some_and(| lba | {
+	    block >= lba && block < lba + (self.cache.len() as u64 / block_size)
+	});
+	if !hit {
+	    let total = self.len.div_ceil(block_size);
+	    let count = min(CACHE_BLOCKS, total - block);
+	    self.cache_lba = None;
+	    self.cache.resize((count * block_size) as usize, 0);
+	    self.device.read_blocks(self.start_lba + block, &mut self.cache)?;
+	    self.cache_lba = Some(block);
+	}
+
+	let cache_start = self.cache_lba.unwrap_or(0) * block_size;
+	let offset = (self.pos - cache_start) as usize;
+	let end = min(self.cache.len() as u64, self.len - cache_start);
+	Ok((offset, end as usize))
+    }
+}
+
+impl<D> Read for DiskSlice<D>
+where
+    D: BlockDevice,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+	if buf.is_empty() || self.pos >= self.len {
+	    return Ok(0);
+	}
+
+	let cached = self.cached()?;
+	let n = min(buf.len(), cached.len());
+	buf[.. n].copy_from_slice(&cached[.. n]);
+	self.pos += n as u64;
+	Ok(n)
+    }
+}
+
+impl<D> Write for DiskSlice<D>
+where
+    D: BlockDevice,
+{
+    ///
+    /// Writes bytes through the cache to the device (i.e., nothing is
+    /// buffered).  The file is not extended; 0 is returned at the end.
+    ///
+    fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+	if buf.is_empty() || self.pos >= self.len {
+	    return Ok(0);
+	}
+
+	let (offset, end) = self.load_cache()?;
+	let n = min(buf.len(), end - offset);
+	self.cache[offset .. offset + n].copy_from_slice(&buf[.. n]);
+
+	// Write the blocks modified.
+	let block_size = self.device.block_size();
+	let first = offset / block_size;
+	let last = (offset + n).div_ceil(block_size);
+	let lba = self.start_lba + self.cache_lba.unwrap_or(0) + first as u64;
+	let blocks = &self.cache[first * block_size .. last * block_size];
+	if let Err(err) = self.device.write_blocks(lba, blocks) {
+	    // The cache no longer matches the device.
+	    self.cache_lba = None;
+	    return Err(err.into());
+	}
+
+	self.pos += n as u64;
+	Ok(n)
+    }
+}
+
+impl<D> Seek for DiskSlice<D>
+where
+    D: BlockDevice,
+{
+    ///
+    /// Moves the cursor.  It may be moved beyond the end, where
+    /// nothing is read.
+    ///
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, IoError> {
+	let new_pos = match pos {
+	    SeekFrom::Start(offset) => Some(offset),
+	    SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+	    SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+	};
+	self.pos = new_pos.ok_or(IoError::InvalidSeek)?;
+	Ok(self.pos)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A block device on memory counting the reads.
+    struct RamDisk {
+	data: Vec<u8>,
+	reads: usize,
+	writes: usize,
+    }
+
+    impl BlockDevice for RamDisk {
+	fn block_size(&self) -> usize {
+	    512
+	}
+
+	fn num_blocks(&self) -> u64 {
+	    self.data.len() as u64 / 512
+	}
+
+	fn read_blocks(&mut self, lba: u64, buf: &mut [u8])
+		       -> Result<(), BlockError> {
+	    self.check_range(lba, buf.len())?;
+	    let start = lba as usize * 512;
+	    buf.copy_from_slice(&self.data[start .. start + buf.len()]);
+	    self.reads += 1;
+	    Ok(())
+	}
+
+	fn write_blocks(&mut self, lba: u64, buf: &[u8])
+			-> Result<(), BlockError> {
+	    self.check_range(lba, buf.len())?;
+	    let start = lba as usize * 512;
+	    self.data[start .. start + buf.len()].copy_from_slice(buf);
+	    self.writes += 1;
+	    Ok(())
+	}
+    }
+
+    #[test]
+    fn read_and_seek() {
+	let data = (0 .. 64 * 512).map(| i | (i % 251) as u8).collect();
+	let disk = RamDisk { data, reads: 0, writes: 0 };
+
+	// Blocks 2 to 19 (the last one partially)
+	let len = 17 * 512 + 100;
+	let mut file = DiskSlice::new(disk, 2, len).unwrap();
+	let expected = | pos: u64 | ((2 * 512 + pos) % 251) as u8;
+
+	let mut buf = [0; 700];
+	file.read_exact(&mut buf).unwrap();
+	assert!((0 ..).zip(buf).all(| (pos, b) | b == expected(pos)));
+
+	// Reading within the cache calls the device once.
+	assert_eq!(file.seek(SeekFrom::Start(10)), Ok(10));
+	file.read_exact(&mut buf[.. 16]).unwrap();
+	assert_eq!(file.device.reads, 1);
+
+	// Read the last bytes (out of the cache), then nothing.
+	assert_eq!(file.seek(SeekFrom::End(-50)), Ok(len - 50));
+	assert_eq!(file.read(&mut buf), Ok(50));
+	assert_eq!(buf[49], expected(len - 1));
+	assert_eq!(file.read(&mut buf), Ok(0));
+	assert_eq!(file.read_exact(&mut buf[.. 1]),
+		   Err(IoError::UnexpectedEof));
+
+	assert_eq!(file.seek(SeekFrom::Current(-(len as i64) - 1)),
+		   Err(IoError::InvalidSeek));
+	assert!(DiskSlice::new(file.into_inner(), 60, 5 * 512).is_err());
+    }
+
+    #[test]
+    fn write_through() {
+	let disk = RamDisk { data: vec![0; 16 * 512], reads: 0, writes: 0 };
+	let mut file = DiskSlice::new(disk, 1, 3 * 512).unwrap();
+
+	// Across a block boundary: two blocks are written at once.
+	file.seek(SeekFrom::Start(500)).unwrap();
+	file.write_all(&[0xaa; 20]).unwrap();
+	assert_eq!(file.device.writes, 1);
+
+	// The bytes are read from the cache and the device.
+	let mut buf = [0; 22];
+	file.seek(SeekFrom::Start(499)).unwrap();
+	file.read_exact(&mut buf).unwrap();
+	assert_eq!((buf[0], buf[1], buf[20], buf[21]), (0, 0xaa, 0xaa, 0));
+	assert!(file.device.data[512 + 500 .. 512 + 520]
+		.iter().all(| &b | b == 0xaa));
+
+	// The file is not extended.
+	file.seek(SeekFrom::End(-1)).unwrap();
+	assert_eq!(file.write_all(&[1, 2]), Err(IoError::WriteZero));
+	assert_eq!(file.device.data[4 * 512 - 1], 1);
+	assert_eq!(file.device.data[4 * 512], 0);
+    }
+}
@@ -0,0 +1,124 @@
+//
+// I/O Traits - no_std counterparts of `std::io::Read`, `Write` and
+// `Seek`.
+//
+// They are implemented by files (`DiskSlice` and `MemSlice`), serial
+// ports and in-memory buffers (`Vec<u8, A>` with any allocator), so
+// that parsers and loaders can be built on one I/O abstraction.
+//
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::fmt;
+
+use crate::storage::BlockError;
+
+
+/// Errors returned by [`Read`] and [`Seek`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IoError {
+    /// The underlying block device failed.
+    Block(BlockError),
+    /// The position would be before the start (or overflow).
+    InvalidSeek,
+    /// The end was reached before the buffer was filled.
+    UnexpectedEof,
+    /// The end was reached before the buffer was written.
+    WriteZero,
+}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Block(err) => write!(f, "I/O: {}", err),
+	    Self::InvalidSeek => write!(f, "I/O: Invalid seek"),
+	    Self::UnexpectedEof => write!(f, "I/O: Unexpected end of file"),
+	    Self::WriteZero => write!(f, "I/O: Write beyond the end"),
+	}
+    }
+}
+
+impl From<BlockError> for IoError {
+    fn from(err: BlockError) -> Self {
+	Self::Block(err)
+    }
+}
+
+
+/// A source of bytes.
+pub trait Read {
+    ///
+    /// Reads bytes into `buf`, then returns the number of bytes read
+    /// (0 at the end).
+    ///
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError>;
+
+    /// Reads exactly `buf.len()` bytes.
+    fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<(), IoError> {
+	while !buf.is_empty() {
+	    match self.read(buf)? {
+		0 => return Err(IoError::UnexpectedEof),
+		n => buf = &mut buf[n ..],
+	    }
+	}
+	Ok(())
+    }
+}
+
+/// A sink of bytes.
+pub trait Write {
+    ///
+    /// Writes bytes from `buf`, then returns the number of bytes
+    /// written (0 if no more bytes can be written).
+    ///
+    fn write(&mut self, buf: &[u8]) -> Result<usize, IoError>;
+
+    /// Writes the bytes buffered (if any) to the underlying device.
+    fn flush(&mut self) -> Result<(), IoError> {
+	Ok(())
+    }
+
+    /// Writes all bytes of `buf`.
+    fn write_all(&mut self, mut buf: &[u8]) -> Result<(), IoError> {
+	while !buf.is_empty() {
+	    match self.write(buf)? {
+		0 => return Err(IoError::WriteZero),
+		n => buf = &buf[n ..],
+	    }
+	}
+	Ok(())
+    }
+}
+
+// An in-memory buffer appends the bytes (in its allocator).
+impl<A> Write for Vec<u8, A>
+where
+    A: Allocator,
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+	self.extend_from_slice(buf);
+	Ok(buf.len())
+    }
+}
+
+/// Positions to seek to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SeekFrom {
+    /// From the start
+    Start(u64),
+    /// From the end (usually negative)
+    End(i64),
+    /// From the current position
+    Current(i64),
+}
+
+/// A cursor which can be moved.
+pub trait Seek {
+    /// Moves the cursor, then returns the new position from the start.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, IoError>;
+
+    /// Returns the current position from the start.
+    fn stream_position(&mut self) -> Result<u64, IoError> {
+	self.seek(SeekFrom::Current(0))
+    }
+}
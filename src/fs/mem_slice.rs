@@ -0,0 +1,104 @@
+//
+// Memory Slice - A range of memory as a file.
+//
+// It lets the loaders (cf. `loader`) read a program that is already in
+// memory, e.g., received over the network or embedded in the image,
+// in the same way as a program on a disk (cf. `DiskSlice`).
+//
+
+use core::cmp::min;
+use core::slice;
+
+use super::{IoError, Read, Seek, SeekFrom};
+
+
+/// A range of memory, read as a file.
+pub struct MemSlice<'a> {
+    bytes: &'a [u8],
+    pos: u64,			// Current Position
+}
+
+impl<'a> MemSlice<'a> {
+    /// Returns the bytes as a file.
+    pub fn new(bytes: &'a [u8]) -> Self {
+	Self { bytes, pos: 0 }
+    }
+
+    /// Returns the length in bytes.
+    pub fn len(&self) -> u64 {
+	self.bytes.len() as u64
+    }
+
+    /// Returns true if it is empty.
+    pub fn is_empty(&self) -> bool {
+	self.bytes.is_empty()
+    }
+}
+
+impl MemSlice<'static> {
+    ///
+    /// Returns `len` bytes at the physical address `addr` as a file.
+    ///
+    /// # Safety
+    ///
+    /// The range must be mapped (i.e., below 4GiB) and must not be
+    /// written while it is read.
+    ///
+    pub unsafe fn from_addr(addr: u64, len: u64) -> Self {
+	Self::new(slice::from_raw_parts(addr as *const u8, len as usize))
+    }
+}
+
+impl Read for MemSlice<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+	if buf.is_empty() || self.pos >= self.len() {
+	    return Ok(0);
+	}
+
+	let rest = &self.bytes[self.pos as usize ..];
+	let n = min(buf.len(), rest.len());
+	buf[.. n].copy_from_slice(&rest[.. n]);
+	self.pos += n as u64;
+	Ok(n)
+    }
+}
+
+impl Seek for MemSlice<'_> {
+    ///
+    /// Moves the cursor.  It may be moved beyond the end, where
+    /// nothing is read.
+    ///
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, IoError> {
+	let new_pos = match pos {
+	    SeekFrom::Start(offset) => Some(offset),
+	    SeekFrom::End(offset) => self.len().checked_add_signed(offset),
+	    SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+	};
+	self.pos = new_pos.ok_or(IoError::InvalidSeek)?;
+	Ok(self.pos)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_and_seek() {
+	let bytes: Vec<u8> = (0 .. 100).collect();
+	let mut file = MemSlice::new(&bytes);
+
+	let mut buf = [0; 8];
+	file.seek(SeekFrom::End(-4)).unwrap();
+	assert_eq!(file.read(&mut buf).unwrap(), 4);
+	assert_eq!(buf[.. 4], [96, 97, 98, 99]);
+	assert_eq!(file.read(&mut buf).unwrap(), 0);
+
+	file.seek(SeekFrom::Start(10)).unwrap();
+	file.read_exact(&mut buf).unwrap();
+	assert_eq!(buf[0], 10);
+	assert_eq!(file.seek(SeekFrom::Current(-20)),
+		   Err(IoError::InvalidSeek));
+    }
+}
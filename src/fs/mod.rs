@@ -0,0 +1,37 @@
+/*!
+
+Provides file-like access to data on block devices.
+
+* `Read`, `Write` and `Seek` - no_std counterparts of those of
+  `std::io`, on which parsers and loaders (e.g., of ELF, BMP or ISO
+  9660) can be built.  Besides the files below, they are implemented
+  by `x86::SerialPort` and by in-memory buffers (`Vec<u8, A>` writes
+  in any allocator `A`).
+
+* `DiskSlice` - a range of blocks of a
+  [`BlockDevice`](crate::storage::BlockDevice) as a file.  Blocks are
+  loaded lazily and cached a few at a time, so that a large file can be
+  parsed without loading all of it into the limited heaps.  Writes go
+  through to the device.
+
+* `MemSlice` - a range of memory as a file, e.g., a program received
+  over the network, to be loaded by [`loader`](crate::loader).
+
+```ignore
+let disk = storage::BiosDisk::open(drive_id, &ALLOC_UNDER20)?;
+let mut file = fs::DiskSlice::new(disk, lba, len)?;
+file.seek(fs::SeekFrom::Start(0x40))?;
+let mut header = [0; 16];
+file.read_exact(&mut header)?;
+```
+
+ */
+
+
+#[doc(hidden)] pub mod disk_slice;
+#[doc(hidden)] pub mod io;
+#[doc(hidden)] pub mod mem_slice;
+
+#[doc(inline)] pub use self::disk_slice::DiskSlice;
+#[doc(inline)] pub use self::io::{IoError, Read, Seek, SeekFrom, Write};
+#[doc(inline)] pub use self::mem_slice::MemSlice;
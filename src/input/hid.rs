@@ -0,0 +1,104 @@
+//
+// HID Boot Keyboard - Translates boot protocol input reports.
+//
+// Report Format (8 bytes):
+//	00:    Modifier keys (bit 1 = Left Shift, bit 5 = Right Shift, ...)
+//	01:    Reserved
+//	02-07: Usage IDs of the keys pressed (0 if none, 1 on rollover)
+//
+
+use super::Key;
+use super::keymap::{self, Modifiers};
+
+
+/// The size in bytes of boot protocol keyboard reports.
+pub const REPORT_SIZE: usize = 8;
+
+// Modifier Keys
+const MOD_LEFT_SHIFT: u8 = 1 << 1;
+const MOD_RIGHT_SHIFT: u8 = 1 << 5;
+const MOD_RIGHT_ALT: u8 = 1 << 6;	// AltGr
+
+// Usage IDs (Keyboard/Keypad Page)
+const USAGE_ROLLOVER: u8 = 0x01;
+const USAGE_CAPS_LOCK: u8 = 0x39;
+
+/// The state of a boot protocol keyboard.
+pub struct BootKeyboard {
+    prev: [u8; REPORT_SIZE],	// The last report
+    caps_lock: bool,
+    pending: [Option<Key>; 6],	// Keys not yet returned
+}
+
+impl BootKeyboard {
+    /// Returns the initial state (no keys pressed).
+    pub const fn new() -> Self {
+	Self {
+	    prev: [0; REPORT_SIZE],
+	    caps_lock: false,
+	    pending: [None; 6],
+	}
+    }
+
+    /// Updates the state by the report, queueing newly pressed keys.
+    pub fn update(&mut self, report: &[u8; REPORT_SIZE]) {
+	if report[2] == USAGE_ROLLOVER {
+	    return;	// Too many keys are pressed.
+	}
+
+	let shift = (report[0] & (MOD_LEFT_SHIFT | MOD_RIGHT_SHIFT)) != 0;
+	let alt_gr = (report[0] & MOD_RIGHT_ALT) != 0;
+	for &usage in &report[2 ..] {
+	    if usage == 0 || self.prev[2 ..].contains(&usage) {
+		continue;
+	    }
+	    if usage == USAGE_CAPS_LOCK {
+		self.caps_lock = !self.caps_lock;
+		continue;
+	    }
+	    if let Some(key) = translate(usage, Modifiers {
+		shift,
+		alt_gr,
+		caps_lock: self.caps_lock,
+	    }) {
+		if let Some(slot) = self.pending.iter_mut()
+		    .find(|slot| slot.is_none()) {
+		    *slot = Some(key);
+		}
+	    }
+	}
+	self.prev = *report;
+    }
+
+    /// Returns a queued key if any.
+    pub fn pop(&mut self) -> Option<Key> {
+	let key = self.pending[0].take()?;
+	self.pending.rotate_left(1);
+	Some(key)
+    }
+}
+
+impl Default for BootKeyboard {
+    fn default() -> Self {
+	Self::new()
+    }
+}
+
+
+// Translates a usage ID into a key.
+fn translate(usage: u8, modifiers: Modifiers) -> Option<Key> {
+    match usage {
+	0x4a => return Some(Key::Home),
+	0x4c => return Some(Key::Delete),
+	0x4d => return Some(Key::End),
+	0x4f => return Some(Key::Right),
+	0x50 => return Some(Key::Left),
+	0x51 => return Some(Key::Down),
+	0x52 => return Some(Key::Up),
+	0x58 => return Some(Key::Enter),	// Keypad Enter
+	_ => (),
+    }
+
+    // Characters depend on the keyboard layout.
+    keymap::translate(usage, modifiers)
+}
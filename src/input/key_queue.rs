@@ -0,0 +1,102 @@
+//
+// Key Queue - A single queue of key events from all keyboards.
+//
+// Key events are pushed by interrupt handlers or drivers (e.g., of a
+// USB keyboard).  When the queue is empty, the PS/2 keyboard
+// controller is polled as a fallback, since no IRQ handler is
+// installed in this environment.
+//
+
+use super::{Key, ps2};
+use crate::mu::{MuMutex, MuRingBuf};
+use crate::x86::idle;
+
+
+// The number of key events kept in the queue
+const QUEUE_SIZE: usize = 32;
+
+/// The key queue shared by all keyboards.
+pub static KEY_QUEUE: KeyQueue = KeyQueue::new();
+
+
+/// Keyboards generating key events.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeySource {
+    Ps2,
+    Bios,
+    Usb,
+}
+
+/// A key pressed on a keyboard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub source: KeySource,
+}
+
+
+/// A queue of key events.
+pub struct KeyQueue {
+    events: MuMutex<MuRingBuf<KeyEvent, QUEUE_SIZE>>,
+}
+
+impl KeyQueue {
+    /// Returns an empty queue.
+    pub const fn new() -> Self {
+	let init = KeyEvent { key: Key::Enter, source: KeySource::Ps2 };
+	Self { events: MuMutex::new(MuRingBuf::new(init)) }
+    }
+
+    ///
+    /// Pushes a key event, and returns false if the queue is full
+    /// (the event is dropped).
+    ///
+    pub fn push(&self, event: KeyEvent) -> bool {
+	self.events.lock().push(event).is_ok()
+    }
+
+    /// Returns true if a key event is available (non-blocking).
+    pub fn is_pending(&self) -> bool {
+	self.fill();
+	!self.events.lock().is_empty()
+    }
+
+    /// Returns a key event if available (non-blocking).
+    pub fn poll(&self) -> Option<KeyEvent> {
+	self.fill();
+	self.events.lock().pop()
+    }
+
+    ///
+    /// Waits for a key event.  The processor is halted between polls
+    /// if interrupts are enabled.
+    ///
+    pub fn wait(&self) -> KeyEvent {
+	loop {
+	    if let Some(event) = self.poll() {
+		return event;
+	    }
+	    idle();
+	}
+    }
+
+    /// Removes all key events.
+    pub fn clear(&self) {
+	self.events.lock().clear();
+    }
+
+    // Polls the PS/2 keyboard controller (fallback) if empty.
+    fn fill(&self) {
+	if self.events.lock().is_empty() {
+	    if let Some(key) = ps2::poll_key() {
+		self.push(KeyEvent { key, source: KeySource::Ps2 });
+	    }
+	}
+    }
+}
+
+impl Default for KeyQueue {
+    fn default() -> Self {
+	Self::new()
+    }
+}
@@ -0,0 +1,262 @@
+//
+// Keymap - Translates key positions into keys by a keyboard layout.
+//
+// Key positions are given as HID usage IDs (Keyboard/Keypad Page),
+// which do not depend on the layout.  Scan codes of PS/2 keyboards
+// (Scan Code Set 1) are converted into them by `usage_from_set1`.
+//
+// The layout is selected by the command line `keymap=<us|de|jp>`
+// (US by default), or by `set_layout`.  Only the keys generating
+// characters depend on the layout.  Dead keys are not supported,
+// that is, they generate their characters immediately.
+//
+// Supplementary Resource:
+//	https://usb.org/document-library/hid-usage-tables-15
+//
+
+use core::fmt;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use super::Key;
+use crate::cmdline;
+
+
+/// Keyboard layouts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    /// US (QWERTY)
+    Us,
+    /// German (QWERTZ)
+    De,
+    /// Japanese (JIS, 106/109 keys)
+    Jp,
+}
+
+impl Layout {
+    /// Returns the layout of the name ("us", "de" or "jp").
+    pub fn from_name(name: &str) -> Option<Self> {
+	match name {
+	    "us" => Some(Self::Us),
+	    "de" => Some(Self::De),
+	    "jp" => Some(Self::Jp),
+	    _ => None,
+	}
+    }
+
+    // Keys differing from the US layout
+    fn overrides(&self) -> &'static [Override] {
+	match self {
+	    Self::Us => &[],
+	    Self::De => &KEYMAP_DE,
+	    Self::Jp => &KEYMAP_JP,
+	}
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Us => write!(f, "US"),
+	    Self::De => write!(f, "German"),
+	    Self::Jp => write!(f, "Japanese"),
+	}
+    }
+}
+
+
+/// The state of modifier keys.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt_gr: bool,		// Right Alt
+    pub caps_lock: bool,
+}
+
+
+// The current layout (LAYOUT_UNSET until selected)
+const LAYOUT_UNSET: u8 = 0xff;
+static LAYOUT: AtomicU8 = AtomicU8::new(LAYOUT_UNSET);
+
+/// Selects the keyboard layout.
+pub fn set_layout(layout: Layout) {
+    LAYOUT.store(layout as u8, Ordering::Relaxed);
+}
+
+/// Returns the keyboard layout (given by `keymap=` if not selected).
+pub fn layout() -> Layout {
+    match LAYOUT.load(Ordering::Relaxed) {
+	x if x == Layout::Us as u8 => Layout::Us,
+	x if x == Layout::De as u8 => Layout::De,
+	x if x == Layout::Jp as u8 => Layout::Jp,
+	_ => {
+	    let layout = cmdline::value("keymap")
+		.and_then(Layout::from_name)
+		.unwrap_or(Layout::Us);
+	    set_layout(layout);
+	    layout
+	},
+    }
+}
+
+
+///
+/// Translates a key position (a HID usage ID) into a key by the
+/// current layout.
+///
+/// Only the keys generating characters (and Enter, Escape,
+/// Backspace and Tab) are translated.  Returns None for the others.
+///
+pub fn translate(usage: u8, modifiers: Modifiers) -> Option<Key> {
+    let (normal, shifted, alt_gr) =
+	match layout().overrides().iter().find(| o | o.0 == usage) {
+	    Some(&(_, normal, shifted, alt_gr)) => (normal, shifted, alt_gr),
+	    None => {
+		let (normal, shifted) = us_chars(usage)?;
+		(normal, shifted, NONE)
+	    },
+	};
+
+    // Caps Lock affects letters only (e.g., not 'ß' of German).
+    let letter = normal.is_alphabetic() &&
+	normal.to_uppercase().eq(core::iter::once(shifted));
+    let ch =
+	if modifiers.alt_gr {
+	    alt_gr
+	} else if letter && modifiers.caps_lock {
+	    if modifiers.shift { normal } else { shifted }
+	} else if modifiers.shift {
+	    shifted
+	} else {
+	    normal
+	};
+
+    match ch {
+	NONE => None,
+	'\x08' => Some(Key::Backspace),
+	'\x1b' => Some(Key::Escape),
+	'\t' => Some(Key::Tab),
+	'\n' => Some(Key::Enter),
+	_ => Some(Key::Char(ch)),
+    }
+}
+
+// Returns the characters of the key position in the US layout.
+fn us_chars(usage: u8) -> Option<(char, char)> {
+    match usage {
+	USAGE_KEYPAD_ASTERISK => Some(('*', '*')),
+	_ => {
+	    let index = (usage as usize).checked_sub(USAGE_FIRST as usize)?;
+	    let (normal, shifted) = *KEYMAP_US.get(index)?;
+	    Some((normal as char, shifted as char))
+	},
+    }
+}
+
+///
+/// Converts a scan code (Scan Code Set 1, not extended) into a key
+/// position (a HID usage ID).  Returns None for modifier keys and
+/// the keys not generating characters.
+///
+pub fn usage_from_set1(code: u8) -> Option<u8> {
+    let usage = match code {
+	0x56 => USAGE_NON_US_BACKSLASH,
+	0x73 => USAGE_INTERNATIONAL1,
+	0x7d => USAGE_INTERNATIONAL3,
+	_ => *SET1_TO_USAGE.get(code as usize)?,
+    };
+    (usage != 0).then_some(usage)
+}
+
+
+// Usage IDs (Keyboard/Keypad Page)
+const USAGE_FIRST: u8 = 0x04;			// a and A
+const USAGE_KEYPAD_ASTERISK: u8 = 0x55;
+const USAGE_NON_US_BACKSLASH: u8 = 0x64;	// \ and | (ISO, 102nd key)
+const USAGE_INTERNATIONAL1: u8 = 0x87;		// \ and _ (JIS, Ro)
+const USAGE_INTERNATIONAL3: u8 = 0x89;		// Yen and | (JIS)
+
+// No character is generated.
+const NONE: char = '\0';
+
+// Usage IDs 0x04-0x38 to characters (US Layout): (Normal, Shifted)
+const KEYMAP_US: [(u8, u8); 0x35] = [
+    (b'a', b'A'), (b'b', b'B'), (b'c', b'C'), (b'd', b'D'),	// 04-07
+    (b'e', b'E'), (b'f', b'F'), (b'g', b'G'), (b'h', b'H'),	// 08-0B
+    (b'i', b'I'), (b'j', b'J'), (b'k', b'K'), (b'l', b'L'),	// 0C-0F
+    (b'm', b'M'), (b'n', b'N'), (b'o', b'O'), (b'p', b'P'),	// 10-13
+    (b'q', b'Q'), (b'r', b'R'), (b's', b'S'), (b't', b'T'),	// 14-17
+    (b'u', b'U'), (b'v', b'V'), (b'w', b'W'), (b'x', b'X'),	// 18-1B
+    (b'y', b'Y'), (b'z', b'Z'),					// 1C-1D
+    (b'1', b'!'), (b'2', b'@'), (b'3', b'#'), (b'4', b'$'),	// 1E-21
+    (b'5', b'%'), (b'6', b'^'), (b'7', b'&'), (b'8', b'*'),	// 22-25
+    (b'9', b'('), (b'0', b')'),					// 26-27
+    (b'\n', b'\n'), (0x1b, 0x1b), (0x08, 0x08), (b'\t', b'\t'),	// 28-2B
+    (b' ', b' '), (b'-', b'_'), (b'=', b'+'), (b'[', b'{'),	// 2C-2F
+    (b']', b'}'), (b'\\', b'|'), (0, 0), (b';', b':'),		// 30-33
+    (b'\'', b'"'), (b'`', b'~'), (b',', b'<'), (b'.', b'>'),	// 34-37
+    (b'/', b'?'),						// 38
+];
+
+// Keys differing from the US layout: (Usage ID, Normal, Shifted, AltGr)
+type Override = (u8, char, char, char);
+
+const KEYMAP_DE: [Override; 25] = [
+    (0x08, 'e', 'E', '€'),
+    (0x10, 'm', 'M', 'µ'),
+    (0x14, 'q', 'Q', '@'),
+    (0x1c, 'z', 'Z', NONE),
+    (0x1d, 'y', 'Y', NONE),
+    (0x1f, '2', '"', '²'),
+    (0x20, '3', '§', '³'),
+    (0x23, '6', '&', NONE),
+    (0x24, '7', '/', '{'),
+    (0x25, '8', '(', '['),
+    (0x26, '9', ')', ']'),
+    (0x27, '0', '=', '}'),
+    (0x2d, 'ß', '?', '\\'),
+    (0x2e, '´', '`', NONE),
+    (0x2f, 'ü', 'Ü', NONE),
+    (0x30, '+', '*', '~'),
+    (0x31, '#', '\'', NONE),
+    (0x32, '#', '\'', NONE),
+    (0x33, 'ö', 'Ö', NONE),
+    (0x34, 'ä', 'Ä', NONE),
+    (0x35, '^', '°', NONE),
+    (0x36, ',', ';', NONE),
+    (0x37, '.', ':', NONE),
+    (0x38, '-', '_', NONE),
+    (USAGE_NON_US_BACKSLASH, '<', '>', '|'),
+];
+
+// Note: The Yen key generates a backslash (shown as Yen in JIS X 0201).
+const KEYMAP_JP: [Override; 17] = [
+    (0x1f, '2', '"', NONE),
+    (0x23, '6', '&', NONE),
+    (0x24, '7', '\'', NONE),
+    (0x25, '8', '(', NONE),
+    (0x26, '9', ')', NONE),
+    (0x27, '0', NONE, NONE),
+    (0x2d, '-', '=', NONE),
+    (0x2e, '^', '~', NONE),
+    (0x2f, '@', '`', NONE),
+    (0x30, '[', '{', NONE),
+    (0x31, ']', '}', NONE),
+    (0x32, ']', '}', NONE),
+    (0x33, ';', '+', NONE),
+    (0x34, ':', '*', NONE),
+    (0x35, NONE, NONE, NONE),		// Hankaku/Zenkaku
+    (USAGE_INTERNATIONAL1, '\\', '_', NONE),
+    (USAGE_INTERNATIONAL3, '\\', '|', NONE),
+];
+
+// Scan codes 0x00-0x39 (Scan Code Set 1) to usage IDs (0 if none)
+const SET1_TO_USAGE: [u8; 0x3a] = [
+    0x00, 0x29, 0x1e, 0x1f, 0x20, 0x21, 0x22, 0x23,	// 00-07
+    0x24, 0x25, 0x26, 0x27, 0x2d, 0x2e, 0x2a, 0x2b,	// 08-0F
+    0x14, 0x1a, 0x08, 0x15, 0x17, 0x1c, 0x18, 0x0c,	// 10-17
+    0x12, 0x13, 0x2f, 0x30, 0x28, 0x00, 0x04, 0x16,	// 18-1F
+    0x07, 0x09, 0x0a, 0x0b, 0x0d, 0x0e, 0x0f, 0x33,	// 20-27
+    0x34, 0x35, 0x00, 0x31, 0x1d, 0x1b, 0x06, 0x19,	// 28-2F
+    0x05, 0x11, 0x10, 0x36, 0x37, 0x38, 0x00, 0x55,	// 30-37
+    0x00, 0x2c,						// 38-39
+];
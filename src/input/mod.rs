@@ -0,0 +1,66 @@
+/*!
+
+Provides keyboard input.
+
+* `KeyQueue` - a single queue of key events from all keyboards.
+  `KEY_QUEUE` is shared by the console, the shell and demos, which
+  read keys by `KEY_QUEUE.poll()` (non-blocking) or
+  `KEY_QUEUE.wait()` (blocking).
+
+* `ps2` - reads keys from a PS/2 keyboard by polling the keyboard
+  controller (Scan Code Set 1).  `set_leds` sets its LEDs, and Scroll
+  Lock pauses printing to the serial port.  The typematic rate can be
+  set by `bios::int16h03h`.  Alternatively, keys can be read through
+  BIOS by `bios::int16h00h` (blocking) and `bios::int16h01h` (polling),
+  whose keystrokes are translated by `KeyStroke::key`.
+
+* `hid` - translates input reports of USB keyboards in the boot
+  protocol, e.g., those read by `drivers::usb_uhci`.
+
+* `keymap` - translates key positions into characters by the keyboard
+  layout (US, German or Japanese) given by `keymap=<us|de|jp>` on
+  the command line.
+
+# Supplementary Resources
+
+* [PS/2 Keyboard](https://wiki.osdev.org/PS/2_Keyboard) (OS Dev)
+* [8042 PS/2 Controller](https://wiki.osdev.org/%228042%22_PS/2_Controller) (OS Dev)
+* [HID Usage Tables](https://usb.org/document-library/hid-usage-tables-15) (USB-IF)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://wiki.osdev.org/PS/2_Keyboard
+//	https://wiki.osdev.org/"8042"_PS/2_Controller
+//	https://usb.org/document-library/hid-usage-tables-15
+//
+
+pub mod hid;
+#[doc(hidden)] pub mod key_queue;
+pub mod keymap;
+#[doc(hidden)] pub mod ps2;
+
+#[doc(inline)] pub use self::key_queue::{
+    KEY_QUEUE, KeyEvent, KeyQueue, KeySource,
+};
+#[doc(inline)] pub use self::ps2::{Leds, poll_key, read_key, set_leds};
+
+
+/// Keys returned by the keyboard layers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+    /// A printable character
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Tab,
+    Escape,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+}
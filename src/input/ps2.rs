@@ -0,0 +1,276 @@
+//
+// PS/2 Keyboard - Reads keys by polling the 8042 keyboard controller.
+//
+// Characters are translated by the keyboard layout (cf. `keymap`).
+//
+// The LEDs follow Caps Lock and Scroll Lock.  Scroll Lock pauses
+// printing to the serial port, which gives a cheap debugging switch
+// on real hardware independent of the screen.
+//
+
+use core::hint::spin_loop;
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use super::{Key, keymap};
+use crate::console::{self, Level, Sink};
+use crate::x86::{inb, outb};
+
+
+// I/O Ports of the 8042 Keyboard Controller
+const DATA_PORT: u16 = 0x60;
+const STATUS_PORT: u16 = 0x64;
+
+// Status Register
+const STATUS_OUTPUT_FULL: u8 = 1 << 0;	// Output Buffer Full
+const STATUS_INPUT_FULL: u8 = 1 << 1;	// Input Buffer Full
+const STATUS_AUX_DATA: u8 = 1 << 5;	// Data from the Auxiliary Device
+
+// Keyboard Commands and Responses
+const CMD_SET_LEDS: u8 = 0xed;
+const RESP_ACK: u8 = 0xfa;
+const RESP_RESEND: u8 = 0xfe;
+
+// The number of polls (and retries) before giving up a command.
+const POLL_LIMIT: usize = 100_000;
+const RESEND_LIMIT: usize = 3;
+
+// Keyboard Flags in the BIOS Data Area
+const BDA_KBD_FLAGS: usize = 0x417;	// bit 4-6: Scroll, Num, Caps Lock
+const BDA_KBD_LEDS: usize = 0x497;	// bit 0-2: Scroll, Num, Caps Lock
+const BDA_FLAGS_SHIFT: u8 = 4;
+const BDA_LEDS_MASK: u8 = 0x07;
+
+// Scan Code Set 1
+const SC_EXTENDED: u8 = 0xe0;		// Prefix of Extended Keys
+const SC_RELEASED: u8 = 0x80;		// Bit set in Break Codes
+const SC_LEFT_SHIFT: u8 = 0x2a;
+const SC_RIGHT_SHIFT: u8 = 0x36;
+const SC_CAPS_LOCK: u8 = 0x3a;
+const SC_SCROLL_LOCK: u8 = 0x46;
+const SC_ALT: u8 = 0x38;		// Right Alt (AltGr) if extended
+
+// Modifier State
+const MOD_SHIFT_LEFT: u8 = 1 << 0;
+const MOD_SHIFT_RIGHT: u8 = 1 << 1;
+const MOD_CAPS_LOCK: u8 = 1 << 2;
+const MOD_ALT_GR: u8 = 1 << 3;
+const MOD_SCROLL_LOCK: u8 = 1 << 4;
+const MOD_EXTENDED: u8 = 1 << 7;	// Extended prefix received
+
+static MODIFIERS: AtomicU8 = AtomicU8::new(0);
+
+// The level of the serial port paused by Scroll Lock (0 if none).
+static PAUSED_SERIAL_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+
+/// The state of the keyboard LEDs.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Leds {
+    pub scroll_lock: bool,
+    pub num_lock: bool,
+    pub caps_lock: bool,
+}
+
+impl Leds {
+    // Returns the LED bits (bit 0-2: Scroll, Num, Caps Lock).
+    fn bits(&self) -> u8 {
+	(self.scroll_lock as u8) |
+	(self.num_lock as u8) << 1 |
+	(self.caps_lock as u8) << 2
+    }
+}
+
+
+/// Reads a key (blocking).
+pub fn read_key() -> Key {
+    loop {
+	if let Some(key) = poll_key() {
+	    return key;
+	}
+	spin_loop();
+    }
+}
+
+/// Returns a key if available (non-blocking).
+pub fn poll_key() -> Option<Key> {
+    let status = unsafe { inb(STATUS_PORT) };
+    if (status & STATUS_OUTPUT_FULL) == 0 {
+	return None;
+    }
+
+    let scan_code = unsafe { inb(DATA_PORT) };
+    if (status & STATUS_AUX_DATA) != 0 {
+	return None;	// Ignore data from a PS/2 mouse.
+    }
+
+    translate(scan_code)
+}
+
+
+///
+/// Sets the LEDs of the PS/2 keyboard (command EDh), and records them
+/// in the BIOS Data Area so that BIOS keeps them.  Returns false if
+/// the keyboard does not acknowledge the command.
+///
+/// Note: Keys typed while the command is sent may be lost.
+///
+pub fn set_leds(leds: Leds) -> bool {
+    let bits = leds.bits();
+    if !send_command(CMD_SET_LEDS) || !send_command(bits) {
+	return false;
+    }
+
+    unsafe {
+	let flags = read_volatile(BDA_KBD_FLAGS as *const u8);
+	let flags = (flags & !(BDA_LEDS_MASK << BDA_FLAGS_SHIFT)) |
+	    bits << BDA_FLAGS_SHIFT;
+	write_volatile(BDA_KBD_FLAGS as *mut u8, flags);
+
+	let state = read_volatile(BDA_KBD_LEDS as *const u8);
+	write_volatile(BDA_KBD_LEDS as *mut u8,
+		       (state & !BDA_LEDS_MASK) | bits);
+    }
+    true
+}
+
+// Sends a byte to the keyboard, then waits for the acknowledgement.
+fn send_command(byte: u8) -> bool {
+    for _ in 0 .. RESEND_LIMIT {
+	if !wait_status(STATUS_INPUT_FULL, false) {
+	    return false;
+	}
+	unsafe {
+	    outb(DATA_PORT, byte);
+	}
+
+	if !wait_status(STATUS_OUTPUT_FULL, true) {
+	    return false;
+	}
+	match unsafe { inb(DATA_PORT) } {
+	    RESP_ACK => return true,
+	    RESP_RESEND => continue,
+	    _ => return false,
+	}
+    }
+    false
+}
+
+// Waits until the status bit becomes the state (false on timeout).
+fn wait_status(bit: u8, state: bool) -> bool {
+    for _ in 0 .. POLL_LIMIT {
+	if ((unsafe { inb(STATUS_PORT) } & bit) != 0) == state {
+	    return true;
+	}
+	spin_loop();
+    }
+    false
+}
+
+// Sets the LEDs by the modifier state (Num Lock is kept as BIOS has).
+fn update_leds(modifiers: u8) {
+    let flags = unsafe { read_volatile(BDA_KBD_FLAGS as *const u8) };
+    let _ = set_leds(Leds {
+	scroll_lock: (modifiers & MOD_SCROLL_LOCK) != 0,
+	num_lock: (flags & (1 << (BDA_FLAGS_SHIFT + 1))) != 0,
+	caps_lock: (modifiers & MOD_CAPS_LOCK) != 0,
+    });
+}
+
+// Pauses printing to the serial port (or resumes it).
+fn pause_serial(pause: bool) {
+    if pause {
+	let level = console::config().level(Sink::Serial);
+	PAUSED_SERIAL_LEVEL.store(level.map_or(0, |level| level as u8),
+				  Ordering::Relaxed);
+	console::set_sink_level(Sink::Serial, None);
+    } else {
+	let level = match PAUSED_SERIAL_LEVEL.swap(0, Ordering::Relaxed) {
+	    1 => Some(Level::Error),
+	    2 => Some(Level::Warn),
+	    3 => Some(Level::Info),
+	    4 => Some(Level::Debug),
+	    5 => Some(Level::Trace),
+	    _ => None,
+	};
+	console::set_sink_level(Sink::Serial, level);
+    }
+}
+
+// Translates a scan code into a key, and updates the modifier state.
+fn translate(scan_code: u8) -> Option<Key> {
+    let modifiers = MODIFIERS.load(Ordering::Relaxed);
+
+    if scan_code == SC_EXTENDED {
+	MODIFIERS.store(modifiers | MOD_EXTENDED, Ordering::Relaxed);
+	return None;
+    }
+    let extended = (modifiers & MOD_EXTENDED) != 0;
+    let modifiers = modifiers & !MOD_EXTENDED;
+    MODIFIERS.store(modifiers, Ordering::Relaxed);
+
+    let released = (scan_code & SC_RELEASED) != 0;
+    let code = scan_code & !SC_RELEASED;
+
+    // Update the modifier state.
+    let modifier =
+	match (extended, code) {
+	    (false, SC_LEFT_SHIFT) => MOD_SHIFT_LEFT,
+	    (false, SC_RIGHT_SHIFT) => MOD_SHIFT_RIGHT,
+	    (true, SC_ALT) => MOD_ALT_GR,
+	    (false, SC_CAPS_LOCK) => {
+		if !released {
+		    let modifiers = modifiers ^ MOD_CAPS_LOCK;
+		    MODIFIERS.store(modifiers, Ordering::Relaxed);
+		    update_leds(modifiers);
+		}
+		return None;
+	    },
+	    (false, SC_SCROLL_LOCK) => {
+		if !released {
+		    let modifiers = modifiers ^ MOD_SCROLL_LOCK;
+		    MODIFIERS.store(modifiers, Ordering::Relaxed);
+		    update_leds(modifiers);
+		    pause_serial((modifiers & MOD_SCROLL_LOCK) != 0);
+		}
+		return None;
+	    },
+	    _ => 0,
+	};
+    if modifier != 0 {
+	let modifiers =
+	    if released {
+		modifiers & !modifier
+	    } else {
+		modifiers | modifier
+	    };
+	MODIFIERS.store(modifiers, Ordering::Relaxed);
+	return None;
+    }
+
+    if released {
+	return None;
+    }
+
+    if extended {
+	return match code {
+	    0x1c => Some(Key::Enter),		// Keypad Enter
+	    0x47 => Some(Key::Home),
+	    0x48 => Some(Key::Up),
+	    0x4b => Some(Key::Left),
+	    0x4d => Some(Key::Right),
+	    0x4f => Some(Key::End),
+	    0x50 => Some(Key::Down),
+	    0x53 => Some(Key::Delete),
+	    _ => None,
+	};
+    }
+
+    // Characters depend on the keyboard layout.
+    let usage = keymap::usage_from_set1(code)?;
+    keymap::translate(usage, keymap::Modifiers {
+	shift: (modifiers & (MOD_SHIFT_LEFT | MOD_SHIFT_RIGHT)) != 0,
+	alt_gr: (modifiers & MOD_ALT_GR) != 0,
+	caps_lock: (modifiers & MOD_CAPS_LOCK) != 0,
+    })
+}
@@ -7,11 +7,33 @@ Current status:
 
 * A Rust `no_std` program runs on QEMU in X86 Long Mode.
 * BIOS functions can be called from a Rust `no_std` program.
+* The same runtime can also be booted from UEFI (feature `efi`).
 * Rust `alloc` library (`Vec`, `Box`, etc.) is working
   on a first-fit memory allocator.
 
 Everything is a work in progress, everything is subject to change.
 
+# Features
+
+Major subsystems can be left out to slim the runtime.  All of them
+are enabled by default.
+
+* `video` - VBE graphics modes (`man_video`).
+* `disk` - Disk I/O via INT 13h (`bios::int13h*`, `storage`, `fs`),
+  and the ELF loader (`loader`).
+* `acpi` - ACPI and SMBIOS tables (`acpi`, `smbios`), and the ACPI
+  power management.
+* `net` - virtio-net and the network stack (`drivers::virtio_net`,
+  `net`).
+* `tests` - Tests, demos and benchmarks run at boot (`demo`,
+  `test_alloc`, `test_bench`, `test_diskio`).
+
+For a minimal boot experiment, build it as follows.
+
+```sh
+% cargo build --no-default-features
+```
+
 # Components
 
 * `lmboot0` - is a small boot loader to run a Rust `no_std` program in
@@ -28,6 +50,7 @@ Everything is a work in progress, everything is subject to change.
   - MuAlloc - An implementation of alloc::GlobalAlloc and alloc::Allocator
   - MuHeap - A First-Fit Memory Allocator using Doubly Linked List
   - MuMutex - A Mutual Exclusion Primitive using Spin Lock
+  - MuRingBuf - A Fixed-Size Ring Buffer without Allocation
 
 # Documents
 
@@ -78,21 +101,63 @@ On Windows 11: At the top directory, enter the following commands.
 > .\run-qemu.ps1
 ```
 
+To boot from a CD image (El Torito no-emulation mode), enter the
+following commands instead.  `xorriso` is required to make an ISO
+image.
+
+```sh
+% cargo build
+% ./run-qemu-cd.sh
+```
+
+To run host-side unit tests (e.g., of `MuHeap`), specify the host
+target explicitly.
+
+```sh
+% cargo test --lib --target x86_64-unknown-linux-gnu
+```
+
 Then, make a branch and edit files as you like.
 
  */
 
-#![no_std]
-#![feature(alloc_error_handler)]
+// Host-side unit tests (`cargo test`) use std.
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), feature(alloc_error_handler))]
 #![feature(allocator_api)]
 
 extern crate alloc;
 
+#[cfg(feature = "acpi")] pub mod acpi;
 pub mod bios;
+pub mod cmdline;
+pub mod console;
+pub mod debug;
+#[cfg(feature = "tests")] pub mod demo;
+pub mod drivers;
+#[cfg(feature = "efi")] pub mod efi;
+#[cfg(feature = "disk")] pub mod fs;
+pub mod input;
+#[cfg(feature = "disk")] pub mod loader;
 pub mod man_heap;
-pub mod man_video;
+pub mod man_image;
+pub mod man_memory;
+pub mod man_region;
+#[cfg(feature = "video")] pub mod man_video;
+pub mod memtest;
 pub mod mu;
-pub mod test_alloc;
-pub mod test_diskio;
+#[cfg(feature = "net")] pub mod net;
+pub mod power;
+pub mod report;
+#[cfg(feature = "acpi")] pub mod smbios;
+pub mod stack;
+#[cfg(feature = "disk")] pub mod storage;
+pub mod task;
+#[cfg(feature = "tests")] pub mod test_alloc;
+#[cfg(feature = "tests")] pub mod test_bench;
+pub mod testing;
+#[cfg(all(feature = "tests", feature = "disk"))] pub mod test_diskio;
 pub mod text_writer;
+pub mod time;
+pub mod util;
 pub mod x86;
@@ -0,0 +1,170 @@
+/*!
+
+Runs a user-provided flat binary (a payload), for using nostd_env
+purely as a launcher.
+
+[`run_flat`] loads the payload at the given physical address (cf.
+[`raw::load_flat`](super::raw::load_flat)), registers it in the region
+manager, then jumps to its first byte with the following convention:
+
+* 64-bit long mode at CPL 0, with 0 - 4GiB identity-mapped by lmboot0
+  (the payload should set up its own page tables for more).
+* Interrupts are disabled (IF = 0).  The payload must load its own
+  IDT before enabling them.
+* It is called as `extern "sysv64" fn(info: *const FlatBootInfo)`,
+  i.e. RDI holds the address of [`FlatBootInfo`], and RSP is the stack
+  of the runtime (with a return address pushed).
+* The memory map (e.g., in the global heap), the command line (in
+  main1) and `FlatBootInfo` (on the stack) are valid as long as the
+  payload does not overwrite the regions owned by the runtime.
+
+If the payload returns, the runtime panics.
+
+```ignore
+let mut file = fs::DiskSlice::new(disk, lba, len)?;
+let err = unsafe { boot::run_flat(&mut file, 0x100000, memory_map) };
+println!("{}", err);
+
+// A payload already in memory (e.g., received over the network)
+let mut file = fs::MemSlice::new(&payload);
+let err = unsafe { boot::run_flat(&mut file, 0x100000, memory_map) };
+```
+
+ */
+
+use core::fmt;
+use core::mem::size_of;
+
+use super::raw::{self, RawError};
+use crate::assert_layout;
+use crate::bios::int15he820h::AddrRange;
+use crate::cmdline;
+use crate::fs::{Read, Seek};
+use crate::man_region::{self, RegionError};
+
+
+/// The magic number of [`FlatBootInfo`] ("LMBF").
+pub const FLAT_BOOT_MAGIC: u32 = 0x46424d4c;
+
+/// The version of [`FlatBootInfo`].
+pub const FLAT_BOOT_VERSION: u32 = 1;
+
+
+/// Errors returned by [`run_flat`].
+#[derive(Clone, Copy, Debug)]
+pub enum BootError {
+    /// The payload could not be loaded.
+    Load(RawError),
+    /// The payload could not be registered in the region manager.
+    Region(RegionError),
+}
+
+impl fmt::Display for BootError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Load(err) =>
+		write!(f, "Boot: {}", err),
+	    Self::Region(err) =>
+		write!(f, "Boot: Failed to register the payload ({:?})",
+		       err),
+	}
+    }
+}
+
+impl From<RawError> for BootError {
+    fn from(err: RawError) -> Self {
+	Self::Load(err)
+    }
+}
+
+impl From<RegionError> for BootError {
+    fn from(err: RegionError) -> Self {
+	Self::Region(err)
+    }
+}
+
+
+/// The information passed to a payload in RDI (cf. [`run_flat`]).
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct FlatBootInfo {
+    pub magic: u32,		// 00-03: FLAT_BOOT_MAGIC
+    pub version: u32,		// 04-07: FLAT_BOOT_VERSION
+    pub memory_map: u64,	// 08-0F: Address of AddrRange's (E820h)
+    pub memory_map_len: u64,	// 10-17: Number of AddrRange's
+    pub addr_range_size: u64,	// 18-1F: Size in bytes of an AddrRange
+    pub cmdline: u64,		// 20-27: Address of the command line (UTF-8)
+    pub cmdline_len: u64,	// 28-2F: Length in bytes of the command line
+    pub image_start: u64,	// 30-37: The Lowest Address of the payload
+    pub image_end: u64,		// 38-3F: The Highest Address (exclusive)
+}
+
+assert_layout!(FlatBootInfo, 0x40, {
+    magic: 0x00,
+    version: 0x04,
+    memory_map: 0x08,
+    memory_map_len: 0x10,
+    addr_range_size: 0x18,
+    cmdline: 0x20,
+    cmdline_len: 0x28,
+    image_start: 0x30,
+    image_end: 0x38,
+});
+
+
+///
+/// Loads the whole file as a flat binary at `load_addr`, then jumps
+/// to it (cf. the module documentation).  It returns only if the
+/// payload cannot be loaded.
+///
+/// # Safety
+///
+/// The range must not overwrite memory in use that is not registered
+/// in `man_region` (e.g., memory allocated by BIOS), and the payload
+/// takes over the machine.
+///
+pub unsafe fn run_flat<R>(file: &mut R, load_addr: u64,
+			  memory_map: &[AddrRange]) -> BootError
+where
+    R: Read + Seek,
+{
+    match load(file, load_addr, memory_map) {
+	Ok(info) => jump(load_addr, &info),
+	Err(err) => err,
+    }
+}
+
+// Loads the payload, then returns the information passed to it.
+unsafe fn load<R>(file: &mut R, load_addr: u64, memory_map: &[AddrRange])
+		  -> Result<FlatBootInfo, BootError>
+where
+    R: Read + Seek,
+{
+    let image = raw::load_flat(file, load_addr, 0, memory_map)?;
+    man_region::register("Payload", image.start as usize,
+			 (image.end - image.start) as usize)?;
+
+    let cmdline = cmdline::get();
+    Ok(FlatBootInfo {
+	magic: FLAT_BOOT_MAGIC,
+	version: FLAT_BOOT_VERSION,
+	memory_map: memory_map.as_ptr() as u64,
+	memory_map_len: memory_map.len() as u64,
+	addr_range_size: size_of::<AddrRange>() as u64,
+	cmdline: cmdline.as_ptr() as u64,
+	cmdline_len: cmdline.len() as u64,
+	image_start: image.start,
+	image_end: image.end,
+    })
+}
+
+// Jumps to the entry point with interrupts disabled.
+unsafe fn jump(entry: u64, info: &FlatBootInfo) -> ! {
+    let entry: extern "sysv64" fn(*const FlatBootInfo) =
+	core::mem::transmute(entry as usize);
+
+    core::arch::asm!("cli", options(nomem, nostack));
+    entry(info);
+
+    panic!("Payload at {:#x} returned", info.image_start);
+}
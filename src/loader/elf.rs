@@ -0,0 +1,190 @@
+//
+// ELF Loader - Loads an ELF64 executable into physical memory.
+//
+// The program headers of type PT_LOAD are loaded at their physical
+// addresses (`p_paddr`).  All segments are checked by
+// `placement::check` before any byte is written.  The bytes beyond
+// `p_filesz` (up to `p_memsz`, i.e. BSS) are zeroed.
+//
+// Supplementary Resources:
+//	https://refspecs.linuxfoundation.org/elf/gabi4+/ch4.eheader.html
+//	https://refspecs.linuxfoundation.org/elf/gabi4+/ch5.pheader.html
+//
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ptr::write_bytes;
+use core::slice;
+
+use super::placement::{self, LoadedImage, PlacementError};
+use crate::bios::int15he820h::AddrRange;
+use crate::fs::{IoError, Read, Seek, SeekFrom};
+use crate::util::bytes::{le16, le32, le64};
+
+
+// ELF Header
+const ELF_HEADER_SIZE: usize = 64;
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const ET_EXEC: u16 = 2;
+const EM_X86_64: u16 = 0x3e;
+
+// Program Header
+const PHDR_SIZE: usize = 56;
+const PT_LOAD: u32 = 1;
+
+// The maximum number of program headers.
+const MAX_PHDRS: usize = 64;
+
+
+/// Errors returned by [`load`].
+#[derive(Clone, Copy, Debug)]
+pub enum ElfError {
+    /// Reading the file failed.
+    Io(IoError),
+    /// The file is not an ELF file.
+    NotElf,
+    /// The file is not an ELF64 executable for x86_64 (little-endian).
+    Unsupported,
+    /// The program headers are invalid (or too many).
+    InvalidHeader,
+    /// A segment cannot be loaded there.
+    Placement(PlacementError),
+}
+
+impl fmt::Display for ElfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Io(err) =>
+		write!(f, "ELF: {}", err),
+	    Self::NotElf =>
+		write!(f, "ELF: Not an ELF file"),
+	    Self::Unsupported =>
+		write!(f, "ELF: Not an ELF64 executable for x86_64"),
+	    Self::InvalidHeader =>
+		write!(f, "ELF: Invalid program headers"),
+	    Self::Placement(err) =>
+		write!(f, "ELF: Segment: {}", err),
+	}
+    }
+}
+
+impl From<IoError> for ElfError {
+    fn from(err: IoError) -> Self {
+	Self::Io(err)
+    }
+}
+
+impl From<PlacementError> for ElfError {
+    fn from(err: PlacementError) -> Self {
+	Self::Placement(err)
+    }
+}
+
+
+/// A loadable segment (PT_LOAD).
+#[derive(Clone, Copy, Debug)]
+pub struct Segment {
+    pub offset: u64,		// Offset in the File
+    pub paddr: u64,		// Physical Address
+    pub filesz: u64,		// Size in the File
+    pub memsz: u64,		// Size in Memory (including BSS)
+}
+
+///
+/// Returns the entry point and the loadable segments of the ELF64
+/// executable without loading them.
+///
+pub fn segments<R>(file: &mut R) -> Result<(u64, Vec<Segment>), ElfError>
+where
+    R: Read + Seek,
+{
+    let mut header = [0; ELF_HEADER_SIZE];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut header)?;
+
+    if &header[0 .. 4] != ELF_MAGIC {
+	return Err(ElfError::NotElf);
+    }
+    if header[4] != ELFCLASS64 || header[5] != ELFDATA2LSB
+	|| le16(&header, 0x10) != ET_EXEC || le16(&header, 0x12) != EM_X86_64
+    {
+	return Err(ElfError::Unsupported);
+    }
+
+    let entry = le64(&header, 0x18);
+    let phoff = le64(&header, 0x20);
+    let phentsize = le16(&header, 0x36) as usize;
+    let phnum = le16(&header, 0x38) as usize;
+    if phentsize < PHDR_SIZE || phnum > MAX_PHDRS {
+	return Err(ElfError::InvalidHeader);
+    }
+
+    let mut segments = Vec::new();
+    let mut phdr = [0; PHDR_SIZE];
+    for i in 0 .. phnum {
+	let offset = phoff.checked_add((i * phentsize) as u64)
+	    .ok_or(ElfError::InvalidHeader)?;
+	file.seek(SeekFrom::Start(offset))?;
+	file.read_exact(&mut phdr)?;
+	if le32(&phdr, 0x00) != PT_LOAD {
+	    continue;
+	}
+
+	let segment = Segment {
+	    offset: le64(&phdr, 0x08),
+	    paddr: le64(&phdr, 0x18),
+	    filesz: le64(&phdr, 0x20),
+	    memsz: le64(&phdr, 0x28),
+	};
+	if segment.filesz > segment.memsz {
+	    return Err(ElfError::InvalidHeader);
+	}
+	segments.push(segment);
+    }
+
+    Ok((entry, segments))
+}
+
+///
+/// Loads the ELF64 executable at the physical addresses of its
+/// segments, then returns the loaded image.  The segments are
+/// checked against the memory map and the regions owned by the
+/// runtime (cf. [`placement::check`]) before loading.
+///
+/// # Safety
+///
+/// The segments must not overwrite memory in use that is not
+/// registered in `man_region` (e.g., memory allocated by BIOS).
+///
+pub unsafe fn load<R>(file: &mut R, memory_map: &[AddrRange])
+		      -> Result<LoadedImage, ElfError>
+where
+    R: Read + Seek,
+{
+    let (entry, segments) = segments(file)?;
+
+    let mut image = LoadedImage { entry, start: u64::MAX, end: 0 };
+    for segment in segments.iter().filter(| s | s.memsz != 0) {
+	placement::check(segment.paddr, segment.memsz, memory_map)?;
+	image.start = image.start.min(segment.paddr);
+	image.end = image.end.max(segment.paddr + segment.memsz);
+    }
+    if image.start > image.end {
+	return Err(ElfError::InvalidHeader);
+    }
+
+    for segment in segments.iter().filter(| s | s.memsz != 0) {
+	let dest = slice::from_raw_parts_mut(segment.paddr as *mut u8,
+					     segment.filesz as usize);
+	file.seek(SeekFrom::Start(segment.offset))?;
+	file.read_exact(dest)?;
+
+	// Zero BSS.
+	write_bytes((segment.paddr + segment.filesz) as *mut u8, 0,
+		    (segment.memsz - segment.filesz) as usize);
+    }
+
+    Ok(image)
+}
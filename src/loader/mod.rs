@@ -0,0 +1,44 @@
+/*!
+
+Loads programs into memory.
+
+* `elf` - loads an ELF64 executable (e.g., a second-stage kernel read
+  from a disk by [`DiskSlice`](crate::fs::DiskSlice)) at the physical
+  addresses of its segments.  The segments are validated against the
+  memory map (E820h) and the regions owned by the runtime before any
+  byte is written, and BSS is zeroed.  The entry point is returned to
+  the caller, which jumps to it.
+
+* `raw` - loads a flat binary or a PE/COFF image (PE32+) at the
+  address given by the caller, for kernels built by non-GNU
+  toolchains.  The entry point of a flat binary is given as an offset.
+  A PE/COFF image is laid out by its section table, and relocated if
+  it is loaded at another address than its `ImageBase`.
+
+* `boot` - runs a flat binary payload loaded by `raw`: it jumps to
+  the payload with the address of `FlatBootInfo` (the memory map and
+  the command line) in RDI, for using nostd_env purely as a launcher.
+
+```ignore
+let disk = storage::BiosDisk::open(drive_id, &ALLOC_UNDER20)?;
+let mut file = fs::DiskSlice::new(disk, lba, len)?;
+let image = unsafe { loader::elf::load(&mut file, memory_map)? };
+println!("{}", image);
+
+let image = unsafe {
+    loader::raw::load_flat(&mut file, 0x100000, 0, memory_map)?
+};
+```
+
+ */
+
+
+pub mod boot;
+pub mod elf;
+#[doc(hidden)] pub mod placement;
+pub mod raw;
+
+#[doc(inline)] pub use self::boot::{BootError, FlatBootInfo, run_flat};
+#[doc(inline)] pub use self::elf::ElfError;
+#[doc(inline)] pub use self::placement::{LoadedImage, PlacementError};
+#[doc(inline)] pub use self::raw::RawError;
@@ -0,0 +1,80 @@
+//
+// Placement - Checks where a program is loaded.
+//
+// A program must be loaded in usable RAM of the memory map (E820h)
+// below 4GiB (the identity-mapped address space), and must not
+// overlap with regions owned by the runtime (cf. `man_region`).
+//
+
+use core::fmt;
+
+use crate::bios::int15he820h::AddrRange;
+use crate::man_region::{self, Region};
+
+
+// The limit of the identity-mapped address space (4GiB)
+const ADDR_LIMIT: u64 = 1 << 32;
+
+
+/// Errors returned by [`check`].
+#[derive(Clone, Copy, Debug)]
+pub enum PlacementError {
+    /// The range is not in usable RAM below 4GiB.
+    NotUsable { addr: u64, len: u64 },
+    /// The range overlaps with a region owned by the runtime.
+    Owned(Region),
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::NotUsable { addr, len } =>
+		write!(f, "{:#x} ({:#x} bytes) is not in usable memory",
+		       addr, len),
+	    Self::Owned(region) =>
+		write!(f, "Overlaps with {}", region),
+	}
+    }
+}
+
+
+/// A program loaded into memory.
+#[derive(Clone, Copy, Debug)]
+pub struct LoadedImage {
+    pub entry: u64,		// Entry Point
+    pub start: u64,		// The Lowest Address
+    pub end: u64,		// The Highest Address (exclusive)
+}
+
+impl fmt::Display for LoadedImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "Loaded: {:#x} - {:#x} (entry {:#x})",
+	       self.start, self.end, self.entry)
+    }
+}
+
+
+///
+/// Checks that `len` bytes from `addr` are in usable RAM below 4GiB,
+/// and that they do not overlap with regions owned by the runtime.
+///
+pub fn check(addr: u64, len: u64, memory_map: &[AddrRange])
+	     -> Result<(), PlacementError> {
+    let end = match addr.checked_add(len) {
+	Some(end) if end <= ADDR_LIMIT => end,
+	_ => return Err(PlacementError::NotUsable { addr, len }),
+    };
+
+    let usable = memory_map.iter().any(| e | {
+	e.atype == AddrRange::TYPE_USABLE
+	    && e.addr <= addr && end <= e.addr.saturating_add(e.length)
+    });
+    if !usable {
+	return Err(PlacementError::NotUsable { addr, len });
+    }
+
+    match man_region::find_overlap(addr as usize, len as usize) {
+	Some(region) => Err(PlacementError::Owned(region)),
+	None => Ok(()),
+    }
+}
@@ -0,0 +1,364 @@
+//
+// Raw Loader - Loads a flat binary or a PE/COFF image at a given address.
+//
+// A flat binary is copied as is to the load address, and its entry
+// point is given as an offset from the load address.
+//
+// A PE/COFF image (PE32+ for x86_64, e.g. built by MSVC or lld-link)
+// is laid out by its section table: the headers and the sections are
+// copied to their relative virtual addresses (RVA) from the load
+// address, and the rest of `SizeOfImage` (e.g., BSS) is zeroed.  If
+// the load address differs from `ImageBase`, the base relocations of
+// type IMAGE_REL_BASED_DIR64 are applied.
+//
+// Either way, the range is checked by `placement::check` before any
+// byte is written.
+//
+// Supplementary Resource:
+//	https://learn.microsoft.com/en-us/windows/win32/debug/pe-format
+//
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ptr::{read_unaligned, write_bytes, write_unaligned};
+use core::slice;
+
+use super::placement::{self, LoadedImage, PlacementError};
+use crate::bios::int15he820h::AddrRange;
+use crate::fs::{IoError, Read, Seek, SeekFrom};
+use crate::util::bytes::{le16, le32, le64};
+
+
+// MS-DOS Header
+const MZ_MAGIC: &[u8; 2] = b"MZ";
+const MZ_HEADER_SIZE: usize = 64;
+const MZ_LFANEW: usize = 0x3c;		// Offset of the PE signature
+
+// PE Signature and COFF File Header
+const PE_MAGIC: &[u8; 4] = b"PE\0\0";
+const COFF_HEADER_SIZE: usize = 24;	// (including the signature)
+const IMAGE_FILE_MACHINE_AMD64: u16 = 0x8664;
+const IMAGE_FILE_RELOCS_STRIPPED: u16 = 0x0001;
+
+// Optional Header (PE32+)
+const OPTIONAL_HEADER_SIZE: usize = 112;	// (w/o data directories)
+const PE32PLUS_MAGIC: u16 = 0x20b;
+const IMAGE_DIRECTORY_ENTRY_BASERELOC: usize = 5;
+
+// Section Header
+const SECTION_HEADER_SIZE: usize = 40;
+const MAX_SECTIONS: usize = 96;
+
+// Base Relocation Types
+const IMAGE_REL_BASED_ABSOLUTE: u16 = 0;
+const IMAGE_REL_BASED_DIR64: u16 = 10;
+
+
+/// Errors returned by [`load_flat`] and [`load_pe`].
+#[derive(Clone, Copy, Debug)]
+pub enum RawError {
+    /// Reading the file failed.
+    Io(IoError),
+    /// The file is not a PE/COFF image.
+    NotPe,
+    /// The image is not PE32+ for x86_64.
+    Unsupported,
+    /// A header of the image is invalid.
+    InvalidHeader,
+    /// The entry point is out of the image.
+    EntryOutOfImage { entry: u64 },
+    /// The image cannot be loaded there.
+    Placement(PlacementError),
+    /// The image cannot be relocated from `ImageBase`.
+    NotRelocatable { image_base: u64 },
+}
+
+impl fmt::Display for RawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Io(err) =>
+		write!(f, "Loader: {}", err),
+	    Self::NotPe =>
+		write!(f, "Loader: Not a PE/COFF image"),
+	    Self::Unsupported =>
+		write!(f, "Loader: Not a PE32+ image for x86_64"),
+	    Self::InvalidHeader =>
+		write!(f, "Loader: Invalid PE/COFF header"),
+	    Self::EntryOutOfImage { entry } =>
+		write!(f, "Loader: Entry point {:#x} is out of the image",
+		       entry),
+	    Self::Placement(err) =>
+		write!(f, "Loader: {}", err),
+	    Self::NotRelocatable { image_base } =>
+		write!(f, "Loader: Image cannot be relocated from {:#x}",
+		       image_base),
+	}
+    }
+}
+
+impl From<IoError> for RawError {
+    fn from(err: IoError) -> Self {
+	Self::Io(err)
+    }
+}
+
+impl From<PlacementError> for RawError {
+    fn from(err: PlacementError) -> Self {
+	Self::Placement(err)
+    }
+}
+
+
+///
+/// Loads the whole file as a flat binary at `load_addr`.  The entry
+/// point is `load_addr + entry_offset`.
+///
+/// # Safety
+///
+/// The range must not overwrite memory in use that is not registered
+/// in `man_region` (e.g., memory allocated by BIOS).
+///
+pub unsafe fn load_flat<R>(file: &mut R, load_addr: u64, entry_offset: u64,
+			   memory_map: &[AddrRange])
+			   -> Result<LoadedImage, RawError>
+where
+    R: Read + Seek,
+{
+    let len = file.seek(SeekFrom::End(0))?;
+    if entry_offset >= len {
+	return Err(RawError::EntryOutOfImage { entry: entry_offset });
+    }
+    placement::check(load_addr, len, memory_map)?;
+
+    let dest = slice::from_raw_parts_mut(load_addr as *mut u8, len as usize);
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(dest)?;
+
+    Ok(LoadedImage {
+	entry: load_addr + entry_offset,
+	start: load_addr,
+	end: load_addr + len,
+    })
+}
+
+///
+/// Loads the PE32+ image at `load_addr` (or at its `ImageBase` if
+/// `None`).  The entry point is `AddressOfEntryPoint` from there.
+///
+/// # Safety
+///
+/// The range must not overwrite memory in use that is not registered
+/// in `man_region` (e.g., memory allocated by BIOS).
+///
+pub unsafe fn load_pe<R>(file: &mut R, load_addr: Option<u64>,
+			 memory_map: &[AddrRange])
+			 -> Result<LoadedImage, RawError>
+where
+    R: Read + Seek,
+{
+    let pe = PeHeaders::read(file)?;
+    let load_addr = load_addr.unwrap_or(pe.image_base);
+    let size = pe.size_of_image as u64;
+    if pe.entry_rva as u64 >= size {
+	return Err(RawError::EntryOutOfImage { entry: pe.entry_rva as u64 });
+    }
+    if load_addr != pe.image_base && !pe.relocatable() {
+	return Err(RawError::NotRelocatable { image_base: pe.image_base });
+    }
+    placement::check(load_addr, size, memory_map)?;
+
+    // Check the sections before writing anything.
+    let mut sections = Vec::with_capacity(pe.num_sections);
+    let mut buf = [0; SECTION_HEADER_SIZE];
+    file.seek(SeekFrom::Start(pe.sections_offset))?;
+    for _ in 0 .. pe.num_sections {
+	file.read_exact(&mut buf)?;
+	let section = Section::parse(&buf);
+	if section.rva as u64 + section.size() > size {
+	    return Err(RawError::InvalidHeader);
+	}
+	sections.push(section);
+    }
+
+    // Zero the image, then copy the headers and the sections.
+    write_bytes(load_addr as *mut u8, 0, size as usize);
+    let mut read_at = | offset: u64, rva: u32, len: u64 | {
+	let dest = slice::from_raw_parts_mut((load_addr + rva as u64)
+					     as *mut u8, len as usize);
+	file.seek(SeekFrom::Start(offset))?;
+	file.read_exact(dest)
+    };
+    read_at(0, 0, (pe.size_of_headers as u64).min(size))?;
+    for section in &sections {
+	read_at(section.raw_offset as u64, section.rva, section.raw_size())?;
+    }
+
+    if load_addr != pe.image_base {
+	relocate(&pe, load_addr)?;
+    }
+
+    Ok(LoadedImage {
+	entry: load_addr + pe.entry_rva as u64,
+	start: load_addr,
+	end: load_addr + size,
+    })
+}
+
+// Applies the base relocations to the image loaded at `load_addr`.
+unsafe fn relocate(pe: &PeHeaders, load_addr: u64) -> Result<(), RawError> {
+    let delta = load_addr.wrapping_sub(pe.image_base);
+    let (rva, size) = pe.reloc_dir;
+    if rva as u64 + size as u64 > pe.size_of_image as u64 {
+	return Err(RawError::InvalidHeader);
+    }
+    let relocs = slice::from_raw_parts((load_addr + rva as u64) as *const u8,
+				       size as usize);
+
+    // A block: Page RVA (u32), Block Size (u32), then entries (u16)
+    let mut rest = relocs;
+    while rest.len() >= 8 {
+	let page_rva = le32(rest, 0);
+	let block_size = le32(rest, 4) as usize;
+	if block_size < 8 || block_size > rest.len() {
+	    return Err(RawError::InvalidHeader);
+	}
+	for entry in rest[8 .. block_size].chunks_exact(2) {
+	    let entry = le16(entry, 0);
+	    let offset = page_rva as u64 + (entry & 0x0fff) as u64;
+	    match entry >> 12 {
+		IMAGE_REL_BASED_ABSOLUTE => (),
+		IMAGE_REL_BASED_DIR64
+		    if offset + 8 <= pe.size_of_image as u64 => {
+		    let ptr = (load_addr + offset) as *mut u64;
+		    let value = read_unaligned(ptr).wrapping_add(delta);
+		    write_unaligned(ptr, value);
+		},
+		_ => return Err(RawError::NotRelocatable {
+		    image_base: pe.image_base,
+		}),
+	    }
+	}
+	rest = &rest[block_size ..];
+    }
+    Ok(())
+}
+
+
+// The fields of the PE/COFF headers used by the loader
+struct PeHeaders {
+    characteristics: u16,
+    entry_rva: u32,		// AddressOfEntryPoint
+    image_base: u64,
+    size_of_image: u32,
+    size_of_headers: u32,
+    reloc_dir: (u32, u32),	// Base Relocation Table (RVA, Size)
+    num_sections: usize,
+    sections_offset: u64,	// Offset of the Section Table
+}
+
+impl PeHeaders {
+    // Reads the MS-DOS header, the COFF file header and the optional
+    // header (PE32+).
+    fn read<R>(file: &mut R) -> Result<Self, RawError>
+    where
+	R: Read + Seek,
+    {
+	let mut mz = [0; MZ_HEADER_SIZE];
+	file.seek(SeekFrom::Start(0))?;
+	file.read_exact(&mut mz)?;
+	if &mz[0 .. 2] != MZ_MAGIC {
+	    return Err(RawError::NotPe);
+	}
+
+	let pe_offset = le32(&mz, MZ_LFANEW) as u64;
+	let mut buf = [0; COFF_HEADER_SIZE + OPTIONAL_HEADER_SIZE + 8 * 16];
+	file.seek(SeekFrom::Start(pe_offset))?;
+	file.read_exact(&mut buf[.. COFF_HEADER_SIZE])?;
+	if &buf[0 .. 4] != PE_MAGIC {
+	    return Err(RawError::NotPe);
+	}
+
+	let coff = &buf[4 ..];
+	let machine = le16(coff, 0);
+	let num_sections = le16(coff, 2) as usize;
+	let optional_size = le16(coff, 16) as usize;
+	let characteristics = le16(coff, 18);
+	if machine != IMAGE_FILE_MACHINE_AMD64 {
+	    return Err(RawError::Unsupported);
+	}
+	if optional_size < OPTIONAL_HEADER_SIZE
+	    || num_sections > MAX_SECTIONS
+	{
+	    return Err(RawError::InvalidHeader);
+	}
+
+	let opt_len = optional_size.min(buf.len() - COFF_HEADER_SIZE);
+	file.read_exact(&mut buf[COFF_HEADER_SIZE ..][.. opt_len])?;
+	let opt = &buf[COFF_HEADER_SIZE ..];
+	if le16(opt, 0) != PE32PLUS_MAGIC {
+	    return Err(RawError::Unsupported);
+	}
+
+	// The data directories follow (8 bytes each).
+	let num_dirs = le32(opt, 108) as usize;
+	let dir = OPTIONAL_HEADER_SIZE + IMAGE_DIRECTORY_ENTRY_BASERELOC * 8;
+	let has_dir = num_dirs > IMAGE_DIRECTORY_ENTRY_BASERELOC;
+	let reloc_dir = if has_dir && dir + 8 <= opt_len {
+	    (le32(opt, dir), le32(opt, dir + 4))
+	} else {
+	    (0, 0)
+	};
+
+	Ok(Self {
+	    characteristics,
+	    entry_rva: le32(opt, 16),
+	    image_base: le64(opt, 24),
+	    size_of_image: le32(opt, 56),
+	    size_of_headers: le32(opt, 60),
+	    reloc_dir,
+	    num_sections,
+	    sections_offset: pe_offset + (COFF_HEADER_SIZE + optional_size)
+		as u64,
+	})
+    }
+
+    // Returns true if the image can be loaded at another address.
+    fn relocatable(&self) -> bool {
+	(self.characteristics & IMAGE_FILE_RELOCS_STRIPPED) == 0
+	    && self.reloc_dir.1 != 0
+    }
+}
+
+
+// The fields of a section header used by the loader
+struct Section {
+    virtual_size: u32,
+    rva: u32,			// VirtualAddress
+    raw_data_size: u32,		// SizeOfRawData
+    raw_offset: u32,		// PointerToRawData
+}
+
+impl Section {
+    fn parse(header: &[u8]) -> Self {
+	Self {
+	    virtual_size: le32(header, 8),
+	    rva: le32(header, 12),
+	    raw_data_size: le32(header, 16),
+	    raw_offset: le32(header, 20),
+	}
+    }
+
+    // Returns the size in memory.
+    fn size(&self) -> u64 {
+	self.virtual_size.max(self.raw_data_size) as u64
+    }
+
+    // Returns the size read from the file (excluding the file alignment
+    // padding beyond the virtual size).
+    fn raw_size(&self) -> u64 {
+	match self.virtual_size {
+	    0 => self.raw_data_size as u64,
+	    size => size.min(self.raw_data_size) as u64,
+	}
+    }
+}
@@ -6,45 +6,336 @@ use core::panic::PanicInfo;
 // See src/lib.rs
 use nostd_env::{
     bios,
-    man_heap::{self, ALLOC_UNDER16, ALLOC_UNDER20, GLOBAL_ALLOC},
-    man_video,
+    cmdline,
+    console,
+    debug::{self, post_code::*},
+    debug_print,
+    debug_println,
+    drivers::{self, usb_uhci::UsbKeyboard},
+    man_heap::{self, ALLOC_UNDER20},
+    man_image,
+    power,
     println,
-    test_alloc,
-    test_diskio,
-    x86::halt_forever,
+    report,
+    testing,
+    text_writer::TextWriter,
+    time,
+    x86,
 };
+#[cfg(feature = "acpi")]
+use nostd_env::{acpi, smbios};
+#[cfg(feature = "net")]
+use nostd_env::{
+    drivers::{MacAddress, NetDevice, virtio_net::VirtioNet},
+    net,
+};
+#[cfg(feature = "video")]
+use nostd_env::man_video;
+#[cfg(feature = "tests")]
+use nostd_env::{demo, man_heap::GLOBAL_ALLOC, test_bench, util::Config};
 
 
 // Panic handler (cf. https://doc.rust-lang.org/nomicon/panic-handler.html )
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
+    debug::post_code(POST_PANIC);
+    let regs = x86::Registers::capture();
+    let backtrace = debug::Backtrace::capture();
+
+    // Preserve the crash log across warm reboot (before printing).
+    debug::crash_log::save(format_args!("{}\r\n{}\r\n{}",
+					info, regs, backtrace));
+
+    // Write the output batched by `TextWriter::batch` (if any).
+    TextWriter::end_batch();
+
+    // Return the display to the text mode before anything is printed.
+    debug::restore_text_mode();
+
+    // Print to the serial port and debugcon only, and show the panic
+    // on the screen (Teletype output would be invisible in graphics mode).
+    console::set_sink_level(console::Sink::Screen, None);
     println!("{}", info);
-    halt_forever();
+    println!("{}", regs);
+
+    // Summarize the heaps and the stack, which are likely exhausted.
+    man_heap::print_summary();
+    let stack = bios::StackUsage::watermark();
+    println!("Stack: {}{}", stack,
+	     if stack.is_exhausted() { " (EXHAUSTED)" } else { "" });
+
+    println!("{}", backtrace);
+
+    debug::panic_screen::show(format_args!("{}\r\n\r\n{}\r\n{}",
+					   info, regs, backtrace));
+
+    // Take the action given by `panic_policy=`.  By default, exit QEMU
+    // with a failure status (if isa-debug-exit is available), or take
+    // the action given by `on_exit=`.
+    power::PanicPolicy::from_cmdline().apply();
 }
 
 
 // Entry point of the Rust world.
 #[no_mangle]
 pub extern "C" fn __bare_start() -> ! {
+    // Verify the integrity of the loaded image before anything else.
+    // Note: Because the checksum covers .data and .bss, it must be
+    //       verified before any static variable is modified.
+    let image_status = man_image::verify();
+
+    // Report the progress to I/O port 0x80 at each milestone (for a
+    // POST code card or QEMU tracing).
+    debug::post_code(POST_ENTRY);
+
+    // Write the stack canary to detect stack overflow.
+    bios::init_stack_canary();
+
+    // Bring up the early console (no allocation is required), then
+    // dump the text printed so far (if any) to it.
+    if console::early_init() == Some(console::EarlyPort::Com1) {
+	console::replay(console::Sink::Serial);
+    }
+    debug::post_code(POST_CONSOLE);
+
+    // Install the logger for the `log` crate facade.
+    #[cfg(feature = "log")]
+    console::logger::init(console::logger::LevelFilter::Info).unwrap();
+
+    match image_status {
+	Ok(status) => println!("Boot image: {}", status),
+	Err(err) => {
+	    println!("{}", err);
+	    power::halt();
+	},
+    }
+
+    // Anchor the wall clock (RTC) and calibrate the TSC (by the PIT).
+    time::init();
+    debug::post_code(POST_TIME);
+    println!("Boot time: {}", time::now());
+
+    // Print the crash log of the previous boot (if any).
+    if let Some(crash_log) = debug::crash_log::init() {
+	println!("Crash log of the previous boot:");
+	println!("{}", crash_log);
+    }
+
     // Print the current stack usage.
-    println!("Stack max = {}", bios::StackUsage::new());
+    debug_println!("Stack max = {}", bios::StackUsage::new());
 
     // Initialize the global allocator (size = 1MB)
-    man_heap::init_global_alloc(1024 * 1024, &ALLOC_UNDER20);
+    // The memory map is sanitized with the regions owned by the runtime.
+    let boot_info = man_heap::init_global_alloc(1024 * 1024, &ALLOC_UNDER20);
+    debug::post_code(POST_HEAP);
+
+    // Print the memory map (By default, not to the screen).
+    TextWriter::batch(| _ | {
+	debug_print!("Memory map:\r\n{}",
+		     bios::int15he820h::MemoryMap(boot_info.memory_map()));
+    });
+
+    // Print the disk and time services hooked by option ROMs or PXE
+    // stacks (By default, not to the screen).
+    for entry in bios::ivt::hooks() {
+	debug_println!("Hooked: {}", entry);
+    }
+
+    // Hook the system timer tick to count the ticks spent in BIOS.
+    if let Err(err) = bios::int1ch::install(&man_heap::ALLOC_UNDER16) {
+	debug_println!("{}", err);
+    }
+
+    // Register the hook to return to the text mode on panic and test
+    // failure, then select the screen: a VBE frame buffer console, the
+    // VGA text buffer, or BIOS teletype output (in order of preference).
+    #[cfg(feature = "video")]
+    debug::set_text_mode_hook(man_video::ensure_text_mode);
+    let screen = console::init_screen(&ALLOC_UNDER20);
+    debug::post_code(POST_SCREEN);
+
+    // Print the ACPI tables and the SMBIOS information.
+    #[cfg(feature = "acpi")]
+    print_firmware_tables();
 
-    // Find the best mode using VESA BIOS Extentions.
-    man_video::find_graphics_mode(1280, 1024, 24, &ALLOC_UNDER20);
+    // Print the PCI devices (By default, not to the screen).
+    debug_println!("PCI devices:");
+    for device in drivers::pci::devices() {
+	debug_println!("  {}", device);
+    }
 
-    // Try Checking Stack Usages of BIOS Text Output and Disk I/O.
-    test_diskio::try_read_sectors1(&ALLOC_UNDER16);
-    test_diskio::try_read_sectors2(&ALLOC_UNDER16);
+    // Print the hypervisor (if any) and the TSC frequency.
+    if let Some(hypervisor) = x86::hypervisor::detect() {
+	debug_println!("Hypervisor: {}", hypervisor);
+    }
+    debug_println!("TSC: {} kHz", time::tsc_frequency() / 1000);
 
-    // Test: allocator and heap manager
-    test_alloc::try_sieve(30, 100, 10000, &GLOBAL_ALLOC);
+    // Print the variable-range MTRRs in use (By default, not to the
+    // screen).
+    if let Some(cap) = x86::mtrr::capabilities() {
+	debug_println!("MTRRs: {} variable ranges", cap.variable_count);
+	x86::mtrr::for_each_variable_range(| range | {
+	    debug_println!("  {}", range);
+	});
+    }
+
+    debug::post_code(POST_PLATFORM);
+
+    // Gather what was detected (the CPU, the memory, the drives, VBE,
+    // the screen and ACPI) into the boot report, then print it.
+    let report = report::init(boot_info.memory_map(), screen, &ALLOC_UNDER20);
+    println!("{}", report);
+    debug::post_code(POST_DISK);
+
+    // Print the PCI function owning the boot drive.
+    #[cfg(feature = "disk")]
+    print_boot_device();
+
+    // Initialize the virtio-net device (if any).
+    #[cfg(feature = "net")]
+    try_virtio_net();
+
+    // Initialize the USB keyboard if `usb` is given (This disables the
+    // BIOS keyboard emulation of USB keyboards).
+    if cmdline::flag("usb") {
+	match UsbKeyboard::find() {
+	    Ok(keyboard) => println!("usb: keyboard on port {}",
+				     keyboard.device().port),
+	    Err(err) => println!("usb: {}", err),
+	}
+    }
+
+    // Run the demos (selected by `demo=`) and the benchmarks.
+    debug::post_code(POST_MAIN);
+    #[cfg(feature = "tests")]
+    run_tests(boot_info.memory_map());
+
+    // Print the I/O statistics of the drives (By default, not to the
+    // screen).
+    #[cfg(feature = "disk")]
+    for stats in bios::disk::stats() {
+	debug_println!("Disk I/O: {}", stats);
+    }
+
+    // Print the time spent in BIOS (counted by the INT 1Ch hook).
+    if let Some(ticks) = bios::int1ch::ticks() {
+	debug_println!("Ticks in BIOS = {} ({:?})",
+		       ticks, bios::int1ch::ticks_to_duration(ticks));
+    }
+
+    // Print the usage and the fragmentation of the heaps if `heap` is
+    // given.
+    if cmdline::flag("heap") {
+	man_heap::print_report();
+    }
 
     // Print the current stack usage.
-    println!("Stack max = {}", bios::StackUsage::new());
+    debug_println!("Stack max = {}", bios::StackUsage::new());
+
+    // Exit QEMU with the test results (if isa-debug-exit is available),
+    // or take the action given by `on_exit=` (halt by default).
+    let summary = testing::summary();
+    debug::post_code(POST_FINISH);
+    debug::restore_text_mode();
+    println!("{}", summary);
+    power::finish(summary.exit_code());
+}
+
+// Prints the ACPI tables and the SMBIOS information
+// (By default, not to the screen).
+#[cfg(feature = "acpi")]
+fn print_firmware_tables() {
+    match acpi::Acpi::new() {
+	Ok(acpi) => {
+	    debug_println!("ACPI: {}", acpi.root);
+	    for table in acpi.tables() {
+		debug_println!("  {}", table);
+	    }
+	},
+	Err(err) => println!("{}", err),
+    }
+
+    if let Ok(smbios) = smbios::Smbios::new() {
+	let entry = smbios.entry;
+	debug_println!("SMBIOS {}.{}", entry.major, entry.minor);
+	if let Some(bios) = smbios.bios_info() {
+	    debug_println!("  BIOS: {}", bios);
+	}
+	if let Some(system) = smbios.system_info() {
+	    debug_println!("  System: {}", system);
+	}
+	for device in smbios.memory_devices() {
+	    debug_println!("  Memory: {}", device);
+	}
+    }
+}
+
+// Prints the PCI function owning the boot drive (EDD 3.0)
+// (By default, not to the screen).
+#[cfg(feature = "disk")]
+fn print_boot_device() {
+    if let Some(path) = bios::boot_device_path(&ALLOC_UNDER20) {
+	debug_println!("Boot device: {}", path);
+	if let Some(device) = path.host_bus.pci_address()
+	    .and_then(drivers::pci::PciDevice::probe) {
+	    debug_println!("  {}", device);
+	}
+    }
+}
+
+// Runs the demos and benchmarks (The results are summarized by
+// `testing::summary`).
+#[cfg(feature = "tests")]
+fn run_tests(memory_map: &[bios::int15he820h::AddrRange]) {
+    // Demos: disk I/O and the heap stress scenario by default (a
+    // failure can be reproduced by passing the printed seed as
+    // `seed=<number>`).
+    let seed = Config::cmdline().value_or("seed", 1);
+    demo::run_selected(&demo::DemoContext { memory_map, seed });
+
+    // Benchmark: heap managers and disk I/O (if `bench` is given)
+    if cmdline::flag("bench") {
+	test_bench::try_bench_heap("GLOBAL_ALLOC", &GLOBAL_ALLOC);
+	test_bench::try_bench_heap("ALLOC_UNDER20", &ALLOC_UNDER20);
+	#[cfg(feature = "disk")]
+	test_bench::try_bench_diskio(&ALLOC_UNDER20);
+    }
+}
+
+// Initializes the virtio-net device (if any).
+#[cfg(feature = "net")]
+fn try_virtio_net() {
+    if let Some(dev) = VirtioNet::find() {
+	match VirtioNet::new(&dev) {
+	    Ok(net) => {
+		println!("virtio-net: {} MAC={}",
+			 dev.addr, MacAddress(net.mac_address()));
+		try_network(net);
+	    },
+	    Err(err) => println!("virtio-net: {}", err),
+	}
+    }
+}
+
+// Resolves the MAC address of the gateway as a smoke test.
+#[cfg(feature = "net")]
+fn try_network<D>(dev: D)
+where
+    D: NetDevice
+{
+    let mut iface = net::Interface::new(dev, net::NetConfig::QEMU_USER);
+    let gateway = iface.config().gateway;
+    match iface.resolve(gateway) {
+	Ok(mac) => println!("net: {} is at {}", gateway, MacAddress(mac)),
+	Err(err) => println!("net: {}", err),
+    }
 
-    // Halt
-    halt_forever();
+    // Fetch a file from the TFTP server if `tftp=<filename>` is given.
+    if let Some(filename) = cmdline::value("tftp") {
+	match net::tftp::get(&mut iface, gateway, filename,
+			     &man_heap::GLOBAL_ALLOC) {
+	    Ok(data) => println!("tftp: {} ({} bytes)", filename, data.len()),
+	    Err(err) => println!("tftp: {}: {}", filename, err),
+	}
+    }
 }
@@ -5,56 +5,154 @@ Manages heap areas.
  */
 
 
-use alloc::alloc::Layout;
-use alloc::vec::Vec;
 use core::alloc::Allocator;
 
 use crate::bios::{self, int15he820h::AddrRange};
-use crate::mu::{MuAlloc16, MuAlloc32};
+use crate::man_memory::{self, BootInfo};
+use crate::man_region;
+use crate::mu::{
+    MuAlloc, MuAlloc16, MuAlloc32, MuFallbackAlloc, MuHeapIndex,
+};
+use crate::println;
+use crate::x86::PhysMem;
 
 
 // Heap area in 16-bit address space: 0x0500 - 0x2FFF (10KB+)
-// Mainly for buffers to be exchanged with BIOS.
+// Mainly for buffers to be exchanged with BIOS (zeroed on free).
 pub static ALLOC_UNDER16: MuAlloc16 =
-    unsafe { MuAlloc16::heap(0x0500, 0x2b00) };
+    unsafe { MuAlloc16::heap(0x0500, 0x2b00).with_zero_on_free() };
 
 // Heap area in 20-bit address space: 0x60000 - 0x7FFFF (128KB)
-// Mainly for buffers to be exchanged with BIOS.
+// Mainly for buffers to be exchanged with BIOS (zeroed on free).
 pub static ALLOC_UNDER20: MuAlloc32 =
-    unsafe { MuAlloc32::heap(0x60000, 0x20000) };
+    unsafe { MuAlloc32::heap(0x60000, 0x20000).with_zero_on_free() };
+
+// Buffers exchanged with BIOS by far pointers (in 20-bit address space):
+// Allocated in the 16-bit heap first, then in the 20-bit heap when the
+// 16-bit heap fills up.
+pub static ALLOC_UNDER16_OR_20: MuFallbackAlloc<&MuAlloc16, &MuAlloc32> =
+    MuFallbackAlloc::new(&ALLOC_UNDER16, &ALLOC_UNDER20);
 
 // Heap area in 64-bit address space: (Initialized in the function above)
 // For the global allocator.
-#[global_allocator]
+#[cfg_attr(not(test), global_allocator)]
 pub static GLOBAL_ALLOC: MuAlloc32 = MuAlloc32::noheap();
 
 
+#[cfg(not(test))]
 #[alloc_error_handler]
-fn alloc_error_handler(layout: Layout) -> ! {
+fn alloc_error_handler(layout: alloc::alloc::Layout) -> ! {
+    // Tell whether the heap is full or merely fragmented.
+    if let Some(heap) = GLOBAL_ALLOC.try_lock() {
+	println!("Global heap: largest allocatable = {}, {}",
+		 heap.largest_allocatable(layout.align()),
+		 heap.fragmentation());
+    }
     panic!("Failed to allocate {:?}", layout)
 }
 
 
+/// Prints the usage and the fragmentation of the heaps.
+pub fn print_report() {
+    print_heap("Heap (16-bit)", &ALLOC_UNDER16);
+    print_heap("Heap (20-bit)", &ALLOC_UNDER20);
+    print_heap("Global heap", &GLOBAL_ALLOC);
+}
+
+///
+/// Prints a line per heap: the bytes in use and free, and the
+/// high-water mark of the bytes requested.
+///
+/// It is called by the panic handler.  Hence, it does not block even
+/// if a heap is locked (e.g., by the panicking allocation), and checks
+/// the links of the free blocks instead of asserting them.
+///
+pub fn print_summary() {
+    print_heap_summary("Heap (16-bit)", &ALLOC_UNDER16);
+    print_heap_summary("Heap (20-bit)", &ALLOC_UNDER20);
+    print_heap_summary("Global heap", &GLOBAL_ALLOC);
+}
+
+fn print_heap_summary<I>(name: &str, alloc: &MuAlloc<I>)
+where
+    I: MuHeapIndex,
+{
+    match alloc.try_lock() {
+	Some(heap) => {
+	    let usage = heap.usage();
+	    println!("{}: in use {} bytes, free {} bytes (largest {}), \
+		      high-water {} bytes{}",
+		     name, usage.inuse_bytes, usage.free_bytes,
+		     usage.free_largest, alloc.high_water(),
+		     if usage.consistent { "" } else { ", BROKEN" });
+	},
+	None => {
+	    println!("{}: (locked) requested {} bytes, high-water {} bytes",
+		     name, alloc.requested_bytes(), alloc.high_water());
+	},
+    }
+}
+
+fn print_heap<I>(name: &str, alloc: &MuAlloc<I>)
+where
+    I: MuHeapIndex,
+{
+    let heap = alloc.lock();
+    let stats = heap.stats();
+    println!("{}: {}", name, stats.usage);
+    println!("  calls: alloc={} dealloc={} grow={} shrink={}, \
+	      largest request={}",
+	     stats.alloc_calls, stats.dealloc_calls, stats.grow_calls,
+	     stats.shrink_calls, stats.largest_request);
+    println!("  largest allocatable = {} (align = 16), {}",
+	     heap.largest_allocatable(16), heap.fragmentation());
+}
+
+
+//
 // Initialize the Global Allocator.
-pub fn init_global_alloc<A20>(size: usize, alloc20: A20) -> Vec<AddrRange, A20>
+//
+// The runtime regions are registered first so that they are excluded
+// from the memory map, then the global heap is registered as well.
+// Returns the boot information with the sanitized (canonical) memory
+// map.
+//
+pub fn init_global_alloc<A20>(size: usize, alloc20: A20) -> BootInfo<A20>
 where
-    A20: Allocator,
+    A20: Copy + Allocator,
 {
-    let lowest_addr = 1 << 20;  // Above 20-bit address space (i.e., above 1MB)
+    man_memory::reserve_runtime_regions();
 
     if let Some(addr_ranges) = bios::int15he820h::call(alloc20) {
-	for entry in &addr_ranges {
-	    #[allow(unused_parens)]
-	    if (entry.atype == AddrRange::TYPE_USABLE &&
-		entry.addr >= lowest_addr && entry.length as usize >= size) {
-		let base = entry.addr as usize;
-		unsafe {
-		    GLOBAL_ALLOC.lock().set_heap(base, size);
-		}
-		return addr_ranges;
-	    }
+	let addr_ranges = man_memory::sanitize(&addr_ranges, alloc20);
+	if init_global_alloc_in(size, &addr_ranges) {
+	    let memory_map = man_memory::sanitize(&addr_ranges, alloc20);
+	    PhysMem::init(&memory_map);
+	    return BootInfo::new(memory_map);
 	}
     }
 
     panic!("Failed to initialize the global allocator");
 }
+
+// Initialize the Global Allocator in one of given address ranges,
+// then register it in the region manager.
+// Returns false if no usable address range is large enough.
+pub fn init_global_alloc_in(size: usize, addr_ranges: &[AddrRange]) -> bool {
+    let lowest_addr = 1 << 20;	// Above 20-bit address space (i.e., above 1MB)
+
+    for entry in addr_ranges {
+	#[allow(unused_parens)]
+	if (entry.atype == AddrRange::TYPE_USABLE &&
+	    entry.addr >= lowest_addr && entry.length as usize >= size) {
+	    let base = entry.addr as usize;
+	    unsafe {
+		GLOBAL_ALLOC.lock().set_heap(base, size);
+	    }
+	    let _ = man_region::register("Global Heap", base, size);
+	    return true;
+	}
+    }
+
+    false
+}
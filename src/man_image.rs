@@ -0,0 +1,143 @@
+/*!
+
+Manages the loaded boot image.
+
+It verifies the integrity of the loaded image (main1) using the image
+trailer placed at the tail of main1 by the linker script
+( config/x86_64-unknown-none.ld ).  The checksum in the trailer is
+embedded by `mkimage` (and by `patch-cksum.sh` after the symbol table
+is embedded).
+
+If some sectors were not loaded (e.g. the number of sectors loaded by
+lmboot0 or BIOS was too small), this check fails instead of causing
+random crashes deep in execution.
+
+ */
+
+
+use core::fmt;
+use core::ptr;
+use core::slice;
+
+use crate::bios::ffi;
+use crate::util::cksum;
+
+
+/// Image Trailer (placed at the tail of main1)
+#[repr(C)]
+pub struct ImageTrailer {
+    pub magic: u32,	//00-03: Magic "LMBT"
+    pub size: u32,	//04-07: Size in Bytes of main1 (including trailer)
+    pub checksum: u32,	//08-0B: POSIX cksum of main1 (excluding trailer)
+    pub reserved: u32,	//0C-0F: (reserved)
+}
+
+impl ImageTrailer {
+    /// Magic "LMBT" in little-endian.
+    pub const MAGIC: u32 = 0x54424d4c;
+
+    /// Checksum value that means the checksum has not been embedded.
+    pub const NO_CHECKSUM: u32 = 0;
+}
+
+
+/// Errors detected by [`verify`].
+#[derive(Debug)]
+pub enum ImageError {
+    /// The trailer is not found (i.e., the image is truncated).
+    NoTrailer { addr: usize },
+    /// The size in the trailer does not match the linked size.
+    SizeMismatch { expected: usize, actual: usize },
+    /// The checksum does not match.
+    ChecksumMismatch { expected: u32, actual: u32 },
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::NoTrailer { addr } =>
+		write!(f, "Boot image is truncated: \
+			   no trailer found at {:#x} \
+			   (too few sectors loaded?)", addr),
+	    Self::SizeMismatch { expected, actual } =>
+		write!(f, "Boot image is broken: \
+			   size={:#x} in trailer, but {:#x} linked",
+		       actual, expected),
+	    Self::ChecksumMismatch { expected, actual } =>
+		write!(f, "Boot image is corrupted: \
+			   checksum={:#x} in trailer, but {:#x} computed",
+		       expected, actual),
+	}
+    }
+}
+
+
+/// Results of [`verify`].
+#[derive(Debug)]
+pub struct ImageStatus {
+    pub size: usize,		// Size in bytes of main1
+    pub checksum: Option<u32>,	// Verified checksum (None if not embedded)
+}
+
+impl fmt::Display for ImageStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self.checksum {
+	    Some(checksum) =>
+		write!(f, "size={:#x}, checksum={:#x} OK",
+		       self.size, checksum),
+	    None =>
+		write!(f, "size={:#x}, checksum not embedded", self.size),
+	}
+    }
+}
+
+
+/// Returns the image trailer of the loaded image (main1).
+pub fn trailer() -> &'static ImageTrailer {
+    let trailer = ptr::addr_of!(ffi::__lmb_trailer_start);
+    unsafe { &*(trailer as *const ImageTrailer) }
+}
+
+
+///
+/// Verifies the integrity of the loaded image (main1).
+///
+/// It must be called at the very beginning of the program because
+/// the checksum covers `.data` and `.bss`, too.
+///
+pub fn verify() -> Result<ImageStatus, ImageError> {
+    let (start, trailer_addr) = unsafe {
+	(&ffi::__lmb_main1_start as *const u8 as usize,
+	 &ffi::__lmb_trailer_start as *const u8 as usize)
+    };
+    let trailer = unsafe { &*(trailer_addr as *const ImageTrailer) };
+
+    if trailer.magic != ImageTrailer::MAGIC {
+	return Err(ImageError::NoTrailer { addr: trailer_addr });
+    }
+
+    let size = trailer_addr + core::mem::size_of::<ImageTrailer>() - start;
+    if trailer.size as usize != size {
+	return Err(ImageError::SizeMismatch {
+	    expected: size,
+	    actual: trailer.size as usize,
+	});
+    }
+
+    if trailer.checksum == ImageTrailer::NO_CHECKSUM {
+	return Ok(ImageStatus { size, checksum: None });
+    }
+
+    let main1 = unsafe {
+	slice::from_raw_parts(start as *const u8, trailer_addr - start)
+    };
+    let checksum = cksum(main1);
+    if checksum != trailer.checksum {
+	return Err(ImageError::ChecksumMismatch {
+	    expected: trailer.checksum,
+	    actual: checksum,
+	});
+    }
+
+    Ok(ImageStatus { size, checksum: Some(checksum) })
+}
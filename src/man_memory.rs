@@ -0,0 +1,221 @@
+/*!
+
+Sanitizes the memory map.
+
+The memory map reported by firmware (e.g. BIOS INT 15h AX=E820h)
+does not know which memory is used by the runtime itself.  Hence,
+[`reserve_runtime_regions`] registers the areas owned by the runtime
+in the region manager ([`man_region`](crate::man_region)), and
+[`sanitize`] marks them as reserved in the memory map.
+
+The sanitized memory map is the canonical one, which is held by
+[`BootInfo`].  Anything allocating memory from the memory map must
+use it instead of the one reported by firmware.
+
+ */
+
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::ptr::read_volatile;
+
+use crate::bios::{ffi, int15he820h::AddrRange};
+use crate::man_region::{self, MAX_REGIONS, Region};
+
+
+// The Interrupt Vector Table (IVT) and the BIOS Data Area (BDA)
+const IVT_BDA_START: usize = 0x0000;
+const IVT_BDA_END: usize = 0x0500;
+
+// The segment of the Extended BIOS Data Area (EBDA) in the BDA.
+const BDA_EBDA_SEGMENT: usize = 0x040e;
+
+// The EBDA lies just below the video memory.
+const EBDA_LOWEST: usize = 0x80000;
+const EBDA_END: usize = 0xa0000;
+
+// The copy of the MBR (lmboot0)
+const LMBOOT0_START: usize = 0x7c00;
+const LMBOOT0_SIZE: usize = 0x200;
+
+
+/// Resources obtained at boot.
+pub struct BootInfo<A>
+where
+    A: Allocator,
+{
+    memory_map: Vec<AddrRange, A>,
+}
+
+impl<A> BootInfo<A>
+where
+    A: Allocator,
+{
+    /// Creates boot information with a sanitized memory map.
+    pub fn new(memory_map: Vec<AddrRange, A>) -> Self {
+	Self { memory_map }
+    }
+
+    /// Returns the canonical (sanitized) memory map.
+    pub fn memory_map(&self) -> &[AddrRange] {
+	&self.memory_map
+    }
+}
+
+
+///
+/// Registers the areas owned by the runtime in the region manager:
+/// the IVT and the BDA, the EBDA, lmboot0 and lmbios1 (including the
+/// Rust program), and the heap areas in 16-bit and 20-bit address
+/// space.
+///
+/// The stack area and the page tables are registered by
+/// `bios::init_stack_canary`, and the crash log area is registered
+/// by `debug::crash_log::init`.
+///
+pub fn reserve_runtime_regions() {
+    let _ = man_region::register("IVT and BDA", IVT_BDA_START,
+				 IVT_BDA_END - IVT_BDA_START);
+
+    if let Some(ebda) = ebda_start() {
+	let _ = man_region::register("EBDA", ebda, EBDA_END - ebda);
+    }
+
+    unsafe {
+	let _ = man_region::register("lmboot0", LMBOOT0_START, LMBOOT0_SIZE);
+	register_area("lmbios1", &ffi::__lmb_main1_start,
+		      &ffi::__lmb_main1_end);
+	register_area("Heap (16-bit)", &ffi::__lmb_heap16_start,
+		      &ffi::__lmb_heap16_end);
+	register_area("Heap (20-bit)", &ffi::__lmb_heap32_start,
+		      &ffi::__lmb_heap32_end);
+    }
+}
+
+// Registers an area defined in the linker script (if not empty).
+fn register_area(name: &'static str, start: &u8, end: &u8) {
+    let start = start as *const u8 as usize;
+    let end = end as *const u8 as usize;
+    if start < end {
+	let _ = man_region::register(name, start, end - start);
+    }
+}
+
+// Returns the start address of the EBDA (if any).
+fn ebda_start() -> Option<usize> {
+    let segment = unsafe { read_volatile(BDA_EBDA_SEGMENT as *const u16) };
+    let start = (segment as usize) << 4;
+    (EBDA_LOWEST..EBDA_END).contains(&start).then_some(start)
+}
+
+
+///
+/// Returns the memory map in which the parts of usable address
+/// ranges owned by the runtime are marked as reserved.
+///
+/// A usable address range overlapping with registered regions is
+/// split into usable and reserved address ranges.  The other address
+/// ranges are copied as they are.
+///
+pub fn sanitize<A>(addr_ranges: &[AddrRange], alloc: A) -> Vec<AddrRange, A>
+where
+    A: Allocator,
+{
+    // Note: The global allocator may not be initialized yet.  Hence,
+    //       the regions are copied into an array.
+    let mut regions = [None; MAX_REGIONS];
+    let mut nregions = 0;
+    man_region::for_each(| region | {
+	regions[nregions] = Some(*region);
+	nregions += 1;
+    });
+    let regions = &mut regions[.. nregions];
+    regions.sort_unstable_by_key(| region | region.map(| r | r.start));
+
+    let mut vec = Vec::new_in(alloc);
+    for entry in addr_ranges {
+	if entry.atype == AddrRange::TYPE_USABLE {
+	    split_usable(entry, regions.iter().flatten(), &mut vec);
+	} else {
+	    vec.push(*entry);
+	}
+    }
+
+    vec
+}
+
+// Splits a usable address range by the regions (sorted by the start).
+fn split_usable<'a, I, A>(entry: &AddrRange, regions: I,
+			  vec: &mut Vec<AddrRange, A>)
+where
+    I: IntoIterator<Item = &'a Region>,
+    A: Allocator,
+{
+    let end = entry.addr.saturating_add(entry.length);
+    let mut push = | start: u64, end: u64, atype: u32 | {
+	if start < end {
+	    vec.push(AddrRange {
+		addr: start,
+		length: end - start,
+		atype,
+		attr: entry.attr,
+	    });
+	}
+    };
+
+    let mut cur = entry.addr;
+    for region in regions {
+	let start = (region.start as u64).clamp(cur, end);
+	let region_end = (region.end as u64).clamp(cur, end);
+	if start == region_end {
+	    continue;
+	}
+	push(cur, start, AddrRange::TYPE_USABLE);
+	push(start, region_end, AddrRange::TYPE_RESERVED);
+	cur = region_end;
+    }
+    push(cur, end, AddrRange::TYPE_USABLE);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::alloc::Global;
+
+    fn range(addr: u64, length: u64, atype: u32) -> AddrRange {
+	AddrRange { addr, length, atype, attr: AddrRange::ATTR_DEFAULT }
+    }
+
+    fn region(start: usize, end: usize) -> Region {
+	Region { name: "test", start, end }
+    }
+
+    fn split(entry: AddrRange, regions: &[Region]) -> Vec<(u64, u64, u32)> {
+	let mut vec = Vec::new_in(Global);
+	split_usable(&entry, regions, &mut vec);
+	vec.iter().map(| r | (r.addr, r.length, r.atype)).collect()
+    }
+
+    #[test]
+    fn split_usable_ranges() {
+	const USABLE: u32 = AddrRange::TYPE_USABLE;
+	const RESERVED: u32 = AddrRange::TYPE_RESERVED;
+
+	// No overlap
+	assert_eq!(split(range(0x1000, 0x1000, USABLE),
+			 &[region(0x3000, 0x4000)]),
+		   [(0x1000, 0x1000, USABLE)]);
+
+	// Regions in the middle and across the end
+	assert_eq!(split(range(0x1000, 0x4000, USABLE),
+			 &[region(0x2000, 0x2800), region(0x4000, 0x8000)]),
+		   [(0x1000, 0x1000, USABLE), (0x2000, 0x800, RESERVED),
+		    (0x2800, 0x1800, USABLE), (0x4000, 0x1000, RESERVED)]);
+
+	// A region covering the whole range
+	assert_eq!(split(range(0x1000, 0x1000, USABLE),
+			 &[region(0x0000, 0x4000)]),
+		   [(0x1000, 0x1000, RESERVED)]);
+    }
+}
@@ -0,0 +1,132 @@
+/*!
+
+Manages memory regions owned by the runtime.
+
+A region registered here (e.g. the crash log area) is owned by the
+runtime.  Hence, it must not be handed out by allocators nor
+overwritten by loaded programs.
+
+The region table is a fixed-size array.  Hence, regions can be
+registered before the global allocator is initialized.
+
+ */
+
+
+use core::fmt;
+
+use crate::mu::MuMutex;
+use crate::println;
+
+
+/// The maximum number of regions that can be registered.
+pub const MAX_REGIONS: usize = 32;
+
+
+/// A memory region owned by the runtime.
+#[derive(Clone, Copy, Debug)]
+pub struct Region {
+    pub name: &'static str,	// Name of Region (for debug)
+    pub start: usize,		// Start Address
+    pub end: usize,		// End Address (exclusive)
+}
+
+impl Region {
+    /// Returns the size in bytes of the region.
+    pub fn size(&self) -> usize {
+	self.end - self.start
+    }
+
+    /// Returns true if the region contains the address.
+    pub fn contains(&self, addr: usize) -> bool {
+	self.start <= addr && addr < self.end
+    }
+
+    /// Returns true if the region overlaps with the range.
+    pub fn overlaps(&self, start: usize, end: usize) -> bool {
+	self.start < end && start < self.end
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{:#010x} - {:#010x} ({:#x}) {}",
+	       self.start, self.end, self.size(), self.name)
+    }
+}
+
+
+/// Errors returned by [`register`].
+#[derive(Clone, Copy, Debug)]
+pub enum RegionError {
+    /// The region table is full.
+    Full,
+    /// The region is empty.
+    Empty,
+    /// The region overlaps with a registered region.
+    Overlap(Region),
+}
+
+
+struct RegionTable {
+    regions: [Option<Region>; MAX_REGIONS],
+}
+
+static REGIONS: MuMutex<RegionTable> = MuMutex::new(RegionTable {
+    regions: [None; MAX_REGIONS],
+});
+
+
+/// Registers a memory region owned by the runtime.
+pub fn register(name: &'static str, start: usize, size: usize)
+		-> Result<(), RegionError> {
+    let end = start + size;
+    if size == 0 {
+	return Err(RegionError::Empty);
+    }
+
+    let mut table = REGIONS.lock();
+
+    for region in table.regions.iter().flatten() {
+	if region.overlaps(start, end) {
+	    return Err(RegionError::Overlap(*region));
+	}
+    }
+
+    match table.regions.iter_mut().find(| slot | slot.is_none()) {
+	Some(slot) => {
+	    *slot = Some(Region { name, start, end });
+	    Ok(())
+	},
+	None => Err(RegionError::Full),
+    }
+}
+
+/// Finds the registered region that contains the address.
+pub fn find(addr: usize) -> Option<Region> {
+    let table = REGIONS.lock();
+    table.regions.iter().flatten().find(| r | r.contains(addr)).copied()
+}
+
+/// Finds a registered region that overlaps with the range.
+pub fn find_overlap(start: usize, size: usize) -> Option<Region> {
+    let table = REGIONS.lock();
+    table.regions.iter().flatten()
+	.find(| r | r.overlaps(start, start + size)).copied()
+}
+
+/// Calls a closure for each registered region.
+pub fn for_each<F>(mut f: F)
+where
+    F: FnMut(&Region)
+{
+    let table = REGIONS.lock();
+    for region in table.regions.iter().flatten() {
+	f(region);
+    }
+}
+
+/// Prints registered regions.
+pub fn print() {
+    println!("Runtime Regions:");
+    for_each(| region | println!("  {}", region));
+}
@@ -4,18 +4,598 @@ Manages vide mode.
 
 It finds the best video mode using VESA BIOS Extentions (INT 10h AX=4Fxxh).
 
+`FrameBuffer` describes the linear frame buffer of a mode (packed
+pixel or direct color by the mask sizes and the field positions), and
+draws pixels, rectangles and images on it.
+
+`ShadowBuffer` keeps a copy of a frame buffer in system RAM, so that
+drawing reads no video memory, and `present` copies only the changed
+spans of the scan lines to the frame buffer.
+
+`VideoState` captures the current video mode and state (INT 10h AH=0Fh
+and AX=4F04h), and restores them later.  `ensure_text_mode` returns
+the display to the standard 80x25 text mode (mode 03h).
+
+```ignore
+let state = man_video::VideoState::capture(&ALLOC_UNDER20);
+// ... set a graphics mode and draw something ...
+state.restore();
+```
+
+`with_mode` does the same as a transaction: it sets a VBE mode, runs a
+closure with its `FrameBuffer`, then restores the previous mode when
+the closure returns.  If the closure panics, the panic handler returns
+the display to the text mode instead (there is no unwinding).
+
+```ignore
+man_video::with_mode(0x118, 0, &ALLOC_UNDER20, | fb | {
+    fb.fill_rect(0, 0, 100, 100, 0x00ff0000);
+});
+```
+
 */
 
 
+use alloc::vec::Vec;
 use core::alloc::Allocator;
+use core::ptr::{copy_nonoverlapping, read_volatile, write_volatile};
+use core::sync::atomic::{AtomicBool, Ordering};
 
 use crate::bios;
+use crate::console;
+use crate::bios::int10h4f00h::VbeInfoBlock;
 use crate::bios::int10h4f01h::ModeInfoBlock;
 use crate::{print, println};
+use crate::text_writer::TextWriter;
 use crate::x86::X86FarPtr;
 
 const DEBUG: bool = false;
 
+// True if a VBE mode other than the standard VGA modes has been set.
+static VBE_MODE_SET: AtomicBool = AtomicBool::new(false);
+
+// True while the closure of `with_mode` runs (the screen sink is not
+// drawable in the mode).
+static IN_WITH_MODE: AtomicBool = AtomicBool::new(false);
+
+
+/// Returns true if a VBE mode (e.g., a graphics mode) has been set.
+pub fn is_vbe_mode_set() -> bool {
+    VBE_MODE_SET.load(Ordering::Acquire)
+}
+
+/// Restores the standard 80x25 text mode (e.g., to show a panic screen).
+pub fn restore_text_mode() {
+    bios::int10h00h::call(bios::int10h00h::MODE_TEXT_80X25);
+    VBE_MODE_SET.store(false, Ordering::Release);
+    console::reset_screen();
+}
+
+/// Restores the standard 80x25 text mode unless text is printable on
+/// the screen (i.e., in the text mode or on the frame buffer console).
+/// It does not allocate memory (It can be called on panic).
+pub fn ensure_text_mode() {
+    if console::screen_kind() == console::ScreenKind::FrameBuffer &&
+	!IN_WITH_MODE.load(Ordering::Acquire) {
+	return;
+    }
+
+    let cur_mode = bios::int10h0fh::call();
+    #[allow(unused_parens)]
+    if (is_vbe_mode_set() ||
+	cur_mode.is_some_and(|cur| {
+	    cur.mode != bios::int10h00h::MODE_TEXT_80X25
+	})) {
+	restore_text_mode();
+    }
+}
+
+
+/// A field of a color component in a direct color pixel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorField {
+    pub size: u8,	// Number of Bits (Mask Size)
+    pub position: u8,	// Bit Position of the LSB
+}
+
+impl ColorField {
+    // Places the upper bits of an 8-bit component in the field.
+    fn encode(&self, component: u32) -> u32 {
+	let size = self.size.min(8) as u32;
+	(component >> (8 - size)) << self.position
+    }
+
+    // Extracts the component from the field, and scales it to 8 bits.
+    fn decode(&self, pixel: u32) -> u32 {
+	let size = self.size.min(8) as u32;
+	let value = (pixel >> self.position) & ((1 << size) - 1);
+	value << (8 - size)
+    }
+}
+
+/// Layouts of pixels in a frame buffer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PixelFormat {
+    /// Packed pixel: a pixel is an index of the palette (e.g. 8 bpp).
+    Packed,
+    /// Direct color: a pixel consists of the fields of the components.
+    Direct {
+	red: ColorField,
+	green: ColorField,
+	blue: ColorField,
+    },
+}
+
+impl PixelFormat {
+    /// The direct color layout 0x00RRGGBB (8 bits per component).
+    pub const RGB888: Self = Self::Direct {
+	red: ColorField { size: 8, position: 16 },
+	green: ColorField { size: 8, position: 8 },
+	blue: ColorField { size: 8, position: 0 },
+    };
+}
+
+
+///
+/// A linear frame buffer of a VBE graphics mode.
+///
+/// Colors are given as 0x00RRGGBB in direct color modes, and as
+/// indexes of the palette in packed pixel modes.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct FrameBuffer {
+    pub base: usize,		// Physical Address
+    pub pitch: usize,		// Bytes per Scan Line
+    pub width: usize,		// Width in Pixels
+    pub height: usize,		// Height in Pixels
+    pub bpp: u8,		// Bits per Pixel
+    pub format: PixelFormat,	// Layout of Pixels
+}
+
+impl FrameBuffer {
+    ///
+    /// Describes the linear frame buffer of the mode (e.g. after the
+    /// mode is set by `int10h4f02h::call`).  Returns None if the mode
+    /// has no linear frame buffer, or its memory model is neither
+    /// packed pixel (8 bpp) nor direct color (15, 16, 24 or 32 bpp).
+    ///
+    pub fn from_mode_info(mib: &ModeInfoBlock) -> Option<Self> {
+	// Note: VBE 3.0 reports the pitch and the fields in linear modes
+	//       separately.
+	let linear = mib.lin_bytes_per_scan_line != 0;
+	let pitch = if linear {
+	    mib.lin_bytes_per_scan_line
+	} else {
+	    mib.bytes_per_scan_line
+	};
+
+	let field = | size, position, lin_size, lin_position | {
+	    if linear && lin_size != 0 {
+		ColorField { size: lin_size, position: lin_position }
+	    } else {
+		ColorField { size, position }
+	    }
+	};
+	let format = match (mib.memory_model, mib.bits_per_pixel) {
+	    (ModeInfoBlock::MEM_PACKED_PIXEL, 8) => PixelFormat::Packed,
+	    (ModeInfoBlock::MEM_DIRECT_COLOR, 15 | 16 | 24 | 32) => {
+		let red = field(mib.red_mask_size, mib.red_field_position,
+				mib.lin_red_mask_size,
+				mib.lin_red_field_position);
+		let green = field(mib.green_mask_size,
+				  mib.green_field_position,
+				  mib.lin_green_mask_size,
+				  mib.lin_green_field_position);
+		let blue = field(mib.blue_mask_size, mib.blue_field_position,
+				 mib.lin_blue_mask_size,
+				 mib.lin_blue_field_position);
+		if red.size == 0 || green.size == 0 || blue.size == 0 {
+		    // Some BIOSes report no fields for 24 and 32 bpp.
+		    PixelFormat::RGB888
+		} else {
+		    PixelFormat::Direct { red, green, blue }
+		}
+	    },
+	    _ => return None,
+	};
+
+	let fb = Self {
+	    base: mib.phys_base_ptr() as usize,
+	    pitch: pitch as usize,
+	    width: mib.x_resolution as usize,
+	    height: mib.y_resolution as usize,
+	    bpp: mib.bits_per_pixel,
+	    format,
+	};
+	(fb.base != 0).then_some(fb)
+    }
+
+    /// Returns the number of bytes per pixel.
+    pub fn bytes_per_pixel(&self) -> usize {
+	(self.bpp as usize).div_ceil(8)
+    }
+
+    /// Converts the color into the value of a pixel.
+    pub fn encode(&self, color: u32) -> u32 {
+	match self.format {
+	    PixelFormat::Packed => color & 0xff,
+	    PixelFormat::Direct { red, green, blue } => {
+		red.encode((color >> 16) & 0xff) |
+		green.encode((color >> 8) & 0xff) |
+		blue.encode(color & 0xff)
+	    },
+	}
+    }
+
+    /// Converts the value of a pixel into the color.
+    pub fn decode(&self, pixel: u32) -> u32 {
+	match self.format {
+	    PixelFormat::Packed => pixel & 0xff,
+	    PixelFormat::Direct { red, green, blue } => {
+		red.decode(pixel) << 16 |
+		green.decode(pixel) << 8 |
+		blue.decode(pixel)
+	    },
+	}
+    }
+
+    /// Sets the color of the pixel (ignored if outside the screen).
+    pub fn put_pixel(&self, x: usize, y: usize, color: u32) {
+	if x < self.width && y < self.height {
+	    self.write_pixel(x, y, self.encode(color));
+	}
+    }
+
+    /// Fills the rectangle (clipped to the screen) with the color.
+    pub fn fill_rect(&self, x: usize, y: usize, width: usize,
+		     height: usize, color: u32) {
+	let pixel = self.encode(color);
+	let x_end = x.saturating_add(width).min(self.width);
+	let y_end = y.saturating_add(height).min(self.height);
+	for cur_y in y .. y_end {
+	    for cur_x in x .. x_end {
+		self.write_pixel(cur_x, cur_y, pixel);
+	    }
+	}
+    }
+
+    ///
+    /// Draws the image of `width` x `height` colors (in row-major
+    /// order) at the position.  It is clipped to the screen.
+    ///
+    pub fn blit(&self, x: usize, y: usize, width: usize, height: usize,
+		colors: &[u32]) {
+	for (dy, row) in colors.chunks(width.max(1)).take(height).enumerate() {
+	    for (dx, &color) in row.iter().enumerate() {
+		self.put_pixel(x + dx, y + dy, color);
+	    }
+	}
+    }
+
+    // Writes the value of the pixel (in little endian).
+    fn write_pixel(&self, x: usize, y: usize, pixel: u32) {
+	let bytes_per_pixel = self.bytes_per_pixel();
+	let addr = self.base + y * self.pitch + x * bytes_per_pixel;
+	unsafe {
+	    match bytes_per_pixel {
+		4 => write_volatile(addr as *mut u32, pixel),
+		2 => write_volatile(addr as *mut u16, pixel as u16),
+		_ => {
+		    let bytes = pixel.to_le_bytes();
+		    for (i, &byte) in bytes[.. bytes_per_pixel].iter()
+			.enumerate() {
+			write_volatile((addr + i) as *mut u8, byte);
+		    }
+		},
+	    }
+	}
+    }
+}
+
+/// Sets the VBE direct color mode (24 or 32 bpp) with a linear frame
+/// buffer closest to the size, then returns the frame buffer.
+pub fn set_frame_buffer_mode<A20>(width: u16, height: u16, alloc20: A20)
+				  -> Option<FrameBuffer>
+where
+    A20: Copy + Allocator,
+{
+    let best_mode = VbeMode::find_graphics_mode(width, height, 32, alloc20)?;
+    let mib = bios::int10h4f01h::call(best_mode.mode, alloc20)?;
+
+    let fb = FrameBuffer::from_mode_info(&mib)?;
+    if fb.bpp != 24 && fb.bpp != 32 {
+	return None;
+    }
+
+    if !best_mode.set_mode(VbeMode::USE_FRAME_BUFFER) {
+	return None;
+    }
+
+    Some(fb)
+}
+
+
+///
+/// A copy of a frame buffer in system RAM.
+///
+/// Drawing operations that read pixels (e.g. blending and scrolling)
+/// read the copy instead of slow video memory.  The changed span of
+/// each scan line is recorded, and `present` copies only the changed
+/// spans to the frame buffer.
+///
+pub struct ShadowBuffer<A>
+where
+    A: Allocator,
+{
+    fb: FrameBuffer,
+    pixels: Vec<u8, A>,			// Scan lines without padding
+    dirty: Vec<(usize, usize), A>,	// Changed bytes of each scan line
+}
+
+impl<A> ShadowBuffer<A>
+where
+    A: Copy + Allocator,
+{
+    ///
+    /// Allocates a copy of the frame buffer by `alloc`, then fills it
+    /// with the current contents of the frame buffer.  Returns None
+    /// if the allocation fails.
+    ///
+    pub fn new(fb: FrameBuffer, alloc: A) -> Option<Self> {
+	let line_bytes = fb.width * fb.bytes_per_pixel();
+	let mut pixels = Vec::new_in(alloc);
+	pixels.try_reserve_exact(line_bytes * fb.height).ok()?;
+	let mut dirty = Vec::new_in(alloc);
+	dirty.try_reserve_exact(fb.height).ok()?;
+
+	for y in 0 .. fb.height {
+	    let line = (fb.base + y * fb.pitch) as *const u8;
+	    for x in 0 .. line_bytes {
+		pixels.push(unsafe { read_volatile(line.add(x)) });
+	    }
+	    dirty.push(Self::CLEAN);
+	}
+
+	Some(Self { fb, pixels, dirty })
+    }
+}
+
+impl<A> ShadowBuffer<A>
+where
+    A: Allocator,
+{
+    // The span of a scan line without changes.
+    const CLEAN: (usize, usize) = (usize::MAX, 0);
+
+    /// Returns the frame buffer.
+    pub fn frame_buffer(&self) -> &FrameBuffer {
+	&self.fb
+    }
+
+    /// Returns the color of the pixel (cf. [`FrameBuffer`]).
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<u32> {
+	let off = self.offset(x, y)?;
+	let bytes = &self.pixels[off .. off + self.bytes_per_pixel()];
+	let pixel = bytes.iter().rev().fold(0, | v, &b | v << 8 | b as u32);
+	Some(self.fb.decode(pixel))
+    }
+
+    /// Sets the color of the pixel (cf. [`FrameBuffer`]).
+    pub fn put_pixel(&mut self, x: usize, y: usize, color: u32) {
+	if let Some(off) = self.offset(x, y) {
+	    let n = self.bytes_per_pixel();
+	    let pixel = self.fb.encode(color);
+	    self.pixels[off .. off + n]
+		.copy_from_slice(&pixel.to_le_bytes()[.. n]);
+	    self.mark_dirty(x, y, 1, 1);
+	}
+    }
+
+    /// Fills the rectangle (clipped to the screen) with the color.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize,
+		     height: usize, color: u32) {
+	let x_end = x.saturating_add(width).min(self.fb.width);
+	let y_end = y.saturating_add(height).min(self.fb.height);
+	for cur_y in y .. y_end {
+	    for cur_x in x .. x_end {
+		self.put_pixel(cur_x, cur_y, color);
+	    }
+	}
+    }
+
+    ///
+    /// Scrolls up by `lines` scan lines, then fills the scan lines at
+    /// the bottom with the color.
+    ///
+    pub fn scroll_up(&mut self, lines: usize, color: u32) {
+	let lines = lines.min(self.fb.height);
+	let line_bytes = self.line_bytes();
+	self.pixels.copy_within(lines * line_bytes .., 0);
+	self.mark_dirty(0, 0, self.fb.width, self.fb.height);
+	self.fill_rect(0, self.fb.height - lines, self.fb.width, lines, color);
+    }
+
+    /// Records that the rectangle has been changed.
+    pub fn mark_dirty(&mut self, x: usize, y: usize, width: usize,
+		      height: usize) {
+	let bpp = self.bytes_per_pixel();
+	let start = x.min(self.fb.width) * bpp;
+	let end = x.saturating_add(width).min(self.fb.width) * bpp;
+	let y_end = y.saturating_add(height).min(self.fb.height);
+	for span in self.dirty.iter_mut().take(y_end).skip(y) {
+	    *span = (span.0.min(start), span.1.max(end));
+	}
+    }
+
+    /// Returns true if any pixel has been changed since `present`.
+    pub fn is_dirty(&self) -> bool {
+	self.dirty.iter().any(| &(start, end) | start < end)
+    }
+
+    /// Copies the changed spans to the frame buffer.
+    pub fn present(&mut self) {
+	let line_bytes = self.line_bytes();
+	for (y, span) in self.dirty.iter_mut().enumerate() {
+	    let (start, end) = *span;
+	    if start < end {
+		let src = &self.pixels[y * line_bytes + start ..
+				       y * line_bytes + end];
+		let dst = (self.fb.base + y * self.fb.pitch + start) as *mut u8;
+		unsafe {
+		    copy_nonoverlapping(src.as_ptr(), dst, end - start);
+		}
+		*span = Self::CLEAN;
+	    }
+	}
+    }
+
+    fn bytes_per_pixel(&self) -> usize {
+	self.fb.bytes_per_pixel()
+    }
+
+    fn line_bytes(&self) -> usize {
+	self.fb.width * self.bytes_per_pixel()
+    }
+
+    // Returns the offset of the pixel in the copy (None if outside).
+    fn offset(&self, x: usize, y: usize) -> Option<usize> {
+	(x < self.fb.width && y < self.fb.height)
+	    .then(|| y * self.line_bytes() + x * self.bytes_per_pixel())
+    }
+}
+
+
+/// A video mode and state captured to be restored later.
+pub struct VideoState<A20>
+where
+    A20: Allocator,
+{
+    mode: Option<bios::int10h0fh::VideoMode>,	// Standard VGA mode
+    vbe_mode: Option<u16>,			// VBE mode (if set)
+    state: Option<Vec<u8, A20>>,		// VBE controller state
+}
+
+impl<A20> VideoState<A20>
+where
+    A20: Allocator,
+{
+    // Restored without clearing the display memory.
+    const VGA_DONT_CLEAR: u8 = 1 << 7;
+    const VBE_DONT_CLEAR: u16 = 1 << 15;
+
+    /// Captures the current video mode and state.
+    /// The state is saved in a buffer allocated by `alloc20`.
+    pub fn capture(alloc20: A20) -> Self {
+	let vbe_mode = if is_vbe_mode_set() {
+	    Some(VbeMode::get_mode().mode)
+	} else {
+	    None
+	};
+
+	Self {
+	    mode: bios::int10h0fh::call(),
+	    vbe_mode,
+	    state: bios::int10h4f04h::save(bios::int10h4f04h::STATE_ALL,
+					   alloc20),
+	}
+    }
+
+    /// Returns the captured standard VGA mode (if any).
+    pub fn mode(&self) -> Option<u8> {
+	self.mode.map(|mode| mode.mode)
+    }
+
+    /// Returns the captured VBE mode (if any).
+    pub fn vbe_mode(&self) -> Option<u16> {
+	self.vbe_mode
+    }
+
+    /// Restores the captured video mode and state.
+    pub fn restore(&self) -> bool {
+	let mut ok = match (self.vbe_mode, self.mode) {
+	    (Some(vbe_mode), _) => {
+		VbeMode { mode: vbe_mode }.set_mode(Self::VBE_DONT_CLEAR)
+	    },
+	    (None, Some(mode)) => {
+		bios::int10h00h::call(mode.mode | Self::VGA_DONT_CLEAR);
+		VBE_MODE_SET.store(false, Ordering::Release);
+		true
+	    },
+	    (None, None) => false,
+	};
+
+	if let Some(state) = &self.state {
+	    ok &= bios::int10h4f04h::restore(bios::int10h4f04h::STATE_ALL,
+					     state);
+	}
+
+	ok
+    }
+}
+
+
+///
+/// Sets the VBE mode with a linear frame buffer, runs the closure with
+/// its frame buffer, then restores the previous video mode and state
+/// (cf. `VideoState`).  Returns None (without running the closure) if
+/// the mode has no linear frame buffer or cannot be set.
+///
+/// `flags` are added to the mode number (e.g. bit 15 not to clear the
+/// display memory).  While the closure runs, the output is not printed
+/// to the screen, which is not drawable in the mode.
+///
+/// The previous mode is restored by a drop guard.  If the closure
+/// panics, the guard is not dropped (there is no unwinding), but the
+/// panic handler returns the display to the text mode.
+///
+pub fn with_mode<A20, F, R>(mode: u16, flags: u16, alloc20: A20, f: F)
+			    -> Option<R>
+where
+    A20: Copy + Allocator,
+    F: FnOnce(&FrameBuffer) -> R,
+{
+    let mib = bios::int10h4f01h::call(mode, alloc20)?;
+    let fb = FrameBuffer::from_mode_info(&mib)?;
+
+    let guard = ModeGuard {
+	state: VideoState::capture(alloc20),
+	screen_level: console::config().screen,
+    };
+
+    console::set_sink_level(console::Sink::Screen, None);
+    IN_WITH_MODE.store(true, Ordering::Release);
+    if !(VbeMode { mode }).set_mode(flags | VbeMode::USE_FRAME_BUFFER) {
+	return None;
+    }
+
+    let result = f(&fb);
+    drop(guard);
+    Some(result)
+}
+
+// Restores the video mode and the screen sink captured by `with_mode`
+// when dropped.
+struct ModeGuard<A20>
+where
+    A20: Allocator,
+{
+    state: VideoState<A20>,
+    screen_level: Option<console::Level>,
+}
+
+impl<A20> Drop for ModeGuard<A20>
+where
+    A20: Allocator,
+{
+    fn drop(&mut self) {
+	if !self.state.restore() {
+	    restore_text_mode();
+	}
+	IN_WITH_MODE.store(false, Ordering::Release);
+	console::set_sink_level(console::Sink::Screen, self.screen_level);
+    }
+}
+
 
 pub fn find_graphics_mode<A20>(width: u16, height: u16, bpp: u8, alloc20: A20)
 			       -> Option<u16>
@@ -60,6 +640,9 @@ pub struct VbeMode {
 impl VbeMode {
     pub const USE_FRAME_BUFFER: u16 = 1 << 14;
 
+    // VBE mode numbers start from 0x100 (below are standard VGA modes).
+    const VBE_MODE_MIN: u16 = 0x100;
+
     pub fn find_graphics_mode<A20>(width: u16, height: u16, bpp: u8,
 				   alloc20: A20) -> Option<Self>
     where
@@ -68,21 +651,14 @@ impl VbeMode {
 	let vbe_info_block = bios::int10h4f00h::call(alloc20)?;
 
 	if DEBUG {
-	    vbe_info_block.print();
+	    TextWriter::batch(| _ | vbe_info_block.print());
 	}
 
 	let mode_fp = X86FarPtr::from_array(vbe_info_block.video_mode_ptr);
-	let mode_ptr = mode_fp.to_linear_ptr::<u16>();
 
 	let mut desired_size = DesiredSize::new(width, height, bpp);
 
-	let mut i: isize = 0;
-	loop {
-	    let mode = unsafe { *mode_ptr.offset(i) };
-	    if mode == 0xffff {
-		break;
-	    }
-
+	for mode in VbeInfoBlock::mode_list(&mode_fp) {
 	    let mib = bios::int10h4f01h::call(mode, alloc20)?;
 
 	    #[allow(unused_parens)]
@@ -97,8 +673,6 @@ impl VbeMode {
 		    break;
 		}
 	    }
-
-	    i += 1;
 	}
 
 	let best_mode = desired_size.get_best_mode();
@@ -113,7 +687,12 @@ impl VbeMode {
     }
 
     pub fn set_mode(&self, flags: u16) -> bool {
-	bios::int10h4f02h::call(self.mode | flags, None)
+	let ok = bios::int10h4f02h::call(self.mode | flags, None);
+	if ok {
+	    VBE_MODE_SET.store(self.mode >= Self::VBE_MODE_MIN,
+			       Ordering::Release);
+	}
+	ok
     }
 
     pub fn print<A20>(&self, alloc20: A20)
@@ -121,8 +700,10 @@ impl VbeMode {
 	A20: Allocator,
     {
 	if let Some(mib) = bios::int10h4f01h::call(self.mode, alloc20) {
-	    println!("mode = 0x{:04x}", self.mode);
-	    mib.print();
+	    TextWriter::batch(| _ | {
+		println!("mode = 0x{:04x}", self.mode);
+		mib.print();
+	    });
 	} else {
 	    println!("mode=0x{:04x}: Failed to get ModeInfoBlock", self.mode);
 	}
@@ -0,0 +1,222 @@
+/*!
+
+Tests the usable memory.
+
+[`run`] walks the usable address ranges of the memory map, excluding
+the regions owned by the runtime (cf. [`man_memory`](crate::man_memory)),
+and tests them with the following patterns.  Each pattern fills the
+whole range first, then verifies it, so that address lines shorted
+to each other (aliasing) are also detected.
+
+* [`Pattern::AddressInAddress`] - each word holds its own address
+  (then its complement).
+* [`Pattern::WalkingOnes`] - each word holds a single bit set, which
+  walks from bit 0 to bit 63 across words.
+* [`Pattern::Random`] - words of a pseudo-random sequence, which is
+  regenerated from the seed to verify them.
+
+Only the identity-mapped address space below 4GiB is tested.  The
+contents of the tested memory are destroyed.
+
+```ignore
+let report = memtest::run(boot_info.memory_map(), None, seed);
+println!("{}", report);
+```
+
+ */
+
+
+use alloc::alloc::Global;
+use core::fmt;
+use core::mem::size_of;
+use core::ptr::{read_volatile, write_volatile};
+
+use crate::bios::int15he820h::AddrRange;
+use crate::man_memory;
+use crate::util::XorShift64;
+
+
+/// The limit of the identity-mapped address space (4GiB).
+pub const ADDR_LIMIT: u64 = 1 << 32;
+
+/// The maximum number of errors recorded in a report.
+pub const MAX_ERRORS: usize = 8;
+
+// The size of a word tested at once.
+const WORD_SIZE: u64 = size_of::<u64>() as u64;
+
+
+/// Test Patterns
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pattern {
+    /// Each word holds its own address (then its complement).
+    AddressInAddress,
+    /// Each word holds a single bit set, walking across words.
+    WalkingOnes,
+    /// Words of a pseudo-random sequence from the seed.
+    Random(u64),
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::AddressInAddress => write!(f, "address-in-address"),
+	    Self::WalkingOnes => write!(f, "walking-ones"),
+	    Self::Random(seed) => write!(f, "random (seed={})", seed),
+	}
+    }
+}
+
+
+/// A word read differently from the value written.
+#[derive(Clone, Copy, Debug)]
+pub struct MemError {
+    pub addr: u64,
+    pub expected: u64,
+    pub actual: u64,
+    pub pattern: Pattern,
+}
+
+impl fmt::Display for MemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{:#010x}: expected {:#018x}, read {:#018x} \
+		   (bits {:#018x}, {})",
+	       self.addr, self.expected, self.actual,
+	       self.expected ^ self.actual, self.pattern)
+    }
+}
+
+
+/// The result of [`run`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemtestReport {
+    pub ranges: usize,			// Number of Tested Ranges
+    pub tested_bytes: u64,		// Number of Tested Bytes
+    pub error_count: usize,		// Number of Errors
+    pub errors: [Option<MemError>; MAX_ERRORS],	// The First Errors
+}
+
+impl MemtestReport {
+    /// Returns true if no error is found.
+    pub fn passed(&self) -> bool {
+	self.error_count == 0
+    }
+
+    // Records an error.
+    fn record(&mut self, error: MemError) {
+	if let Some(slot) = self.errors.get_mut(self.error_count) {
+	    *slot = Some(error);
+	}
+	self.error_count += 1;
+    }
+}
+
+impl fmt::Display for MemtestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "memtest: {} bytes in {} ranges, {} errors",
+	       self.tested_bytes, self.ranges, self.error_count)?;
+	for error in self.errors.iter().flatten() {
+	    write!(f, "\r\n  {}", error)?;
+	}
+	if self.error_count > MAX_ERRORS {
+	    write!(f, "\r\n  ...")?;
+	}
+	Ok(())
+    }
+}
+
+
+///
+/// Tests the usable address ranges of the memory map with all
+/// patterns, then returns the report.
+///
+/// The memory map is sanitized again, so that the regions registered
+/// after boot are also excluded.  At most `max_bytes` bytes are
+/// tested (if given).
+///
+pub fn run(memory_map: &[AddrRange], max_bytes: Option<u64>, seed: u64)
+	   -> MemtestReport {
+    let mut report = MemtestReport::default();
+    let mut budget = max_bytes.unwrap_or(u64::MAX);
+
+    let memory_map = man_memory::sanitize(memory_map, Global);
+    for entry in memory_map.iter() {
+	if entry.atype != AddrRange::TYPE_USABLE || budget == 0 {
+	    continue;
+	}
+
+	// Test whole words below the limit.
+	let start = entry.addr.next_multiple_of(WORD_SIZE);
+	let end = entry.addr.saturating_add(entry.length)
+	    .min(ADDR_LIMIT)
+	    .min(start.saturating_add(budget));
+	let end = end - end % WORD_SIZE;
+	if start >= end {
+	    continue;
+	}
+
+	// Note: The sanitized usable ranges are owned by no one.
+	for pattern in [Pattern::AddressInAddress, Pattern::WalkingOnes,
+			Pattern::Random(seed)] {
+	    unsafe {
+		test_range(start, end, pattern, | error | report.record(error));
+	    }
+	}
+	report.ranges += 1;
+	report.tested_bytes += end - start;
+	budget -= end - start;
+    }
+
+    report
+}
+
+///
+/// Tests the range of words from `start` to `end` (both aligned to
+/// 8 bytes) with the pattern, then calls `on_error` for each error.
+///
+/// # Safety
+///
+/// The range must be identity-mapped, and must not be used by anyone.
+///
+pub unsafe fn test_range<F>(start: u64, end: u64, pattern: Pattern,
+			    mut on_error: F)
+where
+    F: FnMut(MemError)
+{
+    let mut check = | value: fn(u64, &mut XorShift64) -> u64 | {
+	let mut rng = XorShift64::new(seed_of(pattern));
+	for addr in (start .. end).step_by(WORD_SIZE as usize) {
+	    write_volatile(addr as *mut u64, value(addr, &mut rng));
+	}
+
+	let mut rng = XorShift64::new(seed_of(pattern));
+	for addr in (start .. end).step_by(WORD_SIZE as usize) {
+	    let expected = value(addr, &mut rng);
+	    let actual = read_volatile(addr as *const u64);
+	    if actual != expected {
+		on_error(MemError { addr, expected, actual, pattern });
+	    }
+	}
+    };
+
+    match pattern {
+	Pattern::AddressInAddress => {
+	    check(| addr, _ | addr);
+	    check(| addr, _ | !addr);
+	},
+	Pattern::WalkingOnes => {
+	    check(| addr, _ | 1 << ((addr / WORD_SIZE) % 64));
+	},
+	Pattern::Random(_) => {
+	    check(| _, rng | rng.next_u64());
+	},
+    }
+}
+
+// Returns the seed of the pattern (0 if not random).
+fn seed_of(pattern: Pattern) -> u64 {
+    match pattern {
+	Pattern::Random(seed) => seed,
+	_ => 0,
+    }
+}
@@ -6,11 +6,22 @@ Provides a small library called Micro (mu) Library.
 
 
 #[doc(hidden)] mod mu_alloc;
+#[doc(hidden)] mod mu_fallback_alloc;
 #[doc(hidden)] mod mu_heap;
+#[doc(hidden)] mod mu_heap_track;
 #[doc(hidden)] mod mu_mutex;
+#[doc(hidden)] mod mu_ring_buf;
 #[doc(hidden)] mod push_bulk;
 
-#[doc(inline)] pub use self::mu_alloc::{MuAlloc, MuAlloc16, MuAlloc32};
-#[doc(inline)] pub use self::mu_heap::{MuHeap, MuHeapIndex};
+#[doc(inline)] pub use self::mu_alloc::{
+    AllocHooks, MuAlloc, MuAlloc8, MuAlloc16, MuAlloc32, MuAlloc64,
+};
+#[doc(inline)] pub use self::mu_fallback_alloc::{MuFallbackAlloc, MuOwns};
+#[doc(inline)] pub use self::mu_heap::{
+    HeapBlock, HeapCorruption, HeapFragmentation, HeapStats, HeapUsage,
+    MuHeap, MuHeapIndex,
+};
+#[doc(inline)] pub use self::mu_heap_track::AllocRecord;
 #[doc(inline)] pub use self::mu_mutex::MuMutex;
+#[doc(inline)] pub use self::mu_ring_buf::MuRingBuf;
 #[doc(inline)] pub use self::push_bulk::PushBulk;
@@ -5,19 +5,82 @@
 use core::{
     alloc::{Allocator, AllocError, GlobalAlloc, Layout},
     ops::Deref,
-    ptr::NonNull,
+    ptr::{NonNull, null_mut, write_bytes},
     slice,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
 };
 
 use super::{MuHeap, MuHeapIndex, MuMutex};
 
 
+/// Provides a mutex'ed allocator backed by [`MuHeap`]`<i8>`
+/// (for a tiny pool of up to 254 bytes).
+pub type MuAlloc8 = MuAlloc<i8>;
+
 /// Provides a mutex'ed allocator backed by [`MuHeap`]`<i16>`.
 pub type MuAlloc16 = MuAlloc<i16>;
 
 /// Provides a mutex'ed allocator backed by [`MuHeap`]`<i32>`.
 pub type MuAlloc32 = MuAlloc<i32>;
 
+/// Provides a mutex'ed allocator backed by [`MuHeap`]`<i64>`
+/// (for a heap of more than 16GiB).
+pub type MuAlloc64 = MuAlloc<i64>;
+
+///
+/// Hooks observing the allocations of a [`MuAlloc`] (cf. method
+/// [`MuAlloc::set_hooks`]).
+///
+/// Resizing (grow and shrink) is reported as the deallocation of the
+/// old block followed by the allocation of the new block.  If it
+/// fails, only `on_fail` is called (the old block remains valid).
+///
+#[derive(Clone, Copy, Default)]
+pub struct AllocHooks {
+    /// Called with a block allocated.
+    pub on_alloc: Option<fn(ptr: *mut u8, layout: Layout)>,
+    /// Called with a block to be deallocated.
+    pub on_dealloc: Option<fn(ptr: *mut u8, layout: Layout)>,
+    /// Called with the layout failed to be allocated.
+    pub on_fail: Option<fn(layout: Layout)>,
+}
+
+impl AllocHooks {
+    /// Returns hooks doing nothing (for a static declaration).
+    pub const fn new() -> Self {
+	Self {
+	    on_alloc: None,
+	    on_dealloc: None,
+	    on_fail: None,
+	}
+    }
+
+    fn allocated(&self, ptr: *mut u8, layout: Layout) {
+	if ptr.is_null() {
+	    if let Some(on_fail) = self.on_fail {
+		on_fail(layout);
+	    }
+	} else if let Some(on_alloc) = self.on_alloc {
+	    on_alloc(ptr, layout);
+	}
+    }
+
+    fn deallocated(&self, ptr: *mut u8, layout: Layout) {
+	if let Some(on_dealloc) = self.on_dealloc {
+	    on_dealloc(ptr, layout);
+	}
+    }
+
+    fn resized(&self, old_ptr: *mut u8, old_layout: Layout,
+	       new_ptr: *mut u8, new_layout: Layout) {
+	if !new_ptr.is_null() {
+	    self.deallocated(old_ptr, old_layout);
+	}
+	self.allocated(new_ptr, new_layout);
+    }
+}
+
+
 ///
 /// Provides a mutex'ed allocator backed by [`MuHeap`].
 ///
@@ -31,6 +94,10 @@ where
     I: MuHeapIndex
 {
     heap: MuMutex<MuHeap<I>>,
+    zero_on_free: bool,		// Zeroes memory on deallocation
+    hooks: AtomicPtr<AllocHooks>,	// Hooks (null if not installed)
+    requested: AtomicUsize,	// Bytes requested by the blocks in use
+    high_water: AtomicUsize,	// The maximum of `requested`
 }
 
 impl<I> MuAlloc<I>
@@ -42,14 +109,171 @@ where
     pub const unsafe fn heap(given_base: usize, given_size: usize) -> Self {
 	Self {
 	    heap: MuMutex::new(MuHeap::<I>::heap(given_base, given_size)),
+	    zero_on_free: false,
+	    hooks: AtomicPtr::new(null_mut()),
+	    requested: AtomicUsize::new(0),
+	    high_water: AtomicUsize::new(0),
 	}
     }
 
     /// Initializes a statically defined variable with no heap.
     pub const fn noheap() -> Self {
 	Self {
-	    heap: MuMutex::new(MuHeap::<I>::noheap())
+	    heap: MuMutex::new(MuHeap::<I>::noheap()),
+	    zero_on_free: false,
+	    hooks: AtomicPtr::new(null_mut()),
+	    requested: AtomicUsize::new(0),
+	    high_water: AtomicUsize::new(0),
+	}
+    }
+
+    ///
+    /// Makes the allocator zero memory on deallocation (including the
+    /// tail released by shrinking and the old block moved by growing).
+    ///
+    /// It is useful for buffers exchanged with BIOS.  That is, stale
+    /// data in a reused buffer cannot masquerade as a valid result
+    /// when a BIOS call fails without touching the buffer.
+    ///
+    pub const fn with_zero_on_free(mut self) -> Self {
+	self.zero_on_free = true;
+	self
+    }
+
+    /// Returns true if the allocator zeroes memory on deallocation.
+    pub fn zeroes_on_free(&self) -> bool {
+	self.zero_on_free
+    }
+
+    ///
+    /// Installs the hooks observing allocations (e.g., for tracing,
+    /// statistics or a leak tracker).  Replaces the current ones.
+    ///
+    /// The hooks are called after the heap is unlocked.  However,
+    /// they must not allocate memory from the same allocator.
+    ///
+    pub fn set_hooks(&self, hooks: &'static AllocHooks) {
+	let ptr = hooks as *const AllocHooks as *mut AllocHooks;
+	self.hooks.store(ptr, Ordering::Release);
+    }
+
+    /// Removes the hooks (if any).
+    pub fn clear_hooks(&self) {
+	self.hooks.store(null_mut(), Ordering::Release);
+    }
+
+    ///
+    /// Returns the number of bytes requested by the blocks in use
+    /// (excluding the rounding up and the headers of the heap).
+    ///
+    /// Unlike `MuHeap::usage`, it does not lock the heap.  Hence, it
+    /// can be called even while the heap is locked (e.g., on panic).
+    ///
+    pub fn requested_bytes(&self) -> usize {
+	self.requested.load(Ordering::Relaxed)
+    }
+
+    /// Returns the maximum of `requested_bytes` so far (high-water).
+    pub fn high_water(&self) -> usize {
+	self.high_water.load(Ordering::Relaxed)
+    }
+
+    // Counts the bytes requested by the blocks in use.
+    fn count_requested(&self, allocated: usize, released: usize) {
+	let requested = self.requested.fetch_add(allocated, Ordering::Relaxed)
+	    + allocated;
+	self.high_water.fetch_max(requested, Ordering::Relaxed);
+	self.requested.fetch_sub(released, Ordering::Relaxed);
+    }
+
+    fn hooks(&self) -> Option<&'static AllocHooks> {
+	let ptr = self.hooks.load(Ordering::Acquire);
+	unsafe { ptr.as_ref() }
+    }
+
+    // Allocates a memory block, then calls the hook.
+    unsafe fn do_alloc(&self, layout: Layout) -> *mut u8 {
+	let ptr = self.lock().alloc(layout.size(), layout.align());
+	if !ptr.is_null() {
+	    self.count_requested(layout.size(), 0);
+	}
+	if let Some(hooks) = self.hooks() {
+	    hooks.allocated(ptr, layout);
+	}
+	ptr
+    }
+
+    // Calls the hook, then deallocates a memory block (zeroed if
+    // zero_on_free).
+    unsafe fn do_dealloc(&self, ptr: *mut u8, layout: Layout) {
+	if let Some(hooks) = self.hooks() {
+	    hooks.deallocated(ptr, layout);
+	}
+	if self.zero_on_free {
+	    write_bytes(ptr, 0, layout.size());
+	}
+	self.lock().dealloc(ptr, layout.size(), layout.align());
+	self.count_requested(0, layout.size());
+    }
+
+    // Grows a memory block.  If it cannot be extended in place, it is
+    // moved elsewhere or towards the preceding free cells (cf.
+    // MuHeap::grow).  If moved, the old block is zeroed except the
+    // part overlapping with the new block (if zero_on_free).
+    unsafe fn do_grow(&self, ptr: *mut u8, old_layout: Layout,
+		      new_layout: Layout) -> *mut u8 {
+	let new_ptr = {
+	    let mut heap = self.lock();
+	    let new_ptr = heap.grow(ptr, old_layout.size(), new_layout.size(),
+				    old_layout.align());
+
+	    // Note: The data of a freed block are not overwritten by
+	    //       MuHeap.  Hence, the old block can be zeroed here
+	    //       (while the heap is locked).  If the block is moved
+	    //       towards the preceding free cells, the old block
+	    //       overlaps with the new one, whose data and trailing
+	    //       management cell must be kept.
+	    if self.zero_on_free && !new_ptr.is_null() && new_ptr != ptr {
+		let old_end = ptr.add(old_layout.size());
+		let new_end = MuHeap::<I>::block_end(new_ptr,
+						     new_layout.size());
+		let start = if new_ptr < ptr && ptr < new_end {
+		    new_end.min(old_end)
+		} else {
+		    ptr
+		};
+		write_bytes(start, 0, old_end.offset_from(start) as usize);
+	    }
+	    new_ptr
+	};
+
+	if !new_ptr.is_null() {
+	    self.count_requested(new_layout.size() - old_layout.size(), 0);
+	}
+	if let Some(hooks) = self.hooks() {
+	    hooks.resized(ptr, old_layout, new_ptr, new_layout);
 	}
+	new_ptr
+    }
+
+    // Shrinks a memory block.  The released tail is zeroed (if
+    // zero_on_free).
+    unsafe fn do_shrink(&self, ptr: *mut u8, old_layout: Layout,
+			new_layout: Layout) -> *mut u8 {
+	if self.zero_on_free {
+	    write_bytes(ptr.add(new_layout.size()), 0,
+			old_layout.size() - new_layout.size());
+	}
+	let new_ptr = self.lock().shrink(ptr, old_layout.size(),
+					 new_layout.size(), old_layout.align());
+	if !new_ptr.is_null() {
+	    self.count_requested(0, old_layout.size() - new_layout.size());
+	}
+
+	if let Some(hooks) = self.hooks() {
+	    hooks.resized(ptr, old_layout, new_ptr, new_layout);
+	}
+	new_ptr
     }
 }
 
@@ -72,19 +296,21 @@ where
     I: MuHeapIndex
 {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-	self.lock().alloc(layout.size(), layout.align())
+	self.do_alloc(layout)
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-	self.lock().dealloc(ptr, layout.size(), layout.align());
+	self.do_dealloc(ptr, layout);
     }
 
     unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize)
 		      -> *mut u8 {
+	let new_layout = Layout::from_size_align_unchecked(new_size,
+							   layout.align());
 	if new_size < layout.size() {
-	    self.lock().shrink(ptr, layout.size(), new_size, layout.align())
+	    self.do_shrink(ptr, layout, new_layout)
 	} else if new_size > layout.size() {
-	    self.lock().grow(ptr, layout.size(), new_size, layout.align())
+	    self.do_grow(ptr, layout, new_layout)
 	} else {
 	    ptr
 	}
@@ -101,35 +327,26 @@ where
 {
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
 	unsafe {
-	    let ptr = self.lock().alloc(layout.size(),
-					layout.align());
+	    let ptr = self.do_alloc(layout);
 	    alloc_result(ptr, layout.size())
 	}
     }
 
     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
-	self.lock().dealloc(ptr.as_ptr(),
-			    layout.size(),
-			    layout.align());
+	self.do_dealloc(ptr.as_ptr(), layout);
     }
 
     unsafe fn grow(&self, ptr: NonNull<u8>,
 		   old_layout: Layout, new_layout: Layout)
 		   -> Result<NonNull<[u8]>, AllocError> {
-	let ptr = self.lock().grow(ptr.as_ptr(),
-				   old_layout.size(),
-				   new_layout.size(),
-				   old_layout.align());
+	let ptr = self.do_grow(ptr.as_ptr(), old_layout, new_layout);
 	alloc_result(ptr, new_layout.size())
     }
 
     unsafe fn shrink(&self, ptr: NonNull<u8>,
 		     old_layout: Layout, new_layout: Layout)
 		     -> Result<NonNull<[u8]>, AllocError> {
-	let ptr = self.lock().shrink(ptr.as_ptr(),
-				     old_layout.size(),
-				     new_layout.size(),
-				     old_layout.align());
+	let ptr = self.do_shrink(ptr.as_ptr(), old_layout, new_layout);
 	alloc_result(ptr, new_layout.size())
     }
 }
@@ -144,3 +361,42 @@ unsafe fn alloc_result(ptr: *mut u8, size: usize)
 	Err(AllocError)
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec;
+
+    #[test]
+    fn zero_on_free_grow_by_move_keeps_blocks() {
+	let mut area = vec![0_u64; 128];
+	let alloc = unsafe {
+	    MuAlloc32::heap(area.as_mut_ptr() as usize, area.len() * 8)
+		.with_zero_on_free()
+	};
+	let alloc = &alloc;
+	let layout = Layout::from_size_align(64, 8).unwrap();
+
+	let a = alloc.allocate(layout).unwrap().cast::<u8>();
+	let b = alloc.allocate(layout).unwrap().cast::<u8>();
+	let _c = alloc.allocate(layout).unwrap();
+	let rest = alloc.lock().largest_allocatable(8);
+	let _d = alloc.allocate(Layout::from_size_align(rest, 8).unwrap())
+	    .unwrap();
+	unsafe {
+	    b.as_ptr().write_bytes(0x5a, 64);
+	    alloc.deallocate(a, layout);
+	}
+
+	// b cannot grow in place nor elsewhere, so it moves to a.
+	let new_layout = Layout::from_size_align(128, 8).unwrap();
+	let new_b = unsafe { alloc.grow(b, layout, new_layout) }.unwrap();
+	assert_eq!(new_b.cast::<u8>(), a);
+
+	let usage = alloc.lock().validate().unwrap();
+	assert_eq!(usage.inuse_count, 3);
+	let data = unsafe { new_b.as_ref() };
+	assert!(data[.. 64].iter().all(| &byte | byte == 0x5a));
+    }
+}
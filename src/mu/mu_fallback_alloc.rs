@@ -0,0 +1,186 @@
+//
+// Micro Fallback Alloc - An allocator trying one allocator, then falling
+// back to another on failure.
+//
+
+use core::{
+    alloc::{Allocator, AllocError, Layout},
+    ptr::{NonNull, copy_nonoverlapping},
+};
+
+use super::{MuAlloc, MuHeapIndex};
+
+
+///
+/// Tells whether a memory block was allocated by the allocator.
+///
+/// It is required of the primary allocator of [`MuFallbackAlloc`] in
+/// order to deallocate a block by the allocator which allocated it.
+///
+pub trait MuOwns {
+    /// Returns true if the block at `ptr` was allocated by `self`.
+    fn owns(&self, ptr: NonNull<u8>) -> bool;
+}
+
+impl<I> MuOwns for &MuAlloc<I>
+where
+    I: MuHeapIndex
+{
+    fn owns(&self, ptr: NonNull<u8>) -> bool {
+	self.lock().contains(ptr.as_ptr() as usize)
+    }
+}
+
+
+///
+/// Provides an allocator trying the `primary` allocator first, then
+/// the `fallback` allocator if it fails.
+///
+/// For example, buffers exchanged with BIOS can spill from the tiny
+/// 16-bit heap into the 20-bit heap (cf. `man_heap::ALLOC_UNDER16_OR_20`).
+/// A block is deallocated (or resized) by the allocator which allocated
+/// it.  A block growing out of the primary allocator is moved to the
+/// fallback allocator.
+///
+#[derive(Clone, Copy)]
+pub struct MuFallbackAlloc<A, B>
+where
+    A: Allocator + MuOwns,
+    B: Allocator,
+{
+    primary: A,
+    fallback: B,
+}
+
+impl<A, B> MuFallbackAlloc<A, B>
+where
+    A: Allocator + MuOwns,
+    B: Allocator,
+{
+    /// Returns an allocator with the primary and fallback allocators
+    /// (for a static declaration, too).
+    pub const fn new(primary: A, fallback: B) -> Self {
+	Self { primary, fallback }
+    }
+
+    /// Returns the primary allocator.
+    pub fn primary(&self) -> &A {
+	&self.primary
+    }
+
+    /// Returns the fallback allocator.
+    pub fn fallback(&self) -> &B {
+	&self.fallback
+    }
+
+    // Returns true if the block was allocated by the primary allocator.
+    // (Zero-sized blocks are not allocated by either of them.)
+    fn in_primary(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+	layout.size() == 0 || self.primary.owns(ptr)
+    }
+}
+
+// The fallback allocator owns what it allocates (to chain them).
+impl<A, B> MuOwns for MuFallbackAlloc<A, B>
+where
+    A: Allocator + MuOwns,
+    B: Allocator + MuOwns,
+{
+    fn owns(&self, ptr: NonNull<u8>) -> bool {
+	self.primary.owns(ptr) || self.fallback.owns(ptr)
+    }
+}
+
+
+//
+// An implementation of alloc::Allocator
+//
+unsafe impl<A, B> Allocator for MuFallbackAlloc<A, B>
+where
+    A: Allocator + MuOwns,
+    B: Allocator,
+{
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+	self.primary.allocate(layout)
+	    .or_else(| _ | self.fallback.allocate(layout))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+	if self.in_primary(ptr, layout) {
+	    self.primary.deallocate(ptr, layout);
+	} else {
+	    self.fallback.deallocate(ptr, layout);
+	}
+    }
+
+    unsafe fn grow(&self, ptr: NonNull<u8>,
+		   old_layout: Layout, new_layout: Layout)
+		   -> Result<NonNull<[u8]>, AllocError> {
+	if !self.in_primary(ptr, old_layout) {
+	    return self.fallback.grow(ptr, old_layout, new_layout);
+	}
+
+	if let Ok(new_ptr) = self.primary.grow(ptr, old_layout, new_layout) {
+	    return Ok(new_ptr);
+	}
+
+	// Move the block to the fallback allocator.
+	let new_ptr = self.fallback.allocate(new_layout)?;
+	copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr() as *mut u8,
+			    old_layout.size());
+	self.primary.deallocate(ptr, old_layout);
+	Ok(new_ptr)
+    }
+
+    unsafe fn shrink(&self, ptr: NonNull<u8>,
+		     old_layout: Layout, new_layout: Layout)
+		     -> Result<NonNull<[u8]>, AllocError> {
+	if self.in_primary(ptr, old_layout) {
+	    self.primary.shrink(ptr, old_layout, new_layout)
+	} else {
+	    self.fallback.shrink(ptr, old_layout, new_layout)
+	}
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mu::MuAlloc16;
+    use std::vec::Vec;
+
+    #[test]
+    fn spills_into_fallback() {
+	let mut area1 = vec![0_u8; 256];
+	let mut area2 = vec![0_u8; 4096];
+	let small = unsafe {
+	    MuAlloc16::heap(area1.as_mut_ptr() as usize, area1.len())
+	};
+	let large = unsafe {
+	    MuAlloc16::heap(area2.as_mut_ptr() as usize, area2.len())
+	};
+	let alloc = MuFallbackAlloc::new(&small, &large);
+
+	// Fill the primary heap, then spill into the fallback heap.
+	let mut v1: Vec<u8, _> = Vec::with_capacity_in(128, alloc);
+	let mut v2: Vec<u8, _> = Vec::with_capacity_in(128, alloc);
+	let in_small = | v: &Vec<u8, _> | {
+	    (&small).owns(NonNull::new(v.as_ptr() as *mut u8).unwrap())
+	};
+	assert!(in_small(&v1));
+	assert!(!in_small(&v2));
+
+	// Growing out of the primary heap moves the block.
+	v1.extend_from_slice(&[0x5a; 128]);
+	v1.extend_from_slice(&[0xa5; 512]);
+	assert!(!in_small(&v1));
+	assert_eq!((v1[127], v1[128]), (0x5a, 0xa5));
+
+	v2.push(1);
+	drop(v1);
+	drop(v2);
+	assert_eq!(small.lock().usage().inuse_count, 0);
+	assert_eq!(large.lock().usage().inuse_count, 0);
+    }
+}
@@ -7,14 +7,15 @@ use core::{
     fmt,
     mem::size_of,
     ops,
-    ptr::{copy_nonoverlapping, null_mut},
+    ptr::{copy, copy_nonoverlapping, null_mut},
     slice,
 };
 
+use super::mu_heap_track::{AllocRecord, HeapTracker};
 use crate::println;
 
 
-#[doc(hidden)] const DEBUG_HEAP: bool = false;
+#[doc(hidden)] const DEBUG_HEAP: bool = cfg!(test);  // Checked in unit tests
 #[doc(hidden)] const DEBUG_PRIOR_CHECK: bool = false;
 #[doc(hidden)] const DEBUG_POST_CHECK: bool = true;
 #[doc(hidden)] const DEBUG_CHECK_PTR: bool = true;
@@ -61,7 +62,12 @@ use crate::println;
 ///
 /// As described above, struct `HeapCell` has two signed integer
 /// fields: the `prev` and `next` fields.  From the practical point of
-/// view, `i16` or `i32` are useful as their types.
+/// view, `i8`, `i16`, `i32` or `i64` are useful as their types.
+///
+/// * If `i8` is chosen, the size of struct `HeapCell` is 2 bytes,
+///   and the maximum managable heap area size is 254 bytes (= 2 * 127).
+///   It suits a tiny dedicated pool (e.g. of Disk Address Packets),
+///   in which the overhead of 4-byte cells would be significant.
 ///
 /// * If `i16` is chosen, the size of struct `HeapCell` is 4 bytes,
 ///   and the maximum managable heap area size is 128KiB (= 4 * 2^15).
@@ -71,6 +77,11 @@ use crate::println;
 ///   (Needless to say, 16GiB space is too huge to manage with a
 ///   first-fit memory allocator)
 ///
+/// * If `i64` is chosen, the size of struct `HeapCell` is 16 bytes,
+///   and the size of a heap area is virtually unlimited.  It suits a
+///   machine with more than 16GiB of memory, at the cost of doubling
+///   the overhead per block.
+///
 /// In order to make `MuHeap` independent from the type of index,
 /// trait [`MuHeapIndex`] is defined.
 ///
@@ -91,6 +102,7 @@ where
     given_base: usize,	// Given Base Address of Heap Area (for debug)
     given_size: usize,	// Given Size in Bytes of Heap Area (for debug)
     stat: HeapStat,	// Statistics (for debug)
+    tracker: Option<HeapTracker>,	// Live allocations (if tracking)
 }
 
 
@@ -128,17 +140,25 @@ const MAX_ALIGNMENT: usize = 1024 / 8;	// 1024 bit (for now)
 
 
 //
-// Defines the minimum number of cells in heap area.
-// The number below is chosen because at least the 0-th cell must exist.
-// (Obviously, it is not a practical number)
+// Defines the minimum size in bytes of heap area.
+// The minimum number of cells scales with the type of index (e.g.,
+// 4 cells of i16, 1 cell of i64) because at least the 0-th cell must
+// exist.  (Obviously, it is not a practical size)
 //
-const MIN_NCELLS: usize = 1;
+const MIN_HEAP_SIZE: usize = 16;
 
 
 impl<I> MuHeap<I>
 where
     I: MuHeapIndex
 {
+    // The minimum number of cells in heap area (cf. MIN_HEAP_SIZE).
+    const MIN_NCELLS: usize = if MIN_HEAP_SIZE > Self::heapcell_size() {
+	MIN_HEAP_SIZE / Self::heapcell_size()
+    } else {
+	1
+    };
+
     // Returns all-zero initializer for a static heap declaration.
     const fn zero() -> Self {
 	Self {
@@ -148,6 +168,7 @@ where
 	    ncells: I::ZERO,
 	    search_start: I::ZERO,
 	    stat: HeapStat::zero(),
+	    tracker: None,
 	}
     }
 
@@ -182,6 +203,11 @@ where
 	self.build_heap();
     }
 
+    /// Returns true if the address is in the heap area.
+    pub fn contains(&self, addr: usize) -> bool {
+	addr >= self.given_base && addr - self.given_base < self.given_size
+    }
+
     /// Attempts to allocate a block of memory.
     pub unsafe fn alloc(&mut self, size: usize, align: usize) -> *mut u8 {
 	debug_assert!(self.given_base != 0 && self.given_size != 0);
@@ -192,15 +218,13 @@ where
 	    self.build_heap();
 	}
 
-	if DEBUG_HEAP {
-	    // Update statistics.
-	    self.stat.alloc_calls += 1;
-	    if self.stat.largest_size < size {
-		self.stat.largest_size = size;
-	    }
-	    if self.stat.largest_align < align {
-		self.stat.largest_align = align;
-	    }
+	// Update statistics.
+	self.stat.alloc_calls += 1;
+	if self.stat.largest_size < size {
+	    self.stat.largest_size = size;
+	}
+	if self.stat.largest_align < align {
+	    self.stat.largest_align = align;
 	}
 
 	if size == 0 {
@@ -209,7 +233,9 @@ where
 	    align as *mut u8
 	} else {
 	    // Allocate a new memory area.
-	    self.do_alloc(size, align)
+	    let ptr = self.do_alloc(size, align);
+	    self.track_alloc(ptr, size);
+	    ptr
 	}
     }
 
@@ -218,10 +244,8 @@ where
 	debug_assert!(self.given_base != 0 && self.given_size != 0 &&
 		      self.base != 0 && self.ncells != I::ZERO);
 
-	if DEBUG_HEAP {
-	    // Update statistics.
-	    self.stat.dealloc_calls += 1;
-	}
+	// Update statistics.
+	self.stat.dealloc_calls += 1;
 
 	if size == 0 {
 	    // For zero-sized allocation,
@@ -229,11 +253,17 @@ where
 	    debug_assert_eq!(ptr as usize, align);
 	} else {
 	    // Deallocate the memory area.
+	    self.track_dealloc(ptr);
 	    self.do_dealloc(ptr, size, align)
 	}
     }
 
     /// Attempts to extend the memory block.
+    ///
+    /// If it cannot be extended in place, it is moved to a new block
+    /// elsewhere, or (if no other free cells are large enough) towards
+    /// the free cells preceding it.  Returns null if all of them fail,
+    /// in which case the old block remains valid.
     pub unsafe fn grow(&mut self, old_ptr: *mut u8,
 		       old_size: usize, new_size: usize, align: usize)
 		       -> *mut u8 {
@@ -241,15 +271,13 @@ where
 		      self.base != 0 && self.ncells != I::ZERO);
 	debug_assert!(old_size <= new_size);
 
-	if DEBUG_HEAP {
-	    // Update statistics.
-	    self.stat.grow_calls += 1;
-	    if self.stat.largest_size < new_size {
-		self.stat.largest_size = new_size;
-	    }
-	    if self.stat.largest_align < align {
-		self.stat.largest_align = align;
-	    }
+	// Update statistics.
+	self.stat.grow_calls += 1;
+	if self.stat.largest_size < new_size {
+	    self.stat.largest_size = new_size;
+	}
+	if self.stat.largest_align < align {
+	    self.stat.largest_align = align;
 	}
 
 	if old_size == 0 {
@@ -257,10 +285,14 @@ where
 	    // alignment was returned without allocating memory.
 	    debug_assert_eq!(old_ptr as usize, align);
 	    // Allocate a new memory area.
-	    self.do_alloc(new_size, align)
+	    let new_ptr = self.do_alloc(new_size, align);
+	    self.track_alloc(new_ptr, new_size);
+	    new_ptr
 	} else {
 	    // Grow the memory area.
-	    self.do_grow(old_ptr, old_size, new_size, align)
+	    let new_ptr = self.do_grow(old_ptr, old_size, new_size, align);
+	    self.track_resize(old_ptr, new_ptr, new_size);
+	    new_ptr
 	}
     }
 
@@ -272,10 +304,8 @@ where
 		      self.base != 0 && self.ncells != I::ZERO);
 	debug_assert!(old_size >= new_size);
 
-	if DEBUG_HEAP {
-	    // Update statistics.
-	    self.stat.shrink_calls += 1;
-	}
+	// Update statistics.
+	self.stat.shrink_calls += 1;
 
 	if old_size == 0 {
 	    // For zero-sized allocation,
@@ -285,7 +315,268 @@ where
 	    ptr
 	} else {
 	    // Shrink the memory area.
-	    self.do_shrink(ptr, old_size, new_size, align)
+	    let new_ptr = self.do_shrink(ptr, old_size, new_size, align);
+	    self.track_resize(ptr, new_ptr, new_size);
+	    new_ptr
+	}
+    }
+
+    ///
+    /// Starts recording the address, the size and the tag (cf. method
+    /// [`set_alloc_tag`](Self::set_alloc_tag)) of each live allocation
+    /// in the table (e.g., a static array of [`AllocRecord::EMPTY`]).
+    ///
+    /// The blocks allocated before are not recorded, nor are those
+    /// allocated while the table is full.  Since the table is searched
+    /// linearly, it slows down every call.  Hence, it is meant for
+    /// debugging leaks (cf. method [`dump_leaks`](Self::dump_leaks)).
+    ///
+    pub fn set_tracking(&mut self, records: &'static mut [AllocRecord]) {
+	self.tracker = Some(HeapTracker::new(records));
+    }
+
+    /// Sets the tag recorded with the following allocations, then
+    /// returns the previous one.  It does nothing unless tracking.
+    pub fn set_alloc_tag(&mut self, tag: &'static str) -> &'static str {
+	match &mut self.tracker {
+	    Some(tracker) => tracker.set_tag(tag),
+	    None => "",
+	}
+    }
+
+    /// Calls `f` with each live allocation recorded (cf. method
+    /// [`set_tracking`](Self::set_tracking)).
+    pub fn for_each_tracked<F>(&self, f: F)
+    where
+	F: FnMut(&AllocRecord)
+    {
+	if let Some(tracker) = &self.tracker {
+	    tracker.records().for_each(f);
+	}
+    }
+
+    /// Prints all live allocations recorded (cf. method
+    /// [`set_tracking`](Self::set_tracking)).
+    pub fn dump_leaks(&self) {
+	let tracker = match &self.tracker {
+	    Some(tracker) => tracker,
+	    None => {
+		println!("heap: not tracking allocations");
+		return;
+	    },
+	};
+
+	let mut count = 0;
+	let mut bytes = 0;
+	for record in tracker.records() {
+	    println!("  {}", record);
+	    count += 1;
+	    bytes += record.size;
+	}
+	println!("heap: {} live blocks ({} bytes), {} not recorded",
+		 count, bytes, tracker.untracked());
+    }
+
+    ///
+    /// Checks the consistency of the heap, then returns its usage, or
+    /// the first inconsistency found (e.g., a broken link between
+    /// cells).
+    ///
+    /// Unlike the checks of the debug constants, it does not panic.
+    /// Hence, it can be called periodically to audit the heap.
+    ///
+    pub fn validate(&self) -> Result<HeapUsage, HeapCorruption> {
+	let mut usage = self.new_usage();
+	if self.base != 0 {
+	    self.check_list(None, &mut usage)?;
+	}
+	Ok(usage)
+    }
+
+    ///
+    /// Calls `f` with each block in the heap in address order, then
+    /// returns true if the end of the heap is reached.
+    ///
+    /// Unlike [`validate`](Self::validate), it does not check all links.
+    /// If a broken link is found, it stops walking and returns false.
+    ///
+    pub fn walk<F>(&self, mut f: F) -> bool
+    where
+	F: FnMut(HeapBlock)
+    {
+	if self.base == 0 {
+	    return true;
+	}
+
+	let cells = self.heapcells();
+	let cell_size = Self::heapcell_size();
+	let block = |cur_i: I, nxt_i: I, in_use| HeapBlock {
+	    addr: self.base + (cur_i + I::ONE).to_usize() * cell_size,
+	    size: (nxt_i - cur_i - I::ONE).to_usize() * cell_size,
+	    in_use,
+	};
+
+	let mut cur_i = I::ZERO;
+	loop {
+	    let next_val = cells[cur_i.to_usize()].next;
+	    let (nxt_i, in_use) = if next_val == I::ZERO {
+		if self.ncells - cur_i > I::ONE {
+		    f(block(cur_i, self.ncells, false));
+		}
+		return true;
+	    } else if next_val > I::ZERO {
+		(next_val, true)
+	    } else {
+		(!next_val, false)
+	    };
+
+	    if nxt_i <= cur_i || nxt_i >= self.ncells {
+		return false;
+	    }
+	    if nxt_i - cur_i > I::ONE {
+		f(block(cur_i, nxt_i, in_use));
+	    }
+	    cur_i = nxt_i;
+	}
+    }
+
+    ///
+    /// Returns the usage of the heap checked as method
+    /// [`validate`](Self::validate) does.  If an inconsistency is
+    /// found, it returns the blocks counted until then with
+    /// `consistent` false.
+    ///
+    pub fn usage(&self) -> HeapUsage {
+	let mut usage = self.new_usage();
+	if self.base != 0 {
+	    usage.consistent = self.check_list(None, &mut usage).is_ok();
+	}
+	usage
+    }
+
+    ///
+    /// Returns a histogram of free blocks by size (powers of two)
+    /// summarized by method `walk`.
+    ///
+    /// Comparing the largest free block with the total free bytes
+    /// tells whether the heap is full or merely fragmented.
+    ///
+    pub fn fragmentation(&self) -> HeapFragmentation {
+	let mut frag = HeapFragmentation::default();
+
+	frag.consistent = self.walk(|block| {
+	    if !block.in_use && block.size > 0 {
+		let bucket = block.size.ilog2() as usize;
+		frag.free_histogram[bucket] += 1;
+		frag.free_count += 1;
+		frag.free_bytes += block.size;
+		frag.free_largest = frag.free_largest.max(block.size);
+	    }
+	});
+
+	frag
+    }
+
+    ///
+    /// Returns the size in bytes of the largest block that can be
+    /// allocated with the alignment, which may be smaller than the
+    /// largest free block because of the alignment.
+    ///
+    /// It returns 0 if the heap is not built yet (i.e., before the
+    /// first allocation) or a broken link is found.
+    ///
+    pub fn largest_allocatable(&self, align: usize) -> usize {
+	if self.base == 0 || !align.is_power_of_two() {
+	    return 0;
+	}
+
+	let cells = self.heapcells();
+	let mut largest = I::ZERO;
+	let mut cur_i = I::ZERO;
+	loop {
+	    let next_val = cells[cur_i.to_usize()].next;
+	    // The number of cells for management after the data
+	    // (The last free block needs a new management cell).
+	    let (nxt_i, in_use, nmanage) = if next_val == I::ZERO {
+		(self.ncells, false, I::ONE + I::ONE)
+	    } else if next_val > I::ZERO {
+		(next_val, true, I::ONE)
+	    } else {
+		(!next_val, false, I::ONE)
+	    };
+
+	    if !in_use {
+		if let Some(bgn_i) = self.align_cell(cur_i, align) {
+		    let free_ncells = nxt_i - bgn_i - nmanage;
+		    if largest < free_ncells {
+			largest = free_ncells;
+		    }
+		}
+	    }
+
+	    if next_val == I::ZERO {
+		return largest.to_usize() * Self::heapcell_size();
+	    } else if nxt_i <= cur_i || nxt_i >= self.ncells {
+		return 0;
+	    }
+	    cur_i = nxt_i;
+	}
+    }
+
+    ///
+    /// Returns the statistics of the heap: the usage summarized by
+    /// method `walk`, the fragmentation and the numbers of calls.
+    ///
+    /// Unlike the consistency checks, the calls are always counted.
+    /// Hence, it can be used to monitor memory pressure at runtime.
+    ///
+    pub fn stats(&self) -> HeapStats {
+	HeapStats {
+	    usage: self.usage(),
+	    alloc_calls: self.stat.alloc_calls,
+	    dealloc_calls: self.stat.dealloc_calls,
+	    grow_calls: self.stat.grow_calls,
+	    shrink_calls: self.stat.shrink_calls,
+	    largest_request: self.stat.largest_size,
+	}
+    }
+
+    // Returns the end of the management cell following the data of a
+    // block in use (i.e., the end of the bytes owned by the heap).
+    pub(crate) fn block_end(ptr: *mut u8, size: usize) -> *mut u8 {
+	let ncells = Self::ncells_up(size).to_usize() + 1;
+	ptr.wrapping_add(ncells * Self::heapcell_size())
+    }
+
+    // Returns the statistics of calls (inuse_count only if DEBUG_HEAP).
+    pub(crate) fn stat(&self) -> &HeapStat {
+	&self.stat
+    }
+
+    // Records a block allocated (if tracking).
+    fn track_alloc(&mut self, ptr: *mut u8, size: usize) {
+	if let Some(tracker) = &mut self.tracker {
+	    if !ptr.is_null() {
+		tracker.insert(ptr as usize, size);
+	    }
+	}
+    }
+
+    // Forgets a block deallocated (if tracking).
+    fn track_dealloc(&mut self, ptr: *mut u8) {
+	if let Some(tracker) = &mut self.tracker {
+	    tracker.remove(ptr as usize);
+	}
+    }
+
+    // Updates a block resized (if tracking).  The old block remains
+    // if resizing failed.
+    fn track_resize(&mut self, old_ptr: *mut u8, new_ptr: *mut u8,
+		    new_size: usize) {
+	if let Some(tracker) = &mut self.tracker {
+	    if !new_ptr.is_null() {
+		tracker.resize(old_ptr as usize, new_ptr as usize, new_size);
+	    }
 	}
     }
 
@@ -308,37 +599,42 @@ where
 		// If next_val is negative, those cells between this
 		// cell and the next cell are free.
 		let nxt_i = !next_val;
-		let bgn_i = Self::align_cell(cur_i, align);
-		let free_ncells = nxt_i - bgn_i - I::ONE;
-		if free_ncells >= req_ncells {
-		    // Required size of memory can be allocated.
-		    let end_i = bgn_i + req_ncells + I::ONE;
-		    self.alloc_cells(cells, cur_i, bgn_i, end_i, nxt_i,
-				     Caller::Alloc);
-		    // Return the allocated address.
-		    return self.cell_to_ptr_checked(cells, bgn_i, size, align);
-		} else {
-		    // Required size of memory cannot be allocated.
-		    // Skip to the next cell.
-		    cur_i = nxt_i;
+		match self.align_cell(cur_i, align) {
+		    Some(bgn_i) if nxt_i - bgn_i - I::ONE >= req_ncells => {
+			// Required size of memory can be allocated.
+			let end_i = bgn_i + req_ncells + I::ONE;
+			self.alloc_cells(cells, cur_i, bgn_i, end_i, nxt_i,
+					 Caller::Alloc);
+			// Return the allocated address.
+			return self.cell_to_ptr_checked(cells, bgn_i,
+							size, align);
+		    },
+		    _ => {
+			// Required size of memory cannot be allocated.
+			// Skip to the next cell.
+			cur_i = nxt_i;
+		    },
 		}
 	    } else {
 		// If next_val is zero, those cells following this
 		// cell are free.
 		let nxt_i = self.ncells;
-		let bgn_i = Self::align_cell(cur_i, align);
-		let free_ncells = nxt_i - bgn_i - (I::ONE + I::ONE);
-		if free_ncells >= req_ncells {
-		    // Required size of memory can be allocated.
-		    let end_i = bgn_i + req_ncells + I::ONE;
-		    self.alloc_cells(cells, cur_i, bgn_i, end_i, nxt_i,
-				     Caller::Alloc);
-		    // Return the allocated address.
-		    return self.cell_to_ptr_checked(cells, bgn_i, size, align);
-		} else {
-		    // Required size of memory cannot be allocated.
-		    // Skip to the next cell.
-		    cur_i = I::ZERO;
+		match self.align_cell(cur_i, align) {
+		    Some(bgn_i)
+			if nxt_i - bgn_i - (I::ONE + I::ONE) >= req_ncells => {
+			// Required size of memory can be allocated.
+			let end_i = bgn_i + req_ncells + I::ONE;
+			self.alloc_cells(cells, cur_i, bgn_i, end_i, nxt_i,
+					 Caller::Alloc);
+			// Return the allocated address.
+			return self.cell_to_ptr_checked(cells, bgn_i,
+							size, align);
+		    },
+		    _ => {
+			// Required size of memory cannot be allocated.
+			// Skip to the next cell.
+			cur_i = I::ZERO;
+		    },
 		}
 	    }
 
@@ -394,15 +690,72 @@ where
 	    }
 	}
 
+	// Note: If allocation fails, the old memory block must remain
+	//       valid (cf. Allocator::grow).
 	let new_ptr = self.do_alloc(new_size, align);
 	if !new_ptr.is_null() {
 	    unsafe {
 		copy_nonoverlapping::<u8>(old_ptr, new_ptr, old_size);
 	    }
+	    self.do_dealloc(old_ptr, old_size, align);
+	    return new_ptr;
+	}
+
+	// Finally, try the free cells around the block (including the
+	// preceding ones) merged with the block itself.
+	self.grow_by_move(cells, cur_i, nxt_i, old_size, new_size, align)
+    }
+
+    //
+    // Moves the block towards the preceding free cells so that it can
+    // grow into the free cells merged around it.  It is tried only if
+    // no other free cells are large enough.
+    //
+    // Note: The old data are moved before the management cells of the
+    //       new block are written, because they may lie in the old
+    //       data cells.  Freeing the block does not touch its data.
+    //
+    fn grow_by_move(&mut self, cells: &mut [HeapCell<I>], cur_i: I, nxt_i: I,
+		    old_size: usize, new_size: usize, align: usize)
+		    -> *mut u8 {
+	// Find the head of preceding free cells.
+	let prev_val = cells[cur_i.to_usize()].prev;
+	let prev = if cur_i > I::ZERO && prev_val < I::ZERO {
+	    !prev_val
+	} else {
+	    cur_i
+	};
+
+	// Find the end of the free cells after the block (the number
+	// of cells if the free cells continue to the end).
+	let next_val = cells[nxt_i.to_usize()].next;
+	let (next, nmanage) = if next_val == I::ZERO {
+	    (self.ncells, I::ONE + I::ONE)
+	} else if next_val < I::ZERO {
+	    (!next_val, I::ONE)
+	} else {
+	    (nxt_i, I::ONE)
+	};
+
+	let req_ncells = Self::ncells_up(new_size);
+	let bgn_i = match self.align_cell(prev, align) {
+	    Some(bgn_i) if bgn_i <= cur_i => bgn_i,
+	    _ => return null_mut(),
+	};
+	if next - bgn_i - nmanage < req_ncells {
+	    return null_mut();
 	}
-	self.do_dealloc(old_ptr, old_size, align);
 
-	new_ptr
+	// Note: The block is freed and allocated again (as counted).
+	self.free_cells(cells, cur_i, nxt_i, Caller::Dealloc);
+	let new_ptr = Self::cell_to_ptr(cells, bgn_i);
+	unsafe {
+	    copy::<u8>(Self::cell_to_ptr(cells, cur_i), new_ptr, old_size);
+	}
+	let end_i = bgn_i + req_ncells + I::ONE;
+	self.alloc_cells(cells, prev, bgn_i, end_i, next, Caller::Alloc);
+
+	self.cell_to_ptr_checked(cells, bgn_i, new_size, align)
     }
 
     fn do_shrink(&mut self, ptr: *mut u8,
@@ -480,7 +833,7 @@ where
 	// Find the head of preceding free cells.
 	let mut prev = cur_i;
 	while prev > I::ZERO && cells[prev.to_usize()].prev < I::ZERO {
-	    prev = !cells[cur_i.to_usize()].prev;
+	    prev = !cells[prev.to_usize()].prev;
 	}
 
 	// Find the tail of succeeding free cells.
@@ -557,6 +910,12 @@ where
 				      Self::heapcell_size());
 	let min_base = min_addr - Self::heapcell_size();
 
+	// Align the base to the size of HeapCell
+	// (e.g., a heap area backed by Vec<u8> in unit tests).
+	let aligned_base = Self::round_up(given_base, Self::heapcell_size());
+	let (given_base, given_size) =
+	    (aligned_base, given_size.saturating_sub(aligned_base - given_base));
+
 	// Adjust the base and the size allocatable.
 	let (mut adj_base, mut adj_size) = (given_base, given_size);
 	if given_base < min_base {
@@ -575,7 +934,7 @@ where
 
 	// Check the number of usable cells.
 	adj_size = adj_ncells.to_usize() * Self::heapcell_size();
-	assert!(adj_ncells >= I::from_usize(MIN_NCELLS),
+	assert!(adj_ncells >= I::from_usize(Self::MIN_NCELLS),
 		"Given heap is too small: \
 		 given=({:#x}, {:#x}), adjusted=({:#x}, {:#x} (#{:#x}))",
 		given_base, given_size, adj_base, adj_size, adj_ncells);
@@ -663,13 +1022,23 @@ where
 	I::from_usize(min(r, I::MAX_USIZE))
     }
 
+    // Returns the index of the management cell before the aligned
+    // data cells, or None if they are beyond the heap (the index may
+    // not fit in I, e.g., a large alignment in a heap of i8).
+    //
+    // Note: The address (not the offset from the base) is aligned
+    //       because the base may not be aligned to `align`.
     #[inline]
-    fn align_cell(cur_i: I, align: usize) -> I {
+    fn align_cell(&self, cur_i: I, align: usize) -> Option<I> {
 	let cur_mem_i = cur_i + I::ONE;
 	let cur_mem_off = cur_mem_i.to_usize() * Self::heapcell_size();
-	let ali_mem_off = Self::round_up(cur_mem_off, align);
-	let ali_mem_i = I::from_usize(ali_mem_off / Self::heapcell_size());
-	ali_mem_i - I::ONE
+	let cur_mem_addr = self.base + cur_mem_off;
+	let ali_mem_off = Self::round_up(cur_mem_addr, align) - self.base;
+	let ali_mem_i = ali_mem_off / Self::heapcell_size();
+	if ali_mem_i >= self.ncells.to_usize() {
+	    return None;
+	}
+	Some(I::from_usize(ali_mem_i) - I::ONE)
     }
 
     #[inline]
@@ -683,51 +1052,111 @@ where
     I: MuHeapIndex
 {
     fn debug_check_list(&self, check_index: I, _caller: Caller)
-			-> HeapFigures<I> {
+			-> HeapUsage {
+	let mut usage = self.new_usage();
+	self.check_list(Some(check_index), &mut usage)
+	    .unwrap_or_else(| err | panic!("{}", err));
+	assert_eq!(usage.inuse_count, self.stat.inuse_count);
+	usage
+    }
+
+    // Returns the usage of the heap before its blocks are counted.
+    fn new_usage(&self) -> HeapUsage {
+	HeapUsage {
+	    base: self.base,
+	    size: self.ncells.to_usize() * Self::heapcell_size(),
+	    consistent: true,
+	    ..HeapUsage::default()
+	}
+    }
+
+    // Checks the consistency of the cell list while counting the
+    // blocks in `usage`.  Returns the first inconsistency found (if
+    // any).
+    fn check_list(&self, check_index: Option<I>, usage: &mut HeapUsage)
+		  -> Result<(), HeapCorruption> {
 	let cells = self.heapcells();
+	let cell_size = Self::heapcell_size();
 	let search_start = self.search_start;
 	let mut search_start_found = false;
-	let mut check_index_found = false;
-	let mut figures = HeapFigures::<I>::zero();
+	let mut check_index_found = check_index.is_none();
+
+	let link = | index: I, expected: I, actual: I | {
+	    HeapCorruption::BackLink {
+		index: index.to_usize(),
+		expected: expected.to_isize(),
+		actual: actual.to_isize(),
+	    }
+	};
 
 	let mut cur_i = I::ZERO;
 	loop {
 	    if cur_i == search_start {
 		search_start_found = true;
 	    }
-	    if cur_i == check_index {
+	    if Some(cur_i) == check_index {
 		check_index_found = true;
 	    }
 	    let next_val = cells[cur_i.to_usize()].next;
-	    let nxt_i;
-	    if next_val > I::ZERO {
-		nxt_i = next_val;
-		let cur_ncells = nxt_i - cur_i - I::ONE;
-		figures.add_inuse(cur_ncells);
-		assert!(nxt_i < self.ncells);
-		assert_eq!(cells[nxt_i.to_usize()].prev, cur_i);
-		assert!(cur_ncells > I::ZERO);
-	    } else if next_val < I::ZERO {
-		nxt_i = !next_val;
-		let cur_ncells = nxt_i - cur_i - I::ONE;
-		figures.add_free(cur_ncells);
-		assert!(nxt_i < self.ncells);
-		assert_eq!(cells[nxt_i.to_usize()].prev, !cur_i);
-		assert!(cells[nxt_i.to_usize()].next >= I::ZERO);
-	    } else { // next_val == I::ZERO
+	    if next_val == I::ZERO {
 		let cur_ncells = self.ncells - cur_i - I::ONE;
-		figures.add_free(cur_ncells);
-		assert!(cells[cur_i.to_usize()].prev >= I::ZERO);
+		usage.add_free(cur_ncells.to_usize() * cell_size);
+		if cells[cur_i.to_usize()].prev < I::ZERO {
+		    return Err(HeapCorruption::AdjacentFree {
+			index: cur_i.to_usize(),
+		    });
+		}
 		break;
 	    }
+
+	    let nxt_i = if next_val > I::ZERO { next_val } else { !next_val };
+	    if nxt_i <= cur_i || nxt_i >= self.ncells {
+		return Err(HeapCorruption::LinkOutOfRange {
+		    index: cur_i.to_usize(),
+		    next: next_val.to_isize(),
+		});
+	    }
+	    let cur_ncells = nxt_i - cur_i - I::ONE;
+	    let nxt_cell = &cells[nxt_i.to_usize()];
+	    if next_val > I::ZERO {
+		usage.add_inuse(cur_ncells.to_usize() * cell_size);
+		if nxt_cell.prev != cur_i {
+		    return Err(link(nxt_i, cur_i, nxt_cell.prev));
+		}
+		if cur_ncells == I::ZERO {
+		    return Err(HeapCorruption::EmptyBlock {
+			index: cur_i.to_usize(),
+		    });
+		}
+	    } else {
+		usage.add_free(cur_ncells.to_usize() * cell_size);
+		if nxt_cell.prev != !cur_i {
+		    return Err(link(nxt_i, !cur_i, nxt_cell.prev));
+		}
+		if nxt_cell.next < I::ZERO {
+		    return Err(HeapCorruption::AdjacentFree {
+			index: nxt_i.to_usize(),
+		    });
+		}
+	    }
 	    cur_i = nxt_i;
 	}
 
-	assert!(search_start_found && check_index_found);
-	assert_eq!(figures.inuse_count.to_usize(), self.stat.inuse_count);
-	assert_eq!(cells[0].prev, I::ZERO);
+	if cells[0].prev != I::ZERO {
+	    return Err(link(I::ZERO, I::ZERO, cells[0].prev));
+	}
+	if !search_start_found {
+	    return Err(HeapCorruption::UnlinkedIndex {
+		index: search_start.to_usize(),
+	    });
+	}
+	if let Some(index) = check_index.filter(| _ | !check_index_found) {
+	    return Err(HeapCorruption::UnlinkedIndex {
+		index: index.to_usize(),
+	    });
+	}
 
-	figures
+	Ok(())
     }
 
     fn debug_check_ptr(&self, ptr: *mut u8, cur_i: I,
@@ -776,8 +1205,145 @@ where
 }
 
 
+/// A block of memory in a heap (cf. method [`MuHeap::walk`]).
+#[derive(Clone, Copy, Debug)]
+pub struct HeapBlock {
+    pub addr: usize,	// Address of the data
+    pub size: usize,	// Size in bytes of the data
+    pub in_use: bool,	// true if in use, false if free
+}
+
+///
+/// The usage of a heap (cf. methods [`MuHeap::usage`] and
+/// [`MuHeap::validate`]).
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeapUsage {
+    pub base: usize,		// Base address of the heap
+    pub size: usize,		// Size in bytes of the heap
+    pub inuse_count: usize,	// Number of blocks in use
+    pub inuse_bytes: usize,	// Total size in bytes of blocks in use
+    pub free_count: usize,	// Number of free blocks
+    pub free_bytes: usize,	// Total size in bytes of free blocks
+    pub free_largest: usize,	// Size in bytes of the largest free block
+    pub consistent: bool,	// false if an inconsistency is found
+}
+
+impl HeapUsage {
+    fn add_inuse(&mut self, nbytes: usize) {
+	self.inuse_count += 1;
+	self.inuse_bytes += nbytes;
+    }
+
+    fn add_free(&mut self, nbytes: usize) {
+	if nbytes > 0 {
+	    self.free_count += 1;
+	    self.free_bytes += nbytes;
+	    self.free_largest = self.free_largest.max(nbytes);
+	}
+    }
+
+    /// Returns the percentage of free bytes outside the largest free
+    /// block (cf. method [`HeapFragmentation::percent`]).
+    pub fn fragmentation_percent(&self) -> usize {
+	fragmentation_percent(self.free_bytes, self.free_largest)
+    }
+}
+
+impl fmt::Display for HeapUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "heap={:#x}+{:#x}: in use={} ({} bytes), \
+		   free={} ({} bytes, largest={}){}",
+	       self.base, self.size,
+	       self.inuse_count, self.inuse_bytes,
+	       self.free_count, self.free_bytes, self.free_largest,
+	       if self.consistent { "" } else { ", BROKEN" })
+    }
+}
+
+
+/// The statistics of a heap (cf. method [`MuHeap::stats`]).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeapStats {
+    pub usage: HeapUsage,	// Usage of the heap
+    pub alloc_calls: usize,	// Number of calls of method alloc
+    pub dealloc_calls: usize,	// Number of calls of method dealloc
+    pub grow_calls: usize,	// Number of calls of method grow
+    pub shrink_calls: usize,	// Number of calls of method shrink
+    pub largest_request: usize,	// Largest size in bytes requested
+}
+
+impl fmt::Display for HeapStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{}, fragmentation={}%, \
+		   calls: alloc={} dealloc={} grow={} shrink={}",
+	       self.usage, self.usage.fragmentation_percent(),
+	       self.alloc_calls, self.dealloc_calls, self.grow_calls,
+	       self.shrink_calls)
+    }
+}
+
+
+/// The number of buckets in [`HeapFragmentation::free_histogram`].
+pub const FRAG_BUCKETS: usize = usize::BITS as usize;
+
+/// Free blocks of a heap by size (cf. method [`MuHeap::fragmentation`]).
+#[derive(Clone, Copy, Debug)]
+pub struct HeapFragmentation {
+    pub free_histogram: [usize; FRAG_BUCKETS],	// [n]: 2^n <= size < 2^(n+1)
+    pub free_count: usize,	// Number of free blocks
+    pub free_bytes: usize,	// Total size in bytes of free blocks
+    pub free_largest: usize,	// Size in bytes of the largest free block
+    pub consistent: bool,	// false if a broken link is found
+}
+
+impl HeapFragmentation {
+    ///
+    /// Returns the percentage of free bytes outside the largest free
+    /// block (0 if all free bytes are in a block).
+    ///
+    pub fn percent(&self) -> usize {
+	fragmentation_percent(self.free_bytes, self.free_largest)
+    }
+}
+
+// Returns the percentage of free bytes outside the largest free block.
+fn fragmentation_percent(free_bytes: usize, free_largest: usize) -> usize {
+    ((free_bytes - free_largest) * 100)
+	.checked_div(free_bytes)
+	.unwrap_or(0)
+}
+
+impl Default for HeapFragmentation {
+    fn default() -> Self {
+	Self {
+	    free_histogram: [0; FRAG_BUCKETS],
+	    free_count: 0,
+	    free_bytes: 0,
+	    free_largest: 0,
+	    consistent: true,
+	}
+    }
+}
+
+impl fmt::Display for HeapFragmentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "free={} ({} bytes, largest={}), fragmentation={}%{}",
+	       self.free_count, self.free_bytes, self.free_largest,
+	       self.percent(),
+	       if self.consistent { "" } else { ", BROKEN" })?;
+	for (n, &count) in self.free_histogram.iter().enumerate() {
+	    if count != 0 {
+		write!(f, "\r\n  >= {:>10} bytes: {}", 1_usize << n, count)?;
+	    }
+	}
+	Ok(())
+    }
+}
+
+
 #[derive(Debug)]
-struct HeapStat
+pub(crate) struct HeapStat
 {
     alloc_calls: usize,
     dealloc_calls: usize,
@@ -803,44 +1369,43 @@ impl HeapStat {
 }
 
 
-#[derive(Debug)]
-struct HeapFigures<I>
-where
-    I: MuHeapIndex
-{
-    inuse_count: I,
-    inuse_ncells: I,
-    free_count: I,
-    free_ncells: I,
-    free_largest: I,
+///
+/// Inconsistencies of the cell list found by method
+/// [`MuHeap::validate`].  The indexes are those of the management
+/// cells, and the links are their `prev` or `next` fields (negative
+/// if the block between the cells is free).
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeapCorruption {
+    /// The `next` link of the cell points backwards or out of the heap.
+    LinkOutOfRange { index: usize, next: isize },
+    /// The `prev` link of the cell does not point back.
+    BackLink { index: usize, expected: isize, actual: isize },
+    /// The block in use after the cell is empty.
+    EmptyBlock { index: usize },
+    /// The free block before the cell is followed by another free
+    /// block (they must have been merged).
+    AdjacentFree { index: usize },
+    /// The index (e.g., where the next search starts) is not a cell
+    /// of the list.
+    UnlinkedIndex { index: usize },
 }
 
-impl<I> HeapFigures<I>
-where
-    I: MuHeapIndex
-{
-    fn zero() -> Self {
-	Self {
-	    inuse_count: I::ZERO,
-	    inuse_ncells: I::ZERO,
-	    free_count: I::ZERO,
-	    free_ncells: I::ZERO,
-	    free_largest: I::ZERO,
-	}
-    }
-
-    fn add_inuse(&mut self, ncells: I) {
-	self.inuse_count += I::ONE;
-	self.inuse_ncells += ncells;
-    }
-
-    fn add_free(&mut self, ncells: I) {
-	if ncells > I::ZERO {
-	    self.free_count += I::ONE;
-	    self.free_ncells += ncells;
-	    if self.free_largest < ncells {
-		self.free_largest = ncells;
-	    }
+impl fmt::Display for HeapCorruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::LinkOutOfRange { index, next } =>
+		write!(f, "heap: cell {}: next={} is out of range",
+		       index, next),
+	    Self::BackLink { index, expected, actual } =>
+		write!(f, "heap: cell {}: prev={} (expected {})",
+		       index, actual, expected),
+	    Self::EmptyBlock { index } =>
+		write!(f, "heap: cell {}: empty block in use", index),
+	    Self::AdjacentFree { index } =>
+		write!(f, "heap: cell {}: adjacent free blocks", index),
+	    Self::UnlinkedIndex { index } =>
+		write!(f, "heap: cell {}: not in the cell list", index),
 	}
     }
 }
@@ -848,7 +1413,8 @@ where
 
 /// A trait that the types of indexes in heap cells must satisfy.
 ///
-/// From the practical point of view, `i16` or `i32` are useful.
+/// From the practical point of view, `i8`, `i16`, `i32` or `i64` are
+/// useful.
 pub trait MuHeapIndex
 where
     Self: 'static + Copy + PartialOrd
@@ -867,6 +1433,32 @@ where
     fn from_usize(n: usize) -> Self;
     /// Converts a value from Self into usize.
     fn to_usize(&self) -> usize;
+
+    /// Converts self into isize.
+    fn to_isize(&self) -> isize {
+	// Negative values are ones' complements (!n = -n - 1).
+	if *self < Self::ZERO {
+	    -((!*self).to_usize() as isize) - 1
+	} else {
+	    self.to_usize() as isize
+	}
+    }
+}
+
+impl MuHeapIndex for i8 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+    const MAX_USIZE: usize = Self::MAX as usize;
+
+    #[inline]
+    fn from_usize(n: usize) -> Self {
+	n as Self
+    }
+
+    #[inline]
+    fn to_usize(&self) -> usize {
+	*self as usize
+    }
 }
 
 impl MuHeapIndex for i16 {
@@ -900,3 +1492,470 @@ impl MuHeapIndex for i32 {
 	*self as usize
     }
 }
+
+impl MuHeapIndex for i64 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+    const MAX_USIZE: usize = Self::MAX as usize;
+
+    #[inline]
+    fn from_usize(n: usize) -> Self {
+	n as Self
+    }
+
+    #[inline]
+    fn to_usize(&self) -> usize {
+	*self as usize
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    // A heap area backed by Vec<u8> (intentionally misaligned by one).
+    struct TestArea {
+	buf: Vec<u8>,
+    }
+
+    impl TestArea {
+	fn new(size: usize) -> Self {
+	    Self { buf: vec![0; size + 1] }
+	}
+
+	fn heap<I: MuHeapIndex>(&mut self) -> MuHeap<I> {
+	    let mut heap = MuHeap::noheap();
+	    unsafe {
+		heap.set_heap(self.buf.as_mut_ptr() as usize + 1,
+			      self.buf.len() - 1);
+	    }
+	    heap
+	}
+    }
+
+    fn checked_usage<I: MuHeapIndex>(heap: &MuHeap<I>) -> HeapUsage {
+	heap.debug_check_list(I::ZERO, Caller::Alloc)
+    }
+
+    #[test]
+    fn alloc_returns_aligned_pointers() {
+	check_aligned_pointers::<i32>();
+	check_aligned_pointers::<i64>();
+    }
+
+    fn check_aligned_pointers<I: MuHeapIndex>() {
+	let mut area = TestArea::new(64 * 1024);
+	let mut heap = area.heap::<I>();
+
+	let mut ptrs = Vec::new();
+	for align in [1, 2, 4, 8, 16, 32, 64, 128] {
+	    for size in [1, 7, 8, 9, 100] {
+		let ptr = unsafe { heap.alloc(size, align) };
+		assert!(!ptr.is_null());
+		assert_eq!(ptr as usize % align, 0);
+		ptrs.push((ptr, size, align));
+	    }
+	}
+
+	for (ptr, size, align) in ptrs {
+	    unsafe { heap.dealloc(ptr, size, align) };
+	}
+	assert_eq!(checked_usage(&heap).inuse_count, 0);
+    }
+
+    #[test]
+    fn zero_sized_alloc_returns_alignment() {
+	let mut area = TestArea::new(1024);
+	let mut heap = area.heap::<i16>();
+
+	let ptr = unsafe { heap.alloc(0, 16) };
+	assert_eq!(ptr as usize, 16);
+	unsafe { heap.dealloc(ptr, 0, 16) };
+    }
+
+    #[test]
+    fn dealloc_coalesces_neighbors() {
+	let mut area = TestArea::new(4 * 1024);
+	let mut heap = area.heap::<i16>();
+	let initial = checked_usage(&heap).free_largest;
+
+	let a = unsafe { heap.alloc(64, 8) };
+	let b = unsafe { heap.alloc(64, 8) };
+	let c = unsafe { heap.alloc(64, 8) };
+	let d = unsafe { heap.alloc(64, 8) };
+
+	// Free b and c, then a block of their total size fits there.
+	unsafe {
+	    heap.dealloc(b, 64, 8);
+	    heap.dealloc(c, 64, 8);
+	}
+	let f = checked_usage(&heap);
+	assert_eq!(f.inuse_count, 2);
+	assert_eq!(f.free_count, 2);	// (b, c) and the tail
+
+	// Free a and d, then the whole area is free again.
+	unsafe {
+	    heap.dealloc(a, 64, 8);
+	    heap.dealloc(d, 64, 8);
+	}
+	let f = checked_usage(&heap);
+	assert_eq!(f.inuse_count, 0);
+	assert_eq!(f.free_count, 1);
+	assert_eq!(f.free_largest, initial);
+    }
+
+    #[test]
+    fn dealloc_coalesces_free_predecessors() {
+	let mut area = TestArea::new(4 * 1024);
+	let mut heap = area.heap::<i16>();
+
+	let blocks: Vec<_> = (0..5)
+	    .map(| _ | unsafe { heap.alloc(64, 8) })
+	    .collect();
+
+	// Free the first three in order; each merges with the free
+	// cells preceding it.
+	for &ptr in &blocks[..3] {
+	    unsafe { heap.dealloc(ptr, 64, 8) };
+	}
+	let f = heap.validate().unwrap();
+	assert_eq!(f.inuse_count, 2);
+	assert_eq!(f.free_count, 2);	// The first three and the tail
+
+	// The fourth merges with all of them.
+	unsafe { heap.dealloc(blocks[3], 64, 8) };
+	let f = heap.validate().unwrap();
+	assert_eq!(f.inuse_count, 1);
+	assert_eq!(f.free_count, 2);
+	assert!(f.free_largest >= 4 * 64);
+
+	unsafe { heap.dealloc(blocks[4], 64, 8) };
+	assert_eq!(heap.validate().unwrap().free_count, 1);
+    }
+
+    #[test]
+    fn stats_count_calls_and_bytes() {
+	let mut area = TestArea::new(4 * 1024);
+	let mut heap = area.heap::<i16>();
+
+	let a = unsafe { heap.alloc(64, 8) };
+	let b = unsafe { heap.alloc(128, 8) };
+	let b = unsafe { heap.grow(b, 128, 256, 8) };
+	unsafe { heap.dealloc(a, 64, 8) };
+
+	let stats = heap.stats();
+	assert!(stats.usage.consistent);
+	assert_eq!((stats.alloc_calls, stats.dealloc_calls), (2, 1));
+	assert_eq!((stats.grow_calls, stats.shrink_calls), (1, 0));
+	assert_eq!(stats.largest_request, 256);
+	assert_eq!(stats.usage.inuse_count, 1);
+	assert_eq!(stats.usage.inuse_bytes, 256);
+	assert_eq!(stats.usage.free_bytes, checked_usage(&heap).free_bytes);
+
+	unsafe { heap.dealloc(b, 256, 8) };
+	assert_eq!(heap.stats().usage.fragmentation_percent(), 0);
+    }
+
+    #[test]
+    fn alloc_fails_when_exhausted() {
+	let mut area = TestArea::new(1024);
+	let mut heap = area.heap::<i16>();
+
+	let mut ptrs = Vec::new();
+	loop {
+	    let ptr = unsafe { heap.alloc(100, 4) };
+	    if ptr.is_null() {
+		break;
+	    }
+	    ptrs.push(ptr);
+	}
+	assert!(!ptrs.is_empty());
+
+	// After freeing a block, allocation succeeds again.
+	let ptr = ptrs.pop().unwrap();
+	unsafe { heap.dealloc(ptr, 100, 4) };
+	let ptr = unsafe { heap.alloc(100, 4) };
+	assert!(!ptr.is_null());
+	ptrs.push(ptr);
+
+	for ptr in ptrs {
+	    unsafe { heap.dealloc(ptr, 100, 4) };
+	}
+	assert_eq!(checked_usage(&heap).inuse_count, 0);
+    }
+
+    #[test]
+    fn tiny_pool_with_i8_index() {
+	// Only 127 cells (254 bytes) of the area are managed.
+	let mut area = TestArea::new(512);
+	let mut heap = area.heap::<i8>();
+	assert!(heap.largest_allocatable(1) < 254);
+
+	// Disk Address Packets (16 bytes = 8 cells, plus a management cell)
+	let mut ptrs = Vec::new();
+	loop {
+	    let ptr = unsafe { heap.alloc(16, 2) };
+	    if ptr.is_null() {
+		break;
+	    }
+	    ptrs.push(ptr);
+	}
+	assert_eq!(ptrs.len(), 127 / 9);
+
+	for ptr in ptrs {
+	    unsafe { heap.dealloc(ptr, 16, 2) };
+	}
+	let f = checked_usage(&heap);
+	assert_eq!(f.inuse_count, 0);
+	assert_eq!(f.free_count, 1);
+    }
+
+    #[test]
+    fn alignment_beyond_i8_heap_fails() {
+	// A 480-byte area starting 16 bytes after a 4KiB boundary, so
+	// that no 512-byte boundary lies in the managed 254 bytes.
+	let mut buf = vec![0_u8; 8 * 1024];
+	let addr = buf.as_mut_ptr() as usize;
+	let base = (addr + 4095) / 4096 * 4096 + 16;
+	let mut heap = MuHeap::<i8>::noheap();
+	unsafe { heap.set_heap(base, 480) };
+
+	let ptr = unsafe { heap.alloc(8, 256) };
+	assert!(!ptr.is_null());
+	for align in [512, 4096] {
+	    assert!(unsafe { heap.alloc(8, align) }.is_null());
+	    assert_eq!(heap.largest_allocatable(align), 0);
+	}
+	unsafe { heap.dealloc(ptr, 8, 256) };
+	assert_eq!(heap.validate().unwrap().inuse_count, 0);
+    }
+
+    #[test]
+    fn grow_preserves_contents() {
+	let mut area = TestArea::new(4 * 1024);
+	let mut heap = area.heap::<i32>();
+
+	let ptr = unsafe { heap.alloc(16, 8) };
+	let blocker = unsafe { heap.alloc(16, 8) };
+	unsafe {
+	    for i in 0 .. 16 {
+		*ptr.add(i) = i as u8;
+	    }
+	}
+
+	// The next block is in use.  Hence, the block is moved.
+	let new_ptr = unsafe { heap.grow(ptr, 16, 256, 8) };
+	assert!(!new_ptr.is_null());
+	assert_ne!(new_ptr, ptr);
+	for i in 0 .. 16 {
+	    assert_eq!(unsafe { *new_ptr.add(i) }, i as u8);
+	}
+
+	unsafe {
+	    heap.dealloc(blocker, 16, 8);
+	    heap.dealloc(new_ptr, 256, 8);
+	}
+	assert_eq!(checked_usage(&heap).inuse_count, 0);
+    }
+
+    #[test]
+    fn failed_grow_keeps_the_old_block() {
+	let mut area = TestArea::new(1024);
+	let mut heap = area.heap::<i16>();
+
+	let a = unsafe { heap.alloc(64, 8) };
+	let b = unsafe { heap.alloc(64, 8) };
+	for i in 0..64 {
+	    unsafe { a.add(i).write(i as u8) };
+	}
+
+	// Neither in place, elsewhere nor by moving.
+	let new_a = unsafe { heap.grow(a, 64, 2048, 8) };
+	assert!(new_a.is_null());
+
+	assert_eq!(heap.validate().unwrap().inuse_count, 2);
+	for i in 0..64 {
+	    assert_eq!(unsafe { a.add(i).read() }, i as u8);
+	}
+	unsafe {
+	    heap.dealloc(a, 64, 8);
+	    heap.dealloc(b, 64, 8);
+	}
+	assert_eq!(heap.validate().unwrap().inuse_count, 0);
+    }
+
+    #[test]
+    fn grow_by_moving_into_free_predecessor() {
+	let mut area = TestArea::new(4 * 1024);
+	let mut heap = area.heap::<i32>();
+
+	let a = unsafe { heap.alloc(64, 8) };
+	let b = unsafe { heap.alloc(64, 8) };
+	let c = unsafe { heap.alloc(64, 8) };
+	let rest = heap.largest_allocatable(8);
+	let d = unsafe { heap.alloc(rest, 8) };
+	unsafe {
+	    heap.dealloc(a, 64, 8);
+	    for i in 0 .. 64 {
+		*b.add(i) = i as u8;
+	    }
+	}
+
+	// Neither in place nor elsewhere, but merged with the free
+	// cells of `a`.  Hence, moved towards them.
+	assert!(heap.largest_allocatable(8) < 128);
+	let new_b = unsafe { heap.grow(b, 64, 128, 8) };
+	assert_eq!(new_b, a);
+	for i in 0 .. 64 {
+	    assert_eq!(unsafe { *new_b.add(i) }, i as u8);
+	}
+
+	// Too large even if merged.
+	assert!(unsafe { heap.grow(new_b, 128, 256, 8) }.is_null());
+
+	unsafe {
+	    heap.dealloc(new_b, 128, 8);
+	    heap.dealloc(c, 64, 8);
+	    heap.dealloc(d, rest, 8);
+	}
+	assert_eq!(checked_usage(&heap).inuse_count, 0);
+    }
+
+    #[test]
+    fn grow_in_place_into_free_neighbor() {
+	let mut area = TestArea::new(4 * 1024);
+	let mut heap = area.heap::<i32>();
+
+	let a = unsafe { heap.alloc(16, 8) };
+	let b = unsafe { heap.alloc(64, 8) };
+	let c = unsafe { heap.alloc(16, 8) };
+	unsafe { heap.dealloc(b, 64, 8) };
+
+	// The freed neighbor is large enough.  Hence, grown in place.
+	let new_a = unsafe { heap.grow(a, 16, 64, 8) };
+	assert_eq!(new_a, a);
+
+	unsafe {
+	    heap.dealloc(new_a, 64, 8);
+	    heap.dealloc(c, 16, 8);
+	}
+	assert_eq!(checked_usage(&heap).inuse_count, 0);
+    }
+
+    #[test]
+    fn validate_reports_broken_links() {
+	let mut area = TestArea::new(4 * 1024);
+	let mut heap = area.heap::<i16>();
+
+	let a = unsafe { heap.alloc(64, 8) };
+	let usage = heap.validate().unwrap();
+	assert_eq!(usage.inuse_count, 1);
+	assert!(usage.inuse_bytes >= 64);
+
+	// Break the back link of the cell after a.
+	let cells = heap.heapcells();
+	let next = cells[0].next;
+	cells[next as usize].prev = 5;
+	assert_eq!(heap.validate().unwrap_err(), HeapCorruption::BackLink {
+	    index: next as usize, expected: 0, actual: 5,
+	});
+	assert!(!heap.usage().consistent);
+
+	cells[next as usize].prev = 0;
+	unsafe { heap.dealloc(a, 64, 8) };
+	assert_eq!(heap.validate().unwrap().inuse_count, 0);
+    }
+
+    #[test]
+    fn tracking_records_live_blocks() {
+	let mut area = TestArea::new(4 * 1024);
+	let mut heap = area.heap::<i16>();
+
+	let records = vec![AllocRecord::EMPTY; 2].leak();
+	heap.set_tracking(records);
+	heap.set_alloc_tag("a");
+	let a = unsafe { heap.alloc(16, 8) };
+	assert_eq!(heap.set_alloc_tag("b"), "a");
+	let b = unsafe { heap.alloc(32, 8) };
+	let c = unsafe { heap.alloc(8, 8) };	// Not recorded (full)
+
+	// The tag of a is kept by growing.
+	let a = unsafe { heap.grow(a, 16, 64, 8) };
+	unsafe { heap.dealloc(b, 32, 8) };
+
+	let mut live = Vec::new();
+	heap.for_each_tracked(| record | live.push(*record));
+	let a_record = AllocRecord { addr: a as usize, size: 64, tag: "a" };
+	assert_eq!(live, [a_record]);
+	assert_eq!(heap.tracker.as_ref().unwrap().untracked(), 1);
+
+	unsafe {
+	    heap.dealloc(a, 64, 8);
+	    heap.dealloc(c, 8, 8);
+	}
+	heap.for_each_tracked(| _ | panic!("leaked"));
+    }
+
+    #[test]
+    fn shrink_releases_the_tail() {
+	let mut area = TestArea::new(4 * 1024);
+	let mut heap = area.heap::<i16>();
+
+	let a = unsafe { heap.alloc(256, 8) };
+	let b = unsafe { heap.alloc(16, 8) };
+	let free_before = checked_usage(&heap).free_bytes;
+
+	let new_a = unsafe { heap.shrink(a, 256, 32, 8) };
+	assert_eq!(new_a, a);
+
+	// The released tail becomes a free block before b.
+	let f = checked_usage(&heap);
+	assert!(f.free_bytes > free_before);
+	assert_eq!(f.free_count, 2);	// The released tail and the tail
+
+	unsafe {
+	    heap.dealloc(new_a, 32, 8);
+	    heap.dealloc(b, 16, 8);
+	}
+	assert_eq!(checked_usage(&heap).inuse_count, 0);
+    }
+
+    #[test]
+    fn fragmentation_and_largest_allocatable() {
+	let mut area = TestArea::new(4 * 1024);
+	let mut heap = area.heap::<i16>();
+
+	let a = unsafe { heap.alloc(256, 8) };
+	let b = unsafe { heap.alloc(16, 8) };
+	let c = unsafe { heap.alloc(256, 8) };
+	let d = unsafe { heap.alloc(16, 8) };
+	let total = heap.largest_allocatable(8);
+
+	// Free a and c, then the free bytes are split into three blocks.
+	unsafe {
+	    heap.dealloc(a, 256, 8);
+	    heap.dealloc(c, 256, 8);
+	}
+	let frag = heap.fragmentation();
+	assert!(frag.consistent);
+	assert_eq!(frag.free_count, 3);
+	assert_eq!(frag.free_histogram.iter().sum::<usize>(), 3);
+	assert!(frag.percent() > 0);
+
+	// The largest allocatable block is the tail.
+	assert_eq!(heap.largest_allocatable(8), total);
+	let ptr = unsafe { heap.alloc(total, 8) };
+	assert!(!ptr.is_null());
+	assert!(unsafe { heap.alloc(total, 8) }.is_null());
+
+	unsafe {
+	    heap.dealloc(ptr, total, 8);
+	    heap.dealloc(b, 16, 8);
+	    heap.dealloc(d, 16, 8);
+	}
+	assert_eq!(heap.fragmentation().free_count, 1);
+	assert_eq!(heap.fragmentation().percent(), 0);
+    }
+}
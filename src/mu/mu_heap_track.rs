@@ -0,0 +1,98 @@
+//
+// Micro Heap Track - A table of the live allocations of a heap (opt-in).
+//
+
+use core::fmt;
+
+
+///
+/// A live allocation recorded by [`MuHeap`](super::MuHeap) (cf.
+/// method [`MuHeap::set_tracking`](super::MuHeap::set_tracking)).
+///
+/// An unused record has zero `addr`.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AllocRecord {
+    pub addr: usize,		// Address of the block
+    pub size: usize,		// Size in bytes requested
+    pub tag: &'static str,	// Tag set when the block was allocated
+}
+
+impl AllocRecord {
+    /// An unused record (for a static declaration of the table).
+    pub const EMPTY: Self = Self { addr: 0, size: 0, tag: "" };
+
+    /// Returns true if the record is unused.
+    pub fn is_empty(&self) -> bool {
+	self.addr == 0
+    }
+}
+
+impl fmt::Display for AllocRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{:#x}+{:#x} {}", self.addr, self.size, self.tag)
+    }
+}
+
+
+// The table of live allocations in caller-supplied storage.
+pub(super) struct HeapTracker {
+    records: &'static mut [AllocRecord],
+    tag: &'static str,		// Tag of the following allocations
+    untracked: usize,		// Allocations not recorded (table full)
+}
+
+impl HeapTracker {
+    pub(super) fn new(records: &'static mut [AllocRecord]) -> Self {
+	records.fill(AllocRecord::EMPTY);
+	Self {
+	    records,
+	    tag: "",
+	    untracked: 0,
+	}
+    }
+
+    pub(super) fn set_tag(&mut self, tag: &'static str) -> &'static str {
+	core::mem::replace(&mut self.tag, tag)
+    }
+
+    pub(super) fn records(&self) -> impl Iterator<Item = &AllocRecord> {
+	self.records.iter().filter(| record | !record.is_empty())
+    }
+
+    pub(super) fn untracked(&self) -> usize {
+	self.untracked
+    }
+
+    // Records a block allocated.
+    pub(super) fn insert(&mut self, addr: usize, size: usize) {
+	match self.records.iter_mut().find(| record | record.is_empty()) {
+	    Some(record) => {
+		*record = AllocRecord { addr, size, tag: self.tag };
+	    },
+	    None => {
+		self.untracked += 1;
+	    },
+	}
+    }
+
+    // Forgets a block deallocated.
+    pub(super) fn remove(&mut self, addr: usize) {
+	if let Some(record) = self.find(addr) {
+	    *record = AllocRecord::EMPTY;
+	}
+    }
+
+    // Updates a block resized (keeping the tag of the allocation).
+    pub(super) fn resize(&mut self, old_addr: usize,
+			 new_addr: usize, new_size: usize) {
+	if let Some(record) = self.find(old_addr) {
+	    record.addr = new_addr;
+	    record.size = new_size;
+	}
+    }
+
+    fn find(&mut self, addr: usize) -> Option<&mut AllocRecord> {
+	self.records.iter_mut().find(| record | record.addr == addr)
+    }
+}
@@ -34,6 +34,15 @@ impl<T> MuMutex<T> {
 	MuMutexGuard::<T> { locked: self }
     }
 
+    /// Acquires a mutex if it is not locked.
+    pub fn try_lock(&self) -> Option<MuMutexGuard<'_, T>> {
+	self.atomic.compare_exchange(false,
+				     true,
+				     Ordering::Acquire,
+				     Ordering::Relaxed).ok()?;
+	Some(MuMutexGuard::<T> { locked: self })
+    }
+
     fn spin_lock(&self) {
 	while self.atomic.compare_exchange_weak(false,
 						true,
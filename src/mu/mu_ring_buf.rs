@@ -0,0 +1,141 @@
+//
+// Micro Ring Buffer - A fixed-size ring buffer without allocation.
+//
+
+
+///
+/// Provides a fixed-size ring buffer (FIFO) without allocation.
+///
+/// Because it can be defined statically, it can be used before the
+/// global allocator is initialized (e.g. to record early boot logs)
+/// and in interrupt handlers.
+///
+pub struct MuRingBuf<T, const N: usize>
+where
+    T: Copy,
+{
+    buf: [T; N],
+    head: usize,	// Index of the oldest element
+    len: usize,		// Number of elements
+}
+
+impl<T, const N: usize> MuRingBuf<T, N>
+where
+    T: Copy,
+{
+    /// Returns an empty ring buffer whose slots are filled with `init`.
+    pub const fn new(init: T) -> Self {
+	Self {
+	    buf: [init; N],
+	    head: 0,
+	    len: 0,
+	}
+    }
+
+    /// Returns the maximum number of elements.
+    pub const fn capacity(&self) -> usize {
+	N
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+	self.len
+    }
+
+    /// Returns true if there are no elements.
+    pub fn is_empty(&self) -> bool {
+	self.len == 0
+    }
+
+    /// Returns true if there is no free slot.
+    pub fn is_full(&self) -> bool {
+	self.len == N
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+	self.head = 0;
+	self.len = 0;
+    }
+
+    /// Appends an element.  If full, it returns `Err(value)`.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+	if self.is_full() {
+	    return Err(value);
+	}
+	let tail = (self.head + self.len) % N;
+	self.buf[tail] = value;
+	self.len += 1;
+	Ok(())
+    }
+
+    /// Appends an element.  If full, the oldest element is overwritten
+    /// and returned.
+    pub fn push_overwrite(&mut self, value: T) -> Option<T> {
+	if N == 0 {
+	    return Some(value);
+	}
+	if self.is_full() {
+	    let oldest = self.buf[self.head];
+	    self.buf[self.head] = value;
+	    self.head = (self.head + 1) % N;
+	    Some(oldest)
+	} else {
+	    let _ = self.push(value);
+	    None
+	}
+    }
+
+    /// Appends elements.  If full, the oldest elements are overwritten.
+    pub fn push_slice_overwrite(&mut self, values: &[T]) {
+	for &value in values {
+	    self.push_overwrite(value);
+	}
+    }
+
+    /// Removes the oldest element and returns it.
+    pub fn pop(&mut self) -> Option<T> {
+	if self.is_empty() {
+	    return None;
+	}
+	let value = self.buf[self.head];
+	self.head = (self.head + 1) % N;
+	self.len -= 1;
+	Some(value)
+    }
+
+    /// Returns the oldest element without removing it.
+    pub fn peek(&self) -> Option<&T> {
+	if self.is_empty() {
+	    None
+	} else {
+	    Some(&self.buf[self.head])
+	}
+    }
+
+    /// Returns the elements as two slices (oldest first).
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+	if self.head + self.len <= N {
+	    (&self.buf[self.head .. self.head + self.len], &[])
+	} else {
+	    let wrapped = self.head + self.len - N;
+	    (&self.buf[self.head ..], &self.buf[.. wrapped])
+	}
+    }
+
+    /// Rearranges the elements so that they are contiguous, then
+    /// returns them as a slice (oldest first).
+    pub fn make_contiguous(&mut self) -> &[T] {
+	if self.head + self.len > N {
+	    self.buf.rotate_left(self.head);
+	    self.head = 0;
+	}
+	&self.buf[self.head .. self.head + self.len]
+    }
+
+    /// Returns an iterator over the elements (oldest first).
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+	let (first, second) = self.as_slices();
+	first.iter().chain(second.iter())
+    }
+}
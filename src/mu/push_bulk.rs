@@ -1,5 +1,6 @@
 use alloc::vec::Vec;
 use core::alloc::Allocator;
+use core::mem::MaybeUninit;
 use core::result::Result;
 use core::slice;
 
@@ -16,12 +17,16 @@ use core::slice;
 /// elements and calls a closure to fill the additional slots, then
 /// extends the length of the vector to expose the additional
 /// elements.  The callee closure receives additional elements as a
-/// slice of uninitialized elements.  It must fill all elements to
-/// avoid exposing uninitialized slots.
+/// slice of uninitialized elements (`MaybeUninit<T>`).  It must
+/// initialize all elements to avoid exposing uninitialized slots.
 ///
-/// Method `push_bulk` returns the same result returned by the
-/// closure.  If the closure returns `Err`(), the vector is not
-/// extended.
+/// Method `push_bulk_init` initializes the additional slots with a
+/// value (e.g. zero) before calling the closure, which receives them
+/// as a slice of `T`.  Hence, it is safe even if the closure does not
+/// fill all elements (e.g. a buffer passed to BIOS).
+///
+/// Both methods return the same result returned by the closure.  If
+/// the closure returns `Err`(), the vector is not extended.
 ///
 /// In typical use cases, method `push_bulk` would be called for an
 /// empty vector to fill with data.  In some cases, it might be called
@@ -40,7 +45,7 @@ use core::slice;
 ///
 /// ```
 /// use std::io::{Cursor, Read};
-/// use castflip::experimental::PushBulk;
+/// use nostd_env::mu::PushBulk;
 ///
 /// // Input data 1 (6 bytes)
 /// let bytes1: [u8; 16] = [0x10, 0x11, 0x12, 0x13,
@@ -54,11 +59,9 @@ use core::slice;
 ///
 /// // Fill the vector with loaded data.
 /// for i in 0 .. 4 {
-///     unsafe {
-///         vec2.push_bulk(4, | buf | {
-///             input1.read_exact(buf)
-///         }).unwrap();
-///     }
+///     vec2.push_bulk_init(4, 0, | buf | {
+///         input1.read_exact(buf)
+///     }).unwrap();
 /// }
 ///
 /// // Check the result (vec2)
@@ -67,15 +70,23 @@ use core::slice;
 ///
 /// # Safety
 ///
-/// The closure must fill whole slots because extended slots are not
-/// initialized.
+/// For `push_bulk`, the closure must initialize whole slots (if it
+/// returns `Ok`) because extended slots are not initialized.
 ///
 pub trait PushBulk<T, R, E> {
     /// Extends a vector by `additional` and calls closure
-    /// `fill_new_slice` to fill the extended slots.
+    /// `fill_new_slice` to initialize the extended slots.
     unsafe fn push_bulk<F>(&mut self, additional: usize, fill_new_slice: F)
 			   -> Result<R, E>
     where
+	F: FnMut(&mut [MaybeUninit<T>]) -> Result<R, E>;
+
+    /// Extends a vector by `additional` slots initialized with `value`,
+    /// and calls closure `fill_new_slice` to fill the extended slots.
+    fn push_bulk_init<F>(&mut self, additional: usize, value: T,
+			 fill_new_slice: F) -> Result<R, E>
+    where
+	T: Clone,
 	F: FnMut(&mut [T]) -> Result<R, E>;
 }
 
@@ -86,7 +97,7 @@ where
     unsafe fn push_bulk<F>(&mut self, additional: usize, mut fill_new_slice: F)
 			   -> Result<R, E>
     where
-	F: FnMut(&mut [T]) -> Result<R, E>
+	F: FnMut(&mut [MaybeUninit<T>]) -> Result<R, E>
     {
 	// Prepare enough size of hidden area.
 	self.reserve(additional);
@@ -95,7 +106,7 @@ where
 	// The hidden area is passed as an ephemeral slice (soon dropped).
 	let result = fill_new_slice(
 	    slice::from_raw_parts_mut(
-		self.as_mut_ptr().add(self.len()),
+		self.as_mut_ptr().add(self.len()) as *mut MaybeUninit<T>,
 		additional)
 	);
 
@@ -106,4 +117,50 @@ where
 
 	result
     }
+
+    fn push_bulk_init<F>(&mut self, additional: usize, value: T,
+			 mut fill_new_slice: F) -> Result<R, E>
+    where
+	T: Clone,
+	F: FnMut(&mut [T]) -> Result<R, E>
+    {
+	// Extend the length with initialized slots.
+	let len = self.len();
+	self.resize(len + additional, value);
+
+	// Fill the extended slots with caller-supplied closure
+	// `fill_new_slice`.  If the result is not ok, shrink back.
+	let result = fill_new_slice(&mut self[len ..]);
+	if result.is_err() {
+	    self.truncate(len);
+	}
+
+	result
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn push_bulk_init_keeps_vec_on_error() {
+	let mut vec: Vec<u8> = Vec::new();
+	let result: Result<(), ()> = vec.push_bulk_init(4, 0, | buf | {
+	    buf[.. 2].copy_from_slice(&[1, 2]);
+	    Ok(())
+	});
+	assert!(result.is_ok());
+	assert_eq!(vec, [1, 2, 0, 0]);
+
+	// On error, the extended slots are removed.
+	let result: Result<(), ()> = vec.push_bulk_init(4, 0, | buf | {
+	    buf.fill(9);
+	    Err(())
+	});
+	assert!(result.is_err());
+	assert_eq!(vec, [1, 2, 0, 0]);
+    }
 }
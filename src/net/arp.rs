@@ -0,0 +1,99 @@
+//
+// ARP (Address Resolution Protocol) for IPv4 over Ethernet
+//
+//	00-01: Hardware Type (1)	02-03: Protocol Type (0x0800)
+//	04   : Hardware Size (6)	05   : Protocol Size (4)
+//	06-07: Operation (1: Request, 2: Reply)
+//	08-0D: Sender MAC Address	0E-11: Sender IP Address
+//	12-17: Target MAC Address	18-1B: Target IP Address
+//
+
+use super::Ipv4Addr;
+use super::ethernet::ETHERTYPE_IPV4;
+use super::{read_be16, write_be16};
+
+
+/// The size in bytes of an ARP packet.
+pub const PACKET_SIZE: usize = 28;
+
+/// Operation: Request
+pub const OP_REQUEST: u16 = 1;
+/// Operation: Reply
+pub const OP_REPLY: u16 = 2;
+
+/// The number of entries in the ARP cache.
+const CACHE_SIZE: usize = 8;
+
+
+/// A parsed ARP packet.
+#[derive(Clone, Copy, Debug)]
+pub struct ArpPacket {
+    pub op: u16,
+    pub sender_mac: [u8; 6],
+    pub sender_ip: Ipv4Addr,
+    pub target_mac: [u8; 6],
+    pub target_ip: Ipv4Addr,
+}
+
+impl ArpPacket {
+    /// Parses an ARP packet for IPv4 over Ethernet.
+    pub fn parse(packet: &[u8]) -> Option<Self> {
+	if packet.len() < PACKET_SIZE ||
+	    read_be16(packet, 0) != 1 ||
+	    read_be16(packet, 2) != ETHERTYPE_IPV4 ||
+	    packet[4] != 6 || packet[5] != 4 {
+	    return None;
+	}
+
+	Some(Self {
+	    op: read_be16(packet, 6),
+	    sender_mac: packet[8 .. 14].try_into().unwrap(),
+	    sender_ip: Ipv4Addr(packet[14 .. 18].try_into().unwrap()),
+	    target_mac: packet[18 .. 24].try_into().unwrap(),
+	    target_ip: Ipv4Addr(packet[24 .. 28].try_into().unwrap()),
+	})
+    }
+
+    /// Writes the ARP packet at the head of the buffer.
+    pub fn write(&self, buf: &mut [u8]) {
+	write_be16(buf, 0, 1);
+	write_be16(buf, 2, ETHERTYPE_IPV4);
+	buf[4] = 6;
+	buf[5] = 4;
+	write_be16(buf, 6, self.op);
+	buf[8 .. 14].copy_from_slice(&self.sender_mac);
+	buf[14 .. 18].copy_from_slice(&self.sender_ip.0);
+	buf[18 .. 24].copy_from_slice(&self.target_mac);
+	buf[24 .. 28].copy_from_slice(&self.target_ip.0);
+    }
+}
+
+
+/// A small ARP cache (the oldest entry is replaced when full).
+#[derive(Default)]
+pub struct ArpCache {
+    entries: [Option<(Ipv4Addr, [u8; 6])>; CACHE_SIZE],
+    next: usize,
+}
+
+impl ArpCache {
+    /// Returns the MAC address of the IP address (if cached).
+    pub fn lookup(&self, ip: Ipv4Addr) -> Option<[u8; 6]> {
+	self.entries.iter().flatten()
+	    .find(|(entry_ip, _)| *entry_ip == ip)
+	    .map(|(_, mac)| *mac)
+    }
+
+    /// Adds or updates the entry.
+    pub fn insert(&mut self, ip: Ipv4Addr, mac: [u8; 6]) {
+	let existing = self.entries.iter_mut().flatten()
+	    .find(|(entry_ip, _)| *entry_ip == ip);
+	match existing {
+	    Some(entry) => entry.1 = mac,
+	    None => {
+		self.entries[self.next] = Some((ip, mac));
+		self.next = (self.next + 1) % CACHE_SIZE;
+	    },
+	}
+    }
+}
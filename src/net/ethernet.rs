@@ -0,0 +1,54 @@
+//
+// Ethernet II Frames
+//
+//	00-05: Destination MAC Address
+//	06-0B: Source MAC Address
+//	0C-0D: EtherType
+//	0E-  : Payload
+//
+
+use super::{read_be16, write_be16};
+
+
+/// The size in bytes of the Ethernet header.
+pub const HEADER_SIZE: usize = 14;
+
+/// EtherType: IPv4
+pub const ETHERTYPE_IPV4: u16 = 0x0800;
+/// EtherType: ARP
+pub const ETHERTYPE_ARP: u16 = 0x0806;
+
+/// The broadcast MAC address.
+pub const BROADCAST: [u8; 6] = [0xff; 6];
+
+
+/// A parsed Ethernet frame.
+pub struct EthernetFrame<'a> {
+    pub dst: [u8; 6],
+    pub src: [u8; 6],
+    pub ethertype: u16,
+    pub payload: &'a [u8],
+}
+
+impl<'a> EthernetFrame<'a> {
+    /// Parses a frame.
+    pub fn parse(frame: &'a [u8]) -> Option<Self> {
+	if frame.len() < HEADER_SIZE {
+	    return None;
+	}
+	Some(Self {
+	    dst: frame[0 .. 6].try_into().unwrap(),
+	    src: frame[6 .. 12].try_into().unwrap(),
+	    ethertype: read_be16(frame, 12),
+	    payload: &frame[HEADER_SIZE ..],
+	})
+    }
+}
+
+/// Writes an Ethernet header at the head of the buffer.
+pub fn write_header(buf: &mut [u8], dst: [u8; 6], src: [u8; 6],
+		    ethertype: u16) {
+    buf[0 .. 6].copy_from_slice(&dst);
+    buf[6 .. 12].copy_from_slice(&src);
+    write_be16(buf, 12, ethertype);
+}
@@ -0,0 +1,245 @@
+//
+// Network Interface - A network device with a static IPv4 configuration.
+//
+
+use core::hint::spin_loop;
+
+use super::arp::{self, ArpCache, ArpPacket, OP_REPLY, OP_REQUEST};
+use super::ethernet::{self, BROADCAST, ETHERTYPE_ARP, ETHERTYPE_IPV4};
+use super::ipv4::{self, Ipv4Packet, PROTOCOL_UDP};
+use super::udp::{self, UdpDatagram};
+use super::{Ipv4Addr, NetStackError};
+use crate::drivers::{MAX_FRAME_SIZE, NetDevice};
+
+
+/// The number of polls waiting for an ARP reply (per request).
+const ARP_POLLS: usize = 1_000_000;
+
+/// The number of ARP requests sent before giving up.
+const ARP_RETRIES: usize = 3;
+
+// The offset of the UDP header in a frame.
+const UDP_OFFSET: usize = ethernet::HEADER_SIZE + ipv4::HEADER_SIZE;
+
+/// The maximum size in bytes of a UDP payload.
+pub const MAX_UDP_PAYLOAD: usize = MAX_FRAME_SIZE - UDP_OFFSET
+    - udp::HEADER_SIZE;
+
+
+/// A static IPv4 configuration.
+#[derive(Clone, Copy, Debug)]
+pub struct NetConfig {
+    pub ip: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+    pub gateway: Ipv4Addr,
+}
+
+impl NetConfig {
+    /// The configuration for QEMU user-mode networking.
+    pub const QEMU_USER: Self = Self {
+	ip: Ipv4Addr([10, 0, 2, 15]),
+	netmask: Ipv4Addr([255, 255, 255, 0]),
+	gateway: Ipv4Addr([10, 0, 2, 2]),
+    };
+}
+
+
+/// A network device with a static IPv4 configuration.
+pub struct Interface<D>
+where
+    D: NetDevice
+{
+    dev: D,
+    config: NetConfig,
+    mac: [u8; 6],
+    arp_cache: ArpCache,
+    ip_id: u16,
+    frame: [u8; MAX_FRAME_SIZE],	// Buffer of the received frame
+}
+
+impl<D> Interface<D>
+where
+    D: NetDevice
+{
+    /// Returns a new interface.
+    pub fn new(dev: D, config: NetConfig) -> Self {
+	let mac = dev.mac_address();
+	Self {
+	    dev,
+	    config,
+	    mac,
+	    arp_cache: ArpCache::default(),
+	    ip_id: 0,
+	    frame: [0; MAX_FRAME_SIZE],
+	}
+    }
+
+    /// Returns the configuration.
+    pub fn config(&self) -> &NetConfig {
+	&self.config
+    }
+
+    /// Returns the network device.
+    pub fn device(&mut self) -> &mut D {
+	&mut self.dev
+    }
+
+    // Receives a frame and handles ARP.  An IPv4 frame is kept in
+    // self.frame for the caller.
+    fn poll(&mut self) -> Polled {
+	let Some(len) = self.dev.receive(&mut self.frame) else {
+	    return Polled::Nothing;
+	};
+	let Some(frame) = ethernet::EthernetFrame::parse(&self.frame[.. len])
+	else {
+	    return Polled::Dropped;
+	};
+
+	match frame.ethertype {
+	    ETHERTYPE_ARP => {
+		if let Some(packet) = ArpPacket::parse(frame.payload) {
+		    self.handle_arp(packet);
+		}
+		Polled::Dropped
+	    },
+	    ETHERTYPE_IPV4 => Polled::Ipv4(len),
+	    _ => Polled::Dropped,
+	}
+    }
+
+    fn handle_arp(&mut self, packet: ArpPacket) {
+	if packet.target_ip != self.config.ip {
+	    return;
+	}
+	self.arp_cache.insert(packet.sender_ip, packet.sender_mac);
+
+	if packet.op == OP_REQUEST {
+	    let reply = ArpPacket {
+		op: OP_REPLY,
+		sender_mac: self.mac,
+		sender_ip: self.config.ip,
+		target_mac: packet.sender_mac,
+		target_ip: packet.sender_ip,
+	    };
+	    // A lost reply will be requested again.
+	    let _ = self.send_arp(reply, packet.sender_mac);
+	}
+    }
+
+    fn send_arp(&mut self, packet: ArpPacket, dst: [u8; 6])
+		-> Result<(), NetStackError> {
+	let mut buf = [0; ethernet::HEADER_SIZE + arp::PACKET_SIZE];
+	ethernet::write_header(&mut buf, dst, self.mac, ETHERTYPE_ARP);
+	packet.write(&mut buf[ethernet::HEADER_SIZE ..]);
+	Ok(self.dev.send(&buf)?)
+    }
+
+    ///
+    /// Returns the MAC address of the next hop to the IP address.
+    /// An ARP request is sent if it is not cached.
+    ///
+    pub fn resolve(&mut self, ip: Ipv4Addr) -> Result<[u8; 6], NetStackError> {
+	if ip == Ipv4Addr::BROADCAST {
+	    return Ok(BROADCAST);
+	}
+
+	let config = self.config;
+	let next_hop = if ip.is_same_subnet(config.ip, config.netmask) {
+	    ip
+	} else {
+	    config.gateway
+	};
+
+	for _ in 0 .. ARP_RETRIES {
+	    if let Some(mac) = self.arp_cache.lookup(next_hop) {
+		return Ok(mac);
+	    }
+
+	    let request = ArpPacket {
+		op: OP_REQUEST,
+		sender_mac: self.mac,
+		sender_ip: self.config.ip,
+		target_mac: [0; 6],
+		target_ip: next_hop,
+	    };
+	    self.send_arp(request, BROADCAST)?;
+
+	    for _ in 0 .. ARP_POLLS {
+		// IPv4 frames are dropped while resolving.
+		self.poll();
+		if self.arp_cache.lookup(next_hop).is_some() {
+		    break;
+		}
+		spin_loop();
+	    }
+	}
+
+	self.arp_cache.lookup(next_hop)
+	    .ok_or(NetStackError::ArpTimeout { ip: next_hop })
+    }
+
+    /// Sends a UDP datagram from the local port to the destination.
+    pub fn send_udp(&mut self, src_port: u16, dst: (Ipv4Addr, u16),
+		    payload: &[u8]) -> Result<(), NetStackError> {
+	if payload.len() > MAX_UDP_PAYLOAD {
+	    return Err(NetStackError::PayloadTooLarge { len: payload.len() });
+	}
+
+	let dst_mac = self.resolve(dst.0)?;
+
+	let udp_len = udp::HEADER_SIZE + payload.len();
+	let mut buf = [0; MAX_FRAME_SIZE];
+	let frame_len = UDP_OFFSET + udp_len;
+
+	ethernet::write_header(&mut buf, dst_mac, self.mac, ETHERTYPE_IPV4);
+	self.ip_id = self.ip_id.wrapping_add(1);
+	ipv4::write_header(&mut buf[ethernet::HEADER_SIZE ..],
+			   self.config.ip, dst.0, PROTOCOL_UDP,
+			   udp_len, self.ip_id);
+	let udp_buf = &mut buf[UDP_OFFSET .. frame_len];
+	udp_buf[udp::HEADER_SIZE ..].copy_from_slice(payload);
+	udp::write_header(udp_buf, (self.config.ip, src_port), dst);
+
+	Ok(self.dev.send(&buf[.. frame_len])?)
+    }
+
+    ///
+    /// Receives a UDP datagram to the local port into `buf` if
+    /// available, then returns its length and its source.  Other
+    /// frames received meanwhile are handled (ARP) or dropped.
+    ///
+    pub fn recv_udp(&mut self, port: u16, buf: &mut [u8])
+		    -> Option<(usize, (Ipv4Addr, u16))> {
+	loop {
+	    let len = match self.poll() {
+		Polled::Nothing => return None,
+		Polled::Dropped => continue,
+		Polled::Ipv4(len) => len,
+	    };
+
+	    let frame = &self.frame[ethernet::HEADER_SIZE .. len];
+	    let Some(packet) = Ipv4Packet::parse(frame) else { continue };
+	    if packet.protocol != PROTOCOL_UDP ||
+		(packet.dst != self.config.ip &&
+		 packet.dst != Ipv4Addr::BROADCAST) {
+		continue;
+	    }
+
+	    let datagram = UdpDatagram::parse(packet.payload,
+					      packet.src, packet.dst);
+	    if let Some(datagram) = datagram.filter(|d| d.dst_port == port) {
+		let n = datagram.payload.len().min(buf.len());
+		buf[.. n].copy_from_slice(&datagram.payload[.. n]);
+		return Some((n, (packet.src, datagram.src_port)));
+	    }
+	}
+    }
+}
+
+
+// Results of Interface::poll
+enum Polled {
+    Nothing,		// No frame is received.
+    Dropped,		// A frame is handled or dropped.
+    Ipv4(usize),	// An IPv4 frame of the length is received.
+}
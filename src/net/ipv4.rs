@@ -0,0 +1,117 @@
+//
+// IPv4 Packets (without options)
+//
+//	00   : Version (4) and IHL (5)	01   : DSCP and ECN
+//	02-03: Total Length		04-05: Identification
+//	06-07: Flags and Fragment Offset
+//	08   : TTL			09   : Protocol
+//	0A-0B: Header Checksum
+//	0C-0F: Source Address		10-13: Destination Address
+//
+
+use core::fmt;
+
+use super::{read_be16, write_be16};
+use crate::util::internet_checksum as checksum;
+
+
+/// The size in bytes of the IPv4 header (without options).
+pub const HEADER_SIZE: usize = 20;
+
+/// Protocol: UDP
+pub const PROTOCOL_UDP: u8 = 17;
+
+/// The default TTL.
+const DEFAULT_TTL: u8 = 64;
+
+
+/// An IPv4 address.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Ipv4Addr(pub [u8; 4]);
+
+impl Ipv4Addr {
+    pub const UNSPECIFIED: Self = Self([0, 0, 0, 0]);
+    pub const BROADCAST: Self = Self([255, 255, 255, 255]);
+
+    /// Returns the address as u32.
+    pub fn to_u32(self) -> u32 {
+	u32::from_be_bytes(self.0)
+    }
+
+    /// Returns true if both addresses are in the same subnet.
+    pub fn is_same_subnet(self, other: Self, netmask: Self) -> bool {
+	(self.to_u32() ^ other.to_u32()) & netmask.to_u32() == 0
+    }
+}
+
+impl fmt::Display for Ipv4Addr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	let a = &self.0;
+	write!(f, "{}.{}.{}.{}", a[0], a[1], a[2], a[3])
+    }
+}
+
+
+/// A parsed IPv4 packet.
+pub struct Ipv4Packet<'a> {
+    pub src: Ipv4Addr,
+    pub dst: Ipv4Addr,
+    pub protocol: u8,
+    pub payload: &'a [u8],
+}
+
+impl<'a> Ipv4Packet<'a> {
+    ///
+    /// Parses a packet.  Returns None if it is invalid or fragmented,
+    /// or if its checksum is wrong.  (Options are skipped.)
+    ///
+    pub fn parse(packet: &'a [u8]) -> Option<Self> {
+	if packet.len() < HEADER_SIZE || (packet[0] >> 4) != 4 {
+	    return None;
+	}
+
+	let header_len = ((packet[0] & 0x0f) as usize) * 4;
+	let total_len = read_be16(packet, 2) as usize;
+	let fragment = read_be16(packet, 6) & 0x3fff;	// MF and Offset
+	if header_len < HEADER_SIZE || total_len < header_len ||
+	    total_len > packet.len() || fragment != 0 ||
+	    checksum(0, &packet[.. header_len]) != 0 {
+	    return None;
+	}
+
+	Some(Self {
+	    src: Ipv4Addr(packet[12 .. 16].try_into().unwrap()),
+	    dst: Ipv4Addr(packet[16 .. 20].try_into().unwrap()),
+	    protocol: packet[9],
+	    payload: &packet[header_len .. total_len],
+	})
+    }
+}
+
+/// Writes an IPv4 header at the head of the buffer.
+pub fn write_header(buf: &mut [u8], src: Ipv4Addr, dst: Ipv4Addr,
+		    protocol: u8, payload_len: usize, id: u16) {
+    let header = &mut buf[.. HEADER_SIZE];
+    header[0] = 0x45;			// Version 4, IHL 5
+    header[1] = 0;
+    write_be16(header, 2, (HEADER_SIZE + payload_len) as u16);
+    write_be16(header, 4, id);
+    write_be16(header, 6, 0x4000);	// Don't Fragment
+    header[8] = DEFAULT_TTL;
+    header[9] = protocol;
+    write_be16(header, 10, 0);
+    header[12 .. 16].copy_from_slice(&src.0);
+    header[16 .. 20].copy_from_slice(&dst.0);
+
+    let sum = checksum(0, header);
+    write_be16(header, 10, sum);
+}
+
+/// Returns the sum of the pseudo header for UDP and TCP checksums.
+pub fn pseudo_header_sum(src: Ipv4Addr, dst: Ipv4Addr,
+			 protocol: u8, len: usize) -> u32 {
+    let words = |addr: Ipv4Addr| {
+	(addr.to_u32() >> 16) + (addr.to_u32() & 0xffff)
+    };
+    words(src) + words(dst) + protocol as u32 + len as u32
+}
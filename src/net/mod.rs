@@ -0,0 +1,87 @@
+/*!
+
+Provides a minimal network stack (Ethernet, ARP, IPv4 and UDP) on
+top of a [`NetDevice`](crate::drivers::NetDevice).
+
+* `Interface` - owns a network device with a static IPv4
+  configuration, answers ARP requests, and resolves MAC addresses.
+
+* `UdpSocket` - sends and receives UDP datagrams via an `Interface`.
+
+* `tftp::get` - fetches a file from a TFTP server.
+
+Everything is done by polling.  IP fragmentation, IP options and
+routing other than the default gateway are not supported.
+
+```ignore
+let mut iface = net::Interface::new(dev, net::NetConfig::QEMU_USER);
+let socket = net::UdpSocket::bind(5000);
+socket.send_to(&mut iface, b"hello", (net::Ipv4Addr([10, 0, 2, 2]), 5000))?;
+```
+
+# Supplementary Resources
+
+* [RFC 826](https://www.rfc-editor.org/rfc/rfc826) - ARP
+* [RFC 791](https://www.rfc-editor.org/rfc/rfc791) - IPv4
+* [RFC 768](https://www.rfc-editor.org/rfc/rfc768) - UDP
+* [RFC 1350](https://www.rfc-editor.org/rfc/rfc1350) - TFTP
+
+ */
+
+
+#[doc(hidden)] pub mod arp;
+#[doc(hidden)] pub mod ethernet;
+#[doc(hidden)] pub mod interface;
+#[doc(hidden)] pub mod ipv4;
+#[doc(hidden)] pub mod udp;
+pub mod tftp;
+
+#[doc(inline)] pub use self::interface::{Interface, NetConfig};
+#[doc(inline)] pub use self::ipv4::Ipv4Addr;
+#[doc(inline)] pub use self::udp::UdpSocket;
+
+use core::fmt;
+
+use crate::drivers::NetError;
+
+
+/// Errors detected by the network stack.
+#[derive(Debug)]
+pub enum NetStackError {
+    /// The network device failed.
+    Device(NetError),
+    /// No ARP reply was received.
+    ArpTimeout { ip: Ipv4Addr },
+    /// The payload does not fit in a frame.
+    PayloadTooLarge { len: usize },
+}
+
+impl fmt::Display for NetStackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Device(err) =>
+		write!(f, "{}", err),
+	    Self::ArpTimeout { ip } =>
+		write!(f, "No ARP reply from {}", ip),
+	    Self::PayloadTooLarge { len } =>
+		write!(f, "Payload is too large ({} bytes)", len),
+	}
+    }
+}
+
+impl From<NetError> for NetStackError {
+    fn from(err: NetError) -> Self {
+	Self::Device(err)
+    }
+}
+
+
+// Reads a big-endian u16 at the offset.
+fn read_be16(buf: &[u8], off: usize) -> u16 {
+    u16::from_be_bytes([buf[off], buf[off + 1]])
+}
+
+// Writes a big-endian u16 at the offset.
+fn write_be16(buf: &mut [u8], off: usize, value: u16) {
+    buf[off .. off + 2].copy_from_slice(&value.to_be_bytes());
+}
@@ -0,0 +1,202 @@
+/*!
+
+Provides a TFTP client (RFC 1350, octet mode only).
+
+QEMU user-mode networking has a built-in TFTP server at 10.0.2.2,
+which serves files in the directory given by the `tftp` option.
+
+```sh
+-netdev user,id=net0,tftp=tftp -device virtio-net-pci,netdev=net0
+```
+
+```ignore
+let server = Ipv4Addr([10, 0, 2, 2]);
+let data = net::tftp::get(&mut iface, server, "payload.bin", Global)?;
+```
+
+ */
+
+//
+// Packets (all numbers are in big-endian):
+//	RRQ:   01 | Filename | 0 | Mode | 0
+//	DATA:  03 | Block# | Data (0 - 512 bytes)
+//	ACK:   04 | Block#
+//	ERROR: 05 | ErrorCode | ErrMsg | 0
+//
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::fmt;
+use core::hint::spin_loop;
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use super::{Interface, Ipv4Addr, NetStackError, UdpSocket};
+use super::{read_be16, write_be16};
+use crate::drivers::NetDevice;
+
+
+/// The well-known port of TFTP servers.
+pub const SERVER_PORT: u16 = 69;
+
+// Opcodes
+const OP_RRQ: u16 = 1;
+const OP_DATA: u16 = 3;
+const OP_ACK: u16 = 4;
+const OP_ERROR: u16 = 5;
+
+/// The maximum length in bytes of filenames.
+pub const MAX_FILENAME_LEN: usize = 256;
+
+/// The size in bytes of data in a full DATA packet.
+const BLOCK_SIZE: usize = 512;
+
+/// The number of polls waiting for a packet (per transmission).
+const RECV_POLLS: usize = 1_000_000;
+
+/// The number of retransmissions before giving up.
+const RETRIES: usize = 5;
+
+// The next local port (ephemeral ports are used in turn).
+static NEXT_PORT: AtomicU16 = AtomicU16::new(49152);
+
+
+/// Errors detected by the TFTP client.
+#[derive(Debug)]
+pub enum TftpError {
+    /// The network stack failed.
+    Net(NetStackError),
+    /// The filename is too long.
+    FilenameTooLong,
+    /// No response from the server.
+    Timeout,
+    /// The server returned an error.
+    Server { code: u16 },
+}
+
+impl fmt::Display for TftpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Net(err) =>
+		write!(f, "{}", err),
+	    Self::FilenameTooLong =>
+		write!(f, "TFTP filename is too long"),
+	    Self::Timeout =>
+		write!(f, "No response from the TFTP server"),
+	    Self::Server { code } =>
+		write!(f, "TFTP server returned error {}", code),
+	}
+    }
+}
+
+impl From<NetStackError> for TftpError {
+    fn from(err: NetStackError) -> Self {
+	Self::Net(err)
+    }
+}
+
+
+///
+/// Gets the file from the TFTP server, then returns its contents in
+/// a `Vec` allocated by `alloc`.
+///
+pub fn get<D, A>(iface: &mut Interface<D>, server: Ipv4Addr,
+		 filename: &str, alloc: A) -> Result<Vec<u8, A>, TftpError>
+where
+    D: NetDevice,
+    A: Allocator
+{
+    // RRQ: Opcode, Filename, 0, "octet", 0
+    let filename = filename.as_bytes();
+    if filename.len() > MAX_FILENAME_LEN {
+	return Err(TftpError::FilenameTooLong);
+    }
+    let mut request = [0; 2 + MAX_FILENAME_LEN + 1 + 6];
+    write_be16(&mut request, 0, OP_RRQ);
+    request[2 .. 2 + filename.len()].copy_from_slice(filename);
+    let mode_off = 2 + filename.len() + 1;
+    request[mode_off .. mode_off + 5].copy_from_slice(b"octet");
+    let request_len = mode_off + 6;
+
+    let port = NEXT_PORT.fetch_add(1, Ordering::Relaxed) | 0xc000;
+    let socket = UdpSocket::bind(port);
+    socket.send_to(iface, &request[.. request_len], (server, SERVER_PORT))?;
+
+    // The server replies from its new port (transfer ID).
+    let mut peer: Option<(Ipv4Addr, u16)> = None;
+    let mut data = Vec::new_in(alloc);
+    let mut block: u16 = 0;		// The last block received
+    let mut packet = [0; 4 + BLOCK_SIZE];
+    let mut retries = 0;
+
+    loop {
+	let Some((len, from)) = poll(iface, &socket, &mut packet) else {
+	    // Retransmit the last packet.
+	    retries += 1;
+	    if retries > RETRIES {
+		return Err(TftpError::Timeout);
+	    }
+	    match peer {
+		None => socket.send_to(iface, &request[.. request_len],
+				       (server, SERVER_PORT))?,
+		Some(peer) => send_ack(iface, &socket, peer, block)?,
+	    }
+	    continue;
+	};
+
+	if from.0 != server || peer.is_some_and(|peer| peer != from) ||
+	    len < 4 {
+	    continue;
+	}
+
+	match read_be16(&packet, 0) {
+	    OP_DATA => {
+		// Duplicated blocks are acknowledged again.
+		let received = read_be16(&packet, 2);
+		if received == block.wrapping_add(1) {
+		    peer = Some(from);
+		    data.extend_from_slice(&packet[4 .. len]);
+		    block = received;
+		    retries = 0;
+		} else if received != block || peer.is_none() {
+		    continue;
+		}
+		send_ack(iface, &socket, from, received)?;
+		if received == block && len - 4 < BLOCK_SIZE {
+		    return Ok(data);
+		}
+	    },
+	    OP_ERROR => {
+		let code = read_be16(&packet, 2);
+		return Err(TftpError::Server { code });
+	    },
+	    _ => (),
+	}
+    }
+}
+
+// Sends an ACK packet of the block.
+fn send_ack<D>(iface: &mut Interface<D>, socket: &UdpSocket,
+	       peer: (Ipv4Addr, u16), block: u16) -> Result<(), NetStackError>
+where
+    D: NetDevice
+{
+    let mut ack = [0; 4];
+    write_be16(&mut ack, 0, OP_ACK);
+    write_be16(&mut ack, 2, block);
+    socket.send_to(iface, &ack, peer)
+}
+
+// Polls a datagram for a while.
+fn poll<D>(iface: &mut Interface<D>, socket: &UdpSocket, buf: &mut [u8])
+	   -> Option<(usize, (Ipv4Addr, u16))>
+where
+    D: NetDevice
+{
+    for _ in 0 .. RECV_POLLS {
+	if let Some(received) = socket.recv_from(iface, buf) {
+	    return Some(received);
+	}
+	spin_loop();
+    }
+    None
+}
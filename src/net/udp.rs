@@ -0,0 +1,115 @@
+//
+// UDP Datagrams
+//
+//	00-01: Source Port		02-03: Destination Port
+//	04-05: Length			06-07: Checksum
+//
+
+use super::ipv4::{PROTOCOL_UDP, pseudo_header_sum};
+use super::{Interface, Ipv4Addr, NetStackError};
+use crate::util::internet_checksum as checksum;
+use super::{read_be16, write_be16};
+use crate::drivers::NetDevice;
+
+
+/// The size in bytes of the UDP header.
+pub const HEADER_SIZE: usize = 8;
+
+
+/// A parsed UDP datagram.
+pub struct UdpDatagram<'a> {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub payload: &'a [u8],
+}
+
+impl<'a> UdpDatagram<'a> {
+    /// Parses a datagram, and verifies its checksum (if not zero).
+    pub fn parse(datagram: &'a [u8], src: Ipv4Addr, dst: Ipv4Addr)
+		 -> Option<Self> {
+	if datagram.len() < HEADER_SIZE {
+	    return None;
+	}
+
+	let len = read_be16(datagram, 4) as usize;
+	if len < HEADER_SIZE || len > datagram.len() {
+	    return None;
+	}
+
+	let datagram = &datagram[.. len];
+	if read_be16(datagram, 6) != 0 {
+	    let sum = pseudo_header_sum(src, dst, PROTOCOL_UDP, len);
+	    if checksum(sum, datagram) != 0 {
+		return None;
+	    }
+	}
+
+	Some(Self {
+	    src_port: read_be16(datagram, 0),
+	    dst_port: read_be16(datagram, 2),
+	    payload: &datagram[HEADER_SIZE ..],
+	})
+    }
+}
+
+/// Writes a UDP header with its checksum before the payload in `buf`.
+pub fn write_header(buf: &mut [u8], src: (Ipv4Addr, u16),
+		    dst: (Ipv4Addr, u16)) {
+    let len = buf.len();
+    write_be16(buf, 0, src.1);
+    write_be16(buf, 2, dst.1);
+    write_be16(buf, 4, len as u16);
+    write_be16(buf, 6, 0);
+
+    let sum = pseudo_header_sum(src.0, dst.0, PROTOCOL_UDP, len);
+    let sum = match checksum(sum, buf) {
+	0 => 0xffff,	// 0 means no checksum.
+	sum => sum,
+    };
+    write_be16(buf, 6, sum);
+}
+
+
+///
+/// A UDP socket bound to a local port.
+///
+/// It is used with an [`Interface`] by polling.  Datagrams to other
+/// ports are dropped while receiving.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct UdpSocket {
+    port: u16,
+}
+
+impl UdpSocket {
+    /// Returns a socket bound to the local port.
+    pub fn bind(port: u16) -> Self {
+	Self { port }
+    }
+
+    /// Returns the local port.
+    pub fn port(&self) -> u16 {
+	self.port
+    }
+
+    /// Sends a datagram to the destination (IP address, port).
+    pub fn send_to<D>(&self, iface: &mut Interface<D>, payload: &[u8],
+		      dst: (Ipv4Addr, u16)) -> Result<(), NetStackError>
+    where
+	D: NetDevice
+    {
+	iface.send_udp(self.port, dst, payload)
+    }
+
+    ///
+    /// Receives a datagram into `buf` if available, then returns its
+    /// length and its source (IP address, port).
+    ///
+    pub fn recv_from<D>(&self, iface: &mut Interface<D>, buf: &mut [u8])
+			-> Option<(usize, (Ipv4Addr, u16))>
+    where
+	D: NetDevice
+    {
+	iface.recv_udp(self.port, buf)
+    }
+}
@@ -0,0 +1,192 @@
+//
+// ACPI Power Management - Enters S5 and resets via the FADT.
+//
+// The sleeping type of S5 (SLP_TYPa and SLP_TYPb) is defined by the
+// \_S5 package in the DSDT, which is found by scanning the AML for
+// its name instead of interpreting the AML.
+//
+// Supplementary Resource:
+//	https://wiki.osdev.org/Shutdown
+//	https://forum.osdev.org/viewtopic.php?t=16990
+//
+
+use core::ptr::read_unaligned;
+
+use super::PowerError;
+use crate::acpi::{Acpi, SdtHeader};
+use crate::time::{Duration, wait_until};
+use crate::x86::{inw, outb, outw};
+
+
+// Offsets in the FADT
+const FADT_DSDT: usize = 40;
+const FADT_SMI_CMD: usize = 48;
+const FADT_ACPI_ENABLE: usize = 52;
+const FADT_PM1A_CNT_BLK: usize = 64;
+const FADT_PM1B_CNT_BLK: usize = 68;
+const FADT_FLAGS: usize = 112;
+const FADT_RESET_REG: usize = 116;	// Generic Address Structure
+const FADT_RESET_VALUE: usize = 128;
+const FADT_X_DSDT: usize = 140;
+
+// Flags in the FADT
+const FLAGS_RESET_REG_SUP: u32 = 1 << 10;
+
+// Address spaces of the Generic Address Structure
+const GAS_SYSTEM_MEMORY: u8 = 0;
+const GAS_SYSTEM_IO: u8 = 1;
+
+// PM1 Control Register
+const PM1_SCI_EN: u16 = 1 << 0;
+const PM1_SLP_TYP_SHIFT: u16 = 10;
+const PM1_SLP_EN: u16 = 1 << 13;
+
+// AML Opcodes
+const AML_PACKAGE_OP: u8 = 0x12;
+const AML_BYTE_PREFIX: u8 = 0x0a;
+
+// The time waiting for the firmware to enable ACPI mode
+const ACPI_ENABLE_TIMEOUT: Duration = Duration::from_millis(300);
+
+
+/// ACPI power management registers found in the FADT.
+#[derive(Clone, Copy)]
+pub struct AcpiPm {
+    fadt: &'static SdtHeader,
+}
+
+impl AcpiPm {
+    /// Finds the FADT.
+    pub fn new() -> Result<Self, PowerError> {
+	let acpi = Acpi::new()?;
+	let fadt = acpi.find_table(b"FACP").ok_or(PowerError::NoFadt)?;
+	Ok(Self { fadt })
+    }
+
+    /// Enters the sleeping state S5 (soft off).
+    pub fn enter_s5(&self) -> Result<(), PowerError> {
+	let (slp_typa, slp_typb) = self.s5_sleep_types()?;
+	self.enable_acpi()?;
+
+	let pm1a = self.read_u32(FADT_PM1A_CNT_BLK) as u16;
+	let pm1b = self.read_u32(FADT_PM1B_CNT_BLK) as u16;
+	unsafe {
+	    outw(pm1a, (slp_typa as u16) << PM1_SLP_TYP_SHIFT | PM1_SLP_EN);
+	    if pm1b != 0 {
+		outw(pm1b, (slp_typb as u16) << PM1_SLP_TYP_SHIFT |
+		     PM1_SLP_EN);
+	    }
+	}
+
+	// Reached only if the system did not power off.
+	Err(PowerError::AcpiNotEnabled)
+    }
+
+    /// Resets the system via the reset register.
+    pub fn reset(&self) -> Result<(), PowerError> {
+	let data = self.fadt.bytes();
+	if data.len() < FADT_RESET_VALUE + 1 ||
+	    (self.read_u32(FADT_FLAGS) & FLAGS_RESET_REG_SUP) == 0 {
+	    return Err(PowerError::NoResetRegister);
+	}
+
+	let space = data[FADT_RESET_REG];
+	let addr = self.read_u64(FADT_RESET_REG + 4);
+	let value = data[FADT_RESET_VALUE];
+	match space {
+	    GAS_SYSTEM_IO => unsafe { outb(addr as u16, value) },
+	    GAS_SYSTEM_MEMORY => unsafe {
+		(addr as *mut u8).write_volatile(value)
+	    },
+	    _ => return Err(PowerError::NoResetRegister),
+	}
+
+	// Reached only if the system did not reset.
+	Err(PowerError::NoResetRegister)
+    }
+
+    // Returns (SLP_TYPa, SLP_TYPb) in the \_S5 package of the DSDT.
+    fn s5_sleep_types(&self) -> Result<(u8, u8), PowerError> {
+	let dsdt = self.dsdt()?;
+	let aml = dsdt.data();
+
+	// NameOp "_S5_" PackageOp PkgLength NumElements Elements...
+	let pos = aml.windows(4).position(|name| name == b"_S5_")
+	    .ok_or(PowerError::NoS5)?;
+	let mut rest = aml.get(pos + 4 ..).ok_or(PowerError::NoS5)?;
+	if rest.first() != Some(&AML_PACKAGE_OP) || rest.len() < 3 {
+	    return Err(PowerError::NoS5);
+	}
+	let pkg_length_size = 1 + (rest[1] >> 6) as usize;
+	rest = rest.get(1 + pkg_length_size + 1 ..).ok_or(PowerError::NoS5)?;
+
+	// Each element is a ByteConst, Zero or One.
+	let mut element = || {
+	    let (value, size) = match rest {
+		[AML_BYTE_PREFIX, value, ..] => (*value, 2),
+		[value, ..] => (*value, 1),
+		[] => return None,
+	    };
+	    rest = &rest[size ..];
+	    Some(value)
+	};
+	let slp_typa = element().ok_or(PowerError::NoS5)?;
+	let slp_typb = element().unwrap_or(0);
+	Ok((slp_typa, slp_typb))
+    }
+
+    // Returns the DSDT (X_DSDT is preferred if available).
+    fn dsdt(&self) -> Result<&'static SdtHeader, PowerError> {
+	let x_dsdt = if self.fadt.bytes().len() >= FADT_X_DSDT + 8 {
+	    self.read_u64(FADT_X_DSDT)
+	} else {
+	    0
+	};
+	let addr = if x_dsdt != 0 {
+	    x_dsdt
+	} else {
+	    self.read_u32(FADT_DSDT) as u64
+	};
+	Ok(unsafe { SdtHeader::at(addr as usize)? })
+    }
+
+    // Switches from legacy mode to ACPI mode (if not yet).
+    fn enable_acpi(&self) -> Result<(), PowerError> {
+	let pm1a = self.read_u32(FADT_PM1A_CNT_BLK) as u16;
+	let is_enabled = || unsafe { (inw(pm1a) & PM1_SCI_EN) != 0 };
+	if pm1a == 0 {
+	    return Err(PowerError::AcpiNotEnabled);
+	}
+	if is_enabled() {
+	    return Ok(());
+	}
+
+	let smi_cmd = self.read_u32(FADT_SMI_CMD) as u16;
+	let acpi_enable = self.fadt.bytes()[FADT_ACPI_ENABLE];
+	if smi_cmd == 0 || acpi_enable == 0 {
+	    return Err(PowerError::AcpiNotEnabled);
+	}
+	unsafe { outb(smi_cmd, acpi_enable) };
+	if wait_until(ACPI_ENABLE_TIMEOUT, is_enabled) {
+	    Ok(())
+	} else {
+	    Err(PowerError::AcpiNotEnabled)
+	}
+    }
+
+    fn read_u32(&self, off: usize) -> u32 {
+	let bytes = self.fadt.bytes();
+	if off + 4 > bytes.len() {
+	    return 0;
+	}
+	unsafe { read_unaligned(bytes[off ..].as_ptr() as *const u32) }
+    }
+
+    fn read_u64(&self, off: usize) -> u64 {
+	let bytes = self.fadt.bytes();
+	if off + 8 > bytes.len() {
+	    return 0;
+	}
+	unsafe { read_unaligned(bytes[off ..].as_ptr() as *const u64) }
+    }
+}
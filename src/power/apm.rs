@@ -0,0 +1,47 @@
+//
+// APM - Turns the power off via the APM BIOS (INT 15h AH=53h).
+//
+// Supplementary Resource:
+//	https://wiki.osdev.org/APM
+//
+
+use super::PowerError;
+use crate::bios::CallRegs;
+
+
+// APM Functions (AX)
+const APM_INSTALLATION_CHECK: u16 = 0x5300;
+const APM_CONNECT_REAL_MODE: u16 = 0x5301;
+const APM_DRIVER_VERSION: u16 = 0x530e;
+const APM_SET_POWER_STATE: u16 = 0x5307;
+
+// Device IDs (BX) and Power States (CX)
+const APM_BIOS: u16 = 0x0000;
+const APM_ALL_DEVICES: u16 = 0x0001;
+const APM_STATE_OFF: u16 = 0x0003;
+
+
+/// Turns the power off via the APM BIOS (version 1.2 is requested).
+pub fn power_off() -> Result<(), PowerError> {
+    apm_call(APM_INSTALLATION_CHECK, APM_BIOS, 0)?;
+
+    // Connecting fails if already connected, which is harmless.
+    let _ = apm_call(APM_CONNECT_REAL_MODE, APM_BIOS, 0);
+    apm_call(APM_DRIVER_VERSION, APM_BIOS, 0x0102)?;
+    apm_call(APM_SET_POWER_STATE, APM_ALL_DEVICES, APM_STATE_OFF)?;
+
+    // Reached only if the system did not power off.
+    Err(PowerError::NoApm)
+}
+
+fn apm_call(ax: u16, bx: u16, cx: u16) -> Result<(), PowerError> {
+    // Note: On error, the carry flag (CF) is set.
+    let result = unsafe {
+	CallRegs::int(0x15).ax(ax).bx(bx).cx(cx).call()
+    };
+    if result.carry() {
+	Err(PowerError::NoApm)
+    } else {
+	Ok(())
+    }
+}
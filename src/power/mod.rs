@@ -0,0 +1,234 @@
+/*!
+
+Provides power management: shutdown, reboot and halt.
+
+* `shutdown` - enters the ACPI sleeping state S5 (soft off).  If it
+  fails, tries the APM BIOS, then the QEMU isa-debug-exit device.
+
+* `reboot` - resets the system via the ACPI reset register, the
+  keyboard controller, and the reset control register (0xCF9).
+
+* `halt` - halts the processor forever.
+
+* `finish` - ends a test run.  QEMU exits with the exit code if the
+  isa-debug-exit device is available.  Otherwise, the action given
+  by the command line `on_exit=<halt|shutdown|reboot>` is taken
+  (`halt` by default).
+
+* `PanicPolicy` - the action after a panic is reported, given by the
+  command line `panic_policy=<halt|reboot[:<secs>]|exit>`.  By
+  default, the run ends as failed (as by `finish`).  CI runs can exit
+  QEMU at once, while interactive sessions can halt to inspect the
+  screen (or the state by a debugger).
+
+```ignore
+let summary = testing::summary();
+power::finish(summary.exit_code());
+```
+
+# Supplementary Resources
+
+* [ACPI Specification](https://uefi.org/specifications) (UEFI Forum)
+* [Shutdown](https://wiki.osdev.org/Shutdown) (OSDev Wiki)
+* [Reboot](https://wiki.osdev.org/Reboot) (OSDev Wiki)
+* [APM](https://wiki.osdev.org/APM) (OSDev Wiki)
+
+ */
+
+
+#[cfg(feature = "acpi")] #[doc(hidden)] pub mod acpi_pm;
+#[doc(hidden)] pub mod apm;
+
+#[cfg(feature = "acpi")]
+#[doc(inline)] pub use self::acpi_pm::AcpiPm;
+
+use core::fmt;
+use core::time::Duration;
+
+#[cfg(feature = "acpi")]
+use crate::acpi::AcpiError;
+use crate::cmdline;
+use crate::testing::{self, ExitCode};
+use crate::time;
+use crate::x86::{self, outb};
+
+
+/// Errors detected by the power management.
+#[derive(Debug)]
+pub enum PowerError {
+    /// ACPI tables are not available.
+    #[cfg(feature = "acpi")]
+    Acpi(AcpiError),
+    /// The FADT is not found.
+    NoFadt,
+    /// The \_S5 object is not found in the DSDT.
+    NoS5,
+    /// ACPI mode could not be enabled.
+    AcpiNotEnabled,
+    /// The ACPI reset register is not supported.
+    NoResetRegister,
+    /// The APM BIOS is not available.
+    NoApm,
+}
+
+impl fmt::Display for PowerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    #[cfg(feature = "acpi")]
+	    Self::Acpi(err) =>
+		write!(f, "{}", err),
+	    Self::NoFadt =>
+		write!(f, "ACPI FADT is not found"),
+	    Self::NoS5 =>
+		write!(f, "ACPI \\_S5 is not found"),
+	    Self::AcpiNotEnabled =>
+		write!(f, "ACPI mode could not be enabled"),
+	    Self::NoResetRegister =>
+		write!(f, "ACPI reset register is not supported"),
+	    Self::NoApm =>
+		write!(f, "APM BIOS is not available"),
+	}
+    }
+}
+
+#[cfg(feature = "acpi")]
+impl From<AcpiError> for PowerError {
+    fn from(err: AcpiError) -> Self {
+	Self::Acpi(err)
+    }
+}
+
+
+/// Actions at the end of a run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExitAction {
+    Halt,
+    Shutdown,
+    Reboot,
+}
+
+impl ExitAction {
+    /// Returns the action given by `on_exit=` (Halt by default).
+    pub fn from_cmdline() -> Self {
+	match cmdline::value("on_exit") {
+	    Some("shutdown") => Self::Shutdown,
+	    Some("reboot") => Self::Reboot,
+	    _ => Self::Halt,
+	}
+    }
+}
+
+
+/// Actions after a panic is reported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PanicPolicy {
+    /// Ends the run as failed (cf. [`finish`]).
+    Finish,
+    /// Halts the processor (even if QEMU could exit).
+    Halt,
+    /// Reboots after the delay.
+    Reboot(Duration),
+    /// Exits QEMU with the failure code, or halts if it cannot.
+    ExitQemu,
+}
+
+impl PanicPolicy {
+    /// The delay of `panic_policy=reboot` (without `:<secs>`)
+    pub const DEFAULT_REBOOT_DELAY: Duration = Duration::from_secs(5);
+
+    ///
+    /// Returns the policy given by `panic_policy=` (Finish by default,
+    /// or if the value is unknown).
+    ///
+    pub fn from_cmdline() -> Self {
+	let Some(value) = cmdline::value("panic_policy") else {
+	    return Self::Finish;
+	};
+	let (name, secs) = match value.split_once(':') {
+	    Some((name, secs)) => (name, secs.parse::<u64>().ok()),
+	    None => (value, None),
+	};
+	match name {
+	    "halt" => Self::Halt,
+	    "reboot" => Self::Reboot(secs.map_or(Self::DEFAULT_REBOOT_DELAY,
+						 Duration::from_secs)),
+	    "exit" => Self::ExitQemu,
+	    _ => Self::Finish,
+	}
+    }
+
+    /// Takes the action.
+    pub fn apply(self) -> ! {
+	match self {
+	    Self::Finish => finish(ExitCode::Failed),
+	    Self::Halt => halt(),
+	    Self::Reboot(delay) => {
+		crate::println!("Rebooting in {} seconds...", delay.as_secs());
+		time::sleep(delay);
+		reboot();
+	    },
+	    Self::ExitQemu => {
+		testing::exit_qemu(ExitCode::Failed);
+		halt();
+	    },
+	}
+    }
+}
+
+
+/// Turns the power off.
+pub fn shutdown() -> ! {
+    #[cfg(feature = "acpi")]
+    if let Err(err) = AcpiPm::new().and_then(|pm| pm.enter_s5()) {
+	crate::println!("shutdown: {}", err);
+    }
+    if let Err(err) = apm::power_off() {
+	crate::println!("shutdown: {}", err);
+    }
+    testing::exit_qemu(ExitCode::Success);
+    halt();
+}
+
+/// Resets the system.
+pub fn reboot() -> ! {
+    #[cfg(feature = "acpi")]
+    if let Err(err) = AcpiPm::new().and_then(|pm| pm.reset()) {
+	crate::println!("reboot: {}", err);
+    }
+
+    unsafe {
+	// Pulse the reset line via the keyboard controller.
+	outb(KBC_COMMAND_PORT, KBC_PULSE_RESET);
+	// Full reset via the Reset Control Register.
+	outb(RESET_CONTROL_PORT, RESET_CONTROL_FULL);
+    }
+    halt();
+}
+
+/// Halts the processor forever.
+pub fn halt() -> ! {
+    x86::halt_forever();
+}
+
+///
+/// Ends a run with the exit code.  QEMU exits if isa-debug-exit is
+/// available, otherwise the action given by the command line is taken.
+///
+pub fn finish(exit_code: ExitCode) -> ! {
+    testing::exit_qemu(exit_code);
+
+    match ExitAction::from_cmdline() {
+	ExitAction::Halt => halt(),
+	ExitAction::Shutdown => shutdown(),
+	ExitAction::Reboot => reboot(),
+    }
+}
+
+
+// Keyboard Controller
+const KBC_COMMAND_PORT: u16 = 0x64;
+const KBC_PULSE_RESET: u8 = 0xfe;
+
+// Reset Control Register
+const RESET_CONTROL_PORT: u16 = 0xcf9;
+const RESET_CONTROL_FULL: u8 = 0x0e;	// Full reset, reset CPU, SYS_RST
@@ -0,0 +1,164 @@
+/*!
+
+Gathers what was detected at boot into a report.
+
+[`init`] gathers the CPU, the memory totals, the drives (feature
+`disk`), the VBE version and mode (feature `video`), the screen and
+the presence of ACPI (feature `acpi`) into a [`BootReport`] once at
+startup.  Afterwards, payload code can retrieve it by [`get`].
+
+```ignore
+let report = report::init(boot_info.memory_map(), screen, &ALLOC_UNDER20);
+println!("{}", report);
+
+// Later, e.g. in a payload
+if let Some(report) = report::get() {
+    println!("{} MiB usable", report.memory_usable >> 20);
+}
+```
+
+ */
+
+
+#[cfg(feature = "disk")]
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::fmt;
+
+use crate::bios::int15he820h::AddrRange;
+#[cfg(feature = "disk")]
+use crate::bios::disk::DiskInfo;
+use crate::console::ScreenKind;
+use crate::mu::MuMutex;
+use crate::x86::CpuInfo;
+
+
+/// The VBE version and the current VBE mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VbeReport {
+    pub version: u16,		// VBE Version (BCD, e.g. 0x0300)
+    pub mode: u16,		// Current VBE Mode
+}
+
+impl fmt::Display for VbeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{}.{}, mode {:#x}",
+	       self.version >> 8, self.version & 0xff, self.mode)
+    }
+}
+
+
+/// What was detected at boot.
+#[derive(Clone, Debug)]
+pub struct BootReport {
+    pub cpu: CpuInfo,
+    pub memory_total: u64,	// Bytes of RAM in the memory map
+    pub memory_usable: u64,	// Bytes of Usable RAM
+    #[cfg(feature = "disk")]
+    pub drives: Vec<DiskInfo>,
+    pub vbe: Option<VbeReport>,	// None if VBE is not available
+    pub screen: ScreenKind,
+    pub acpi_revision: Option<u8>,	// None if ACPI is not found
+}
+
+impl fmt::Display for BootReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "Boot report:")?;
+	write!(f, "\r\n  CPU:    {}", self.cpu)?;
+	write!(f, "\r\n  Memory: {} MiB usable / {} MiB total",
+	       self.memory_usable >> 20, self.memory_total >> 20)?;
+	#[cfg(feature = "disk")]
+	{
+	    write!(f, "\r\n  Drives: {}", self.drives.len())?;
+	    for drive in self.drives.iter() {
+		write!(f, "\r\n    {}", drive)?;
+	    }
+	}
+	match self.vbe {
+	    Some(vbe) => write!(f, "\r\n  VBE:    {}", vbe)?,
+	    None => write!(f, "\r\n  VBE:    not available")?,
+	}
+	write!(f, "\r\n  Screen: {}", self.screen)?;
+	match self.acpi_revision {
+	    Some(revision) => write!(f, "\r\n  ACPI:   revision {}", revision),
+	    None => write!(f, "\r\n  ACPI:   not found"),
+	}
+    }
+}
+
+
+// The report gathered by `init`
+static REPORT: MuMutex<Option<BootReport>> = MuMutex::new(None);
+
+
+///
+/// Gathers what was detected into the report, keeps it for [`get`],
+/// then returns it.  It should be called once after the screen is
+/// selected.  Buffers are allocated by `alloc20`.
+///
+pub fn init<A20>(memory_map: &[AddrRange], screen: ScreenKind, alloc20: A20)
+		 -> BootReport
+where
+    A20: Copy + Allocator,
+{
+    let mut memory_total = 0;
+    let mut memory_usable = 0;
+    for entry in memory_map {
+	match entry.atype {
+	    AddrRange::TYPE_USABLE => {
+		memory_total += entry.length;
+		memory_usable += entry.length;
+	    },
+	    AddrRange::TYPE_RESERVED | AddrRange::TYPE_DISABLED => (),
+	    _ => memory_total += entry.length,
+	}
+    }
+
+    let report = BootReport {
+	cpu: CpuInfo::detect(),
+	memory_total,
+	memory_usable,
+	#[cfg(feature = "disk")]
+	drives: crate::bios::disk::enumerate(alloc20),
+	vbe: vbe_report(alloc20),
+	screen,
+	acpi_revision: acpi_revision(),
+    };
+
+    *REPORT.lock() = Some(report.clone());
+    report
+}
+
+/// Returns the report gathered by [`init`] (None if not yet).
+pub fn get() -> Option<BootReport> {
+    REPORT.lock().clone()
+}
+
+// Returns the VBE version and the current VBE mode.
+#[cfg(feature = "video")]
+fn vbe_report<A20>(alloc20: A20) -> Option<VbeReport>
+where
+    A20: Copy + Allocator,
+{
+    let info = crate::bios::int10h4f00h::call(alloc20)?;
+    Some(VbeReport {
+	version: info.version,
+	mode: crate::bios::int10h4f03h::call(),
+    })
+}
+
+#[cfg(not(feature = "video"))]
+fn vbe_report<A20>(_alloc20: A20) -> Option<VbeReport> {
+    None
+}
+
+// Returns the revision of the RSDP (if found).
+#[cfg(feature = "acpi")]
+fn acpi_revision() -> Option<u8> {
+    crate::acpi::find_rsdp().ok().map(| rsdp | rsdp.revision)
+}
+
+#[cfg(not(feature = "acpi"))]
+fn acpi_revision() -> Option<u8> {
+    None
+}
@@ -0,0 +1,117 @@
+//
+// SMBIOS Entry Point (32-bit "_SM_" and 64-bit "_SM3_")
+//
+// Supplementary Resource:
+//	https://wiki.osdev.org/System_Management_BIOS
+//
+
+use core::slice;
+
+use super::SmbiosError;
+use crate::util;
+
+
+///
+/// The location of the structure table found in an entry point.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct EntryPoint {
+    pub major: u8,		// SMBIOS Major Version
+    pub minor: u8,		// SMBIOS Minor Version
+    pub table_addr: usize,	// Address of the Structure Table
+    pub table_max_size: usize,	// (Maximum) Size of the Structure Table
+    pub is_64bit: bool,		// true if found in the 64-bit entry point
+}
+
+impl EntryPoint {
+    ///
+    /// Parses the entry point at the address if its anchor string and
+    /// checksum are valid.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be readable for 0x20 bytes.
+    ///
+    pub unsafe fn at(addr: usize) -> Option<Self> {
+	let bytes = slice::from_raw_parts(addr as *const u8, 0x20);
+
+	if bytes[.. 5] == *b"_SM3_" {
+	    // 64-bit Entry Point (SMBIOS 3.0+)
+	    //	05   : Checksum		06   : Length
+	    //	07-08: Version		0C-0F: Maximum Size
+	    //	10-17: Table Address
+	    let length = bytes[6] as usize;
+	    if !(0x18 ..= 0x20).contains(&length) ||
+		!is_checksum_valid(&bytes[.. length]) {
+		return None;
+	    }
+	    Some(Self {
+		major: bytes[7],
+		minor: bytes[8],
+		table_addr: read_u64(bytes, 0x10) as usize,
+		table_max_size: read_u32(bytes, 0x0c) as usize,
+		is_64bit: true,
+	    })
+	} else if bytes[.. 4] == *b"_SM_" {
+	    // 32-bit Entry Point (SMBIOS 2.1+)
+	    //	04   : Checksum		05   : Length
+	    //	06-07: Version
+	    //	10-14: "_DMI_"		16-17: Table Length
+	    //	18-1B: Table Address
+	    let length = bytes[5] as usize;
+	    if !(0x1e ..= 0x1f).contains(&length) ||
+		!is_checksum_valid(&bytes[.. length]) ||
+		bytes[0x10 .. 0x15] != *b"_DMI_" {
+		return None;
+	    }
+	    Some(Self {
+		major: bytes[6],
+		minor: bytes[7],
+		table_addr: read_u32(bytes, 0x18) as usize,
+		table_max_size: read_u16(bytes, 0x16) as usize,
+		is_64bit: false,
+	    })
+	} else {
+	    None
+	}
+    }
+}
+
+
+///
+/// Finds the SMBIOS entry point.
+///
+/// It searches for the anchor strings on 16-byte boundaries in the
+/// BIOS area (0xF0000 - 0xFFFFF).  The 64-bit entry point is
+/// preferred if both are found.
+///
+pub fn find_entry_point() -> Result<EntryPoint, SmbiosError> {
+    let mut found = None;
+    for addr in (0xf0000 .. 0x100000 - 0x20).step_by(16) {
+	if let Some(entry) = unsafe { EntryPoint::at(addr) } {
+	    if entry.is_64bit {
+		return Ok(entry);
+	    }
+	    found.get_or_insert(entry);
+	}
+    }
+    found.ok_or(SmbiosError::EntryPointNotFound)
+}
+
+
+// Returns true if the sum of all bytes is zero.
+fn is_checksum_valid(bytes: &[u8]) -> bool {
+    util::sum8(bytes) == 0
+}
+
+pub(super) fn read_u16(bytes: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([bytes[off], bytes[off + 1]])
+}
+
+pub(super) fn read_u32(bytes: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes(bytes[off .. off + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], off: usize) -> u64 {
+    u64::from_le_bytes(bytes[off .. off + 8].try_into().unwrap())
+}
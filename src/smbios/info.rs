@@ -0,0 +1,128 @@
+//
+// Typed Views of SMBIOS Structures
+//
+
+use core::fmt;
+
+use super::Structure;
+
+
+/// BIOS Information (Type 0)
+#[derive(Clone, Copy, Debug)]
+pub struct BiosInfo {
+    pub vendor: &'static str,		//04: Vendor
+    pub version: &'static str,		//05: BIOS Version
+    pub release_date: &'static str,	//08: BIOS Release Date
+}
+
+impl BiosInfo {
+    pub const TYPE: u8 = 0;
+}
+
+impl From<Structure> for BiosInfo {
+    fn from(s: Structure) -> Self {
+	Self {
+	    vendor: s.string(0x04).unwrap_or(""),
+	    version: s.string(0x05).unwrap_or(""),
+	    release_date: s.string(0x08).unwrap_or(""),
+	}
+    }
+}
+
+impl fmt::Display for BiosInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{} {} ({})", self.vendor, self.version, self.release_date)
+    }
+}
+
+
+/// System Information (Type 1)
+#[derive(Clone, Copy, Debug)]
+pub struct SystemInfo {
+    pub manufacturer: &'static str,	//04: Manufacturer
+    pub product_name: &'static str,	//05: Product Name
+    pub version: &'static str,		//06: Version
+}
+
+impl SystemInfo {
+    pub const TYPE: u8 = 1;
+}
+
+impl From<Structure> for SystemInfo {
+    fn from(s: Structure) -> Self {
+	Self {
+	    manufacturer: s.string(0x04).unwrap_or(""),
+	    product_name: s.string(0x05).unwrap_or(""),
+	    version: s.string(0x06).unwrap_or(""),
+	}
+    }
+}
+
+impl fmt::Display for SystemInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{} {} {}",
+	       self.manufacturer, self.product_name, self.version)
+    }
+}
+
+
+/// Memory Device (Type 17)
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryDevice {
+    pub size_mib: Option<u64>,		//0C, 1C: Size (None if unknown)
+    pub locator: &'static str,		//10: Device Locator
+    pub bank_locator: &'static str,	//11: Bank Locator
+    pub memory_type: u8,		//12: Memory Type
+    pub speed_mts: u16,			//15: Speed in MT/s (0 if unknown)
+    pub manufacturer: &'static str,	//17: Manufacturer
+    pub part_number: &'static str,	//1A: Part Number
+}
+
+impl MemoryDevice {
+    pub const TYPE: u8 = 17;
+
+    /// Returns true if a memory device is installed in the slot.
+    pub fn is_installed(&self) -> bool {
+	self.size_mib != Some(0)
+    }
+}
+
+impl From<Structure> for MemoryDevice {
+    fn from(s: Structure) -> Self {
+	// Size: 0 = not installed, 0xFFFF = unknown, 0x7FFF = see 1Ch.
+	//	 Bit 15 = 0 if in MiB, 1 if in KiB.
+	let size_mib = match s.word(0x0c) {
+	    None | Some(0xffff) => None,
+	    Some(0x7fff) => s.dword(0x1c).map(|mib| (mib & 0x7fff_ffff) as u64),
+	    Some(size) if (size & 0x8000) != 0 =>
+		Some(((size & 0x7fff) as u64) / 1024),
+	    Some(size) => Some(size as u64),
+	};
+
+	Self {
+	    size_mib,
+	    locator: s.string(0x10).unwrap_or(""),
+	    bank_locator: s.string(0x11).unwrap_or(""),
+	    memory_type: s.byte(0x12).unwrap_or(0),
+	    speed_mts: s.word(0x15).unwrap_or(0),
+	    manufacturer: s.string(0x17).unwrap_or(""),
+	    part_number: s.string(0x1a).unwrap_or(""),
+	}
+    }
+}
+
+impl fmt::Display for MemoryDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{}: ", self.locator)?;
+	match self.size_mib {
+	    Some(0) => return write!(f, "(not installed)"),
+	    Some(mib) => write!(f, "{} MiB", mib)?,
+	    None => write!(f, "unknown size")?,
+	}
+	if self.speed_mts != 0 {
+	    write!(f, ", {} MT/s", self.speed_mts)?;
+	}
+	write!(f, ", type={:#x} {} {}",
+	       self.memory_type, self.manufacturer, self.part_number)
+    }
+}
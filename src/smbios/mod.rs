@@ -0,0 +1,54 @@
+/*!
+
+Provides access to SMBIOS tables.
+
+* `find_entry_point` - scans the BIOS area for the SMBIOS entry point
+  (32-bit "_SM_" or 64-bit "_SM3_"), and validates its checksum.
+
+* `Smbios` - walks structures in the structure table.
+
+* `BiosInfo`, `SystemInfo` and `MemoryDevice` - typed views of
+  structures of type 0, 1 and 17, respectively.
+
+```ignore
+let smbios = smbios::Smbios::new()?;
+if let Some(bios) = smbios.bios_info() {
+    println!("BIOS: {} {}", bios.vendor, bios.version);
+}
+```
+
+# Supplementary Resources
+
+* [SMBIOS](https://www.dmtf.org/standards/smbios) (DMTF)
+* [System Management BIOS](https://wiki.osdev.org/System_Management_BIOS)
+  (OSDev Wiki)
+
+ */
+
+
+#[doc(hidden)] pub mod entry_point;
+#[doc(hidden)] pub mod info;
+#[doc(hidden)] pub mod table;
+
+#[doc(inline)] pub use self::entry_point::{EntryPoint, find_entry_point};
+#[doc(inline)] pub use self::info::{BiosInfo, MemoryDevice, SystemInfo};
+#[doc(inline)] pub use self::table::{Smbios, Structure};
+
+use core::fmt;
+
+
+/// Errors detected while accessing SMBIOS tables.
+#[derive(Debug)]
+pub enum SmbiosError {
+    /// The entry point is not found.
+    EntryPointNotFound,
+}
+
+impl fmt::Display for SmbiosError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::EntryPointNotFound =>
+		write!(f, "SMBIOS entry point is not found"),
+	}
+    }
+}
@@ -0,0 +1,147 @@
+//
+// SMBIOS Structure Table
+//
+// Each structure consists of a formatted area (starting with a 4-byte
+// header) and an unformatted area of strings.  The strings are
+// terminated by NUL, and the unformatted area is terminated by an
+// additional NUL (i.e., two NULs if there are no strings).
+//
+
+use core::slice;
+
+use super::entry_point::{read_u16, read_u32};
+use super::{EntryPoint, SmbiosError, find_entry_point};
+use super::info::{BiosInfo, MemoryDevice, SystemInfo};
+
+
+/// A structure in the SMBIOS structure table.
+#[derive(Clone, Copy, Debug)]
+pub struct Structure {
+    pub stype: u8,			// Type
+    pub handle: u16,			// Handle
+    pub formatted: &'static [u8],	// Formatted Area (with header)
+    pub strings: &'static [u8],		// Unformatted Area (Strings)
+}
+
+impl Structure {
+    /// Type 127: End-of-Table
+    pub const END_OF_TABLE: u8 = 127;
+
+    /// Returns the byte at the offset in the formatted area.
+    pub fn byte(&self, off: usize) -> Option<u8> {
+	self.formatted.get(off).copied()
+    }
+
+    /// Returns the word at the offset in the formatted area.
+    pub fn word(&self, off: usize) -> Option<u16> {
+	(off + 2 <= self.formatted.len())
+	    .then(|| read_u16(self.formatted, off))
+    }
+
+    /// Returns the dword at the offset in the formatted area.
+    pub fn dword(&self, off: usize) -> Option<u32> {
+	(off + 4 <= self.formatted.len())
+	    .then(|| read_u32(self.formatted, off))
+    }
+
+    ///
+    /// Returns the string referred by the byte at the offset in the
+    /// formatted area.  (String numbers start with 1, and 0 means
+    /// no string)
+    ///
+    pub fn string(&self, off: usize) -> Option<&'static str> {
+	let index = self.byte(off)? as usize;
+	if index == 0 {
+	    return None;
+	}
+	let bytes = self.strings.split(|&b| b == 0).nth(index - 1)?;
+	core::str::from_utf8(bytes).ok()
+    }
+}
+
+
+/// Provides access to the SMBIOS structure table.
+#[derive(Clone, Copy, Debug)]
+pub struct Smbios {
+    pub entry: EntryPoint,
+}
+
+impl Smbios {
+    /// Finds the entry point of the structure table.
+    pub fn new() -> Result<Self, SmbiosError> {
+	Ok(Self { entry: find_entry_point()? })
+    }
+
+    /// Returns an iterator over the structures in the table.
+    pub fn structures(&self) -> Structures {
+	let table = unsafe {
+	    slice::from_raw_parts(self.entry.table_addr as *const u8,
+				  self.entry.table_max_size)
+	};
+	Structures { table, off: 0 }
+    }
+
+    /// Returns the first structure of the type.
+    pub fn find(&self, stype: u8) -> Option<Structure> {
+	self.structures().find(|s| s.stype == stype)
+    }
+
+    /// Returns the BIOS Information (Type 0).
+    pub fn bios_info(&self) -> Option<BiosInfo> {
+	self.find(BiosInfo::TYPE).map(BiosInfo::from)
+    }
+
+    /// Returns the System Information (Type 1).
+    pub fn system_info(&self) -> Option<SystemInfo> {
+	self.find(SystemInfo::TYPE).map(SystemInfo::from)
+    }
+
+    /// Returns an iterator over the Memory Devices (Type 17).
+    pub fn memory_devices(&self) -> impl Iterator<Item = MemoryDevice> {
+	self.structures()
+	    .filter(|s| s.stype == MemoryDevice::TYPE)
+	    .map(MemoryDevice::from)
+    }
+}
+
+
+/// An iterator over the structures in the table.
+pub struct Structures {
+    table: &'static [u8],
+    off: usize,
+}
+
+impl Iterator for Structures {
+    type Item = Structure;
+
+    fn next(&mut self) -> Option<Structure> {
+	let rest = self.table.get(self.off ..)?;
+	if rest.len() < 4 {
+	    return None;
+	}
+
+	let length = rest[1] as usize;
+	if length < 4 || length > rest.len() {
+	    return None;
+	}
+
+	// Find the double NUL terminating the strings.
+	let strings_end = rest[length ..].windows(2)
+	    .position(|w| w == [0, 0])?;
+	let strings = &rest[length .. length + strings_end + 1];
+
+	let structure = Structure {
+	    stype: rest[0],
+	    handle: read_u16(rest, 2),
+	    formatted: &rest[.. length],
+	    strings,
+	};
+	if structure.stype == Structure::END_OF_TABLE {
+	    self.off = self.table.len();
+	} else {
+	    self.off += length + strings_end + 2;
+	}
+
+	Some(structure)
+    }
+}
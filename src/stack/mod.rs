@@ -0,0 +1,15 @@
+/*!
+
+Provides stack utilities.
+
+* `Watermark` - measures the high-water mark of a stack region by
+  filling it with a pattern in advance.  It can instrument any stack
+  region (e.g., IST stacks, AP stacks and user stacks), not just the
+  lmbios stack reported by `bios::StackUsage`.
+
+ */
+
+
+#[doc(hidden)] pub mod watermark;
+
+#[doc(inline)] pub use self::watermark::Watermark;
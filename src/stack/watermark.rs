@@ -0,0 +1,113 @@
+//
+// Stack Watermark - Measures the high-water mark of a stack region.
+//
+
+use core::arch::asm;
+use core::fmt;
+use core::ptr::{read_volatile, write_volatile};
+
+
+///
+/// Measures the high-water mark of a stack region.
+///
+/// A stack region (growing downward from `end` to `start`) is filled
+/// with a pattern in advance.  Later, the lowest address whose value
+/// differs from the pattern tells how deep the stack has grown.
+///
+/// Both `start` and `end` must be 8-byte aligned.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Watermark {
+    pub start: usize,	// Start Address (the lowest address)
+    pub end: usize,	// End Address (exclusive, the initial stack top)
+    pub pattern: u64,	// Fill Pattern
+}
+
+impl Watermark {
+    /// The default fill pattern.
+    pub const DEFAULT_PATTERN: u64 = 0x5354_4b57_4d41_524b;  // "KRAMWKTS"
+
+    /// Returns a watermark of the stack region with the default pattern.
+    pub const fn new(start: usize, end: usize) -> Self {
+	Self::with_pattern(start, end, Self::DEFAULT_PATTERN)
+    }
+
+    /// Returns a watermark of the stack region with the pattern.
+    pub const fn with_pattern(start: usize, end: usize, pattern: u64)
+			      -> Self {
+	Self { start, end, pattern }
+    }
+
+    /// Returns the size in bytes of the stack region.
+    pub fn size(&self) -> usize {
+	self.end - self.start
+    }
+
+    ///
+    /// Fills the whole stack region with the pattern.
+    ///
+    /// # Safety
+    ///
+    /// The stack region must not be in use (e.g., before an AP or a
+    /// task starts using it).
+    ///
+    pub unsafe fn fill(&self) {
+	self.fill_range(self.start, self.end);
+    }
+
+    ///
+    /// Fills the unused part of the current stack with the pattern.
+    /// That is, the range from `start` to the current stack pointer.
+    ///
+    /// # Safety
+    ///
+    /// The stack region must be the current stack, and no data below
+    /// the stack pointer must be in use (i.e., no red zone).
+    ///
+    pub unsafe fn fill_current(&self) {
+	let rsp: usize;
+	asm!("mov {}, rsp", out(reg) rsp, options(nomem, nostack));
+
+	if self.start < rsp && rsp <= self.end {
+	    self.fill_range(self.start, rsp & !7);
+	}
+    }
+
+    /// Returns the lowest address whose value differs from the pattern
+    /// (i.e., the high-water mark).  Returns `end` if unused.
+    pub fn high_water(&self) -> usize {
+	let mut addr = self.start;
+	while addr < self.end {
+	    if unsafe { read_volatile(addr as *const u64) } != self.pattern {
+		break;
+	    }
+	    addr += 8;
+	}
+	addr
+    }
+
+    /// Returns the maximum usage in bytes of the stack region.
+    pub fn max_usage(&self) -> usize {
+	self.end - self.high_water()
+    }
+
+    /// Returns true if the stack has (possibly) reached its bottom.
+    pub fn is_exhausted(&self) -> bool {
+	self.high_water() == self.start
+    }
+
+    unsafe fn fill_range(&self, start: usize, end: usize) {
+	let mut addr = start;
+	while addr < end {
+	    write_volatile(addr as *mut u64, self.pattern);
+	    addr += 8;
+	}
+    }
+}
+
+impl fmt::Display for Watermark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{:#x} / {:#x} used in {:#x} - {:#x}",
+	       self.max_usage(), self.size(), self.start, self.end)
+    }
+}
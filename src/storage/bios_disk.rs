@@ -0,0 +1,151 @@
+//
+// BIOS Disk - A drive of BIOS as a block device.
+//
+// The extended services (INT 13h AH=42h and AH=43h) are used if the
+// drive supports them (cf. `bios::disk::probe`).  Otherwise, blocks
+// are transferred by the CHS services (INT 13h AH=02h and AH=03h)
+// within a track at a time.  Either way, the transfers are retried
+// (cf. `bios::disk`) through buffers in 20-bit address space.
+//
+
+use core::alloc::Allocator;
+use core::cmp::min;
+
+use super::{BlockDevice, BlockError};
+use crate::bios::disk::{self, DiskInfo};
+use crate::bios::int13h08h::{self, DriveParams};
+use crate::bios::{int13h02h, int13h03h, int13h42h, int13h43h};
+use crate::bios::is_cdrom_drive;
+
+
+// The maximum number of bytes read by one call of `int13h42h`, which
+// bounds the buffer in 20-bit address space (= 127 sectors of 512
+// bytes, the most transferred by one BIOS call).
+const MAX_EXT_NBYTES: usize = 127 * 512;
+
+
+/// A drive of BIOS implementing `BlockDevice`.
+pub struct BiosDisk<A20>
+where
+    A20: Copy + Allocator,
+{
+    info: DiskInfo,
+    geometry: Option<DriveParams>,	// CHS geometry (w/o extensions)
+    alloc20: A20,
+}
+
+impl<A20> BiosDisk<A20>
+where
+    A20: Copy + Allocator,
+{
+    ///
+    /// Probes the drive, then returns it if present.  Buffers are
+    /// allocated by `alloc20` on each transfer.
+    ///
+    pub fn open(drive_id: u8, alloc20: A20) -> Option<Self> {
+	let info = disk::probe(drive_id, alloc20)?;
+	let geometry = match info.edd_version {
+	    Some(_) => None,
+	    None => Some(int13h08h::call(drive_id)?),
+	};
+	Some(Self { info, geometry, alloc20 })
+    }
+
+    /// Returns the drive present.
+    pub fn info(&self) -> &DiskInfo {
+	&self.info
+    }
+
+    // Returns the CHS address of the LBA and the number of blocks to
+    // the end of its track.
+    fn chs(params: &DriveParams, lba: u64) -> (u16, u8, u8, u64) {
+	let (cylinder, head, sector) = params.lba_to_chs(lba as u32);
+	let nsectors = params.sectors_to_track_end(sector) as u64;
+	(cylinder, head, sector, nsectors)
+    }
+}
+
+impl<A20> BlockDevice for BiosDisk<A20>
+where
+    A20: Copy + Allocator,
+{
+    fn block_size(&self) -> usize {
+	self.info.sector_size
+    }
+
+    fn num_blocks(&self) -> u64 {
+	self.info.sectors
+    }
+
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8])
+		   -> Result<(), BlockError> {
+	let count = self.check_range(lba, buf.len())?;
+	let drive_id = self.info.drive_id;
+	let block_size = self.block_size();
+
+	let mut done = 0;
+	while done < count {
+	    let cur_lba = lba + done;
+	    let (data, n) = match &self.geometry {
+		None => {
+		    let max_blocks = (MAX_EXT_NBYTES / block_size) as u64;
+		    let n = min(count - done, max_blocks);
+		    let data = int13h42h::call(drive_id, cur_lba, n as u16,
+					       self.alloc20);
+		    (data, n)
+		},
+		Some(params) => {
+		    let (cylinder, head, sector, n) =
+			Self::chs(params, cur_lba);
+		    let n = min(count - done, n);
+		    let data = int13h02h::call(drive_id, cylinder, head,
+					       sector, n as u8, self.alloc20);
+		    (data, n)
+		},
+	    };
+	    let data = data.ok_or(BlockError::Io { lba: cur_lba, count: n })?;
+
+	    let offset = done as usize * block_size;
+	    let len = n as usize * block_size;
+	    buf[offset .. offset + len].copy_from_slice(&data[.. len]);
+	    done += n;
+	}
+
+	Ok(())
+    }
+
+    fn write_blocks(&mut self, lba: u64, buf: &[u8])
+		    -> Result<(), BlockError> {
+	if is_cdrom_drive(self.info.drive_id) {
+	    return Err(BlockError::ReadOnly);
+	}
+	let count = self.check_range(lba, buf.len())?;
+	let drive_id = self.info.drive_id;
+	let block_size = self.block_size();
+
+	let Some(params) = self.geometry else {
+	    // Note: `int13h43h` splits the transfer into chunks by itself.
+	    if !int13h43h::call(drive_id, lba, buf, self.alloc20) {
+		return Err(BlockError::Io { lba, count });
+	    }
+	    return Ok(());
+	};
+
+	let mut done = 0;
+	while done < count {
+	    let cur_lba = lba + done;
+	    let (cylinder, head, sector, n) = Self::chs(&params, cur_lba);
+	    let n = min(count - done, n);
+
+	    let offset = done as usize * block_size;
+	    let data = &buf[offset .. offset + n as usize * block_size];
+	    if !int13h03h::call(drive_id, cylinder, head, sector, data,
+				self.alloc20) {
+		return Err(BlockError::Io { lba: cur_lba, count: n });
+	    }
+	    done += n;
+	}
+
+	Ok(())
+    }
+}
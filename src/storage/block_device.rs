@@ -0,0 +1,99 @@
+//
+// Block Device - A common interface of devices made of blocks.
+//
+
+use core::fmt;
+
+
+/// Errors detected by block devices.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockError {
+    /// The buffer is not a multiple of the block size (or empty).
+    Misaligned { len: usize },
+    /// The blocks are beyond the end of the device.
+    OutOfRange { lba: u64, count: u64 },
+    /// The device cannot be written.
+    ReadOnly,
+    /// Transferring the blocks failed.
+    Io { lba: u64, count: u64 },
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Misaligned { len } =>
+		write!(f, "Buffer is not a multiple of blocks ({} bytes)",
+		       len),
+	    Self::OutOfRange { lba, count } =>
+		write!(f, "Blocks {}+{} are out of range", lba, count),
+	    Self::ReadOnly =>
+		write!(f, "Device is read-only"),
+	    Self::Io { lba, count } =>
+		write!(f, "Failed to transfer blocks {}+{}", lba, count),
+	}
+    }
+}
+
+
+///
+/// A device made of fixed-size blocks addressed by LBA.
+///
+/// A buffer passed to `read_blocks` and `write_blocks` must be a
+/// multiple of the block size, whose length tells the number of the
+/// blocks transferred.
+///
+pub trait BlockDevice {
+    /// Returns the size in bytes of a block.
+    fn block_size(&self) -> usize;
+
+    /// Returns the number of blocks.
+    fn num_blocks(&self) -> u64;
+
+    /// Reads the blocks from `lba` into `buf`.
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8])
+		   -> Result<(), BlockError>;
+
+    /// Writes `buf` into the blocks from `lba`.
+    fn write_blocks(&mut self, lba: u64, buf: &[u8])
+		    -> Result<(), BlockError>;
+
+    ///
+    /// Checks that `len` bytes from `lba` are whole blocks within the
+    /// device, then returns the number of the blocks.
+    ///
+    fn check_range(&self, lba: u64, len: usize) -> Result<u64, BlockError> {
+	let block_size = self.block_size();
+	if len == 0 || !len.is_multiple_of(block_size) {
+	    return Err(BlockError::Misaligned { len });
+	}
+	let count = (len / block_size) as u64;
+	match lba.checked_add(count) {
+	    Some(end) if end <= self.num_blocks() => Ok(count),
+	    _ => Err(BlockError::OutOfRange { lba, count }),
+	}
+    }
+}
+
+// A block device can be lent (e.g., to `fs::DiskSlice`).
+impl<D> BlockDevice for &mut D
+where
+    D: BlockDevice + ?Sized,
+{
+    fn block_size(&self) -> usize {
+	(**self).block_size()
+    }
+
+    fn num_blocks(&self) -> u64 {
+	(**self).num_blocks()
+    }
+
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8])
+		   -> Result<(), BlockError> {
+	(**self).read_blocks(lba, buf)
+    }
+
+    fn write_blocks(&mut self, lba: u64, buf: &[u8])
+		    -> Result<(), BlockError> {
+	(**self).write_blocks(lba, buf)
+    }
+}
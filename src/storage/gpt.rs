@@ -0,0 +1,491 @@
+//
+// GPT - Reads the GUID Partition Table of a block device.
+//
+// The primary header is at LBA 1, and the backup header at the last
+// block.  A header is valid if its signature, its size and its CRC-32
+// are correct.  The partition entry array it points to must match the
+// CRC-32 recorded in the header.  If the primary header (or its array)
+// is broken, the backup header is used instead.
+//
+// All fields are little-endian.  LBAs are 64-bit, so that disks bigger
+// than 2TiB (the limit of MBR with 512-byte blocks) can be handled.
+//
+// Supplementary Resources:
+//	https://uefi.org/specs/UEFI/2.10/05_GUID_Partition_Table_Format.html
+//	https://en.wikipedia.org/wiki/GUID_Partition_Table
+//
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::{BlockDevice, BlockError};
+use crate::util::bytes::{le32, le64};
+use crate::util::crc32;
+
+
+// "EFI PART"
+const SIGNATURE: &[u8; 8] = b"EFI PART";
+
+// The LBA of the primary header
+const PRIMARY_HEADER_LBA: u64 = 1;
+
+// The size of the fields of the header (covered by `header_crc32`)
+const MIN_HEADER_SIZE: usize = 92;
+
+// The size of the fields of a partition entry
+const MIN_ENTRY_SIZE: usize = 128;
+
+// The maximum size of the partition entry array loaded into the heap.
+// (128 entries of 128 bytes are usual.)
+const MAX_ENTRIES_NBYTES: usize = 64 * 1024;
+
+// The number of UTF-16 code units of a partition name
+const NAME_LEN: usize = 36;
+
+
+/// Errors returned by [`Gpt::read`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GptError {
+    /// Reading the device failed.
+    Block(BlockError),
+    /// The block has no GPT header.
+    NoSignature { lba: u64 },
+    /// A field of the header is invalid.
+    InvalidHeader { lba: u64 },
+    /// The CRC-32 of the header does not match.
+    HeaderCrc { lba: u64, expected: u32, actual: u32 },
+    /// The CRC-32 of the partition entry array does not match.
+    EntriesCrc { lba: u64, expected: u32, actual: u32 },
+}
+
+impl fmt::Display for GptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Block(err) =>
+		write!(f, "GPT: {}", err),
+	    Self::NoSignature { lba } =>
+		write!(f, "GPT: No header at LBA {}", lba),
+	    Self::InvalidHeader { lba } =>
+		write!(f, "GPT: Invalid header at LBA {}", lba),
+	    Self::HeaderCrc { lba, expected, actual } =>
+		write!(f, "GPT: CRC-32 of the header at LBA {} is {:#010x} \
+			   (expected {:#010x})", lba, actual, expected),
+	    Self::EntriesCrc { lba, expected, actual } =>
+		write!(f, "GPT: CRC-32 of the entries at LBA {} is {:#010x} \
+			   (expected {:#010x})", lba, actual, expected),
+	}
+    }
+}
+
+impl From<BlockError> for GptError {
+    fn from(err: BlockError) -> Self {
+	Self::Block(err)
+    }
+}
+
+
+/// A GUID (in the mixed-endian layout of EFI_GUID).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Guid {
+    pub data1: u32,
+    pub data2: u16,
+    pub data3: u16,
+    pub data4: [u8; 8],
+}
+
+impl Guid {
+    /// The type of unused partition entries
+    pub const UNUSED: Self = Self::new(0, 0, 0, [0; 8]);
+
+    /// EFI System Partition
+    pub const EFI_SYSTEM: Self = Self::new(
+	0xc12a7328, 0xf81f, 0x11d2,
+	[0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b]);
+
+    /// BIOS Boot Partition (of GRUB)
+    pub const BIOS_BOOT: Self = Self::new(
+	0x21686148, 0x6449, 0x6e6f,
+	[0x74, 0x4e, 0x65, 0x65, 0x64, 0x45, 0x46, 0x49]);
+
+    /// Microsoft Basic Data Partition (FAT, NTFS, exFAT)
+    pub const BASIC_DATA: Self = Self::new(
+	0xebd0a0a2, 0xb9e5, 0x4433,
+	[0x87, 0xc0, 0x68, 0xb6, 0xb7, 0x26, 0x99, 0xc7]);
+
+    /// Linux Filesystem Data
+    pub const LINUX_FILESYSTEM: Self = Self::new(
+	0x0fc63daf, 0x8483, 0x4772,
+	[0x8e, 0x79, 0x3d, 0x69, 0xd8, 0x47, 0x7d, 0xe4]);
+
+    /// Returns a GUID of the fields.
+    pub const fn new(data1: u32, data2: u16, data3: u16, data4: [u8; 8])
+		     -> Self {
+	Self { data1, data2, data3, data4 }
+    }
+
+    /// Returns the GUID stored in 16 bytes (as on disk).
+    pub fn from_bytes(bytes: &[u8; 16]) -> Self {
+	let mut data4 = [0; 8];
+	data4.copy_from_slice(&bytes[8 ..]);
+	Self {
+	    data1: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+	    data2: u16::from_le_bytes([bytes[4], bytes[5]]),
+	    data3: u16::from_le_bytes([bytes[6], bytes[7]]),
+	    data4,
+	}
+    }
+
+    /// Returns the name of a well-known partition type (if any).
+    pub fn type_name(&self) -> Option<&'static str> {
+	match *self {
+	    Self::UNUSED => Some("Unused"),
+	    Self::EFI_SYSTEM => Some("EFI System"),
+	    Self::BIOS_BOOT => Some("BIOS Boot"),
+	    Self::BASIC_DATA => Some("Basic Data"),
+	    Self::LINUX_FILESYSTEM => Some("Linux Filesystem"),
+	    _ => None,
+	}
+    }
+}
+
+// E.g., C12A7328-F81F-11D2-BA4B-00A0C93EC93B
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	let d = &self.data4;
+	write!(f, "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-\
+		   {:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+	       self.data1, self.data2, self.data3, d[0], d[1],
+	       d[2], d[3], d[4], d[5], d[6], d[7])
+    }
+}
+
+
+/// The fields of a GPT header.
+#[derive(Clone, Copy, Debug)]
+pub struct GptHeader {
+    pub revision: u32,
+    pub my_lba: u64,			// The LBA of this header
+    pub alternate_lba: u64,		// The LBA of the other header
+    pub first_usable_lba: u64,
+    pub last_usable_lba: u64,
+    pub disk_guid: Guid,
+    pub entries_lba: u64,		// The First Block of the Entries
+    pub num_entries: u32,
+    pub entry_size: u32,
+    pub entries_crc32: u32,
+}
+
+impl GptHeader {
+    // Parses the header in the block at the LBA, and checks its CRC-32.
+    fn parse(block: &[u8], lba: u64) -> Result<Self, GptError> {
+	if block.len() < MIN_HEADER_SIZE || &block[0 .. 8] != SIGNATURE {
+	    return Err(GptError::NoSignature { lba });
+	}
+
+	let header_size = le32(block, 12) as usize;
+	if header_size < MIN_HEADER_SIZE || header_size > block.len() {
+	    return Err(GptError::InvalidHeader { lba });
+	}
+
+	// The CRC-32 is computed with the field itself zeroed.
+	let expected = le32(block, 16);
+	let mut copy = block[.. header_size].to_vec();
+	copy[16 .. 20].fill(0);
+	let actual = crc32(&copy);
+	if actual != expected {
+	    return Err(GptError::HeaderCrc { lba, expected, actual });
+	}
+
+	let header = Self {
+	    revision: le32(block, 8),
+	    my_lba: le64(block, 24),
+	    alternate_lba: le64(block, 32),
+	    first_usable_lba: le64(block, 40),
+	    last_usable_lba: le64(block, 48),
+	    disk_guid: guid(block, 56),
+	    entries_lba: le64(block, 72),
+	    num_entries: le32(block, 80),
+	    entry_size: le32(block, 84),
+	    entries_crc32: le32(block, 88),
+	};
+
+	let entry_size = header.entry_size as usize;
+	if header.my_lba != lba
+	    || entry_size < MIN_ENTRY_SIZE || !entry_size.is_multiple_of(8)
+	    || header.entries_nbytes() > MAX_ENTRIES_NBYTES
+	    || header.first_usable_lba > header.last_usable_lba
+	{
+	    return Err(GptError::InvalidHeader { lba });
+	}
+	Ok(header)
+    }
+
+    // Returns the size in bytes of the partition entry array.
+    fn entries_nbytes(&self) -> usize {
+	(self.num_entries as usize).saturating_mul(self.entry_size as usize)
+    }
+}
+
+
+/// A used entry of the partition entry array.
+#[derive(Clone, Copy, Debug)]
+pub struct GptPartition {
+    pub index: usize,			// The Index in the Array (from 0)
+    pub type_guid: Guid,
+    pub unique_guid: Guid,
+    pub first_lba: u64,
+    pub last_lba: u64,			// (inclusive)
+    pub attributes: u64,
+    name: [u16; NAME_LEN],		// UTF-16LE (padded with 0)
+}
+
+impl GptPartition {
+    /// Attribute: Required by the platform
+    pub const ATTR_REQUIRED: u64 = 1 << 0;
+    /// Attribute: No block I/O protocol
+    pub const ATTR_NO_BLOCK_IO: u64 = 1 << 1;
+    /// Attribute: Legacy BIOS bootable
+    pub const ATTR_LEGACY_BIOS_BOOTABLE: u64 = 1 << 2;
+
+    // Parses an entry, then returns it unless it is unused.
+    fn parse(entry: &[u8], index: usize) -> Option<Self> {
+	let type_guid = guid(entry, 0);
+	if type_guid == Guid::UNUSED {
+	    return None;
+	}
+
+	let mut name = [0; NAME_LEN];
+	for (i, unit) in name.iter_mut().enumerate() {
+	    *unit = u16::from_le_bytes([entry[56 + i * 2], entry[57 + i * 2]]);
+	}
+
+	Some(Self {
+	    index,
+	    type_guid,
+	    unique_guid: guid(entry, 16),
+	    first_lba: le64(entry, 32),
+	    last_lba: le64(entry, 40),
+	    attributes: le64(entry, 48),
+	    name,
+	})
+    }
+
+    /// Returns the number of blocks.
+    pub fn num_blocks(&self) -> u64 {
+	(self.last_lba + 1).saturating_sub(self.first_lba)
+    }
+
+    /// Returns the name (invalid UTF-16 is replaced with U+FFFD).
+    pub fn name(&self) -> impl Iterator<Item = char> + '_ {
+	let len = self.name.iter().position(| &unit | unit == 0)
+	    .unwrap_or(NAME_LEN);
+	char::decode_utf16(self.name[.. len].iter().copied())
+	    .map(| ch | ch.unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+}
+
+impl fmt::Display for GptPartition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "Partition {}: LBA {} - {} ", self.index + 1,
+	       self.first_lba, self.last_lba)?;
+	match self.type_guid.type_name() {
+	    Some(name) => write!(f, "{}", name)?,
+	    None => write!(f, "{}", self.type_guid)?,
+	}
+	write!(f, " \"")?;
+	for ch in self.name() {
+	    write!(f, "{}", ch)?;
+	}
+	write!(f, "\"")
+    }
+}
+
+
+/// A GUID Partition Table read from a block device.
+pub struct Gpt {
+    pub header: GptHeader,		// The Valid Header Used
+    pub partitions: Vec<GptPartition>,	// The Used Entries
+}
+
+impl Gpt {
+    ///
+    /// Reads the GPT of the device.  The backup header is used if the
+    /// primary one is broken.  If both are broken, the error of the
+    /// primary one is returned.
+    ///
+    pub fn read<D>(device: &mut D) -> Result<Self, GptError>
+    where
+	D: BlockDevice + ?Sized,
+    {
+	match Self::read_at(device, PRIMARY_HEADER_LBA) {
+	    Err(GptError::Block(err)) => Err(GptError::Block(err)),
+	    Err(err) => {
+		let backup_lba = device.num_blocks().saturating_sub(1);
+		Self::read_at(device, backup_lba).map_err(|_| err)
+	    },
+	    gpt => gpt,
+	}
+    }
+
+    /// Returns the first partition of the type (if any).
+    pub fn find(&self, type_guid: Guid) -> Option<&GptPartition> {
+	self.partitions.iter().find(| p | p.type_guid == type_guid)
+    }
+
+    // Reads the header at the LBA and its partition entry array.
+    fn read_at<D>(device: &mut D, lba: u64) -> Result<Self, GptError>
+    where
+	D: BlockDevice + ?Sized,
+    {
+	let block_size = device.block_size();
+	let mut block = vec![0; block_size];
+	device.read_blocks(lba, &mut block)?;
+	let header = GptHeader::parse(&block, lba)?;
+
+	let nbytes = header.entries_nbytes();
+	let mut entries = vec![0; nbytes.div_ceil(block_size) * block_size];
+	if !entries.is_empty() {
+	    device.read_blocks(header.entries_lba, &mut entries)?;
+	}
+
+	let expected = header.entries_crc32;
+	let actual = crc32(&entries[.. nbytes]);
+	if actual != expected {
+	    return Err(GptError::EntriesCrc { lba, expected, actual });
+	}
+
+	let partitions = entries[.. nbytes]
+	    .chunks_exact(header.entry_size as usize)
+	    .enumerate()
+	    .filter_map(| (index, entry) | GptPartition::parse(entry, index))
+	    .collect();
+	Ok(Self { header, partitions })
+    }
+}
+
+impl fmt::Display for Gpt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "GPT: Disk {} (LBA {} - {})", self.header.disk_guid,
+	       self.header.first_usable_lba, self.header.last_usable_lba)?;
+	for partition in &self.partitions {
+	    write!(f, "\n  {}", partition)?;
+	}
+	Ok(())
+    }
+}
+
+
+fn guid(bytes: &[u8], offset: usize) -> Guid {
+    let mut buf = [0; 16];
+    buf.copy_from_slice(&bytes[offset .. offset + 16]);
+    Guid::from_bytes(&buf)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The number of blocks of the disk image
+    const NUM_BLOCKS: u64 = 128;
+
+    // A block device on memory.
+    struct RamDisk {
+	data: Vec<u8>,
+    }
+
+    impl BlockDevice for RamDisk {
+	fn block_size(&self) -> usize {
+	    512
+	}
+
+	fn num_blocks(&self) -> u64 {
+	    self.data.len() as u64 / 512
+	}
+
+	fn read_blocks(&mut self, lba: u64, buf: &mut [u8])
+		       -> Result<(), BlockError> {
+	    self.check_range(lba, buf.len())?;
+	    let start = lba as usize * 512;
+	    buf.copy_from_slice(&self.data[start .. start + buf.len()]);
+	    Ok(())
+	}
+
+	fn write_blocks(&mut self, _lba: u64, _buf: &[u8])
+			-> Result<(), BlockError> {
+	    Err(BlockError::ReadOnly)
+	}
+    }
+
+    // Writes a header at the LBA pointing to the entries at another LBA.
+    fn put_header(data: &mut [u8], lba: u64, alternate_lba: u64,
+		  entries_lba: u64, entries_crc32: u32) {
+	let header = &mut data[lba as usize * 512 ..][.. 512];
+	header[0 .. 8].copy_from_slice(SIGNATURE);
+	header[8 .. 12].copy_from_slice(&0x0001_0000_u32.to_le_bytes());
+	header[12 .. 16].copy_from_slice(&92_u32.to_le_bytes());
+	header[24 .. 32].copy_from_slice(&lba.to_le_bytes());
+	header[32 .. 40].copy_from_slice(&alternate_lba.to_le_bytes());
+	header[40 .. 48].copy_from_slice(&34_u64.to_le_bytes());
+	header[48 .. 56].copy_from_slice(&(NUM_BLOCKS - 34).to_le_bytes());
+	header[72 .. 80].copy_from_slice(&entries_lba.to_le_bytes());
+	header[80 .. 84].copy_from_slice(&128_u32.to_le_bytes());
+	header[84 .. 88].copy_from_slice(&128_u32.to_le_bytes());
+	header[88 .. 92].copy_from_slice(&entries_crc32.to_le_bytes());
+	let crc = crc32(&header[.. 92]);
+	header[16 .. 20].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    // Returns a disk image with an EFI System Partition named "ESP".
+    fn disk_image() -> Vec<u8> {
+	let mut data = vec![0; NUM_BLOCKS as usize * 512];
+
+	let mut entries = vec![0; 128 * 128];
+	entries[0 .. 4].copy_from_slice(&Guid::EFI_SYSTEM.data1.to_le_bytes());
+	entries[4 .. 6].copy_from_slice(&Guid::EFI_SYSTEM.data2.to_le_bytes());
+	entries[6 .. 8].copy_from_slice(&Guid::EFI_SYSTEM.data3.to_le_bytes());
+	entries[8 .. 16].copy_from_slice(&Guid::EFI_SYSTEM.data4);
+	entries[32 .. 40].copy_from_slice(&34_u64.to_le_bytes());
+	entries[40 .. 48].copy_from_slice(&40_u64.to_le_bytes());
+	for (i, ch) in "ESP".encode_utf16().enumerate() {
+	    let offset = 56 + i * 2;
+	    entries[offset .. offset + 2].copy_from_slice(&ch.to_le_bytes());
+	}
+	let entries_crc32 = crc32(&entries);
+
+	// The primary entries at LBA 2, the backup ones before the backup
+	// header.
+	let last = NUM_BLOCKS - 1;
+	data[2 * 512 ..][.. entries.len()].copy_from_slice(&entries);
+	data[(last as usize - 32) * 512 ..][.. entries.len()]
+	    .copy_from_slice(&entries);
+	put_header(&mut data, 1, last, 2, entries_crc32);
+	put_header(&mut data, last, 1, last - 32, entries_crc32);
+	data
+    }
+
+    #[test]
+    fn read_primary_and_backup() {
+	let mut disk = RamDisk { data: disk_image() };
+	let gpt = Gpt::read(&mut disk).unwrap();
+	assert_eq!(gpt.header.my_lba, 1);
+	let esp = gpt.find(Guid::EFI_SYSTEM).unwrap();
+	assert_eq!((esp.index, esp.first_lba, esp.num_blocks()), (0, 34, 7));
+	assert!(esp.name().eq("ESP".chars()));
+	assert_eq!(gpt.partitions.len(), 1);
+
+	// Break the primary header, then the backup one is used.
+	disk.data[512 + 40] ^= 1;
+	let gpt = Gpt::read(&mut disk).unwrap();
+	assert_eq!(gpt.header.my_lba, NUM_BLOCKS - 1);
+	assert_eq!(gpt.partitions[0].type_guid, Guid::EFI_SYSTEM);
+
+	// Break the backup entries, then the error of the primary one.
+	disk.data[(NUM_BLOCKS as usize - 33) * 512] ^= 1;
+	assert!(matches!(Gpt::read(&mut disk),
+			 Err(GptError::HeaderCrc { lba: 1, .. })));
+	assert_eq!(Guid::EFI_SYSTEM.to_string(),
+		   "C12A7328-F81F-11D2-BA4B-00A0C93EC93B");
+    }
+}
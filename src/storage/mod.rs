@@ -0,0 +1,46 @@
+/*!
+
+Provides block devices.
+
+* `BlockDevice` - a common interface of devices made of fixed-size
+  blocks (e.g., sectors of a disk), on which filesystems and partition
+  parsers can be built.
+
+* `BiosDisk` - a drive of BIOS implementing `BlockDevice`.  It uses
+  the extended services (INT 13h AH=42h and AH=43h) if available, or
+  the CHS services (INT 13h AH=02h and AH=03h) track by track.  The
+  buffers in 20-bit address space are hidden from the caller.
+
+* `Gpt` - the GUID Partition Table of a block device.  The CRC-32 of
+  the header and of the partition entry array are validated, and the
+  backup header is used if the primary one is broken.  LBAs are
+  64-bit, so that disks bigger than 2TiB can be handled.
+
+```ignore
+let mut disk = storage::BiosDisk::open(drive_id, &ALLOC_UNDER20)?;
+let mut buf = vec![0; disk.block_size()];
+disk.read_blocks(0, &mut buf)?;
+
+let gpt = storage::Gpt::read(&mut disk)?;
+if let Some(esp) = gpt.find(storage::Guid::EFI_SYSTEM) {
+    let len = esp.num_blocks() * disk.block_size() as u64;
+    let file = fs::DiskSlice::new(&mut disk, esp.first_lba, len)?;
+}
+```
+
+# Supplementary Resource
+
+* [GUID Partition Table Format](https://uefi.org/specs/UEFI/2.10/05_GUID_Partition_Table_Format.html) (UEFI Specification)
+
+ */
+
+
+#[doc(hidden)] pub mod bios_disk;
+#[doc(hidden)] pub mod block_device;
+#[doc(hidden)] pub mod gpt;
+
+#[doc(inline)] pub use self::bios_disk::BiosDisk;
+#[doc(inline)] pub use self::block_device::{BlockDevice, BlockError};
+#[doc(inline)] pub use self::gpt::{
+    Gpt, GptError, GptHeader, GptPartition, Guid,
+};
@@ -0,0 +1,132 @@
+/*!
+
+Runs `async` tasks by polling.
+
+Each task has a waker which sets its ready flag.  The executor polls
+ready tasks in turn, and polls the wake-up sources (see
+[`sources`](super::sources)) while no task is ready.
+
+ */
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use alloc::vec::Vec;
+use core::future::Future;
+use core::hint::spin_loop;
+use core::pin::{Pin, pin};
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use super::sources;
+
+
+// Sets the ready flag of a task.
+struct TaskWaker {
+    ready: AtomicBool,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+	self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+	self.ready.store(true, Ordering::Release);
+    }
+}
+
+struct Task {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    state: Arc<TaskWaker>,
+    waker: Waker,
+}
+
+
+/// An executor of `async` tasks.
+pub struct Executor {
+    tasks: Vec<Task>,
+}
+
+impl Executor {
+    /// Returns an executor without tasks.
+    pub const fn new() -> Self {
+	Self { tasks: Vec::new() }
+    }
+
+    /// Adds a task (ready to run).
+    pub fn spawn<F>(&mut self, future: F)
+    where
+	F: Future<Output = ()> + 'static
+    {
+	let state = Arc::new(TaskWaker { ready: AtomicBool::new(true) });
+	let waker = Waker::from(state.clone());
+	self.tasks.push(Task { future: Box::pin(future), state, waker });
+    }
+
+    /// Returns the number of the tasks not completed yet.
+    pub fn len(&self) -> usize {
+	self.tasks.len()
+    }
+
+    /// Returns true if all the tasks have completed.
+    pub fn is_empty(&self) -> bool {
+	self.tasks.is_empty()
+    }
+
+    ///
+    /// Polls the ready tasks once, then returns true if any task was
+    /// polled.  Completed tasks are removed.
+    ///
+    pub fn run_ready(&mut self) -> bool {
+	let mut polled = false;
+	self.tasks.retain_mut(|task| {
+	    if !task.state.ready.swap(false, Ordering::Acquire) {
+		return true;
+	    }
+	    polled = true;
+	    let mut cx = Context::from_waker(&task.waker);
+	    task.future.as_mut().poll(&mut cx).is_pending()
+	});
+	polled
+    }
+
+    /// Runs the tasks until all of them complete.
+    pub fn run(&mut self) {
+	while !self.tasks.is_empty() {
+	    if !self.run_ready() {
+		sources::poll();
+		spin_loop();
+	    }
+	}
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+	Self::new()
+    }
+}
+
+
+/// Runs the future to completion, then returns its output.
+pub fn block_on<F>(future: F) -> F::Output
+where
+    F: Future
+{
+    let state = Arc::new(TaskWaker { ready: AtomicBool::new(true) });
+    let waker = Waker::from(state.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+	if state.ready.swap(false, Ordering::Acquire) {
+	    if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+		return output;
+	    }
+	} else {
+	    sources::poll();
+	    spin_loop();
+	}
+    }
+}
@@ -0,0 +1,40 @@
+/*!
+
+Provides a minimal executor of `async` tasks.
+
+* `Executor` - runs spawned futures until all of them complete.
+  A task is polled again only after its waker is called.
+
+* `sleep` - a future completing after the duration (woken by a
+  timer of `time::timers`).
+
+* `read_key` - a future completing when a key event is available in
+  `input::KEY_QUEUE`.
+
+Since no interrupt handlers are installed in this environment, the
+wake-up sources (the timers and the keyboard) are polled by the
+executor while no task is ready to run.
+
+```ignore
+let mut executor = task::Executor::new();
+executor.spawn(async {
+    task::sleep(Duration::from_millis(500)).await;
+    let key = task::read_key().await;
+    println!("{:?}", key);
+});
+executor.run();
+```
+
+# Supplementary Resources
+
+* [Async/Await](https://os.phil-opp.com/async-await/) (Writing an OS in Rust)
+* [The `Future` Trait](https://rust-lang.github.io/async-book/02_execution/02_future.html) (Asynchronous Programming in Rust)
+
+ */
+
+
+pub mod executor;
+#[doc(hidden)] pub mod sources;
+
+#[doc(inline)] pub use self::executor::{Executor, block_on};
+#[doc(inline)] pub use self::sources::{ReadKey, Sleep, read_key, sleep};
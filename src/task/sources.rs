@@ -0,0 +1,93 @@
+//
+// Wake-up Sources - The timers and the keyboard.
+//
+// Futures waiting for a source register their wakers here, and the
+// executor calls `poll()` to wake them when the source is ready.
+//
+
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+
+use crate::input::{KEY_QUEUE, Key};
+use crate::mu::MuMutex;
+use crate::time::Instant;
+use crate::time::timers::{self, TimerId};
+
+
+// Wakers of the futures waiting for keys
+static KEY_WAITERS: MuMutex<Vec<Waker>> = MuMutex::new(Vec::new());
+
+
+/// Wakes the futures whose sources are ready.
+pub fn poll() {
+    timers::poll();
+
+    if KEY_QUEUE.is_pending() {
+	for waker in KEY_WAITERS.lock().drain(..) {
+	    waker.wake();
+	}
+    }
+}
+
+
+/// Returns a future completing after the duration.
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep { deadline: Instant::now() + duration, timer: None }
+}
+
+/// A future returned by [`sleep`].
+pub struct Sleep {
+    deadline: Instant,
+    timer: Option<TimerId>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+	let now = Instant::now();
+	if now >= self.deadline {
+	    return Poll::Ready(());
+	}
+
+	// Restart the timer since the waker may be changed.
+	if let Some(timer) = self.timer.take() {
+	    timers::cancel(timer);
+	}
+	let delay = self.deadline - now;
+	self.timer = Some(timers::wake_after(delay, cx.waker().clone()));
+	Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+	if let Some(timer) = self.timer.take() {
+	    timers::cancel(timer);
+	}
+    }
+}
+
+
+/// Returns a future completing when a key is pressed (`KEY_QUEUE`).
+pub fn read_key() -> ReadKey {
+    ReadKey
+}
+
+/// A future returned by [`read_key`].
+pub struct ReadKey;
+
+impl Future for ReadKey {
+    type Output = Key;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Key> {
+	if let Some(event) = KEY_QUEUE.poll() {
+	    return Poll::Ready(event.key);
+	}
+	KEY_WAITERS.lock().push(cx.waker().clone());
+	Poll::Pending
+    }
+}
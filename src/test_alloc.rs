@@ -7,9 +7,14 @@ Tests a memory allocator and a heap manager.
 
 use alloc::collections::VecDeque;
 use alloc::vec::Vec;
-use core::alloc::Allocator;
+use core::alloc::{Allocator, Layout};
+use core::ptr::NonNull;
 
-use crate::{print, println};
+use crate::console::StatusLine;
+use crate::man_heap::{ALLOC_UNDER20, GLOBAL_ALLOC};
+use crate::mu::{MuAlloc, MuHeapIndex};
+use crate::util::XorShift64;
+use crate::{heap_assert_eq, print, println};
 
 
 ///
@@ -23,9 +28,12 @@ where
     // Create an empty vector that will hold resulting sieves.
     let mut results_queue = VecDeque::new_in(alloc);
 
+    let mut status = StatusLine::new();
     print!("Running: ");
     for i in 0 .. count {
-	if (i % 10) == 0 {
+	if status.is_active() {
+	    status.progress("Sieve", i as u64, count as u64);
+	} else if (i % 10) == 0 {
 	    print!("{},", i);
 	}
 
@@ -80,3 +88,351 @@ where
 
     println!();
 }
+
+
+
+/// Allocators that a heap stress scenario runs on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeapChoice {
+    /// The global allocator (`GLOBAL_ALLOC`)
+    Global,
+    /// The allocator in 20-bit address space (`ALLOC_UNDER20`)
+    Under20,
+}
+
+impl HeapChoice {
+    /// Returns the allocator of the name ("global" or "under20").
+    pub fn from_name(name: &str) -> Option<Self> {
+	match name {
+	    "global" => Some(Self::Global),
+	    "under20" => Some(Self::Under20),
+	    _ => None,
+	}
+    }
+}
+
+/// Workloads of heap stress scenarios.
+#[derive(Clone, Copy, Debug)]
+pub enum Workload {
+    /// `Sieve of Eratosthenes` (see [`try_sieve`])
+    Sieve { n: usize, m: usize, count: usize },
+    /// Random blocks, each of which is released after `lifetime`
+    /// allocations (at most `MAX_LIVE_BLOCKS` blocks alive).
+    Random {
+	min_size: usize,	// The minimum size in bytes
+	max_size: usize,	// The maximum size in bytes
+	lifetime: usize,	// Lifetime in the number of allocations
+	max_align_shift: u32,	// Alignment is 1 << (0 ..= max_align_shift)
+	count: usize,		// The number of allocations
+    },
+}
+
+///
+/// A heap stress scenario.
+///
+/// A scenario can be selected by the command line argument
+/// `heap_scenario=<name>`, and its allocator can be overridden by
+/// `heap_alloc=global` or `heap_alloc=under20`.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Scenario {
+    pub name: &'static str,
+    pub workload: Workload,
+    pub heap: HeapChoice,
+}
+
+/// The maximum number of blocks alive at the same time in a scenario.
+pub const MAX_LIVE_BLOCKS: usize = 256;
+
+/// Predefined heap stress scenarios (the first one is the default).
+pub const SCENARIOS: [Scenario; 5] = [
+    Scenario {
+	name: "sieve",
+	workload: Workload::Sieve { n: 30, m: 100, count: 10000 },
+	heap: HeapChoice::Global,
+    },
+    Scenario {
+	name: "small",		// Many small, short-lived objects
+	workload: Workload::Random {
+	    min_size: 1, max_size: 64, lifetime: 16,
+	    max_align_shift: 3, count: 100000,
+	},
+	heap: HeapChoice::Global,
+    },
+    Scenario {
+	name: "large",		// Large, long-lived buffers
+	workload: Workload::Random {
+	    min_size: 4096, max_size: 65536, lifetime: 8,
+	    max_align_shift: 12, count: 2000,
+	},
+	heap: HeapChoice::Global,
+    },
+    Scenario {
+	name: "mixed",		// Mixed sizes and lifetimes
+	workload: Workload::Random {
+	    min_size: 1, max_size: 8192, lifetime: 200,
+	    max_align_shift: 6, count: 50000,
+	},
+	heap: HeapChoice::Global,
+    },
+    Scenario {
+	name: "bios",		// Buffers exchanged with BIOS
+	workload: Workload::Random {
+	    min_size: 16, max_size: 4096, lifetime: 4,
+	    max_align_shift: 4, count: 10000,
+	},
+	heap: HeapChoice::Under20,
+    },
+];
+
+impl Scenario {
+    /// Returns the predefined scenario of the name.
+    pub fn find(name: &str) -> Option<&'static Self> {
+	SCENARIOS.iter().find(|scenario| scenario.name == name)
+    }
+
+    ///
+    /// Returns the scenario selected by the command line (or the
+    /// default scenario).
+    ///
+    pub fn from_cmdline() -> Self {
+	let mut scenario =
+	    crate::cmdline::value("heap_scenario")
+	    .and_then(Self::find)
+	    .copied()
+	    .unwrap_or(SCENARIOS[0]);
+
+	if let Some(heap) = crate::cmdline::value("heap_alloc")
+	    .and_then(HeapChoice::from_name) {
+	    scenario.heap = heap;
+	}
+
+	scenario
+    }
+
+    /// Runs the scenario on the chosen allocator.
+    pub fn run(&self, seed: u64) {
+	println!("Heap scenario: {} on {:?} (seed={})",
+		 self.name, self.heap, seed);
+	match self.heap {
+	    HeapChoice::Global => self.run_on(seed, &GLOBAL_ALLOC),
+	    HeapChoice::Under20 => self.run_on(seed, &ALLOC_UNDER20),
+	}
+    }
+
+    fn run_on<I>(&self, seed: u64, alloc: &'static MuAlloc<I>)
+    where
+	I: MuHeapIndex
+    {
+	match self.workload {
+	    Workload::Sieve { n, m, count } => try_sieve(n, m, count, alloc),
+	    Workload::Random { min_size, max_size, lifetime,
+			       max_align_shift, count } => {
+		let lifetime = lifetime.clamp(1, MAX_LIVE_BLOCKS);
+		let mut rng = XorShift64::new(seed);
+		let mut live: [Option<(NonNull<u8>, Layout)>; MAX_LIVE_BLOCKS] =
+		    [None; MAX_LIVE_BLOCKS];
+		let mut failures = 0;
+
+		let mut status = StatusLine::new();
+		print!("Running: ");
+		for i in 0 .. count {
+		    if status.is_active() {
+			status.progress(self.name, i as u64, count as u64);
+		    } else if (i % (count / 10).max(1)) == 0 {
+			print!("{},", i);
+		    }
+
+		    // Release the block allocated `lifetime` allocations ago.
+		    let slot = i % lifetime;
+		    if let Some((ptr, layout)) = live[slot].take() {
+			unsafe { alloc.deallocate(ptr, layout) };
+		    }
+
+		    let size = rng.range(min_size, max_size);
+		    let align = 1 << rng.below(max_align_shift as usize + 1);
+		    let layout = Layout::from_size_align(size, align).unwrap();
+		    match alloc.allocate(layout) {
+			Ok(ptr) => live[slot] = Some((ptr.cast(), layout)),
+			Err(_) => failures += 1,
+		    }
+		}
+
+		for (ptr, layout) in live.iter().flatten() {
+		    unsafe { alloc.deallocate(*ptr, *layout) };
+		}
+		alloc.lock().validate()
+		    .unwrap_or_else(| err | panic!("{}", err));
+
+		println!("done (allocation failures = {})", failures);
+	    },
+	}
+    }
+}
+
+// The maximum number of blocks alive at the same time in fuzz.
+const FUZZ_MAX_BLOCKS: usize = 64;
+
+// The maximum size in bytes of a block in fuzz.
+const FUZZ_MAX_SIZE: usize = 2048;
+
+// A block allocated in fuzz, and its expected contents.
+#[derive(Clone, Copy)]
+struct FuzzBlock {
+    ptr: NonNull<u8>,
+    layout: Layout,
+    seed: u8,		// The i-th byte is expected to be seed + i.
+}
+
+impl FuzzBlock {
+    fn fill(&self) {
+	for i in 0 .. self.layout.size() {
+	    unsafe {
+		*self.ptr.as_ptr().add(i) = self.seed.wrapping_add(i as u8);
+	    }
+	}
+    }
+
+    // Verifies the first `size` bytes.
+    fn verify<I>(&self, size: usize, step: usize, alloc: &MuAlloc<I>)
+    where
+	I: MuHeapIndex
+    {
+	for i in 0 .. size {
+	    let actual = unsafe { *self.ptr.as_ptr().add(i) };
+	    let expected = self.seed.wrapping_add(i as u8);
+	    heap_assert_eq!(alloc, actual, expected,
+			    "fuzz: step {}: ptr={:p}, {:?}, offset={}: \
+			     corrupted", step, self.ptr, self.layout, i);
+	}
+    }
+}
+
+///
+/// Tests a heap manager by a random interleaving of alloc, dealloc,
+/// grow and shrink with varied sizes and alignments.
+///
+/// The contents of all allocated blocks are tracked, and verified
+/// before they are released or resized.  The consistency of the
+/// heap is validated after each step.  The same `seed` reproduces
+/// the same sequence.  It panics if a problem is found.
+///
+pub fn fuzz<I>(seed: u64, iterations: usize, alloc: &MuAlloc<I>)
+where
+    I: MuHeapIndex
+{
+    let mut rng = XorShift64::new(seed);
+    let mut blocks: [Option<FuzzBlock>; FUZZ_MAX_BLOCKS] =
+	[None; FUZZ_MAX_BLOCKS];
+    let mut failures = 0;
+
+    let mut status = StatusLine::new();
+    print!("Fuzzing (seed={}): ", seed);
+    for step in 0 .. iterations {
+	if status.is_active() {
+	    status.progress("Fuzzing", step as u64, iterations as u64);
+	} else if (step % 1000) == 0 {
+	    print!("{},", step);
+	}
+
+	let slot = rng.below(FUZZ_MAX_BLOCKS);
+	let new_size = rng.below(FUZZ_MAX_SIZE) + 1;
+
+	match (blocks[slot], rng.below(4)) {
+	    (None, _) => {
+		// Alloc
+		let align = 1 << rng.below(8);	// 1, 2, 4, .., 128
+		let layout = Layout::from_size_align(new_size, align).unwrap();
+		match alloc.allocate(layout) {
+		    Ok(ptr) => {
+			let block = FuzzBlock {
+			    ptr: ptr.cast(),
+			    layout,
+			    seed: rng.next_u64() as u8,
+			};
+			let addr = ptr.cast::<u8>().as_ptr() as usize;
+			heap_assert_eq!(alloc, addr % align, 0,
+					"fuzz: step {}: {:?}: misaligned",
+					step, layout);
+			block.fill();
+			blocks[slot] = Some(block);
+		    },
+		    Err(_) => failures += 1,
+		}
+	    },
+	    (Some(block), 0 | 1) => {
+		// Dealloc
+		block.verify(block.layout.size(), step, alloc);
+		unsafe { alloc.deallocate(block.ptr, block.layout) };
+		blocks[slot] = None;
+	    },
+	    (Some(block), _) => {
+		// Grow or Shrink
+		let old_size = block.layout.size();
+		block.verify(old_size, step, alloc);
+		let new_layout =
+		    Layout::from_size_align(new_size, block.layout.align())
+		    .unwrap();
+		let result = unsafe {
+		    if new_size >= old_size {
+			alloc.grow(block.ptr, block.layout, new_layout)
+		    } else {
+			alloc.shrink(block.ptr, block.layout, new_layout)
+		    }
+		};
+		match result {
+		    Ok(ptr) => {
+			let resized = FuzzBlock {
+			    ptr: ptr.cast(),
+			    layout: new_layout,
+			    ..block
+			};
+			resized.verify(old_size.min(new_size), step, alloc);
+			resized.fill();
+			blocks[slot] = Some(resized);
+		    },
+		    Err(_) => {
+			// The block must remain valid on failure.
+			failures += 1;
+			block.verify(old_size, step, alloc);
+		    },
+		}
+	    },
+	}
+
+	alloc.lock().validate()
+	    .unwrap_or_else(| err | panic!("{}", err));
+    }
+
+    // Release all remaining blocks.
+    for block in blocks.iter().flatten() {
+	block.verify(block.layout.size(), iterations, alloc);
+	unsafe { alloc.deallocate(block.ptr, block.layout) };
+    }
+    alloc.lock().validate()
+	.unwrap_or_else(| err | panic!("{}", err));
+
+    println!("done (allocation failures = {})", failures);
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mu::MuAlloc32;
+    use std::vec::Vec;
+
+    #[test]
+    fn fuzz_small_heap() {
+	// A small heap area causes allocation failures as well.
+	let mut area: Vec<u64> = vec![0; 48 * 1024 / 8];
+	let alloc = unsafe {
+	    MuAlloc32::heap(area.as_mut_ptr() as usize, area.len() * 8)
+	};
+
+	for seed in 1 ..= 4 {
+	    fuzz(seed, 5000, &alloc);
+	}
+    }
+}
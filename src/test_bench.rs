@@ -0,0 +1,81 @@
+/*!
+
+Benchmarks heap managers and disk I/O using `testing::bench`.
+
+The results are in TSC cycles.  Note that they are only rough
+estimates when running on QEMU (especially without KVM).
+
+ */
+
+
+use alloc::vec::Vec;
+use core::alloc::{Allocator, Layout};
+use core::hint::black_box;
+
+#[cfg(feature = "disk")]
+use crate::bios;
+use crate::mu::{MuAlloc, MuHeapIndex};
+use crate::println;
+use crate::testing::{bench, print_summary};
+
+
+///
+/// Benchmarks a heap manager: alloc and dealloc of small and large
+/// blocks, and growing a `Vec`.
+///
+pub fn try_bench_heap<I>(label: &str, alloc: &MuAlloc<I>)
+where
+    I: MuHeapIndex
+{
+    let small = Layout::from_size_align(64, 8).unwrap();
+    let large = Layout::from_size_align(4096, 16).unwrap();
+
+    let results = [
+	bench("alloc+dealloc 64B", 1000, || alloc_dealloc(alloc, small)),
+	bench("alloc+dealloc 4KiB", 1000, || alloc_dealloc(alloc, large)),
+	bench("Vec push 1000 x u32", 100, || {
+	    let mut vec = Vec::new_in(alloc);
+	    for i in 0 .. 1000_u32 {
+		vec.push(i);
+	    }
+	    black_box(&vec);
+	}),
+    ];
+
+    println!("Benchmark: heap ({})", label);
+    print_summary(&results);
+}
+
+fn alloc_dealloc<I>(alloc: &MuAlloc<I>, layout: Layout)
+where
+    I: MuHeapIndex
+{
+    if let Ok(ptr) = alloc.allocate(layout) {
+	unsafe { alloc.deallocate(black_box(ptr).cast(), layout) };
+    }
+}
+
+///
+/// Benchmarks disk reads from the boot drive using
+/// BIOS INT 13h AH=42h (Extended Read Sectors From Drive).
+///
+#[cfg(feature = "disk")]
+pub fn try_bench_diskio<A20>(alloc20: A20)
+where
+    A20: Allocator + Copy
+{
+    let drive_id = bios::get_boot_drive_id();
+
+    let read = |nsectors| {
+	black_box(bios::int13h42h::call(drive_id, 0, nsectors, alloc20));
+    };
+
+    let results = [
+	bench("BIOS read 1 sector", 20, || read(1)),
+	bench("BIOS read 8 sectors", 20, || read(8)),
+	bench("BIOS read 64 sectors", 20, || read(64)),
+    ];
+
+    println!("Benchmark: disk I/O (drive={:#x})", drive_id);
+    print_summary(&results);
+}
@@ -5,13 +5,26 @@ Tests simple disk I/O using BIOS.
  */
 
 
+use alloc::vec::Vec;
 use core::alloc::Allocator;
+use core::cmp::min;
+use core::fmt;
+use core::mem::size_of;
 
-use crate::bios;
+use crate::bios::{self, SECTOR_SIZE};
+use crate::console::StatusLine;
+use crate::man_image::{self, ImageTrailer};
+use crate::testing;
+use crate::util::Cksum;
 use crate::{print, println};
 use crate::x86::X86GetAddr;
 
 
+/// The number of sectors read by one call in `verify_image`.
+/// (More than 127 sectors to exercise the chunking in `int13h42h`)
+const VERIFY_LBA_NSECTORS: u16 = 160;
+
+
 
 ///
 /// Tests simple disk I/O using
@@ -31,6 +44,12 @@ where
     print!("Read sectors: CHS=({}, {}, {}), nsectors={}, drive={:#x} ... ",
 	   cylinder, head, sector, nsectors, drive_id);
 
+    // CHS addressing is not available for CD-ROM drives.
+    if bios::is_cdrom_drive(drive_id) {
+	println!("skipped (CD-ROM)");
+	return;
+    }
+
     match bios::int13h02h::call(drive_id, cylinder, head, sector, nsectors,
 				alloc20) {
 	Some(vec) => {
@@ -72,6 +91,146 @@ where
     }
 }
 
+///
+/// Tests BIOS INT 13h AX=4B01h (Get Disk Emulation Status).
+///
+/// If the boot drive is a CD-ROM drive, it shows the El Torito
+/// specification packet.
+///
+pub fn try_get_emulation_status<A20>(alloc20: A20)
+where
+    A20: Allocator
+{
+    let drive_id = bios::get_boot_drive_id();
+
+    if !bios::is_cdrom_drive(drive_id) {
+	return;
+    }
+
+    print!("Get disk emulation status: drive={:#x} ... ", drive_id);
+
+    match bios::int13h4b01h::call(drive_id, alloc20) {
+	Some(packet) => {
+	    println!("OK!");
+	    packet.print();
+	},
+	None => {
+	    println!("failed");
+	},
+    }
+}
+
+///
+/// Verifies the boot image (main1) on the boot drive.
+///
+/// It reads main1 from the boot drive (starting at LBA 1) using both
+/// BIOS INT 13h AH=42h (LBA) and AH=02h (CHS), and compares its
+/// checksum with the one in the image trailer embedded after the
+/// build.  The results are reported through `testing::report`.
+///
+pub fn verify_image<A20>(alloc20: A20)
+where
+    A20: Allocator + Copy
+{
+    const LBA_TEST: &str = "diskio::verify_image (LBA)";
+    const CHS_TEST: &str = "diskio::verify_image (CHS)";
+
+    let drive_id = bios::get_boot_drive_id();
+    let trailer = man_image::trailer();
+
+    // main1 is not placed at LBA 1 on CD-ROM (El Torito).
+    let reason = if bios::is_cdrom_drive(drive_id) {
+	Some("CD-ROM")
+    } else if trailer.magic != ImageTrailer::MAGIC ||
+	trailer.checksum == ImageTrailer::NO_CHECKSUM {
+	Some("checksum not embedded")
+    } else {
+	None
+    };
+    if let Some(reason) = reason {
+	testing::skip(LBA_TEST, reason);
+	testing::skip(CHS_TEST, reason);
+	return;
+    }
+
+    let nbytes = trailer.size as usize - size_of::<ImageTrailer>();
+
+    testing::report(LBA_TEST, verify_main1(nbytes, trailer.checksum,
+					    | lba, nsectors | {
+	let nsectors = min(nsectors, VERIFY_LBA_NSECTORS as u32);
+	bios::int13h42h::call(drive_id, lba as u64, nsectors as u16, alloc20)
+    }));
+
+    let Some(params) = bios::int13h08h::call(drive_id) else {
+	testing::skip(CHS_TEST, "no drive parameters");
+	return;
+    };
+
+    testing::report(CHS_TEST, verify_main1(nbytes, trailer.checksum,
+					    | lba, nsectors | {
+	// Read up to the end of the track.
+	let (cylinder, head, sector) = params.lba_to_chs(lba);
+	let rest = params.sectors_to_track_end(sector) as u32;
+	let nsectors = min(nsectors, rest) as u8;
+	bios::int13h02h::call(drive_id, cylinder, head, sector, nsectors,
+			      alloc20)
+    }));
+}
+
+/// Errors detected by `verify_image`.
+#[derive(Debug)]
+enum VerifyError {
+    ReadFailed { lba: u32 },
+    ChecksumMismatch { expected: u32, actual: u32 },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::ReadFailed { lba } =>
+		write!(f, "failed to read LBA={}", lba),
+	    Self::ChecksumMismatch { expected, actual } =>
+		write!(f, "checksum={:#x} in trailer, but {:#x} read",
+		       expected, actual),
+	}
+    }
+}
+
+// Reads `nbytes` bytes of main1 by `read(lba, nsectors)`, which may
+// read fewer sectors than requested, then verifies the checksum.
+fn verify_main1<A, F>(nbytes: usize, expected: u32, mut read: F)
+		      -> Result<(), VerifyError>
+where
+    A: Allocator,
+    F: FnMut(u32, u32) -> Option<Vec<u8, A>>
+{
+    let mut cksum = Cksum::new();
+    let mut lba = 1;
+    let mut unread_nbytes = nbytes;
+    let mut status = StatusLine::new();
+
+    while unread_nbytes > 0 {
+	status.progress("Reading main1",
+			(nbytes - unread_nbytes) as u64, nbytes as u64);
+	let nsectors = unread_nbytes.div_ceil(SECTOR_SIZE) as u32;
+	let buf = read(lba, nsectors)
+	    .filter(|buf| buf.len() >= SECTOR_SIZE)
+	    .ok_or(VerifyError::ReadFailed { lba })?;
+
+	let cur_nbytes = min(unread_nbytes, buf.len());
+	cksum.update(&buf[.. cur_nbytes]);
+	unread_nbytes -= cur_nbytes;
+	lba += (buf.len() / SECTOR_SIZE) as u32;
+    }
+
+    let actual = cksum.finish();
+    if actual == expected {
+	Ok(())
+    } else {
+	Err(VerifyError::ChecksumMismatch { expected, actual })
+    }
+}
+
 fn dump(buf: &[u8], n: usize) {
     print!("{:#x}:", buf.get_linear_addr());
     for i in 0 .. n {
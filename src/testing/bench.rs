@@ -0,0 +1,89 @@
+//
+// Benchmark Harness using the Time Stamp Counter (TSC)
+//
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::x86::{has_rdtscp, tsc_end, tsc_start};
+
+
+/// The number of iterations to estimate the measurement overhead.
+const OVERHEAD_ITERS: usize = 16;
+
+/// The result of a benchmark (in TSC cycles per iteration).
+#[derive(Clone, Copy, Debug)]
+pub struct BenchResult {
+    pub name: &'static str,
+    pub iters: usize,
+    pub min: u64,
+    pub median: u64,
+    pub max: u64,
+}
+
+///
+/// Measures `f` for `iters` times, and returns the minimum, median
+/// and maximum cycles.
+///
+/// Each iteration is measured separately by serialized RDTSC(P), and
+/// the measurement overhead (estimated with an empty closure) is
+/// subtracted.  Samples are stored in a `Vec` allocated before the
+/// measurement.
+///
+pub fn bench<F>(name: &'static str, iters: usize, mut f: F) -> BenchResult
+where
+    F: FnMut()
+{
+    let rdtscp = has_rdtscp();
+    let overhead = (0 .. OVERHEAD_ITERS)
+	.map(|_| measure(rdtscp, || ()))
+	.min()
+	.unwrap_or(0);
+
+    let iters = iters.max(1);
+    let mut samples = Vec::with_capacity(iters);
+    for _ in 0 .. iters {
+	samples.push(measure(rdtscp, &mut f).saturating_sub(overhead));
+    }
+    samples.sort_unstable();
+
+    BenchResult {
+	name,
+	iters,
+	min: samples[0],
+	median: samples[iters / 2],
+	max: samples[iters - 1],
+    }
+}
+
+#[inline(always)]
+fn measure<F>(rdtscp: bool, mut f: F) -> u64
+where
+    F: FnMut()
+{
+    let start = tsc_start();
+    f();
+    let end = tsc_end(rdtscp);
+    end.wrapping_sub(start)
+}
+
+/// Benchmark results formatted as a table.
+pub struct BenchTable<'a>(pub &'a [BenchResult]);
+
+impl fmt::Display for BenchTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{:<28} {:>8} {:>12} {:>12} {:>12}\r\n",
+	       "Benchmark", "Iters", "Min", "Median", "Max")?;
+	for result in self.0 {
+	    write!(f, "{:<28} {:>8} {:>12} {:>12} {:>12}\r\n",
+		   result.name, result.iters,
+		   result.min, result.median, result.max)?;
+	}
+	Ok(())
+    }
+}
+
+/// Prints benchmark results as a table (in TSC cycles).
+pub fn print_summary(results: &[BenchResult]) {
+    crate::print!("{}", BenchTable(results));
+}
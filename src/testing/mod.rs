@@ -0,0 +1,24 @@
+/*!
+
+Provides facilities for automated test runs.
+
+* `exit_qemu` - exits QEMU with an exit status using the
+  `isa-debug-exit` device.
+
+* `report` - reports the result of a test, and `summary` returns the
+  numbers of passed, failed and skipped tests.
+
+* `bench` - measures the cycles of a closure by serialized RDTSC(P),
+  and `print_summary` prints the results as a table.
+
+ */
+
+
+#[doc(hidden)] pub mod bench;
+#[doc(hidden)] pub mod qemu_exit;
+#[doc(hidden)] pub mod report;
+
+#[doc(inline)] pub use self::bench::{BenchResult, BenchTable};
+#[doc(inline)] pub use self::bench::{bench, print_summary};
+#[doc(inline)] pub use self::qemu_exit::{ExitCode, exit_qemu, set_exit_device};
+#[doc(inline)] pub use self::report::{TestSummary, report, skip, summary};
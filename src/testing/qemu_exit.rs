@@ -0,0 +1,60 @@
+//
+// QEMU Exit - Exits QEMU using the isa-debug-exit device.
+//
+// To enable the device, add the following option to qemu-system-x86_64:
+//	-device isa-debug-exit,iobase=0xf4,iosize=0x04
+//
+// Then, QEMU exits with status (value << 1) | 1 when a value is written
+// to the I/O port.
+//
+
+use core::sync::atomic::{AtomicU8, AtomicU16, Ordering};
+
+use crate::x86::{outb, outl, outw};
+
+
+/// Exit codes written to the isa-debug-exit device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u32)]
+pub enum ExitCode {
+    /// QEMU exits with status 33 ((0x10 << 1) | 1).
+    Success = 0x10,
+    /// QEMU exits with status 35 ((0x11 << 1) | 1).
+    Failed = 0x11,
+}
+
+// The I/O port address and size of the isa-debug-exit device.
+static EXIT_PORT: AtomicU16 = AtomicU16::new(0xf4);
+static EXIT_IOSIZE: AtomicU8 = AtomicU8::new(4);
+
+
+///
+/// Sets the I/O port address and size of the isa-debug-exit device.
+///
+/// They must match `iobase` and `iosize` of the device.  The size
+/// must be 1, 2 or 4.  The defaults are `0xf4` and `4`.
+///
+pub fn set_exit_device(port: u16, iosize: u8) {
+    assert!(matches!(iosize, 1 | 2 | 4), "Invalid iosize: {}", iosize);
+    EXIT_PORT.store(port, Ordering::Relaxed);
+    EXIT_IOSIZE.store(iosize, Ordering::Relaxed);
+}
+
+///
+/// Exits QEMU with the exit code.
+///
+/// If the isa-debug-exit device is not available (e.g., on real
+/// hardware), it just returns.
+///
+pub fn exit_qemu(exit_code: ExitCode) {
+    let port = EXIT_PORT.load(Ordering::Relaxed);
+    let value = exit_code as u32;
+
+    unsafe {
+	match EXIT_IOSIZE.load(Ordering::Relaxed) {
+	    1 => outb(port, value as u8),
+	    2 => outw(port, value as u16),
+	    _ => outl(port, value),
+	}
+    }
+}
@@ -0,0 +1,85 @@
+//
+// Test Report - Counts and prints the results of tests.
+//
+// Each result is printed in the same format as `cargo test`:
+//	test <name> ... ok
+//	test <name> ... FAILED (<reason>)
+//	test <name> ... skipped (<reason>)
+//
+
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use super::ExitCode;
+use crate::debug;
+use crate::println;
+
+
+static PASSED: AtomicUsize = AtomicUsize::new(0);
+static FAILED: AtomicUsize = AtomicUsize::new(0);
+static SKIPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// Reports the result of a test, then returns true if it passed.
+pub fn report<E>(name: &str, result: Result<(), E>) -> bool
+where
+    E: fmt::Display
+{
+    // Make sure that the result is visible on the screen.
+    debug::restore_text_mode();
+
+    match result {
+	Ok(()) => {
+	    PASSED.fetch_add(1, Ordering::Relaxed);
+	    println!("test {} ... ok", name);
+	    true
+	},
+	Err(reason) => {
+	    FAILED.fetch_add(1, Ordering::Relaxed);
+	    println!("test {} ... FAILED ({})", name, reason);
+	    false
+	},
+    }
+}
+
+/// Reports a test skipped for the reason.
+pub fn skip(name: &str, reason: &str) {
+    SKIPPED.fetch_add(1, Ordering::Relaxed);
+    debug::restore_text_mode();
+    println!("test {} ... skipped ({})", name, reason);
+}
+
+/// Numbers of test results reported so far.
+#[derive(Clone, Copy, Debug)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+impl TestSummary {
+    /// Returns the exit code for `exit_qemu`.
+    pub fn exit_code(&self) -> ExitCode {
+	if self.failed == 0 {
+	    ExitCode::Success
+	} else {
+	    ExitCode::Failed
+	}
+    }
+}
+
+impl fmt::Display for TestSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "test result: {}. {} passed; {} failed; {} skipped",
+	       if self.failed == 0 { "ok" } else { "FAILED" },
+	       self.passed, self.failed, self.skipped)
+    }
+}
+
+/// Returns the numbers of test results reported so far.
+pub fn summary() -> TestSummary {
+    TestSummary {
+	passed: PASSED.load(Ordering::Relaxed),
+	failed: FAILED.load(Ordering::Relaxed),
+	skipped: SKIPPED.load(Ordering::Relaxed),
+    }
+}
@@ -1,30 +1,161 @@
 /*!
 
-Provides a text writer using BIOS.
+Provides text writers and sinks of printed text.
 
-TextWriter - A Text Writer using BIOS INT 10h AH=0Eh (Teletype Output)
+TextWriter - A Text Writer using BIOS INT 10h AH=0Eh (Teletype Output).
+Its output can be batched by `TextWriter::batch` into one BIOS call of
+INT 10h AH=13h (Write String) per chunk instead of one per character.
+
+FbTextWriter - A Text Writer drawing an embedded 8x16 bitmap font on a
+VBE linear frame buffer with colors (feature `video`).  Teletype output
+is invisible once a graphics mode is set, whereas it keeps the output
+on the screen.  It wraps the frame buffer console of `console`, which
+draws the embedded font as well if the ROM font is not available (or
+`font=embedded` is given).
+
+Non-ASCII characters are transliterated into Code Page 437 (CP437).
+
+VgaTextWriter - A Text Writer writing directly to the VGA text buffer
+at 0xB8000 (in text mode 03h) with colors.  It is much faster than
+Teletype output, which switches to Real Mode for each character.  The
+screen sink of `console` also uses it in text mode.
+
+ConsoleSink - A sink of printed text.  In addition to the sinks of
+`console::ConsoleConfig` (the screen, serial and debugcon), sinks can
+be registered at runtime by `add_console` (or `set_console` to make
+it the only one), e.g. a `FbTextWriter` on a second frame buffer or
+`x86::SerialPort::COM2`.  Output of `print!` and `println!` is
+written to all of them.
+
+```ignore
+text_writer::add_console(x86::SerialPort::COM2);
+```
 
  */
 
 
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::fmt;
 
 use crate::bios;
+use crate::console::{self, ConsoleConfig, Level};
+use crate::console::cp437;
+#[cfg(feature = "video")]
+use crate::console::fb_console::{FbConsole, FbFont};
+use crate::console::vga_text::VgaText;
+#[cfg(feature = "video")]
+use crate::man_video::FrameBuffer;
+use crate::mu::MuMutex;
+use crate::x86::SerialPort;
 
 
 pub struct TextWriter;
 
+// The output buffered by `TextWriter::batch` (None = not batching)
+static BATCH: MuMutex<Option<Vec<u8>>> = MuMutex::new(None);
+
+// The buffered output is written when it reaches this size.
+const BATCH_FLUSH_SIZE: usize = 4096;
+
+// The number of bytes written by one call of INT 10h AH=13h.  Each chunk
+// is copied onto the stack (in 20-bit address space).
+const CHUNK_SIZE: usize = 256;
+
+// Page Number and Colors
+const PAGE_NUMBER: u8 = 0;
+const COLOR: u8 = 15;		// White (in graphics modes)
+const ATTR: u8 = 0x07;		// Light Gray on Black (INT 10h AH=13h)
+
 impl TextWriter {
     pub fn write_ascii_printables(&mut self, utf8_str: &str) {
-	for byte in utf8_str.bytes() {
-	    let ch =
-		match byte {
-		    0x20 ..= 0x7E | b'\n' | b'\r' => byte,
-		    _ => b'.'
-		};
-	    let page_number = 0;
-	    let color = 15; // White
-	    bios::int10h0eh::call(ch, page_number, color);
+	let mut batch = BATCH.lock();
+	if let Some(buf) = batch.as_mut() {
+	    buf.extend(utf8_str.chars().map(cp437::encode_or_control));
+	    if buf.len() >= BATCH_FLUSH_SIZE {
+		write_bytes(buf);
+		buf.clear();
+	    }
+	    return;
+	}
+	drop(batch);
+
+	for ch in utf8_str.chars() {
+	    let byte = cp437::encode_or_control(ch);
+	    bios::int10h0eh::call(byte, PAGE_NUMBER, COLOR);
+	}
+    }
+
+    ///
+    /// Calls `f` with the output of teletype suspended, then writes
+    /// the output buffered meanwhile with the fewest BIOS calls, i.e.
+    /// by INT 10h AH=13h (Write String) if available.  Output printed
+    /// to the screen sink in `f` (e.g. by `println!`) is batched, too.
+    ///
+    /// Teletype output switches to Real Mode for each character,
+    /// hence it speeds up large dumps (e.g., the memory map).
+    /// Nested calls are batched by the outermost call.  The global
+    /// allocator must be initialized.
+    ///
+    /// ```ignore
+    /// TextWriter::batch(| w | write!(w, "{}", MemoryMap(memory_map)));
+    /// ```
+    ///
+    pub fn batch<F, T>(f: F) -> T
+    where
+	F: FnOnce(&mut Self) -> T,
+    {
+	let outermost = {
+	    let mut batch = BATCH.lock();
+	    let outermost = batch.is_none();
+	    if outermost {
+		*batch = Some(Vec::new());
+	    }
+	    outermost
+	};
+
+	let result = f(&mut TextWriter);
+
+	if outermost {
+	    Self::end_batch();
+	}
+	result
+    }
+
+    ///
+    /// Resumes teletype output suspended by [`TextWriter::batch`],
+    /// then writes the buffered output.  It is called by the panic
+    /// handler (It does nothing if the buffer is locked).
+    ///
+    pub fn end_batch() {
+	let Some(mut batch) = BATCH.try_lock() else {
+	    return;
+	};
+	if let Some(buf) = batch.take() {
+	    write_bytes(&buf);
+	}
+    }
+}
+
+// Writes the CP437 bytes by INT 10h AH=13h (Write String) in chunks,
+// or by INT 10h AH=0Eh (Teletype Output) if it is not available.
+fn write_bytes(bytes: &[u8]) {
+    if bytes.is_empty() {
+	return;
+    }
+
+    if bios::int10h13h::is_available() {
+	let mut chunk = [0; CHUNK_SIZE];
+	for src in bytes.chunks(CHUNK_SIZE) {
+	    let chunk = &mut chunk[.. src.len()];
+	    chunk.copy_from_slice(src);
+	    if !bios::int10h13h::call(chunk, PAGE_NUMBER, ATTR) {
+		return;
+	    }
+	}
+    } else {
+	for &byte in bytes {
+	    bios::int10h0eh::call(byte, PAGE_NUMBER, COLOR);
 	}
     }
 }
@@ -37,6 +168,211 @@ impl fmt::Write for TextWriter {
 }
 
 
+///
+/// A text writer drawing the embedded 8x16 font on a frame buffer
+/// (cf. `console::fb_console::FbConsole`).
+///
+#[cfg(feature = "video")]
+pub struct FbTextWriter {
+    console: FbConsole,
+}
+
+#[cfg(feature = "video")]
+impl FbTextWriter {
+    /// The default foreground color (Light Gray).
+    pub const DEFAULT_FG: u32 = 0x00c0c0c0;
+
+    /// The default background color (Black).
+    pub const DEFAULT_BG: u32 = 0x00000000;
+
+    /// Creates a writer on the frame buffer, then clears the screen.
+    /// Returns None if the screen is smaller than a character.
+    pub fn new(fb: FrameBuffer) -> Option<Self> {
+	let mut console = FbConsole::new(fb, FbFont::Embedded)?;
+	console.set_colors(Self::DEFAULT_FG, Self::DEFAULT_BG);
+	Some(Self { console })
+    }
+
+    /// Returns the number of columns and rows.
+    pub fn size(&self) -> (usize, usize) {
+	self.console.size()
+    }
+
+    /// Sets the colors of the characters written after.
+    pub fn set_colors(&mut self, fg: u32, bg: u32) {
+	self.console.set_colors(fg, bg);
+    }
+
+    /// Fills the screen with the background color, then moves the
+    /// cursor to the top left.
+    pub fn clear(&mut self) {
+	self.console.clear();
+    }
+
+    /// Writes a CP437 character (CR, LF and BS are interpreted).
+    pub fn write_byte(&mut self, byte: u8) {
+	self.console.write_byte(byte);
+    }
+}
+
+#[cfg(feature = "video")]
+impl fmt::Write for FbTextWriter {
+    fn write_str(&mut self, utf8_str: &str) -> fmt::Result {
+	for ch in utf8_str.chars() {
+	    self.write_byte(cp437::encode_or_control(ch));
+	}
+	Ok(())
+    }
+}
+
+
+/// A text writer writing directly to the VGA text buffer (mode 03h).
+pub struct VgaTextWriter {
+    vga: VgaText,
+}
+
+impl VgaTextWriter {
+    /// Creates a writer starting at the cursor position of BIOS.
+    pub fn new() -> Self {
+	Self { vga: VgaText::new() }
+    }
+
+    ///
+    /// Sets the colors of the characters written after.  `fg` is one of
+    /// the 16 colors (e.g. 0x0F for White), and `bg` is one of the first
+    /// 8 colors (e.g. 0x01 for Blue).
+    ///
+    pub fn set_colors(&mut self, fg: u8, bg: u8) {
+	self.vga.set_attr((bg & 0x07) << 4 | (fg & 0x0f));
+    }
+
+    /// Writes a CP437 character (CR, LF and BS are interpreted).
+    pub fn write_byte(&mut self, byte: u8) {
+	self.vga.write_byte(byte);
+    }
+}
+
+impl Default for VgaTextWriter {
+    fn default() -> Self {
+	Self::new()
+    }
+}
+
+impl fmt::Write for VgaTextWriter {
+    fn write_str(&mut self, utf8_str: &str) -> fmt::Result {
+	for ch in utf8_str.chars() {
+	    self.vga.write_byte(cp437::encode_or_control(ch));
+	}
+	self.vga.sync_cursor();
+	Ok(())
+    }
+}
+
+
+///
+/// A sink of printed text registered by [`add_console`] or
+/// [`set_console`].
+///
+pub trait ConsoleSink: Send {
+    /// Writes the string.
+    fn write_str(&mut self, s: &str);
+
+    /// Returns the most verbose level printed to the sink
+    /// (`Level::Info` by default, i.e. without debug output).
+    fn max_level(&self) -> Level {
+	Level::Info
+    }
+}
+
+impl ConsoleSink for TextWriter {
+    fn write_str(&mut self, s: &str) {
+	self.write_ascii_printables(s);
+    }
+}
+
+#[cfg(feature = "video")]
+impl ConsoleSink for FbTextWriter {
+    fn write_str(&mut self, s: &str) {
+	let _ = fmt::Write::write_str(self, s);
+    }
+}
+
+impl ConsoleSink for VgaTextWriter {
+    fn write_str(&mut self, s: &str) {
+	let _ = fmt::Write::write_str(self, s);
+    }
+}
+
+impl ConsoleSink for SerialPort {
+    fn write_str(&mut self, s: &str) {
+	self.write_bytes(s.as_bytes());
+    }
+}
+
+// The sinks registered at runtime
+static CONSOLES: MuMutex<Vec<Box<dyn ConsoleSink>>> = MuMutex::new(Vec::new());
+
+/// Adds the sink.  Output of `print!` is also written to it.
+pub fn add_console<S>(sink: S)
+where
+    S: ConsoleSink + 'static,
+{
+    CONSOLES.lock().push(Box::new(sink));
+}
+
+///
+/// Makes the sink the only one.  The other sinks registered are
+/// removed, and the sinks of the console configuration (the screen,
+/// serial and debugcon) are disabled.
+///
+pub fn set_console<S>(sink: S)
+where
+    S: ConsoleSink + 'static,
+{
+    {
+	let mut consoles = CONSOLES.lock();
+	consoles.clear();
+	consoles.push(Box::new(sink));
+    }
+    console::set_config(ConsoleConfig {
+	screen: None,
+	serial: None,
+	debugcon: None,
+    });
+}
+
+/// Removes the sinks registered (The console configuration is kept).
+pub fn clear_consoles() {
+    CONSOLES.lock().clear();
+}
+
+//
+// Writes text of the level to the sinks registered (called by
+// `console::print_at`).  If a sink prints (or panics) while it is
+// written, the nested output is not written to the sinks registered.
+//
+pub(crate) fn write_consoles(level: Level, args: fmt::Arguments) {
+    if let Some(mut consoles) = CONSOLES.try_lock() {
+	for sink in consoles.iter_mut() {
+	    if level <= sink.max_level() {
+		let _ = fmt::Write::write_fmt(&mut SinkWriter(&mut **sink),
+					      args);
+	    }
+	}
+    }
+}
+
+// An adapter to write formatted text to a sink.
+struct SinkWriter<'a>(&'a mut dyn ConsoleSink);
+
+impl fmt::Write for SinkWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+	self.0.write_str(s);
+	Ok(())
+    }
+}
+
+
 /// Prints to the console with a newline.
 #[macro_export]
 macro_rules! println {
@@ -56,8 +392,34 @@ macro_rules! print {
     };
 }
 
+/// Prints debug output to the console with a newline.
+#[macro_export]
+macro_rules! debug_println {
+    () => {
+	$crate::debug_print!("\r\n")
+    };
+    ( $($arg:tt)* ) => {
+	$crate::debug_print!("{}\r\n", format_args!( $($arg)* ))
+    };
+}
+
+/// Prints debug output to the console (By default, not to the screen).
+#[macro_export]
+macro_rules! debug_print {
+    ( $($arg:tt)* ) => {
+	$crate::console::print_at($crate::console::Level::Debug,
+				  format_args!( $($arg)* ))
+    };
+}
+
+#[cfg(not(test))]
+pub fn _text_print(args: fmt::Arguments) {
+    // Route the output to the sinks in the console configuration.
+    crate::console::print_at(crate::console::Level::Info, args);
+}
+
+// In host-side unit tests, print to the standard output.
+#[cfg(test)]
 pub fn _text_print(args: fmt::Arguments) {
-    use fmt::Write;
-    let mut text_writer = TextWriter;
-    text_writer.write_fmt(args).unwrap();
+    std::print!("{}", args);
 }
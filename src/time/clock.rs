@@ -0,0 +1,77 @@
+//
+// Clock - Wall-clock time anchored by the RTC and advanced by the TSC.
+//
+// The RTC has only 1-second resolution and is slow to read, so it is
+// read only once.  Then the time elapsed since is measured by the
+// TSC (calibrated by the PIT), which is monotonic.
+//
+
+use core::time::Duration;
+
+use super::{DateTime, Instant, rtc};
+use crate::mu::MuMutex;
+
+
+static CLOCK: MuMutex<Option<Clock>> = MuMutex::new(None);
+
+
+/// A wall clock anchored at boot.
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    boot_unix: u64,	// The RTC time at boot (seconds since 1970)
+    boot: Instant,	// The monotonic time at boot
+}
+
+impl Clock {
+    /// Returns the clock anchored now by the RTC.
+    pub fn new() -> Self {
+	let boot = Instant::now();
+	let boot_unix = rtc::read().to_unix();
+	Self { boot_unix, boot }
+    }
+
+    /// Returns the current date and time (UTC).
+    pub fn now(&self) -> DateTime {
+	DateTime::from_unix(self.boot_unix + self.uptime().as_secs())
+    }
+
+    /// Returns the time elapsed since boot.
+    pub fn uptime(&self) -> Duration {
+	self.boot.elapsed()
+    }
+
+    /// Returns the instant at boot.
+    pub fn boot_instant(&self) -> Instant {
+	self.boot
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+	Self::new()
+    }
+}
+
+
+///
+/// Returns the system clock.  It is anchored at the first call, so
+/// it should be called early at boot (e.g., by `time::init`).
+///
+pub fn clock() -> Clock {
+    *CLOCK.lock().get_or_insert_with(Clock::new)
+}
+
+/// Anchors the system clock (if not yet).
+pub fn init() {
+    clock();
+}
+
+/// Returns the current date and time (UTC) of the system clock.
+pub fn now() -> DateTime {
+    clock().now()
+}
+
+/// Returns the time elapsed since boot.
+pub fn uptime() -> Duration {
+    clock().uptime()
+}
@@ -0,0 +1,74 @@
+//
+// DateTime - A calendar date and time (UTC).
+//
+// Supplementary Resource:
+//	chrono-Compatible Low-Level Date Algorithms (Howard Hinnant)
+//	https://howardhinnant.github.io/date_algorithms.html
+//
+
+use core::fmt;
+
+
+/// A date and time in the proleptic Gregorian calendar (UTC).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,	// 1 - 12
+    pub day: u8,	// 1 - 31
+    pub hour: u8,	// 0 - 23
+    pub minute: u8,	// 0 - 59
+    pub second: u8,	// 0 - 59
+}
+
+impl DateTime {
+    /// Returns the date and time of the seconds since 1970-01-01.
+    pub fn from_unix(secs: u64) -> Self {
+	let days = (secs / 86400) as i64;
+	let rem = secs % 86400;
+
+	// civil_from_days
+	let z = days + 719468;
+	let era = z.div_euclid(146097);
+	let doe = z.rem_euclid(146097);
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+	Self {
+	    year: year as u16,
+	    month: month as u8,
+	    day: day as u8,
+	    hour: (rem / 3600) as u8,
+	    minute: (rem / 60 % 60) as u8,
+	    second: (rem % 60) as u8,
+	}
+    }
+
+    /// Returns the seconds since 1970-01-01 (0 if earlier).
+    pub fn to_unix(&self) -> u64 {
+	// days_from_civil
+	let (month, day) = (self.month as i64, self.day as i64);
+	let year = self.year as i64 - if month <= 2 { 1 } else { 0 };
+	let era = year.div_euclid(400);
+	let yoe = year.rem_euclid(400);
+	let mp = if month > 2 { month - 3 } else { month + 9 };
+	let doy = (153 * mp + 2) / 5 + day - 1;
+	let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+	let days = era * 146097 + doe - 719468;
+
+	let secs = days * 86400 + self.hour as i64 * 3600 +
+	    self.minute as i64 * 60 + self.second as i64;
+	secs.max(0) as u64
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+	       self.year, self.month, self.day,
+	       self.hour, self.minute, self.second)
+    }
+}
@@ -0,0 +1,55 @@
+//
+// Delays - Sleeping, busy waiting and bounded polling.
+//
+// No timer interrupt is available in this environment, so `sleep`
+// halts the processor only if interrupts are enabled (otherwise it
+// polls the TSC), and runs expired software timers meanwhile.
+//
+
+use core::hint::spin_loop;
+use core::time::Duration;
+
+use super::{Instant, timers};
+use crate::x86::idle;
+
+
+///
+/// Sleeps for the duration.  Expired timers of `time::timers` are
+/// processed meanwhile.
+///
+pub fn sleep(duration: Duration) {
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline {
+	timers::poll();
+	idle();
+    }
+}
+
+/// Busy-waits for the microseconds (measured by the TSC).
+pub fn busy_wait_us(us: u64) {
+    let deadline = Instant::now() + Duration::from_micros(us);
+    while Instant::now() < deadline {
+	spin_loop();
+    }
+}
+
+///
+/// Polls `cond` until it returns true or the timeout expires, then
+/// returns whether it returned true.  It replaces unbounded spin
+/// loops waiting for devices.
+///
+pub fn wait_until<F>(timeout: Duration, mut cond: F) -> bool
+where
+    F: FnMut() -> bool
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+	if cond() {
+	    return true;
+	}
+	if Instant::now() >= deadline {
+	    return cond();
+	}
+	spin_loop();
+    }
+}
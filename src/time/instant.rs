@@ -0,0 +1,113 @@
+//
+// Instant - A monotonic point of time measured by the TSC.
+//
+// The TSC is assumed to be invariant (constant rate), which is true
+// on QEMU and recent processors.  Its frequency is given by the
+// hypervisor (e.g. kvmclock on KVM) if any, because calibrating it
+// against the PIT is noisy under heavy host load.
+//
+
+use core::fmt;
+use core::ops::{Add, Sub};
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+use super::pit;
+use crate::x86::{hypervisor, rdtsc};
+
+
+// The time in milliseconds to calibrate the TSC frequency.
+const CALIBRATION_MS: u32 = 10;
+
+// The TSC frequency in Hz (0 if not calibrated yet).
+static TSC_FREQUENCY: AtomicU64 = AtomicU64::new(0);
+
+
+///
+/// Returns the TSC frequency in Hz.  It is obtained from the
+/// hypervisor at the first call, or otherwise calibrated by the PIT
+/// (which takes about 10 milliseconds).
+///
+pub fn tsc_frequency() -> u64 {
+    let freq = TSC_FREQUENCY.load(Ordering::Relaxed);
+    if freq != 0 {
+	return freq;
+    }
+
+    let freq = hypervisor::tsc_frequency().unwrap_or_else(|| {
+	let start = rdtsc();
+	pit::wait_ms(CALIBRATION_MS);
+	let end = rdtsc();
+	(end - start) * 1000 / CALIBRATION_MS as u64
+    }).max(1);
+    TSC_FREQUENCY.store(freq, Ordering::Relaxed);
+    freq
+}
+
+
+/// A monotonic point of time (in TSC ticks).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(u64);
+
+impl Instant {
+    /// Returns the current time.
+    pub fn now() -> Self {
+	tsc_frequency();	// Calibrate before the first measurement.
+	Self(rdtsc())
+    }
+
+    /// Returns the time elapsed since this instant.
+    pub fn elapsed(&self) -> Duration {
+	Self::now().duration_since(*self)
+    }
+
+    /// Returns the time elapsed from `earlier` (0 if it is later).
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+	ticks_to_duration(self.0.saturating_sub(earlier.0))
+    }
+
+    /// Returns the instant after the duration (None on overflow).
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+	self.0.checked_add(duration_to_ticks(duration)?).map(Self)
+    }
+
+    /// Returns the TSC value of this instant.
+    pub fn ticks(&self) -> u64 {
+	self.0
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+	self.checked_add(duration).expect("overflow adding to Instant")
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, earlier: Instant) -> Duration {
+	self.duration_since(earlier)
+    }
+}
+
+impl fmt::Display for Instant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	let since_reset = ticks_to_duration(self.0);
+	write!(f, "{}.{:06}", since_reset.as_secs(),
+	       since_reset.subsec_micros())
+    }
+}
+
+
+fn ticks_to_duration(ticks: u64) -> Duration {
+    let nanos = ticks as u128 * 1_000_000_000 / tsc_frequency() as u128;
+    Duration::from_nanos(nanos as u64)
+}
+
+fn duration_to_ticks(duration: Duration) -> Option<u64> {
+    let ticks = duration.as_nanos() * tsc_frequency() as u128 / 1_000_000_000;
+    u64::try_from(ticks).ok()
+}
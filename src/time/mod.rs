@@ -0,0 +1,54 @@
+/*!
+
+Provides time sources without interrupts.
+
+* `Clock` - the wall-clock time anchored by the RTC at boot and
+  advanced by the TSC.  `time::now()` returns the current date and
+  time, and `time::uptime()` returns the time elapsed since boot.
+
+* `Instant` - a point of time measured by the time stamp counter
+  (TSC), whose frequency is given by the hypervisor (e.g. kvmclock)
+  or calibrated by the PIT at the first use.
+
+* `rtc` - reads the date and time from the CMOS RTC.
+
+* `pit` - waits for a short time using the PIT channel 2 (the PC
+  speaker channel) by polling its output, without IRQ 0.
+
+* `sleep`, `busy_wait_us` and `wait_until` - delays for driver
+  initialization sequences, instead of unbounded spin loops.
+
+* `timers` - one-shot and periodic software timers calling callbacks
+  or waking `async` tasks.
+
+```ignore
+time::init();
+println!("{} (up {:?})", time::now(), time::uptime());
+
+let start = time::Instant::now();
+do_something();
+println!("{:?}", start.elapsed());
+```
+
+# Supplementary Resources
+
+* [Programmable Interval Timer](https://wiki.osdev.org/Programmable_Interval_Timer) (OSDev Wiki)
+* [TSC](https://wiki.osdev.org/TSC) (OSDev Wiki)
+* [CMOS](https://wiki.osdev.org/CMOS) (OSDev Wiki)
+
+ */
+
+
+#[doc(hidden)] pub mod clock;
+#[doc(hidden)] pub mod date_time;
+#[doc(hidden)] pub mod delay;
+#[doc(hidden)] pub mod instant;
+pub mod pit;
+pub mod rtc;
+pub mod timers;
+
+#[doc(inline)] pub use self::clock::{Clock, clock, init, now, uptime};
+#[doc(inline)] pub use self::date_time::DateTime;
+#[doc(inline)] pub use self::delay::{busy_wait_us, sleep, wait_until};
+#[doc(inline)] pub use self::instant::{Instant, tsc_frequency};
+#[doc(inline)] pub use core::time::Duration;
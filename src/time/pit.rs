@@ -0,0 +1,67 @@
+/*!
+
+Waits for a short time using the PIT (8253/8254) channel 2.
+
+Channel 2 is gated by bit 0 of port 0x61, and its output can be
+read from bit 5 of port 0x61.  So a one-shot count (mode 0) can be
+polled without IRQ 0, which is not available in this environment.
+
+ */
+
+use crate::x86::{inb, outb};
+
+
+/// The input clock frequency of the PIT in Hz.
+pub const FREQUENCY: u32 = 1_193_182;
+
+/// The longest time in milliseconds waited by `wait_ms` at once.
+pub const MAX_WAIT_MS: u32 = 0xffff * 1000 / FREQUENCY;
+
+// I/O Ports
+const CHANNEL2_PORT: u16 = 0x42;
+const COMMAND_PORT: u16 = 0x43;
+const CONTROL_PORT: u16 = 0x61;		// NMI Status and Control
+
+// Command: Channel 2, Access lobyte/hibyte, Mode 0, Binary
+const COMMAND_CH2_MODE0: u8 = 0b1011_0000;
+
+// NMI Status and Control Register
+const CONTROL_GATE2: u8 = 1 << 0;	// Timer 2 Gate
+const CONTROL_SPEAKER: u8 = 1 << 1;	// Speaker Data Enable
+const CONTROL_OUT2: u8 = 1 << 5;	// Timer 2 Output
+
+
+///
+/// Waits for the time in milliseconds by polling the PIT channel 2
+/// (up to `MAX_WAIT_MS` at once, longer waits are repeated).
+///
+pub fn wait_ms(ms: u32) {
+    let mut remaining = ms;
+    while remaining > 0 {
+	let n = remaining.min(MAX_WAIT_MS);
+	wait_count((FREQUENCY as u64 * n as u64 / 1000) as u16);
+	remaining -= n;
+    }
+}
+
+///
+/// Waits until the PIT channel 2 counts down from `count` to 0.
+///
+pub fn wait_count(count: u16) {
+    unsafe {
+	// Stop the counter, and keep the speaker silent.
+	let control = inb(CONTROL_PORT) & !(CONTROL_GATE2 | CONTROL_SPEAKER);
+	outb(CONTROL_PORT, control);
+
+	outb(COMMAND_PORT, COMMAND_CH2_MODE0);
+	outb(CHANNEL2_PORT, count as u8);
+	outb(CHANNEL2_PORT, (count >> 8) as u8);
+
+	// Start counting, then wait for the output to go high.
+	outb(CONTROL_PORT, control | CONTROL_GATE2);
+	while (inb(CONTROL_PORT) & CONTROL_OUT2) == 0 {
+	    core::hint::spin_loop();
+	}
+	outb(CONTROL_PORT, control);
+    }
+}
@@ -0,0 +1,91 @@
+/*!
+
+Reads the date and time from the CMOS Real-Time Clock (RTC).
+
+The registers are read repeatedly until two reads agree, so that an
+update in progress does not yield an inconsistent value.  Both BCD
+and binary formats, and both 12-hour and 24-hour modes are handled.
+The RTC is assumed to keep UTC (as QEMU does by default).
+
+ */
+
+use super::DateTime;
+use crate::x86::{inb, outb};
+
+
+// I/O Ports
+const CMOS_ADDRESS: u16 = 0x70;
+const CMOS_DATA: u16 = 0x71;
+
+// Registers
+const REG_SECONDS: u8 = 0x00;
+const REG_MINUTES: u8 = 0x02;
+const REG_HOURS: u8 = 0x04;
+const REG_DAY: u8 = 0x07;
+const REG_MONTH: u8 = 0x08;
+const REG_YEAR: u8 = 0x09;
+const REG_STATUS_A: u8 = 0x0a;
+const REG_STATUS_B: u8 = 0x0b;
+
+// Status Register A
+const STATUS_A_UIP: u8 = 1 << 7;	// Update In Progress
+
+// Status Register B
+const STATUS_B_24HOUR: u8 = 1 << 1;
+const STATUS_B_BINARY: u8 = 1 << 2;
+
+// Bit 7 of the hours is set for PM in the 12-hour mode.
+const HOURS_PM: u8 = 1 << 7;
+
+
+/// Reads the current date and time.
+pub fn read() -> DateTime {
+    let mut last = read_raw();
+    loop {
+	let current = read_raw();
+	if current == last {
+	    break;
+	}
+	last = current;
+    }
+    let [second, minute, hour, day, month, year] = last;
+
+    let status_b = read_register(REG_STATUS_B);
+    let binary = (status_b & STATUS_B_BINARY) != 0;
+    let decode = |value: u8| {
+	if binary { value } else { (value >> 4) * 10 + (value & 0x0f) }
+    };
+
+    let pm = (hour & HOURS_PM) != 0;
+    let mut hour = decode(hour & !HOURS_PM);
+    if (status_b & STATUS_B_24HOUR) == 0 {
+	hour = hour % 12 + if pm { 12 } else { 0 };
+    }
+
+    DateTime {
+	year: 2000 + decode(year) as u16,
+	month: decode(month),
+	day: decode(day),
+	hour,
+	minute: decode(minute),
+	second: decode(second),
+    }
+}
+
+
+// Reads the registers after waiting for an update to finish.
+fn read_raw() -> [u8; 6] {
+    while (read_register(REG_STATUS_A) & STATUS_A_UIP) != 0 {
+	core::hint::spin_loop();
+    }
+    [REG_SECONDS, REG_MINUTES, REG_HOURS, REG_DAY, REG_MONTH, REG_YEAR]
+	.map(read_register)
+}
+
+fn read_register(reg: u8) -> u8 {
+    unsafe {
+	// Bit 7 of the address disables NMI (kept cleared).
+	outb(CMOS_ADDRESS, reg & 0x7f);
+	inb(CMOS_DATA)
+    }
+}
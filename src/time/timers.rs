@@ -0,0 +1,213 @@
+/*!
+
+Provides one-shot and periodic software timers on a timer wheel.
+
+A timer either calls a callback or wakes an `async` task when it
+expires.  Since no timer interrupt is available in this environment,
+timers are driven by calling [`poll`] (the executor of
+[`task`](crate::task) does it while no task is ready).  So callbacks
+are called from the polling context, not from an interrupt handler.
+
+The wheel has 256 slots of 1 millisecond ticks.  A timer is stored
+in the slot of its expiration tick modulo 256, so only one slot is
+examined per tick regardless of the number of timers.
+
+```ignore
+let blink = timers::start_periodic(Duration::from_millis(500), || {
+    toggle_cursor();
+});
+let timeout = timers::start_oneshot(Duration::from_secs(5), || {
+    boot_default();
+});
+...
+timers::cancel(timeout);
+```
+
+ */
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::task::Waker;
+use core::time::Duration;
+
+use super::Instant;
+use crate::mu::MuMutex;
+
+
+/// The resolution of timers.
+pub const TICK: Duration = Duration::from_millis(1);
+
+// The number of slots of the wheel
+const WHEEL_SIZE: usize = 256;
+
+static WHEEL: MuMutex<TimerWheel> = MuMutex::new(TimerWheel::new());
+
+
+/// The identifier of a timer, used to cancel it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimerId(u64);
+
+// What to do when a timer expires
+enum Action {
+    Callback(Box<dyn FnMut() + Send>),
+    Wake(Waker),
+}
+
+struct Timer {
+    id: u64,
+    expires: u64,	// Tick
+    period: u64,	// Ticks (0 if one-shot)
+    action: Action,
+}
+
+struct TimerWheel {
+    slots: [Vec<Timer>; WHEEL_SIZE],
+    epoch: Option<Instant>,	// Tick 0
+    tick: u64,			// The last tick processed
+    next_id: u64,
+    firing: Vec<u64>,		// Periodic timers being processed
+}
+
+impl TimerWheel {
+    const fn new() -> Self {
+	Self {
+	    slots: [const { Vec::new() }; WHEEL_SIZE],
+	    epoch: None,
+	    tick: 0,
+	    next_id: 0,
+	    firing: Vec::new(),
+	}
+    }
+
+    // Returns the current tick.
+    fn now(&mut self) -> u64 {
+	let epoch = *self.epoch.get_or_insert_with(Instant::now);
+	epoch.elapsed().as_millis() as u64
+    }
+
+    fn add(&mut self, delay: Duration, period: Duration, action: Action)
+	   -> TimerId {
+	let id = self.next_id;
+	self.next_id += 1;
+
+	// A timer expires at the next tick at the earliest.
+	let delay = to_ticks(delay).max(1);
+	let expires = self.now().max(self.tick) + delay;
+	self.insert(Timer { id, expires, period: to_ticks(period), action });
+	TimerId(id)
+    }
+
+    fn insert(&mut self, timer: Timer) {
+	self.slots[timer.expires as usize % WHEEL_SIZE].push(timer);
+    }
+
+    fn cancel(&mut self, id: TimerId) -> bool {
+	for slot in &mut self.slots {
+	    if let Some(i) = slot.iter().position(|timer| timer.id == id.0) {
+		slot.swap_remove(i);
+		return true;
+	    }
+	}
+
+	// A periodic timer may be canceled by its own callback.
+	if let Some(i) = self.firing.iter().position(|&f| f == id.0) {
+	    self.firing.swap_remove(i);
+	    return true;
+	}
+	false
+    }
+
+    // Removes the timers expired by the current tick.
+    fn expire(&mut self) -> Vec<Timer> {
+	let now = self.now();
+	let mut expired = Vec::new();
+
+	// Each slot is examined once even if many ticks have passed.
+	let ticks = (now.saturating_sub(self.tick)).min(WHEEL_SIZE as u64);
+	for tick in now + 1 - ticks ..= now {
+	    let slot = &mut self.slots[tick as usize % WHEEL_SIZE];
+	    let mut i = 0;
+	    while i < slot.len() {
+		if slot[i].expires <= now {
+		    expired.push(slot.swap_remove(i));
+		} else {
+		    i += 1;
+		}
+	    }
+	}
+	self.tick = self.tick.max(now);
+	self.firing.extend(expired.iter()
+			   .filter(|timer| timer.period != 0)
+			   .map(|timer| timer.id));
+	expired
+    }
+}
+
+
+///
+/// Starts a timer calling `callback` once after the delay, then
+/// returns its identifier.
+///
+pub fn start_oneshot<F>(delay: Duration, callback: F) -> TimerId
+where
+    F: FnMut() + Send + 'static
+{
+    let action = Action::Callback(Box::new(callback));
+    WHEEL.lock().add(delay, Duration::ZERO, action)
+}
+
+///
+/// Starts a timer calling `callback` every period, then returns its
+/// identifier.  The timer runs until it is canceled.
+///
+pub fn start_periodic<F>(period: Duration, callback: F) -> TimerId
+where
+    F: FnMut() + Send + 'static
+{
+    let period = period.max(TICK);
+    let action = Action::Callback(Box::new(callback));
+    WHEEL.lock().add(period, period, action)
+}
+
+/// Starts a timer waking the task after the delay.
+pub fn wake_after(delay: Duration, waker: Waker) -> TimerId {
+    WHEEL.lock().add(delay, Duration::ZERO, Action::Wake(waker))
+}
+
+/// Cancels the timer, and returns true if it was pending.
+pub fn cancel(id: TimerId) -> bool {
+    WHEEL.lock().cancel(id)
+}
+
+///
+/// Processes the expired timers, then returns the number of them.
+/// Callbacks are called without the lock, so they can start or
+/// cancel timers.
+///
+pub fn poll() -> usize {
+    let expired = WHEEL.lock().expire();
+    let count = expired.len();
+
+    for mut timer in expired {
+	match &mut timer.action {
+	    Action::Callback(callback) => callback(),
+	    Action::Wake(waker) => waker.wake_by_ref(),
+	}
+	if timer.period != 0 {
+	    let mut wheel = WHEEL.lock();
+	    let Some(i) = wheel.firing.iter().position(|&f| f == timer.id)
+	    else {
+		continue;	// Canceled by the callback
+	    };
+	    wheel.firing.swap_remove(i);
+	    timer.expires = (timer.expires + timer.period).max(wheel.tick + 1);
+	    wheel.insert(timer);
+	}
+    }
+    count
+}
+
+
+fn to_ticks(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX / 2)
+}
@@ -0,0 +1,23 @@
+//
+// Bytes - Reads little-endian integers at offsets in byte slices, e.g.,
+// fields of ELF, PE and GPT headers.  Each panics if it is out of range.
+//
+
+/// Returns the little-endian u16 at the offset.
+pub fn le16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+/// Returns the little-endian u32 at the offset.
+pub fn le32(bytes: &[u8], offset: usize) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[offset .. offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+/// Returns the little-endian u64 at the offset.
+pub fn le64(bytes: &[u8], offset: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[offset .. offset + 8]);
+    u64::from_le_bytes(buf)
+}
@@ -0,0 +1,205 @@
+//
+// Checksums - CRC-32, POSIX cksum, the Internet checksum and sums.
+//
+// Supplementary Resources:
+//	https://en.wikipedia.org/wiki/Cyclic_redundancy_check
+//	https://pubs.opengroup.org/onlinepubs/9699919799/utilities/cksum.html
+//	https://www.rfc-editor.org/rfc/rfc1071
+//
+
+
+// CRC-32 (IEEE 802.3, reflected) used by GPT, Ethernet, zlib, etc.
+const CRC32_POLY: u32 = 0xedb8_8320;
+const CRC32_TABLE: [u32; 256] = crc_table(CRC32_POLY, true);
+
+// CRC-32 (not reflected) used by POSIX cksum
+const CKSUM_POLY: u32 = 0x04c1_1db7;
+const CKSUM_TABLE: [u32; 256] = crc_table(CKSUM_POLY, false);
+
+
+/// Computes CRC-32 (IEEE 802.3), e.g., of GPT headers.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc32 = Crc32::new();
+    crc32.update(data);
+    crc32.finish()
+}
+
+/// Computes CRC-32 (IEEE 802.3) incrementally (cf. [`crc32`]).
+#[derive(Clone, Copy, Debug)]
+pub struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    /// Returns a new checksum of empty data.
+    pub const fn new() -> Self {
+	Self { crc: !0 }
+    }
+
+    /// Appends data.
+    pub fn update(&mut self, data: &[u8]) {
+	self.crc = data.iter().fold(self.crc, |crc, &byte| {
+	    CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+	});
+    }
+
+    /// Returns the checksum of the data appended so far.
+    pub fn finish(&self) -> u32 {
+	!self.crc
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+	Self::new()
+    }
+}
+
+
+/// Computes POSIX cksum (CRC-32 with the length appended).
+///
+/// The result is the same as the first field printed by `cksum`.
+pub fn cksum(data: &[u8]) -> u32 {
+    let mut cksum = Cksum::new();
+    cksum.update(data);
+    cksum.finish()
+}
+
+/// Computes POSIX cksum incrementally (cf. [`cksum`]).
+#[derive(Clone, Copy, Debug)]
+pub struct Cksum {
+    crc: u32,
+    len: usize,
+}
+
+impl Cksum {
+    /// Returns a new checksum of empty data.
+    pub const fn new() -> Self {
+	Self { crc: 0, len: 0 }
+    }
+
+    /// Appends data.
+    pub fn update(&mut self, data: &[u8]) {
+	self.crc = data.iter().fold(self.crc, |crc, &byte| {
+	    Self::update_byte(crc, byte)
+	});
+	self.len += data.len();
+    }
+
+    /// Returns the checksum of the data appended so far.
+    pub fn finish(&self) -> u32 {
+	let mut crc = self.crc;
+
+	let mut len = self.len;
+	while len != 0 {
+	    crc = Self::update_byte(crc, len as u8);
+	    len >>= 8;
+	}
+
+	!crc
+    }
+
+    fn update_byte(crc: u32, byte: u8) -> u32 {
+	CKSUM_TABLE[((crc >> 24) ^ byte as u32) as usize] ^ (crc << 8)
+    }
+}
+
+impl Default for Cksum {
+    fn default() -> Self {
+	Self::new()
+    }
+}
+
+
+///
+/// Computes the Internet checksum (RFC 1071) of the data following
+/// the initial sum (e.g., of a pseudo header).
+///
+pub fn internet_checksum(initial: u32, data: &[u8]) -> u16 {
+    let mut sum = initial;
+    for chunk in data.chunks(2) {
+	let word = if chunk.len() == 2 {
+	    u16::from_be_bytes([chunk[0], chunk[1]])
+	} else {
+	    u16::from_be_bytes([chunk[0], 0])
+	};
+	sum += word as u32;
+    }
+
+    while (sum >> 16) != 0 {
+	sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+
+///
+/// Returns the 8-bit sum of bytes.  ACPI and SMBIOS structures are
+/// valid if the sum of their bytes is 0.
+///
+pub fn sum8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0_u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+/// Returns the 32-bit sum of bytes.
+pub fn sum32(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0_u32, |sum, &byte| sum.wrapping_add(byte as u32))
+}
+
+
+// Generates the table of a CRC-32 polynomial.
+const fn crc_table(poly: u32, reflected: bool) -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+	let mut crc = if reflected { i as u32 } else { (i as u32) << 24 };
+	let mut bit = 0;
+	while bit < 8 {
+	    crc = if reflected {
+		if (crc & 1) != 0 { (crc >> 1) ^ poly } else { crc >> 1 }
+	    } else if (crc & 0x8000_0000) != 0 {
+		(crc << 1) ^ poly
+	    } else {
+		crc << 1
+	    };
+	    bit += 1;
+	}
+	table[i] = crc;
+	i += 1;
+    }
+    table
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_values() {
+	// The check values of the catalogue of CRC algorithms
+	assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+	assert_eq!(Cksum::new().finish(), 0xffff_ffff);
+	assert_eq!(cksum(b"123456789"), 930_766_865);
+    }
+
+    #[test]
+    fn incremental() {
+	let mut crc32 = Crc32::new();
+	let mut cksum = Cksum::new();
+	for chunk in b"The quick brown fox".chunks(3) {
+	    crc32.update(chunk);
+	    cksum.update(chunk);
+	}
+	assert_eq!(crc32.finish(), super::crc32(b"The quick brown fox"));
+	assert_eq!(cksum.finish(), super::cksum(b"The quick brown fox"));
+    }
+
+    #[test]
+    fn internet() {
+	// RFC 1071 Section 3 example: 0x0001 + 0xf203 + 0xf4f5 + 0xf6f7
+	let data = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+	assert_eq!(internet_checksum(0, &data), !0xddf2);
+	assert_eq!(sum8(&[0x80, 0x80]), 0);
+    }
+}
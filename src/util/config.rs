@@ -0,0 +1,242 @@
+//
+// Config - A line-oriented `key = value` configuration.
+//
+// The text is parsed lazily without allocation, so it can be used on
+// a file read by a driver, on a ramdisk, or on the command line.
+//
+//	# Comment (also `;` at the beginning of a line)
+//	keymap = de
+//	title = "Boot Menu"
+//	verbose			(a key without a value is a flag)
+//
+// When a key is given more than once, the last one wins, so that
+// later lines (or arguments) override earlier ones.
+//
+
+use core::fmt;
+
+use crate::cmdline;
+
+
+/// Errors in a configuration text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigError<'a> {
+    /// The entry has no key (e.g. `= value`).
+    MissingKey { line: usize },
+    /// The value is not of the expected type.
+    InvalidValue { key: &'a str, value: &'a str },
+}
+
+impl fmt::Display for ConfigError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::MissingKey { line } =>
+		write!(f, "Config: No key at entry {}", line),
+	    Self::InvalidValue { key, value } =>
+		write!(f, "Config: Invalid value {:?} of {}", value, key),
+	}
+    }
+}
+
+
+///
+/// A type of configuration values.
+///
+/// Integers are decimal, or hexadecimal with the prefix `0x`.
+/// Booleans are `true`/`false`, `yes`/`no`, `on`/`off` or `1`/`0`
+/// (a flag without a value is true).
+///
+pub trait ConfigValue<'a>: Sized {
+    /// Parses the value (None if invalid).
+    fn parse_value(value: &'a str) -> Option<Self>;
+}
+
+impl<'a> ConfigValue<'a> for &'a str {
+    fn parse_value(value: &'a str) -> Option<Self> {
+	Some(value)
+    }
+}
+
+impl ConfigValue<'_> for bool {
+    fn parse_value(value: &str) -> Option<Self> {
+	match value {
+	    "" | "true" | "yes" | "on" | "1" => Some(true),
+	    "false" | "no" | "off" | "0" => Some(false),
+	    _ => None,
+	}
+    }
+}
+
+macro_rules! impl_config_value_int {
+    ($($t:ty),*) => {
+	$(
+	    impl ConfigValue<'_> for $t {
+		fn parse_value(value: &str) -> Option<Self> {
+		    match value.strip_prefix("0x") {
+			Some(hex) => <$t>::from_str_radix(hex, 16).ok(),
+			None => value.parse().ok(),
+		    }
+		}
+	    }
+	)*
+    };
+}
+
+impl_config_value_int!(u8, u16, u32, u64, usize, i32, i64);
+
+
+/// A configuration text.
+#[derive(Clone, Copy, Debug)]
+pub struct Config<'a> {
+    text: &'a str,
+    by_whitespace: bool,	// Entries are separated by whitespace.
+}
+
+impl<'a> Config<'a> {
+    /// Creates a configuration of `key = value` lines.
+    pub const fn new(text: &'a str) -> Self {
+	Self { text, by_whitespace: false }
+    }
+
+    ///
+    /// Creates a configuration of whitespace-separated `key=value`
+    /// arguments (e.g., the command line).
+    ///
+    pub const fn from_args(text: &'a str) -> Self {
+	Self { text, by_whitespace: true }
+    }
+
+    /// Returns an iterator over the entries `(key, value)` in order.
+    pub fn entries(&self)
+		   -> impl Iterator<Item = Result<(&'a str, &'a str),
+						  ConfigError<'a>>> + 'a {
+	let by_whitespace = self.by_whitespace;
+	self.text
+	    .split(move | ch: char | match by_whitespace {
+		true => ch.is_ascii_whitespace(),
+		false => ch == '\n',
+	    })
+	    .enumerate()
+	    .filter_map(| (index, entry) | parse_entry(index + 1, entry))
+    }
+
+    /// Returns the first error in the text (if any).
+    pub fn validate(&self) -> Result<(), ConfigError<'a>> {
+	self.entries().try_for_each(| entry | entry.map(|_| ()))
+    }
+
+    /// Returns the value of the key (the last one if given repeatedly).
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+	self.entries()
+	    .flatten()
+	    .filter(| &(k, _) | k == key)
+	    .last()
+	    .map(| (_, value) | value)
+    }
+
+    /// Returns true if the key is given (with any value).
+    pub fn contains(&self, key: &str) -> bool {
+	self.get(key).is_some()
+    }
+
+    ///
+    /// Returns the value of the key parsed as `T` (None if the key is
+    /// not given).
+    ///
+    pub fn value<T>(&self, key: &'a str) -> Result<Option<T>, ConfigError<'a>>
+    where
+	T: ConfigValue<'a>,
+    {
+	match self.get(key) {
+	    Some(value) => T::parse_value(value)
+		.map(Some)
+		.ok_or(ConfigError::InvalidValue { key, value }),
+	    None => Ok(None),
+	}
+    }
+
+    ///
+    /// Returns the value of the key parsed as `T`, or `default` if the
+    /// key is not given or its value is invalid.
+    ///
+    pub fn value_or<T>(&self, key: &'a str, default: T) -> T
+    where
+	T: ConfigValue<'a>,
+    {
+	self.value(key).ok().flatten().unwrap_or(default)
+    }
+}
+
+impl Config<'static> {
+    /// Returns the configuration given by the command line.
+    pub fn cmdline() -> Self {
+	Self::from_args(cmdline::get())
+    }
+}
+
+// Parses an entry (None if it is blank or a comment).
+fn parse_entry(line: usize, entry: &str)
+	       -> Option<Result<(&str, &str), ConfigError<'_>>> {
+    let entry = entry.trim();
+    if entry.is_empty() || entry.starts_with(['#', ';']) {
+	return None;
+    }
+
+    let (key, value) = entry.split_once('=').unwrap_or((entry, ""));
+    let key = key.trim_end();
+    if key.is_empty() {
+	return Some(Err(ConfigError::MissingKey { line }));
+    }
+
+    let value = value.trim_start();
+    let value = value.strip_prefix('"')
+	.and_then(| v | v.strip_suffix('"'))
+	.unwrap_or(value);
+
+    Some(Ok((key, value)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+# Boot menu
+title = \"Boot Menu\"
+timeout = 5
+\r
+; Console
+verbose
+serial = off
+timeout = 0x10
+";
+
+    #[test]
+    fn lookup_lines() {
+	let config = Config::new(TEXT);
+	assert_eq!(config.validate(), Ok(()));
+	assert_eq!(config.get("title"), Some("Boot Menu"));
+	assert_eq!(config.value::<u32>("timeout"), Ok(Some(16)));
+	assert!(config.value_or("verbose", false));
+	assert!(!config.value_or("serial", true));
+	assert_eq!(config.value_or("missing", 3_u8), 3);
+	assert_eq!(config.value::<u8>("title"),
+		   Err(ConfigError::InvalidValue {
+		       key: "title",
+		       value: "Boot Menu",
+		   }));
+    }
+
+    #[test]
+    fn lookup_args() {
+	let config = Config::from_args("seed=42  keymap=de usb");
+	assert_eq!(config.value_or("seed", 1_u64), 42);
+	assert_eq!(config.get("keymap"), Some("de"));
+	assert!(config.contains("usb"));
+	assert!(!config.contains("bench"));
+
+	let config = Config::from_args("a=1 =2");
+	assert_eq!(config.validate(), Err(ConfigError::MissingKey { line: 2 }));
+    }
+}
@@ -0,0 +1,38 @@
+/*!
+
+Provides small utilities usable without allocation.
+
+* `bytes` - readers of little-endian integers at offsets in byte
+  slices (e.g., fields of ELF, PE and GPT headers).
+
+* `checksum` - CRC-32 (table-based), POSIX cksum, the Internet
+  checksum and additive sums, shared by the image verification, ACPI,
+  SMBIOS, the network stack and disk verification.
+
+* `Config` - a line-oriented `key = value` configuration with typed
+  lookups and defaults.  It parses a file (e.g., read from a disk or
+  a ramdisk) or the command line (`Config::cmdline()`) in place.
+
+* `XorShift64` - a deterministic pseudo-random number generator for
+  randomized tests.  The same seed reproduces the same sequence, so
+  a failure can be reproduced by printing the seed and passing it
+  again (e.g., by the command line argument `seed=<number>`).
+
+# Supplementary Resource
+
+* [Xorshift RNGs](https://www.jstatsoft.org/article/view/v008i14)
+  by George Marsaglia
+
+ */
+
+
+pub mod bytes;
+pub mod checksum;
+#[doc(hidden)] pub mod config;
+#[doc(hidden)] pub mod xorshift;
+
+#[doc(inline)] pub use self::checksum::{
+    Cksum, Crc32, cksum, crc32, internet_checksum, sum8, sum32,
+};
+#[doc(inline)] pub use self::config::{Config, ConfigError, ConfigValue};
+#[doc(inline)] pub use self::xorshift::XorShift64;
@@ -0,0 +1,65 @@
+//
+// XorShift64 - A Deterministic Pseudo-Random Number Generator
+//
+
+
+///
+/// A seedable xorshift64 pseudo-random number generator.
+///
+/// It needs no allocation and no hardware support, and is NOT
+/// cryptographically secure.  It is intended for randomized tests.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct XorShift64 {
+    seed: u64,
+    state: u64,
+}
+
+impl XorShift64 {
+    // A seed used instead of zero (the state must not be zero).
+    const NONZERO_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+    /// Creates a generator from `seed`.
+    pub const fn new(seed: u64) -> Self {
+	let state = if seed == 0 { Self::NONZERO_SEED } else { seed };
+	Self { seed, state }
+    }
+
+    /// Returns the seed passed to `new`.
+    pub const fn seed(&self) -> u64 {
+	self.seed
+    }
+
+    /// Returns the next random number.
+    pub fn next_u64(&mut self) -> u64 {
+	let mut x = self.state;
+	x ^= x << 13;
+	x ^= x >> 7;
+	x ^= x << 17;
+	self.state = x;
+	x
+    }
+
+    /// Returns a random number in `0 .. n` (`n` must not be zero).
+    pub fn below(&mut self, n: usize) -> usize {
+	(self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns a random number in `min ..= max`.
+    pub fn range(&mut self, min: usize, max: usize) -> usize {
+	min + self.below(max - min + 1)
+    }
+
+    /// Returns true with the probability `1 / n`.
+    pub fn one_in(&mut self, n: usize) -> bool {
+	self.below(n) == 0
+    }
+
+    /// Fills `buf` with random bytes.
+    pub fn fill_bytes(&mut self, buf: &mut [u8]) {
+	for chunk in buf.chunks_mut(8) {
+	    let bytes = self.next_u64().to_le_bytes();
+	    chunk.copy_from_slice(&bytes[.. chunk.len()]);
+	}
+    }
+}
@@ -0,0 +1,147 @@
+//
+// CPU Info - Identifies the CPU and its features by CPUID.
+//
+// Supplementary Resource:
+//	https://wiki.osdev.org/CPUID
+//
+
+use core::arch::x86_64::__cpuid;
+use core::fmt;
+
+
+// CPUID registers holding feature flags
+#[derive(Clone, Copy)]
+enum Reg {
+    Leaf1Ecx,			// CPUID 01h ECX
+    Leaf1Edx,			// CPUID 01h EDX
+    Leaf7Ebx,			// CPUID 07h (subleaf 0) EBX
+    Ext1Edx,			// CPUID 8000_0001h EDX
+    Ext7Edx,			// CPUID 8000_0007h EDX
+}
+
+// The features reported (name, register, bit)
+const FEATURES: [(&str, Reg, u32); 14] = [
+    ("sse2", Reg::Leaf1Edx, 26),
+    ("sse3", Reg::Leaf1Ecx, 0),
+    ("ssse3", Reg::Leaf1Ecx, 9),
+    ("sse4.1", Reg::Leaf1Ecx, 19),
+    ("sse4.2", Reg::Leaf1Ecx, 20),
+    ("popcnt", Reg::Leaf1Ecx, 23),
+    ("avx", Reg::Leaf1Ecx, 28),
+    ("avx2", Reg::Leaf7Ebx, 5),
+    ("rdrand", Reg::Leaf1Ecx, 30),
+    ("x2apic", Reg::Leaf1Ecx, 21),
+    ("hypervisor", Reg::Leaf1Ecx, 31),
+    ("1gb-pages", Reg::Ext1Edx, 26),
+    ("rdtscp", Reg::Ext1Edx, 27),
+    ("invariant-tsc", Reg::Ext7Edx, 8),
+];
+
+
+/// The CPU features (a bit per entry of the features reported).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CpuFeatures(u32);
+
+impl CpuFeatures {
+    /// Returns true if the feature (e.g. "avx2") is available.
+    pub fn has(&self, name: &str) -> bool {
+	self.names().any(| n | n == name)
+    }
+
+    /// Returns an iterator over the names of the available features.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+	FEATURES.iter().enumerate()
+	    .filter(| (i, _) | (self.0 & (1 << i)) != 0)
+	    .map(| (_, &(name, _, _)) | name)
+    }
+}
+
+impl fmt::Display for CpuFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	for (i, name) in self.names().enumerate() {
+	    if i > 0 {
+		write!(f, " ")?;
+	    }
+	    write!(f, "{}", name)?;
+	}
+	Ok(())
+    }
+}
+
+
+/// The identification and the features of the CPU.
+#[derive(Clone, Copy, Debug)]
+pub struct CpuInfo {
+    pub vendor: [u8; 12],	// Vendor ID (e.g. "GenuineIntel")
+    pub family: u32,		// Family (with the extended family)
+    pub model: u32,		// Model (with the extended model)
+    pub stepping: u32,
+    pub features: CpuFeatures,
+}
+
+impl CpuInfo {
+    /// Identifies the CPU by CPUID.
+    pub fn detect() -> Self {
+	let leaf0 = __cpuid(0);
+	let mut vendor = [0; 12];
+	vendor[0 .. 4].copy_from_slice(&leaf0.ebx.to_le_bytes());
+	vendor[4 .. 8].copy_from_slice(&leaf0.edx.to_le_bytes());
+	vendor[8 .. 12].copy_from_slice(&leaf0.ecx.to_le_bytes());
+
+	let leaf1 = __cpuid(1);
+	let leaf7_ebx = if leaf0.eax >= 7 { __cpuid(7).ebx } else { 0 };
+	let max_ext = __cpuid(0x8000_0000).eax;
+	let ext_edx = | leaf | {
+	    if max_ext >= leaf { __cpuid(leaf).edx } else { 0 }
+	};
+	let ext1_edx = ext_edx(0x8000_0001);
+	let ext7_edx = ext_edx(0x8000_0007);
+
+	let mut features = 0;
+	for (i, &(_, reg, bit)) in FEATURES.iter().enumerate() {
+	    let value = match reg {
+		Reg::Leaf1Ecx => leaf1.ecx,
+		Reg::Leaf1Edx => leaf1.edx,
+		Reg::Leaf7Ebx => leaf7_ebx,
+		Reg::Ext1Edx => ext1_edx,
+		Reg::Ext7Edx => ext7_edx,
+	    };
+	    if (value & (1 << bit)) != 0 {
+		features |= 1 << i;
+	    }
+	}
+
+	// The extended family and model are added for family 0Fh (and
+	// the extended model also for family 06h).
+	let base_family = (leaf1.eax >> 8) & 0xf;
+	let mut family = base_family;
+	let mut model = (leaf1.eax >> 4) & 0xf;
+	if base_family == 0xf {
+	    family += (leaf1.eax >> 20) & 0xff;
+	}
+	if base_family == 0x6 || base_family == 0xf {
+	    model += ((leaf1.eax >> 16) & 0xf) << 4;
+	}
+
+	Self {
+	    vendor,
+	    family,
+	    model,
+	    stepping: leaf1.eax & 0xf,
+	    features: CpuFeatures(features),
+	}
+    }
+}
+
+impl fmt::Display for CpuInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	for &byte in self.vendor.iter().take_while(| &&b | b != 0) {
+	    match byte {
+		0x20 ..= 0x7e => write!(f, "{}", byte as char)?,
+		_ => write!(f, ".")?,
+	    }
+	}
+	write!(f, " family {:#x} model {:#x} stepping {} ({})",
+	       self.family, self.model, self.stepping, self.features)
+    }
+}
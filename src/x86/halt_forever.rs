@@ -1,4 +1,7 @@
 use core::arch::asm;
+use core::hint::spin_loop;
+
+use super::FLAGS_IF;
 use crate::println;
 
 /// Halt forever.
@@ -10,3 +13,26 @@ pub fn halt_forever() -> ! {
 	}
     }
 }
+
+///
+/// Halts until the next interrupt if interrupts are enabled, or just
+/// pauses (a spin-loop hint) otherwise.  Used by idle loops.
+///
+pub fn idle() {
+    if interrupts_enabled() {
+	unsafe {
+	    asm!("hlt", options(nomem, nostack));
+	}
+    } else {
+	spin_loop();
+    }
+}
+
+/// Returns true if the Interrupt Flag (IF) is set.
+pub fn interrupts_enabled() -> bool {
+    let rflags: u64;
+    unsafe {
+	asm!("pushfq; pop {}", out(reg) rflags, options(nomem));
+    }
+    (rflags as u16 & FLAGS_IF) != 0
+}
@@ -0,0 +1,253 @@
+/*!
+
+Detects the hypervisor, and obtains the TSC frequency from it.
+
+A hypervisor sets bit 31 of ECX of CPUID 01h, and returns its vendor
+signature by CPUID 4000_0000h.  `detect` recognizes KVM, QEMU TCG,
+VMware, Hyper-V and Xen.
+
+Calibrating the TSC against the PIT is noisy in a virtual machine
+under heavy host load, because the virtual CPU may be descheduled
+while the PIT is polled.  [`tsc_frequency`] instead obtains the TSC
+frequency exactly:
+
+* KVM - from the kvmclock (pvclock) structure, which is written by
+  KVM after its physical address is set to `MSR_KVM_SYSTEM_TIME_NEW`.
+
+* VMware (and QEMU with `vmware-cpuid-freq=on`) - from the timing
+  information leaf (CPUID 4000_0010h).
+
+```ignore
+if let Some(hypervisor) = x86::hypervisor::detect() {
+    println!("Hypervisor: {}", hypervisor);
+}
+```
+
+# Supplementary Resources
+
+* [KVM CPUID bits](https://docs.kernel.org/virt/kvm/x86/cpuid.html) (The Linux Kernel documentation)
+* [KVM-specific MSRs](https://docs.kernel.org/virt/kvm/x86/msr.html) (The Linux Kernel documentation)
+
+ */
+
+//
+// Supplementary Resources:
+//	https://docs.kernel.org/virt/kvm/x86/cpuid.html
+//	https://docs.kernel.org/virt/kvm/x86/msr.html
+//
+
+use core::arch::x86_64::__cpuid;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::ptr::{addr_of, read_volatile};
+use core::sync::atomic::{Ordering, fence};
+
+use super::msr::wrmsr;
+
+
+// CPUID 01h: ECX bit 31 = Hypervisor Present
+const CPUID_HYPERVISOR_PRESENT: u32 = 1 << 31;
+
+// CPUID leaves of hypervisors
+const CPUID_SIGNATURE: u32 = 0x4000_0000;
+const CPUID_KVM_FEATURES: u32 = 0x4000_0001;
+const CPUID_TIMING_INFO: u32 = 0x4000_0010;
+
+// CPUID 4000_0001h (KVM): EAX bit 3 = KVM_FEATURE_CLOCKSOURCE2
+const KVM_FEATURE_CLOCKSOURCE2: u32 = 1 << 3;
+
+// MSR_KVM_SYSTEM_TIME_NEW (bit 0 of the value = Enable)
+const MSR_KVM_SYSTEM_TIME_NEW: u32 = 0x4b56_4d01;
+const KVM_SYSTEM_TIME_ENABLE: u64 = 1;
+
+// The number of times to retry reading the kvmclock structure.
+const KVMCLOCK_RETRIES: usize = 1000;
+
+
+/// Hypervisors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hypervisor {
+    /// KVM ("KVMKVMKVM")
+    Kvm,
+    /// QEMU without acceleration ("TCGTCGTCGTCG")
+    Tcg,
+    /// VMware ("VMwareVMware")
+    VMware,
+    /// Microsoft Hyper-V ("Microsoft Hv")
+    HyperV,
+    /// Xen ("XenVMMXenVMM")
+    Xen,
+    /// Others (the vendor signature)
+    Unknown([u8; 12]),
+}
+
+impl Hypervisor {
+    // Converts a vendor signature into a hypervisor.
+    fn from_signature(signature: [u8; 12]) -> Self {
+	match &signature {
+	    b"KVMKVMKVM\0\0\0" => Self::Kvm,
+	    b"TCGTCGTCGTCG" => Self::Tcg,
+	    b"VMwareVMware" => Self::VMware,
+	    b"Microsoft Hv" => Self::HyperV,
+	    b"XenVMMXenVMM" => Self::Xen,
+	    _ => Self::Unknown(signature),
+	}
+    }
+}
+
+impl fmt::Display for Hypervisor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Kvm => write!(f, "KVM"),
+	    Self::Tcg => write!(f, "QEMU TCG"),
+	    Self::VMware => write!(f, "VMware"),
+	    Self::HyperV => write!(f, "Hyper-V"),
+	    Self::Xen => write!(f, "Xen"),
+	    Self::Unknown(signature) => {
+		for &byte in signature.iter().take_while(| &&b | b != 0) {
+		    match byte {
+			0x20 ..= 0x7e => write!(f, "{}", byte as char)?,
+			_ => write!(f, ".")?,
+		    }
+		}
+		Ok(())
+	    },
+	}
+    }
+}
+
+
+/// Returns the hypervisor (None on bare metal).
+pub fn detect() -> Option<Hypervisor> {
+    let (hypervisor, _) = detect_with_max_leaf()?;
+    Some(hypervisor)
+}
+
+// Returns the hypervisor and the maximum hypervisor CPUID leaf.
+fn detect_with_max_leaf() -> Option<(Hypervisor, u32)> {
+    if (__cpuid(1).ecx & CPUID_HYPERVISOR_PRESENT) == 0 {
+	return None;
+    }
+
+    let leaf = __cpuid(CPUID_SIGNATURE);
+    let mut signature = [0; 12];
+    signature[0 .. 4].copy_from_slice(&leaf.ebx.to_le_bytes());
+    signature[4 .. 8].copy_from_slice(&leaf.ecx.to_le_bytes());
+    signature[8 .. 12].copy_from_slice(&leaf.edx.to_le_bytes());
+
+    Some((Hypervisor::from_signature(signature), leaf.eax))
+}
+
+
+///
+/// Returns the TSC frequency in Hz given by the hypervisor.
+///
+/// Returns None on bare metal, or if the hypervisor does not give it
+/// (e.g. QEMU TCG).  Then the TSC needs to be calibrated.
+///
+pub fn tsc_frequency() -> Option<u64> {
+    let (hypervisor, max_leaf) = detect_with_max_leaf()?;
+
+    if hypervisor == Hypervisor::Kvm {
+	if let Some(freq) = kvmclock_tsc_frequency() {
+	    return Some(freq);
+	}
+    }
+
+    // CPUID 4000_0010h: EAX = TSC frequency in kHz
+    if max_leaf >= CPUID_TIMING_INFO {
+	let khz = __cpuid(CPUID_TIMING_INFO).eax;
+	if khz != 0 {
+	    return Some(khz as u64 * 1000);
+	}
+    }
+
+    None
+}
+
+
+// The kvmclock structure (struct pvclock_vcpu_time_info)
+#[repr(C, align(64))]
+struct KvmClock {
+    version: u32,
+    pad0: u32,
+    tsc_timestamp: u64,
+    system_time: u64,
+    tsc_to_system_mul: u32,
+    tsc_shift: i8,
+    flags: u8,
+    pad: [u8; 2],
+}
+
+// Note: The structure is written by KVM, and read in volatile.
+struct KvmClockArea(UnsafeCell<KvmClock>);
+
+unsafe impl Sync for KvmClockArea {}
+
+// Note: Its physical address is the same as its linear address
+//       (identity mapping), and it does not cross a page boundary
+//       because it is 64-byte aligned.
+static KVMCLOCK: KvmClockArea = KvmClockArea(UnsafeCell::new(KvmClock {
+    version: 0,
+    pad0: 0,
+    tsc_timestamp: 0,
+    system_time: 0,
+    tsc_to_system_mul: 0,
+    tsc_shift: 0,
+    flags: 0,
+    pad: [0; 2],
+}));
+
+//
+// Enables kvmclock, reads the scale from TSC ticks to nanoseconds,
+// then disables it again (so that KVM no longer writes to it).
+//
+// The TSC frequency is 10^9 * 2^32 / tsc_to_system_mul shifted right
+// by tsc_shift (left if negative).
+//
+fn kvmclock_tsc_frequency() -> Option<u64> {
+    if (__cpuid(CPUID_KVM_FEATURES).eax & KVM_FEATURE_CLOCKSOURCE2) == 0 {
+	return None;
+    }
+
+    let clock = KVMCLOCK.0.get();
+    unsafe {
+	wrmsr(MSR_KVM_SYSTEM_TIME_NEW,
+	      clock as u64 | KVM_SYSTEM_TIME_ENABLE);
+    }
+
+    // The version is odd while KVM is updating the structure.
+    let mut scale = None;
+    for _ in 0 .. KVMCLOCK_RETRIES {
+	unsafe {
+	    let version = read_volatile(addr_of!((*clock).version));
+	    fence(Ordering::Acquire);
+	    let mul = read_volatile(addr_of!((*clock).tsc_to_system_mul));
+	    let shift = read_volatile(addr_of!((*clock).tsc_shift));
+	    fence(Ordering::Acquire);
+	    if (version & 1) == 0 && version != 0 &&
+		version == read_volatile(addr_of!((*clock).version)) {
+		scale = Some((mul, shift));
+		break;
+	    }
+	}
+	core::hint::spin_loop();
+    }
+
+    unsafe {
+	wrmsr(MSR_KVM_SYSTEM_TIME_NEW, 0);
+    }
+
+    let (mul, shift) = scale?;
+    if mul == 0 {
+	return None;
+    }
+    let freq = (1_000_000_000_u128 << 32) / mul as u128;
+    let freq =
+	if shift >= 0 {
+	    freq >> shift
+	} else {
+	    freq << -shift
+	};
+    u64::try_from(freq).ok()
+}
@@ -0,0 +1,129 @@
+/*!
+
+Memory-Mapped I/O Registers
+
+A block of memory-mapped registers is declared by [`register_block!`]
+as a `#[repr(C)]` structure whose fields are typed registers:
+[`ReadOnly`], [`WriteOnly`] and [`ReadWrite`].  Every access is
+volatile, and the offset of every field (and the size of the block)
+is asserted at compile time.  A block is viewed on a mapped range by
+`drivers::Mmio::block`.
+
+```ignore
+register_block! {
+    /// Example Registers
+    pub struct ExampleRegs(0x10) {
+        0x00 => pub id: ReadOnly<u32>,
+        0x04 => pub control: ReadWrite<u32>,
+        0x08 => pub doorbell: WriteOnly<u64>,
+    }
+}
+
+let regs = mmio.block::<ExampleRegs>();
+regs.control.modify(| value | value | 1);
+```
+
+ */
+
+use core::cell::UnsafeCell;
+use core::ptr::{read_volatile, write_volatile};
+
+
+/// A read-only register.
+#[repr(transparent)]
+pub struct ReadOnly<T>(UnsafeCell<T>);
+
+impl<T> ReadOnly<T>
+where
+    T: Copy,
+{
+    /// Reads the register.
+    pub fn read(&self) -> T {
+	unsafe { read_volatile(self.0.get()) }
+    }
+}
+
+/// A write-only register.
+#[repr(transparent)]
+pub struct WriteOnly<T>(UnsafeCell<T>);
+
+impl<T> WriteOnly<T>
+where
+    T: Copy,
+{
+    /// Writes the register.
+    pub fn write(&self, value: T) {
+	unsafe { write_volatile(self.0.get(), value) }
+    }
+}
+
+/// A read-write register.
+#[repr(transparent)]
+pub struct ReadWrite<T>(UnsafeCell<T>);
+
+impl<T> ReadWrite<T>
+where
+    T: Copy,
+{
+    /// Reads the register.
+    pub fn read(&self) -> T {
+	unsafe { read_volatile(self.0.get()) }
+    }
+
+    /// Writes the register.
+    pub fn write(&self, value: T) {
+	unsafe { write_volatile(self.0.get(), value) }
+    }
+
+    /// Reads the register, then writes the value returned by `f`.
+    pub fn modify<F>(&self, f: F)
+    where
+	F: FnOnce(T) -> T,
+    {
+	self.write(f(self.read()));
+    }
+}
+
+
+///
+/// A block of memory-mapped registers (implemented by
+/// [`register_block!`]).
+///
+/// # Safety
+///
+/// It must be a `#[repr(C)]` structure of the register types only.
+///
+pub unsafe trait RegisterBlock {}
+
+
+///
+/// Declares a block of memory-mapped registers (cf. [`RegisterBlock`]).
+///
+/// The size of the block is given in parentheses, and each register
+/// is given with its offset.  Gaps must be filled by reserved fields.
+///
+#[macro_export]
+macro_rules! register_block {
+    (
+	$(#[$attr:meta])*
+	$vis:vis struct $name:ident ($size:expr) {
+	    $(
+		$(#[$field_attr:meta])*
+		$offset:literal => $field_vis:vis $field:ident : $type:ty
+	    ),* $(,)?
+	}
+    ) => {
+	$(#[$attr])*
+	#[repr(C)]
+	$vis struct $name {
+	    $(
+		$(#[$field_attr])*
+		$field_vis $field: $type,
+	    )*
+	}
+
+	$crate::assert_layout!($name, $size, { $($field: $offset),* });
+
+	unsafe impl $crate::x86::mmio::RegisterBlock for $name {}
+    };
+}
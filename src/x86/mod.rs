@@ -5,13 +5,42 @@ Provides X86-related utilities.
  */
 
 
+#[doc(hidden)] pub mod cpu_info;
 #[doc(hidden)] pub mod halt_forever;
+pub mod hypervisor;
+pub mod mmio;
+#[doc(hidden)] pub mod msr;
+pub mod mtrr;
+#[doc(hidden)] pub mod paging;
+#[doc(hidden)] pub mod phys_mem;
+#[doc(hidden)] pub mod port_io;
+#[doc(hidden)] pub mod real_mode_str;
+#[doc(hidden)] pub mod regs;
+#[doc(hidden)] pub mod serial;
+#[doc(hidden)] pub mod tsc;
 #[doc(hidden)] pub mod x86_far_ptr;
 #[doc(hidden)] pub mod x86_get_addr;
 
-#[doc(inline)] pub use self::halt_forever::halt_forever;
+#[doc(inline)] pub use self::cpu_info::{CpuFeatures, CpuInfo};
+#[doc(inline)] pub use self::halt_forever::{
+    halt_forever, idle, interrupts_enabled,
+};
+#[doc(inline)] pub use self::msr::{rdmsr, wrmsr};
+#[doc(inline)] pub use self::paging::{PagingError, map_uncached};
+#[doc(inline)] pub use self::phys_mem::{PhysMem, PhysMemError};
+#[doc(inline)] pub use self::port_io::{inb, inl, inw, outb, outl, outw};
+#[doc(inline)] pub use self::real_mode_str::RealModeStr;
+#[doc(inline)] pub use self::regs::Registers;
+#[doc(inline)] pub use self::serial::SerialPort;
+#[doc(inline)] pub use self::tsc::{has_rdtscp, rdtsc, tsc_end, tsc_start};
 #[doc(inline)] pub use self::x86_far_ptr::X86FarPtr;
 #[doc(inline)] pub use self::x86_get_addr::X86GetAddr;
 
 /// The Carry Flag (CF) in the FLAGS register.
 pub const FLAGS_CF: u16 = 0x0001;
+
+/// The Zero Flag (ZF) in the FLAGS register.
+pub const FLAGS_ZF: u16 = 0x0040;
+
+/// The Interrupt Enable Flag (IF) in the FLAGS register.
+pub const FLAGS_IF: u16 = 0x0200;
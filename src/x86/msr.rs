@@ -0,0 +1,23 @@
+//
+// Model-Specific Registers (MSRs)
+//
+
+use core::arch::asm;
+
+
+/// Reads the model-specific register.
+#[inline]
+pub unsafe fn rdmsr(msr: u32) -> u64 {
+    let (low, high): (u32, u32);
+    asm!("rdmsr", in("ecx") msr, out("eax") low, out("edx") high,
+	 options(nomem, nostack, preserves_flags));
+    (high as u64) << 32 | low as u64
+}
+
+/// Writes the model-specific register.
+#[inline]
+pub unsafe fn wrmsr(msr: u32, value: u64) {
+    asm!("wrmsr", in("ecx") msr,
+	 in("eax") value as u32, in("edx") (value >> 32) as u32,
+	 options(nostack, preserves_flags));
+}
@@ -0,0 +1,390 @@
+/*!
+
+Memory Type Range Registers (MTRRs)
+
+The MTRRs give the memory types (e.g. write-back or uncacheable) of
+physical address ranges.  The variable-range MTRRs can be programmed,
+e.g., to make the frame buffer write-combining, or MMIO apertures
+uncacheable on CPUs without PAT (or alongside it).
+
+Because programming MTRRs is error-prone, [`snapshot`] saves all
+variable-range MTRRs, and [`MtrrSnapshot::restore`] restores them.
+
+```ignore
+let saved = mtrr::snapshot().ok_or(MtrrError::NotSupported)?;
+unsafe {
+    mtrr::set_variable_range(fb_base, fb_size, MemoryType::WriteCombining)?;
+}
+...
+unsafe { saved.restore(); }
+```
+
+Note: Only the bootstrap processor is taken care of.
+
+# Resource
+
+* Intel 64 and IA-32 Architectures Software Developer's Manual
+  Vol.3A: Section 12.11 (Memory Type Range Registers (MTRRs))
+
+ */
+
+//
+// Resource:
+//	Intel 64 and IA-32 Architectures Software Developer's Manual
+//	Vol.3A: Section 12.11 (Memory Type Range Registers (MTRRs))
+//
+
+use core::arch::asm;
+use core::arch::x86_64::__cpuid;
+use core::fmt;
+
+use super::msr::{rdmsr, wrmsr};
+use super::paging::flush_tlb;
+
+
+// MSRs
+const IA32_MTRRCAP: u32 = 0xfe;
+const IA32_MTRR_DEF_TYPE: u32 = 0x2ff;
+const IA32_MTRR_PHYSBASE0: u32 = 0x200;	// PHYSBASEn = 0x200 + 2n
+const IA32_MTRR_PHYSMASK0: u32 = 0x201;	// PHYSMASKn = 0x201 + 2n
+
+// IA32_MTRRCAP
+const CAP_VCNT_MASK: u64 = 0xff;
+const CAP_FIX: u64 = 1 << 8;
+const CAP_WC: u64 = 1 << 10;
+
+// IA32_MTRR_DEF_TYPE
+const DEF_TYPE_MASK: u64 = 0xff;
+const DEF_TYPE_E: u64 = 1 << 11;	// MTRRs Enabled
+
+// IA32_MTRR_PHYSBASEn and IA32_MTRR_PHYSMASKn
+const PHYSBASE_TYPE_MASK: u64 = 0xff;
+const PHYSMASK_VALID: u64 = 1 << 11;
+
+// CR0
+const CR0_NW: u64 = 1 << 29;		// Not Write-through
+const CR0_CD: u64 = 1 << 30;		// Cache Disable
+
+/// The maximum number of variable-range MTRRs kept in a snapshot.
+pub const MAX_VARIABLE_RANGES: usize = 16;
+
+
+/// Memory Types
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MemoryType {
+    Uncacheable = 0,
+    WriteCombining = 1,
+    WriteThrough = 4,
+    WriteProtected = 5,
+    WriteBack = 6,
+}
+
+impl MemoryType {
+    /// Converts the value of a type field into a memory type.
+    pub fn from_u8(value: u8) -> Option<Self> {
+	match value {
+	    0 => Some(Self::Uncacheable),
+	    1 => Some(Self::WriteCombining),
+	    4 => Some(Self::WriteThrough),
+	    5 => Some(Self::WriteProtected),
+	    6 => Some(Self::WriteBack),
+	    _ => None,
+	}
+    }
+}
+
+impl fmt::Display for MemoryType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::Uncacheable => write!(f, "UC"),
+	    Self::WriteCombining => write!(f, "WC"),
+	    Self::WriteThrough => write!(f, "WT"),
+	    Self::WriteProtected => write!(f, "WP"),
+	    Self::WriteBack => write!(f, "WB"),
+	}
+    }
+}
+
+
+/// Errors returned by [`set_variable_range`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MtrrError {
+    /// MTRRs are not supported by the CPU.
+    NotSupported,
+    /// Write-combining is not supported by the CPU.
+    WriteCombiningNotSupported,
+    /// The size is not a power of two (>= 4KiB), or the base is not
+    /// aligned to the size.
+    Misaligned { base: u64, size: u64 },
+    /// All variable-range MTRRs are in use.
+    NoFreeRange,
+}
+
+impl fmt::Display for MtrrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::NotSupported =>
+		write!(f, "MTRR: Not supported"),
+	    Self::WriteCombiningNotSupported =>
+		write!(f, "MTRR: Write-combining is not supported"),
+	    Self::Misaligned { base, size } =>
+		write!(f, "MTRR: Misaligned range {:#x}+{:#x}", base, size),
+	    Self::NoFreeRange =>
+		write!(f, "MTRR: No free variable-range MTRR"),
+	}
+    }
+}
+
+
+/// The capabilities of MTRRs (IA32_MTRRCAP).
+#[derive(Clone, Copy, Debug)]
+pub struct MtrrCap {
+    pub variable_count: usize,	// Number of variable-range MTRRs
+    pub fixed: bool,		// Fixed-range MTRRs are supported.
+    pub write_combining: bool,	// Write-combining is supported.
+}
+
+/// Returns true if MTRRs are supported (CPUID 01h: EDX bit 12).
+pub fn is_supported() -> bool {
+    (__cpuid(1).edx & (1 << 12)) != 0
+}
+
+/// Returns the capabilities of MTRRs (if supported).
+pub fn capabilities() -> Option<MtrrCap> {
+    if !is_supported() {
+	return None;
+    }
+
+    let cap = unsafe { rdmsr(IA32_MTRRCAP) };
+    Some(MtrrCap {
+	variable_count: (cap & CAP_VCNT_MASK) as usize,
+	fixed: (cap & CAP_FIX) != 0,
+	write_combining: (cap & CAP_WC) != 0,
+    })
+}
+
+/// Returns the default memory type, and true if MTRRs are enabled.
+pub fn default_type() -> Option<(Option<MemoryType>, bool)> {
+    if !is_supported() {
+	return None;
+    }
+
+    let def_type = unsafe { rdmsr(IA32_MTRR_DEF_TYPE) };
+    Some((MemoryType::from_u8((def_type & DEF_TYPE_MASK) as u8),
+	  (def_type & DEF_TYPE_E) != 0))
+}
+
+
+/// A variable-range MTRR in use.
+#[derive(Clone, Copy, Debug)]
+pub struct VariableRange {
+    pub index: usize,			// Index of the MTRR
+    pub base: u64,			// Base Address
+    pub size: u64,			// Size in Bytes
+    pub mem_type: Option<MemoryType>,	// None if reserved
+}
+
+impl fmt::Display for VariableRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "MTRR{}: {:#x}+{:#x} ", self.index, self.base, self.size)?;
+	match self.mem_type {
+	    Some(mem_type) => write!(f, "{}", mem_type),
+	    None => write!(f, "(reserved)"),
+	}
+    }
+}
+
+/// Returns the variable-range MTRR of the index (if in use).
+pub fn variable_range(index: usize) -> Option<VariableRange> {
+    let cap = capabilities()?;
+    if index >= cap.variable_count {
+	return None;
+    }
+
+    let (base, mask) = unsafe { read_pair(index) };
+    if (mask & PHYSMASK_VALID) == 0 {
+	return None;
+    }
+
+    // The mask covers the bits from 12 to MAXPHYADDR - 1.
+    let phys_mask = phys_addr_mask();
+    let range_mask = mask & phys_mask;
+    Some(VariableRange {
+	index,
+	base: base & phys_mask,
+	size: (!range_mask & phys_mask) + 0x1000,
+	mem_type: MemoryType::from_u8((base & PHYSBASE_TYPE_MASK) as u8),
+    })
+}
+
+/// Calls a closure for each variable-range MTRR in use.
+pub fn for_each_variable_range<F>(mut f: F)
+where
+    F: FnMut(&VariableRange)
+{
+    let count = capabilities().map_or(0, |cap| cap.variable_count);
+    for index in 0 .. count {
+	if let Some(range) = variable_range(index) {
+	    f(&range);
+	}
+    }
+}
+
+
+///
+/// Programs a free variable-range MTRR with the memory type of the
+/// range, then returns its index.
+///
+/// The size must be a power of two (>= 4KiB), and the base must be
+/// aligned to the size.  E.g., a frame buffer of 1200KiB needs a
+/// range of 2MiB.
+///
+/// # Safety
+///
+/// Making RAM in use uncacheable degrades performance, and making it
+/// write-combining breaks the memory ordering.  Overlapping ranges
+/// must be consistent (cf. the precedences of memory types).
+///
+pub unsafe fn set_variable_range(base: u64, size: u64, mem_type: MemoryType)
+				 -> Result<usize, MtrrError> {
+    let cap = capabilities().ok_or(MtrrError::NotSupported)?;
+    if mem_type == MemoryType::WriteCombining && !cap.write_combining {
+	return Err(MtrrError::WriteCombiningNotSupported);
+    }
+    if size < 0x1000 || !size.is_power_of_two() || (base & (size - 1)) != 0 {
+	return Err(MtrrError::Misaligned { base, size });
+    }
+
+    let index = (0 .. cap.variable_count)
+	.find(|&index| (read_pair(index).1 & PHYSMASK_VALID) == 0)
+	.ok_or(MtrrError::NoFreeRange)?;
+
+    let phys_mask = phys_addr_mask();
+    let new_base = (base & phys_mask) | mem_type as u64;
+    let new_mask = (!(size - 1) & phys_mask) | PHYSMASK_VALID;
+    update(|| write_pair(index, new_base, new_mask));
+
+    Ok(index)
+}
+
+///
+/// Frees the variable-range MTRR of the index.
+///
+/// # Safety
+///
+/// The memory type of the range returns to that of the other
+/// MTRRs or the default type.
+///
+pub unsafe fn clear_variable_range(index: usize) {
+    let count = capabilities().map_or(0, |cap| cap.variable_count);
+    if index < count {
+	update(|| write_pair(index, 0, 0));
+    }
+}
+
+
+/// A snapshot of the default type and the variable-range MTRRs.
+#[derive(Clone, Copy, Debug)]
+pub struct MtrrSnapshot {
+    def_type: u64,
+    count: usize,
+    pairs: [(u64, u64); MAX_VARIABLE_RANGES],	// (PHYSBASEn, PHYSMASKn)
+}
+
+/// Takes a snapshot of the MTRRs (if supported).
+pub fn snapshot() -> Option<MtrrSnapshot> {
+    let cap = capabilities()?;
+    let count = cap.variable_count.min(MAX_VARIABLE_RANGES);
+
+    let mut snapshot = MtrrSnapshot {
+	def_type: unsafe { rdmsr(IA32_MTRR_DEF_TYPE) },
+	count,
+	pairs: [(0, 0); MAX_VARIABLE_RANGES],
+    };
+    for index in 0 .. count {
+	snapshot.pairs[index] = unsafe { read_pair(index) };
+    }
+
+    Some(snapshot)
+}
+
+impl MtrrSnapshot {
+    ///
+    /// Restores the MTRRs taken in the snapshot.
+    ///
+    /// # Safety
+    ///
+    /// The memory types of all ranges return to those at the time of
+    /// the snapshot.
+    ///
+    pub unsafe fn restore(&self) {
+	update(|| {
+	    for (index, &(base, mask)) in self.pairs[.. self.count].iter()
+		.enumerate() {
+		write_pair(index, base, mask);
+	    }
+	    self.def_type
+	});
+    }
+}
+
+
+// Reads PHYSBASEn and PHYSMASKn.
+unsafe fn read_pair(index: usize) -> (u64, u64) {
+    let msr = 2 * index as u32;
+    (rdmsr(IA32_MTRR_PHYSBASE0 + msr), rdmsr(IA32_MTRR_PHYSMASK0 + msr))
+}
+
+// Writes PHYSBASEn and PHYSMASKn, then returns the default type to be
+// written (i.e., unchanged).
+unsafe fn write_pair(index: usize, base: u64, mask: u64) -> u64 {
+    let msr = 2 * index as u32;
+    wrmsr(IA32_MTRR_PHYSBASE0 + msr, base);
+    wrmsr(IA32_MTRR_PHYSMASK0 + msr, mask);
+    rdmsr(IA32_MTRR_DEF_TYPE) | DEF_TYPE_E
+}
+
+// Returns the mask of physical address bits from 12 to MAXPHYADDR - 1.
+fn phys_addr_mask() -> u64 {
+    // CPUID 8000_0008h: EAX bits 7-0 = MAXPHYADDR (36 if unknown)
+    let max_ext = __cpuid(0x8000_0000).eax;
+    let max_phys_addr =
+	if max_ext >= 0x8000_0008 {
+	    __cpuid(0x8000_0008).eax & 0xff
+	} else {
+	    36
+	};
+
+    ((1_u64 << max_phys_addr) - 1) & !0xfff
+}
+
+//
+// Updates MTRRs by the closure, which returns the default type to be
+// written (cf. Section 12.11.7.2 (MemTypeSet Function)).
+//
+// Note: Interrupts are never delivered in Long Mode in this
+//       environment.  Hence, they need not be disabled.
+//
+unsafe fn update<F>(f: F)
+where
+    F: FnOnce() -> u64
+{
+    // Enter the no-fill cache mode, then flush caches and TLBs.
+    let cr0: u64;
+    asm!("mov {}, cr0", out(reg) cr0, options(nomem, nostack));
+    asm!("mov cr0, {}", in(reg) (cr0 | CR0_CD) & !CR0_NW,
+	 options(nostack));
+    asm!("wbinvd", options(nostack));
+    flush_tlb();
+
+    // Disable MTRRs, update them, then enable them again.
+    let def_type = rdmsr(IA32_MTRR_DEF_TYPE);
+    wrmsr(IA32_MTRR_DEF_TYPE, def_type & !DEF_TYPE_E);
+    let new_def_type = f();
+    asm!("wbinvd", options(nostack));
+    flush_tlb();
+    wrmsr(IA32_MTRR_DEF_TYPE, new_def_type);
+
+    // Leave the no-fill cache mode.
+    asm!("mov cr0, {}", in(reg) cr0, options(nostack));
+}
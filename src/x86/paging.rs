@@ -0,0 +1,197 @@
+//
+// Paging - Changes the attributes of identity-mapped pages.
+//
+// lmboot0 identity-maps 0 - 4GiB using 1GiB pages with write-back
+// caching.  MMIO ranges must be mapped uncached instead.  To avoid
+// making RAM in the same 1GiB page uncached, a 1GiB page is split into
+// 2MiB pages as required.  (Page tables made by UEFI firmware, which
+// may use 2MiB and 4KiB pages, are handled in the same way.)
+//
+// Supplementary Resource:
+//	Intel 64 and IA-32 Architectures Software Developer's Manual
+//	Vol.3A: Section 4.5 (4-Level Paging)
+//
+
+use alloc::alloc::{Layout, alloc_zeroed};
+use core::arch::asm;
+use core::fmt;
+use core::slice;
+
+
+// Bits in Page Table Entries
+const PTE_PRESENT: u64 = 1 << 0;
+const PTE_WRITABLE: u64 = 1 << 1;
+const PTE_PWT: u64 = 1 << 3;		// Page-Level Write-Through
+const PTE_PCD: u64 = 1 << 4;		// Page-Level Cache Disable
+const PTE_PS: u64 = 1 << 7;		// Page Size (1GiB or 2MiB Page)
+const PTE_ADDR_MASK: u64 = 0x000f_ffff_ffff_f000;
+const PTE_FLAGS_MASK: u64 = 0x1ff | (1 << 63);
+
+const PTE_UNCACHED: u64 = PTE_PCD | PTE_PWT;
+
+// Page Sizes
+const SIZE_4K: u64 = 1 << 12;
+const SIZE_2M: u64 = 1 << 21;
+const SIZE_1G: u64 = 1 << 30;
+
+
+/// Errors detected while changing page tables.
+#[derive(Debug)]
+pub enum PagingError {
+    /// The address is not covered by the PML4 table.
+    NotMapped { addr: u64 },
+    /// No memory for a new page table.
+    OutOfMemory,
+}
+
+impl fmt::Display for PagingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::NotMapped { addr } =>
+		write!(f, "Address {:#x} is not covered by page tables", addr),
+	    Self::OutOfMemory =>
+		write!(f, "No memory for a new page table"),
+	}
+    }
+}
+
+
+///
+/// Identity-maps the physical address range uncached (PCD=1, PWT=1).
+///
+/// Pages are split into 2MiB pages as required.  Note that a 2MiB
+/// page is not split further, i.e., the whole 2MiB page containing
+/// the range becomes uncached.  New page tables are allocated from
+/// the global allocator.
+///
+/// # Safety
+///
+/// The range must not contain memory used as RAM by the program.
+///
+pub unsafe fn map_uncached(addr: u64, size: u64) -> Result<(), PagingError> {
+    let end = addr.saturating_add(size);
+    let mut cur = addr & !(SIZE_4K - 1);
+    while cur < end {
+	cur = map_uncached_page(cur, end)?;
+    }
+
+    flush_tlb();
+    Ok(())
+}
+
+// Makes the page containing `addr` uncached, then returns the start
+// address of the next page.
+unsafe fn map_uncached_page(addr: u64, end: u64) -> Result<u64, PagingError> {
+    let pml4 = table(read_cr3() & PTE_ADDR_MASK);
+    let pml4e = &mut pml4[index(addr, 39)];
+    if (*pml4e & PTE_PRESENT) == 0 {
+	return Err(PagingError::NotMapped { addr });
+    }
+
+    // Level 3: Page Directory Pointer Table (1GiB pages)
+    let pdpt = table(*pml4e & PTE_ADDR_MASK);
+    let pdpte = &mut pdpt[index(addr, 30)];
+    let base_1g = addr & !(SIZE_1G - 1);
+    if (*pdpte & PTE_PRESENT) == 0 {
+	*pdpte = base_1g | PTE_PRESENT | PTE_WRITABLE | PTE_PS;
+    }
+    if (*pdpte & PTE_PS) != 0 {
+	if addr == base_1g && end >= base_1g + SIZE_1G {
+	    *pdpte |= PTE_UNCACHED;
+	    return Ok(base_1g + SIZE_1G);
+	}
+	*pdpte = split(*pdpte, SIZE_2M)?;
+    }
+
+    // Level 2: Page Directory (2MiB pages)
+    let pd = table(*pdpte & PTE_ADDR_MASK);
+    let pde = &mut pd[index(addr, 21)];
+    let base_2m = addr & !(SIZE_2M - 1);
+    if (*pde & PTE_PRESENT) == 0 {
+	*pde = base_2m | PTE_PRESENT | PTE_WRITABLE | PTE_PS;
+    }
+    if (*pde & PTE_PS) != 0 {
+	*pde |= PTE_UNCACHED;
+	return Ok(base_2m + SIZE_2M);
+    }
+
+    // Level 1: Page Table (4KiB pages)
+    let pt = table(*pde & PTE_ADDR_MASK);
+    let pte = &mut pt[index(addr, 12)];
+    let base_4k = addr & !(SIZE_4K - 1);
+    if (*pte & PTE_PRESENT) == 0 {
+	*pte = base_4k | PTE_PRESENT | PTE_WRITABLE;
+    }
+    *pte |= PTE_UNCACHED;
+    Ok(base_4k + SIZE_4K)
+}
+
+//
+// Returns the end address of the page containing `addr` if it is
+// present in the page tables (None if not mapped).
+//
+pub(super) fn mapped_page_end(addr: u64) -> Option<u64> {
+    // (Shift of the index, Size of a page mapped at the level)
+    const LEVELS: [(u32, u64); 4] = [
+	(39, 0), (30, SIZE_1G), (21, SIZE_2M), (12, SIZE_4K),
+    ];
+
+    let mut entry = read_cr3();
+    for (shift, size) in LEVELS {
+	let table = unsafe { table(entry & PTE_ADDR_MASK) };
+	entry = table[index(addr, shift)];
+	if (entry & PTE_PRESENT) == 0 {
+	    return None;
+	}
+	if size == SIZE_4K || (size != 0 && (entry & PTE_PS) != 0) {
+	    return Some((addr & !(size - 1)) + size);
+	}
+    }
+    None
+}
+
+// Splits a large page into 512 pages of `child_size`, then returns
+// the new entry pointing to the new table.
+unsafe fn split(entry: u64, child_size: u64) -> Result<u64, PagingError> {
+    let layout = Layout::from_size_align(4096, 4096).unwrap();
+    let new_table = alloc_zeroed(layout);
+    if new_table.is_null() {
+	return Err(PagingError::OutOfMemory);
+    }
+
+    let base = entry & PTE_ADDR_MASK;
+    let flags = entry & PTE_FLAGS_MASK;
+    let children = table(new_table as u64);
+    for (i, child) in children.iter_mut().enumerate() {
+	*child = (base + (i as u64) * child_size) | flags;
+    }
+
+    Ok(new_table as u64 | PTE_PRESENT | PTE_WRITABLE)
+}
+
+// Returns the page table at the (identity-mapped) address.
+unsafe fn table<'a>(addr: u64) -> &'a mut [u64] {
+    slice::from_raw_parts_mut(addr as *mut u64, 512)
+}
+
+// Returns the index in the page table at the level (shift).
+fn index(addr: u64, shift: u32) -> usize {
+    ((addr >> shift) & 0x1ff) as usize
+}
+
+fn read_cr3() -> u64 {
+    let cr3: u64;
+    unsafe {
+	asm!("mov {}, cr3", out(reg) cr3,
+	     options(nomem, nostack, preserves_flags));
+    }
+    cr3
+}
+
+// Flushes the TLB by reloading CR3.
+pub(super) fn flush_tlb() {
+    unsafe {
+	asm!("mov {0}, cr3", "mov cr3, {0}", out(reg) _,
+	     options(nostack, preserves_flags));
+    }
+}
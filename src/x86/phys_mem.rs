@@ -0,0 +1,171 @@
+//
+// PhysMem - Reads and writes physical memory with validation.
+//
+// Firmware hands out addresses (e.g. far pointers of VBE mode lists
+// and OEM strings) that may be garbage.  Before dereferencing, the
+// range is validated:
+//	1. It lies in the identity-mapped address space below 4GiB.
+//	2. Its pages are present in the page tables.
+//	3. Above 1MiB, it is covered by the memory map (registered by
+//	   `PhysMem::init`) and contains no bad memory.  Below 1MiB
+//	   (IVT, BDA, EBDA and ROMs), it is always accessible.
+//
+// Until the memory map is registered, only 1 and 2 are checked.
+//
+
+use core::fmt;
+use core::mem::size_of;
+use core::ptr::{read_unaligned, write_unaligned};
+use core::slice;
+
+use super::paging;
+use crate::bios::int15he820h::AddrRange;
+use crate::man_region::{self, Region};
+use crate::mu::MuMutex;
+
+
+// The limit of the identity-mapped address space (4GiB)
+const ADDR_LIMIT: u64 = 1 << 32;
+
+// The limit of 20-bit address space (1MiB)
+const REAL_MODE_LIMIT: u64 = 1 << 20;
+
+// The maximum number of address ranges kept from the memory map.
+const MAX_RANGES: usize = 64;
+
+
+/// Errors returned by [`PhysMem`].
+#[derive(Clone, Copy, Debug)]
+pub enum PhysMemError {
+    /// The range is not below 4GiB (or empty).
+    OutOfRange { addr: u64, len: usize },
+    /// The page at the address is not mapped.
+    NotMapped { addr: u64 },
+    /// The address is not covered by the memory map.
+    NotInMemoryMap { addr: u64 },
+    /// The address is in a range containing bad memory.
+    BadMemory { addr: u64 },
+    /// The range overlaps with a region owned by the runtime.
+    Owned(Region),
+}
+
+impl fmt::Display for PhysMemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Self::OutOfRange { addr, len } =>
+		write!(f, "PhysMem: {:#x} ({:#x} bytes) is out of range",
+		       addr, len),
+	    Self::NotMapped { addr } =>
+		write!(f, "PhysMem: {:#x} is not mapped", addr),
+	    Self::NotInMemoryMap { addr } =>
+		write!(f, "PhysMem: {:#x} is not in the memory map", addr),
+	    Self::BadMemory { addr } =>
+		write!(f, "PhysMem: {:#x} is in bad memory", addr),
+	    Self::Owned(region) =>
+		write!(f, "PhysMem: Owned by the runtime ({})", region),
+	}
+    }
+}
+
+
+// The address ranges of the memory map
+struct MemoryMap {
+    ranges: [Option<AddrRange>; MAX_RANGES],
+}
+
+static MEMORY_MAP: MuMutex<Option<MemoryMap>> = MuMutex::new(None);
+
+
+///
+/// Accessor of physical memory validated against the memory map and
+/// the page tables.
+///
+pub struct PhysMem;
+
+impl PhysMem {
+    ///
+    /// Registers the (sanitized) memory map to validate addresses
+    /// above 1MiB.  At most `MAX_RANGES` ranges are kept.
+    ///
+    pub fn init(memory_map: &[AddrRange]) {
+	let mut ranges = [None; MAX_RANGES];
+	for (slot, entry) in ranges.iter_mut().zip(memory_map) {
+	    *slot = Some(*entry);
+	}
+	*MEMORY_MAP.lock() = Some(MemoryMap { ranges });
+    }
+
+    /// Reads a value at the address (unaligned).
+    pub fn read<T>(addr: usize) -> Result<T, PhysMemError>
+    where
+	T: Copy,
+    {
+	Self::validate(addr as u64, size_of::<T>())?;
+	Ok(unsafe { read_unaligned(addr as *const T) })
+    }
+
+    ///
+    /// Writes a value at the address (unaligned).  The range must not
+    /// overlap with regions owned by the runtime.
+    ///
+    /// # Safety
+    ///
+    /// The range must not be used by anyone else (e.g. BIOS).
+    ///
+    pub unsafe fn write<T>(addr: usize, value: T) -> Result<(), PhysMemError>
+    where
+	T: Copy,
+    {
+	Self::validate(addr as u64, size_of::<T>())?;
+	if let Some(region) = man_region::find_overlap(addr, size_of::<T>()) {
+	    return Err(PhysMemError::Owned(region));
+	}
+	write_unaligned(addr as *mut T, value);
+	Ok(())
+    }
+
+    /// Returns the bytes of the range.
+    pub fn slice(addr: usize, len: usize)
+		 -> Result<&'static [u8], PhysMemError> {
+	Self::validate(addr as u64, len)?;
+	Ok(unsafe { slice::from_raw_parts(addr as *const u8, len) })
+    }
+
+    /// Checks that the range can be accessed.
+    pub fn validate(addr: u64, len: usize) -> Result<(), PhysMemError> {
+	let end = addr.saturating_add(len as u64);
+	if len == 0 || end > ADDR_LIMIT {
+	    return Err(PhysMemError::OutOfRange { addr, len });
+	}
+
+	let mut cur = addr;
+	while cur < end {
+	    cur = paging::mapped_page_end(cur)
+		.ok_or(PhysMemError::NotMapped { addr: cur })?;
+	}
+
+	if end > REAL_MODE_LIMIT {
+	    if let Some(memory_map) = &*MEMORY_MAP.lock() {
+		memory_map.validate(addr.max(REAL_MODE_LIMIT), end)?;
+	    }
+	}
+	Ok(())
+    }
+}
+
+impl MemoryMap {
+    // Checks that the range is covered by the address ranges.
+    fn validate(&self, start: u64, end: u64) -> Result<(), PhysMemError> {
+	let mut cur = start;
+	while cur < end {
+	    let entry = self.ranges.iter().flatten()
+		.find(| e | e.addr <= cur && cur - e.addr < e.length)
+		.ok_or(PhysMemError::NotInMemoryMap { addr: cur })?;
+	    if entry.atype == AddrRange::TYPE_UNUSABLE {
+		return Err(PhysMemError::BadMemory { addr: cur });
+	    }
+	    cur = entry.addr + entry.length;
+	}
+	Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use core::arch::asm;
+
+
+/// Reads a byte from the I/O port.
+#[inline]
+pub unsafe fn inb(port: u16) -> u8 {
+    let value: u8;
+    asm!("in al, dx", out("al") value, in("dx") port,
+	 options(nomem, nostack, preserves_flags));
+    value
+}
+
+/// Writes a byte to the I/O port.
+#[inline]
+pub unsafe fn outb(port: u16, value: u8) {
+    asm!("out dx, al", in("dx") port, in("al") value,
+	 options(nomem, nostack, preserves_flags));
+}
+
+/// Reads a word from the I/O port.
+#[inline]
+pub unsafe fn inw(port: u16) -> u16 {
+    let value: u16;
+    asm!("in ax, dx", out("ax") value, in("dx") port,
+	 options(nomem, nostack, preserves_flags));
+    value
+}
+
+/// Writes a word to the I/O port.
+#[inline]
+pub unsafe fn outw(port: u16, value: u16) {
+    asm!("out dx, ax", in("dx") port, in("ax") value,
+	 options(nomem, nostack, preserves_flags));
+}
+
+/// Reads a double word from the I/O port.
+#[inline]
+pub unsafe fn inl(port: u16) -> u32 {
+    let value: u32;
+    asm!("in eax, dx", out("eax") value, in("dx") port,
+	 options(nomem, nostack, preserves_flags));
+    value
+}
+
+/// Writes a double word to the I/O port.
+#[inline]
+pub unsafe fn outl(port: u16, value: u32) {
+    asm!("out dx, eax", in("dx") port, in("eax") value,
+	 options(nomem, nostack, preserves_flags));
+}
@@ -0,0 +1,95 @@
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::fmt;
+
+use super::{PhysMem, X86FarPtr};
+
+
+///
+/// A NUL-terminated string in 20-bit address space (e.g. an OEM string
+/// returned by BIOS), copied into a buffer.
+///
+/// Because BIOS may return a garbage pointer, reading stops at the
+/// 1 MiB limit, at an inaccessible address (cf. [`PhysMem`]) or after
+/// `max_len` bytes even if no NUL is found.
+///
+pub struct RealModeStr<A>
+where
+    A: Allocator,
+{
+    bytes: Vec<u8, A>,
+    truncated: bool,
+}
+
+impl<A> RealModeStr<A>
+where
+    A: Allocator,
+{
+    /// The limit of 20-bit address space (1 MiB)
+    pub const ADDR_LIMIT: usize = 1 << 20;
+
+    ///
+    /// Reads a NUL-terminated string at the far pointer.
+    ///
+    /// Returns None if the far pointer is null or points at or above
+    /// the 1 MiB limit.  The terminating NUL is not included.
+    ///
+    pub fn read(far_ptr: &X86FarPtr, max_len: usize, alloc: A)
+		-> Option<Self> {
+	let start = far_ptr.to_linear_addr();
+	if (far_ptr.segment == 0 && far_ptr.offset == 0) ||
+	    start >= Self::ADDR_LIMIT {
+	    return None;
+	}
+
+	let limit = max_len.min(Self::ADDR_LIMIT - start);
+	let mut bytes = Vec::new_in(alloc);
+	let mut truncated = true;
+	for addr in start .. start + limit {
+	    let Ok(byte) = PhysMem::read::<u8>(addr) else {
+		break;
+	    };
+	    if byte == 0 {
+		truncated = false;
+		break;
+	    }
+	    bytes.push(byte);
+	}
+
+	Some(Self { bytes, truncated })
+    }
+
+    /// Returns the bytes of the string (without the terminating NUL).
+    pub fn as_bytes(&self) -> &[u8] {
+	&self.bytes
+    }
+
+    /// Returns the bytes of the string (without the terminating NUL).
+    pub fn into_bytes(self) -> Vec<u8, A> {
+	self.bytes
+    }
+
+    /// Returns true if no NUL was found within the limits.
+    pub fn is_truncated(&self) -> bool {
+	self.truncated
+    }
+}
+
+// Prints printable ASCII characters as they are, and others as '.'.
+impl<A> fmt::Display for RealModeStr<A>
+where
+    A: Allocator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	for &byte in self.bytes.iter() {
+	    match byte {
+		0x20 ..= 0x7e | b'\n' | b'\r' => write!(f, "{}", byte as char)?,
+		_ => write!(f, ".")?,
+	    }
+	}
+	if self.truncated {
+	    write!(f, "...")?;
+	}
+	Ok(())
+    }
+}
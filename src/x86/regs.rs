@@ -0,0 +1,49 @@
+use core::arch::asm;
+use core::fmt;
+
+
+/// A snapshot of CPU registers (for debugging).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Registers {
+    pub rsp: u64,
+    pub rbp: u64,
+    pub rflags: u64,
+    pub cr0: u64,
+    pub cr2: u64,
+    pub cr3: u64,
+    pub cr4: u64,
+}
+
+impl Registers {
+    /// Captures the current values of registers.
+    #[inline(always)]
+    pub fn capture() -> Self {
+	let mut regs = Self::default();
+	unsafe {
+	    asm!("mov {}, rsp", out(reg) regs.rsp,
+		 options(nomem, nostack, preserves_flags));
+	    asm!("mov {}, rbp", out(reg) regs.rbp,
+		 options(nomem, nostack, preserves_flags));
+	    asm!("pushfq; pop {}", out(reg) regs.rflags,
+		 options(nomem, preserves_flags));
+	    asm!("mov {}, cr0", out(reg) regs.cr0,
+		 options(nomem, nostack, preserves_flags));
+	    asm!("mov {}, cr2", out(reg) regs.cr2,
+		 options(nomem, nostack, preserves_flags));
+	    asm!("mov {}, cr3", out(reg) regs.cr3,
+		 options(nomem, nostack, preserves_flags));
+	    asm!("mov {}, cr4", out(reg) regs.cr4,
+		 options(nomem, nostack, preserves_flags));
+	}
+	regs
+    }
+}
+
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "RSP={:#x} RBP={:#x} RFLAGS={:#x} \
+		   CR0={:#x} CR2={:#x} CR3={:#x} CR4={:#x}",
+	       self.rsp, self.rbp, self.rflags,
+	       self.cr0, self.cr2, self.cr3, self.cr4)
+    }
+}
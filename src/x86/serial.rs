@@ -0,0 +1,143 @@
+//
+// Serial - A polled driver of 16550 UARTs.
+//
+// The UART is initialized to 115200 bps, 8N1 with FIFOs and without
+// interrupts (there is no IDT), so that reading and writing poll the
+// Line Status Register.
+//
+// Supplementary Resource:
+//	https://wiki.osdev.org/Serial_Ports
+//
+
+use core::fmt;
+use core::hint::spin_loop;
+
+use super::{inb, outb};
+#[cfg(feature = "disk")]
+use crate::fs::{self, IoError};
+
+
+// 16550 UART Registers (offset from the base address)
+const UART_DATA: u16 = 0;	// Data Register (DLAB=0)
+const UART_DLL: u16 = 0;	// Divisor Latch Low Byte (DLAB=1)
+const UART_IER: u16 = 1;	// Interrupt Enable Register (DLAB=0)
+const UART_DLM: u16 = 1;	// Divisor Latch High Byte (DLAB=1)
+const UART_FCR: u16 = 2;	// FIFO Control Register
+const UART_LCR: u16 = 3;	// Line Control Register
+const UART_MCR: u16 = 4;	// Modem Control Register
+const UART_LSR: u16 = 5;	// Line Status Register
+const UART_SCR: u16 = 7;	// Scratch Register
+
+const UART_LSR_DR: u8 = 1 << 0;		// Data Ready
+const UART_LSR_THRE: u8 = 1 << 5;	// Transmitter Holding Register Empty
+
+
+/// A 16550 UART at an I/O port base address.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SerialPort {
+    base: u16,
+}
+
+impl SerialPort {
+    /// COM1 (I/O Port 0x3F8)
+    pub const COM1: Self = Self::new(0x3f8);
+    /// COM2 (I/O Port 0x2F8)
+    pub const COM2: Self = Self::new(0x2f8);
+    /// COM3 (I/O Port 0x3E8)
+    pub const COM3: Self = Self::new(0x3e8);
+    /// COM4 (I/O Port 0x2E8)
+    pub const COM4: Self = Self::new(0x2e8);
+
+    /// Returns the UART at the base address (not initialized yet).
+    pub const fn new(base: u16) -> Self {
+	Self { base }
+    }
+
+    /// Returns the I/O port base address.
+    pub fn base(&self) -> u16 {
+	self.base
+    }
+
+    ///
+    /// Initializes the UART (115200 bps, 8N1, no interrupts).  Returns
+    /// false if the UART is not found (by the scratch register).
+    ///
+    pub fn init(&self) -> bool {
+	let base = self.base;
+	unsafe {
+	    outb(base + UART_SCR, 0x5a);
+	    if inb(base + UART_SCR) != 0x5a {
+		return false;
+	    }
+
+	    outb(base + UART_IER, 0x00);	// Disable interrupts.
+	    outb(base + UART_LCR, 0x80);	// Set DLAB to set the divisor.
+	    outb(base + UART_DLL, 0x01);	// Divisor = 1 (115200 bps)
+	    outb(base + UART_DLM, 0x00);
+	    outb(base + UART_LCR, 0x03);	// 8 bits, no parity, 1 stop bit
+	    outb(base + UART_FCR, 0xc7);	// Enable and clear FIFO
+	    outb(base + UART_MCR, 0x03);	// DTR and RTS
+	}
+	true
+    }
+
+    /// Writes a byte (waits until the transmitter is ready).
+    pub fn write_byte(&self, byte: u8) {
+	unsafe {
+	    while (inb(self.base + UART_LSR) & UART_LSR_THRE) == 0 {
+		spin_loop();
+	    }
+	    outb(self.base + UART_DATA, byte);
+	}
+    }
+
+    /// Reads a received byte (None if no byte is received).
+    pub fn read_byte(&self) -> Option<u8> {
+	unsafe {
+	    if (inb(self.base + UART_LSR) & UART_LSR_DR) != 0 {
+		Some(inb(self.base + UART_DATA))
+	    } else {
+		None
+	    }
+	}
+    }
+
+    /// Writes the bytes.
+    pub fn write_bytes(&self, bytes: &[u8]) {
+	for &byte in bytes {
+	    self.write_byte(byte);
+	}
+    }
+}
+
+impl fmt::Write for SerialPort {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+	self.write_bytes(s.as_bytes());
+	Ok(())
+    }
+}
+
+// Reading does not wait: it returns the bytes received so far (0 if
+// none, which is not the end unlike files).
+#[cfg(feature = "disk")]
+impl fs::Read for SerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+	let mut n = 0;
+	while n < buf.len() {
+	    match self.read_byte() {
+		Some(byte) => buf[n] = byte,
+		None => break,
+	    }
+	    n += 1;
+	}
+	Ok(n)
+    }
+}
+
+#[cfg(feature = "disk")]
+impl fs::Write for SerialPort {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+	self.write_bytes(buf);
+	Ok(buf.len())
+    }
+}
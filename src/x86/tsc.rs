@@ -0,0 +1,75 @@
+//
+// Time Stamp Counter (TSC)
+//
+// Supplementary Resource:
+//	How to Benchmark Code Execution Times on Intel IA-32 and IA-64
+//	Instruction Set Architectures (Intel White Paper, 2010)
+//
+
+use core::arch::x86_64::{__cpuid, __rdtscp, _mm_lfence, _rdtsc};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+
+// 0: unknown, 1: RDTSCP is not available, 2: RDTSCP is available
+static RDTSCP_STATE: AtomicU8 = AtomicU8::new(0);
+
+/// Reads the time stamp counter (not serialized).
+#[inline(always)]
+pub fn rdtsc() -> u64 {
+    unsafe { _rdtsc() }
+}
+
+/// Returns true if the RDTSCP instruction is available.
+pub fn has_rdtscp() -> bool {
+    match RDTSCP_STATE.load(Ordering::Relaxed) {
+	0 => {
+	    // CPUID 8000_0001h: EDX bit 27 = RDTSCP
+	    let max_ext = __cpuid(0x8000_0000).eax;
+	    let available = max_ext >= 0x8000_0001 &&
+		(__cpuid(0x8000_0001).edx & (1 << 27)) != 0;
+	    RDTSCP_STATE.store(if available { 2 } else { 1 },
+			       Ordering::Relaxed);
+	    available
+	},
+	state => state == 2,
+    }
+}
+
+///
+/// Reads the time stamp counter at the beginning of a measurement.
+///
+/// LFENCE before RDTSC waits for the preceding instructions to
+/// complete, and LFENCE after RDTSC keeps the following instructions
+/// from starting before the counter is read.
+///
+#[inline(always)]
+pub fn tsc_start() -> u64 {
+    unsafe {
+	_mm_lfence();
+	let tsc = _rdtsc();
+	_mm_lfence();
+	tsc
+    }
+}
+
+///
+/// Reads the time stamp counter at the end of a measurement.
+///
+/// RDTSCP waits for the preceding instructions to complete (LFENCE
+/// and RDTSC are used instead if RDTSCP is not available), and LFENCE
+/// after it keeps the following instructions from starting early.
+///
+#[inline(always)]
+pub fn tsc_end(rdtscp: bool) -> u64 {
+    unsafe {
+	let tsc = if rdtscp {
+	    let mut aux = 0;
+	    __rdtscp(&mut aux)
+	} else {
+	    _mm_lfence();
+	    _rdtsc()
+	};
+	_mm_lfence();
+	tsc
+    }
+}